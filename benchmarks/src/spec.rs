@@ -272,6 +272,7 @@ impl WorkloadSpec {
                     name: None,
                     inner: nom_sql::CacheInner::Statement(Box::new(stmt)),
                     always: false,
+                    max_staleness: None,
                 };
 
                 let _ = conn.query_drop(create_cache_query.to_string()).await;
@@ -193,6 +193,7 @@ impl ArbitraryQueryParameters {
             name: Some("q".into()),
             inner: nom_sql::CacheInner::Statement(Box::new(stmt)),
             always: false,
+            max_staleness: None,
         };
 
         conn.query_drop(create_cache_query.to_string()).await?;
@@ -49,6 +49,14 @@ pub enum DatabaseURLParseError {
     MySQL(#[from] mysql::UrlError),
 }
 
+/// Error type for the [`FromStr`] implementation for [`ExcludedColumn`]
+#[derive(Debug, Error)]
+#[error("Invalid excluded column `{value}`, expected `[<schema>.]<table>.<column>`")]
+pub struct ExcludedColumnParseError {
+    /// The value that was originally being parsed
+    pub value: String,
+}
+
 /// Error type for the [`FromStr`] implementation for [`DatabaseType`]
 #[derive(Debug, Error)]
 #[error("Invalid upstream type `{value}`, expected one of `mysql` or `postgres`")]
@@ -10,7 +10,7 @@ use std::time::Duration;
 
 use clap::Parser;
 use derive_more::From;
-use error::{ConnectionType, DatabaseTypeParseError};
+use error::{ConnectionType, DatabaseTypeParseError, ExcludedColumnParseError};
 use futures::{StreamExt, TryStreamExt};
 use launchpad::redacted::RedactedString;
 use mysql_async::prelude::Queryable;
@@ -71,11 +71,42 @@ pub struct UpstreamConfig {
     #[serde(default)]
     pub replication_tables: Option<RedactedString>,
 
+    /// Filters on the rows of tables to replicate, as a semicolon-separated list of tables each
+    /// followed by `WHERE` and a predicate, for example `orders WHERE region = 'eu'`. Only rows
+    /// satisfying the predicate of their table are snapshotted and replicated.
+    #[clap(long, env = "REPLICATION_ROW_FILTERS")]
+    #[serde(default)]
+    pub replication_row_filters: Option<RedactedString>,
+
+    /// Columns of tables to exclude from replication, as a comma-separated list of columns each
+    /// of the form `[<schema>.]<table>.<column>`, for example `users.password_hash`. The values of
+    /// excluded columns are never snapshotted or replicated, and queries that reference them are
+    /// proxied to the upstream database. Column names are case-insensitive for MySQL, and
+    /// replication fails if an excluded column doesn't exist in its table. Tables whose excluded
+    /// columns change between restarts are snapshotted again, and the queries cached on them are
+    /// dropped.
+    #[clap(long, env = "REPLICATION_EXCLUDED_COLUMNS", use_value_delimiter = true)]
+    #[serde(default)]
+    pub replication_excluded_columns: Vec<ExcludedColumn>,
+
     /// Sets the time (in seconds) between reports of progress snapshotting the database. A value
     /// of 0 disables reporting.
     #[clap(long, default_value = "30")]
     #[serde(default = "default_snapshot_report_interval_secs")]
     pub snapshot_report_interval_secs: u16,
+
+    /// The maximum number of tables to copy from the upstream database at the same time while
+    /// snapshotting.
+    #[clap(long, env = "SNAPSHOT_PARALLELISM", default_value = "8")]
+    #[serde(default = "default_snapshot_parallelism")]
+    pub snapshot_parallelism: usize,
+
+    /// The number of rows to copy at a time from tables with a primary key while snapshotting.
+    /// Progress is checkpointed after each chunk of rows, so that a snapshot which is interrupted
+    /// can resume from the last completed chunk.
+    #[clap(long, env = "SNAPSHOT_CHUNK_ROWS", default_value = "1000000")]
+    #[serde(default = "default_snapshot_chunk_rows")]
+    pub snapshot_chunk_rows: u64,
 }
 
 impl UpstreamConfig {
@@ -113,6 +144,14 @@ fn default_snapshot_report_interval_secs() -> u16 {
     UpstreamConfig::default().snapshot_report_interval_secs
 }
 
+fn default_snapshot_parallelism() -> usize {
+    UpstreamConfig::default().snapshot_parallelism
+}
+
+fn default_snapshot_chunk_rows() -> u64 {
+    UpstreamConfig::default().snapshot_chunk_rows
+}
+
 fn duration_from_seconds(i: &str) -> Result<Duration, ParseIntError> {
     i.parse::<u64>().map(Duration::from_secs)
 }
@@ -126,12 +165,108 @@ impl Default for UpstreamConfig {
             replication_server_id: Default::default(),
             replicator_restart_timeout: Duration::from_secs(30),
             replication_tables: Default::default(),
+            replication_row_filters: Default::default(),
+            replication_excluded_columns: Default::default(),
             snapshot_report_interval_secs: 30,
+            snapshot_parallelism: 8,
+            snapshot_chunk_rows: 1_000_000,
             ssl_root_cert: None,
         }
     }
 }
 
+/// A column of a table which is excluded from replication, as given to the
+/// `--replication-excluded-columns` option
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct ExcludedColumn {
+    /// The schema of the table, or `None` to exclude the column from tables with the given name in
+    /// any schema
+    pub schema: Option<String>,
+    /// The name of the table
+    pub table: String,
+    /// The name of the column
+    pub column: String,
+}
+
+impl ExcludedColumn {
+    /// Returns true if this exclusion applies to the table with the given schema and name
+    pub fn matches_table(&self, schema: Option<&str>, table: &str) -> bool {
+        self.table == table
+            && match (&self.schema, schema) {
+                (Some(excluded), Some(schema)) => excluded == schema,
+                (Some(_), None) => false,
+                (None, _) => true,
+            }
+    }
+
+    /// Returns true if this exclusion is for the column with the given name. Column names are
+    /// compared case-insensitively if `case_insensitive` is true, as they are in MySQL.
+    pub fn matches_column(&self, column: &str, case_insensitive: bool) -> bool {
+        if case_insensitive {
+            self.column.to_lowercase() == column.to_lowercase()
+        } else {
+            self.column == column
+        }
+    }
+}
+
+/// Parses columns of the form `[<schema>.]<table>.<column>`
+///
+/// # Examples
+///
+/// ```rust
+/// use database_utils::ExcludedColumn;
+///
+/// let col: ExcludedColumn = "users.password_hash".parse().unwrap();
+/// assert_eq!(col.schema, None);
+/// assert_eq!(col.table, "users");
+/// assert_eq!(col.column, "password_hash");
+///
+/// let col: ExcludedColumn = "app.users.ssn".parse().unwrap();
+/// assert_eq!(col.schema.as_deref(), Some("app"));
+/// assert!(col.matches_table(Some("app"), "users"));
+/// assert!(!col.matches_table(Some("other"), "users"));
+/// assert!(col.matches_column("SSN", true));
+/// assert!(!col.matches_column("SSN", false));
+///
+/// assert!("ssn".parse::<ExcludedColumn>().is_err());
+/// assert!("users..ssn".parse::<ExcludedColumn>().is_err());
+/// ```
+impl FromStr for ExcludedColumn {
+    type Err = ExcludedColumnParseError;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let err = || ExcludedColumnParseError {
+            value: s.to_owned(),
+        };
+        let parts = s.trim().split('.').collect::<Vec<_>>();
+        if parts.iter().any(|part| part.is_empty()) {
+            return Err(err());
+        }
+        match parts.as_slice() {
+            [table, column] => Ok(Self {
+                schema: None,
+                table: (*table).to_owned(),
+                column: (*column).to_owned(),
+            }),
+            [schema, table, column] => Ok(Self {
+                schema: Some((*schema).to_owned()),
+                table: (*table).to_owned(),
+                column: (*column).to_owned(),
+            }),
+            _ => Err(err()),
+        }
+    }
+}
+
+impl Display for ExcludedColumn {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(schema) = &self.schema {
+            write!(f, "{}.", schema)?;
+        }
+        write!(f, "{}.{}", self.table, self.column)
+    }
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum DatabaseType {
     MySQL,
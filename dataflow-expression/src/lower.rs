@@ -570,6 +570,9 @@ impl Expr {
             }
             AstExpr::Exists(_) => unsupported!("EXISTS not currently supported"),
             AstExpr::Variable(_) => unsupported!("Variables not currently supported"),
+            AstExpr::WindowFunction { .. } => {
+                unsupported!("Window functions not supported in this position")
+            }
             AstExpr::Between { .. } | AstExpr::NestedSelect(_) | AstExpr::In { .. } => {
                 internal!("Expression should have been desugared earlier: {expr}")
             }
@@ -1,22 +1,88 @@
-//! Implementation of MySQL's [Secure Password Authentication][0] authentication method.
+//! Implementation of MySQL's [Secure Password Authentication][0] (`mysql_native_password`) and
+//! [SHA-2 Pluggable Authentication][1] (`caching_sha2_password`) authentication methods.
 //!
-//! The way the authentication scheme works:
+//! The way the `mysql_native_password` authentication scheme works:
 //!
 //! 1. The server sends 20-bytes of [random data](AuthData) along with the initial handshake packet
 //! 2. The client returns a 20-byte random response based on the algorithm in [`hash_password`]
 //! 3. The server runs the same algorithm, and checks the response against the result
 //!
+//! `caching_sha2_password` starts the same way, using the algorithm in [`scramble_sha256`]
+//! instead. If the server can verify the client's response (the "fast auth" path), it tells the
+//! client so and authentication succeeds. Otherwise, the server asks the client to perform "full
+//! authentication" by sending its password, either in cleartext if the connection uses TLS, or
+//! encrypted with the server's [RSA public key](rsa_public_key_pem) otherwise.
+//!
 //! [0]: https://dev.mysql.com/doc/internals/en/secure-password-authentication.html
+//! [1]: https://dev.mysql.com/doc/refman/8.0/en/caching-sha2-pluggable-authentication.html
 
 use getrandom::getrandom;
+use once_cell::sync::OnceCell;
+use openssl::pkey::Private;
+use openssl::rsa::{Padding, Rsa};
 use sha1::{Digest, Sha1};
+use sha2::Sha256;
 
 use crate::error::MsqlSrvError;
 
 pub type AuthData = [u8; 20];
 
-/// The name of the (currently only) supported auth plugin
-pub const AUTH_PLUGIN_NAME: &str = "mysql_native_password";
+/// The name of the `mysql_native_password` auth plugin
+pub const MYSQL_NATIVE_PASSWORD: &str = "mysql_native_password";
+
+/// The name of the `caching_sha2_password` auth plugin, the default since MySQL 8.0
+pub const CACHING_SHA2_PASSWORD: &str = "caching_sha2_password";
+
+/// Marks a packet sent by the server during authentication as containing extra data for the
+/// current auth plugin
+pub const AUTH_MORE_DATA: u8 = 0x01;
+
+/// Sent by a `caching_sha2_password` client (in place of its password) to request the server's
+/// RSA public key
+pub const REQUEST_PUBLIC_KEY: u8 = 0x02;
+
+/// Sent by the server to tell a `caching_sha2_password` client that its scramble was verified
+pub const FAST_AUTH_SUCCESS: u8 = 0x03;
+
+/// Sent by the server to ask a `caching_sha2_password` client to send its password
+pub const PERFORM_FULL_AUTHENTICATION: u8 = 0x04;
+
+/// The size, in bits, of the RSA key used to encrypt passwords sent during full authentication
+const RSA_KEY_BITS: u32 = 2048;
+
+/// The RSA key pair used to encrypt passwords sent during full authentication, generated the first
+/// time it's needed
+static RSA_KEY: OnceCell<Rsa<Private>> = OnceCell::new();
+
+/// An authentication plugin supported by the server
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AuthPlugin {
+    MysqlNativePassword,
+    CachingSha2Password,
+}
+
+impl AuthPlugin {
+    /// The plugin advertised in the initial handshake, and requested from clients that offer a
+    /// plugin we don't support
+    pub const DEFAULT: AuthPlugin = AuthPlugin::CachingSha2Password;
+
+    /// Returns the name of the plugin, as sent over the wire
+    pub fn name(self) -> &'static str {
+        match self {
+            AuthPlugin::MysqlNativePassword => MYSQL_NATIVE_PASSWORD,
+            AuthPlugin::CachingSha2Password => CACHING_SHA2_PASSWORD,
+        }
+    }
+
+    /// Returns the plugin with the given name, if it's supported
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            MYSQL_NATIVE_PASSWORD => Some(AuthPlugin::MysqlNativePassword),
+            CACHING_SHA2_PASSWORD => Some(AuthPlugin::CachingSha2Password),
+            _ => None,
+        }
+    }
+}
 
 /// Bytewise-XOR b1 with b2 in-place
 fn xor_slice_mut<const N: usize>(b1: &mut [u8; N], b2: &[u8; N]) {
@@ -39,6 +105,12 @@ fn sha1(input: &[u8]) -> [u8; 20] {
     hasher.finalize().into()
 }
 
+fn sha256(input: &[u8]) -> [u8; 32] {
+    let mut hasher = <Sha256 as sha2::Digest>::new();
+    sha2::Digest::update(&mut hasher, input);
+    sha2::Digest::finalize(hasher).into()
+}
+
 /// Hash a password alongside random challenge data per the mysql [secure password authentication
 /// algorithm][0].
 ///
@@ -58,6 +130,53 @@ pub fn hash_password(password: &[u8], auth_data: &AuthData) -> [u8; 20] {
     res
 }
 
+/// Scramble a password alongside random challenge data per the `caching_sha2_password`
+/// algorithm.
+///
+/// The algorithm is:
+///
+/// ```notrust
+/// SHA256(password) XOR SHA256(SHA256(SHA256(password)) <concat> "random data from server")
+/// ```
+pub fn scramble_sha256(password: &[u8], auth_data: &AuthData) -> [u8; 32] {
+    let mut res = sha256(password);
+    let mut salted = [0u8; 52];
+    salted[..32].clone_from_slice(&sha256(&res));
+    salted[32..].clone_from_slice(auth_data);
+    xor_slice_mut(&mut res, &sha256(&salted));
+    res
+}
+
+/// Returns the PEM-encoded public half of the RSA key pair used to encrypt passwords sent during
+/// `caching_sha2_password` full authentication, as sent to clients which request it.
+pub fn rsa_public_key_pem() -> Result<Vec<u8>, MsqlSrvError> {
+    Ok(rsa_key()?.public_key_to_pem()?)
+}
+
+/// Decrypts a password sent by a `caching_sha2_password` client during full authentication over
+/// an insecure connection.
+///
+/// The client encrypts its NUL-terminated password, XORed with the (repeated) random challenge
+/// data, with our [RSA public key](rsa_public_key_pem) using OAEP padding.
+pub fn decrypt_password(encrypted: &[u8], auth_data: &AuthData) -> Result<Vec<u8>, MsqlSrvError> {
+    let key = rsa_key()?;
+    let mut password = vec![0u8; key.size() as usize];
+    let len = key.private_decrypt(encrypted, &mut password, Padding::PKCS1_OAEP)?;
+    password.truncate(len);
+    password
+        .iter_mut()
+        .zip(auth_data.iter().cycle())
+        .for_each(|(x, y)| *x ^= y);
+    if password.last() == Some(&0) {
+        password.pop();
+    }
+    Ok(password)
+}
+
+fn rsa_key() -> Result<&'static Rsa<Private>, MsqlSrvError> {
+    Ok(RSA_KEY.get_or_try_init(|| Rsa::generate(RSA_KEY_BITS))?)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -75,4 +194,43 @@ mod tests {
             ]
         );
     }
+
+    #[test]
+    fn scramble_sha256_works() {
+        let auth_data: AuthData = [1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 9, 8, 7, 6, 5, 4, 3, 2, 1, 0];
+        let password = b"password";
+        let result = scramble_sha256(password, &auth_data);
+
+        // Undo the scramble the way a server with a cached SHA256(SHA256(password)) would
+        let mut salted = [0u8; 52];
+        salted[..32].clone_from_slice(&sha256(&sha256(password)));
+        salted[32..].clone_from_slice(&auth_data);
+        let mut unscrambled = result;
+        xor_slice_mut(&mut unscrambled, &sha256(&salted));
+        assert_eq!(unscrambled, sha256(password));
+
+        assert_ne!(result, scramble_sha256(b"other password", &auth_data));
+    }
+
+    #[test]
+    fn decrypt_password_works() {
+        let auth_data: AuthData = [1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 9, 8, 7, 6, 5, 4, 3, 2, 1, 0];
+        let mut plaintext = b"a password longer than the auth data\0".to_vec();
+        plaintext
+            .iter_mut()
+            .zip(auth_data.iter().cycle())
+            .for_each(|(x, y)| *x ^= y);
+
+        let public_key = Rsa::public_key_from_pem(&rsa_public_key_pem().unwrap()).unwrap();
+        let mut encrypted = vec![0u8; public_key.size() as usize];
+        let len = public_key
+            .public_encrypt(&plaintext, &mut encrypted, Padding::PKCS1_OAEP)
+            .unwrap();
+        encrypted.truncate(len);
+
+        assert_eq!(
+            decrypt_password(&encrypted, &auth_data).unwrap(),
+            b"a password longer than the auth data"
+        );
+    }
 }
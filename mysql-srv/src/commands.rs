@@ -6,6 +6,7 @@ use nom::number::complete::{le_i16, le_i24, le_i64, le_u16, le_u32, le_u8};
 use nom::sequence::preceded;
 use nom::IResult;
 
+use crate::constants::CURSOR_TYPE_READ_ONLY;
 use crate::myc::constants::{CapabilityFlags, Command as CommandByte};
 
 #[derive(Debug)]
@@ -52,6 +53,19 @@ fn null_terminated_string(i: &[u8]) -> IResult<&[u8], &str> {
     Ok((i, res))
 }
 
+/// Returns whether a packet sent by the client in response to the initial handshake is an
+/// [SSLRequest][0] (a truncated handshake response asking to switch to TLS) rather than a full
+/// handshake response.
+///
+/// [0]: https://dev.mysql.com/doc/internals/en/connection-phase-packets.html#packet-Protocol::SSLRequest
+pub fn is_ssl_request(i: &[u8]) -> bool {
+    const SSL_REQUEST_LEN: usize = 4 + 4 + 1 + 23;
+    i.len() == SSL_REQUEST_LEN
+        && map(le_u32, CapabilityFlags::from_bits_truncate)(i).map_or(false, |(_, caps)| {
+            caps.contains(CapabilityFlags::CLIENT_SSL)
+        })
+}
+
 /// <https://dev.mysql.com/doc/internals/en/connection-phase-packets.html#packet-Protocol::HandshakeResponse41>
 pub fn client_handshake(i: &[u8]) -> IResult<&[u8], ClientHandshake<'_>> {
     let (i, capabilities) = map(le_u32, CapabilityFlags::from_bits_truncate)(i)?;
@@ -108,6 +122,12 @@ pub enum Command<'a> {
     Execute {
         stmt: u32,
         params: &'a [u8],
+        /// Whether the client asked for a read-only cursor over the results
+        cursor: bool,
+    },
+    Fetch {
+        stmt: u32,
+        num_rows: u32,
     },
     SendLongData {
         stmt: u32,
@@ -120,9 +140,22 @@ pub enum Command<'a> {
 
 pub fn execute(i: &[u8]) -> IResult<&[u8], Command<'_>> {
     let (i, stmt) = le_u32(i)?;
-    let (i, _flags) = take(1u8)(i)?;
+    let (i, flags) = le_u8(i)?;
     let (i, _iterations) = le_u32(i)?;
-    Ok((&[], Command::Execute { stmt, params: i }))
+    Ok((
+        &[],
+        Command::Execute {
+            stmt,
+            params: i,
+            cursor: flags & CURSOR_TYPE_READ_ONLY != 0,
+        },
+    ))
+}
+
+pub fn fetch(i: &[u8]) -> IResult<&[u8], Command<'_>> {
+    let (i, stmt) = le_u32(i)?;
+    let (i, num_rows) = le_u32(i)?;
+    Ok((i, Command::Fetch { stmt, num_rows }))
 }
 
 pub fn send_long_data(i: &[u8]) -> IResult<&[u8], Command<'_>> {
@@ -165,6 +198,7 @@ pub fn parse(i: &[u8]) -> IResult<&[u8], Command<'_>> {
             Command::ResetStmtData,
         ),
         preceded(tag(&[CommandByte::COM_STMT_EXECUTE as u8]), execute),
+        preceded(tag(&[CommandByte::COM_STMT_FETCH as u8]), fetch),
         preceded(
             tag(&[CommandByte::COM_STMT_SEND_LONG_DATA as u8]),
             send_long_data,
@@ -215,6 +249,33 @@ mod tests {
         assert_eq!(handshake.maxps, 16777216);
     }
 
+    #[tokio::test]
+    async fn it_parses_ssl_request() {
+        let data = &[
+            0x20, 0x00, 0x00, 0x01, 0x85, 0xae, 0x3f, 0x20, 0x00, 0x00, 0x00, 0x01, 0x21, 0x00,
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+        ];
+        let r = Cursor::new(&data[..]);
+        let mut pr = PacketReader::new(r);
+        let (seq, p) = pr.next().await.unwrap().unwrap();
+        assert_eq!(seq, 1);
+        assert!(is_ssl_request(&p));
+    }
+
+    #[tokio::test]
+    async fn full_handshake_is_not_ssl_request() {
+        let data = &[
+            0x25, 0x00, 0x00, 0x01, 0x85, 0xae, 0x3f, 0x20, 0x00, 0x00, 0x00, 0x01, 0x21, 0x00,
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x6a, 0x6f, 0x6e, 0x00, 0x00,
+        ];
+        let r = Cursor::new(&data[..]);
+        let mut pr = PacketReader::new(r);
+        let (_, p) = pr.next().await.unwrap().unwrap();
+        assert!(!is_ssl_request(&p));
+    }
+
     #[tokio::test]
     async fn it_parses_request() {
         let data = &[
@@ -251,4 +312,33 @@ mod tests {
             Command::ListFields(&b"select @@version_comment limit 1"[..])
         );
     }
+
+    #[test]
+    fn it_parses_execute_with_cursor() {
+        let data = &[
+            0x17, 0x01, 0x00, 0x00, 0x00, 0x01, 0x01, 0x00, 0x00, 0x00, 0x2a,
+        ];
+        let (_, cmd) = parse(data).unwrap();
+        assert_eq!(
+            cmd,
+            Command::Execute {
+                stmt: 1,
+                params: &[0x2a],
+                cursor: true,
+            }
+        );
+    }
+
+    #[test]
+    fn it_parses_fetch() {
+        let data = &[0x1c, 0x01, 0x00, 0x00, 0x00, 0x64, 0x00, 0x00, 0x00];
+        let (_, cmd) = parse(data).unwrap();
+        assert_eq!(
+            cmd,
+            Command::Fetch {
+                stmt: 1,
+                num_rows: 100
+            }
+        );
+    }
 }
@@ -58,3 +58,7 @@ pub const CLIENT_PLUGIN_AUTH: u32 = 0x00080000;
 
 pub const SSL_VERIFY_SERVER_CERT: u32 = 0x40000000;
 pub const REMEMBER_OPTIONS: u32 = 0x80000000;
+
+/// Flag of COM_STMT_EXECUTE requesting a read-only cursor over the statement's results, which are
+/// then fetched with COM_STMT_FETCH
+pub const CURSOR_TYPE_READ_ONLY: u8 = 0x01;
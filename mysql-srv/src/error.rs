@@ -49,6 +49,10 @@ pub enum MsqlSrvError {
     /// Error returned when a case is unexpectedly reached.
     #[error("This condition was reached unexpectedly and cannot be handled")]
     UnreachableError,
+    /// Error from OpenSSL while generating the RSA key pair used for authentication, or decrypting
+    /// a password encrypted with it.
+    #[error("RSA error: {0}")]
+    RsaError(#[from] openssl::error::ErrorStack),
     /// Error from mysql_common indicating that the column type is unknown.
     #[error("Unknown column type")]
     UnknownColumnType(#[from] myc::constants::UnknownColumnType),
@@ -164,16 +164,23 @@ use std::io;
 use std::sync::Arc;
 
 use async_trait::async_trait;
-use constants::{CLIENT_PLUGIN_AUTH, PROTOCOL_41, RESERVED, SECURE_CONNECTION};
+use constants::{CLIENT_PLUGIN_AUTH, PROTOCOL_41, RESERVED, SECURE_CONNECTION, SSL};
 use error::{other_error, OtherErrorKind};
 use mysql_common::constants::CapabilityFlags;
 use readyset_data::DfType;
-use tokio::io::{AsyncRead, AsyncWrite};
+use subtle::ConstantTimeEq;
+use tokio::io::{AsyncRead, AsyncWrite, WriteHalf};
 use tokio::net;
+pub use tokio_native_tls::TlsAcceptor;
+use tokio_native_tls::TlsStream;
 use tracing::{debug, trace};
 use writers::write_err;
 
-use crate::authentication::{generate_auth_data, hash_password, AUTH_PLUGIN_NAME};
+use crate::authentication::{
+    decrypt_password, generate_auth_data, hash_password, rsa_public_key_pem, scramble_sha256,
+    AuthData, AuthPlugin, AUTH_MORE_DATA, FAST_AUTH_SUCCESS, PERFORM_FULL_AUTHENTICATION,
+    REQUEST_PUBLIC_KEY,
+};
 pub use crate::myc::constants::{ColumnFlags, ColumnType, StatusFlags};
 pub use crate::writers::prepare_column_definitions;
 
@@ -228,7 +235,9 @@ impl From<&mysql_async::Column> for Column {
 pub use crate::error::MsqlSrvError;
 pub use crate::errorcodes::ErrorKind;
 pub use crate::params::{ParamParser, ParamValue, Params};
-pub use crate::resultset::{InitWriter, QueryResultWriter, RowWriter, StatementMetaWriter};
+pub use crate::resultset::{
+    CursorRows, InitWriter, ListFieldsWriter, QueryResultWriter, RowWriter, StatementMetaWriter,
+};
 pub use crate::value::{ToMySqlValue, Value, ValueInner};
 
 /// Implementors of this trait can be used to drive a MySQL-compatible database backend.
@@ -275,6 +284,19 @@ pub trait MySqlShim<W: AsyncWrite + Unpin + Send> {
     /// Called when client switches database.
     async fn on_init(&mut self, _: &str, _: InitWriter<'_, W>) -> io::Result<()>;
 
+    /// Called when the client asks for the definitions of the columns of `table`, with the
+    /// deprecated `COM_FIELD_LIST` command.
+    ///
+    /// This is still used by some older clients, and by the `mysql` CLI for tab completion. By
+    /// default, the command is rejected as unsupported.
+    async fn on_list_fields(&mut self, _table: &str, w: ListFieldsWriter<'_, W>) -> io::Result<()> {
+        w.error(
+            ErrorKind::ER_UNKNOWN_COM_ERROR,
+            "COM_FIELD_LIST is unsupported".as_bytes(),
+        )
+        .await
+    }
+
     /// Retrieve the password for the user with the given username, if any.
     ///
     /// If the user doesn't exist, return [`None`].
@@ -284,6 +306,14 @@ pub trait MySqlShim<W: AsyncWrite + Unpin + Send> {
     fn require_authentication(&self) -> bool {
         true
     }
+
+    /// Return true if the user with the given username may only connect over TLS.
+    ///
+    /// Only consulted for connections that allow upgrading to TLS (see
+    /// [`MySqlIntermediary::run_on_tcp_with_tls`]).
+    fn require_tls(&self, _username: &str) -> bool {
+        false
+    }
 }
 
 /// Stores a preencoded result schema for a prepared MySQL statement
@@ -321,6 +351,58 @@ impl<B: MySqlShim<net::tcp::OwnedWriteHalf> + Send>
     }
 }
 
+impl<B> MySqlIntermediary<B, net::TcpStream, net::TcpStream>
+where
+    B: MySqlShim<net::tcp::OwnedWriteHalf> + MySqlShim<WriteHalf<TlsStream<net::TcpStream>>> + Send,
+{
+    /// Create a new server over a TCP stream, allowing the client to upgrade the connection to TLS
+    /// using `tls_acceptor`, and process client commands until the client disconnects or an error
+    /// occurs. See also
+    /// [`MySqlIntermediary::run_on_tcp`](struct.MySqlIntermediary.html#method.run_on_tcp).
+    pub async fn run_on_tcp_with_tls(
+        shim: B,
+        stream: net::TcpStream,
+        tls_acceptor: Arc<TlsAcceptor>,
+    ) -> Result<(), io::Error> {
+        stream.set_nodelay(true)?;
+        let (reader, writer) = stream.into_split();
+        let mut mi = MySqlIntermediary::new(shim, reader, writer);
+        let auth_data = mi.write_initial_handshake(CAPABILITIES | SSL).await?;
+        let (seq, handshake) = mi.read_handshake_response().await?;
+        if !commands::is_ssl_request(&handshake) {
+            if mi.authenticate(&auth_data, seq, &handshake, false).await? {
+                mi.run().await?;
+            }
+            return Ok(());
+        }
+
+        debug!("Client requested TLS, upgrading connection");
+        let MySqlIntermediary {
+            shim,
+            reader,
+            writer,
+            ..
+        } = mi;
+        let stream = reader
+            .into_inner()?
+            .reunite(writer.into_inner())
+            .map_err(|e| io::Error::new(io::ErrorKind::Other, e))?;
+        let stream = tls_acceptor
+            .accept(stream)
+            .await
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+        let (reader, writer) = tokio::io::split(stream);
+
+        // The client continues the handshake over TLS, with a full handshake response
+        let mut mi = MySqlIntermediary::new(shim, reader, writer);
+        let (seq, handshake) = mi.read_handshake_response().await?;
+        if mi.authenticate(&auth_data, seq, &handshake, true).await? {
+            mi.run().await?;
+        }
+        Ok(())
+    }
+}
+
 impl<B: MySqlShim<S> + Send, S: AsyncRead + AsyncWrite + Clone + Unpin + Send>
     MySqlIntermediary<B, S, S>
 {
@@ -356,53 +438,95 @@ impl<B: MySqlShim<W> + Send, R: AsyncRead + Unpin, W: AsyncWrite + Unpin + Send>
     /// Create a new server over two one-way channels and process client commands until the client
     /// disconnects or an error occurs.
     pub async fn run_on(shim: B, reader: R, writer: W) -> Result<(), io::Error> {
-        let r = packet::PacketReader::new(reader);
-        let w = packet::PacketWriter::new(writer);
-        let mut mi = MySqlIntermediary {
-            shim,
-            reader: r,
-            writer: w,
-            schema_cache: HashMap::new(),
-        };
+        let mut mi = MySqlIntermediary::new(shim, reader, writer);
         if mi.init().await? {
             mi.run().await?;
         }
         Ok(())
     }
 
+    fn new(shim: B, reader: R, writer: W) -> Self {
+        MySqlIntermediary {
+            shim,
+            reader: packet::PacketReader::new(reader),
+            writer: packet::PacketWriter::new(writer),
+            schema_cache: HashMap::new(),
+        }
+    }
+
     async fn init(&mut self) -> Result<bool, io::Error> {
+        let auth_data = self.write_initial_handshake(CAPABILITIES).await?;
+        let (seq, handshake) = self.read_handshake_response().await?;
+        self.authenticate(&auth_data, seq, &handshake, false).await
+    }
+
+    /// Send the initial handshake packet to the client, advertising the given server
+    /// `capabilities`, and return the auth data sent along with it.
+    async fn write_initial_handshake(&mut self, capabilities: u32) -> Result<AuthData, io::Error> {
         let auth_data =
             generate_auth_data().map_err(|_| other_error(OtherErrorKind::AuthDataErr))?;
 
+        let plugin_name = AuthPlugin::DEFAULT.name();
         let mut init_packet = Vec::with_capacity(
-            1 + 16 + 4 + 8 + 1 + 2 + 1 + 2 + 2 + 1 + 6 + 4 + 12 + 1 + AUTH_PLUGIN_NAME.len() + 1,
+            1 + 16 + 4 + 8 + 1 + 2 + 1 + 2 + 2 + 1 + 6 + 4 + 12 + 1 + plugin_name.len() + 1,
         );
         init_packet.extend_from_slice(&[10]); // protocol 10
         init_packet.extend_from_slice(self.shim.version().as_bytes());
         init_packet.extend_from_slice(&[0x08, 0x00, 0x00, 0x00]); // TODO: connection ID
         init_packet.extend_from_slice(&auth_data[..8]);
         init_packet.push(0);
-        init_packet.extend_from_slice(&CAPABILITIES.to_le_bytes()[..2]);
+        init_packet.extend_from_slice(&capabilities.to_le_bytes()[..2]);
         init_packet.extend_from_slice(&[0x21]); // UTF8_GENERAL_CI
         init_packet.extend_from_slice(&[0x00, 0x00]); // status flags
-        init_packet.extend_from_slice(&CAPABILITIES.to_le_bytes()[2..]);
+        init_packet.extend_from_slice(&capabilities.to_le_bytes()[2..]);
         init_packet.extend_from_slice(&[auth_data.len() as u8]);
         init_packet.extend_from_slice(&[0x00; 10][..]); // filler
         init_packet.extend_from_slice(&auth_data[8..]);
         init_packet.push(0);
-        init_packet.extend_from_slice(AUTH_PLUGIN_NAME.as_bytes());
+        init_packet.extend_from_slice(plugin_name.as_bytes());
         init_packet.push(0);
 
         self.writer.write_packet(&init_packet).await?;
         self.writer.flush().await?;
 
+        Ok(auth_data)
+    }
+
+    /// Read the client's response to the initial handshake, along with its sequence number
+    async fn read_handshake_response(&mut self) -> Result<(u8, Vec<u8>), io::Error> {
         let (seq, handshake_bytes) = self.reader.next().await?.ok_or_else(|| {
             io::Error::new(
                 io::ErrorKind::ConnectionAborted,
                 "peer terminated connection",
             )
         })?;
-        let handshake = commands::client_handshake(&handshake_bytes)
+        Ok((seq, handshake_bytes.to_vec()))
+    }
+
+    /// Read the next packet sent by the client during authentication, and prepare to reply to it
+    async fn read_auth_response(&mut self) -> Result<Vec<u8>, io::Error> {
+        let (seq, response) = self.reader.next().await?.ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::ConnectionAborted,
+                "peer terminated connection",
+            )
+        })?;
+        self.writer.set_seq(seq + 1);
+        Ok(response.to_vec())
+    }
+
+    /// Authenticate the client given its handshake response, and send it either an OK or an
+    /// error packet. `tls` is whether the connection has been upgraded to TLS.
+    ///
+    /// Returns whether authentication succeeded.
+    async fn authenticate(
+        &mut self,
+        auth_data: &AuthData,
+        seq: u8,
+        handshake_bytes: &[u8],
+        tls: bool,
+    ) -> Result<bool, io::Error> {
+        let handshake = commands::client_handshake(handshake_bytes)
             .map_err(|e| match e {
                 nom::Err::Incomplete(_) => io::Error::new(
                     io::ErrorKind::UnexpectedEof,
@@ -428,75 +552,95 @@ impl<B: MySqlShim<W> + Send, R: AsyncRead + Unpin, W: AsyncWrite + Unpin + Send>
         self.writer.set_seq(seq + 1);
 
         let username = handshake.username.to_owned();
-        let password = handshake.password.to_vec();
         let client_auth_plugin = handshake.auth_plugin_name.map(|s| s.to_owned());
 
-        let handshake_password = if client_auth_plugin.iter().all(|apn| apn != AUTH_PLUGIN_NAME)
+        let (auth_plugin, auth_response) = match client_auth_plugin
+            .as_deref()
+            .and_then(AuthPlugin::from_name)
+        {
             // Some clients (at the very least certain versions of PHP's MySQL PDO library) send an
             // empty password response in the initial handshake, even if the auth plugin is set and
             // correct. We want to send a switch-authentication request in that case too
-            || password.is_empty()
-        {
-            // Authentication mismatch - try to switch auth plugins
-
-            if !handshake
-                .capabilities
-                .contains(CapabilityFlags::CLIENT_SECURE_CONNECTION)
-            {
-                debug!("Client does not support SECURE_CONNECTION, returning authentication error");
-                writers::write_err(
-                    ErrorKind::ER_NOT_SUPPORTED_AUTH_MODE,
-                    b"Client does not support authentication protocol requested by server; \
-                      consider upgrading MySQL client",
-                    &mut self.writer,
-                )
-                .await?;
-                return Ok(false);
-            }
+            Some(plugin) if !handshake.password.is_empty() => (plugin, handshake.password.to_vec()),
+            _ => {
+                // Authentication mismatch - try to switch auth plugins
+
+                if !handshake
+                    .capabilities
+                    .contains(CapabilityFlags::CLIENT_SECURE_CONNECTION)
+                {
+                    debug!(
+                        "Client does not support SECURE_CONNECTION, returning authentication error"
+                    );
+                    writers::write_err(
+                        ErrorKind::ER_NOT_SUPPORTED_AUTH_MODE,
+                        b"Client does not support authentication protocol requested by server; \
+                          consider upgrading MySQL client",
+                        &mut self.writer,
+                    )
+                    .await?;
+                    return Ok(false);
+                }
 
-            debug!(
-                ?client_auth_plugin,
-                "Client offered incorrect authentication plugin, sending switch request",
-            );
-
-            let mut auth_switch_request_packet =
-                Vec::with_capacity(1 + AUTH_PLUGIN_NAME.len() + 1 + auth_data.len() + 1);
-            auth_switch_request_packet.push(0xfe);
-            auth_switch_request_packet.extend_from_slice(AUTH_PLUGIN_NAME.as_bytes());
-            auth_switch_request_packet.push(0);
-            auth_switch_request_packet.extend_from_slice(&auth_data);
-            auth_switch_request_packet.push(0);
-            self.writer
-                .write_packet(&auth_switch_request_packet)
-                .await?;
-            self.writer.flush().await?;
+                debug!(
+                    ?client_auth_plugin,
+                    "Client offered unsupported authentication plugin, sending switch request",
+                );
+
+                let plugin_name = AuthPlugin::DEFAULT.name();
+                let mut auth_switch_request_packet =
+                    Vec::with_capacity(1 + plugin_name.len() + 1 + auth_data.len() + 1);
+                auth_switch_request_packet.push(0xfe);
+                auth_switch_request_packet.extend_from_slice(plugin_name.as_bytes());
+                auth_switch_request_packet.push(0);
+                auth_switch_request_packet.extend_from_slice(auth_data);
+                auth_switch_request_packet.push(0);
+                self.writer
+                    .write_packet(&auth_switch_request_packet)
+                    .await?;
+                self.writer.flush().await?;
 
-            let (seq, auth_switch_response) = self.reader.next().await?.ok_or_else(|| {
-                io::Error::new(
-                    io::ErrorKind::ConnectionAborted,
-                    "peer terminated connection",
-                )
-            })?;
-            self.writer.set_seq(seq + 1);
+                (AuthPlugin::DEFAULT, self.read_auth_response().await?)
+            }
+        };
 
-            auth_switch_response.to_vec()
+        let auth_success = if !self.shim.require_authentication() {
+            true
         } else {
-            password
+            let password = self.shim.password_for_username(&username);
+            match auth_plugin {
+                AuthPlugin::MysqlNativePassword => password.map_or(false, |password| {
+                    let expected = hash_password(&password, auth_data);
+                    let actual = auth_response.as_slice();
+                    trace!(?expected, ?actual);
+                    expected[..].ct_eq(actual).into()
+                }),
+                AuthPlugin::CachingSha2Password => match password {
+                    Some(password) => {
+                        self.authenticate_caching_sha2(&password, auth_data, &auth_response, tls)
+                            .await?
+                    }
+                    None => false,
+                },
+            }
         };
 
-        let auth_success = !self.shim.require_authentication()
-            || self
-                .shim
-                .password_for_username(&username)
-                .map_or(false, |password| {
-                    let expected = hash_password(&password, &auth_data);
-                    let actual = handshake_password.as_slice();
-                    trace!(?expected, ?actual);
-                    expected == actual
-                });
+        let tls_missing = auth_success && !tls && self.shim.require_tls(&username);
 
-        if auth_success {
-            debug!(%username, "Successfully authenticated client");
+        if tls_missing {
+            debug!(%username, "Client must connect using TLS");
+            writers::write_err(
+                ErrorKind::ER_ACCESS_DENIED_ERROR,
+                format!(
+                    "Access denied for user {}: connections must use TLS",
+                    username
+                )
+                .as_bytes(),
+                &mut self.writer,
+            )
+            .await?;
+        } else if auth_success {
+            debug!(%username, tls, "Successfully authenticated client");
             writers::write_ok_packet(&mut self.writer, 0, 0, StatusFlags::empty()).await?;
         } else {
             debug!(%username, ?client_auth_plugin, "Received incorrect password");
@@ -509,13 +653,81 @@ impl<B: MySqlShim<W> + Send, R: AsyncRead + Unpin, W: AsyncWrite + Unpin + Send>
         }
         self.writer.flush().await?;
 
-        Ok(auth_success)
+        Ok(auth_success && !tls_missing)
+    }
+
+    /// Check the client's `caching_sha2_password` `scramble` against the user's `password`,
+    /// falling back to full authentication if that fails. On success, this leaves the final OK
+    /// packet to the caller.
+    ///
+    /// Returns whether authentication succeeded.
+    async fn authenticate_caching_sha2(
+        &mut self,
+        password: &[u8],
+        auth_data: &AuthData,
+        scramble: &[u8],
+        tls: bool,
+    ) -> Result<bool, io::Error> {
+        // Clients with an empty password don't scramble it, and just send an empty response
+        let fast_auth_success = if password.is_empty() {
+            scramble.is_empty()
+        } else {
+            let expected = scramble_sha256(password, auth_data);
+            trace!(?expected, actual = ?scramble);
+            expected[..].ct_eq(scramble).into()
+        };
+        if fast_auth_success {
+            self.writer
+                .write_packet(&[AUTH_MORE_DATA, FAST_AUTH_SUCCESS])
+                .await?;
+            return Ok(true);
+        }
+
+        // We don't keep a cache of previously authenticated users, so whenever the scramble can't
+        // be verified we ask the client to send its password instead
+        debug!(tls, "Performing caching_sha2_password full authentication");
+        self.writer
+            .write_packet(&[AUTH_MORE_DATA, PERFORM_FULL_AUTHENTICATION])
+            .await?;
+        self.writer.flush().await?;
+        let mut response = self.read_auth_response().await?;
+
+        let client_password = if tls {
+            // Over TLS, clients send their password in cleartext, NUL-terminated
+            if response.last() == Some(&0) {
+                response.pop();
+            }
+            response
+        } else {
+            if response == [REQUEST_PUBLIC_KEY] {
+                let public_key = rsa_public_key_pem().map_err(io::Error::from)?;
+                let mut packet = Vec::with_capacity(1 + public_key.len());
+                packet.push(AUTH_MORE_DATA);
+                packet.extend_from_slice(&public_key);
+                self.writer.write_packet(&packet).await?;
+                self.writer.flush().await?;
+                response = self.read_auth_response().await?;
+            }
+
+            // Clients which already know our public key send their encrypted password right away
+            match decrypt_password(&response, auth_data) {
+                Ok(client_password) => client_password,
+                Err(error) => {
+                    debug!(%error, "Could not decrypt password sent by client");
+                    return Ok(false);
+                }
+            }
+        };
+
+        Ok(client_password[..].ct_eq(password).into())
     }
 
     async fn run(mut self) -> Result<(), io::Error> {
         use crate::commands::Command;
 
         let mut stmts: HashMap<u32, _> = HashMap::new();
+        // The cursors opened by the last execution of each statement, if the client asked for one
+        let mut cursors: HashMap<u32, resultset::Cursor<W>> = HashMap::new();
         while let Some((seq, packet)) = self.reader.next().await? {
             self.writer.set_seq(seq + 1);
             let cmd = commands::parse(&packet)
@@ -561,23 +773,95 @@ impl<B: MySqlShim<W> + Send, R: AsyncRead + Unpin, W: AsyncWrite + Unpin + Send>
                         })?
                         .long_data
                         .clear();
+                    // Resetting a statement also closes its cursor
+                    cursors.remove(&stmt);
                     writers::write_ok_packet(&mut self.writer, 0, 0, StatusFlags::empty()).await?;
                 }
-                Command::Execute { stmt, params } => {
+                Command::Execute {
+                    stmt,
+                    params,
+                    cursor,
+                } => {
                     let state = stmts.get_mut(&stmt).ok_or_else(|| {
                         io::Error::new(
                             io::ErrorKind::InvalidData,
                             format!("asked to execute unknown statement {}", stmt),
                         )
                     })?;
+                    // Executing a statement again closes the cursor opened by its last execution
+                    cursors.remove(&stmt);
+                    let mut new_cursor = None;
                     {
                         let params = params::ParamParser::new(params, state);
-                        let w = QueryResultWriter::new(&mut self.writer, true);
+                        let w = QueryResultWriter::new(&mut self.writer, true)
+                            .with_cursor(cursor.then_some(&mut new_cursor));
                         self.shim
                             .on_execute(stmt, params, w, &mut self.schema_cache)
                             .await?;
                     }
                     state.long_data.clear();
+                    if let Some(new_cursor) = new_cursor {
+                        cursors.insert(stmt, new_cursor);
+                    }
+                }
+                Command::Fetch { stmt, num_rows } => {
+                    if !stmts.contains_key(&stmt) {
+                        return Err(io::Error::new(
+                            io::ErrorKind::InvalidData,
+                            format!("asked to fetch from unknown statement {}", stmt),
+                        ));
+                    }
+                    match cursors.get_mut(&stmt) {
+                        Some(resultset::Cursor { columns, rows }) => {
+                            // Rows are only read from the cursor's source as they're fetched
+                            let mut rw = RowWriter::for_fetch(
+                                QueryResultWriter::new(&mut self.writer, true),
+                                columns.as_slice(),
+                            );
+                            let mut last_row_sent = false;
+                            let mut error = None;
+                            for _ in 0..num_rows {
+                                match rows.write_row(&mut rw).await {
+                                    Ok(true) => {}
+                                    Ok(false) => {
+                                        last_row_sent = true;
+                                        break;
+                                    }
+                                    Err(e) if e.kind() == io::ErrorKind::InvalidData => {
+                                        error = Some(e);
+                                        break;
+                                    }
+                                    Err(e) => return Err(e),
+                                }
+                            }
+                            drop(rw);
+
+                            if let Some(e) = error {
+                                cursors.remove(&stmt);
+                                writers::write_err(
+                                    ErrorKind::ER_UNKNOWN_ERROR,
+                                    e.to_string().as_bytes(),
+                                    &mut self.writer,
+                                )
+                                .await?;
+                            } else {
+                                let mut status = StatusFlags::SERVER_STATUS_CURSOR_EXISTS;
+                                if last_row_sent {
+                                    status |= StatusFlags::SERVER_STATUS_LAST_ROW_SENT;
+                                    cursors.remove(&stmt);
+                                }
+                                writers::write_eof_packet(&mut self.writer, status).await?;
+                            }
+                        }
+                        None => {
+                            writers::write_err(
+                                ErrorKind::ER_STMT_HAS_NO_OPEN_CURSOR,
+                                "The statement has no open cursor".as_bytes(),
+                                &mut self.writer,
+                            )
+                            .await?;
+                        }
+                    }
                 }
                 Command::SendLongData { stmt, param, data } => {
                     stmts
@@ -596,18 +880,25 @@ impl<B: MySqlShim<W> + Send, R: AsyncRead + Unpin, W: AsyncWrite + Unpin + Send>
                 Command::Close(stmt) => {
                     self.shim.on_close(stmt).await;
                     stmts.remove(&stmt);
+                    cursors.remove(&stmt);
                     // NOTE: spec dictates no response from server
                 }
-                Command::ListFields(_) => {
+                Command::ListFields(fields) => {
                     // This was deprecated in MySQL 5.7.11, but is still used by the `mysql` cli
                     // utility, for autocompletion/"auto-rehash" (`\rehash` will also manually
-                    // trigger it)
-                    writers::write_err(
-                        ErrorKind::ER_UNKNOWN_COM_ERROR,
-                        "COM_FIELD_LIST is unsupported".as_bytes(),
-                        &mut self.writer,
-                    )
-                    .await?;
+                    // trigger it). The table name is followed by a NUL and a column wildcard,
+                    // which we ignore.
+                    let table = fields.split(|b| *b == 0).next().unwrap_or_default();
+                    let w = ListFieldsWriter {
+                        writer: &mut self.writer,
+                    };
+                    self.shim
+                        .on_list_fields(
+                            ::std::str::from_utf8(table)
+                                .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?,
+                            w,
+                        )
+                        .await?;
                 }
                 Command::Init(schema) => {
                     debug!(schema = %String::from_utf8_lossy(schema), "Handling COM_INIT_DB");
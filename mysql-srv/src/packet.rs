@@ -89,6 +89,12 @@ impl<W: AsyncWrite + Unpin> PacketWriter<W> {
         self.seq = seq;
     }
 
+    /// Returns the underlying writer. Any queued packets must have been [flushed](Self::flush)
+    /// first, or they will be lost.
+    pub fn into_inner(self) -> W {
+        self.w
+    }
+
     /// Flushes the writer. This function *must* be called before dropping the internal writer
     /// or writes may be lossed.
     pub async fn flush(&mut self) -> Result<(), tokio::io::Error> {
@@ -243,6 +249,18 @@ impl<R> PacketReader<R> {
             r,
         }
     }
+
+    /// Returns the underlying reader, or an error if there are buffered bytes that haven't been
+    /// read as packets yet (which would otherwise be lost).
+    pub fn into_inner(self) -> io::Result<R> {
+        if self.remaining != 0 {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "received unexpected data before TLS handshake",
+            ));
+        }
+        Ok(self.r)
+    }
 }
 
 impl<R: AsyncRead + Unpin> PacketReader<R> {
@@ -3,6 +3,7 @@ use std::collections::HashMap;
 use std::io;
 use std::sync::Arc;
 
+use async_trait::async_trait;
 use tokio::io::AsyncWrite;
 
 use crate::myc::constants::{ColumnFlags, StatusFlags};
@@ -37,6 +38,49 @@ impl<'a, W: AsyncWrite + Unpin + 'a> InitWriter<'a, W> {
     }
 }
 
+/// Convenience type for responding to a client `COM_FIELD_LIST` command.
+pub struct ListFieldsWriter<'a, W: AsyncWrite + Unpin> {
+    pub(crate) writer: &'a mut PacketWriter<W>,
+}
+
+impl<'a, W: AsyncWrite + Unpin + 'a> ListFieldsWriter<'a, W> {
+    /// Reply to the client with the definitions of the columns of the requested table.
+    pub async fn reply(self, columns: &[Column]) -> io::Result<()> {
+        writers::write_field_list(columns, self.writer).await
+    }
+
+    /// Reply to the client's `COM_FIELD_LIST` with an error, such as
+    /// `ErrorKind::ER_NO_SUCH_TABLE`.
+    pub async fn error<E>(self, kind: ErrorKind, msg: &E) -> io::Result<()>
+    where
+        E: Borrow<[u8]> + ?Sized,
+    {
+        writers::write_err(kind, msg.borrow(), self.writer).await
+    }
+}
+
+/// A source for the rows of a resultset which a client fetches in batches with `COM_STMT_FETCH`,
+/// after executing a prepared statement with a read-only cursor.
+///
+/// Rows are only read from the source and encoded as the client fetches them, so the resultset is
+/// never buffered in its encoded form.
+#[async_trait]
+pub trait CursorRows<W: AsyncWrite + Unpin>: Send {
+    /// Write the next row of the resultset with `rw`, ending it with [`RowWriter::end_row`], or
+    /// return `false` if all the rows have been fetched already.
+    ///
+    /// Errors of kind [`io::ErrorKind::InvalidData`] are reported to the client, and close the
+    /// cursor. All other errors close the connection.
+    async fn write_row(&mut self, rw: &mut RowWriter<'_, W>) -> io::Result<bool>;
+}
+
+/// A cursor opened by the execution of a prepared statement, whose rows haven't all been fetched
+/// yet.
+pub(crate) struct Cursor<W: AsyncWrite + Unpin> {
+    pub(crate) columns: Vec<Column>,
+    pub(crate) rows: Box<dyn CursorRows<W>>,
+}
+
 /// Convenience type for responding to a client `PREPARE` command.
 ///
 /// This type should not be dropped without calling
@@ -118,6 +162,8 @@ pub struct QueryResultWriter<'a, W: AsyncWrite + Unpin> {
     pub(crate) is_bin: bool,
     pub(crate) writer: &'a mut PacketWriter<W>,
     last_end: Option<Finalizer>,
+    /// Set if the client asked for a cursor over the results, to hold the cursor if one is opened
+    cursor: Option<&'a mut Option<Cursor<W>>>,
 }
 
 impl<'a, W: AsyncWrite + Unpin> QueryResultWriter<'a, W> {
@@ -126,9 +172,72 @@ impl<'a, W: AsyncWrite + Unpin> QueryResultWriter<'a, W> {
             is_bin,
             writer,
             last_end: None,
+            cursor: None,
         }
     }
 
+    /// Hold the cursor opened with [`Self::start_cursor`], if any, in `cursor`
+    pub(crate) fn with_cursor(mut self, cursor: Option<&'a mut Option<Cursor<W>>>) -> Self {
+        self.cursor = cursor;
+        self
+    }
+
+    /// Returns true if the client executed the statement with a read-only cursor, and can thus
+    /// fetch the rows of the resultset in batches after a call to [`Self::start_cursor`].
+    pub fn cursor_requested(&self) -> bool {
+        self.cursor.is_some()
+    }
+
+    /// Start a resultset response to the client that conforms to the given `columns`, whose rows
+    /// are read from `rows` as the client fetches them with `COM_STMT_FETCH`. `cached` is an
+    /// optional preencoded representation of the `columns`, as for [`Self::start_with_cache`].
+    ///
+    /// If the client didn't ask for a cursor (see [`Self::cursor_requested`]), all the rows are
+    /// written right away instead.
+    pub async fn start_cursor(
+        mut self,
+        columns: &'a [Column],
+        cached: Option<Arc<[u8]>>,
+        mut rows: Box<dyn CursorRows<W>>,
+    ) -> io::Result<()> {
+        let cursor = match self.cursor.take() {
+            Some(cursor) if !columns.is_empty() => cursor,
+            _ => {
+                self.finalize(true).await?;
+                let mut rw = RowWriter::new(self, columns, cached).await?;
+                loop {
+                    match rows.write_row(&mut rw).await {
+                        Ok(true) => {}
+                        Ok(false) => break,
+                        Err(e) if e.kind() == io::ErrorKind::InvalidData => {
+                            return rw
+                                .error(ErrorKind::ER_UNKNOWN_ERROR, e.to_string().as_bytes())
+                                .await;
+                        }
+                        Err(e) => return Err(e),
+                    }
+                }
+                return rw.finish().await;
+            }
+        };
+
+        self.finalize(true).await?;
+        // The client learns that it has to fetch the rows from the EOF packet following the
+        // column definitions
+        let eof_status = StatusFlags::SERVER_STATUS_CURSOR_EXISTS;
+        match cached {
+            Some(cached) => {
+                writers::column_definitions_cached(columns, cached, self.writer, eof_status).await?
+            }
+            None => writers::column_definitions(columns, self.writer, eof_status).await?,
+        }
+        *cursor = Some(Cursor {
+            columns: columns.to_vec(),
+            rows,
+        });
+        Ok(())
+    }
+
     async fn finalize(&mut self, more_exists: bool) -> io::Result<()> {
         let mut status = match self.last_end {
             Some(Finalizer::Ok {
@@ -311,6 +420,28 @@ where
         Ok(rw)
     }
 
+    /// Create a writer for a batch of rows fetched by the client from a cursor, whose column
+    /// definitions were already sent when the cursor was opened.
+    pub(crate) fn for_fetch(
+        result: QueryResultWriter<'a, W>,
+        columns: &'a [Column],
+    ) -> RowWriter<'a, W> {
+        RowWriter {
+            result,
+            columns,
+            cached: None,
+            bitmap_len: (columns.len() + 7 + 2) / 8,
+            bitmap_idx: 0,
+
+            col: 0,
+
+            finished: false,
+            last_status_flags: None,
+
+            row_data: None,
+        }
+    }
+
     async fn start(&mut self) -> io::Result<()> {
         if self.columns.is_empty() {
             return Ok(());
@@ -318,10 +449,18 @@ where
 
         match &self.cached {
             Some(cached) => {
-                writers::column_definitions_cached(self.columns, cached.clone(), self.result.writer)
+                writers::column_definitions_cached(
+                    self.columns,
+                    cached.clone(),
+                    self.result.writer,
+                    StatusFlags::empty(),
+                )
+                .await
+            }
+            None => {
+                writers::column_definitions(self.columns, self.result.writer, StatusFlags::empty())
                     .await
             }
-            None => writers::column_definitions(self.columns, self.result.writer).await,
         }
     }
 
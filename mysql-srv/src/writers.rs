@@ -81,8 +81,8 @@ where
     buf.write_u16::<LittleEndian>(0)?; // number of warnings
     w.enqueue_packet(buf);
 
-    write_column_definitions(pi, w, true).await?;
-    write_column_definitions(ci, w, true).await
+    write_column_definitions(pi, w, true, StatusFlags::empty()).await?;
+    write_column_definitions(ci, w, true, StatusFlags::empty()).await
 }
 
 /// Compute the size of the buffer required to encode this buffer
@@ -166,6 +166,7 @@ pub(crate) async fn write_column_definitions<'a, I, W>(
     i: I,
     w: &mut PacketWriter<W>,
     only_eof_on_nonempty: bool,
+    eof_status: StatusFlags,
 ) -> io::Result<()>
 where
     I: IntoIterator<Item = &'a Column>,
@@ -183,11 +184,32 @@ where
     if empty && only_eof_on_nonempty {
         Ok(())
     } else {
-        write_eof_packet(w, StatusFlags::empty()).await
+        write_eof_packet(w, eof_status).await
     }
 }
 
-pub(crate) async fn column_definitions<'a, I, W>(i: I, w: &mut PacketWriter<W>) -> io::Result<()>
+/// Write the column definitions sent in response to COM_FIELD_LIST, which are followed by the
+/// columns' default values (which we always send as NULL)
+pub(crate) async fn write_field_list<'a, I, W>(i: I, w: &mut PacketWriter<W>) -> io::Result<()>
+where
+    I: IntoIterator<Item = &'a Column>,
+    W: AsyncWrite + Unpin,
+{
+    for c in i {
+        let mut buf = w.get_buffer();
+        buf.reserve(col_enc_len(c) + 1);
+        write_column_defintion(c, &mut buf);
+        buf.write_u8(0xFB)?; // NULL default value
+        w.enqueue_packet(buf);
+    }
+    write_eof_packet(w, StatusFlags::empty()).await
+}
+
+pub(crate) async fn column_definitions<'a, I, W>(
+    i: I,
+    w: &mut PacketWriter<W>,
+    eof_status: StatusFlags,
+) -> io::Result<()>
 where
     I: IntoIterator<Item = &'a Column>,
     <I as IntoIterator>::IntoIter: ExactSizeIterator,
@@ -197,13 +219,14 @@ where
     let mut buf = w.get_buffer();
     buf.write_lenenc_int(i.len() as u64)?;
     w.enqueue_packet(buf);
-    write_column_definitions(i, w, false).await
+    write_column_definitions(i, w, false, eof_status).await
 }
 
 pub(crate) async fn column_definitions_cached<'a, I, W>(
     i: I,
     cached: Arc<[u8]>,
     w: &mut PacketWriter<W>,
+    eof_status: StatusFlags,
 ) -> io::Result<()>
 where
     I: IntoIterator<Item = &'a Column>,
@@ -213,5 +236,5 @@ where
     let i = i.into_iter();
     w.enqueue_raw(cached).await?;
     w.seq = w.seq.wrapping_add((1 + i.len()) as u8);
-    write_eof_packet(w, StatusFlags::empty()).await
+    write_eof_packet(w, eof_status).await
 }
@@ -10,17 +10,23 @@ extern crate tokio;
 use core::iter;
 use std::collections::HashMap;
 use std::future::Future;
+use std::io::{Read, Write};
 use std::marker::PhantomData;
 use std::pin::Pin;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
 use std::{io, net, thread};
 
 use async_trait::async_trait;
+use myc::constants::CapabilityFlags;
 use mysql::prelude::Queryable;
 use mysql::Row;
 use mysql_srv::{
-    CachedSchema, Column, ErrorKind, InitWriter, MySqlIntermediary, MySqlShim, ParamParser,
-    QueryResultWriter, StatementMetaWriter,
+    CachedSchema, Column, CursorRows, ErrorKind, InitWriter, ListFieldsWriter, MySqlIntermediary,
+    MySqlShim, ParamParser, QueryResultWriter, RowWriter, StatementMetaWriter,
 };
+use openssl::rsa::{Padding, Rsa};
+use sha2::{Digest, Sha256};
 use tokio::io::AsyncWrite;
 use tokio::net::tcp::OwnedWriteHalf;
 
@@ -91,6 +97,15 @@ where
         (self.on_i)(schema, writer).await
     }
 
+    async fn on_list_fields(&mut self, table: &str, w: ListFieldsWriter<'_, W>) -> io::Result<()> {
+        if table == "b" {
+            w.reply(&self.columns).await
+        } else {
+            w.error(ErrorKind::ER_NO_SUCH_TABLE, "no such table".as_bytes())
+                .await
+        }
+    }
+
     async fn on_query(&mut self, query: &str, results: QueryResultWriter<'_, W>) -> io::Result<()> {
         if query.starts_with("SELECT @@") || query.starts_with("select @@") {
             let var = &query.get(b"SELECT @@".len()..);
@@ -197,6 +212,193 @@ where
         drop(db);
         jh.join().unwrap().unwrap();
     }
+
+    /// Like [`test`](Self::test), but hands `c` a [`RawClient`] which has just received the
+    /// server's initial handshake, to test the authentication exchange itself
+    fn test_handshake<C>(self, c: C)
+    where
+        C: FnOnce(&mut RawClient, [u8; 20]),
+    {
+        let listener = net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let rt = tokio::runtime::Runtime::new().unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let jh = thread::spawn(move || {
+            let (s, _) = listener.accept().unwrap();
+            let s = {
+                let _guard = rt.handle().enter();
+                tokio::net::TcpStream::from_std(s).unwrap()
+            };
+            rt.block_on(MySqlIntermediary::run_on_tcp(self, s))
+        });
+
+        let mut client = RawClient {
+            stream: net::TcpStream::connect(("127.0.0.1", port)).unwrap(),
+            seq: 0,
+        };
+        let auth_data = client.read_initial_handshake();
+        c(&mut client, auth_data);
+        drop(client);
+        jh.join().unwrap().unwrap();
+    }
+}
+
+/// A client which speaks just enough of the protocol to drive the authentication exchange by hand
+struct RawClient {
+    stream: net::TcpStream,
+    seq: u8,
+}
+
+impl RawClient {
+    fn read_packet(&mut self) -> Vec<u8> {
+        let mut header = [0u8; 4];
+        self.stream.read_exact(&mut header).unwrap();
+        let len = u32::from_le_bytes([header[0], header[1], header[2], 0]) as usize;
+        self.seq = header[3].wrapping_add(1);
+        let mut packet = vec![0u8; len];
+        self.stream.read_exact(&mut packet).unwrap();
+        packet
+    }
+
+    fn write_packet(&mut self, payload: &[u8]) {
+        let mut packet = (payload.len() as u32).to_le_bytes()[..3].to_vec();
+        packet.push(self.seq);
+        packet.extend_from_slice(payload);
+        self.stream.write_all(&packet).unwrap();
+        self.seq = self.seq.wrapping_add(1);
+    }
+
+    /// Read the server's initial handshake packet, and return the auth data sent with it
+    fn read_initial_handshake(&mut self) -> [u8; 20] {
+        let packet = self.read_packet();
+        assert_eq!(packet[0], 10);
+        // Skip the NUL-terminated server version and the connection ID
+        let start = packet[1..].iter().position(|b| *b == 0).unwrap() + 2 + 4;
+        let mut auth_data = [0u8; 20];
+        auth_data[..8].copy_from_slice(&packet[start..start + 8]);
+        // Skip the filler, capabilities, character set, status flags and reserved bytes
+        let rest = start + 8 + 1 + 2 + 1 + 2 + 2 + 1 + 10;
+        auth_data[8..].copy_from_slice(&packet[rest..rest + 12]);
+        assert_eq!(
+            &packet[rest + 13..],
+            b"caching_sha2_password\0",
+            "Server should default to caching_sha2_password"
+        );
+        auth_data
+    }
+
+    /// Send a handshake response for `user`, offering `auth_plugin` with the given `auth_response`
+    fn write_handshake_response(&mut self, auth_plugin: &str, auth_response: &[u8]) {
+        let capabilities = CapabilityFlags::CLIENT_PROTOCOL_41
+            | CapabilityFlags::CLIENT_SECURE_CONNECTION
+            | CapabilityFlags::CLIENT_PLUGIN_AUTH;
+        let mut packet = capabilities.bits().to_le_bytes().to_vec();
+        packet.extend_from_slice(&16777216u32.to_le_bytes()); // max packet size
+        packet.push(DEFAULT_CHARACTER_SET as u8);
+        packet.extend_from_slice(&[0; 23]);
+        packet.extend_from_slice(b"user\0");
+        packet.push(auth_response.len() as u8);
+        packet.extend_from_slice(auth_response);
+        packet.extend_from_slice(auth_plugin.as_bytes());
+        packet.push(0);
+        self.write_packet(&packet);
+    }
+
+    /// Authenticate as `user`, using the `caching_sha2_password` fast path
+    fn login(&mut self, auth_data: &[u8]) {
+        self.write_handshake_response(
+            "caching_sha2_password",
+            &scramble_sha256(b"password", auth_data),
+        );
+        assert_eq!(self.read_packet(), [0x01, 0x03]);
+        self.expect_ok();
+    }
+
+    /// Send a command packet, which starts a new sequence
+    fn write_command(&mut self, command: myc::constants::Command, payload: &[u8]) {
+        self.seq = 0;
+        let mut packet = vec![command as u8];
+        packet.extend_from_slice(payload);
+        self.write_packet(&packet);
+    }
+
+    /// Read packets until an EOF packet, returning the packets before it and the status flags of
+    /// the EOF packet
+    fn read_until_eof(&mut self) -> (Vec<Vec<u8>>, myc::constants::StatusFlags) {
+        let mut packets = vec![];
+        loop {
+            let packet = self.read_packet();
+            if packet[0] == 0xfe && packet.len() < 9 {
+                let status = myc::constants::StatusFlags::from_bits_truncate(u16::from_le_bytes([
+                    packet[3], packet[4],
+                ]));
+                return (packets, status);
+            }
+            assert_ne!(packet[0], 0xff, "Unexpected error packet {:?}", packet);
+            packets.push(packet);
+        }
+    }
+
+    /// Assert that the next packet is an OK packet
+    fn expect_ok(&mut self) {
+        let packet = self.read_packet();
+        assert_eq!(packet[0], 0x00, "Expected OK packet, got {:?}", packet);
+    }
+
+    /// Assert that the next packet is an error packet with the given error kind
+    fn expect_err(&mut self, kind: ErrorKind) {
+        let packet = self.read_packet();
+        assert_eq!(packet[0], 0xff, "Expected error packet, got {:?}", packet);
+        assert_eq!(u16::from_le_bytes([packet[1], packet[2]]), kind as u16);
+    }
+}
+
+/// Yields `n` rows of a single `SHORT` column, counting how many rows have been read
+struct CountingRows {
+    next: i16,
+    n: i16,
+    read: Arc<AtomicUsize>,
+}
+
+#[async_trait]
+impl<W: AsyncWrite + Unpin + Send> CursorRows<W> for CountingRows {
+    async fn write_row(&mut self, rw: &mut RowWriter<'_, W>) -> io::Result<bool> {
+        if self.next == self.n {
+            return Ok(false);
+        }
+        self.read.fetch_add(1, Ordering::SeqCst);
+        rw.write_row(iter::once(self.next)).await?;
+        self.next += 1;
+        Ok(true)
+    }
+}
+
+/// Scramble `password` with `auth_data` the way a `caching_sha2_password` client does
+fn scramble_sha256(password: &[u8], auth_data: &[u8]) -> Vec<u8> {
+    let hashed = Sha256::digest(password);
+    let salted = Sha256::digest(&[Sha256::digest(&hashed).as_slice(), auth_data].concat());
+    hashed
+        .iter()
+        .zip(salted.iter())
+        .map(|(x, y)| x ^ y)
+        .collect()
+}
+
+/// Encrypt `password` with the server's PEM-encoded `public_key` the way a `caching_sha2_password`
+/// client does during full authentication over an insecure connection
+fn encrypt_password(password: &[u8], auth_data: &[u8], public_key: &[u8]) -> Vec<u8> {
+    let mut plaintext = password.to_vec();
+    plaintext.push(0);
+    plaintext
+        .iter_mut()
+        .zip(auth_data.iter().cycle())
+        .for_each(|(x, y)| *x ^= y);
+    let public_key = Rsa::public_key_from_pem(public_key).unwrap();
+    let mut encrypted = vec![0u8; public_key.size() as usize];
+    let len = public_key
+        .public_encrypt(&plaintext, &mut encrypted, Padding::PKCS1_OAEP)
+        .unwrap();
+    encrypted.truncate(len);
+    encrypted
 }
 
 #[test]
@@ -1110,3 +1312,237 @@ fn really_long_query() {
         db.query::<Row, _>(long).unwrap();
     })
 }
+
+#[test]
+fn caching_sha2_fast_auth() {
+    TestingShim::new(
+        |_, _| unreachable!(),
+        |_| unreachable!(),
+        |_, _, _| unreachable!(),
+        |_, _| unreachable!(),
+    )
+    .test_handshake(|client, auth_data| {
+        client.write_handshake_response(
+            "caching_sha2_password",
+            &scramble_sha256(b"password", &auth_data),
+        );
+        assert_eq!(client.read_packet(), [0x01, 0x03]);
+        client.expect_ok();
+    })
+}
+
+#[test]
+fn auth_switch_from_unsupported_plugin() {
+    TestingShim::new(
+        |_, _| unreachable!(),
+        |_| unreachable!(),
+        |_, _, _| unreachable!(),
+        |_, _| unreachable!(),
+    )
+    .test_handshake(|client, auth_data| {
+        client.write_handshake_response("mysql_clear_password", b"password\0");
+
+        let switch_request = client.read_packet();
+        let mut expected = vec![0xfe];
+        expected.extend_from_slice(b"caching_sha2_password\0");
+        expected.extend_from_slice(&auth_data);
+        expected.push(0);
+        assert_eq!(switch_request, expected);
+
+        client.write_packet(&scramble_sha256(b"password", &auth_data));
+        assert_eq!(client.read_packet(), [0x01, 0x03]);
+        client.expect_ok();
+    })
+}
+
+#[test]
+fn caching_sha2_rsa_full_auth() {
+    TestingShim::new(
+        |_, _| unreachable!(),
+        |_| unreachable!(),
+        |_, _, _| unreachable!(),
+        |_, _| unreachable!(),
+    )
+    .test_handshake(|client, auth_data| {
+        // A client whose scramble can't be verified has to send its password instead
+        client.write_handshake_response("caching_sha2_password", &[0; 32]);
+        assert_eq!(client.read_packet(), [0x01, 0x04]);
+
+        client.write_packet(&[0x02]);
+        let public_key = client.read_packet();
+        assert_eq!(public_key[0], 0x01);
+
+        client.write_packet(&encrypt_password(b"password", &auth_data, &public_key[1..]));
+        client.expect_ok();
+    })
+}
+
+#[test]
+fn caching_sha2_rsa_full_auth_wrong_password() {
+    TestingShim::new(
+        |_, _| unreachable!(),
+        |_| unreachable!(),
+        |_, _, _| unreachable!(),
+        |_, _| unreachable!(),
+    )
+    .test_handshake(|client, auth_data| {
+        client.write_handshake_response("caching_sha2_password", &[0; 32]);
+        assert_eq!(client.read_packet(), [0x01, 0x04]);
+
+        client.write_packet(&[0x02]);
+        let public_key = client.read_packet();
+        client.write_packet(&encrypt_password(
+            b"bad_password",
+            &auth_data,
+            &public_key[1..],
+        ));
+        client.expect_err(ErrorKind::ER_ACCESS_DENIED_ERROR);
+    })
+}
+
+#[test]
+fn cursor_fetches_rows_lazily() {
+    use myc::constants::{Command, StatusFlags};
+
+    let cols = vec![Column {
+        table: String::new(),
+        column: "a".to_owned(),
+        coltype: myc::constants::ColumnType::MYSQL_TYPE_SHORT,
+        column_length: None,
+        colflags: myc::constants::ColumnFlags::empty(),
+        character_set: DEFAULT_CHARACTER_SET,
+    }];
+    let cols2 = cols.clone();
+    let read = Arc::new(AtomicUsize::new(0));
+    let read2 = read.clone();
+
+    TestingShim::new(
+        |_, _| unreachable!(),
+        |_| 41,
+        move |_, _, w| {
+            let cols = cols.clone();
+            let rows = Box::new(CountingRows {
+                next: 0,
+                n: 3,
+                read: read.clone(),
+            });
+            Box::pin(async move {
+                assert!(w.cursor_requested());
+                w.start_cursor(&cols, None, rows).await
+            })
+        },
+        |_, _| unreachable!(),
+    )
+    .with_columns(cols2)
+    .test_handshake(move |client, auth_data| {
+        client.login(&auth_data);
+
+        client.write_command(Command::COM_STMT_PREPARE, b"SELECT a FROM b");
+        assert_eq!(client.read_packet()[..5], [0x00, 41, 0, 0, 0]);
+        client.read_until_eof();
+
+        let mut execute = 41u32.to_le_bytes().to_vec();
+        execute.push(0x01); // CURSOR_TYPE_READ_ONLY
+        execute.extend_from_slice(&1u32.to_le_bytes());
+        client.write_command(Command::COM_STMT_EXECUTE, &execute);
+        assert_eq!(client.read_packet(), [0x01]);
+        let (columns, status) = client.read_until_eof();
+        assert_eq!(columns.len(), 1);
+        assert!(status.contains(StatusFlags::SERVER_STATUS_CURSOR_EXISTS));
+        assert_eq!(read2.load(Ordering::SeqCst), 0);
+
+        let mut fetch = 41u32.to_le_bytes().to_vec();
+        fetch.extend_from_slice(&2u32.to_le_bytes());
+        client.write_command(Command::COM_STMT_FETCH, &fetch);
+        let (rows, status) = client.read_until_eof();
+        assert_eq!(rows, [[0x00, 0x00, 0x00, 0x00], [0x00, 0x00, 0x01, 0x00]]);
+        assert!(!status.contains(StatusFlags::SERVER_STATUS_LAST_ROW_SENT));
+        assert_eq!(read2.load(Ordering::SeqCst), 2);
+
+        client.write_command(Command::COM_STMT_FETCH, &fetch);
+        let (rows, status) = client.read_until_eof();
+        assert_eq!(rows, [[0x00, 0x00, 0x02, 0x00]]);
+        assert!(status.contains(StatusFlags::SERVER_STATUS_LAST_ROW_SENT));
+
+        client.write_command(Command::COM_STMT_FETCH, &fetch);
+        client.expect_err(ErrorKind::ER_STMT_HAS_NO_OPEN_CURSOR);
+    })
+}
+
+#[test]
+fn cursor_rows_sent_right_away_without_cursor() {
+    let cols = vec![Column {
+        table: String::new(),
+        column: "a".to_owned(),
+        coltype: myc::constants::ColumnType::MYSQL_TYPE_SHORT,
+        column_length: None,
+        colflags: myc::constants::ColumnFlags::empty(),
+        character_set: DEFAULT_CHARACTER_SET,
+    }];
+    let cols2 = cols.clone();
+
+    TestingShim::new(
+        |_, _| unreachable!(),
+        |_| 41,
+        move |_, _, w| {
+            let cols = cols.clone();
+            let rows = Box::new(CountingRows {
+                next: 0,
+                n: 3,
+                read: Default::default(),
+            });
+            Box::pin(async move {
+                assert!(!w.cursor_requested());
+                w.start_cursor(&cols, None, rows).await
+            })
+        },
+        |_, _| unreachable!(),
+    )
+    .with_columns(cols2)
+    .test(|db| {
+        let rows = db.exec::<i16, _, _>("SELECT a FROM b", ()).unwrap();
+        assert_eq!(rows, [0, 1, 2]);
+    })
+}
+
+#[test]
+fn it_lists_fields() {
+    let cols = vec![
+        Column {
+            table: "b".to_owned(),
+            column: "a".to_owned(),
+            coltype: myc::constants::ColumnType::MYSQL_TYPE_SHORT,
+            column_length: None,
+            colflags: myc::constants::ColumnFlags::empty(),
+            character_set: DEFAULT_CHARACTER_SET,
+        },
+        Column {
+            table: "b".to_owned(),
+            column: "c".to_owned(),
+            coltype: myc::constants::ColumnType::MYSQL_TYPE_VAR_STRING,
+            column_length: None,
+            colflags: myc::constants::ColumnFlags::empty(),
+            character_set: DEFAULT_CHARACTER_SET,
+        },
+    ];
+
+    TestingShim::new(
+        |_, _| unreachable!(),
+        |_| unreachable!(),
+        |_, _, _| unreachable!(),
+        |_, _| unreachable!(),
+    )
+    .with_columns(cols)
+    .test_handshake(|client, auth_data| {
+        client.login(&auth_data);
+
+        client.write_command(myc::constants::Command::COM_FIELD_LIST, b"b\0");
+        let (columns, _) = client.read_until_eof();
+        assert_eq!(columns.len(), 2);
+        // Each definition is followed by the column's default value, which is always NULL
+        assert!(columns.iter().all(|c| c.last() == Some(&0xfb)));
+
+        client.write_command(myc::constants::Command::COM_FIELD_LIST, b"nonexistent\0");
+        client.expect_err(ErrorKind::ER_NO_SUCH_TABLE);
+    })
+}
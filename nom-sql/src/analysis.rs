@@ -130,6 +130,16 @@ impl<'a> ReferredColumnsIter<'a> {
             }),
             Expr::NestedSelect(_) => None,
             Expr::Variable(_) => None,
+            Expr::WindowFunction {
+                function,
+                partition_by,
+                order_by,
+            } => {
+                self.exprs_to_visit.extend(partition_by.iter());
+                self.exprs_to_visit
+                    .extend(order_by.iter().map(|(expr, _)| expr));
+                self.visit_function_expression(function)
+            }
         }
     }
 
@@ -234,6 +244,16 @@ impl<'a> ReferredColumnsMut<'a> {
             }),
             Expr::NestedSelect(_) => None,
             Expr::Variable(_) => None,
+            Expr::WindowFunction {
+                function,
+                partition_by,
+                order_by,
+            } => {
+                self.exprs_to_visit.extend(partition_by.iter_mut());
+                self.exprs_to_visit
+                    .extend(order_by.iter_mut().map(|(expr, _)| expr));
+                self.visit_function_expression(function)
+            }
         }
     }
 
@@ -421,6 +441,16 @@ pub fn contains_aggregate(expr: &Expr) -> bool {
         }
         Expr::Array(exprs) => exprs.iter().any(contains_aggregate),
         Expr::Variable(_) => false,
+        // Window functions are computed over the rows of the query *before* aggregation, so an
+        // aggregate function used as a window function doesn't make the query an aggregate query
+        Expr::WindowFunction {
+            partition_by,
+            order_by,
+            ..
+        } => {
+            partition_by.iter().any(contains_aggregate)
+                || order_by.iter().any(|(expr, _)| contains_aggregate(expr))
+        }
     }
 }
 
@@ -504,6 +534,16 @@ impl Expr {
                 ..
             } => Box::new(iter::once(lhs.as_ref())) as _,
             Expr::Array(exprs) => Box::new(exprs.iter()),
+            Expr::WindowFunction {
+                function,
+                partition_by,
+                order_by,
+            } => Box::new(
+                function
+                    .arguments()
+                    .chain(partition_by)
+                    .chain(order_by.iter().map(|(expr, _)| expr)),
+            ) as _,
         }
     }
 
@@ -441,6 +441,20 @@ pub fn walk_expr<'ast, V: Visitor<'ast>>(
             Ok(())
         }
         Expr::Variable(var) => visitor.visit_variable(var),
+        Expr::WindowFunction {
+            function,
+            partition_by,
+            order_by,
+        } => {
+            visitor.visit_function_expr(function)?;
+            for expr in partition_by {
+                visitor.visit_expr(expr)?;
+            }
+            for (expr, _) in order_by {
+                visitor.visit_expr(expr)?;
+            }
+            Ok(())
+        }
     }
 }
 
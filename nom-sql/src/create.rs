@@ -1,4 +1,5 @@
 use std::str::FromStr;
+use std::time::Duration;
 use std::{fmt, str};
 
 use derive_more::{Display, From};
@@ -19,6 +20,7 @@ use crate::common::{
 use crate::compound_select::{nested_compound_selection, CompoundSelectStatement};
 use crate::create_table_options::{table_options, CreateTableOption};
 use crate::expression::expression;
+use crate::literal::{raw_string_literal, QuotingStyle};
 use crate::order::{order_type, OrderType};
 use crate::select::{nested_selection, selection, SelectStatement};
 use crate::table::{relation, Relation};
@@ -140,7 +142,7 @@ pub enum CacheInner {
     Id(SqlIdentifier),
 }
 
-/// `CREATE CACHE [ALWAYS] [<name>] FROM ...`
+/// `CREATE CACHE [ALWAYS] [<name>] [WITH MAX_STALENESS '<duration>'] FROM ...`
 ///
 /// This is a non-standard ReadySet specific extension to SQL
 #[derive(Clone, Debug, Eq, Hash, PartialEq, Serialize, Deserialize)]
@@ -148,6 +150,9 @@ pub struct CreateCacheStatement {
     pub name: Option<Relation>,
     pub inner: CacheInner,
     pub always: bool,
+    /// The maximum amount of time the results of reads against the cache may lag behind the
+    /// upstream database. Reads for which this can't be guaranteed are proxied upstream.
+    pub max_staleness: Option<Duration>,
 }
 
 impl fmt::Display for CreateCacheStatement {
@@ -159,10 +164,47 @@ impl fmt::Display for CreateCacheStatement {
         if let Some(name) = &self.name {
             write!(f, "{} ", name)?;
         }
+        if let Some(max_staleness) = self.max_staleness {
+            let millis = max_staleness.as_millis();
+            if millis % 1000 == 0 {
+                write!(f, "WITH MAX_STALENESS '{}s' ", millis / 1000)?;
+            } else {
+                write!(f, "WITH MAX_STALENESS '{}ms' ", millis)?;
+            }
+        }
         write!(f, "FROM {}", self.inner)
     }
 }
 
+/// Parse a duration such as `500ms`, `2s`, `5m` or `1h`. A duration without a unit is in seconds.
+fn parse_duration(s: &str) -> Option<Duration> {
+    let s = s.trim();
+    let unit_start = s.find(|c: char| !c.is_ascii_digit()).unwrap_or(s.len());
+    let (value, unit) = s.split_at(unit_start);
+    let value = value.parse::<u64>().ok()?;
+    match unit.trim_start().to_ascii_lowercase().as_str() {
+        "ms" => Some(Duration::from_millis(value)),
+        "" | "s" => Some(Duration::from_secs(value)),
+        "m" | "min" => Some(Duration::from_secs(value.checked_mul(60)?)),
+        "h" => Some(Duration::from_secs(value.checked_mul(60 * 60)?)),
+        _ => None,
+    }
+}
+
+/// Parse the `WITH MAX_STALENESS '<duration>'` clause of a [`CreateCacheStatement`]
+fn max_staleness(i: LocatedSpan<&[u8]>) -> NomSqlResult<&[u8], Duration> {
+    let (i, _) = tag_no_case("with")(i)?;
+    let (i, _) = whitespace1(i)?;
+    let (i, _) = tag_no_case("max_staleness")(i)?;
+    let (i, _) = whitespace1(i)?;
+    map_res(raw_string_literal(QuotingStyle::Single), |s| {
+        str::from_utf8(&s)
+            .ok()
+            .and_then(parse_duration)
+            .ok_or("invalid duration")
+    })(i)
+}
+
 // MySQL grammar element for index column definition (§13.1.18, index_col_name)
 #[allow(clippy::type_complexity)]
 pub fn index_col_name(
@@ -687,6 +729,7 @@ pub fn create_cached_query(
         let (i, _) = whitespace1(i)?;
         let (i, always) = opt(terminated(tag_no_case("always"), whitespace1))(i)?;
         let (i, name) = opt(terminated(relation(dialect), whitespace1))(i)?;
+        let (i, max_staleness) = opt(terminated(max_staleness, whitespace1))(i)?;
         let (i, _) = tag_no_case("from")(i)?;
         let (i, _) = whitespace1(i)?;
         let (i, inner) = cached_query_inner(dialect)(i)?;
@@ -696,6 +739,7 @@ pub fn create_cached_query(
                 name,
                 inner,
                 always: always.is_some(),
+                max_staleness,
             },
         ))
     }
@@ -1431,6 +1475,59 @@ mod tests {
             assert!(res.always);
         }
 
+        #[test]
+        fn create_cached_query_with_max_staleness() {
+            let res = test_parse!(
+                create_cached_query(Dialect::MySQL),
+                b"CREATE CACHE foo WITH MAX_STALENESS '2s' FROM SELECT id FROM users WHERE name = ?"
+            );
+            assert_eq!(res.name, Some("foo".into()));
+            assert_eq!(res.max_staleness, Some(Duration::from_secs(2)));
+            assert!(!res.always);
+
+            let res = test_parse!(
+                create_cached_query(Dialect::MySQL),
+                b"CREATE CACHE WITH MAX_STALENESS '500ms' FROM q_0123456789ABCDEF"
+            );
+            assert!(res.name.is_none());
+            assert_eq!(res.max_staleness, Some(Duration::from_millis(500)));
+
+            let res = test_parse!(
+                create_cached_query(Dialect::MySQL),
+                b"CREATE CACHE FROM SELECT id FROM users WHERE name = ?"
+            );
+            assert!(res.max_staleness.is_none());
+        }
+
+        #[test]
+        fn create_cached_query_with_invalid_max_staleness() {
+            let res = create_cached_query(Dialect::MySQL)(LocatedSpan::new(
+                b"CREATE CACHE WITH MAX_STALENESS 'soon' FROM SELECT id FROM users".as_slice(),
+            ));
+            assert!(res.is_err());
+        }
+
+        #[test]
+        fn display_create_cached_query_with_max_staleness() {
+            for (input, expected) in [
+                (
+                    "CREATE CACHE foo WITH MAX_STALENESS '2m' FROM SELECT id FROM users",
+                    "CREATE CACHE `foo` WITH MAX_STALENESS '120s' FROM SELECT `id` FROM `users`",
+                ),
+                (
+                    "CREATE CACHE WITH MAX_STALENESS '1500ms' FROM SELECT id FROM users",
+                    "CREATE CACHE WITH MAX_STALENESS '1500ms' FROM SELECT `id` FROM `users`",
+                ),
+            ] {
+                let stmt = test_parse!(create_cached_query(Dialect::MySQL), input.as_bytes());
+                assert_eq!(stmt.to_string(), expected);
+                assert_eq!(
+                    test_parse!(create_cached_query(Dialect::MySQL), expected.as_bytes()),
+                    stmt
+                );
+            }
+        }
+
         #[test]
         fn display_create_query_cache() {
             let stmt = test_parse!(
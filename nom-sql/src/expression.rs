@@ -8,7 +8,7 @@ use nom::branch::alt;
 use nom::bytes::complete::{tag, tag_no_case};
 use nom::character::complete::char;
 use nom::combinator::{complete, map, opt};
-use nom::multi::{many0, separated_list0};
+use nom::multi::{many0, separated_list0, separated_list1};
 use nom::sequence::{delimited, pair, preceded, terminated, tuple};
 use nom::Parser;
 use nom_locate::LocatedSpan;
@@ -19,11 +19,14 @@ use test_strategy::Arbitrary;
 use crate::case::case_when;
 use crate::common::{column_identifier_no_alias, function_expr, ws_sep_comma};
 use crate::literal::literal;
+use crate::order::order_type;
 use crate::select::nested_selection;
 use crate::set::{variable_scope_prefix, Variable};
 use crate::sql_type::{mysql_int_cast_targets, type_identifier};
 use crate::whitespace::{whitespace0, whitespace1};
-use crate::{Column, Dialect, Literal, NomSqlResult, SelectStatement, SqlIdentifier, SqlType};
+use crate::{
+    Column, Dialect, Literal, NomSqlResult, OrderType, SelectStatement, SqlIdentifier, SqlType,
+};
 
 /// Function call expressions
 #[derive(Clone, Debug, Eq, PartialEq, PartialOrd, Hash, Serialize, Deserialize)]
@@ -404,6 +407,14 @@ pub enum Expr {
 
     /// A variable reference
     Variable(Variable),
+
+    /// A window function call, eg `ROW_NUMBER() OVER (PARTITION BY x ORDER BY y DESC)`
+    #[from(ignore)]
+    WindowFunction {
+        function: FunctionExpr,
+        partition_by: Vec<Expr>,
+        order_by: Vec<(Expr, OrderType)>,
+    },
 }
 
 impl Display for Expr {
@@ -487,6 +498,30 @@ impl Display for Expr {
                 write!(f, "]")
             }
             Expr::Variable(var) => write!(f, "{}", var),
+            Expr::WindowFunction {
+                function,
+                partition_by,
+                order_by,
+            } => {
+                write!(f, "{} OVER (", function)?;
+                if !partition_by.is_empty() {
+                    write!(f, "PARTITION BY {}", partition_by.iter().join(", "))?;
+                    if !order_by.is_empty() {
+                        write!(f, " ")?;
+                    }
+                }
+                if !order_by.is_empty() {
+                    write!(
+                        f,
+                        "ORDER BY {}",
+                        order_by
+                            .iter()
+                            .map(|(expr, ot)| format!("{} {}", expr, ot))
+                            .join(", ")
+                    )?;
+                }
+                write!(f, ")")
+            }
         }
     }
 }
@@ -1081,6 +1116,61 @@ fn array_expr(dialect: Dialect) -> impl Fn(LocatedSpan<&[u8]>) -> NomSqlResult<&
     }
 }
 
+fn window_order_field(
+    dialect: Dialect,
+) -> impl Fn(LocatedSpan<&[u8]>) -> NomSqlResult<&[u8], (Expr, OrderType)> {
+    move |i| {
+        let (i, expr) = expression(dialect)(i)?;
+        let (i, ord_typ) = opt(preceded(whitespace1, order_type))(i)?;
+        Ok((i, (expr, ord_typ.unwrap_or(OrderType::OrderAscending))))
+    }
+}
+
+/// Parse a window function call, of the form `func(args) OVER ([PARTITION BY exprs] [ORDER BY
+/// exprs])`
+fn window_function(dialect: Dialect) -> impl Fn(LocatedSpan<&[u8]>) -> NomSqlResult<&[u8], Expr> {
+    move |i| {
+        let (i, function) = function_expr(dialect)(i)?;
+        let (i, _) = whitespace0(i)?;
+        let (i, _) = tag_no_case("over")(i)?;
+        let (i, _) = whitespace0(i)?;
+        let (i, _) = char('(')(i)?;
+        let (i, _) = whitespace0(i)?;
+
+        let (i, partition_by) = opt(preceded(
+            tuple((
+                tag_no_case("partition"),
+                whitespace1,
+                tag_no_case("by"),
+                whitespace1,
+            )),
+            separated_list1(ws_sep_comma, expression(dialect)),
+        ))(i)?;
+        let (i, _) = whitespace0(i)?;
+
+        let (i, order_by) = opt(preceded(
+            tuple((
+                tag_no_case("order"),
+                whitespace1,
+                tag_no_case("by"),
+                whitespace1,
+            )),
+            separated_list1(ws_sep_comma, window_order_field(dialect)),
+        ))(i)?;
+        let (i, _) = whitespace0(i)?;
+        let (i, _) = char(')')(i)?;
+
+        Ok((
+            i,
+            Expr::WindowFunction {
+                function,
+                partition_by: partition_by.unwrap_or_default(),
+                order_by: order_by.unwrap_or_default(),
+            },
+        ))
+    }
+}
+
 // Expressions without (binary or unary) operators
 pub(crate) fn simple_expr(
     dialect: Dialect,
@@ -1092,6 +1182,7 @@ pub(crate) fn simple_expr(
             exists_expr(dialect),
             between_expr(dialect),
             in_expr(dialect),
+            window_function(dialect),
             map(function_expr(dialect), Expr::Call),
             map(literal(dialect), Expr::Literal),
             case_when(dialect),
@@ -1819,6 +1910,60 @@ mod tests {
         }
     }
 
+    mod window_functions {
+        use super::*;
+        use crate::to_nom_result;
+
+        #[test]
+        fn row_number_partition_and_order() {
+            let qs = b"row_number() over (partition by x order by y desc, z)";
+            let expected = Expr::WindowFunction {
+                function: FunctionExpr::Call {
+                    name: "row_number".into(),
+                    arguments: vec![],
+                },
+                partition_by: vec![Expr::Column("x".into())],
+                order_by: vec![
+                    (Expr::Column("y".into()), OrderType::OrderDescending),
+                    (Expr::Column("z".into()), OrderType::OrderAscending),
+                ],
+            };
+            let (remaining, result) =
+                to_nom_result(expression(Dialect::MySQL)(LocatedSpan::new(qs))).unwrap();
+            assert_eq!(std::str::from_utf8(remaining).unwrap(), "");
+            assert_eq!(result, expected);
+        }
+
+        #[test]
+        fn sum_over_empty_window() {
+            let qs = b"sum(x) OVER ()";
+            let expected = Expr::WindowFunction {
+                function: FunctionExpr::Sum {
+                    expr: Box::new(Expr::Column("x".into())),
+                    distinct: false,
+                },
+                partition_by: vec![],
+                order_by: vec![],
+            };
+            let (remaining, result) =
+                to_nom_result(expression(Dialect::PostgreSQL)(LocatedSpan::new(qs))).unwrap();
+            assert_eq!(std::str::from_utf8(remaining).unwrap(), "");
+            assert_eq!(result, expected);
+        }
+
+        #[test]
+        fn display_round_trip() {
+            let qs = "lag(x, 2) OVER (PARTITION BY y ORDER BY z DESC)";
+            let (_, result) =
+                to_nom_result(expression(Dialect::MySQL)(LocatedSpan::new(qs.as_bytes()))).unwrap();
+            let (_, round_tripped) = to_nom_result(expression(Dialect::MySQL)(LocatedSpan::new(
+                result.to_string().as_bytes(),
+            )))
+            .unwrap();
+            assert_eq!(round_tripped, result);
+        }
+    }
+
     mod negation {
         use super::*;
         use crate::to_nom_result;
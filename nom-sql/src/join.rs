@@ -45,6 +45,12 @@ pub enum JoinOperator {
     LeftOuterJoin,
     #[weight(0)]
     RightJoin,
+    #[weight(0)]
+    RightOuterJoin,
+    #[weight(0)]
+    FullJoin,
+    #[weight(0)]
+    FullOuterJoin,
     InnerJoin,
     #[weight(0)]
     CrossJoin,
@@ -65,6 +71,9 @@ impl fmt::Display for JoinOperator {
             JoinOperator::LeftJoin => write!(f, "LEFT JOIN")?,
             JoinOperator::LeftOuterJoin => write!(f, "LEFT OUTER JOIN")?,
             JoinOperator::RightJoin => write!(f, "RIGHT JOIN")?,
+            JoinOperator::RightOuterJoin => write!(f, "RIGHT OUTER JOIN")?,
+            JoinOperator::FullJoin => write!(f, "FULL JOIN")?,
+            JoinOperator::FullOuterJoin => write!(f, "FULL OUTER JOIN")?,
             JoinOperator::InnerJoin => write!(f, "INNER JOIN")?,
             JoinOperator::CrossJoin => write!(f, "CROSS JOIN")?,
             JoinOperator::StraightJoin => write!(f, "STRAIGHT JOIN")?,
@@ -108,6 +117,13 @@ pub fn join_operator(i: LocatedSpan<&[u8]>) -> NomSqlResult<&[u8], JoinOperator>
             JoinOperator::LeftOuterJoin
         }),
         map(tag_no_case("right join"), |_| JoinOperator::RightJoin),
+        map(tag_no_case("right outer join"), |_| {
+            JoinOperator::RightOuterJoin
+        }),
+        map(tag_no_case("full join"), |_| JoinOperator::FullJoin),
+        map(tag_no_case("full outer join"), |_| {
+            JoinOperator::FullOuterJoin
+        }),
         map(tag_no_case("inner join"), |_| JoinOperator::InnerJoin),
         map(tag_no_case("cross join"), |_| JoinOperator::CrossJoin),
         map(tag_no_case("straight_join"), |_| JoinOperator::StraightJoin),
@@ -150,4 +166,25 @@ mod tests {
         assert_eq!(q, expected_stmt);
         assert_eq!(expected, q.to_string());
     }
+
+    #[test]
+    fn outer_joins() {
+        for (input, operator) in [
+            ("RIGHT JOIN", JoinOperator::RightJoin),
+            ("RIGHT OUTER JOIN", JoinOperator::RightOuterJoin),
+            ("FULL JOIN", JoinOperator::FullJoin),
+            ("full outer join", JoinOperator::FullOuterJoin),
+        ] {
+            let qstring = format!("SELECT * FROM a {} b ON (a.x = b.y)", input);
+            let res = selection(Dialect::PostgreSQL)(LocatedSpan::new(qstring.as_bytes()));
+            let q = res.unwrap().1;
+            assert_eq!(q.tables, vec![TableExpr::from(Relation::from("a"))]);
+            assert_eq!(q.join.len(), 1);
+            assert_eq!(q.join[0].operator, operator);
+            assert_eq!(
+                q.join[0].right,
+                JoinRightSide::Table(TableExpr::from(Relation::from("b")))
+            );
+        }
+    }
 }
@@ -0,0 +1,505 @@
+//! Password authentication of frontends, using either a cleartext password, an [MD5 hash][md5] of
+//! the password, or [SCRAM-SHA-256][scram].
+//!
+//! Passwords can be stored by a [`Backend`](crate::Backend) either in plaintext, or as a salted
+//! verifier in the same format as PostgreSQL stores them in `pg_authid.rolpassword`:
+//!
+//! * `md5<hex>`, where `<hex>` is the hex-encoded MD5 hash of the password followed by the username
+//! * `SCRAM-SHA-256$<iterations>:<salt>$<StoredKey>:<ServerKey>`, where `<salt>`, `<StoredKey>` and
+//!   `<ServerKey>` are base64-encoded, as described in [RFC 5803][rfc5803]
+//!
+//! Since a verifier can only be used to check the kind of proof it was created for, the method
+//! used to authenticate a user depends on both the method requested by the backend and how that
+//! user's password is stored (see [`AuthenticationStep::start`]).
+//!
+//! [md5]: https://www.postgresql.org/docs/current/auth-password.html
+//! [scram]: https://www.postgresql.org/docs/current/sasl-authentication.html
+//! [rfc5803]: https://datatracker.ietf.org/doc/html/rfc5803
+
+use std::borrow::Cow;
+use std::str;
+
+use hmac::{Hmac, Mac, NewMac};
+use md5::Md5;
+use rand::Rng;
+use sha2::{Digest, Sha256};
+use subtle::ConstantTimeEq;
+
+use crate::error::Error;
+use crate::{Backend, CredentialsNeeded};
+
+/// The name of the (currently only) supported SASL mechanism
+pub(crate) const SCRAM_SHA_256: &str = "SCRAM-SHA-256";
+
+/// The number of iterations used when computing a SCRAM-SHA-256 verifier for a password stored in
+/// plaintext. This matches the default used by PostgreSQL.
+const SCRAM_ITERATIONS: u32 = 4096;
+
+/// The length, in bytes, of the salts we generate for SCRAM-SHA-256 verifiers
+const SCRAM_SALT_LENGTH: usize = 16;
+
+/// The length, in bytes, of the random nonce we add to the client's nonce in a SCRAM exchange
+const SCRAM_NONCE_LENGTH: usize = 18;
+
+const MD5_PREFIX: &str = "md5";
+const SCRAM_SHA_256_PREFIX: &str = "SCRAM-SHA-256$";
+
+/// A user's password as stored by a [`Backend`](crate::Backend)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum StoredPassword {
+    /// The password in plaintext
+    Plaintext(String),
+    /// The hex-encoded MD5 hash of the password followed by the username
+    Md5(String),
+    /// A SCRAM-SHA-256 verifier for the password
+    ScramSha256(ScramSha256Verifier),
+}
+
+impl StoredPassword {
+    /// Interprets a password returned by [`Backend::password_for_user`](crate::Backend), treating
+    /// it as plaintext unless it's a verifier in one of the formats described in the module
+    /// documentation.
+    pub(crate) fn parse(password: &str) -> Self {
+        if let Some(hash) = password.strip_prefix(MD5_PREFIX) {
+            if hash.len() == 32 && hash.chars().all(|c| c.is_ascii_hexdigit()) {
+                return StoredPassword::Md5(hash.to_ascii_lowercase());
+            }
+        }
+        if let Some(verifier) = ScramSha256Verifier::parse(password) {
+            return StoredPassword::ScramSha256(verifier);
+        }
+        StoredPassword::Plaintext(password.to_owned())
+    }
+
+    /// Looks up the stored password of `user` from `backend`, if the user exists
+    pub(crate) fn for_user<B: Backend>(backend: &B, user: &str) -> Option<Self> {
+        backend
+            .password_for_user(user)
+            .map(|password| Self::parse(&password))
+    }
+
+    /// Returns whether `password`, sent by the frontend in cleartext, is the password of `user`
+    pub(crate) fn matches_cleartext(&self, user: &str, password: &str) -> bool {
+        match self {
+            StoredPassword::Plaintext(stored) => {
+                stored.as_bytes().ct_eq(password.as_bytes()).into()
+            }
+            StoredPassword::Md5(hash) => hash
+                .as_bytes()
+                .ct_eq(md5_hex(&[password.as_bytes(), user.as_bytes()]).as_bytes())
+                .into(),
+            StoredPassword::ScramSha256(verifier) => verifier.matches_password(password),
+        }
+    }
+
+    /// Returns whether `response`, sent by the frontend in response to an MD5 password challenge
+    /// with the given `salt`, proves that it knows the password of `user`.
+    pub(crate) fn matches_md5(&self, user: &str, salt: &[u8; 4], response: &str) -> bool {
+        let hash = match self {
+            StoredPassword::Plaintext(password) => md5_hex(&[password.as_bytes(), user.as_bytes()]),
+            StoredPassword::Md5(hash) => hash.clone(),
+            StoredPassword::ScramSha256(_) => return false,
+        };
+        let expected = format!("{}{}", MD5_PREFIX, md5_hex(&[hash.as_bytes(), salt]));
+        expected.as_bytes().ct_eq(response.as_bytes()).into()
+    }
+}
+
+/// A verifier for a password, from which a server can check a frontend's SCRAM-SHA-256 proof
+/// without knowing the password itself
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct ScramSha256Verifier {
+    iterations: u32,
+    salt: Vec<u8>,
+    stored_key: Vec<u8>,
+    server_key: Vec<u8>,
+}
+
+impl ScramSha256Verifier {
+    /// Computes the verifier for `password` with the given `salt` and number of `iterations`
+    pub(crate) fn new(password: &str, salt: Vec<u8>, iterations: u32) -> Self {
+        let salted_password = hi(saslprep(password).as_bytes(), &salt, iterations);
+        let client_key = hmac(&salted_password, b"Client Key");
+        ScramSha256Verifier {
+            iterations,
+            salt,
+            stored_key: Sha256::digest(&client_key).to_vec(),
+            server_key: hmac(&salted_password, b"Server Key"),
+        }
+    }
+
+    /// Computes the verifier for `password` with a random salt
+    pub(crate) fn generate(password: &str) -> Self {
+        Self::new(password, random_bytes(SCRAM_SALT_LENGTH), SCRAM_ITERATIONS)
+    }
+
+    /// Returns a verifier which no proof will match, used to carry out an exchange with a frontend
+    /// claiming to be a user that doesn't exist (or whose password can't be checked with SCRAM)
+    /// without revealing that fact until the exchange is complete.
+    pub(crate) fn mock() -> Self {
+        ScramSha256Verifier {
+            iterations: SCRAM_ITERATIONS,
+            salt: random_bytes(SCRAM_SALT_LENGTH),
+            stored_key: random_bytes(32),
+            server_key: random_bytes(32),
+        }
+    }
+
+    /// Parses a verifier in the format `SCRAM-SHA-256$<iterations>:<salt>$<StoredKey>:<ServerKey>`
+    fn parse(s: &str) -> Option<Self> {
+        let (iterations_and_salt, keys) = s.strip_prefix(SCRAM_SHA_256_PREFIX)?.split_once('$')?;
+        let (iterations, salt) = iterations_and_salt.split_once(':')?;
+        let (stored_key, server_key) = keys.split_once(':')?;
+        Some(ScramSha256Verifier {
+            iterations: iterations.parse().ok()?,
+            salt: base64::decode(salt).ok()?,
+            stored_key: base64::decode(stored_key).ok()?,
+            server_key: base64::decode(server_key).ok()?,
+        })
+    }
+
+    fn matches_password(&self, password: &str) -> bool {
+        let verifier = Self::new(password, self.salt.clone(), self.iterations);
+        verifier.stored_key.ct_eq(&self.stored_key).into()
+    }
+}
+
+/// The server side of an in-progress [SCRAM-SHA-256][rfc7677] exchange with a frontend, after the
+/// server has sent its first message.
+///
+/// [rfc7677]: https://datatracker.ietf.org/doc/html/rfc7677
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct ScramSha256Exchange {
+    verifier: ScramSha256Verifier,
+    /// The GS2 header sent by the client, which it must echo back (base64-encoded) in its final
+    /// message
+    gs2_header: String,
+    client_first_message_bare: String,
+    server_first_message: String,
+    /// The client's nonce followed by our own
+    nonce: String,
+}
+
+impl ScramSha256Exchange {
+    /// Begins an exchange given the client-first-message sent by the frontend in its
+    /// `SASLInitialResponse`, returning the exchange along with the server-first-message to send
+    /// back.
+    pub(crate) fn start(
+        verifier: ScramSha256Verifier,
+        client_first_message: &[u8],
+    ) -> Result<(Self, String), Error> {
+        let client_first_message = str::from_utf8(client_first_message)
+            .map_err(|_| invalid_message("client-first-message is not valid UTF-8"))?;
+
+        // client-first-message = gs2-cbind-flag "," [authzid] "," client-first-message-bare
+        let (cbind_flag, rest) = client_first_message
+            .split_once(',')
+            .ok_or_else(|| invalid_message("missing GS2 header"))?;
+        match cbind_flag {
+            // The client either doesn't support channel binding, or thinks we don't (since we
+            // never advertise SCRAM-SHA-256-PLUS)
+            "n" | "y" => {}
+            _ if cbind_flag.starts_with("p=") => {
+                return Err(Error::Unsupported("SCRAM channel binding".to_string()))
+            }
+            _ => return Err(invalid_message("invalid channel binding flag")),
+        }
+        let (authzid, client_first_message_bare) = rest
+            .split_once(',')
+            .ok_or_else(|| invalid_message("missing GS2 header"))?;
+        if !authzid.is_empty() {
+            return Err(Error::Unsupported(
+                "SCRAM authorization identity".to_string(),
+            ));
+        }
+
+        // client-first-message-bare = [reserved-mext ","] username "," nonce ["," extensions]
+        //
+        // Like PostgreSQL, we ignore the username here in favor of the one sent in the startup
+        // message
+        if client_first_message_bare.starts_with("m=") {
+            return Err(Error::Unsupported("SCRAM mandatory extensions".to_string()));
+        }
+        let client_nonce = client_first_message_bare
+            .split(',')
+            .find_map(|attr| attr.strip_prefix("r="))
+            .filter(|nonce| !nonce.is_empty())
+            .ok_or_else(|| invalid_message("missing nonce"))?;
+
+        let nonce = format!(
+            "{}{}",
+            client_nonce,
+            base64::encode(random_bytes(SCRAM_NONCE_LENGTH))
+        );
+        let server_first_message = format!(
+            "r={},s={},i={}",
+            nonce,
+            base64::encode(&verifier.salt),
+            verifier.iterations
+        );
+
+        Ok((
+            ScramSha256Exchange {
+                verifier,
+                gs2_header: format!("{},{},", cbind_flag, authzid),
+                client_first_message_bare: client_first_message_bare.to_owned(),
+                server_first_message: server_first_message.clone(),
+                nonce,
+            },
+            server_first_message,
+        ))
+    }
+
+    /// Completes the exchange given the client-final-message sent by the frontend in its
+    /// `SASLResponse`. If the client's proof is valid, returns the server-final-message to send
+    /// back, which proves to the client that we know its password's verifier. Otherwise, returns
+    /// [`None`].
+    pub(crate) fn finish(&self, client_final_message: &[u8]) -> Result<Option<String>, Error> {
+        let client_final_message = str::from_utf8(client_final_message)
+            .map_err(|_| invalid_message("client-final-message is not valid UTF-8"))?;
+
+        // client-final-message = client-final-message-without-proof "," proof
+        let (without_proof, proof) = client_final_message
+            .rsplit_once(",p=")
+            .ok_or_else(|| invalid_message("missing proof"))?;
+        let proof = base64::decode(proof).map_err(|_| invalid_message("invalid proof"))?;
+
+        let mut attrs = without_proof.split(',');
+        let channel_binding = attrs
+            .next()
+            .and_then(|attr| attr.strip_prefix("c="))
+            .ok_or_else(|| invalid_message("missing channel binding"))?;
+        if channel_binding != base64::encode(&self.gs2_header) {
+            return Err(invalid_message("unexpected channel binding"));
+        }
+        let nonce = attrs
+            .next()
+            .and_then(|attr| attr.strip_prefix("r="))
+            .ok_or_else(|| invalid_message("missing nonce"))?;
+        if nonce != self.nonce {
+            return Err(invalid_message("nonce does not match"));
+        }
+
+        let auth_message = format!(
+            "{},{},{}",
+            self.client_first_message_bare, self.server_first_message, without_proof
+        );
+
+        // ClientKey = ClientProof XOR HMAC(StoredKey, AuthMessage)
+        let client_signature = hmac(&self.verifier.stored_key, auth_message.as_bytes());
+        if proof.len() != client_signature.len() {
+            return Ok(None);
+        }
+        let client_key = proof
+            .iter()
+            .zip(client_signature)
+            .map(|(p, s)| p ^ s)
+            .collect::<Vec<_>>();
+        if !bool::from(Sha256::digest(&client_key).ct_eq(&self.verifier.stored_key[..])) {
+            return Ok(None);
+        }
+
+        let server_signature = hmac(&self.verifier.server_key, auth_message.as_bytes());
+        Ok(Some(format!("v={}", base64::encode(server_signature))))
+    }
+}
+
+/// The point a frontend has reached in authenticating
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum AuthenticationStep {
+    /// Waiting for the frontend to send its password in cleartext
+    Cleartext,
+    /// Waiting for the frontend to send the MD5 hash of its password, salted with `salt`
+    Md5 { salt: [u8; 4] },
+    /// Waiting for the frontend to choose a SASL mechanism and send its first SCRAM message
+    SaslInitial,
+    /// Waiting for the frontend to send its final SCRAM message
+    SaslContinue(Box<ScramSha256Exchange>),
+}
+
+impl AuthenticationStep {
+    /// Returns the first step of authenticating a user whose password is stored as `stored` (or
+    /// who doesn't exist, if [`None`]), given the authentication method requested by the backend.
+    ///
+    /// A cleartext password can be checked against any stored password, but a frontend that
+    /// only knows a verifier can only be authenticated with the method the verifier was created
+    /// for, so that's the method used in that case.
+    pub(crate) fn start(requested: CredentialsNeeded, stored: Option<&StoredPassword>) -> Self {
+        match (requested, stored) {
+            (CredentialsNeeded::Cleartext, _) => AuthenticationStep::Cleartext,
+            (_, Some(StoredPassword::ScramSha256(_))) => AuthenticationStep::SaslInitial,
+            (_, Some(StoredPassword::Md5(_))) | (CredentialsNeeded::Md5, _) => {
+                AuthenticationStep::Md5 {
+                    salt: rand::thread_rng().gen(),
+                }
+            }
+            _ => AuthenticationStep::SaslInitial,
+        }
+    }
+}
+
+fn invalid_message(reason: &str) -> Error {
+    Error::InvalidSaslMessage(reason.to_string())
+}
+
+/// Normalizes a password with SASLprep, as PostgreSQL does before computing SCRAM verifiers.
+/// Passwords which can't be normalized are used as-is.
+fn saslprep(password: &str) -> Cow<'_, str> {
+    stringprep::saslprep(password).unwrap_or(Cow::Borrowed(password))
+}
+
+/// The `Hi` function from [RFC 5802](https://datatracker.ietf.org/doc/html/rfc5802#section-2.2),
+/// which is PBKDF2 with HMAC-SHA-256 as the pseudorandom function
+fn hi(password: &[u8], salt: &[u8], iterations: u32) -> Vec<u8> {
+    let mut mac = Hmac::<Sha256>::new_from_slice(password).expect("HMAC accepts keys of any size");
+    mac.update(salt);
+    mac.update(&[0, 0, 0, 1]);
+    let mut prev = mac.finalize().into_bytes();
+    let mut res = prev;
+
+    for _ in 1..iterations {
+        let mut mac =
+            Hmac::<Sha256>::new_from_slice(password).expect("HMAC accepts keys of any size");
+        mac.update(&prev);
+        prev = mac.finalize().into_bytes();
+        res.iter_mut().zip(prev.iter()).for_each(|(r, p)| *r ^= p);
+    }
+
+    res.to_vec()
+}
+
+fn hmac(key: &[u8], message: &[u8]) -> Vec<u8> {
+    let mut mac = Hmac::<Sha256>::new_from_slice(key).expect("HMAC accepts keys of any size");
+    mac.update(message);
+    mac.finalize().into_bytes().to_vec()
+}
+
+/// Returns the hex-encoded MD5 hash of the concatenation of `inputs`
+fn md5_hex(inputs: &[&[u8]]) -> String {
+    let mut hasher = Md5::new();
+    inputs.iter().for_each(|input| hasher.update(input));
+    hex::encode(hasher.finalize())
+}
+
+fn random_bytes(len: usize) -> Vec<u8> {
+    let mut rng = rand::thread_rng();
+    (0..len).map(|_| rng.gen()).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use postgres_protocol::authentication::md5_hash;
+    use postgres_protocol::authentication::sasl::{ChannelBinding, ScramSha256};
+
+    use super::*;
+
+    /// Runs a SCRAM exchange between a client that knows `client_password` and a server with the
+    /// given verifier, returning whether both sides authenticated each other
+    fn scram_exchange(verifier: ScramSha256Verifier, client_password: &str) -> bool {
+        let mut client =
+            ScramSha256::new(client_password.as_bytes(), ChannelBinding::unsupported());
+        let (exchange, server_first_message) =
+            ScramSha256Exchange::start(verifier, client.message()).unwrap();
+        client.update(server_first_message.as_bytes()).unwrap();
+        match exchange.finish(client.message()).unwrap() {
+            Some(server_final_message) => {
+                client.finish(server_final_message.as_bytes()).unwrap();
+                true
+            }
+            None => false,
+        }
+    }
+
+    #[test]
+    fn scram_with_plaintext_password() {
+        let verifier = ScramSha256Verifier::generate("password");
+        assert!(scram_exchange(verifier.clone(), "password"));
+        assert!(!scram_exchange(verifier, "wrong password"));
+    }
+
+    #[test]
+    fn scram_with_stored_verifier() {
+        let stored = postgres_protocol::password::scram_sha_256(b"password");
+        let verifier = match StoredPassword::parse(&stored) {
+            StoredPassword::ScramSha256(verifier) => verifier,
+            p => panic!("Expected SCRAM-SHA-256 verifier, got {:?}", p),
+        };
+        assert!(scram_exchange(verifier.clone(), "password"));
+        assert!(!scram_exchange(verifier, "wrong password"));
+    }
+
+    #[test]
+    fn scram_with_mock_verifier() {
+        assert!(!scram_exchange(ScramSha256Verifier::mock(), "password"));
+    }
+
+    #[test]
+    fn scram_rejects_mismatched_nonce() {
+        let mut client = ScramSha256::new(b"password", ChannelBinding::unsupported());
+        let (exchange, _) =
+            ScramSha256Exchange::start(ScramSha256Verifier::generate("password"), client.message())
+                .unwrap();
+        assert!(exchange.finish(b"c=biws,r=nonce,p=cHJvb2Y=").is_err());
+    }
+
+    #[test]
+    fn md5_with_stored_passwords() {
+        let salt = [1, 2, 3, 4];
+        let response = md5_hash(b"user", b"password", salt);
+
+        let plaintext = StoredPassword::parse("password");
+        assert_eq!(plaintext, StoredPassword::Plaintext("password".to_owned()));
+        assert!(plaintext.matches_md5("user", &salt, &response));
+        assert!(!plaintext.matches_md5("user", &[4, 3, 2, 1], &response));
+
+        let hashed = StoredPassword::parse(&postgres_protocol::password::md5(b"password", "user"));
+        assert!(matches!(hashed, StoredPassword::Md5(_)));
+        assert!(hashed.matches_md5("user", &salt, &response));
+        assert!(!hashed.matches_md5("other_user", &salt, &response));
+    }
+
+    #[test]
+    fn cleartext_with_stored_verifiers() {
+        for stored in [
+            "password".to_owned(),
+            postgres_protocol::password::md5(b"password", "user"),
+            postgres_protocol::password::scram_sha_256(b"password"),
+        ] {
+            let stored = StoredPassword::parse(&stored);
+            assert!(stored.matches_cleartext("user", "password"));
+            assert!(!stored.matches_cleartext("user", "wrong password"));
+        }
+    }
+
+    #[test]
+    fn start_uses_method_supported_by_stored_password() {
+        let plaintext = StoredPassword::Plaintext("password".to_owned());
+        let md5 = StoredPassword::parse(&postgres_protocol::password::md5(b"password", "user"));
+        let scram = StoredPassword::parse(&postgres_protocol::password::scram_sha_256(b"password"));
+
+        assert_eq!(
+            AuthenticationStep::start(CredentialsNeeded::ScramSha256, Some(&plaintext)),
+            AuthenticationStep::SaslInitial
+        );
+        assert!(matches!(
+            AuthenticationStep::start(CredentialsNeeded::ScramSha256, Some(&md5)),
+            AuthenticationStep::Md5 { .. }
+        ));
+        assert!(matches!(
+            AuthenticationStep::start(CredentialsNeeded::Md5, Some(&plaintext)),
+            AuthenticationStep::Md5 { .. }
+        ));
+        assert_eq!(
+            AuthenticationStep::start(CredentialsNeeded::Md5, Some(&scram)),
+            AuthenticationStep::SaslInitial
+        );
+        assert_eq!(
+            AuthenticationStep::start(CredentialsNeeded::Cleartext, Some(&scram)),
+            AuthenticationStep::Cleartext
+        );
+        assert_eq!(
+            AuthenticationStep::start(CredentialsNeeded::ScramSha256, None),
+            AuthenticationStep::SaslInitial
+        );
+    }
+}
@@ -5,7 +5,7 @@ use postgres_types::Type;
 use tokio::io::{AsyncRead, AsyncWrite};
 use tokio_util::codec::Framed;
 
-use crate::codec::{Codec, DecodeError, EncodeError};
+use crate::codec::{AuthenticationMessageKind, Codec, DecodeError, EncodeError};
 use crate::error::Error;
 use crate::message::FrontendMessage;
 use crate::response::Response;
@@ -44,6 +44,12 @@ where
         self.0.codec_mut().set_start_up_complete();
     }
 
+    /// Set the kind of authentication message the frontend is expected to send next, which
+    /// determines how it will be parsed.
+    pub fn set_authentication_message_kind(&mut self, kind: AuthenticationMessageKind) {
+        self.0.codec_mut().set_authentication_message_kind(kind);
+    }
+
     /// Set the data types of a prepared statement's parameters. These data types must be set
     /// before the data values within a `FrontendMessage::Bind` message referencing the named
     /// pepared statement can be parsed.
@@ -77,4 +83,21 @@ where
     pub async fn flush(&mut self) -> Result<(), EncodeError> {
         self.0.flush().await
     }
+
+    /// Consume the channel, returning the underlying byte stream. Used to upgrade the connection to
+    /// TLS after an `SSLRequest` has been accepted.
+    ///
+    /// Returns an error if the frontend has sent any data we haven't processed yet, since the
+    /// frontend must wait for our response before starting the TLS handshake. Anything it sent
+    /// before that would otherwise be processed as if it had been sent over the encrypted
+    /// connection.
+    pub fn into_inner(self) -> Result<C, Error> {
+        let parts = self.0.into_parts();
+        if !parts.read_buf.is_empty() {
+            return Err(Error::Unsupported(
+                "received unencrypted data after SSL request".to_string(),
+            ));
+        }
+        Ok(parts.io)
+    }
 }
@@ -1,28 +1,30 @@
 use std::borrow::Borrow;
 use std::convert::{TryFrom, TryInto};
-use std::net::IpAddr;
-use std::str;
+use std::iter::Peekable;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+use std::str::{self, Chars};
 
 use bit_vec::BitVec;
 use bytes::{Buf, Bytes, BytesMut};
 use chrono::{DateTime, FixedOffset, NaiveDate, NaiveDateTime, NaiveTime};
 use eui48::MacAddress;
-use postgres_types::{FromSql, Kind, Type};
+use postgres_protocol::types::{array_to_sql, ArrayDimension};
+use postgres_types::{FromSql, IsNull, Kind, ToSql, Type};
 use readyset_data::Array;
-use rust_decimal::prelude::FromStr;
-use rust_decimal::Decimal;
+use rust_decimal::prelude::{FromStr, ToPrimitive};
+use rust_decimal::{Decimal, RoundingStrategy};
 use tokio_util::codec::Decoder;
 use uuid::Uuid;
 
 use crate::bytes::BytesStr;
 use crate::codec::error::DecodeError as Error;
 use crate::codec::error::DecodeError::InvalidTextByteArrayValue;
-use crate::codec::{Codec, DecodeError};
+use crate::codec::{AuthenticationMessageKind, Codec, DecodeError};
 use crate::error::Error as BackendError;
 use crate::message::FrontendMessage::{self, *};
 use crate::message::StatementName::*;
 use crate::message::TransferFormat::{self, *};
-use crate::value::Value;
+use crate::value::{Interval, IpNetwork, Range, RangeBound, Value};
 
 const ID_AUTHENTICATE: u8 = b'p';
 const ID_BIND: u8 = b'B';
@@ -41,6 +43,7 @@ const CLOSE_TYPE_PREPARED_STATEMENT: u8 = b'S';
 const DESCRIBE_TYPE_PORTAL: u8 = b'P';
 const DESCRIBE_TYPE_PREPARED_STATEMENT: u8 = b'S';
 
+const CANCEL_REQUEST_CODE: i32 = 80877102;
 const SSL_REQUEST_CODE: i32 = 80877103;
 
 const STARTUP_MESSAGE_DATABASE_PARAMETER: &str = "database";
@@ -51,6 +54,14 @@ const BOOL_TRUE_TEXT_REP: &str = "t";
 const HEADER_LENGTH: usize = 5;
 const LENGTH_NULL_SENTINEL: i32 = -1;
 const NUL_BYTE: u8 = b'\0';
+const PGSQL_AF_INET: u8 = 2;
+const PGSQL_AF_INET6: u8 = 3;
+const RANGE_EMPTY: u8 = 0x01;
+const RANGE_LOWER_INCLUSIVE: u8 = 0x02;
+const RANGE_UPPER_INCLUSIVE: u8 = 0x04;
+const RANGE_LOWER_UNBOUNDED: u8 = 0x08;
+const RANGE_UPPER_UNBOUNDED: u8 = 0x10;
+const RANGE_TEXT_EMPTY: &str = "empty";
 const TIMESTAMP_FORMAT: &str = "%Y-%m-%d %H:%M:%S%.f";
 const TIMESTAMP_TZ_FORMAT: &str = "%Y-%m-%d %H:%M:%S%.f %:z";
 
@@ -95,6 +106,11 @@ impl<R: IntoIterator<Item: TryInto<Value, Error = BackendError>>> Decoder for Co
             let ret = match token {
                 SSL_REQUEST_CODE => Ok(Some(SSLRequest)),
 
+                CANCEL_REQUEST_CODE => Ok(Some(CancelRequest {
+                    process_id: get_i32(msg)?,
+                    secret_key: get_i32(msg)?,
+                })),
+
                 // Parse StartupMessage
                 protocol_version => {
                     let mut user: Option<BytesStr> = None;
@@ -130,9 +146,31 @@ impl<R: IntoIterator<Item: TryInto<Value, Error = BackendError>>> Decoder for Co
         let _length = get_i32(msg)?;
 
         let ret = match id {
-            ID_AUTHENTICATE => Ok(Some(PasswordMessage {
-                password: get_str(msg)?,
-            })),
+            ID_AUTHENTICATE => match self.authentication_message_kind {
+                AuthenticationMessageKind::Password => Ok(Some(PasswordMessage {
+                    password: get_str(msg)?,
+                })),
+                AuthenticationMessageKind::SaslInitialResponse => {
+                    let sasl_mechanism = get_str(msg)?;
+                    let sasl_data = match get_i32(msg)? {
+                        LENGTH_NULL_SENTINEL => Bytes::new(),
+                        len => {
+                            let len = usize::try_from(len)?;
+                            if msg.remaining() < len {
+                                return Err(Error::UnexpectedMessageEnd);
+                            }
+                            msg.split_to(len)
+                        }
+                    };
+                    Ok(Some(SASLInitialResponse {
+                        sasl_mechanism,
+                        sasl_data,
+                    }))
+                }
+                AuthenticationMessageKind::SaslResponse => Ok(Some(SASLResponse {
+                    sasl_data: msg.split_off(0),
+                })),
+            },
 
             ID_BIND => {
                 let portal_name = get_str(msg)?;
@@ -280,6 +318,14 @@ fn get_i32(src: &mut Bytes) -> Result<i32, Error> {
     }
 }
 
+fn get_i64(src: &mut Bytes) -> Result<i64, Error> {
+    if src.remaining() >= 8 {
+        Ok(src.get_i64())
+    } else {
+        Err(Error::UnexpectedMessageEnd)
+    }
+}
+
 fn get_str(src: &mut Bytes) -> Result<BytesStr, Error> {
     let nul_pos = src
         .iter()
@@ -310,10 +356,16 @@ fn get_binary_value(src: &mut Bytes, t: &Type) -> Result<Value, Error> {
         return Ok(Value::Null);
     }
 
-    let buf = &mut src.split_to(usize::try_from(len)?);
+    decode_binary_value(&mut src.split_to(usize::try_from(len)?), t)
+}
 
+/// Decodes a value of type `t` in the binary format, which makes up the whole of `buf`
+pub(super) fn decode_binary_value(buf: &mut Bytes, t: &Type) -> Result<Value, Error> {
     match t.kind() {
-        Kind::Array(member_type) => Ok(Value::Array(Array::from_sql(t, buf)?, member_type.clone())),
+        Kind::Array(_) => Ok(Value::Array(Array::from_sql(t, buf)?, t.clone())),
+        Kind::Range(member_type) => {
+            Ok(Value::Range(get_binary_range(buf, member_type)?, t.clone()))
+        }
         Kind::Enum(_) => Ok(Value::Text(str::from_utf8(buf)?.into())),
         _ => match *t {
             // Postgres does not allow interior 0 bytes, even though it is valid UTF-8
@@ -338,14 +390,17 @@ fn get_binary_value(src: &mut Bytes, t: &Type) -> Result<Value, Error> {
             Type::TIMESTAMPTZ => Ok(Value::TimestampTz(DateTime::<FixedOffset>::from_sql(
                 t, buf,
             )?)),
+            Type::INTERVAL => Ok(Value::Interval(get_binary_interval(buf)?)),
             Type::BYTEA => Ok(Value::ByteArray(<Vec<u8>>::from_sql(t, buf)?)),
             Type::MACADDR => Ok(Value::MacAddress(MacAddress::from_sql(t, buf)?)),
-            Type::INET => Ok(Value::Inet(IpAddr::from_sql(t, buf)?)),
+            Type::INET => Ok(Value::Inet(get_binary_network(buf)?)),
+            Type::CIDR => Ok(Value::Cidr(get_binary_network(buf)?)),
             Type::UUID => Ok(Value::Uuid(Uuid::from_sql(t, buf)?)),
             Type::JSON => Ok(Value::Json(serde_json::Value::from_sql(t, buf)?)),
             Type::JSONB => Ok(Value::Jsonb(serde_json::Value::from_sql(t, buf)?)),
             Type::BIT => Ok(Value::Bit(BitVec::from_sql(t, buf)?)),
             Type::VARBIT => Ok(Value::VarBit(BitVec::from_sql(t, buf)?)),
+            Type::MONEY => Ok(Value::Money(get_i64(buf)?)),
             _ => Ok(Value::PassThrough(readyset_data::PassThrough {
                 ty: t.clone(),
                 data: buf.to_vec().into_boxed_slice(),
@@ -354,6 +409,62 @@ fn get_binary_value(src: &mut Bytes, t: &Type) -> Result<Value, Error> {
     }
 }
 
+fn get_binary_interval(buf: &mut Bytes) -> Result<Interval, Error> {
+    let microseconds = get_i64(buf)?;
+    let days = get_i32(buf)?;
+    let months = get_i32(buf)?;
+    Ok(Interval {
+        months,
+        days,
+        microseconds,
+    })
+}
+
+fn get_binary_network(buf: &mut Bytes) -> Result<IpNetwork, Error> {
+    let family = get_u8(buf)?;
+    let netmask = get_u8(buf)?;
+    let _is_cidr = get_u8(buf)?;
+    let len = get_u8(buf)?;
+    let addr = match (family, len) {
+        (PGSQL_AF_INET, 4) if buf.remaining() >= 4 => {
+            let mut octets = [0; 4];
+            buf.copy_to_slice(&mut octets);
+            IpAddr::V4(Ipv4Addr::from(octets))
+        }
+        (PGSQL_AF_INET6, 16) if buf.remaining() >= 16 => {
+            let mut octets = [0; 16];
+            buf.copy_to_slice(&mut octets);
+            IpAddr::V6(Ipv6Addr::from(octets))
+        }
+        _ => return Err(Error::UnexpectedValue(family)),
+    };
+    Ok(IpNetwork { addr, netmask })
+}
+
+/// Decodes a range in the binary format, which consists of a byte of flags followed by each of the
+/// bounds that are present, in the binary format of `member_type` and prefixed with their length.
+fn get_binary_range(buf: &mut Bytes, member_type: &Type) -> Result<Range, Error> {
+    let flags = get_u8(buf)?;
+    if flags & RANGE_EMPTY != 0 {
+        return Ok(Range::Empty);
+    }
+
+    let mut get_bound = |inclusive, unbounded| -> Result<RangeBound, Error> {
+        if flags & unbounded != 0 {
+            return Ok(RangeBound::Unbounded);
+        }
+        let value = Box::new(get_binary_value(buf, member_type)?);
+        if flags & inclusive != 0 {
+            Ok(RangeBound::Inclusive(value))
+        } else {
+            Ok(RangeBound::Exclusive(value))
+        }
+    };
+    let lower = get_bound(RANGE_LOWER_INCLUSIVE, RANGE_LOWER_UNBOUNDED)?;
+    let upper = get_bound(RANGE_UPPER_INCLUSIVE, RANGE_UPPER_UNBOUNDED)?;
+    Ok(Range::NonEmpty { lower, upper })
+}
+
 fn get_bitvec_from_str(bit_str: &str) -> Result<BitVec, Error> {
     let mut bits = BitVec::with_capacity(bit_str.len());
     for c in bit_str.chars() {
@@ -374,6 +485,28 @@ fn get_text_value(src: &mut Bytes, t: &Type) -> Result<Value, Error> {
 
     let text = BytesStr::try_from(src.split_to(usize::try_from(len)?))?;
     let text_str: &str = text.borrow();
+    parse_text_value(text_str, t)
+}
+
+/// Parses the text representation of a value of type `t`
+fn parse_text_value(text_str: &str, t: &Type) -> Result<Value, Error> {
+    match t.kind() {
+        Kind::Array(member_type) => {
+            return Ok(Value::Array(
+                parse_text_array(text_str, t, member_type)?,
+                t.clone(),
+            ))
+        }
+        Kind::Range(member_type) => {
+            return Ok(Value::Range(
+                parse_text_range(text_str, member_type)?,
+                t.clone(),
+            ))
+        }
+        Kind::Enum(_) => return Ok(Value::Text(text_str.into())),
+        _ => {}
+    }
+
     match *t {
         Type::BOOL => Ok(Value::Bool(text_str == BOOL_TRUE_TEXT_REP)),
         Type::VARCHAR => Ok(Value::VarChar(text_str.into())),
@@ -409,13 +542,12 @@ fn get_text_value(src: &mut Bytes, t: &Type) -> Result<Value, Error> {
             let bytes = hex::decode(text_str).map_err(InvalidTextByteArrayValue)?;
             Ok(Value::ByteArray(bytes))
         }
+        Type::INTERVAL => parse_text_interval(text_str).map(Value::Interval),
         Type::MACADDR => MacAddress::parse_str(text_str)
             .map_err(DecodeError::InvalidTextMacAddressValue)
             .map(Value::MacAddress),
-        Type::INET => text_str
-            .parse::<IpAddr>()
-            .map_err(DecodeError::InvalidTextIpAddressValue)
-            .map(Value::Inet),
+        Type::INET => text_str.parse::<IpNetwork>().map(Value::Inet),
+        Type::CIDR => text_str.parse::<IpNetwork>().map(Value::Cidr),
         Type::UUID => Uuid::parse_str(text_str)
             .map_err(DecodeError::InvalidTextUuidValue)
             .map(Value::Uuid),
@@ -427,10 +559,398 @@ fn get_text_value(src: &mut Bytes, t: &Type) -> Result<Value, Error> {
             .map(Value::Jsonb),
         Type::BIT => get_bitvec_from_str(text_str).map(Value::Bit),
         Type::VARBIT => get_bitvec_from_str(text_str).map(Value::VarBit),
+        Type::MONEY => parse_text_money(text_str).map(Value::Money),
         _ => Err(Error::UnsupportedType(t.clone())),
     }
 }
 
+/// Parses an interval in the format PostgreSQL outputs them in with the default `IntervalStyle`,
+/// such as `1 year 2 mons -3 days +04:05:06.7`, or more generally, a sequence of quantities
+/// followed by their units, and times of day. A trailing `ago` negates the interval.
+fn parse_text_interval(text: &str) -> Result<Interval, Error> {
+    let err = || Error::InvalidTextIntervalValue(text.to_owned());
+
+    // The fractional parts of each field are carried over into the next smaller one
+    let mut months = 0f64;
+    let mut days = 0f64;
+    let mut microseconds = 0f64;
+    let mut negate = false;
+
+    let mut tokens = text.split_whitespace().peekable();
+    if tokens.peek() == Some(&"@") {
+        tokens.next();
+    }
+    while let Some(token) = tokens.next() {
+        if negate {
+            // `ago` has to come last
+            return Err(err());
+        }
+        if token.eq_ignore_ascii_case("ago") {
+            negate = true;
+            continue;
+        }
+
+        if token.contains(':') {
+            let (sign, time) = match token.strip_prefix('-') {
+                Some(time) => (-1.0, time),
+                None => (1.0, token.strip_prefix('+').unwrap_or(token)),
+            };
+            let mut parts = time.split(':');
+            let mut next_part = || -> Result<f64, Error> {
+                parts
+                    .next()
+                    .map_or(Ok(0.0), |p| p.parse::<f64>().map_err(|_| err()))
+            };
+            let hours = next_part()?;
+            let minutes = next_part()?;
+            let seconds = next_part()?;
+            if parts.next().is_some() {
+                return Err(err());
+            }
+            microseconds += sign * ((hours * 60.0 + minutes) * 60.0 + seconds) * 1_000_000.0;
+            continue;
+        }
+
+        let unit_start = token
+            .find(|c: char| c.is_ascii_alphabetic())
+            .unwrap_or(token.len());
+        let (quantity, unit) = token.split_at(unit_start);
+        let quantity = quantity.parse::<f64>().map_err(|_| err())?;
+        let unit = if !unit.is_empty() {
+            unit
+        } else {
+            // A quantity without a unit is a number of seconds
+            match tokens.peek() {
+                Some(unit) if !unit.eq_ignore_ascii_case("ago") && !unit.contains(':') => {
+                    tokens.next().unwrap_or_default()
+                }
+                _ => "s",
+            }
+        };
+        match unit.to_ascii_lowercase().as_str() {
+            "y" | "yr" | "yrs" | "year" | "years" => months += quantity * 12.0,
+            "mon" | "mons" | "month" | "months" => months += quantity,
+            "w" | "week" | "weeks" => days += quantity * 7.0,
+            "d" | "day" | "days" => days += quantity,
+            "h" | "hr" | "hrs" | "hour" | "hours" => microseconds += quantity * 3_600_000_000.0,
+            "m" | "min" | "mins" | "minute" | "minutes" => microseconds += quantity * 60_000_000.0,
+            "s" | "sec" | "secs" | "second" | "seconds" => microseconds += quantity * 1_000_000.0,
+            "ms" | "msec" | "msecs" | "millisecond" | "milliseconds" => {
+                microseconds += quantity * 1_000.0
+            }
+            "us" | "usec" | "usecs" | "microsecond" | "microseconds" => microseconds += quantity,
+            _ => return Err(err()),
+        }
+    }
+
+    days += months.fract() * 30.0;
+    microseconds += days.fract() * 86_400_000_000.0;
+    let sign = if negate { -1.0 } else { 1.0 };
+    let months = sign * months.trunc();
+    let days = sign * days.trunc();
+    let microseconds = sign * microseconds.round();
+    if months.abs() > i32::MAX as f64
+        || days.abs() > i32::MAX as f64
+        || microseconds.abs() > i64::MAX as f64
+    {
+        return Err(err());
+    }
+    Ok(Interval {
+        months: months as i32,
+        days: days as i32,
+        microseconds: microseconds as i64,
+    })
+}
+
+/// Parses an amount of money such as `$1,234.56` or `-$0.99` into a number of cents. Amounts with
+/// more than two decimal places are rounded.
+fn parse_text_money(text: &str) -> Result<i64, Error> {
+    let err = || Error::InvalidTextMoneyValue(text.to_owned());
+
+    let mut amount = text.trim();
+    let mut negative = false;
+    if let Some(inner) = amount.strip_prefix('(').and_then(|a| a.strip_suffix(')')) {
+        negative = true;
+        amount = inner.trim();
+    }
+    if let Some(rest) = amount.strip_prefix('-') {
+        negative = true;
+        amount = rest.trim_start();
+    }
+    if let Some(rest) = amount.strip_prefix('$') {
+        amount = rest.trim_start();
+    }
+    if let Some(rest) = amount.strip_prefix('-') {
+        negative = true;
+        amount = rest;
+    }
+
+    let digits = amount.replace(',', "");
+    let cents = (Decimal::from_str(&digits).map_err(|_| err())? * Decimal::from(100))
+        .round_dp_with_strategy(0, RoundingStrategy::MidpointAwayFromZero)
+        .to_i64()
+        .ok_or_else(err)?;
+    Ok(if negative { -cents } else { cents })
+}
+
+/// Parses an array such as `{1,2,3}`, `{{"a",NULL},{"b","c"}}` or `[0:1]={4,5}` whose elements
+/// are of type `member_type`.
+fn parse_text_array(text: &str, array_type: &Type, member_type: &Type) -> Result<Array, Error> {
+    let err = || Error::InvalidTextArrayValue(text.to_owned());
+
+    // Explicit bounds for each dimension, if provided
+    let mut bounds = vec![];
+    let mut rest = text.trim_start();
+    while let Some(after) = rest.strip_prefix('[') {
+        let (dimension, after) = after.split_once(']').ok_or_else(err)?;
+        let (lower, upper) = dimension.split_once(':').ok_or_else(err)?;
+        let lower = lower.trim().parse::<i32>().map_err(|_| err())?;
+        let upper = upper.trim().parse::<i32>().map_err(|_| err())?;
+        bounds.push((lower, upper));
+        rest = after.trim_start();
+    }
+    if !bounds.is_empty() {
+        rest = rest.strip_prefix('=').ok_or_else(err)?;
+    }
+
+    let mut chars = rest.chars().peekable();
+    let mut lens = vec![];
+    let mut elements = vec![];
+    parse_text_array_level(&mut chars, 0, &mut lens, &mut None, &mut elements).ok_or_else(err)?;
+    if chars.any(|c| !c.is_whitespace()) {
+        return Err(err());
+    }
+
+    // PostgreSQL represents empty arrays as having no dimensions at all
+    let lens = if elements.is_empty() {
+        vec![]
+    } else {
+        lens.into_iter()
+            .collect::<Option<Vec<i32>>>()
+            .ok_or_else(err)?
+    };
+    let dimensions = if bounds.is_empty() {
+        lens.iter()
+            .map(|len| ArrayDimension {
+                len: *len,
+                lower_bound: 1,
+            })
+            .collect::<Vec<_>>()
+    } else {
+        if bounds.len() != lens.len()
+            || bounds
+                .iter()
+                .zip(&lens)
+                .any(|((lower, upper), len)| upper - lower + 1 != *len)
+        {
+            return Err(err());
+        }
+        bounds
+            .iter()
+            .zip(&lens)
+            .map(|((lower, _), len)| ArrayDimension {
+                len: *len,
+                lower_bound: *lower,
+            })
+            .collect()
+    };
+
+    let values = elements
+        .into_iter()
+        .map(|element| match element {
+            Some(element) => parse_text_value(&element, member_type),
+            None => Ok(Value::Null),
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+
+    // Build the array in the binary format, so that its elements are converted in the same way as
+    // those of arrays received in the binary format
+    let mut buf = BytesMut::new();
+    array_to_sql(
+        dimensions,
+        member_type.oid(),
+        values,
+        |value, buf| match value.to_sql(member_type, buf)? {
+            IsNull::No => Ok(postgres_protocol::IsNull::No),
+            IsNull::Yes => Ok(postgres_protocol::IsNull::Yes),
+        },
+        &mut buf,
+    )?;
+    Ok(Array::from_sql(array_type, &buf)?)
+}
+
+/// Parses one level of a (possibly nested) text array, recording the number of elements at that
+/// level in `lens` and the elements themselves in `elements`. Returns `None` if the array is
+/// malformed, or if its sub-arrays don't all have the same dimensions.
+fn parse_text_array_level(
+    chars: &mut Peekable<Chars>,
+    depth: usize,
+    lens: &mut Vec<Option<i32>>,
+    element_depth: &mut Option<usize>,
+    elements: &mut Vec<Option<String>>,
+) -> Option<()> {
+    skip_whitespace(chars);
+    if chars.next()? != '{' {
+        return None;
+    }
+
+    let mut len = 0;
+    skip_whitespace(chars);
+    if chars.peek() == Some(&'}') {
+        chars.next();
+    } else {
+        loop {
+            skip_whitespace(chars);
+            if chars.peek() == Some(&'{') {
+                parse_text_array_level(chars, depth + 1, lens, element_depth, elements)?;
+            } else {
+                // Elements can only appear in the innermost sub-arrays
+                if *element_depth.get_or_insert(depth) != depth {
+                    return None;
+                }
+                elements.push(parse_text_array_element(chars)?);
+            }
+            len += 1;
+
+            skip_whitespace(chars);
+            match chars.next()? {
+                ',' => {}
+                '}' => break,
+                _ => return None,
+            }
+        }
+    }
+
+    if lens.len() <= depth {
+        lens.resize(depth + 1, None);
+    }
+    match lens[depth] {
+        Some(expected) if expected != len => None,
+        _ => {
+            lens[depth] = Some(len);
+            Some(())
+        }
+    }
+}
+
+/// Parses a single, possibly quoted, array element. Returns `Some(None)` for `NULL`.
+fn parse_text_array_element(chars: &mut Peekable<Chars>) -> Option<Option<String>> {
+    let mut element = String::new();
+    if chars.peek() == Some(&'"') {
+        chars.next();
+        loop {
+            match chars.next()? {
+                '"' => break,
+                '\\' => element.push(chars.next()?),
+                c => element.push(c),
+            }
+        }
+        return Some(Some(element));
+    }
+
+    while let Some(&c) = chars.peek() {
+        if c == ',' || c == '}' {
+            break;
+        }
+        chars.next();
+        if c == '\\' {
+            element.push(chars.next()?);
+        } else {
+            element.push(c);
+        }
+    }
+    let element = element.trim_end();
+    if element.is_empty() {
+        None
+    } else if element.eq_ignore_ascii_case("NULL") {
+        Some(None)
+    } else {
+        Some(Some(element.to_owned()))
+    }
+}
+
+fn skip_whitespace(chars: &mut Peekable<Chars>) {
+    while chars.next_if(|c| c.is_whitespace()).is_some() {}
+}
+
+/// Parses a range such as `[1,10)`, `(,"2020-01-01")` or `empty` whose bounds are of type
+/// `member_type`.
+fn parse_text_range(text: &str, member_type: &Type) -> Result<Range, Error> {
+    let err = || Error::InvalidTextRangeValue(text.to_owned());
+
+    let text = text.trim();
+    if text.eq_ignore_ascii_case(RANGE_TEXT_EMPTY) {
+        return Ok(Range::Empty);
+    }
+
+    let mut chars = text.chars().peekable();
+    let lower_inclusive = match chars.next() {
+        Some('[') => true,
+        Some('(') => false,
+        _ => return Err(err()),
+    };
+    let lower = parse_text_range_bound(&mut chars).ok_or_else(err)?;
+    if chars.next() != Some(',') {
+        return Err(err());
+    }
+    let upper = parse_text_range_bound(&mut chars).ok_or_else(err)?;
+    let upper_inclusive = match chars.next() {
+        Some(']') => true,
+        Some(')') => false,
+        _ => return Err(err()),
+    };
+    if chars.next().is_some() {
+        return Err(err());
+    }
+
+    let make_bound = |bound: Option<String>, inclusive| -> Result<RangeBound, Error> {
+        let value = match bound {
+            Some(bound) => Box::new(parse_text_value(&bound, member_type)?),
+            None => return Ok(RangeBound::Unbounded),
+        };
+        if inclusive {
+            Ok(RangeBound::Inclusive(value))
+        } else {
+            Ok(RangeBound::Exclusive(value))
+        }
+    };
+    Ok(Range::NonEmpty {
+        lower: make_bound(lower, lower_inclusive)?,
+        upper: make_bound(upper, upper_inclusive)?,
+    })
+}
+
+/// Parses one bound of a text range, stopping at the `,` or closing bracket that follows it.
+/// Returns `Some(None)` if the bound is omitted, which means that the range is unbounded.
+fn parse_text_range_bound(chars: &mut Peekable<Chars>) -> Option<Option<String>> {
+    if matches!(chars.peek(), Some(',' | ')' | ']')) {
+        return Some(None);
+    }
+
+    let mut bound = String::new();
+    let mut in_quotes = false;
+    while let Some(&c) = chars.peek() {
+        if !in_quotes && matches!(c, ',' | ')' | ']') {
+            break;
+        }
+        chars.next();
+        match c {
+            '\\' => bound.push(chars.next()?),
+            // Within quotes, a doubled quote stands for a literal one
+            '"' if in_quotes && chars.peek() == Some(&'"') => {
+                chars.next();
+                bound.push('"');
+            }
+            '"' => in_quotes = !in_quotes,
+            c => bound.push(c),
+        }
+    }
+    if in_quotes {
+        return None;
+    }
+    Some(Some(bound))
+}
+
 #[cfg(test)]
 mod tests {
 
@@ -475,6 +995,33 @@ mod tests {
         codec.decode(&mut buf).unwrap_err();
     }
 
+    #[test]
+    fn test_decode_cancel_request() {
+        let mut codec = Codec::<Vec<Value>>::new();
+        let mut buf = BytesMut::new();
+        buf.put_i32(16); // size
+        buf.put_i32(80877102); // cancel request code
+        buf.put_i32(42); // process id
+        buf.put_i32(-7); // secret key
+        assert_eq!(
+            codec.decode(&mut buf).unwrap(),
+            Some(CancelRequest {
+                process_id: 42,
+                secret_key: -7
+            })
+        );
+    }
+
+    #[test]
+    fn test_decode_cancel_request_missing_key() {
+        let mut codec = Codec::<Vec<Value>>::new();
+        let mut buf = BytesMut::new();
+        buf.put_i32(12); // size
+        buf.put_i32(80877102); // cancel request code
+        buf.put_i32(42); // process id
+        codec.decode(&mut buf).unwrap_err();
+    }
+
     #[test]
     fn test_decode_startup_message() {
         let mut codec = Codec::<Vec<Value>>::new();
@@ -546,6 +1093,59 @@ mod tests {
         assert!(buf.capacity() >= 8);
     }
 
+    #[test]
+    fn test_decode_password_message() {
+        let mut codec = Codec::<Vec<Value>>::new();
+        codec.set_start_up_complete();
+        let mut buf = BytesMut::new();
+        buf.put_u8(b'p'); // message id
+        buf.put_i32(4 + 9); // size
+        buf.put(&b"password\0"[..]);
+        assert_eq!(
+            codec.decode(&mut buf).unwrap().unwrap(),
+            PasswordMessage {
+                password: bytes_str("password")
+            }
+        );
+    }
+
+    #[test]
+    fn test_decode_sasl_initial_response() {
+        let mut codec = Codec::<Vec<Value>>::new();
+        codec.set_start_up_complete();
+        codec.set_authentication_message_kind(AuthenticationMessageKind::SaslInitialResponse);
+        let mut buf = BytesMut::new();
+        buf.put_u8(b'p'); // message id
+        buf.put_i32(4 + 14 + 4 + 12); // size
+        buf.put(&b"SCRAM-SHA-256\0"[..]);
+        buf.put_i32(12); // sasl data length
+        buf.put(&b"n,,n=,r=abcd"[..]);
+        assert_eq!(
+            codec.decode(&mut buf).unwrap().unwrap(),
+            SASLInitialResponse {
+                sasl_mechanism: bytes_str("SCRAM-SHA-256"),
+                sasl_data: Bytes::from_static(b"n,,n=,r=abcd"),
+            }
+        );
+    }
+
+    #[test]
+    fn test_decode_sasl_response() {
+        let mut codec = Codec::<Vec<Value>>::new();
+        codec.set_start_up_complete();
+        codec.set_authentication_message_kind(AuthenticationMessageKind::SaslResponse);
+        let mut buf = BytesMut::new();
+        buf.put_u8(b'p'); // message id
+        buf.put_i32(4 + 13); // size
+        buf.put(&b"c=biws,r=abcd"[..]);
+        assert_eq!(
+            codec.decode(&mut buf).unwrap().unwrap(),
+            SASLResponse {
+                sasl_data: Bytes::from_static(b"c=biws,r=abcd"),
+            }
+        );
+    }
+
     #[test]
     fn test_decode_regular_partial_header() {
         let mut codec = Codec::<Vec<Value>>::new();
@@ -1431,4 +2031,178 @@ mod tests {
             DataValue::TimestampTz(expected)
         );
     }
+
+    fn text_value(text: &str) -> Bytes {
+        let mut buf = BytesMut::new();
+        buf.put_i32(text.len() as i32);
+        buf.extend_from_slice(text.as_bytes());
+        buf.freeze()
+    }
+
+    #[test]
+    fn test_decode_binary_interval() {
+        let mut buf = BytesMut::new();
+        buf.put_i32(16);
+        buf.put_i64(4_000_000);
+        buf.put_i32(3);
+        buf.put_i32(14);
+        assert_eq!(
+            get_binary_value(&mut buf.freeze(), &Type::INTERVAL).unwrap(),
+            DataValue::Interval(Interval {
+                months: 14,
+                days: 3,
+                microseconds: 4_000_000
+            })
+        );
+    }
+
+    #[test]
+    fn test_decode_binary_inet() {
+        let mut buf = BytesMut::new();
+        buf.put_i32(8);
+        buf.put_u8(2); // family
+        buf.put_u8(24); // netmask
+        buf.put_u8(0); // is_cidr
+        buf.put_u8(4); // address length
+        buf.extend_from_slice(&[192, 168, 0, 1]);
+        assert_eq!(
+            get_binary_value(&mut buf.freeze(), &Type::INET).unwrap(),
+            DataValue::Inet(IpNetwork {
+                addr: "192.168.0.1".parse().unwrap(),
+                netmask: 24
+            })
+        );
+    }
+
+    #[test]
+    fn test_decode_binary_range() {
+        let mut buf = BytesMut::new();
+        buf.put_i32(13);
+        buf.put_u8(RANGE_UPPER_UNBOUNDED);
+        buf.put_i32(8);
+        buf.put_i64(5);
+        assert_eq!(
+            get_binary_value(&mut buf.freeze(), &Type::INT8_RANGE).unwrap(),
+            DataValue::Range(
+                Range::NonEmpty {
+                    lower: RangeBound::Exclusive(Box::new(DataValue::BigInt(5))),
+                    upper: RangeBound::Unbounded,
+                },
+                Type::INT8_RANGE
+            )
+        );
+    }
+
+    #[test]
+    fn test_decode_text_interval() {
+        assert_eq!(
+            get_text_value(
+                &mut text_value("1 year 2 mons -3 days +04:05:06.7"),
+                &Type::INTERVAL
+            )
+            .unwrap(),
+            DataValue::Interval(Interval {
+                months: 14,
+                days: -3,
+                microseconds: 14_706_700_000
+            })
+        );
+        assert_eq!(
+            get_text_value(&mut text_value("@ 1 hour ago"), &Type::INTERVAL).unwrap(),
+            DataValue::Interval(Interval {
+                months: 0,
+                days: 0,
+                microseconds: -3_600_000_000
+            })
+        );
+        assert_eq!(
+            get_text_value(&mut text_value("1.5 days"), &Type::INTERVAL).unwrap(),
+            DataValue::Interval(Interval {
+                months: 0,
+                days: 1,
+                microseconds: 43_200_000_000
+            })
+        );
+        get_text_value(&mut text_value("1 fortnight"), &Type::INTERVAL).unwrap_err();
+    }
+
+    #[test]
+    fn test_decode_text_network() {
+        let addr = "10.1.2.3".parse().unwrap();
+        assert_eq!(
+            get_text_value(&mut text_value("10.1.2.3"), &Type::INET).unwrap(),
+            DataValue::Inet(IpNetwork { addr, netmask: 32 })
+        );
+        assert_eq!(
+            get_text_value(&mut text_value("10.1.2.3/16"), &Type::CIDR).unwrap(),
+            DataValue::Cidr(IpNetwork { addr, netmask: 16 })
+        );
+        get_text_value(&mut text_value("10.1.2.3/33"), &Type::INET).unwrap_err();
+    }
+
+    #[test]
+    fn test_decode_text_money() {
+        assert_eq!(
+            get_text_value(&mut text_value("-$1,234.56"), &Type::MONEY).unwrap(),
+            DataValue::Money(-123456)
+        );
+        assert_eq!(
+            get_text_value(&mut text_value("12.345"), &Type::MONEY).unwrap(),
+            DataValue::Money(1235)
+        );
+    }
+
+    #[test]
+    fn test_decode_text_range() {
+        assert_eq!(
+            get_text_value(&mut text_value("[1,10)"), &Type::INT4_RANGE).unwrap(),
+            DataValue::Range(
+                Range::NonEmpty {
+                    lower: RangeBound::Inclusive(Box::new(DataValue::Int(1))),
+                    upper: RangeBound::Exclusive(Box::new(DataValue::Int(10))),
+                },
+                Type::INT4_RANGE
+            )
+        );
+        assert_eq!(
+            get_text_value(&mut text_value("(\"1.5\",]"), &Type::NUM_RANGE).unwrap(),
+            DataValue::Range(
+                Range::NonEmpty {
+                    lower: RangeBound::Exclusive(Box::new(DataValue::Numeric(Decimal::new(15, 1)))),
+                    upper: RangeBound::Unbounded,
+                },
+                Type::NUM_RANGE
+            )
+        );
+        assert_eq!(
+            get_text_value(&mut text_value("empty"), &Type::INT4_RANGE).unwrap(),
+            DataValue::Range(Range::Empty, Type::INT4_RANGE)
+        );
+        get_text_value(&mut text_value("[1,10"), &Type::INT4_RANGE).unwrap_err();
+    }
+
+    #[test]
+    fn test_decode_text_array() {
+        assert_eq!(
+            get_text_value(&mut text_value("{1.5, NULL,\"2\"}"), &Type::NUMERIC_ARRAY).unwrap(),
+            DataValue::Array(
+                Array::from(vec![
+                    readyset_data::DfValue::from(Decimal::new(15, 1)),
+                    readyset_data::DfValue::None,
+                    readyset_data::DfValue::from(Decimal::new(2, 0)),
+                ]),
+                Type::NUMERIC_ARRAY
+            )
+        );
+        assert_eq!(
+            get_text_value(&mut text_value("{{1,2},{3,4}}"), &Type::INT4_ARRAY).unwrap(),
+            get_text_value(
+                &mut text_value("[1:2][1:2]={{1,2},{3,4}}"),
+                &Type::INT4_ARRAY
+            )
+            .unwrap()
+        );
+        get_text_value(&mut text_value("{{1,2},{3}}"), &Type::INT4_ARRAY).unwrap_err();
+        get_text_value(&mut text_value("{1,{2}}"), &Type::INT4_ARRAY).unwrap_err();
+    }
 }
@@ -1,10 +1,12 @@
 use std::convert::{TryFrom, TryInto};
+use std::net::IpAddr;
 
-use bytes::{BufMut, BytesMut};
+use bytes::{BufMut, Bytes, BytesMut};
 use eui48::MacAddressFormat;
-use postgres_types::{ToSql, Type};
+use postgres_types::{to_sql_checked, IsNull, ToSql, Type};
 use tokio_util::codec::Encoder;
 
+use crate::codec::decoder::decode_binary_value;
 use crate::codec::error::EncodeError as Error;
 use crate::codec::Codec;
 use crate::error::Error as BackendError;
@@ -12,23 +14,34 @@ use crate::message::BackendMessage::{self, *};
 use crate::message::CommandCompleteTag::*;
 use crate::message::ErrorSeverity;
 use crate::message::TransferFormat::{self, *};
-use crate::value::Value;
+use crate::value::{Interval, IpNetwork, Range, RangeBound, Value};
 
 const ID_AUTHENTICATION_REQUEST: u8 = b'R';
+const ID_BACKEND_KEY_DATA: u8 = b'K';
 const ID_BIND_COMPLETE: u8 = b'2';
 const ID_CLOSE_COMPLETE: u8 = b'3';
 const ID_COMMAND_COMPLETE: u8 = b'C';
+const ID_COPY_DATA: u8 = b'd';
+const ID_COPY_DONE: u8 = b'c';
+const ID_COPY_OUT_RESPONSE: u8 = b'H';
 const ID_DATA_ROW: u8 = b'D';
 const ID_ERROR_RESPONSE: u8 = b'E';
+const ID_NOTIFICATION_RESPONSE: u8 = b'A';
 const ID_PARAMETER_DESCRIPTION: u8 = b't';
 const ID_PARAMETER_STATUS: u8 = b'S';
 const ID_PARSE_COMPLETE: u8 = b'1';
+const ID_PORTAL_SUSPENDED: u8 = b's';
 const ID_READY_FOR_QUERY: u8 = b'Z';
 const ID_ROW_DESCRIPTION: u8 = b'T';
 
 const AUTHENTICATION_OK_SUCCESS: i32 = 0;
 const AUTHENTICATION_CLEARTEXT_REQUIRED: i32 = 3;
+const AUTHENTICATION_MD5_REQUIRED: i32 = 5;
+const AUTHENTICATION_SASL_REQUIRED: i32 = 10;
+const AUTHENTICATION_SASL_CONTINUE: i32 = 11;
+const AUTHENTICATION_SASL_FINAL: i32 = 12;
 
+const COMMAND_COMPLETE_COPY_TAG: &str = "COPY";
 const COMMAND_COMPLETE_DELETE_TAG: &str = "DELETE";
 const COMMAND_COMPLETE_INSERT_TAG: &str = "INSERT";
 const COMMAND_COMPLETE_INSERT_LEGACY_OID: &str = "0";
@@ -47,11 +60,21 @@ const ERROR_RESPONSE_TERMINATOR: u8 = b'\0';
 
 const BOOL_FALSE_TEXT_REP: &str = "f";
 const BOOL_TRUE_TEXT_REP: &str = "t";
+const COPY_TEXT_DELIMITER: u8 = b'\t';
+const COPY_TEXT_NULL: &[u8] = b"\\N";
 const COUNT_PLACEHOLDER: i16 = -1;
 const LENGTH_NULL_SENTINEL: i32 = -1;
 const LENGTH_PLACEHOLDER: i32 = -1;
 const NUL_BYTE: u8 = b'\0';
 const NUL_CHAR: char = '\0';
+const PGSQL_AF_INET: u8 = 2;
+const PGSQL_AF_INET6: u8 = 3;
+const RANGE_EMPTY: u8 = 0x01;
+const RANGE_LOWER_INCLUSIVE: u8 = 0x02;
+const RANGE_UPPER_INCLUSIVE: u8 = 0x04;
+const RANGE_LOWER_UNBOUNDED: u8 = 0x08;
+const RANGE_UPPER_UNBOUNDED: u8 = 0x10;
+const RANGE_TEXT_EMPTY: &str = "empty";
 const TIMESTAMP_FORMAT: &str = "%Y-%m-%d %H:%M:%S%.f";
 const TIMESTAMP_TZ_FORMAT: &str = "%Y-%m-%d %H:%M:%S%.f %:z";
 const TIME_FORMAT: &str = "%H:%M:%S%.f";
@@ -94,12 +117,53 @@ where
             put_i32(AUTHENTICATION_CLEARTEXT_REQUIRED, dst);
         }
 
+        AuthenticationMD5Password { salt } => {
+            put_u8(ID_AUTHENTICATION_REQUEST, dst);
+            put_i32(LENGTH_PLACEHOLDER, dst);
+            put_i32(AUTHENTICATION_MD5_REQUIRED, dst);
+            put_slice(&salt, dst);
+        }
+
         AuthenticationOk => {
             put_u8(ID_AUTHENTICATION_REQUEST, dst);
             put_i32(LENGTH_PLACEHOLDER, dst);
             put_i32(AUTHENTICATION_OK_SUCCESS, dst);
         }
 
+        AuthenticationSASL { allowed_mechanisms } => {
+            put_u8(ID_AUTHENTICATION_REQUEST, dst);
+            put_i32(LENGTH_PLACEHOLDER, dst);
+            put_i32(AUTHENTICATION_SASL_REQUIRED, dst);
+            for mechanism in allowed_mechanisms {
+                put_str(&mechanism, dst);
+            }
+            put_u8(NUL_BYTE, dst);
+        }
+
+        AuthenticationSASLContinue { sasl_data } => {
+            put_u8(ID_AUTHENTICATION_REQUEST, dst);
+            put_i32(LENGTH_PLACEHOLDER, dst);
+            put_i32(AUTHENTICATION_SASL_CONTINUE, dst);
+            put_slice(&sasl_data, dst);
+        }
+
+        AuthenticationSASLFinal { sasl_data } => {
+            put_u8(ID_AUTHENTICATION_REQUEST, dst);
+            put_i32(LENGTH_PLACEHOLDER, dst);
+            put_i32(AUTHENTICATION_SASL_FINAL, dst);
+            put_slice(&sasl_data, dst);
+        }
+
+        BackendKeyData {
+            process_id,
+            secret_key,
+        } => {
+            put_u8(ID_BACKEND_KEY_DATA, dst);
+            put_i32(LENGTH_PLACEHOLDER, dst);
+            put_i32(process_id, dst);
+            put_i32(secret_key, dst);
+        }
+
         BindComplete => {
             put_u8(ID_BIND_COMPLETE, dst);
             put_i32(LENGTH_PLACEHOLDER, dst);
@@ -116,6 +180,7 @@ where
             // Format command complete "tag" (eg "DELETE 5" to indicate 5 rows deleted).
             let mut tag_buf = [0u8; COMMAND_COMPLETE_TAG_BUF_LEN];
             match tag {
+                CopyOut(n) => write!(&mut tag_buf[..], "{} {}", COMMAND_COMPLETE_COPY_TAG, n)?,
                 Delete(n) => write!(&mut tag_buf[..], "{} {}", COMMAND_COMPLETE_DELETE_TAG, n)?,
                 Empty => {}
                 Insert(n) => write!(
@@ -140,6 +205,66 @@ where
             );
         }
 
+        CopyData { data } => {
+            put_u8(ID_COPY_DATA, dst);
+            put_i32(LENGTH_PLACEHOLDER, dst);
+            put_slice(&data, dst);
+        }
+
+        CopyDataRow { values, format } => {
+            put_u8(ID_COPY_DATA, dst);
+            put_i32(LENGTH_PLACEHOLDER, dst);
+            match format {
+                Binary => {
+                    put_i16(COUNT_PLACEHOLDER, dst);
+                    let mut n_values = 0;
+                    for v in values {
+                        let v = v
+                            .try_into()
+                            .map_err(|e| Error::InternalError(e.to_string()))?;
+                        put_binary_value(v, dst)?;
+                        n_values += 1;
+                    }
+                    set_i16(i16::try_from(n_values)?, dst, start_ofs + 5)?;
+                }
+                Text => {
+                    for (i, v) in values.into_iter().enumerate() {
+                        if i > 0 {
+                            put_u8(COPY_TEXT_DELIMITER, dst);
+                        }
+                        let v = v
+                            .try_into()
+                            .map_err(|e| Error::InternalError(e.to_string()))?;
+                        put_copy_text_value(v, dst)?;
+                    }
+                    put_u8(b'\n', dst);
+                }
+            }
+        }
+
+        CopyDone => {
+            put_u8(ID_COPY_DONE, dst);
+            put_i32(LENGTH_PLACEHOLDER, dst);
+        }
+
+        CopyOutResponse {
+            format,
+            column_formats,
+        } => {
+            put_u8(ID_COPY_OUT_RESPONSE, dst);
+            put_i32(LENGTH_PLACEHOLDER, dst);
+            // The overall format is sent as an Int8, unlike the format codes of each column
+            let format_code = match format {
+                Binary => 1,
+                Text => 0,
+            };
+            put_u8(format_code, dst);
+            put_i16(i16::try_from(column_formats.len())?, dst);
+            for f in column_formats {
+                put_format(f, dst);
+            }
+        }
+
         DataRow {
             values,
             explicit_transfer_formats,
@@ -205,6 +330,18 @@ where
             put_u8(ERROR_RESPONSE_TERMINATOR, dst);
         }
 
+        NotificationResponse {
+            process_id,
+            channel,
+            payload,
+        } => {
+            put_u8(ID_NOTIFICATION_RESPONSE, dst);
+            put_i32(LENGTH_PLACEHOLDER, dst);
+            put_i32(process_id, dst);
+            put_str(&channel, dst);
+            put_str(&payload, dst);
+        }
+
         ParameterDescription {
             parameter_data_types,
         } => {
@@ -231,6 +368,11 @@ where
             put_i32(LENGTH_PLACEHOLDER, dst);
         }
 
+        PortalSuspended => {
+            put_u8(ID_PORTAL_SUSPENDED, dst);
+            put_i32(LENGTH_PLACEHOLDER, dst);
+        }
+
         ReadyForQuery { status } => {
             put_u8(ID_READY_FOR_QUERY, dst);
             put_i32(LENGTH_PLACEHOLDER, dst);
@@ -305,6 +447,10 @@ fn put_i32(val: i32, dst: &mut BytesMut) {
     dst.put_i32(val);
 }
 
+fn put_i64(val: i64, dst: &mut BytesMut) {
+    dst.put_i64(val);
+}
+
 fn set_i32(val: i32, dst: &mut BytesMut, ofs: usize) -> Result<(), Error> {
     let mut window = dst
         .get_mut(ofs..ofs + 4)
@@ -398,14 +544,22 @@ fn put_binary_value(val: Value, dst: &mut BytesMut) -> Result<(), Error> {
         Value::Time(v) => {
             v.to_sql(&Type::TIME, dst)?;
         }
+        Value::Interval(v) => {
+            put_i64(v.microseconds, dst);
+            put_i32(v.days, dst);
+            put_i32(v.months, dst);
+        }
         Value::ByteArray(b) => {
             b.to_sql(&Type::BYTEA, dst)?;
         }
         Value::MacAddress(m) => {
             m.to_sql(&Type::MACADDR, dst)?;
         }
-        Value::Inet(ip) => {
-            ip.to_sql(&Type::INET, dst)?;
+        Value::Inet(n) => {
+            put_binary_network(n, false, dst);
+        }
+        Value::Cidr(n) => {
+            put_binary_network(n, true, dst);
         }
         Value::Uuid(u) => {
             u.to_sql(&Type::UUID, dst)?;
@@ -422,9 +576,15 @@ fn put_binary_value(val: Value, dst: &mut BytesMut) -> Result<(), Error> {
         Value::VarBit(bits) => {
             bits.to_sql(&Type::VARBIT, dst)?;
         }
+        Value::Money(m) => {
+            put_i64(m, dst);
+        }
         Value::Array(arr, ty) => {
             arr.to_sql(&ty, dst)?;
         }
+        Value::Range(range, _) => {
+            put_binary_range(range, dst)?;
+        }
         Value::PassThrough(p) => {
             dst.put(&p.data[..]);
         }
@@ -436,6 +596,105 @@ fn put_binary_value(val: Value, dst: &mut BytesMut) -> Result<(), Error> {
     Ok(())
 }
 
+fn put_binary_network(network: IpNetwork, is_cidr: bool, dst: &mut BytesMut) {
+    match network.addr {
+        IpAddr::V4(addr) => {
+            put_u8(PGSQL_AF_INET, dst);
+            put_u8(network.netmask, dst);
+            put_u8(is_cidr as u8, dst);
+            put_u8(4, dst);
+            put_slice(&addr.octets(), dst);
+        }
+        IpAddr::V6(addr) => {
+            put_u8(PGSQL_AF_INET6, dst);
+            put_u8(network.netmask, dst);
+            put_u8(is_cidr as u8, dst);
+            put_u8(16, dst);
+            put_slice(&addr.octets(), dst);
+        }
+    }
+}
+
+/// Write a range in the binary format, which consists of a byte of flags followed by each of the
+/// bounds that are present, in the binary format of the range's element type and prefixed with
+/// their length.
+fn put_binary_range(range: Range, dst: &mut BytesMut) -> Result<(), Error> {
+    let (lower, upper) = match range {
+        Range::Empty => {
+            put_u8(RANGE_EMPTY, dst);
+            return Ok(());
+        }
+        Range::NonEmpty { lower, upper } => (lower, upper),
+    };
+
+    let flags = |bound: &RangeBound, inclusive, unbounded| match bound {
+        RangeBound::Inclusive(_) => inclusive,
+        RangeBound::Exclusive(_) => 0,
+        RangeBound::Unbounded => unbounded,
+    };
+    put_u8(
+        flags(&lower, RANGE_LOWER_INCLUSIVE, RANGE_LOWER_UNBOUNDED)
+            | flags(&upper, RANGE_UPPER_INCLUSIVE, RANGE_UPPER_UNBOUNDED),
+        dst,
+    );
+    for bound in [lower, upper] {
+        if let RangeBound::Inclusive(value) | RangeBound::Exclusive(value) = bound {
+            put_binary_value(*value, dst)?;
+        }
+    }
+    Ok(())
+}
+
+/// Values are written in the binary format, without the length prefix they have in `DataRow`s, so
+/// that they can be used as the elements of arrays, or passed on to the upstream database.
+impl ToSql for Value {
+    fn to_sql(
+        &self,
+        _ty: &Type,
+        out: &mut BytesMut,
+    ) -> Result<IsNull, Box<dyn std::error::Error + Sync + Send>> {
+        if *self == Value::Null {
+            return Ok(IsNull::Yes);
+        }
+        let mut buf = BytesMut::new();
+        put_binary_value(self.clone(), &mut buf)?;
+        out.extend_from_slice(&buf[4..]);
+        Ok(IsNull::No)
+    }
+
+    fn accepts(_ty: &Type) -> bool {
+        true
+    }
+
+    to_sql_checked!();
+}
+
+/// Write a value in the text format used by `COPY`. This is the same as the regular text format,
+/// except that the value has no length prefix, NULL is represented as `\N`, and backslashes and
+/// control characters which would be mistaken for delimiters are escaped with a backslash.
+fn put_copy_text_value(val: Value, dst: &mut BytesMut) -> Result<(), Error> {
+    let mut buf = BytesMut::new();
+    put_text_value(val, &mut buf)?;
+    if buf.starts_with(&LENGTH_NULL_SENTINEL.to_be_bytes()) {
+        put_slice(COPY_TEXT_NULL, dst);
+        return Ok(());
+    }
+
+    for byte in buf.iter().skip(4) {
+        match byte {
+            b'\\' => put_slice(b"\\\\", dst),
+            b'\x08' => put_slice(b"\\b", dst),
+            b'\x0c' => put_slice(b"\\f", dst),
+            b'\n' => put_slice(b"\\n", dst),
+            b'\r' => put_slice(b"\\r", dst),
+            b'\t' => put_slice(b"\\t", dst),
+            b'\x0b' => put_slice(b"\\v", dst),
+            _ => put_u8(*byte, dst),
+        }
+    }
+    Ok(())
+}
+
 fn put_text_value(val: Value, dst: &mut BytesMut) -> Result<(), Error> {
     use std::fmt::Write;
 
@@ -446,6 +705,16 @@ fn put_text_value(val: Value, dst: &mut BytesMut) -> Result<(), Error> {
         return Ok(());
     }
 
+    // Values of types that we don't otherwise represent are passed through in the binary format,
+    // but we can still convert them to text if they're of a type that we know how to decode
+    if let Value::PassThrough(ref p) = val {
+        let decoded = decode_binary_value(&mut Bytes::copy_from_slice(&p.data), &p.ty)
+            .map_err(|e| Error::InternalError(e.to_string()))?;
+        if !matches!(decoded, Value::PassThrough(_)) {
+            return put_text_value(decoded, dst);
+        }
+    }
+
     let start_ofs = dst.len();
     put_i32(LENGTH_PLACEHOLDER, dst);
     match val {
@@ -506,6 +775,7 @@ fn put_text_value(val: Value, dst: &mut BytesMut) -> Result<(), Error> {
         Value::Time(v) => {
             write!(dst, "{}", v.format(TIME_FORMAT))?;
         }
+        Value::Interval(v) => put_text_interval(v, dst)?,
         Value::ByteArray(b) => {
             write!(
                 dst,
@@ -517,7 +787,9 @@ fn put_text_value(val: Value, dst: &mut BytesMut) -> Result<(), Error> {
             )?;
         }
         Value::MacAddress(m) => write!(dst, "{}", m.to_string(MacAddressFormat::HexString))?,
-        Value::Inet(ip) => write!(dst, "{}", ip)?,
+        Value::Inet(n) => write!(dst, "{}", n)?,
+        // Unlike for `inet`, the network mask of a `cidr` is always included
+        Value::Cidr(n) => write!(dst, "{}/{}", n.addr, n.netmask)?,
         Value::Uuid(u) => write!(dst, "{}", u)?,
         Value::Json(v) => write!(dst, "{}", v)?,
         Value::Jsonb(v) => write!(dst, "{}", v)?,
@@ -529,7 +801,9 @@ fn put_text_value(val: Value, dst: &mut BytesMut) -> Result<(), Error> {
                 .collect::<Vec<String>>()
                 .join("")
         )?,
+        Value::Money(m) => put_text_money(m, dst)?,
         Value::Array(arr, _) => write!(dst, "{}", arr)?,
+        Value::Range(range, _) => put_text_range(range, dst)?,
         Value::PassThrough(p) => {
             return Err(Error::InternalError(format!(
                 "Data of type {} unsupported in text mode",
@@ -544,6 +818,143 @@ fn put_text_value(val: Value, dst: &mut BytesMut) -> Result<(), Error> {
     Ok(())
 }
 
+/// Write an interval in the format used by PostgreSQL's default `IntervalStyle`, such as
+/// `1 year 2 mons -3 days +04:05:06.7`
+fn put_text_interval(interval: Interval, dst: &mut BytesMut) -> Result<(), Error> {
+    use std::fmt::Write;
+
+    let mut is_zero = true;
+    // Whether the last field written was negative, in which case the sign of the next field is
+    // written explicitly even if it is positive
+    let mut is_before = false;
+    for (value, unit) in [
+        (i64::from(interval.months / 12), "year"),
+        (i64::from(interval.months % 12), "mon"),
+        (i64::from(interval.days), "day"),
+    ] {
+        if value == 0 {
+            continue;
+        }
+        write!(
+            dst,
+            "{}{}{} {}{}",
+            if is_zero { "" } else { " " },
+            if is_before && value > 0 { "+" } else { "" },
+            value,
+            unit,
+            if value != 1 { "s" } else { "" }
+        )?;
+        is_before = value < 0;
+        is_zero = false;
+    }
+
+    let microseconds = interval.microseconds;
+    if is_zero || microseconds != 0 {
+        let abs = microseconds.unsigned_abs();
+        write!(
+            dst,
+            "{}{}{:02}:{:02}:{:02}",
+            if is_zero { "" } else { " " },
+            if microseconds < 0 {
+                "-"
+            } else if is_before {
+                "+"
+            } else {
+                ""
+            },
+            abs / 3_600_000_000,
+            abs / 60_000_000 % 60,
+            abs / 1_000_000 % 60
+        )?;
+        let fraction = abs % 1_000_000;
+        if fraction != 0 {
+            write!(dst, ".{}", format!("{:06}", fraction).trim_end_matches('0'))?;
+        }
+    }
+    Ok(())
+}
+
+/// Write an amount of money, given in cents, such as `-$1,234.56`
+fn put_text_money(cents: i64, dst: &mut BytesMut) -> Result<(), Error> {
+    use std::fmt::Write;
+
+    let abs = cents.unsigned_abs();
+    let units = (abs / 100).to_string();
+    let mut grouped = String::with_capacity(units.len() + units.len() / 3);
+    for (i, digit) in units.chars().enumerate() {
+        if i != 0 && (units.len() - i) % 3 == 0 {
+            grouped.push(',');
+        }
+        grouped.push(digit);
+    }
+    write!(
+        dst,
+        "{}${}.{:02}",
+        if cents < 0 { "-" } else { "" },
+        grouped,
+        abs % 100
+    )?;
+    Ok(())
+}
+
+/// Write a range such as `[1,10)` or `empty`, quoting bounds that contain characters that have a
+/// special meaning in ranges
+fn put_text_range(range: Range, dst: &mut BytesMut) -> Result<(), Error> {
+    let (lower, upper) = match range {
+        Range::Empty => {
+            put_slice(RANGE_TEXT_EMPTY.as_bytes(), dst);
+            return Ok(());
+        }
+        Range::NonEmpty { lower, upper } => (lower, upper),
+    };
+
+    let (open, lower) = match lower {
+        RangeBound::Inclusive(v) => (b'[', Some(v)),
+        RangeBound::Exclusive(v) => (b'(', Some(v)),
+        RangeBound::Unbounded => (b'(', None),
+    };
+    let (close, upper) = match upper {
+        RangeBound::Inclusive(v) => (b']', Some(v)),
+        RangeBound::Exclusive(v) => (b')', Some(v)),
+        RangeBound::Unbounded => (b')', None),
+    };
+
+    put_u8(open, dst);
+    if let Some(lower) = lower {
+        put_text_range_bound(*lower, dst)?;
+    }
+    put_u8(b',', dst);
+    if let Some(upper) = upper {
+        put_text_range_bound(*upper, dst)?;
+    }
+    put_u8(close, dst);
+    Ok(())
+}
+
+fn put_text_range_bound(val: Value, dst: &mut BytesMut) -> Result<(), Error> {
+    let mut buf = BytesMut::new();
+    put_text_value(val, &mut buf)?;
+    let text = buf.get(4..).unwrap_or_default();
+    let needs_quotes = text.is_empty()
+        || text.iter().any(|c| {
+            matches!(c, b'"' | b'\\' | b',' | b'(' | b')' | b'[' | b']') || c.is_ascii_whitespace()
+        });
+    if !needs_quotes {
+        put_slice(text, dst);
+        return Ok(());
+    }
+
+    put_u8(b'"', dst);
+    for c in text {
+        if matches!(c, b'"' | b'\\') {
+            put_u8(b'\\', dst);
+        }
+        put_u8(*c, dst);
+    }
+    put_u8(b'"', dst);
+    Ok(())
+}
+
 #[cfg(test)]
 mod tests {
     use std::sync::Arc;
@@ -610,6 +1021,83 @@ mod tests {
         assert_eq!(buf, exp);
     }
 
+    #[test]
+    fn test_encode_authentication_md5_password() {
+        let mut codec = Codec::<Vec<Value>>::new();
+        let mut buf = BytesMut::new();
+        codec
+            .encode(AuthenticationMD5Password { salt: [1, 2, 3, 4] }, &mut buf)
+            .unwrap();
+        let mut exp = BytesMut::new();
+        exp.put_u8(b'R'); // message id
+        exp.put_i32(12); // message length
+        exp.put_i32(5); // require md5 password
+        exp.put_slice(&[1, 2, 3, 4]); // salt
+        assert_eq!(buf, exp);
+    }
+
+    #[test]
+    fn test_encode_backend_key_data() {
+        let mut codec = Codec::<Vec<Value>>::new();
+        let mut buf = BytesMut::new();
+        codec
+            .encode(
+                BackendKeyData {
+                    process_id: 42,
+                    secret_key: -7,
+                },
+                &mut buf,
+            )
+            .unwrap();
+        let mut exp = BytesMut::new();
+        exp.put_u8(b'K'); // message id
+        exp.put_i32(12); // message length
+        exp.put_i32(42); // process id
+        exp.put_i32(-7); // secret key
+        assert_eq!(buf, exp);
+    }
+
+    #[test]
+    fn test_encode_authentication_sasl() {
+        let mut codec = Codec::<Vec<Value>>::new();
+        let mut buf = BytesMut::new();
+        codec
+            .encode(
+                AuthenticationSASL {
+                    allowed_mechanisms: vec!["SCRAM-SHA-256".to_owned()],
+                },
+                &mut buf,
+            )
+            .unwrap();
+        let mut exp = BytesMut::new();
+        exp.put_u8(b'R'); // message id
+        exp.put_i32(4 + 4 + 14 + 1); // message length
+        exp.put_i32(10); // require sasl
+        exp.put_slice(b"SCRAM-SHA-256\0");
+        exp.put_u8(b'\0'); // end of mechanisms
+        assert_eq!(buf, exp);
+    }
+
+    #[test]
+    fn test_encode_authentication_sasl_continue() {
+        let mut codec = Codec::<Vec<Value>>::new();
+        let mut buf = BytesMut::new();
+        codec
+            .encode(
+                AuthenticationSASLContinue {
+                    sasl_data: b"r=abc,s=c2FsdA==,i=4096".to_vec(),
+                },
+                &mut buf,
+            )
+            .unwrap();
+        let mut exp = BytesMut::new();
+        exp.put_u8(b'R'); // message id
+        exp.put_i32(4 + 4 + 23); // message length
+        exp.put_i32(11); // sasl continue
+        exp.put_slice(b"r=abc,s=c2FsdA==,i=4096");
+        assert_eq!(buf, exp);
+    }
+
     #[test]
     fn test_encode_bind_complete() {
         let mut codec = Codec::<Vec<Value>>::new();
@@ -632,6 +1120,20 @@ mod tests {
         assert_eq!(buf, exp);
     }
 
+    #[test]
+    fn test_encode_command_complete_copy() {
+        let mut codec = Codec::<Vec<Value>>::new();
+        let mut buf = BytesMut::new();
+        codec
+            .encode(CommandComplete { tag: CopyOut(7) }, &mut buf)
+            .unwrap();
+        let mut exp = BytesMut::new();
+        exp.put_u8(b'C'); // message id
+        exp.put_i32(4 + 7); // message length
+        exp.extend_from_slice(b"COPY 7\0");
+        assert_eq!(buf, exp);
+    }
+
     #[test]
     fn test_encode_command_complete_delete() {
         let mut codec = Codec::<Vec<Value>>::new();
@@ -705,6 +1207,87 @@ mod tests {
         assert_eq!(buf, exp);
     }
 
+    #[test]
+    fn test_encode_copy_out_response() {
+        let mut codec = Codec::<Vec<Value>>::new();
+        let mut buf = BytesMut::new();
+        codec
+            .encode(
+                CopyOutResponse {
+                    format: Binary,
+                    column_formats: vec![Binary, Binary],
+                },
+                &mut buf,
+            )
+            .unwrap();
+        let mut exp = BytesMut::new();
+        exp.put_u8(b'H'); // message id
+        exp.put_i32(4 + 1 + 2 + 2 + 2); // message length
+        exp.put_u8(1); // overall format
+        exp.put_i16(2); // number of columns
+        exp.put_i16(1); // format of column 1
+        exp.put_i16(1); // format of column 2
+        assert_eq!(buf, exp);
+    }
+
+    #[test]
+    fn test_encode_copy_data_row_text() {
+        let mut codec = Codec::<Vec<Value>>::new();
+        let mut buf = BytesMut::new();
+        codec
+            .encode(
+                CopyDataRow {
+                    values: vec![
+                        Value(DataValue::Int(42)),
+                        Value(DataValue::Null),
+                        Value(DataValue::Text("a\tb\\c".into())),
+                    ],
+                    format: Text,
+                },
+                &mut buf,
+            )
+            .unwrap();
+        let mut exp = BytesMut::new();
+        exp.put_u8(b'd'); // message id
+        exp.put_i32(4 + 14); // message length
+        exp.extend_from_slice(b"42\t\\N\ta\\tb\\\\c\n");
+        assert_eq!(buf, exp);
+    }
+
+    #[test]
+    fn test_encode_copy_data_row_binary() {
+        let mut codec = Codec::<Vec<Value>>::new();
+        let mut buf = BytesMut::new();
+        codec
+            .encode(
+                CopyDataRow {
+                    values: vec![Value(DataValue::Int(42)), Value(DataValue::Null)],
+                    format: Binary,
+                },
+                &mut buf,
+            )
+            .unwrap();
+        let mut exp = BytesMut::new();
+        exp.put_u8(b'd'); // message id
+        exp.put_i32(4 + 2 + 4 + 4 + 4); // message length
+        exp.put_i16(2); // number of values
+        exp.put_i32(4); // length of value
+        exp.put_i32(42); // value
+        exp.put_i32(-1); // null value sentinel
+        assert_eq!(buf, exp);
+    }
+
+    #[test]
+    fn test_encode_copy_done() {
+        let mut codec = Codec::<Vec<Value>>::new();
+        let mut buf = BytesMut::new();
+        codec.encode(CopyDone, &mut buf).unwrap();
+        let mut exp = BytesMut::new();
+        exp.put_u8(b'c'); // message id
+        exp.put_i32(4); // message length
+        assert_eq!(buf, exp);
+    }
+
     #[test]
     fn test_encode_data_row_empty() {
         let mut codec = Codec::<Vec<Value>>::new();
@@ -894,6 +1477,29 @@ mod tests {
         assert_eq!(buf, exp);
     }
 
+    #[test]
+    fn test_encode_notification_response() {
+        let mut codec = Codec::<Vec<Value>>::new();
+        let mut buf = BytesMut::new();
+        codec
+            .encode(
+                NotificationResponse {
+                    process_id: 42,
+                    channel: "ch".to_string(),
+                    payload: "abc".to_string(),
+                },
+                &mut buf,
+            )
+            .unwrap();
+        let mut exp = BytesMut::new();
+        exp.put_u8(b'A'); // message id
+        exp.put_i32(4 + 4 + 3 + 4); // message length
+        exp.put_i32(42); // process id
+        exp.extend_from_slice(b"ch\0");
+        exp.extend_from_slice(b"abc\0");
+        assert_eq!(buf, exp);
+    }
+
     #[test]
     fn test_encode_parameter_description() {
         let mut codec = Codec::<Vec<Value>>::new();
@@ -945,6 +1551,17 @@ mod tests {
         assert_eq!(buf, exp);
     }
 
+    #[test]
+    fn test_encode_portal_suspended() {
+        let mut codec = Codec::<Vec<Value>>::new();
+        let mut buf = BytesMut::new();
+        codec.encode(PortalSuspended, &mut buf).unwrap();
+        let mut exp = BytesMut::new();
+        exp.put_u8(b's'); // message id
+        exp.put_i32(4); // message length
+        assert_eq!(buf, exp);
+    }
+
     #[test]
     fn test_encode_ready_for_query() {
         let mut codec = Codec::<Vec<Value>>::new();
@@ -1331,6 +1948,92 @@ mod tests {
         assert_eq!(buf, exp);
     }
 
+    #[test]
+    fn test_encode_binary_interval() {
+        let mut buf = BytesMut::new();
+        put_binary_value(
+            DataValue::Interval(Interval {
+                months: 14,
+                days: 3,
+                microseconds: 4_000_000,
+            }),
+            &mut buf,
+        )
+        .unwrap();
+        let mut exp = BytesMut::new();
+        exp.put_i32(16);
+        exp.put_i64(4_000_000);
+        exp.put_i32(3);
+        exp.put_i32(14);
+        assert_eq!(buf, exp);
+    }
+
+    #[test]
+    fn test_encode_binary_cidr() {
+        let mut buf = BytesMut::new();
+        let network = IpNetwork {
+            addr: "10.0.0.0".parse().unwrap(),
+            netmask: 8,
+        };
+        put_binary_value(DataValue::Cidr(network), &mut buf).unwrap();
+        let mut exp = BytesMut::new();
+        exp.put_i32(8);
+        exp.put_u8(2); // family
+        exp.put_u8(8); // netmask
+        exp.put_u8(1); // is_cidr
+        exp.put_u8(4); // address length
+        exp.extend_from_slice(&[10, 0, 0, 0]);
+        assert_eq!(buf, exp);
+    }
+
+    #[test]
+    fn test_encode_binary_money() {
+        let mut buf = BytesMut::new();
+        put_binary_value(DataValue::Money(-12345), &mut buf).unwrap();
+        let mut exp = BytesMut::new();
+        exp.put_i32(8);
+        exp.put_i64(-12345);
+        assert_eq!(buf, exp);
+    }
+
+    #[test]
+    fn test_encode_binary_range() {
+        let mut buf = BytesMut::new();
+        let range = Range::NonEmpty {
+            lower: RangeBound::Inclusive(Box::new(DataValue::Int(1))),
+            upper: RangeBound::Exclusive(Box::new(DataValue::Int(10))),
+        };
+        put_binary_value(DataValue::Range(range, Type::INT4_RANGE), &mut buf).unwrap();
+        let mut exp = BytesMut::new();
+        exp.put_i32(17);
+        exp.put_u8(RANGE_LOWER_INCLUSIVE);
+        exp.put_i32(4);
+        exp.put_i32(1);
+        exp.put_i32(4);
+        exp.put_i32(10);
+        assert_eq!(buf, exp);
+
+        let mut buf = BytesMut::new();
+        let range = Range::NonEmpty {
+            lower: RangeBound::Unbounded,
+            upper: RangeBound::Inclusive(Box::new(DataValue::Int(10))),
+        };
+        put_binary_value(DataValue::Range(range, Type::INT4_RANGE), &mut buf).unwrap();
+        let mut exp = BytesMut::new();
+        exp.put_i32(9);
+        exp.put_u8(RANGE_LOWER_UNBOUNDED | RANGE_UPPER_INCLUSIVE);
+        exp.put_i32(4);
+        exp.put_i32(10);
+        assert_eq!(buf, exp);
+
+        let mut buf = BytesMut::new();
+        put_binary_value(DataValue::Range(Range::Empty, Type::INT4_RANGE), &mut buf).unwrap();
+        let mut exp = BytesMut::new();
+        exp.put_i32(1);
+        exp.put_u8(RANGE_EMPTY);
+        assert_eq!(buf, exp);
+    }
+
     #[test]
     fn test_encode_text_null() {
         let mut buf = BytesMut::new();
@@ -1549,4 +2252,109 @@ mod tests {
         exp.extend_from_slice(b"2020-01-02 08:04:05.660 +05:00");
         assert_eq!(buf, exp);
     }
+
+    fn encode_text(val: DataValue) -> BytesMut {
+        let mut buf = BytesMut::new();
+        put_text_value(val, &mut buf).unwrap();
+        buf.split_off(4)
+    }
+
+    #[test]
+    fn test_encode_text_interval() {
+        assert_eq!(
+            encode_text(DataValue::Interval(Interval {
+                months: 14,
+                days: 3,
+                microseconds: 14_706_500_000,
+            })),
+            &b"1 year 2 mons 3 days 04:05:06.5"[..]
+        );
+        assert_eq!(
+            encode_text(DataValue::Interval(Interval {
+                months: 0,
+                days: -1,
+                microseconds: 7_380_000_000,
+            })),
+            &b"-1 days +02:03:00"[..]
+        );
+        assert_eq!(
+            encode_text(DataValue::Interval(Interval {
+                months: -1,
+                days: 0,
+                microseconds: -1,
+            })),
+            &b"-1 mons -00:00:00.000001"[..]
+        );
+        assert_eq!(
+            encode_text(DataValue::Interval(Interval::default())),
+            &b"00:00:00"[..]
+        );
+    }
+
+    #[test]
+    fn test_encode_text_network() {
+        let addr = "10.0.0.1".parse().unwrap();
+        assert_eq!(
+            encode_text(DataValue::Inet(IpNetwork::from(addr))),
+            &b"10.0.0.1"[..]
+        );
+        assert_eq!(
+            encode_text(DataValue::Inet(IpNetwork { addr, netmask: 8 })),
+            &b"10.0.0.1/8"[..]
+        );
+        assert_eq!(
+            encode_text(DataValue::Cidr(IpNetwork::from(addr))),
+            &b"10.0.0.1/32"[..]
+        );
+    }
+
+    #[test]
+    fn test_encode_text_money() {
+        assert_eq!(
+            encode_text(DataValue::Money(-123456789)),
+            &b"-$1,234,567.89"[..]
+        );
+        assert_eq!(encode_text(DataValue::Money(5)), &b"$0.05"[..]);
+    }
+
+    #[test]
+    fn test_encode_text_range() {
+        let range = Range::NonEmpty {
+            lower: RangeBound::Inclusive(Box::new(DataValue::Int(1))),
+            upper: RangeBound::Exclusive(Box::new(DataValue::Int(10))),
+        };
+        assert_eq!(
+            encode_text(DataValue::Range(range, Type::INT4_RANGE)),
+            &b"[1,10)"[..]
+        );
+
+        let range = Range::NonEmpty {
+            lower: RangeBound::Unbounded,
+            upper: RangeBound::Inclusive(Box::new(DataValue::Timestamp(
+                NaiveDate::from_ymd(2020, 1, 2).and_hms(3, 4, 5),
+            ))),
+        };
+        assert_eq!(
+            encode_text(DataValue::Range(range, Type::TS_RANGE)),
+            &b"(,\"2020-01-02 03:04:05\"]"[..]
+        );
+
+        assert_eq!(
+            encode_text(DataValue::Range(Range::Empty, Type::INT4_RANGE)),
+            &b"empty"[..]
+        );
+    }
+
+    #[test]
+    fn test_encode_text_pass_through() {
+        let mut data = BytesMut::new();
+        data.put_i64(0);
+        data.put_i32(1);
+        data.put_i32(0);
+        let val = DataValue::PassThrough(readyset_data::PassThrough {
+            ty: Type::INTERVAL,
+            data: data.to_vec().into_boxed_slice(),
+        });
+        assert_eq!(encode_text(val), &b"1 day"[..]);
+    }
 }
@@ -58,6 +58,21 @@ pub enum DecodeError {
     #[error("invalid text bit vector value: {0}")]
     InvalidTextBitVectorValue(String),
 
+    #[error("invalid text interval value: {0}")]
+    InvalidTextIntervalValue(String),
+
+    #[error("invalid text network value: {0}")]
+    InvalidTextNetworkValue(String),
+
+    #[error("invalid text money value: {0}")]
+    InvalidTextMoneyValue(String),
+
+    #[error("invalid text array value: {0}")]
+    InvalidTextArrayValue(String),
+
+    #[error("invalid text range value: {0}")]
+    InvalidTextRangeValue(String),
+
     #[error("unknown enum variant: {0}")]
     UnknownEnumVariant(String),
 
@@ -12,6 +12,19 @@ use postgres_types::Type;
 use crate::error::Error;
 use crate::value::Value;
 
+/// The different kinds of authentication messages a frontend can send, which all share the same
+/// message identifier
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AuthenticationMessageKind {
+    /// A `PasswordMessage`, containing a cleartext or MD5-hashed password
+    Password,
+    /// A `SASLInitialResponse`, containing the SASL mechanism selected by the frontend and its
+    /// first message
+    SaslInitialResponse,
+    /// A `SASLResponse`, containing a subsequent SASL message
+    SaslResponse,
+}
+
 /// A [`Decoder`] implementation that deserializes `FrontendMessage` and [`Encoder`] implementation
 /// that serializes `BackendMessage`.
 ///
@@ -19,6 +32,7 @@ use crate::value::Value;
 /// [`Encoder`]: https://docs.rs/tokio-util/0.2.0/tokio_util/codec/trait.Encoder.html
 pub struct Codec<R> {
     is_starting_up: bool,
+    authentication_message_kind: AuthenticationMessageKind,
     statement_param_types: HashMap<String, Vec<Type>>,
     _unused: PhantomData<R>,
 }
@@ -27,6 +41,7 @@ impl<R: IntoIterator<Item: TryInto<Value, Error = Error>>> Codec<R> {
     pub fn new() -> Codec<R> {
         Codec {
             is_starting_up: true,
+            authentication_message_kind: AuthenticationMessageKind::Password,
             statement_param_types: HashMap::new(),
             _unused: PhantomData,
         }
@@ -38,6 +53,12 @@ impl<R: IntoIterator<Item: TryInto<Value, Error = Error>>> Codec<R> {
         self.is_starting_up = false;
     }
 
+    /// Set the kind of authentication message the frontend is expected to send next. This must be
+    /// set before the message can be parsed, since the different kinds share a message identifier.
+    pub fn set_authentication_message_kind(&mut self, kind: AuthenticationMessageKind) {
+        self.authentication_message_kind = kind;
+    }
+
     /// Set the data types of a prepared statement's parameters. These data types must be set
     /// before the data values within a `FrontendMessage::Bind` message referencing the named
     /// pepared statement can be parsed.
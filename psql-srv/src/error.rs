@@ -23,12 +23,21 @@ pub enum Error {
     #[error("internal error: {0}")]
     InternalError(String),
 
+    #[error("invalid SASL message: {0}")]
+    InvalidSaslMessage(String),
+
     #[error("invalid integer: {0}")]
     InvalidInteger(#[from] TryFromIntError),
 
     #[error("io error: {0}")]
     IoError(#[from] std::io::Error),
 
+    #[error("connections must use TLS")]
+    TlsRequired,
+
+    #[error("TLS error: {0}")]
+    TlsError(#[from] tokio_native_tls::native_tls::Error),
+
     #[error("missing portal: {0}")]
     MissingPortal(String),
 
@@ -38,6 +47,9 @@ pub enum Error {
     #[error("parse error: {0}")]
     ParseError(String),
 
+    #[error("canceling statement due to user request")]
+    QueryCanceled,
+
     #[error("unimplemented: {0}")]
     Unimplemented(String),
 
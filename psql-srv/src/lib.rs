@@ -14,6 +14,7 @@
 //! implementing a `Backend` and `ServeOneBackend`, in the `serve_one.rs` example, for a very basic
 //! implementation.
 
+mod authentication;
 mod bytes;
 mod channel;
 mod codec;
@@ -26,32 +27,60 @@ pub mod util;
 mod value;
 
 use std::convert::TryInto;
+use std::sync::Arc;
 
 use async_trait::async_trait;
+use futures::Stream;
 use postgres::SimpleQueryMessage;
 use postgres_types::Type;
 use protocol::Protocol;
 use tokio::io::{AsyncRead, AsyncWrite};
+pub use tokio_native_tls::TlsAcceptor;
 
 pub use crate::bytes::BytesStr;
 pub use crate::error::Error;
-pub use crate::value::Value;
+pub use crate::message::TransferFormat;
+pub use crate::value::{Interval, IpNetwork, Range, RangeBound, Value};
 
+/// The method a frontend should use to authenticate, as requested by a [`Backend`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum CredentialsNeeded {
+    /// Don't authenticate the frontend
     None,
+    /// Request the password in cleartext
     Cleartext,
+    /// Request the MD5 hash of the password, salted with a random value. Only intended for legacy
+    /// frontends that don't support SCRAM-SHA-256.
+    Md5,
+    /// Authenticate using SCRAM-SHA-256
+    ScramSha256,
 }
 
-// Represents different forms of credentials provided by the client as part of authorization
-#[derive(PartialEq, Eq)]
-pub enum Credentials {
-    Cleartext { user: String, password: String },
+/// The key identifying a connection, which a frontend must present in a `CancelRequest` to cancel
+/// the query running on that connection
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BackendKeyData {
+    /// The ID of the backend process serving the connection. This need not refer to an actual
+    /// process, but must be unique among open connections.
+    pub process_id: i32,
+    /// A secret key, which should be chosen randomly so that only the frontend that opened the
+    /// connection can cancel queries running on it
+    pub secret_key: i32,
+}
+
+/// An asynchronous notification, sent to a frontend that is listening on its channel
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Notification {
+    /// The name of the channel the notification was raised on
+    pub channel: String,
+    /// The payload of the notification
+    pub payload: String,
 }
 
 /// A trait for implementing a SQL backend that produces responses to SQL query statements. This
 /// trait is the primary interface for the `psql-srv` crate.
 #[async_trait]
-pub trait Backend {
+pub trait Backend: Send {
     /// An associated type that can be converted into this crate's `Value` type.
     type Value: TryInto<Value, Error = Error>;
 
@@ -78,10 +107,40 @@ pub trait Backend {
     ///   instance.
     async fn on_init(&mut self, database: &str) -> Result<CredentialsNeeded, Error>;
 
-    /// Validate authentication credentials provided by connected client
+    /// Retrieve the password for the user with the given name, if any.
+    ///
+    /// The password may be either in plaintext, or an MD5 or SCRAM-SHA-256 verifier in the format
+    /// PostgreSQL stores in `pg_authid.rolpassword`. Since a verifier can only be used to check the
+    /// kind of proof it was created for, users whose password is stored as a verifier authenticate
+    /// using the corresponding method rather than the one returned by [`Backend::on_init`] (unless
+    /// that's [`CredentialsNeeded::Cleartext`]).
+    ///
+    /// If the user doesn't exist, return [`None`].
+    fn password_for_user(&self, user: &str) -> Option<String>;
+
+    /// Returns the key identifying this connection, which is sent to the frontend once it has
+    /// authenticated, so that it can later cancel queries running on the connection.
+    ///
+    /// If this returns [`None`] (the default), the frontend has no way to cancel queries.
+    fn backend_key_data(&self) -> Option<BackendKeyData> {
+        None
+    }
+
+    /// Cancels the query currently running on the connection identified by `key`, if any.
     ///
-    /// * `credentials` - Authentication info provided by the client
-    async fn on_auth(&mut self, credentials: Credentials) -> Result<(), Error>;
+    /// Frontends send cancel requests on a new connection instead of a `StartupMessage`, so this is
+    /// called on a different `Backend` than the one running the query, which is then closed
+    /// without sending a response. Per the PostgreSQL protocol, invalid keys are ignored silently.
+    async fn on_cancel(&mut self, _key: BackendKeyData) {}
+
+    /// Waits for the next asynchronous notification to send to the frontend, for one of the
+    /// channels it is listening on (see the `LISTEN` statement).
+    ///
+    /// This is raced against reading the next message from the frontend whenever the connection is
+    /// waiting for one, so it must be cancel-safe. The default implementation never resolves.
+    async fn next_notification(&mut self) -> Notification {
+        futures::future::pending().await
+    }
 
     /// Performs the specified SQL query.
     ///
@@ -158,6 +217,33 @@ pub enum QueryResponse<S> {
     /// commands (e.g., SELECT, INSERT, DELETE, etc.). The SimpleQuery protocol is distinct from
     /// the prepare/execute protocol.
     SimpleQuery(Vec<SimpleQueryMessage>),
+    /// The response to a `COPY ... TO STDOUT` statement, whose rows are encoded by `psql_srv` in
+    /// the given `COPY` format.
+    CopyOut {
+        /// The format to copy the rows out in.
+        format: TransferFormat,
+        /// The schema of the rows being copied out.
+        schema: Vec<Column>,
+        /// The rows being copied out.
+        resultset: S,
+    },
+    /// The response to a `COPY ... TO STDOUT` statement which has been proxied to another
+    /// database, whose output is forwarded to the frontend as-is.
+    PassThroughCopyOut {
+        /// The format the data was copied out in.
+        format: TransferFormat,
+        /// The number of columns in each row of the data.
+        num_columns: usize,
+        /// The data copied out, to be sent in a `CopyData` message per chunk as it arrives.
+        data: Box<dyn PassThroughCopyData>,
+    },
+}
+
+/// The output of a `COPY ... TO STDOUT` statement which has been proxied to another database, as a
+/// stream of chunks of data
+pub trait PassThroughCopyData: Stream<Item = Result<::bytes::Bytes, Error>> + Send + Unpin {
+    /// Returns the number of rows in the data streamed so far.
+    fn num_rows(&self) -> u64;
 }
 
 /// Run a `Backend` on the provided bytestream until the bytestream is remotely closed.
@@ -166,8 +252,17 @@ pub enum QueryResponse<S> {
 /// * `channel` - A bytestream channel connected to a PostgreSQL frontend. Requests sent by the
 ///   frontend on this channel will be forwarded to `backend`, and the `backend`'s responses will be
 ///   returned to the frontend. When `channel` is closed by the frontend, `run_backend` returns.
-pub async fn run_backend<B: Backend, C: AsyncRead + AsyncWrite + Unpin>(backend: B, channel: C) {
-    runner::Runner::run(backend, channel).await
+/// * `tls_acceptor` - If set, frontends which request TLS have their connection upgraded to TLS
+///   using this acceptor. Otherwise, such requests are denied.
+/// * `require_tls` - If set along with `tls_acceptor`, frontends which don't request TLS are sent
+///   an error, and their connection is closed. Cancel requests are accepted without TLS.
+pub async fn run_backend<B: Backend, C: AsyncRead + AsyncWrite + Unpin>(
+    backend: B,
+    channel: C,
+    tls_acceptor: Option<Arc<TlsAcceptor>>,
+    require_tls: bool,
+) {
+    runner::Runner::run(backend, channel, tls_acceptor, require_tls).await
 }
 
 pub async fn send_immediate_err<B, C>(channel: C, error: Error) -> Result<(), Error>
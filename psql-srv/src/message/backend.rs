@@ -1,6 +1,7 @@
 use std::convert::TryInto;
 use std::sync::Arc;
 
+use bytes::Bytes;
 pub use postgres::error::SqlState;
 use postgres::SimpleQueryRow;
 use postgres_types::Type;
@@ -12,6 +13,13 @@ use crate::value::Value;
 
 const READY_FOR_QUERY_IDLE: u8 = b'I';
 const SSL_RESPONSE_N: u8 = b'N';
+const SSL_RESPONSE_S: u8 = b'S';
+
+/// Sent before the rows copied out by a `COPY` in binary format: the file signature, followed by
+/// the (empty) flags field and header extension area.
+const COPY_BINARY_HEADER: &[u8] = b"PGCOPY\n\xff\r\n\0\0\0\0\0\0\0\0\0";
+/// Sent after the rows copied out by a `COPY` in binary format: a field count of -1.
+const COPY_BINARY_TRAILER: &[u8] = &[0xff, 0xff];
 
 /// A message to be sent by a Postgresql backend (server). The different types of backend messages,
 /// and the fields they contain, are described in the
@@ -27,12 +35,41 @@ const SSL_RESPONSE_N: u8 = b'N';
 #[derive(Debug, PartialEq, Eq)]
 pub enum BackendMessage<R> {
     AuthenticationCleartextPassword,
+    AuthenticationMD5Password {
+        salt: [u8; 4],
+    },
     AuthenticationOk,
+    AuthenticationSASL {
+        allowed_mechanisms: Vec<String>,
+    },
+    AuthenticationSASLContinue {
+        sasl_data: Vec<u8>,
+    },
+    AuthenticationSASLFinal {
+        sasl_data: Vec<u8>,
+    },
+    BackendKeyData {
+        process_id: i32,
+        secret_key: i32,
+    },
     BindComplete,
     CloseComplete,
     CommandComplete {
         tag: CommandCompleteTag,
     },
+    CopyData {
+        data: Bytes,
+    },
+    /// A `CopyData` message containing a single row, encoded in the given `COPY` format
+    CopyDataRow {
+        values: R,
+        format: TransferFormat,
+    },
+    CopyDone,
+    CopyOutResponse {
+        format: TransferFormat,
+        column_formats: Vec<TransferFormat>,
+    },
     DataRow {
         values: R,
         explicit_transfer_formats: Option<Arc<Vec<TransferFormat>>>,
@@ -42,6 +79,12 @@ pub enum BackendMessage<R> {
         sqlstate: SqlState,
         message: String,
     },
+    /// An asynchronous notification, for a channel the frontend is listening on
+    NotificationResponse {
+        process_id: i32,
+        channel: String,
+        payload: String,
+    },
     ParameterDescription {
         parameter_data_types: Vec<Type>,
     },
@@ -50,6 +93,9 @@ pub enum BackendMessage<R> {
         parameter_value: String,
     },
     ParseComplete,
+    /// Sent instead of `CommandComplete` when executing a portal stopped because it reached the
+    /// row limit requested by the frontend, before the portal's resultset was exhausted
+    PortalSuspended,
     ReadyForQuery {
         status: u8,
     },
@@ -75,10 +121,30 @@ impl<R: IntoIterator<Item: TryInto<Value, Error = Error>>> BackendMessage<R> {
             byte: SSL_RESPONSE_N,
         }
     }
+
+    pub fn ssl_response_s() -> BackendMessage<R> {
+        BackendMessage::SSLResponse {
+            byte: SSL_RESPONSE_S,
+        }
+    }
+
+    pub fn copy_binary_header() -> BackendMessage<R> {
+        BackendMessage::CopyData {
+            data: Bytes::from_static(COPY_BINARY_HEADER),
+        }
+    }
+
+    pub fn copy_binary_trailer() -> BackendMessage<R> {
+        BackendMessage::CopyData {
+            data: Bytes::from_static(COPY_BINARY_TRAILER),
+        }
+    }
 }
 
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub enum CommandCompleteTag {
+    /// The number of rows copied by a `COPY ... TO` statement
+    CopyOut(u64),
     Delete(u64),
     Empty,
     Insert(u64),
@@ -1,5 +1,6 @@
 use std::fmt;
 
+use bytes::Bytes;
 use postgres_types::Type;
 
 use crate::bytes::BytesStr;
@@ -27,6 +28,10 @@ pub enum FrontendMessage {
         params: Vec<Value>,
         result_transfer_formats: Vec<TransferFormat>,
     },
+    CancelRequest {
+        process_id: i32,
+        secret_key: i32,
+    },
     Close {
         name: StatementName,
     },
@@ -48,6 +53,13 @@ pub enum FrontendMessage {
     Query {
         query: BytesStr,
     },
+    SASLInitialResponse {
+        sasl_mechanism: BytesStr,
+        sasl_data: Bytes,
+    },
+    SASLResponse {
+        sasl_data: Bytes,
+    },
     SSLRequest,
     StartupMessage {
         protocol_version: i32,
@@ -69,12 +81,15 @@ impl fmt::Display for FrontendMessage {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match self {
             Self::Bind { .. } => write!(f, "Bind"),
+            Self::CancelRequest { .. } => write!(f, "CancelRequest"),
             Self::Close { .. } => write!(f, "Close"),
             Self::Describe { .. } => write!(f, "Describe"),
             Self::Execute { .. } => write!(f, "Execute"),
             Self::Parse { .. } => write!(f, "Parse"),
             Self::PasswordMessage { .. } => write!(f, "PasswordMessage"),
             Self::Query { .. } => write!(f, "Query"),
+            Self::SASLInitialResponse { .. } => write!(f, "SASLInitialResponse"),
+            Self::SASLResponse { .. } => write!(f, "SASLResponse"),
             Self::SSLRequest => write!(f, "SSLRequest"),
             Self::StartupMessage { .. } => write!(f, "StartupMessage"),
             Self::Sync => write!(f, "Sync"),
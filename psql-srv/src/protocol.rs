@@ -1,25 +1,29 @@
 use std::borrow::Borrow;
-use std::collections::HashMap;
+use std::collections::{HashMap, VecDeque};
 use std::sync::Arc;
 
 use postgres::SimpleQueryMessage;
 use postgres_protocol::Oid;
 use postgres_types::{Kind, Type};
-use smallvec::smallvec;
+use smallvec::{smallvec, SmallVec};
 use tokio::io::{AsyncRead, AsyncWrite};
 
+use crate::authentication::{
+    AuthenticationStep, ScramSha256Exchange, ScramSha256Verifier, StoredPassword, SCRAM_SHA_256,
+};
 use crate::bytes::BytesStr;
 use crate::channel::Channel;
+use crate::codec::AuthenticationMessageKind;
 use crate::error::Error;
 use crate::message::BackendMessage::{self, *};
 use crate::message::FrontendMessage::{self, *};
 use crate::message::StatementName::*;
 use crate::message::TransferFormat::{self, *};
 use crate::message::{CommandCompleteTag, ErrorSeverity, FieldDescription, SqlState};
-use crate::response::Response;
+use crate::response::{CopyDataStream, Response};
 use crate::value::Value;
 use crate::QueryResponse::*;
-use crate::{Backend, Column, PrepareResponse};
+use crate::{Backend, BackendKeyData, Column, CredentialsNeeded, PrepareResponse};
 
 const ATTTYPMOD_NONE: i32 = -1;
 const TRANSFER_FORMAT_PLACEHOLDER: TransferFormat = TransferFormat::Text;
@@ -53,7 +57,10 @@ pub(crate) enum State {
     StartingUp,
 
     /// The client is performing authentication
-    Authenticating { user: BytesStr },
+    Authenticating {
+        user: BytesStr,
+        step: AuthenticationStep,
+    },
 
     /// The server is ready to accept queries
     Ready,
@@ -72,8 +79,8 @@ pub(crate) enum State {
 }
 
 /// A struct to maintain state for an implementation of the backend side of the PostgreSQL
-/// frontend/backend protocol.
-pub struct Protocol {
+/// frontend/backend protocol, returning rows of type `R`.
+pub struct Protocol<R> {
     /// The current state of the request-response flow
     state: State,
 
@@ -86,12 +93,15 @@ pub struct Protocol {
     /// A portal is a combination of a prepared statement and a list of values provided by the
     /// frontend for the prepared statement's parameters. This `HashMap` contains these parameter
     /// values as well as metadata about the portal, and is keyed by the portal's name.
-    portals: HashMap<String, PortalData>,
+    portals: HashMap<String, PortalData<R>>,
 
     /// Stores a mapping of Oid -> type lengths, used for when ReadySet encounters an
     /// unsupported/custom type. On the first instance of such a type, the hashmap will be
     /// populated with the data from pg_catalog.pg_type.
     extended_types: HashMap<Oid, i16>,
+
+    /// Whether to accept requests from the frontend to upgrade the connection to TLS
+    allow_tls: bool,
 }
 
 /// A prepared statement allows a frontend to specify the general form of a SQL statement while
@@ -108,25 +118,58 @@ struct PreparedStatementData {
 /// for the prepared statement's parameters. This struct contains these parameter values as well as
 /// metadata about the portal.
 #[derive(Debug, PartialEq)]
-struct PortalData {
+struct PortalData<R> {
     prepared_statement_id: u32,
     prepared_statement_name: String,
     params: Vec<Value>,
     result_transfer_formats: Arc<Vec<TransferFormat>>,
+    /// The rows of the portal's resultset that are yet to be returned, if the portal was
+    /// suspended after reaching the row limit of an `Execute` request.
+    suspended_rows: Option<VecDeque<R>>,
+}
+
+impl<R> PortalData<R> {
+    /// Returns a response containing up to `limit` rows taken from the front of `rows`, or all of
+    /// them if `limit` is zero. Any rows left over are kept in the portal, to be returned by the
+    /// next `Execute` request for it.
+    fn next_rows<S>(&mut self, mut rows: VecDeque<R>, limit: i32) -> Response<R, S> {
+        let n_rows = match usize::try_from(limit) {
+            Ok(limit) if limit > 0 => limit.min(rows.len()),
+            _ => rows.len(),
+        };
+        let batch = rows.drain(..n_rows).collect();
+        let suspended = !rows.is_empty();
+        if suspended {
+            self.suspended_rows = Some(rows);
+        }
+        Response::PortalRows {
+            rows: batch,
+            result_transfer_formats: Some(self.result_transfer_formats.clone()),
+            suspended,
+        }
+    }
 }
 
 /// An implementation of the backend side of the PostgreSQL frontend/backend protocol. See
 /// `on_request` for the primary entry point.
-impl Protocol {
-    pub fn new() -> Protocol {
+impl<R> Protocol<R> {
+    pub fn new() -> Protocol<R> {
         Protocol {
             state: State::StartingUp,
             prepared_statements: HashMap::new(),
             portals: HashMap::new(),
             extended_types: HashMap::new(),
+            allow_tls: false,
         }
     }
 
+    /// Sets whether to accept requests from the frontend to upgrade the connection to TLS. If this
+    /// is set, the caller is responsible for performing the TLS handshake after an `SSLRequest` has
+    /// been answered.
+    pub fn set_allow_tls(&mut self, allow_tls: bool) {
+        self.allow_tls = allow_tls;
+    }
+
     /// The core implementation of the backend side of the PostgreSQL frontend/backend protocol.
     /// This implementation processes a message received from the frontend, forwards suitable
     /// requests to a `Backend`, and returns appropriate responses as a `Result`.
@@ -142,15 +185,15 @@ impl Protocol {
     ///   the frontend/backend protocol state in order to parse some types of frontend messages.)
     /// * returns - A `Response` representing a sequence of `BackendMessage`s to return to the
     ///   frontend, otherwise an `Error` if a failure occurs.
-    pub async fn on_request<B: Backend, C: AsyncRead + AsyncWrite + Unpin>(
+    pub async fn on_request<B: Backend<Row = R>, C: AsyncRead + AsyncWrite + Unpin>(
         &mut self,
         message: FrontendMessage,
         backend: &mut B,
         channel: &mut Channel<C, B::Row>,
     ) -> Result<Response<B::Row, B::Resultset>, Error> {
         // TODO(grfn): Discard if self.state.is_error()?
-        let get_ready_message = |version| {
-            smallvec![
+        let get_ready_message = |version, key_data: Option<BackendKeyData>| {
+            let mut messages: SmallVec<[BackendMessage<B::Row>; 2]> = smallvec![
                 AuthenticationOk,
                 BackendMessage::ParameterStatus {
                     parameter_name: "client_encoding".to_owned(),
@@ -172,15 +215,47 @@ impl Protocol {
                     parameter_name: "server_version".to_owned(),
                     parameter_value: version,
                 },
-                BackendMessage::ready_for_query_idle(),
-            ]
+            ];
+            if let Some(BackendKeyData {
+                process_id,
+                secret_key,
+            }) = key_data
+            {
+                messages.push(BackendMessage::BackendKeyData {
+                    process_id,
+                    secret_key,
+                });
+            }
+            messages.push(BackendMessage::ready_for_query_idle());
+            messages
         };
         match self.state {
             State::StartingUp => match message {
                 // A request for an SSL connection.
                 SSLRequest { .. } => {
-                    // Deny the SSL connection. The frontend may choose to proceed without SSL.
-                    Ok(Response::Message(BackendMessage::ssl_response_n()))
+                    if self.allow_tls {
+                        // Accept the SSL connection. The frontend will now start the TLS
+                        // handshake.
+                        Ok(Response::Message(BackendMessage::ssl_response_s()))
+                    } else {
+                        // Deny the SSL connection. The frontend may choose to proceed without SSL.
+                        Ok(Response::Message(BackendMessage::ssl_response_n()))
+                    }
+                }
+
+                // A request to cancel the query running on another connection. This is sent on a
+                // new connection, which is closed after the request is handled.
+                CancelRequest {
+                    process_id,
+                    secret_key,
+                } => {
+                    backend
+                        .on_cancel(BackendKeyData {
+                            process_id,
+                            secret_key,
+                        })
+                        .await;
+                    Ok(Response::Empty)
                 }
 
                 // A request to start up a connection, with some metadata provided.
@@ -188,15 +263,31 @@ impl Protocol {
                     let database = database
                         .ok_or_else(|| Error::Unsupported("database is required".to_string()))?;
                     let response = match backend.on_init(database.borrow()).await? {
-                        crate::CredentialsNeeded::None => {
+                        CredentialsNeeded::None => {
                             self.state = State::Ready;
-                            get_ready_message(backend.version())
+                            get_ready_message(backend.version(), backend.backend_key_data())
                         }
-                        crate::CredentialsNeeded::Cleartext => {
-                            self.state = State::Authenticating {
-                                user: user.ok_or(Error::AuthenticationFailure(String::new()))?,
+                        requested => {
+                            let user = user.ok_or(Error::AuthenticationFailure(String::new()))?;
+                            let stored = StoredPassword::for_user(backend, user.borrow());
+                            let step = AuthenticationStep::start(requested, stored.as_ref());
+                            let request = match step {
+                                AuthenticationStep::Cleartext => AuthenticationCleartextPassword,
+                                AuthenticationStep::Md5 { salt } => {
+                                    AuthenticationMD5Password { salt }
+                                }
+                                AuthenticationStep::SaslInitial
+                                | AuthenticationStep::SaslContinue(_) => {
+                                    channel.set_authentication_message_kind(
+                                        AuthenticationMessageKind::SaslInitialResponse,
+                                    );
+                                    AuthenticationSASL {
+                                        allowed_mechanisms: vec![SCRAM_SHA_256.to_owned()],
+                                    }
+                                }
                             };
-                            smallvec![AuthenticationCleartextPassword]
+                            self.state = State::Authenticating { user, step };
+                            smallvec![request]
                         }
                     };
 
@@ -210,21 +301,100 @@ impl Protocol {
                 }
             },
 
-            State::Authenticating { ref user } => match message {
-                PasswordMessage { ref password } => {
-                    backend
-                        .on_auth(crate::Credentials::Cleartext {
-                            user: user.to_string(),
-                            password: password.to_string(),
-                        })
-                        .await?;
-                    self.state = State::Ready;
+            State::Authenticating { ref user, ref step } => {
+                let user = user.clone();
+                match (step, message) {
+                    (AuthenticationStep::Cleartext, PasswordMessage { password }) => {
+                        let authenticated = StoredPassword::for_user(backend, user.borrow())
+                            .map_or(false, |stored| {
+                                stored.matches_cleartext(user.borrow(), password.borrow())
+                            });
+                        if !authenticated {
+                            return Err(Error::AuthenticationFailure(user.to_string()));
+                        }
+                        self.state = State::Ready;
 
-                    Ok(Response::Messages(get_ready_message(backend.version())))
-                }
+                        Ok(Response::Messages(get_ready_message(
+                            backend.version(),
+                            backend.backend_key_data(),
+                        )))
+                    }
 
-                m => Err(Error::UnsupportedMessage(m)),
-            },
+                    (AuthenticationStep::Md5 { salt }, PasswordMessage { password }) => {
+                        let authenticated = StoredPassword::for_user(backend, user.borrow())
+                            .map_or(false, |stored| {
+                                stored.matches_md5(user.borrow(), salt, password.borrow())
+                            });
+                        if !authenticated {
+                            return Err(Error::AuthenticationFailure(user.to_string()));
+                        }
+                        self.state = State::Ready;
+
+                        Ok(Response::Messages(get_ready_message(
+                            backend.version(),
+                            backend.backend_key_data(),
+                        )))
+                    }
+
+                    (
+                        AuthenticationStep::SaslInitial,
+                        SASLInitialResponse {
+                            sasl_mechanism,
+                            sasl_data,
+                        },
+                    ) => {
+                        if sasl_mechanism.borrow() as &str != SCRAM_SHA_256 {
+                            return Err(Error::InvalidSaslMessage(format!(
+                                "unsupported SASL mechanism {}",
+                                sasl_mechanism
+                            )));
+                        }
+                        // Carry out the exchange even if we know it will fail, so as not to reveal
+                        // whether the user exists
+                        let verifier = match StoredPassword::for_user(backend, user.borrow()) {
+                            Some(StoredPassword::ScramSha256(verifier)) => verifier,
+                            Some(StoredPassword::Plaintext(password)) => {
+                                ScramSha256Verifier::generate(&password)
+                            }
+                            Some(StoredPassword::Md5(_)) | None => ScramSha256Verifier::mock(),
+                        };
+                        let (exchange, server_first_message) =
+                            ScramSha256Exchange::start(verifier, &sasl_data)?;
+                        self.state = State::Authenticating {
+                            user,
+                            step: AuthenticationStep::SaslContinue(Box::new(exchange)),
+                        };
+                        channel.set_authentication_message_kind(
+                            AuthenticationMessageKind::SaslResponse,
+                        );
+
+                        Ok(Response::Message(AuthenticationSASLContinue {
+                            sasl_data: server_first_message.into_bytes(),
+                        }))
+                    }
+
+                    (AuthenticationStep::SaslContinue(exchange), SASLResponse { sasl_data }) => {
+                        let server_final_message = exchange
+                            .finish(&sasl_data)?
+                            .ok_or_else(|| Error::AuthenticationFailure(user.to_string()))?;
+                        self.state = State::Ready;
+                        channel
+                            .set_authentication_message_kind(AuthenticationMessageKind::Password);
+
+                        let mut response =
+                            get_ready_message(backend.version(), backend.backend_key_data());
+                        response.insert(
+                            0,
+                            AuthenticationSASLFinal {
+                                sasl_data: server_final_message.into_bytes(),
+                            },
+                        );
+                        Ok(Response::Messages(response))
+                    }
+
+                    (_, m) => Err(Error::UnsupportedMessage(m)),
+                }
+            }
 
             _ => match message {
                 // A request to bind parameters to a prepared statement, creating a portal.
@@ -266,6 +436,7 @@ impl Protocol {
                             prepared_statement_name: prepared_statement_name.to_string(),
                             params,
                             result_transfer_formats: Arc::new(result_transfer_formats),
+                            suspended_rows: None,
                         },
                     );
                     Ok(Response::Message(BindComplete))
@@ -361,26 +532,35 @@ impl Protocol {
                 },
 
                 // A request to execute a portal (a combination of a prepared statement with
-                // parameter values).
-                Execute { portal_name, .. } => {
+                // parameter values). If `limit` is non-zero, at most that many rows are returned,
+                // and the portal is suspended until the next request to execute it if any are left.
+                Execute { portal_name, limit } => {
                     self.state = State::Extended;
-                    let PortalData {
-                        prepared_statement_id,
-                        params,
-                        result_transfer_formats,
-                        ..
-                    } = self
+                    let portal = self
                         .portals
-                        .get(portal_name.borrow() as &str)
+                        .get_mut(portal_name.borrow() as &str)
                         .ok_or_else(|| Error::MissingPreparedStatement(portal_name.to_string()))?;
-                    let response = backend.on_execute(*prepared_statement_id, params).await?;
+                    if let Some(rows) = portal.suspended_rows.take() {
+                        self.state = State::Ready;
+                        return Ok(portal.next_rows(rows, limit));
+                    }
+
+                    let response = backend
+                        .on_execute(portal.prepared_statement_id, &portal.params)
+                        .await?;
                     let res = if let Select { resultset, .. } = response {
-                        Ok(Response::Select {
-                            header: None,
-                            resultset,
-                            result_transfer_formats: Some(result_transfer_formats.clone()),
-                            trailer: None,
-                        })
+                        if limit > 0 {
+                            Ok(portal.next_rows(resultset.into_iter().collect(), limit))
+                        } else {
+                            Ok(Response::Select {
+                                header: None,
+                                resultset,
+                                result_transfer_formats: Some(
+                                    portal.result_transfer_formats.clone(),
+                                ),
+                                trailer: None,
+                            })
+                        }
                     } else {
                         let tag = match response {
                             Insert(n) => CommandCompleteTag::Insert(n),
@@ -396,6 +576,11 @@ impl Protocol {
                                     "Received SimpleQuery response for Execute".to_string(),
                                 ));
                             }
+                            CopyOut { .. } | PassThroughCopyOut { .. } => {
+                                return Err(Error::Unsupported(
+                                    "COPY in the extended query protocol".to_string(),
+                                ));
+                            }
                         };
                         Ok(Response::Message(CommandComplete { tag }))
                     };
@@ -463,6 +648,35 @@ impl Protocol {
                         }
                         messages.push(BackendMessage::ready_for_query_idle());
                         Ok(Response::Messages(messages))
+                    } else if let CopyOut {
+                        format,
+                        schema,
+                        resultset,
+                    } = response
+                    {
+                        Ok(Response::CopyOut {
+                            header: CopyOutResponse {
+                                format,
+                                column_formats: vec![format; schema.len()],
+                            },
+                            resultset,
+                            format,
+                            trailer: Some(BackendMessage::ready_for_query_idle()),
+                        })
+                    } else if let PassThroughCopyOut {
+                        format,
+                        num_columns,
+                        data,
+                    } = response
+                    {
+                        Ok(Response::PassThroughCopyOut {
+                            header: CopyOutResponse {
+                                format,
+                                column_formats: vec![format; num_columns],
+                            },
+                            data: CopyDataStream(data),
+                            trailer: Some(BackendMessage::ready_for_query_idle()),
+                        })
                     } else {
                         let tag = match response {
                             Insert(n) => CommandCompleteTag::Insert(n),
@@ -476,6 +690,9 @@ impl Protocol {
                             SimpleQuery(_) => {
                                 unreachable!("SimpleQuery is handled as a special case above.")
                             }
+                            CopyOut { .. } | PassThroughCopyOut { .. } => {
+                                unreachable!("COPY is handled as a special case above.")
+                            }
                         };
                         Ok(Response::Messages(smallvec![
                             CommandComplete { tag },
@@ -532,7 +749,7 @@ impl Protocol {
     /// * `error` - an `Error` that has occurred while communicating with the frontend or handling
     ///   one of the frontend's requests.
     /// * returns - A `Response` containing an `ErrorResponse` message to send to the frontend.
-    pub async fn on_error<B: Backend>(
+    pub async fn on_error<B: Backend<Row = R>>(
         &mut self,
         error: Error,
     ) -> Result<Response<B::Row, B::Resultset>, Error> {
@@ -549,18 +766,22 @@ impl Protocol {
     }
 }
 
-fn make_error_response<R>(error: Error) -> BackendMessage<R> {
+pub(crate) fn make_error_response<R>(error: Error) -> BackendMessage<R> {
     let sqlstate = match error {
         Error::AuthenticationFailure(_) => SqlState::INVALID_PASSWORD,
         Error::DecodeError(_) => SqlState::IO_ERROR,
         Error::EncodeError(_) => SqlState::IO_ERROR,
         Error::IncorrectFormatCount(_) => SqlState::IO_ERROR,
         Error::InternalError(_) => SqlState::INTERNAL_ERROR,
+        Error::InvalidSaslMessage(_) => SqlState::PROTOCOL_VIOLATION,
         Error::InvalidInteger(_) => SqlState::DATATYPE_MISMATCH,
         Error::IoError(_) => SqlState::IO_ERROR,
+        Error::TlsRequired => SqlState::INVALID_AUTHORIZATION_SPECIFICATION,
+        Error::TlsError(_) => SqlState::PROTOCOL_VIOLATION,
         Error::MissingPortal(_) => SqlState::UNDEFINED_PSTATEMENT,
         Error::MissingPreparedStatement(_) => SqlState::UNDEFINED_PSTATEMENT,
         Error::ParseError(_) => SqlState::INVALID_PSTATEMENT_DEFINITION,
+        Error::QueryCanceled => SqlState::QUERY_CANCELED,
         Error::Unimplemented(_) => SqlState::FEATURE_NOT_SUPPORTED,
         Error::Unknown(_) => SqlState::INTERNAL_ERROR,
         Error::Unsupported(_) => SqlState::FEATURE_NOT_SUPPORTED,
@@ -614,6 +835,7 @@ async fn make_field_description<B: Backend>(
     let data_type_size = match col.col_type.kind() {
         Kind::Array(_) => TYPLEN_VARLENA,
         Kind::Enum(_) => TYPLEN_VARLENA,
+        Kind::Range(_) => TYPLEN_VARLENA,
         _ => match col.col_type {
             Type::BOOL => TYPLEN_1,
             Type::BYTEA => TYPLEN_VARLENA,
@@ -740,15 +962,17 @@ mod tests {
     use std::task::Poll;
 
     use async_trait::async_trait;
-    use bytes::BytesMut;
+    use bytes::{Bytes, BytesMut};
     use futures::task::Context;
+    use postgres_protocol::authentication::md5_hash;
+    use postgres_protocol::authentication::sasl::{ChannelBinding, ScramSha256};
     use tokio::io::ReadBuf;
     use tokio_test::block_on;
 
     use super::*;
     use crate::bytes::BytesStr;
     use crate::value::Value as DataValue;
-    use crate::{Credentials, CredentialsNeeded, PrepareResponse, QueryResponse};
+    use crate::{CredentialsNeeded, PrepareResponse, QueryResponse};
 
     fn bytes_str(s: &str) -> BytesStr {
         let mut buf = BytesMut::new();
@@ -772,6 +996,7 @@ mod tests {
     struct Backend {
         is_query_err: bool,
         is_query_read: bool,
+        is_query_copy: bool,
 
         is_prepare_err: bool,
 
@@ -781,7 +1006,10 @@ mod tests {
         last_close: Option<u32>,
         last_execute_id: Option<u32>,
         last_execute_params: Option<Vec<DataValue>>,
-        needed_credentials: Option<Credentials>,
+        needed_credentials: CredentialsNeeded,
+        users: HashMap<String, String>,
+        backend_key_data: Option<BackendKeyData>,
+        last_cancel: Option<BackendKeyData>,
     }
 
     impl Backend {
@@ -789,6 +1017,7 @@ mod tests {
             Backend {
                 is_query_err: false,
                 is_query_read: true,
+                is_query_copy: false,
                 is_prepare_err: false,
                 database: None,
                 last_query: None,
@@ -796,7 +1025,10 @@ mod tests {
                 last_close: None,
                 last_execute_id: None,
                 last_execute_params: None,
-                needed_credentials: None,
+                needed_credentials: CredentialsNeeded::None,
+                users: HashMap::new(),
+                backend_key_data: None,
+                last_cancel: None,
             }
         }
     }
@@ -809,34 +1041,38 @@ mod tests {
 
         async fn on_init(&mut self, database: &str) -> Result<CredentialsNeeded, Error> {
             self.database = Some(database.to_string());
-            match &self.needed_credentials {
-                Some(_) => Ok(CredentialsNeeded::Cleartext),
-                None => Ok(CredentialsNeeded::None),
-            }
+            Ok(self.needed_credentials)
         }
 
         fn version(&self) -> String {
             "14.5 ReadySet".to_string()
         }
 
-        async fn on_auth(&mut self, provided: Credentials) -> Result<(), Error> {
-            let needed_credentials = match &self.needed_credentials {
-                Some(n) => n,
-                None => return Ok(()),
-            };
+        fn password_for_user(&self, user: &str) -> Option<String> {
+            self.users.get(user).cloned()
+        }
 
-            match (needed_credentials, &provided) {
-                (needed, provided) if needed == provided => Ok(()),
-                (Credentials::Cleartext { .. }, Credentials::Cleartext { user, .. }) => {
-                    Err(Error::AuthenticationFailure(user.to_owned()))
-                }
-            }
+        fn backend_key_data(&self) -> Option<BackendKeyData> {
+            self.backend_key_data
+        }
+
+        async fn on_cancel(&mut self, key: BackendKeyData) {
+            self.last_cancel = Some(key);
         }
 
         async fn on_query(&mut self, query: &str) -> Result<QueryResponse<Self::Resultset>, Error> {
             self.last_query = Some(query.to_string());
             if self.is_query_err {
                 Err(Error::InternalError("error requested".to_string()))
+            } else if self.is_query_copy {
+                Ok(QueryResponse::CopyOut {
+                    format: TransferFormat::Binary,
+                    schema: vec![Column {
+                        name: "col1".to_string(),
+                        col_type: Type::INT4,
+                    }],
+                    resultset: vec![vec![Value(DataValue::Int(88))]],
+                })
             } else if self.is_query_read {
                 Ok(QueryResponse::Select {
                     schema: vec![
@@ -962,6 +1198,20 @@ mod tests {
         );
     }
 
+    #[test]
+    fn ssl_request_allowed() {
+        let mut protocol = Protocol::new();
+        protocol.set_allow_tls(true);
+        let request = FrontendMessage::SSLRequest;
+        let mut backend = Backend::new();
+        let mut channel = Channel::<NullBytestream, Vec<Value>>::new(NullBytestream);
+        assert_eq!(
+            block_on(protocol.on_request(request, &mut backend, &mut channel)).unwrap(),
+            Response::Message(BackendMessage::ssl_response_s())
+        );
+        assert_eq!(protocol.state, State::StartingUp);
+    }
+
     #[test]
     fn startup_message() {
         let mut protocol = Protocol::new();
@@ -1019,10 +1269,10 @@ mod tests {
             database: Some(bytes_str("database_name")),
         };
         let mut backend = Backend::new();
-        backend.needed_credentials = Some(Credentials::Cleartext {
-            user: expected_username.to_string(),
-            password: expected_password.to_string(),
-        });
+        backend.needed_credentials = CredentialsNeeded::Cleartext;
+        backend
+            .users
+            .insert(expected_username.to_string(), expected_password.to_string());
         let mut channel = Channel::<NullBytestream, Vec<Value>>::new(NullBytestream);
         assert_eq!(
             block_on(protocol.on_request(request, &mut backend, &mut channel)).unwrap(),
@@ -1031,7 +1281,8 @@ mod tests {
         assert_eq!(
             protocol.state,
             State::Authenticating {
-                user: expected_username
+                user: expected_username,
+                step: AuthenticationStep::Cleartext,
             }
         );
 
@@ -1081,10 +1332,10 @@ mod tests {
             database: Some(bytes_str("database_name")),
         };
         let mut backend = Backend::new();
-        backend.needed_credentials = Some(Credentials::Cleartext {
-            user: expected_username.to_string(),
-            password: expected_password.to_string(),
-        });
+        backend.needed_credentials = CredentialsNeeded::Cleartext;
+        backend
+            .users
+            .insert(expected_username.to_string(), expected_password.to_string());
         let mut channel = Channel::<NullBytestream, Vec<Value>>::new(NullBytestream);
         assert_eq!(
             block_on(protocol.on_request(request, &mut backend, &mut channel)).unwrap(),
@@ -1093,7 +1344,8 @@ mod tests {
         assert_eq!(
             protocol.state,
             State::Authenticating {
-                user: expected_username.clone()
+                user: expected_username.clone(),
+                step: AuthenticationStep::Cleartext,
             }
         );
 
@@ -1108,6 +1360,176 @@ mod tests {
         );
     }
 
+    #[test]
+    fn authentication_flow_scram_sha_256() {
+        let mut protocol = Protocol::new();
+        let request = FrontendMessage::StartupMessage {
+            protocol_version: 12345,
+            user: Some(bytes_str("user_name")),
+            database: Some(bytes_str("database_name")),
+        };
+        let mut backend = Backend::new();
+        backend.needed_credentials = CredentialsNeeded::ScramSha256;
+        backend.users.insert(
+            "user_name".to_owned(),
+            postgres_protocol::password::scram_sha_256(b"password"),
+        );
+        let mut channel = Channel::<NullBytestream, Vec<Value>>::new(NullBytestream);
+        assert_eq!(
+            block_on(protocol.on_request(request, &mut backend, &mut channel)).unwrap(),
+            Response::Messages(smallvec![BackendMessage::AuthenticationSASL {
+                allowed_mechanisms: vec!["SCRAM-SHA-256".to_owned()]
+            }])
+        );
+
+        let mut client = ScramSha256::new(b"password", ChannelBinding::unsupported());
+        let initial_response = FrontendMessage::SASLInitialResponse {
+            sasl_mechanism: bytes_str("SCRAM-SHA-256"),
+            sasl_data: Bytes::copy_from_slice(client.message()),
+        };
+        match block_on(protocol.on_request(initial_response, &mut backend, &mut channel)).unwrap() {
+            Response::Message(BackendMessage::AuthenticationSASLContinue { sasl_data }) => {
+                client.update(&sasl_data).unwrap()
+            }
+            r => panic!("Unexpected response {:?}", r),
+        }
+
+        let response = FrontendMessage::SASLResponse {
+            sasl_data: Bytes::copy_from_slice(client.message()),
+        };
+        match block_on(protocol.on_request(response, &mut backend, &mut channel)).unwrap() {
+            Response::Messages(messages) => match &messages[..] {
+                [BackendMessage::AuthenticationSASLFinal { sasl_data }, rest @ ..] => {
+                    assert_eq!(rest.first(), Some(&BackendMessage::AuthenticationOk));
+                    client.finish(sasl_data).unwrap()
+                }
+                m => panic!("Unexpected messages {:?}", m),
+            },
+            r => panic!("Unexpected response {:?}", r),
+        }
+        assert_eq!(protocol.state, State::Ready);
+    }
+
+    #[test]
+    fn authentication_flow_scram_sha_256_failure() {
+        let mut protocol = Protocol::new();
+        let request = FrontendMessage::StartupMessage {
+            protocol_version: 12345,
+            user: Some(bytes_str("user_name")),
+            database: Some(bytes_str("database_name")),
+        };
+        let mut backend = Backend::new();
+        backend.needed_credentials = CredentialsNeeded::ScramSha256;
+        backend
+            .users
+            .insert("user_name".to_owned(), "password".to_owned());
+        let mut channel = Channel::<NullBytestream, Vec<Value>>::new(NullBytestream);
+        block_on(protocol.on_request(request, &mut backend, &mut channel)).unwrap();
+
+        let mut client = ScramSha256::new(b"incorrect password", ChannelBinding::unsupported());
+        let initial_response = FrontendMessage::SASLInitialResponse {
+            sasl_mechanism: bytes_str("SCRAM-SHA-256"),
+            sasl_data: Bytes::copy_from_slice(client.message()),
+        };
+        match block_on(protocol.on_request(initial_response, &mut backend, &mut channel)).unwrap() {
+            Response::Message(BackendMessage::AuthenticationSASLContinue { sasl_data }) => {
+                client.update(&sasl_data).unwrap()
+            }
+            r => panic!("Unexpected response {:?}", r),
+        }
+
+        let response = FrontendMessage::SASLResponse {
+            sasl_data: Bytes::copy_from_slice(client.message()),
+        };
+        let output =
+            block_on(protocol.on_request(response, &mut backend, &mut channel)).unwrap_err();
+        assert!(matches!(output, Error::AuthenticationFailure(x) if x == "user_name"));
+    }
+
+    #[test]
+    fn authentication_flow_md5() {
+        let mut protocol = Protocol::new();
+        let request = FrontendMessage::StartupMessage {
+            protocol_version: 12345,
+            user: Some(bytes_str("user_name")),
+            database: Some(bytes_str("database_name")),
+        };
+        let mut backend = Backend::new();
+        backend.needed_credentials = CredentialsNeeded::Md5;
+        backend
+            .users
+            .insert("user_name".to_owned(), "password".to_owned());
+        let mut channel = Channel::<NullBytestream, Vec<Value>>::new(NullBytestream);
+        let salt = match block_on(protocol.on_request(request, &mut backend, &mut channel)).unwrap()
+        {
+            Response::Messages(messages) => match &messages[..] {
+                [BackendMessage::AuthenticationMD5Password { salt }] => *salt,
+                m => panic!("Unexpected messages {:?}", m),
+            },
+            r => panic!("Unexpected response {:?}", r),
+        };
+
+        let auth_request = FrontendMessage::PasswordMessage {
+            password: bytes_str(&md5_hash(b"user_name", b"password", salt)),
+        };
+        block_on(protocol.on_request(auth_request, &mut backend, &mut channel)).unwrap();
+        assert_eq!(protocol.state, State::Ready);
+    }
+
+    #[test]
+    fn startup_message_with_backend_key_data() {
+        let mut protocol = Protocol::new();
+        let request = FrontendMessage::StartupMessage {
+            protocol_version: 12345,
+            user: Some(bytes_str("user_name")),
+            database: Some(bytes_str("database_name")),
+        };
+        let mut backend = Backend::new();
+        backend.backend_key_data = Some(BackendKeyData {
+            process_id: 42,
+            secret_key: -7,
+        });
+        let mut channel = Channel::<NullBytestream, Vec<Value>>::new(NullBytestream);
+        let response = match block_on(protocol.on_request(request, &mut backend, &mut channel)) {
+            Ok(Response::Messages(messages)) => messages,
+            r => panic!("unexpected response: {:?}", r),
+        };
+        // The key is sent just before the connection becomes ready for queries.
+        assert_eq!(
+            response[response.len() - 2..],
+            [
+                BackendMessage::BackendKeyData {
+                    process_id: 42,
+                    secret_key: -7,
+                },
+                BackendMessage::ready_for_query_idle(),
+            ]
+        );
+    }
+
+    #[test]
+    fn cancel_request() {
+        let mut protocol = Protocol::new();
+        let request = FrontendMessage::CancelRequest {
+            process_id: 42,
+            secret_key: -7,
+        };
+        let mut backend = Backend::new();
+        let mut channel = Channel::<NullBytestream, Vec<Value>>::new(NullBytestream);
+        // A CancelRequest is forwarded to the backend, and has no response.
+        assert_eq!(
+            block_on(protocol.on_request(request, &mut backend, &mut channel)).unwrap(),
+            Response::Empty
+        );
+        assert_eq!(
+            backend.last_cancel,
+            Some(BackendKeyData {
+                process_id: 42,
+                secret_key: -7,
+            })
+        );
+    }
+
     #[test]
     fn startup_message_without_database() {
         let mut protocol = Protocol::new();
@@ -1300,6 +1722,37 @@ mod tests {
         assert_eq!(backend.last_query.unwrap(), "DELETE * FROM test;");
     }
 
+    #[test]
+    fn query_copy_out() {
+        let mut protocol = Protocol::new();
+        let mut backend = Backend::new();
+        backend.is_query_copy = true;
+        let mut channel = Channel::<NullBytestream, Vec<Value>>::new(NullBytestream);
+
+        let startup_request = FrontendMessage::StartupMessage {
+            protocol_version: 12345,
+            user: Some(bytes_str("user_name")),
+            database: Some(bytes_str("database_name")),
+        };
+        block_on(protocol.on_request(startup_request, &mut backend, &mut channel)).unwrap();
+
+        let request = FrontendMessage::Query {
+            query: bytes_str("COPY (SELECT * FROM test) TO STDOUT (FORMAT binary)"),
+        };
+        assert_eq!(
+            block_on(protocol.on_request(request, &mut backend, &mut channel)).unwrap(),
+            Response::CopyOut {
+                header: CopyOutResponse {
+                    format: TransferFormat::Binary,
+                    column_formats: vec![TransferFormat::Binary],
+                },
+                resultset: vec![vec![Value(DataValue::Int(88))]],
+                format: TransferFormat::Binary,
+                trailer: Some(BackendMessage::ready_for_query_idle())
+            }
+        );
+    }
+
     #[test]
     fn parse() {
         let mut protocol = Protocol::new();
@@ -1411,7 +1864,8 @@ mod tests {
                 result_transfer_formats: Arc::new(vec![
                     TransferFormat::Text,
                     TransferFormat::Binary
-                ])
+                ]),
+                suspended_rows: None
             }
         );
     }
@@ -1455,7 +1909,8 @@ mod tests {
                 prepared_statement_name: "prepared1".to_string(),
                 params: vec![DataValue::Double(0.8887), DataValue::Int(45678)],
                 // The transfer formats are set to the default value (Text).
-                result_transfer_formats: Arc::new(vec![TransferFormat::Text, TransferFormat::Text])
+                result_transfer_formats: Arc::new(vec![TransferFormat::Text, TransferFormat::Text]),
+                suspended_rows: None
             }
         );
     }
@@ -1502,7 +1957,8 @@ mod tests {
                 result_transfer_formats: Arc::new(vec![
                     TransferFormat::Binary,
                     TransferFormat::Binary
-                ])
+                ]),
+                suspended_rows: None
             }
         );
     }
@@ -1913,6 +2369,82 @@ mod tests {
         );
     }
 
+    #[test]
+    fn execute_read_with_limit() {
+        let mut protocol = Protocol::new();
+        let mut backend = Backend::new();
+        let mut channel = Channel::<NullBytestream, Vec<Value>>::new(NullBytestream);
+
+        let startup_request = FrontendMessage::StartupMessage {
+            protocol_version: 12345,
+            user: Some(bytes_str("user_name")),
+            database: Some(bytes_str("database_name")),
+        };
+        block_on(protocol.on_request(startup_request, &mut backend, &mut channel)).unwrap();
+
+        let parse_request = FrontendMessage::Parse {
+            prepared_statement_name: bytes_str("prepared1"),
+            query: bytes_str("SELECT * FROM test WHERE x = $1 AND y = $2;"),
+            parameter_data_types: vec![],
+        };
+        block_on(protocol.on_request(parse_request, &mut backend, &mut channel)).unwrap();
+
+        let bind_request = FrontendMessage::Bind {
+            prepared_statement_name: bytes_str("prepared1"),
+            portal_name: bytes_str("portal1"),
+            params: vec![DataValue::Double(0.8887), DataValue::Int(45678)],
+            result_transfer_formats: vec![TransferFormat::Text],
+        };
+        block_on(protocol.on_request(bind_request, &mut backend, &mut channel)).unwrap();
+
+        // Executing the portal with a limit returns that many rows, and suspends the portal.
+        let request = FrontendMessage::Execute {
+            portal_name: bytes_str("portal1"),
+            limit: 1,
+        };
+        assert_eq!(
+            block_on(protocol.on_request(request, &mut backend, &mut channel)).unwrap(),
+            Response::PortalRows {
+                rows: vec![vec![
+                    Value(DataValue::Int(88)),
+                    Value(DataValue::Double(0.123))
+                ]],
+                result_transfer_formats: Some(Arc::new(vec![
+                    TransferFormat::Text,
+                    TransferFormat::Text
+                ])),
+                suspended: true
+            }
+        );
+        assert_eq!(backend.last_execute_id.take(), Some(0));
+
+        // Executing the portal again resumes from where it stopped, without executing the
+        // prepared statement again, and completes once all the rows have been returned.
+        let request = FrontendMessage::Execute {
+            portal_name: bytes_str("portal1"),
+            limit: 1,
+        };
+        assert_eq!(
+            block_on(protocol.on_request(request, &mut backend, &mut channel)).unwrap(),
+            Response::PortalRows {
+                rows: vec![vec![
+                    Value(DataValue::Int(22)),
+                    Value(DataValue::Double(0.456))
+                ]],
+                result_transfer_formats: Some(Arc::new(vec![
+                    TransferFormat::Text,
+                    TransferFormat::Text
+                ])),
+                suspended: false
+            }
+        );
+        assert_eq!(backend.last_execute_id, None);
+        assert_eq!(
+            protocol.portals.get("portal1").unwrap().suspended_rows,
+            None
+        );
+    }
+
     #[test]
     fn execute_error() {
         let mut protocol = Protocol::new();
@@ -1,4 +1,5 @@
 use std::convert::TryInto;
+use std::fmt;
 use std::sync::Arc;
 
 use futures::prelude::*;
@@ -7,7 +8,9 @@ use smallvec::SmallVec;
 use crate::codec::EncodeError;
 use crate::error::Error;
 use crate::message::{BackendMessage, CommandCompleteTag, TransferFormat};
+use crate::protocol::make_error_response;
 use crate::value::Value;
+use crate::PassThroughCopyData;
 
 /// An encapsulation of a complete response produced by a Postgresql backend in response to a
 /// request. The response will be sent to the frontend as a sequence of zero or more
@@ -28,8 +31,53 @@ pub enum Response<R, S> {
         result_transfer_formats: Option<Arc<Vec<TransferFormat>>>,
         trailer: Option<BackendMessage<R>>,
     },
+
+    /// A batch of rows read from a portal that was executed with a row limit. If `suspended` is
+    /// set, the portal has rows left to return, and the batch ends with `PortalSuspended` rather
+    /// than `CommandComplete`.
+    PortalRows {
+        rows: Vec<R>,
+        result_transfer_formats: Option<Arc<Vec<TransferFormat>>>,
+        suspended: bool,
+    },
+
+    /// The rows of a `COPY ... TO STDOUT`, to be sent to the frontend as `CopyData` messages in
+    /// the given `COPY` format.
+    CopyOut {
+        header: BackendMessage<R>,
+        resultset: S,
+        format: TransferFormat,
+        trailer: Option<BackendMessage<R>>,
+    },
+
+    /// The output of a `COPY ... TO STDOUT` proxied to another database, to be forwarded to the
+    /// frontend in `CopyData` messages as it arrives.
+    PassThroughCopyOut {
+        header: BackendMessage<R>,
+        data: CopyDataStream,
+        trailer: Option<BackendMessage<R>>,
+    },
 }
 
+/// The data of a [`Response::PassThroughCopyOut`]. Streams are never equal to one another.
+pub struct CopyDataStream(pub Box<dyn PassThroughCopyData>);
+
+impl fmt::Debug for CopyDataStream {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("CopyDataStream")
+            .field("num_rows", &self.0.num_rows())
+            .finish_non_exhaustive()
+    }
+}
+
+impl PartialEq for CopyDataStream {
+    fn eq(&self, _other: &Self) -> bool {
+        false
+    }
+}
+
+impl Eq for CopyDataStream {}
+
 impl<R, S> Response<R, S>
 where
     R: IntoIterator<Item: TryInto<Value, Error = Error>>,
@@ -88,6 +136,103 @@ where
 
                 sink.flush().await
             }
+
+            PortalRows {
+                rows,
+                result_transfer_formats,
+                suspended,
+            } => {
+                let n_rows = rows.len() as u64;
+                for r in rows {
+                    sink.feed(BackendMessage::DataRow {
+                        values: r,
+                        explicit_transfer_formats: result_transfer_formats.clone(),
+                    })
+                    .await?;
+                }
+
+                if suspended {
+                    sink.feed(BackendMessage::PortalSuspended).await?;
+                } else {
+                    sink.feed(BackendMessage::CommandComplete {
+                        tag: CommandCompleteTag::Select(n_rows),
+                    })
+                    .await?;
+                }
+
+                sink.flush().await
+            }
+
+            CopyOut {
+                header,
+                resultset,
+                format,
+                trailer,
+            } => {
+                sink.feed(header).await?;
+                if format == TransferFormat::Binary {
+                    sink.feed(BackendMessage::copy_binary_header()).await?;
+                }
+
+                let mut n_rows = 0;
+                for r in resultset {
+                    sink.feed(BackendMessage::CopyDataRow { values: r, format })
+                        .await?;
+                    n_rows += 1;
+                }
+
+                if format == TransferFormat::Binary {
+                    sink.feed(BackendMessage::copy_binary_trailer()).await?;
+                }
+                sink.feed(BackendMessage::CopyDone).await?;
+                sink.feed(BackendMessage::CommandComplete {
+                    tag: CommandCompleteTag::CopyOut(n_rows),
+                })
+                .await?;
+
+                if let Some(trailer) = trailer {
+                    sink.feed(trailer).await?;
+                }
+
+                sink.flush().await
+            }
+
+            PassThroughCopyOut {
+                header,
+                mut data,
+                trailer,
+            } => {
+                sink.feed(header).await?;
+
+                let mut error = None;
+                while let Some(chunk) = data.0.next().await {
+                    match chunk {
+                        Ok(chunk) => sink.feed(BackendMessage::CopyData { data: chunk }).await?,
+                        Err(e) => {
+                            error = Some(e);
+                            break;
+                        }
+                    }
+                }
+
+                match error {
+                    // As in PostgreSQL, an error ends the copy without a `CopyDone`
+                    Some(error) => sink.feed(make_error_response(error)).await?,
+                    None => {
+                        sink.feed(BackendMessage::CopyDone).await?;
+                        sink.feed(BackendMessage::CommandComplete {
+                            tag: CommandCompleteTag::CopyOut(data.0.num_rows()),
+                        })
+                        .await?;
+                    }
+                }
+
+                if let Some(trailer) = trailer {
+                    sink.feed(trailer).await?;
+                }
+
+                sink.flush().await
+            }
         }
     }
 }
@@ -258,4 +403,209 @@ mod tests {
         futures::pin_mut!(validating_sink);
         block_on(response.write(&mut validating_sink)).unwrap();
     }
+
+    #[test]
+    fn write_portal_rows_suspended() {
+        let response = Response::<Vec<Value>, Vec<Vec<Value>>>::PortalRows {
+            rows: vec![vec![Value(DataValue::Int(5))]],
+            result_transfer_formats: None,
+            suspended: true,
+        };
+        let validating_sink = sink::unfold(0, |i, m: BackendMessage<Vec<Value>>| {
+            async move {
+                match i {
+                    0 => assert_eq!(
+                        m,
+                        BackendMessage::DataRow {
+                            values: vec![Value(DataValue::Int(5))],
+                            explicit_transfer_formats: None
+                        }
+                    ),
+                    1 => assert_eq!(m, BackendMessage::PortalSuspended),
+                    // No further messages are expected.
+                    _ => assert!(false),
+                }
+                Ok::<_, EncodeError>(i + 1)
+            }
+        });
+        futures::pin_mut!(validating_sink);
+        block_on(response.write(&mut validating_sink)).unwrap();
+    }
+
+    #[test]
+    fn write_portal_rows_complete() {
+        let response = Response::<Vec<Value>, Vec<Vec<Value>>>::PortalRows {
+            rows: vec![vec![Value(DataValue::Int(5))]],
+            result_transfer_formats: None,
+            suspended: false,
+        };
+        let validating_sink = sink::unfold(0, |i, m: BackendMessage<Vec<Value>>| {
+            async move {
+                match i {
+                    0 => assert_eq!(
+                        m,
+                        BackendMessage::DataRow {
+                            values: vec![Value(DataValue::Int(5))],
+                            explicit_transfer_formats: None
+                        }
+                    ),
+                    1 => assert_eq!(
+                        m,
+                        BackendMessage::CommandComplete {
+                            tag: CommandCompleteTag::Select(1)
+                        }
+                    ),
+                    // No further messages are expected.
+                    _ => assert!(false),
+                }
+                Ok::<_, EncodeError>(i + 1)
+            }
+        });
+        futures::pin_mut!(validating_sink);
+        block_on(response.write(&mut validating_sink)).unwrap();
+    }
+
+    #[test]
+    fn write_copy_out_binary() {
+        let header = BackendMessage::CopyOutResponse {
+            format: TransferFormat::Binary,
+            column_formats: vec![TransferFormat::Binary],
+        };
+        let response = Response::<Vec<Value>, Vec<Vec<Value>>>::CopyOut {
+            header,
+            resultset: vec![vec![Value(DataValue::Int(5))]],
+            format: TransferFormat::Binary,
+            trailer: Some(BackendMessage::ready_for_query_idle()),
+        };
+        let validating_sink = sink::unfold(0, |i, m: BackendMessage<Vec<Value>>| {
+            async move {
+                match i {
+                    0 => assert_eq!(
+                        m,
+                        BackendMessage::CopyOutResponse {
+                            format: TransferFormat::Binary,
+                            column_formats: vec![TransferFormat::Binary],
+                        }
+                    ),
+                    1 => assert_eq!(m, BackendMessage::copy_binary_header()),
+                    2 => assert_eq!(
+                        m,
+                        BackendMessage::CopyDataRow {
+                            values: vec![Value(DataValue::Int(5))],
+                            format: TransferFormat::Binary
+                        }
+                    ),
+                    3 => assert_eq!(m, BackendMessage::copy_binary_trailer()),
+                    4 => assert_eq!(m, BackendMessage::CopyDone),
+                    5 => assert_eq!(
+                        m,
+                        BackendMessage::CommandComplete {
+                            tag: CommandCompleteTag::CopyOut(1)
+                        }
+                    ),
+                    6 => assert_eq!(m, BackendMessage::ready_for_query_idle()),
+                    // No further messages are expected.
+                    _ => assert!(false),
+                }
+                Ok::<_, EncodeError>(i + 1)
+            }
+        });
+        futures::pin_mut!(validating_sink);
+        block_on(response.write(&mut validating_sink)).unwrap();
+    }
+
+    /// Passes through chunks of data, counting one row per chunk
+    struct TestCopyData {
+        chunks: stream::Iter<std::vec::IntoIter<Result<::bytes::Bytes, Error>>>,
+        num_rows: u64,
+    }
+
+    impl Stream for TestCopyData {
+        type Item = Result<::bytes::Bytes, Error>;
+
+        fn poll_next(
+            mut self: std::pin::Pin<&mut Self>,
+            cx: &mut std::task::Context<'_>,
+        ) -> std::task::Poll<Option<Self::Item>> {
+            let res = self.chunks.poll_next_unpin(cx);
+            if let std::task::Poll::Ready(Some(Ok(_))) = res {
+                self.num_rows += 1;
+            }
+            res
+        }
+    }
+
+    impl PassThroughCopyData for TestCopyData {
+        fn num_rows(&self) -> u64 {
+            self.num_rows
+        }
+    }
+
+    fn pass_through_copy_out(
+        chunks: Vec<Result<::bytes::Bytes, Error>>,
+    ) -> Response<Vec<Value>, Vec<Vec<Value>>> {
+        Response::PassThroughCopyOut {
+            header: BackendMessage::CopyOutResponse {
+                format: TransferFormat::Text,
+                column_formats: vec![TransferFormat::Text],
+            },
+            data: CopyDataStream(Box::new(TestCopyData {
+                chunks: stream::iter(chunks),
+                num_rows: 0,
+            })),
+            trailer: Some(BackendMessage::ready_for_query_idle()),
+        }
+    }
+
+    #[test]
+    fn write_pass_through_copy_out() {
+        let response = pass_through_copy_out(vec![Ok("1\n".into()), Ok("2\n".into())]);
+        let validating_sink = sink::unfold(0, |i, m: BackendMessage<Vec<Value>>| {
+            async move {
+                match i {
+                    0 => assert!(matches!(m, BackendMessage::CopyOutResponse { .. })),
+                    1 => assert_eq!(m, BackendMessage::CopyData { data: "1\n".into() }),
+                    2 => assert_eq!(m, BackendMessage::CopyData { data: "2\n".into() }),
+                    3 => assert_eq!(m, BackendMessage::CopyDone),
+                    4 => assert_eq!(
+                        m,
+                        BackendMessage::CommandComplete {
+                            tag: CommandCompleteTag::CopyOut(2)
+                        }
+                    ),
+                    5 => assert_eq!(m, BackendMessage::ready_for_query_idle()),
+                    // No further messages are expected.
+                    _ => assert!(false),
+                }
+                Ok::<_, EncodeError>(i + 1)
+            }
+        });
+        futures::pin_mut!(validating_sink);
+        block_on(response.write(&mut validating_sink)).unwrap();
+    }
+
+    #[test]
+    fn write_pass_through_copy_out_error() {
+        let response = pass_through_copy_out(vec![
+            Ok("1\n".into()),
+            Err(Error::InternalError("upstream went away".to_string())),
+            Ok("2\n".into()),
+        ]);
+        let validating_sink = sink::unfold(0, |i, m: BackendMessage<Vec<Value>>| {
+            async move {
+                match i {
+                    0 => assert!(matches!(m, BackendMessage::CopyOutResponse { .. })),
+                    1 => assert_eq!(m, BackendMessage::CopyData { data: "1\n".into() }),
+                    // The copy ends at the error, without a CopyDone
+                    2 => assert!(matches!(m, BackendMessage::ErrorResponse { .. })),
+                    3 => assert_eq!(m, BackendMessage::ready_for_query_idle()),
+                    // No further messages are expected.
+                    _ => assert!(false),
+                }
+                Ok::<_, EncodeError>(i + 1)
+            }
+        });
+        futures::pin_mut!(validating_sink);
+        block_on(response.write(&mut validating_sink)).unwrap();
+    }
 }
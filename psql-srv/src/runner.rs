@@ -1,42 +1,151 @@
+use std::sync::Arc;
+
+use futures::future::{self, Either};
+use futures::pin_mut;
 use tokio::io::{AsyncRead, AsyncWrite};
+use tokio_native_tls::{TlsAcceptor, TlsStream};
 
 use crate::channel::Channel;
 use crate::error::Error;
-use crate::message::FrontendMessage;
+use crate::message::{BackendMessage, FrontendMessage};
 use crate::protocol::Protocol;
-use crate::{codec, Backend};
+use crate::response::Response;
+use crate::{codec, Backend, Notification};
 
 /// A helper struct that can be used to run a `Protocol` on a `Backend` and `Channel`.
 pub struct Runner<B: Backend, C> {
     backend: B,
     channel: Channel<C, B::Row>,
-    protocol: Protocol,
+    protocol: Protocol<B::Row>,
 }
 
 impl<B: Backend, C: AsyncRead + AsyncWrite + Unpin> Runner<B, C> {
     /// A simple run loop. For each `FrontendMessage` received on `channel`, use `protocol` to
     /// generate a response. Then send the response. If an error occurs, use `protocol` to generate
     /// an error response, then send the error response.
-    pub async fn run(backend: B, byte_channel: C) {
+    ///
+    /// If `tls_acceptor` is set, and the first message received on `channel` is an `SSLRequest`,
+    /// the connection is upgraded to TLS before the loop continues. If `require_tls` is also set,
+    /// any other first message except a `CancelRequest` is answered with an error, and the
+    /// connection is closed.
+    pub async fn run(
+        backend: B,
+        byte_channel: C,
+        tls_acceptor: Option<Arc<TlsAcceptor>>,
+        require_tls: bool,
+    ) {
+        let mut protocol = Protocol::new();
+        protocol.set_allow_tls(tls_acceptor.is_some());
         let mut runner = Runner {
             backend,
             channel: Channel::new(byte_channel),
-            protocol: Protocol::new(),
+            protocol,
         };
 
-        while let Some(message) = runner.channel.next().await {
-            match runner.handle_request(message).await {
-                Ok(_) => {}
-                Err(e) => {
+        if let Some(tls_acceptor) = tls_acceptor {
+            // A frontend that wants to use TLS sends an SSLRequest as its very first message, and
+            // starts the TLS handshake once we've accepted it.
+            match runner.channel.next().await {
+                Some(Ok(FrontendMessage::SSLRequest)) => {
+                    if let Err(e) = runner.handle_request(Ok(FrontendMessage::SSLRequest)).await {
+                        eprintln!("{}", e);
+                        return;
+                    }
+                    match runner.upgrade(&tls_acceptor).await {
+                        Ok(runner) => runner.run_loop().await,
+                        Err(e) => eprintln!("{}", e),
+                    }
+                    return;
+                }
+                // Most frontends send cancel requests unencrypted on a new connection, even if
+                // the connection running the query uses TLS
+                Some(message @ Ok(FrontendMessage::CancelRequest { .. })) => {
+                    runner.handle_message(message).await;
+                    return;
+                }
+                Some(_) if require_tls => {
                     runner
-                        .handle_error(e)
+                        .handle_error(Error::TlsRequired)
                         .await
                         .unwrap_or_else(|e| eprintln!("{}", e));
+                    return;
+                }
+                Some(message) => {
+                    if !runner.handle_message(message).await {
+                        return;
+                    }
+                }
+                None => return,
+            }
+        }
+
+        runner.run_loop().await
+    }
+
+    async fn run_loop(mut self) {
+        loop {
+            // Notifications for the channels the frontend is listening on can arrive at any time,
+            // so we send them as they come in while waiting for the next message
+            let next = {
+                let next_message = self.channel.next();
+                pin_mut!(next_message);
+                match future::select(next_message, self.backend.next_notification()).await {
+                    Either::Left((message, _)) => Either::Left(message),
+                    Either::Right((notification, _)) => Either::Right(notification),
                 }
             };
+
+            match next {
+                Either::Left(Some(message)) => {
+                    if !self.handle_message(message).await {
+                        break;
+                    }
+                }
+                Either::Left(None) => break,
+                Either::Right(notification) => {
+                    if let Err(e) = self.handle_notification(notification).await {
+                        eprintln!("{}", e);
+                        break;
+                    }
+                }
+            }
         }
     }
 
+    /// Upgrade the connection to TLS by performing the TLS handshake with the frontend
+    async fn upgrade(self, tls_acceptor: &TlsAcceptor) -> Result<Runner<B, TlsStream<C>>, Error> {
+        let Runner {
+            backend,
+            channel,
+            protocol,
+        } = self;
+        let stream = tls_acceptor.accept(channel.into_inner()?).await?;
+        Ok(Runner {
+            backend,
+            channel: Channel::new(stream),
+            protocol,
+        })
+    }
+
+    /// Handle a single message from the frontend, returning whether the connection should be kept
+    /// open
+    async fn handle_message(
+        &mut self,
+        message: Result<FrontendMessage, codec::DecodeError>,
+    ) -> bool {
+        // Frontends send a CancelRequest on a new connection, and wait for it to be closed
+        let keep_open = !matches!(message, Ok(FrontendMessage::CancelRequest { .. }));
+        match self.handle_request(message).await {
+            Ok(_) => {}
+            Err(e) => {
+                self.handle_error(e)
+                    .await
+                    .unwrap_or_else(|e| eprintln!("{}", e));
+            }
+        };
+        keep_open
+    }
+
     async fn handle_request(
         &mut self,
         request: Result<FrontendMessage, codec::DecodeError>,
@@ -53,6 +162,23 @@ impl<B: Backend, C: AsyncRead + AsyncWrite + Unpin> Runner<B, C> {
         Ok(())
     }
 
+    async fn handle_notification(&mut self, notification: Notification) -> Result<(), Error> {
+        let process_id = self
+            .backend
+            .backend_key_data()
+            .map_or(0, |key| key.process_id);
+        self.channel
+            .send(Response::<B::Row, B::Resultset>::Message(
+                BackendMessage::NotificationResponse {
+                    process_id,
+                    channel: notification.channel,
+                    payload: notification.payload,
+                },
+            ))
+            .await?;
+        Ok(())
+    }
+
     async fn handle_error(&mut self, error: Error) -> Result<(), Error> {
         let response = self.protocol.on_error::<B>(error).await?;
         self.channel.send(response).await?;
@@ -1,4 +1,6 @@
+use std::fmt;
 use std::net::IpAddr;
+use std::str::FromStr;
 
 use bit_vec::BitVec;
 use chrono::{DateTime, FixedOffset, NaiveDate, NaiveDateTime, NaiveTime};
@@ -7,6 +9,8 @@ use readyset_data::{Array, Text};
 use rust_decimal::Decimal;
 use uuid::Uuid;
 
+use crate::codec::DecodeError;
+
 /// A PostgreSQL data value that can be received from, or sent to, a PostgreSQL frontend.
 #[derive(Clone, Debug, PartialEq)]
 pub enum Value {
@@ -27,14 +31,111 @@ pub enum Value {
     TimestampTz(DateTime<FixedOffset>),
     Date(NaiveDate),
     Time(NaiveTime),
+    Interval(Interval),
     ByteArray(Vec<u8>),
     MacAddress(MacAddress),
-    Inet(IpAddr),
+    Inet(IpNetwork),
+    Cidr(IpNetwork),
     Uuid(Uuid),
     Json(serde_json::Value),
     Jsonb(serde_json::Value),
     Bit(BitVec),
     VarBit(BitVec),
+    /// An amount of `money`, in hundredths of the currency unit
+    Money(i64),
+    /// An array, along with the array type (not the type of its elements)
     Array(Array, postgres_types::Type),
+    /// A range, along with the range type (not the type of its bounds)
+    Range(Range, postgres_types::Type),
     PassThrough(readyset_data::PassThrough),
 }
+
+/// A PostgreSQL `interval`, which is stored as separate numbers of months, days and microseconds
+/// since neither months nor days have a fixed length.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Interval {
+    pub months: i32,
+    pub days: i32,
+    pub microseconds: i64,
+}
+
+/// A PostgreSQL `inet` or `cidr` value: an IP address, along with the length of the network mask
+/// in bits.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct IpNetwork {
+    pub addr: IpAddr,
+    pub netmask: u8,
+}
+
+impl IpNetwork {
+    /// Returns the length in bits of a network mask covering the whole address
+    pub fn max_netmask(addr: IpAddr) -> u8 {
+        match addr {
+            IpAddr::V4(_) => 32,
+            IpAddr::V6(_) => 128,
+        }
+    }
+}
+
+impl From<IpAddr> for IpNetwork {
+    fn from(addr: IpAddr) -> Self {
+        IpNetwork {
+            addr,
+            netmask: IpNetwork::max_netmask(addr),
+        }
+    }
+}
+
+/// Formats the network the way PostgreSQL formats `inet` values, omitting the network mask if it
+/// covers the whole address
+impl fmt::Display for IpNetwork {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.netmask == IpNetwork::max_netmask(self.addr) {
+            write!(f, "{}", self.addr)
+        } else {
+            write!(f, "{}/{}", self.addr, self.netmask)
+        }
+    }
+}
+
+impl FromStr for IpNetwork {
+    type Err = DecodeError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (addr, netmask) = match s.split_once('/') {
+            Some((addr, netmask)) => (addr, Some(netmask)),
+            None => (s, None),
+        };
+        let addr = addr
+            .parse::<IpAddr>()
+            .map_err(DecodeError::InvalidTextIpAddressValue)?;
+        let max_netmask = IpNetwork::max_netmask(addr);
+        let netmask = match netmask {
+            Some(netmask) => netmask
+                .parse::<u8>()
+                .ok()
+                .filter(|netmask| *netmask <= max_netmask)
+                .ok_or_else(|| DecodeError::InvalidTextNetworkValue(s.to_owned()))?,
+            None => max_netmask,
+        };
+        Ok(IpNetwork { addr, netmask })
+    }
+}
+
+/// A PostgreSQL range value, such as an `int4range` or a `tstzrange`
+#[derive(Clone, Debug, PartialEq)]
+pub enum Range {
+    Empty,
+    NonEmpty {
+        lower: RangeBound,
+        upper: RangeBound,
+    },
+}
+
+/// One of the bounds of a [`Range`]
+#[derive(Clone, Debug, PartialEq)]
+pub enum RangeBound {
+    Inclusive(Box<Value>),
+    Exclusive(Box<Value>),
+    Unbounded,
+}
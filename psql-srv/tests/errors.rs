@@ -5,8 +5,7 @@ use futures::Future;
 use postgres::NoTls;
 use postgres_types::Type;
 use psql_srv::{
-    run_backend, Backend, Column, Credentials, CredentialsNeeded, Error, PrepareResponse,
-    QueryResponse,
+    run_backend, Backend, Column, CredentialsNeeded, Error, PrepareResponse, QueryResponse,
 };
 use tokio::join;
 use tokio::net::TcpListener;
@@ -44,8 +43,8 @@ impl Backend for ErrorBackend {
         Ok(CredentialsNeeded::None)
     }
 
-    async fn on_auth(&mut self, _credentials: Credentials) -> Result<(), Error> {
-        Ok(())
+    fn password_for_user(&self, _user: &str) -> Option<String> {
+        None
     }
 
     async fn on_query(&mut self, _query: &str) -> Result<QueryResponse<Self::Resultset>, Error> {
@@ -116,7 +115,7 @@ where
             .send(listener.local_addr().unwrap().port())
             .unwrap();
         let (socket, _) = listener.accept().await.unwrap();
-        run_backend(ErrorBackend(error_pos), socket).await;
+        run_backend(ErrorBackend(error_pos), socket, None, false).await;
     });
     let client = tokio::spawn(async move {
         let port = recv_port.await.unwrap();
@@ -174,7 +173,7 @@ async fn prepare_error_sync() {
             .send(listener.local_addr().unwrap().port())
             .unwrap();
         let (socket, _) = listener.accept().await.unwrap();
-        run_backend(ErrorBackend(ErrorPosition::Execute), socket).await;
+        run_backend(ErrorBackend(ErrorPosition::Execute), socket, None, false).await;
     });
     let port = recv_port.await.unwrap();
     tokio::task::spawn_blocking(move || {
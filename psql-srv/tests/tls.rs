@@ -0,0 +1,153 @@
+use std::convert::TryFrom;
+use std::sync::{Arc, Mutex};
+
+use async_trait::async_trait;
+use openssl::asn1::Asn1Time;
+use openssl::hash::MessageDigest;
+use openssl::pkey::PKey;
+use openssl::rsa::Rsa;
+use openssl::x509::{X509Builder, X509NameBuilder};
+use psql_srv::{
+    run_backend, Backend, BackendKeyData, CredentialsNeeded, Error, PrepareResponse, QueryResponse,
+    TlsAcceptor,
+};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+use tokio_native_tls::native_tls;
+
+struct Value(psql_srv::Value);
+
+impl TryFrom<Value> for psql_srv::Value {
+    type Error = Error;
+
+    fn try_from(v: Value) -> Result<Self, Self::Error> {
+        Ok(v.0)
+    }
+}
+
+/// A backend which records the cancel requests it receives
+#[derive(Clone, Default)]
+struct CancelBackend {
+    cancelled: Arc<Mutex<Option<BackendKeyData>>>,
+}
+
+#[async_trait]
+impl Backend for CancelBackend {
+    type Value = Value;
+    type Row = Vec<Value>;
+    type Resultset = Vec<Self::Row>;
+
+    async fn on_init(&mut self, _database: &str) -> Result<CredentialsNeeded, Error> {
+        Ok(CredentialsNeeded::None)
+    }
+
+    fn password_for_user(&self, _user: &str) -> Option<String> {
+        None
+    }
+
+    async fn on_cancel(&mut self, key: BackendKeyData) {
+        *self.cancelled.lock().unwrap() = Some(key);
+    }
+
+    async fn on_query(&mut self, _query: &str) -> Result<QueryResponse<Self::Resultset>, Error> {
+        Ok(QueryResponse::Select {
+            schema: vec![],
+            resultset: vec![],
+        })
+    }
+
+    async fn on_prepare(&mut self, _query: &str) -> Result<PrepareResponse, Error> {
+        Err(Error::Unsupported("prepare".to_owned()))
+    }
+
+    async fn on_execute(
+        &mut self,
+        _statement_id: u32,
+        _params: &[psql_srv::Value],
+    ) -> Result<QueryResponse<Self::Resultset>, Error> {
+        Err(Error::Unsupported("execute".to_owned()))
+    }
+
+    async fn on_close(&mut self, _statement_id: u32) -> Result<(), Error> {
+        Ok(())
+    }
+
+    fn version(&self) -> String {
+        "13.4 ReadySet".to_string()
+    }
+}
+
+/// Build an acceptor with a freshly generated self-signed certificate
+fn tls_acceptor() -> Arc<TlsAcceptor> {
+    let key = PKey::from_rsa(Rsa::generate(2048).unwrap()).unwrap();
+    let mut name = X509NameBuilder::new().unwrap();
+    name.append_entry_by_text("CN", "localhost").unwrap();
+    let name = name.build();
+    let mut cert = X509Builder::new().unwrap();
+    cert.set_version(2).unwrap();
+    cert.set_subject_name(&name).unwrap();
+    cert.set_issuer_name(&name).unwrap();
+    cert.set_pubkey(&key).unwrap();
+    cert.set_not_before(&Asn1Time::days_from_now(0).unwrap())
+        .unwrap();
+    cert.set_not_after(&Asn1Time::days_from_now(1).unwrap())
+        .unwrap();
+    cert.sign(&key, MessageDigest::sha256()).unwrap();
+    let identity = native_tls::Identity::from_pkcs8(
+        &cert.build().to_pem().unwrap(),
+        &key.private_key_to_pem_pkcs8().unwrap(),
+    )
+    .unwrap();
+    Arc::new(native_tls::TlsAcceptor::new(identity).unwrap().into())
+}
+
+/// Run `backend` requiring TLS on a single connection, sending it the raw `message` unencrypted,
+/// and return everything it sends back before closing the connection
+async fn send_without_tls(backend: CancelBackend, message: &[u8]) -> Vec<u8> {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    let server = tokio::spawn(async move {
+        let (socket, _) = listener.accept().await.unwrap();
+        run_backend(backend, socket, Some(tls_acceptor()), true).await;
+    });
+
+    let mut client = TcpStream::connect(addr).await.unwrap();
+    client.write_all(message).await.unwrap();
+    let mut response = vec![];
+    client.read_to_end(&mut response).await.unwrap();
+    server.await.unwrap();
+    response
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn cancel_request_without_tls() {
+    let backend = CancelBackend::default();
+    let mut message = vec![];
+    for field in [16, 80877102, 42, -7] {
+        message.extend_from_slice(&i32::to_be_bytes(field));
+    }
+
+    // The cancel request is handled, and the connection closed without a response
+    let response = send_without_tls(backend.clone(), &message).await;
+    assert!(response.is_empty(), "{response:?}");
+    assert_eq!(
+        *backend.cancelled.lock().unwrap(),
+        Some(BackendKeyData {
+            process_id: 42,
+            secret_key: -7,
+        })
+    );
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn startup_message_without_tls() {
+    let params = b"user\0postgres\0database\0noria\0\0";
+    let mut message = vec![];
+    message.extend_from_slice(&i32::to_be_bytes(8 + params.len() as i32));
+    message.extend_from_slice(&i32::to_be_bytes(196608));
+    message.extend_from_slice(params);
+
+    // Anything else is answered with an error
+    let response = send_without_tls(CancelBackend::default(), &message).await;
+    assert_eq!(response.first(), Some(&b'E'), "{response:?}");
+}
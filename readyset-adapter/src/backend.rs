@@ -7,7 +7,8 @@
 //! * `INSERT`, `DELETE`, `UPDATE` - on upstream
 //! * Anything inside a transaction - on upstream
 //! * Cached statements created with "always" - on ReadySet
-//! * `SELECT` - on ReadySet
+//! * `SELECT` - on ReadySet, unless the cache was created with a `MAX_STALENESS` bound that
+//!   ReadySet's replication lag can't be shown to be within
 //! * Anything that failed on ReadySet, or while a migration is ongoing - on upstream
 //!
 //! # The execution flow
@@ -70,7 +71,7 @@
 
 use std::borrow::Cow;
 use std::collections::hash_map::Entry;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::convert::{TryFrom, TryInto};
 use std::fmt::{self, Debug};
 use std::marker::PhantomData;
@@ -87,8 +88,9 @@ use nom_sql::{
 };
 use readyset::consistency::Timestamp;
 use readyset::query::*;
+use readyset::replication::ReplicationOffset;
 use readyset::results::Results;
-use readyset::{ColumnSchema, ViewCreateRequest};
+use readyset::{ColumnSchema, ViewCreateRequest, ViewSubscription};
 pub use readyset_client_metrics::QueryDestination;
 use readyset_client_metrics::{recorded, EventType, QueryExecutionEvent, SqlQueryType};
 use readyset_data::{DfType, DfValue};
@@ -109,9 +111,12 @@ pub use crate::upstream_database::UpstreamPrepare;
 use crate::{rewrite, QueryHandler, UpstreamDatabase, UpstreamDestination};
 
 pub mod noria_connector;
+mod replication_status;
+mod staleness;
 
 use self::noria_connector::MetaVariable;
-pub use self::noria_connector::NoriaConnector;
+pub use self::noria_connector::{NoriaConnector, ReadCanceler};
+pub use self::replication_status::{ReplicationStatus, ReplicationStatusRefresher};
 
 /// Query metadata used to plan query prepare
 #[allow(clippy::large_enum_variant)]
@@ -254,6 +259,8 @@ pub struct BackendBuilder {
     slowlog: bool,
     dialect: Dialect,
     users: HashMap<String, String>,
+    tls_required_users: HashSet<String>,
+    require_tls: bool,
     require_authentication: bool,
     ticket: Option<Timestamp>,
     timestamp_client: Option<TimestampClient>,
@@ -265,6 +272,8 @@ pub struct BackendBuilder {
     migration_mode: MigrationMode,
     query_max_failure_seconds: u64,
     fallback_recovery_seconds: u64,
+    read_your_writes_timeout: Option<Duration>,
+    replication_status: Option<Arc<ReplicationStatus>>,
     telemetry_sender: Option<TelemetrySender>,
 }
 
@@ -274,6 +283,8 @@ impl Default for BackendBuilder {
             slowlog: false,
             dialect: Dialect::MySQL,
             users: Default::default(),
+            tls_required_users: Default::default(),
+            require_tls: false,
             require_authentication: true,
             ticket: None,
             timestamp_client: None,
@@ -285,6 +296,8 @@ impl Default for BackendBuilder {
             migration_mode: MigrationMode::InRequestPath,
             query_max_failure_seconds: (i64::MAX / 1000) as u64,
             fallback_recovery_seconds: 0,
+            read_your_writes_timeout: None,
+            replication_status: None,
             telemetry_sender: None,
         }
     }
@@ -313,6 +326,8 @@ impl BackendBuilder {
             noria,
             upstream,
             users: self.users,
+            tls_required_users: self.tls_required_users,
+            require_tls: self.require_tls,
             query_log_sender: self.query_log_sender,
             last_query: None,
             state: BackendState {
@@ -322,6 +337,8 @@ impl BackendBuilder {
                 query_status_cache,
                 ticket: self.ticket,
                 timestamp_client: self.timestamp_client,
+                read_your_writes: Default::default(),
+                replication_status: self.replication_status,
             },
             settings: BackendSettings {
                 slowlog: self.slowlog,
@@ -334,6 +351,7 @@ impl BackendBuilder {
                 query_max_failure_duration: Duration::new(self.query_max_failure_seconds, 0),
                 query_log_ad_hoc_queries: self.query_log_ad_hoc_queries,
                 fallback_recovery_duration: Duration::new(self.fallback_recovery_seconds, 0),
+                read_your_writes_timeout: self.read_your_writes_timeout,
             },
             telemetry_sender: self.telemetry_sender,
             _query_handler: PhantomData,
@@ -360,11 +378,26 @@ impl BackendBuilder {
         self
     }
 
+    /// Sets the users allowed to connect to the db, as a map from username to password. For
+    /// PostgreSQL, passwords may also be stored as salted MD5 or SCRAM-SHA-256 verifiers, in the
+    /// format PostgreSQL stores in `pg_authid.rolpassword`.
     pub fn users(mut self, users: HashMap<String, String>) -> Self {
         self.users = users;
         self
     }
 
+    /// Sets the users who may only connect to the adapter over TLS
+    pub fn tls_required_users(mut self, tls_required_users: HashSet<String>) -> Self {
+        self.tls_required_users = tls_required_users;
+        self
+    }
+
+    /// Sets whether all users may only connect to the adapter over TLS
+    pub fn require_tls(mut self, require_tls: bool) -> Self {
+        self.require_tls = require_tls;
+        self
+    }
+
     pub fn require_authentication(mut self, require_authentication: bool) -> Self {
         self.require_authentication = require_authentication;
         self
@@ -411,6 +444,24 @@ impl BackendBuilder {
         self
     }
 
+    /// Enables read-your-writes consistency based on the position of writes in the upstream
+    /// database's replication log. If set, reads against ReadySet on a connection which has made
+    /// writes wait for up to the given duration for those writes to be replicated, and are
+    /// proxied to the upstream database if they haven't been by then.
+    pub fn read_your_writes_timeout(mut self, timeout: Option<Duration>) -> Self {
+        self.read_your_writes_timeout = timeout;
+        self
+    }
+
+    /// Sets the progress of replication shared by all the connections of the adapter, which is
+    /// kept up to date by a [`ReplicationStatusRefresher`]. Without it, reads of queries cached
+    /// with `CREATE CACHE ... WITH MAX_STALENESS`, and reads which need to wait for the writes made
+    /// on their connection to be replicated, are always proxied to the upstream database.
+    pub fn replication_status(mut self, replication_status: Arc<ReplicationStatus>) -> Self {
+        self.replication_status = Some(replication_status);
+        self
+    }
+
     pub fn telemetry_sender(mut self, telemetry_sender: TelemetrySender) -> Self {
         self.telemetry_sender = Some(telemetry_sender);
         self
@@ -483,8 +534,13 @@ where
     noria: NoriaConnector,
     /// Optional connector to the upstream DB. Used for fallback reads and all writes if it exists
     upstream: Option<DB>,
-    /// Map from username to password for all users allowed to connect to the db
+    /// Map from username to password for all users allowed to connect to the db. For PostgreSQL,
+    /// passwords may also be stored as salted MD5 or SCRAM-SHA-256 verifiers.
     pub users: HashMap<String, String>,
+    /// Users who may only connect to the db over TLS
+    pub tls_required_users: HashSet<String>,
+    /// Whether all users may only connect to the db over TLS
+    pub require_tls: bool,
 
     query_log_sender: Option<UnboundedSender<QueryExecutionEvent>>,
 
@@ -524,6 +580,43 @@ where
     /// is responsible for creating accurate RYW timestamps/tickets based on writes made by the
     /// Backend client.
     timestamp_client: Option<TimestampClient>,
+    /// Writes made on this connection which reads against ReadySet may not observe yet
+    read_your_writes: ReadYourWrites,
+    /// The progress of replication shared by all the connections of the adapter, used to wait for
+    /// the writes made on this connection to be replicated, and to check the staleness bounds of
+    /// queries cached with `CREATE CACHE ... WITH MAX_STALENESS`
+    replication_status: Option<Arc<ReplicationStatus>>,
+}
+
+/// Tracks the writes made on a connection for read-your-writes consistency based on the position
+/// of those writes in the upstream database's replication log.
+///
+/// See [`BackendBuilder::read_your_writes_timeout`]
+#[derive(Debug, Default)]
+struct ReadYourWrites {
+    /// Set when a statement that may have written to the upstream database is executed. The
+    /// position of the write in the replication log is looked up lazily, on the next read against
+    /// ReadySet
+    unobserved_writes: bool,
+    /// The position in the replication log which ReadySet has to reach before reads against it
+    /// observe all the writes made on this connection, or `None` if they already do
+    offset: Option<ReplicationOffset>,
+}
+
+impl ReadYourWrites {
+    /// Returns whether the given query may write to the upstream database, in which case later
+    /// reads need to observe it. Queries we couldn't parse are assumed to be writes.
+    fn may_write(query: Option<&SqlQuery>) -> bool {
+        matches!(
+            query,
+            None | Some(
+                SqlQuery::Insert(_)
+                    | SqlQuery::Update(_)
+                    | SqlQuery::Delete(_)
+                    | SqlQuery::Commit(_)
+            )
+        )
+    }
 }
 
 /// Settings that have no state and are constant for a given [`Backend`]
@@ -547,6 +640,9 @@ struct BackendSettings {
     /// repeatedly failed for query_max_failure_duration.
     fallback_recovery_duration: Duration,
     fail_invalidated_queries: bool,
+    /// How long reads against ReadySet wait for the writes made on the same connection to be
+    /// replicated, or `None` if read-your-writes consistency isn't enabled.
+    read_your_writes_timeout: Option<Duration>,
 }
 
 /// QueryInfo holds information regarding the last query that was sent along this connection
@@ -872,12 +968,12 @@ where
                         MigrationState::Pending,
                     );
                 } else if e.caused_by_unsupported() {
-                    self.state.query_status_cache.update_query_migration_state(
+                    self.state.query_status_cache.mark_query_unsupported(
                         &ViewCreateRequest::new(
                             select_meta.rewritten.clone(),
                             self.noria.schema_search_path().to_owned(),
                         ),
-                        MigrationState::Unsupported,
+                        e.unsupported_cause().map(str::to_owned),
                     );
                 } else {
                     error!(
@@ -1125,6 +1221,113 @@ where
         Ok(&self.state.prepared_statements.last().unwrap().prep)
     }
 
+    /// If read-your-writes consistency is enabled and this connection has made writes which reads
+    /// against ReadySet may not observe yet, waits (for up to the configured timeout) for the
+    /// replicator to apply those writes.
+    ///
+    /// Only the tables read by the query in `view_request` need to have caught up with the writes,
+    /// or all the tables if we don't know which ones the query reads.
+    ///
+    /// Returns `false` if the writes still haven't been applied by then (or we couldn't find out
+    /// whether they have), in which case the read should be proxied to the upstream database.
+    async fn wait_for_writes(
+        upstream: Option<&mut DB>,
+        settings: &BackendSettings,
+        read_your_writes: &mut ReadYourWrites,
+        replication_status: Option<&ReplicationStatus>,
+        view_request: Option<&ViewCreateRequest>,
+    ) -> bool {
+        let (timeout, upstream) = match (settings.read_your_writes_timeout, upstream) {
+            (Some(timeout), Some(upstream)) => (timeout, upstream),
+            _ => return true,
+        };
+
+        if read_your_writes.unobserved_writes {
+            match upstream.replication_offset().await {
+                Ok(Some(offset)) => {
+                    if let Err(error) = offset.try_max_into(&mut read_your_writes.offset) {
+                        warn!(%error, "Could not track replication offset of writes");
+                        return false;
+                    }
+                }
+                Ok(None) => {}
+                Err(error) => {
+                    warn!(%error, "Could not look up replication offset of writes");
+                    return false;
+                }
+            }
+            read_your_writes.unobserved_writes = false;
+        }
+
+        let target = match &read_your_writes.offset {
+            Some(target) => target,
+            None => return true,
+        };
+
+        // Without the shared replication status, we have no way of knowing whether the writes
+        // have been replicated
+        let replication_status = match replication_status {
+            Some(replication_status) => replication_status,
+            None => return false,
+        };
+        if !replication_status
+            .wait_for_offset(view_request, target, timeout)
+            .await
+        {
+            trace!(%target, "Timed out waiting for writes to be replicated");
+            return false;
+        }
+
+        // Other queries may read tables that haven't caught up with the writes yet, so we can
+        // only stop tracking them once every table has
+        if replication_status.replicated(None, target) {
+            read_your_writes.offset = None;
+        }
+        true
+    }
+
+    /// If the query's cache has a staleness bound (set with `CREATE CACHE ... WITH
+    /// MAX_STALENESS`), checks whether reads of it served by ReadySet are guaranteed to observe
+    /// every write made to the upstream database longer ago than that.
+    ///
+    /// Returns `false` if they aren't (or we couldn't find out whether they are), in which case the
+    /// read should be proxied to the upstream database.
+    async fn within_max_staleness(
+        noria: &mut NoriaConnector,
+        upstream: Option<&mut DB>,
+        replication_status: Option<&ReplicationStatus>,
+        view_request: &ViewCreateRequest,
+    ) -> bool {
+        if upstream.is_none() {
+            return true;
+        }
+        // The bound is stored with the cache by the server, so that it applies to reads through
+        // every adapter
+        let max_staleness = match noria.max_staleness(view_request).await {
+            Ok(Some(max_staleness)) => max_staleness,
+            Ok(None) => return true,
+            // If the query isn't cached yet, it doesn't have a bound either
+            Err(error) if error.caused_by_view_not_found() => return true,
+            Err(error) => {
+                warn!(%error, "Could not look up staleness bound of query");
+                return false;
+            }
+        };
+
+        // Without the shared replication status, we have no way of knowing how stale reads are
+        let replication_status = match replication_status {
+            Some(replication_status) => replication_status,
+            None => return false,
+        };
+        replication_status
+            .staleness(
+                &view_request.statement,
+                &view_request.schema_search_path,
+                max_staleness,
+            )
+            .map_or(false, |staleness| staleness <= max_staleness)
+    }
+
     /// Executes a prepared statement on ReadySet
     async fn execute_noria<'a>(
         noria: &'a mut NoriaConnector,
@@ -1212,6 +1415,8 @@ where
                 }
                 Ok(noria_ok)
             }
+            // The client asked for the query to stop, so we mustn't run it again upstream
+            Err(ReadySetError::QueryCanceled) => Err(ReadySetError::QueryCanceled.into()),
             Err(noria_err) => {
                 if let Some(info) = ex_info {
                     if noria_err.is_networking_related() {
@@ -1223,7 +1428,10 @@ where
                         info.execute_unsupported();
                     }
                 }
-                if !matches!(noria_err, ReadySetError::ReaderMissingKey) {
+                if !matches!(
+                    noria_err,
+                    ReadySetError::ReaderMissingKey | ReadySetError::LimitExceedsCachedRows { .. }
+                ) {
                     warn!(error = %noria_err,
                           "Error received from noria, sending query to fallback");
                 }
@@ -1385,6 +1593,34 @@ where
             }
         };
 
+        // Reads which might not observe the writes made on this connection are proxied upstream
+        let should_fallback = should_fallback
+            || (!cached_statement.always
+                && matches!(cached_statement.prep, PrepareResult::Both(..))
+                && !Self::wait_for_writes(
+                    upstream.as_mut(),
+                    &self.settings,
+                    &mut self.state.read_your_writes,
+                    self.state.replication_status.as_deref(),
+                    cached_statement.view_request.as_ref(),
+                )
+                .await);
+
+        // As are reads which might be staler than the query allows
+        let should_fallback = should_fallback
+            || match (&cached_statement.prep, &cached_statement.view_request) {
+                (PrepareResult::Both(..), Some(view_request)) if !cached_statement.always => {
+                    !Self::within_max_staleness(
+                        noria,
+                        upstream.as_mut(),
+                        self.state.replication_status.as_deref(),
+                        view_request,
+                    )
+                    .await
+                }
+                _ => false,
+            };
+
         let result = match &cached_statement.prep {
             PrepareResult::Noria(prep) => {
                 Self::execute_noria(noria, prep, params, ticket, &mut event)
@@ -1418,6 +1654,10 @@ where
             }
         };
 
+        if result.is_ok() && ReadYourWrites::may_write(cached_statement.parsed_query.as_deref()) {
+            self.state.read_your_writes.unobserved_writes = true;
+        }
+
         if let Some(e) = event.noria_error.as_ref() {
             if e.caused_by_view_not_found() {
                 // This can happen during cascade execution if the noria query was removed from
@@ -1428,9 +1668,9 @@ where
                 //
                 // Must exist or we would not have executed the query against ReadySet.
                 #[allow(clippy::unwrap_used)]
-                self.state.query_status_cache.update_query_migration_state(
+                self.state.query_status_cache.mark_query_unsupported(
                     cached_statement.view_request.as_ref().unwrap(),
-                    MigrationState::Unsupported,
+                    e.unsupported_cause().map(str::to_owned),
                 );
             }
         }
@@ -1517,7 +1757,18 @@ where
         mut stmt: SelectStatement,
         override_schema_search_path: Option<Vec<SqlIdentifier>>,
         always: bool,
+        max_staleness: Option<Duration>,
     ) -> ReadySetResult<noria_connector::QueryResult<'static>> {
+        // Staleness bounds are checked against the upstream database's position in its
+        // replication log, so make sure we can look that up
+        if max_staleness.is_some() {
+            if let Some(upstream) = &mut self.upstream {
+                if matches!(upstream.replication_offset().await, Ok(None)) {
+                    unsupported!("MAX_STALENESS is not supported for this upstream database");
+                }
+            }
+        }
+
         // If we have another query with the same name, drop that query first
         if let Some(name) = name {
             if let Some(view_request) = self.noria.view_create_request_from_name(name) {
@@ -1532,7 +1783,13 @@ where
         // Now migrate the new query
         rewrite::process_query(&mut stmt, self.noria.server_supports_pagination())?;
         self.noria
-            .handle_create_cached_query(name, &stmt, override_schema_search_path, always)
+            .handle_create_cached_query(
+                name,
+                &stmt,
+                override_schema_search_path,
+                always,
+                max_staleness,
+            )
             .await?;
         self.state.query_status_cache.update_query_migration_state(
             &ViewCreateRequest::new(stmt.clone(), self.noria.schema_search_path().to_owned()),
@@ -1619,12 +1876,16 @@ where
         let data = queries
             .into_iter()
             .map(|DeniedQuery { id, query, status }| {
-                let s = match status.migration_state {
-                    MigrationState::DryRunSucceeded | MigrationState::Successful => "yes",
-                    MigrationState::Pending => "pending",
-                    MigrationState::Unsupported => "unsupported",
-                }
-                .to_string();
+                let s = match (status.migration_state, status.unsupported_reason) {
+                    (MigrationState::DryRunSucceeded | MigrationState::Successful, _) => {
+                        "yes".to_string()
+                    }
+                    (MigrationState::Pending, _) => "pending".to_string(),
+                    (MigrationState::Unsupported, Some(reason)) => {
+                        format!("unsupported: {reason}")
+                    }
+                    (MigrationState::Unsupported, None) => "unsupported".to_string(),
+                };
 
                 vec![
                     DfValue::from(id.to_string()),
@@ -1661,6 +1922,7 @@ where
                 name,
                 inner,
                 always,
+                max_staleness,
             }) => {
                 let (stmt, search_path) = match inner {
                     CacheInner::Statement(st) => (*st.clone(), None),
@@ -1691,7 +1953,7 @@ where
                     trace!("No telemetry sender. not sending metric for CREATE CACHE");
                 }
 
-                self.create_cached_query(name.as_ref(), stmt, search_path, *always)
+                self.create_cached_query(name.as_ref(), stmt, search_path, *always, *max_staleness)
                     .await
             }
             SqlQuery::DropCache(DropCacheStatement { name }) => self.drop_cached_query(name).await,
@@ -1734,24 +1996,17 @@ where
         Some(res)
     }
 
-    #[allow(clippy::too_many_arguments)]
-    async fn query_adhoc_select<'a>(
-        noria: &'a mut NoriaConnector,
-        upstream: Option<&'a mut DB>,
+    /// Returns whether the ad-hoc SELECT described by `view_request`, whose query status is
+    /// `status`, should be executed against ReadySet rather than proxied to the upstream database.
+    /// This never executes the query itself.
+    async fn adhoc_select_uses_readyset(
+        noria: &mut NoriaConnector,
+        mut upstream: Option<&mut DB>,
         settings: &BackendSettings,
         state: &mut BackendState<DB>,
-        original_query: &'a str,
-        original_stmt: SelectStatement,
         view_request: &ViewCreateRequest,
-        status: Option<QueryStatus>,
-        event: &mut QueryExecutionEvent,
-    ) -> Result<QueryResult<'a, DB>, DB::Error> {
-        let mut status = status.unwrap_or(QueryStatus {
-            migration_state: MigrationState::Unsupported,
-            execution_info: None,
-            always: false,
-        });
-        let original_status = status.clone();
+        status: &mut QueryStatus,
+    ) -> bool {
         let did_work = if let Some(ref mut i) = status.execution_info {
             i.reset_if_exceeded_recovery(
                 settings.query_max_failure_duration,
@@ -1778,12 +2033,54 @@ where
                 #[allow(clippy::unwrap_used)] // Validated by did_work.
                 state.query_status_cache.update_transition_time(
                     view_request,
-                    &status.execution_info.unwrap().last_transition_time,
+                    &status.execution_info.as_ref().unwrap().last_transition_time,
                 );
             }
-            return Self::query_fallback(upstream, original_query, event).await;
+            return false;
+        }
+
+        if status.always {
+            return true;
+        }
+
+        // Reads which might not observe the writes made on this connection are proxied upstream
+        if !Self::wait_for_writes(
+            upstream.as_deref_mut(),
+            settings,
+            &mut state.read_your_writes,
+            state.replication_status.as_deref(),
+            Some(view_request),
+        )
+        .await
+        {
+            return false;
         }
 
+        // As are reads which might be staler than the query allows
+        Self::within_max_staleness(
+            noria,
+            upstream,
+            state.replication_status.as_deref(),
+            view_request,
+        )
+        .await
+    }
+
+    /// Execute an ad-hoc SELECT against ReadySet, updating its query status in `state` according
+    /// to the outcome. `original_status` is the status of the query before
+    /// [`Self::adhoc_select_uses_readyset`] was called.
+    #[allow(clippy::too_many_arguments)]
+    async fn execute_adhoc_select<'a>(
+        noria: &'a mut NoriaConnector,
+        settings: &BackendSettings,
+        state: &mut BackendState<DB>,
+        original_query: &'a str,
+        original_stmt: SelectStatement,
+        view_request: &ViewCreateRequest,
+        mut status: QueryStatus,
+        original_status: &QueryStatus,
+        event: &mut QueryExecutionEvent,
+    ) -> ReadySetResult<noria_connector::QueryResult<'a>> {
         let noria_res = {
             event.destination = Some(QueryDestination::Readyset);
             let start = Instant::now();
@@ -1810,12 +2107,17 @@ where
                 if let Some(i) = status.execution_info.as_mut() {
                     i.execute_succeeded()
                 }
-                if status != original_status {
+                if status != *original_status {
                     state
                         .query_status_cache
                         .update_query_status(view_request, status);
                 }
-                Ok(noria_ok.into())
+                Ok(noria_ok)
+            }
+            // The client asked for the query to stop, so we mustn't run it again upstream
+            Err(ReadySetError::QueryCanceled) => {
+                event.set_noria_error(&ReadySetError::QueryCanceled);
+                Err(ReadySetError::QueryCanceled)
             }
             Err(noria_err) => {
                 event.set_noria_error(&noria_err);
@@ -1832,33 +2134,84 @@ where
                     status.migration_state = MigrationState::Pending;
                 } else if noria_err.caused_by_unsupported() {
                     status.migration_state = MigrationState::Unsupported;
+                    status.unsupported_reason = noria_err.unsupported_cause().map(str::to_owned);
                 };
 
-                let always = status.always;
-
-                if status != original_status {
+                if status != *original_status {
                     state
                         .query_status_cache
                         .update_query_status(view_request, status);
                 }
 
-                // Try to execute on fallback if present, as long as query is not an `always`
-                // query.
-                match (always, upstream) {
-                    (true, _) | (_, None) => Err(noria_err.into()),
-                    (false, Some(fallback)) => {
-                        event.destination = Some(QueryDestination::ReadysetThenUpstream);
-                        let _t = event.start_upstream_timer();
-                        fallback
-                            .query(original_query)
-                            .await
-                            .map(QueryResult::Upstream)
-                    }
-                }
+                Err(noria_err)
             }
         }
     }
 
+    #[allow(clippy::too_many_arguments)]
+    async fn query_adhoc_select<'a>(
+        noria: &'a mut NoriaConnector,
+        mut upstream: Option<&'a mut DB>,
+        settings: &BackendSettings,
+        state: &mut BackendState<DB>,
+        original_query: &'a str,
+        original_stmt: SelectStatement,
+        view_request: &ViewCreateRequest,
+        status: Option<QueryStatus>,
+        event: &mut QueryExecutionEvent,
+    ) -> Result<QueryResult<'a, DB>, DB::Error> {
+        let mut status = status.unwrap_or(QueryStatus {
+            migration_state: MigrationState::Unsupported,
+            execution_info: None,
+            always: false,
+            unsupported_reason: None,
+        });
+        let original_status = status.clone();
+
+        if !Self::adhoc_select_uses_readyset(
+            noria,
+            upstream.as_deref_mut(),
+            settings,
+            state,
+            view_request,
+            &mut status,
+        )
+        .await
+        {
+            return Self::query_fallback(upstream, original_query, event).await;
+        }
+
+        let always = status.always;
+        match Self::execute_adhoc_select(
+            noria,
+            settings,
+            state,
+            original_query,
+            original_stmt,
+            view_request,
+            status,
+            &original_status,
+            event,
+        )
+        .await
+        {
+            Ok(noria_ok) => Ok(noria_ok.into()),
+            Err(ReadySetError::QueryCanceled) => Err(ReadySetError::QueryCanceled.into()),
+            // Try to execute on fallback if present, as long as query is not an `always` query.
+            Err(noria_err) => match (always, upstream) {
+                (true, _) | (_, None) => Err(noria_err.into()),
+                (false, Some(fallback)) => {
+                    event.destination = Some(QueryDestination::ReadysetThenUpstream);
+                    let _t = event.start_upstream_timer();
+                    fallback
+                        .query(original_query)
+                        .await
+                        .map(QueryResult::Upstream)
+                }
+            },
+        }
+    }
+
     /// Checks if noria should try to execute a given select and in the process mutates the
     /// supplied select statement by rewriting it.
     /// Returns whether noria should try the select, along with the query status if it was obtained
@@ -2110,6 +2463,7 @@ where
             let _t = event.start_parse_timer();
             self.parse_query(query)
         };
+        let may_write = ReadYourWrites::may_write(parse_result.as_ref().ok());
 
         let result = match parse_result {
             // Parse error, but no fallback exists
@@ -2235,6 +2589,10 @@ where
             }
         };
 
+        if may_write && result.is_ok() {
+            self.state.read_your_writes.unobserved_writes = true;
+        }
+
         self.last_query = event.destination.map(|d| QueryInfo {
             destination: d,
             noria_error: event
@@ -2254,6 +2612,109 @@ where
         self.upstream.is_some()
     }
 
+    /// Returns the connector to the upstream database, if we are using fallback.
+    pub fn upstream(&self) -> Option<&DB> {
+        self.upstream.as_ref()
+    }
+
+    /// Returns a mutable reference to the connector to the upstream database, if we are using
+    /// fallback.
+    pub fn upstream_mut(&mut self) -> Option<&mut DB> {
+        self.upstream.as_mut()
+    }
+
+    /// Returns the schema of the columns of the table named `table`, as known to ReadySet. Tables
+    /// which aren't replicated by ReadySet result in an error.
+    pub async fn table_columns(&mut self, table: &str) -> ReadySetResult<Vec<ColumnSchema>> {
+        self.noria.table_columns(table).await
+    }
+
+    /// Executes `query` against ReadySet if it is a SELECT statement that [`Self::query`] would
+    /// serve from ReadySet, and returns `None` without executing it anywhere if it would be proxied
+    /// to the upstream database instead. This lets the caller proxy the query upstream in a form of
+    /// its own choosing, without it ever being executed upstream twice.
+    ///
+    /// Queries which fail in ReadySet are treated as if they would be proxied, unless there is no
+    /// upstream database, or they are cached with `ALWAYS`.
+    pub async fn query_cached_select<'a>(
+        &'a mut self,
+        query: &'a str,
+    ) -> Result<Option<QueryResult<'a, DB>>, DB::Error> {
+        let stmt = match self.parse_query(query) {
+            Ok(SqlQuery::Select(stmt)) => stmt,
+            _ => return Ok(None),
+        };
+        let mut view_request =
+            ViewCreateRequest::new(stmt.clone(), self.noria.schema_search_path().to_owned());
+        let mut status = match self.noria_should_try_select(&mut view_request) {
+            (true, Some(status)) => status,
+            _ => return Ok(None),
+        };
+        let original_status = status.clone();
+
+        if !Self::adhoc_select_uses_readyset(
+            &mut self.noria,
+            self.upstream.as_mut(),
+            &self.settings,
+            &mut self.state,
+            &view_request,
+            &mut status,
+        )
+        .await
+        {
+            return Ok(None);
+        }
+
+        let mut event = QueryExecutionEvent::new(EventType::Query);
+        event.sql_type = SqlQueryType::Read;
+        let always = status.always;
+        let result = Self::execute_adhoc_select(
+            &mut self.noria,
+            &self.settings,
+            &mut self.state,
+            query,
+            stmt,
+            &view_request,
+            status,
+            &original_status,
+            &mut event,
+        )
+        .await;
+        self.last_query = event.destination.map(|d| QueryInfo {
+            destination: d,
+            noria_error: event
+                .noria_error
+                .as_ref()
+                .map(|e| e.to_string())
+                .unwrap_or_default(),
+        });
+        log_query(self.query_log_sender.as_ref(), event, self.settings.slowlog);
+
+        match result {
+            Ok(noria_ok) => Ok(Some(noria_ok.into())),
+            Err(ReadySetError::QueryCanceled) => Err(ReadySetError::QueryCanceled.into()),
+            Err(_) if self.upstream.is_some() && !always => Ok(None),
+            Err(noria_err) => Err(noria_err.into()),
+        }
+    }
+
+    /// Subscribe to the changes made to the results of the query cached under the name `cache`,
+    /// when executed with the given parameter values
+    pub async fn subscribe(
+        &mut self,
+        cache: &Relation,
+        params: &[DfValue],
+    ) -> ReadySetResult<ViewSubscription> {
+        self.noria.subscribe(cache, params).await
+    }
+
+    /// Returns a handle that can be used to cancel the read this backend is currently executing
+    /// against ReadySet, if any, from another task. Queries proxied to the upstream database are
+    /// unaffected, and must be canceled there instead.
+    pub fn read_canceler(&self) -> ReadCanceler {
+        self.noria.read_canceler().clone()
+    }
+
     /// If we are using fallback, this will return the database that was in the original connection
     /// string, if it exists, otherwise it will return None. If we are not using fallback this will
     /// always return None.
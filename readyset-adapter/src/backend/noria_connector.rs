@@ -4,14 +4,16 @@ use std::convert::{TryFrom, TryInto};
 use std::fmt;
 use std::ops::Bound;
 use std::sync::{atomic, Arc, RwLock};
+use std::time::Duration;
 
 use dataflow_expression::{BinaryOperator as DfBinaryOperator, Expr as DfExpr};
 use itertools::Itertools;
 use launchpad::redacted::Sensitive;
 use nom_sql::analysis::visit_mut::VisitorMut;
 use nom_sql::{
-    self, BinaryOperator, ColumnConstraint, DeleteStatement, Expr, InsertStatement, Literal,
-    Relation, SelectStatement, SqlIdentifier, SqlQuery, UnaryOperator, UpdateStatement,
+    self, BinaryOperator, CacheInner, ColumnConstraint, CreateCacheStatement, DeleteStatement,
+    Expr, InsertStatement, Literal, Relation, SelectStatement, SqlIdentifier, SqlQuery,
+    UnaryOperator, UpdateStatement,
 };
 use readyset::consistency::Timestamp;
 use readyset::internal::LocalNodeIndex;
@@ -20,7 +22,7 @@ use readyset::results::{ResultIterator, Results};
 use readyset::{
     ColumnSchema, KeyColumnIdx, KeyComparison, ReadQuery, ReaderAddress, ReadySetError,
     ReadySetHandle, ReadySetResult, SchemaType, Table, TableOperation, View, ViewCreateRequest,
-    ViewPlaceholder, ViewQuery, ViewSchema,
+    ViewPlaceholder, ViewQuery, ViewSchema, ViewSubscription,
 };
 use readyset_data::{DfType, DfValue, Dialect};
 use readyset_errors::ReadySetError::PreparedStatementMissing;
@@ -29,6 +31,8 @@ use readyset_errors::{
 };
 use readyset_server::worker::readers::{CallResult, ReadRequestHandler};
 use readyset_sql_passes::anonymize::anonymize_literals;
+use tokio::sync::futures::Notified;
+use tokio::sync::Notify;
 use tracing::{error, info, instrument, trace};
 use vec1::vec1;
 
@@ -385,6 +389,9 @@ pub struct NoriaConnector {
     /// supports a multi-element schema search path, the concept of "currently connected database"
     /// in MySQL can be thought of as a schema search path that only has one element.
     schema_search_path: Vec<SqlIdentifier>,
+
+    /// Used to cancel reads against ReadySet from another task. See [`ReadCanceler`].
+    read_canceler: ReadCanceler,
 }
 
 mod request_handler {
@@ -431,6 +438,24 @@ impl ReadBehavior {
     }
 }
 
+/// A handle that can be used to cancel the read a [`NoriaConnector`] is executing against
+/// ReadySet, such as a blocking read waiting on an upquery, from another task.
+#[derive(Debug, Clone, Default)]
+pub struct ReadCanceler(Arc<Notify>);
+
+impl ReadCanceler {
+    /// Cancel the read currently being executed, if any, causing it to return
+    /// [`ReadySetError::QueryCanceled`]. Reads started after this is called are unaffected.
+    pub fn cancel(&self) {
+        self.0.notify_waiters();
+    }
+
+    /// Returns a future that resolves the next time [`cancel`](Self::cancel) is called
+    pub fn canceled(&self) -> Notified<'_> {
+        self.0.notified()
+    }
+}
+
 /// Used when we can determine that the params for 'OFFSET ?' or 'LIMIT ?' passed in
 /// with an execute statement will result in an empty resultset
 async fn short_circuit_empty_resultset(getter: &mut View) -> ReadySetResult<QueryResult<'_>> {
@@ -508,6 +533,7 @@ impl NoriaConnector {
             read_request_handler: request_handler::LocalReadHandler::new(read_request_handler),
             dialect,
             schema_search_path,
+            read_canceler: ReadCanceler::default(),
         }
     }
 
@@ -607,6 +633,47 @@ impl NoriaConnector {
         Ok(table_handle.node)
     }
 
+    /// Returns the schema of the columns of the base table named `table_name`, resolving the name
+    /// using the schema search path if it's unqualified.
+    pub async fn table_columns(&mut self, table_name: &str) -> ReadySetResult<Vec<ColumnSchema>> {
+        let candidates = self
+            .schema_search_path
+            .iter()
+            .map(|schema| Relation {
+                schema: Some(schema.clone()),
+                name: table_name.into(),
+            })
+            .chain(std::iter::once(Relation {
+                schema: None,
+                name: table_name.into(),
+            }))
+            .collect::<Vec<_>>();
+
+        let mut res = Err(ReadySetError::TableNotFound {
+            name: table_name.to_owned(),
+            schema: None,
+        });
+        for table in candidates {
+            let schema = match self.inner.get_mut()?.get_noria_table(&table).await {
+                Ok(handle) => handle
+                    .schema()
+                    .ok_or_else(|| internal_err!("no schema for table '{}'", table))?
+                    .clone(),
+                Err(e) if e.caused_by_table_not_found() => {
+                    res = Err(e);
+                    continue;
+                }
+                Err(e) => return Err(e),
+            };
+            return schema
+                .fields
+                .into_iter()
+                .map(|cs| ColumnSchema::from_base(cs, table.clone(), self.dialect))
+                .collect();
+        }
+        res
+    }
+
     pub async fn handle_insert(
         &mut self,
         q: &nom_sql::InsertStatement,
@@ -961,6 +1028,12 @@ impl NoriaConnector {
     pub fn schema_search_path(&self) -> &[SqlIdentifier] {
         self.schema_search_path.as_ref()
     }
+
+    /// Returns a handle that can be used to cancel reads executed by this connector from another
+    /// task
+    pub fn read_canceler(&self) -> &ReadCanceler {
+        &self.read_canceler
+    }
 }
 
 impl NoriaConnector {
@@ -972,6 +1045,7 @@ impl NoriaConnector {
         statement: &nom_sql::SelectStatement,
         override_schema_search_path: Option<Vec<SqlIdentifier>>,
         always: bool,
+        max_staleness: Option<Duration>,
     ) -> ReadySetResult<()> {
         let name = name.cloned().unwrap_or_else(|| {
             utils::generate_query_name(statement, self.schema_search_path()).into()
@@ -979,7 +1053,12 @@ impl NoriaConnector {
         let schema_search_path =
             override_schema_search_path.unwrap_or_else(|| self.schema_search_path.clone());
         let changelist = ChangeList::from_change(
-            Change::create_cache(name.clone(), statement.clone(), always),
+            Change::CreateCache(CreateCacheStatement {
+                name: Some(name.clone()),
+                inner: CacheInner::Statement(Box::new(statement.clone())),
+                always,
+                max_staleness,
+            }),
             self.dialect,
         )
         .with_schema_search_path(schema_search_path.clone());
@@ -988,6 +1067,9 @@ impl NoriaConnector {
             self.inner.get_mut()?,
             self.inner.get_mut()?.noria.extend_recipe(changelist)
         )?;
+        // Any view we already have for the query may have been built with a different staleness
+        // bound
+        self.inner.get_mut()?.views.remove(&name);
 
         // If the query is already in there with a different name, we don't need to make a new name
         // for it, as *lookups* only need one of the names for the query, and when we drop it we'll
@@ -1082,6 +1164,86 @@ impl NoriaConnector {
         self.view_cache.view_create_request_from_name(name)
     }
 
+    /// Returns the maximum staleness of reads of the cache for the given query, as specified with
+    /// `CREATE CACHE ... WITH MAX_STALENESS`
+    pub(crate) async fn max_staleness(
+        &mut self,
+        view_request: &ViewCreateRequest,
+    ) -> ReadySetResult<Option<Duration>> {
+        let name = self
+            .view_cache
+            .statement_name(view_request)
+            .unwrap_or_else(|| {
+                utils::generate_query_name(
+                    &view_request.statement,
+                    &view_request.schema_search_path,
+                )
+                .into()
+            });
+        let view = self.inner.get_mut()?.get_noria_view(&name, false).await?;
+        Ok(view.max_staleness())
+    }
+
+    /// Subscribe to the changes made to the results of the query cached under the given name, when
+    /// executed with the given parameter values.
+    ///
+    /// Only queries whose parameters are all compared for equality can be subscribed to.
+    pub async fn subscribe(
+        &mut self,
+        name: &Relation,
+        params: &[DfValue],
+    ) -> ReadySetResult<ViewSubscription> {
+        let view = self.inner.get_mut()?.get_noria_view(name, false).await?;
+        let schema = view
+            .schema()
+            .ok_or_else(|| internal_err!("no schema for view '{}'", name))?;
+        let key_types = schema.col_types(
+            view.key_map()
+                .iter()
+                .map(|(_, key_column_idx)| *key_column_idx),
+            SchemaType::ProjectedSchema,
+        )?;
+
+        let num_params = view
+            .key_map()
+            .iter()
+            .filter(|(view_placeholder, _)| {
+                matches!(view_placeholder, ViewPlaceholder::OneToOne(_))
+            })
+            .count();
+        if params.len() != num_params {
+            return Err(ReadySetError::InvalidQuery(format!(
+                "query {} takes {} parameters, but {} were given",
+                name,
+                num_params,
+                params.len()
+            )));
+        }
+
+        let key = view
+            .key_map()
+            .iter()
+            .zip(key_types)
+            .map(|((view_placeholder, _), key_type)| match view_placeholder {
+                ViewPlaceholder::Generated => Ok(DfValue::from(0i32)),
+                // parameter numbering is 1-based, but vecs are 0-based, so subtract 1
+                ViewPlaceholder::OneToOne(idx) => params
+                    .get(*idx - 1)
+                    .ok_or_else(|| internal_err!("placeholder ${} out of bounds", idx))?
+                    .coerce_to(key_type, &DfType::Unknown),
+                ViewPlaceholder::Between(..) | ViewPlaceholder::PageNumber { .. } => {
+                    unsupported!("Subscriptions to queries with range or pagination parameters")
+                }
+            })
+            .collect::<ReadySetResult<Vec<_>>>()?;
+
+        // Only changes to keys that are present in the reader are delivered to subscribers, so make
+        // sure the key has been filled before subscribing to it
+        view.lookup(&key, true).await?;
+        let key = vec1::Vec1::try_from_vec(key).map_err(|_| ReadySetError::EmptyKey)?;
+        view.subscribe(vec![KeyComparison::Equal(key)]).await
+    }
+
     async fn do_insert(
         &mut self,
         q: &InsertStatement,
@@ -1462,7 +1624,8 @@ impl NoriaConnector {
             .get_noria_view(&qname, view_failed)
             .await?;
 
-        let res = do_read(
+        let canceled = self.read_canceler.canceled();
+        let read = do_read(
             getter,
             processed_query_params.as_ref(),
             params,
@@ -1472,8 +1635,11 @@ impl NoriaConnector {
             self.read_request_handler.as_mut(),
             event,
             self.dialect,
-        )
-        .await;
+        );
+        let res = tokio::select! {
+            res = read => res,
+            _ = canceled => Err(ReadySetError::QueryCanceled),
+        };
 
         if let Err(e) = res.as_ref() {
             if e.is_networking_related() || e.caused_by_view_destroyed() {
@@ -1621,15 +1787,26 @@ fn build_view_query(
             .map(|((_, key_column_idx), key_type)| (*key_column_idx, key_type))
             .collect();
 
+        // Keys which mix equality and range comparisons, or which have more than one range
+        // column, are looked up as a single inclusive range over the whole key. Since keys are
+        // compared lexicographically, that range only constrains the key up to (and including)
+        // its first range column, so the range comparisons are also applied as post-lookup
+        // filters.
+        let compound_range =
+            mixed_binops || (binop_to_use != BinaryOperator::Equal && key_types.len() > 1);
+
         raw_keys
             .into_iter()
             .map(|key| {
                 let mut k = vec![];
-                let mut bounds: Option<(Vec<DfValue>, Vec<DfValue>)> = if mixed_binops {
+                let mut bounds: Option<(Vec<DfValue>, Vec<DfValue>)> = if compound_range {
                     Some((vec![], vec![]))
                 } else {
                     None
                 };
+                // Whether we've already added a range column to `bounds`, after which the range
+                // lookup no longer constrains any of the key columns
+                let mut seen_range = false;
                 for (view_placeholder, key_column_idx) in key_map {
                     match view_placeholder {
                         ViewPlaceholder::Generated => continue,
@@ -1672,6 +1849,7 @@ fn build_view_query(
                                     BinaryOperator::Equal => {
                                         lower_bound.push(value.clone());
                                         upper_bound.push(value);
+                                        continue;
                                     }
                                     BinaryOperator::GreaterOrEqual => {
                                         filters.push(make_op(DfBinaryOperator::GreaterOrEqual));
@@ -1698,6 +1876,7 @@ fn build_view_query(
                                         op
                                     ),
                                 }
+                                seen_range = true;
                             } else {
                                 if !k.is_empty() && binop_to_use != BinaryOperator::Equal {
                                     filters.push(make_op(DfBinaryOperator::from_sql_op(
@@ -1719,6 +1898,38 @@ fn build_view_query(
                                 key[*lower_idx - 1].coerce_to(key_type, &DfType::Unknown)?;
                             let upper_value =
                                 key[*upper_idx - 1].coerce_to(key_type, &DfType::Unknown)?;
+
+                            // The range lookup includes both of its bounds, so exclusive bounds
+                            // (and any bounds on a column after the first range column) have to
+                            // be applied as filters
+                            for (idx, value) in
+                                [(lower_idx, &lower_value), (upper_idx, &upper_value)]
+                            {
+                                let op =
+                                    binops.get(*idx - 1).map(|(_, op)| *op).ok_or_else(|| {
+                                        internal_err!("Missing operator for placeholder")
+                                    })?;
+                                if seen_range
+                                    || matches!(op, BinaryOperator::Greater | BinaryOperator::Less)
+                                {
+                                    filters.push(DfExpr::Op {
+                                        left: Box::new(DfExpr::Column {
+                                            index: *key_column_idx,
+                                            ty: key_type.clone(),
+                                        }),
+                                        op: DfBinaryOperator::from_sql_op(
+                                            op, dialect, key_type, key_type,
+                                        )?,
+                                        right: Box::new(DfExpr::Literal {
+                                            val: value.clone(),
+                                            ty: key_type.clone(),
+                                        }),
+                                        ty: DfType::Bool,
+                                    });
+                                }
+                            }
+                            seen_range = true;
+
                             let (lower_key, upper_key) =
                                 bounds.get_or_insert_with(Default::default);
                             lower_key.push(lower_value);
@@ -2173,28 +2384,80 @@ mod tests {
                 Dialect::MySQL,
             );
 
+            assert_eq!(
+                query.filter,
+                Some(DfExpr::Op {
+                    left: Box::new(DfExpr::Op {
+                        left: Box::new(DfExpr::Column {
+                            index: 0,
+                            ty: DfType::Int
+                        }),
+                        op: DfBinaryOperator::Greater,
+                        right: Box::new(DfExpr::Literal {
+                            val: 1.into(),
+                            ty: DfType::Int
+                        }),
+                        ty: DfType::Bool
+                    }),
+                    op: DfBinaryOperator::And,
+                    right: Box::new(DfExpr::Op {
+                        left: Box::new(DfExpr::Column {
+                            index: 1,
+                            ty: DfType::DEFAULT_TEXT
+                        }),
+                        op: DfBinaryOperator::Greater,
+                        right: Box::new(DfExpr::Literal {
+                            val: "a".into(),
+                            ty: DfType::DEFAULT_TEXT
+                        }),
+                        ty: DfType::Bool
+                    }),
+                    ty: DfType::Bool
+                })
+            );
+
+            assert_eq!(
+                query.key_comparisons,
+                vec![KeyComparison::from_range(
+                    &(vec1![DfValue::from(1), DfValue::from("a")]
+                        ..=vec1![DfValue::Max, DfValue::Max])
+                )]
+            );
+        }
+
+        #[test]
+        fn mixed_equal_and_exclusive_between() {
+            let query = make_build_query(
+                "SELECT t.x FROM t WHERE t.y = $1 AND t.x > $2 AND t.x <= $3",
+                &[
+                    (ViewPlaceholder::OneToOne(1), 1),
+                    (ViewPlaceholder::Between(2, 3), 0),
+                ],
+                &[DfValue::from("a"), DfValue::from(1), DfValue::from(5)],
+                Dialect::MySQL,
+            );
+
             assert_eq!(
                 query.filter,
                 Some(DfExpr::Op {
                     left: Box::new(DfExpr::Column {
-                        index: 1,
-                        ty: DfType::DEFAULT_TEXT
+                        index: 0,
+                        ty: DfType::Int
                     }),
                     op: DfBinaryOperator::Greater,
                     right: Box::new(DfExpr::Literal {
-                        val: "a".into(),
-                        ty: DfType::DEFAULT_TEXT
+                        val: 1.into(),
+                        ty: DfType::Int
                     }),
-                    ty: DfType::Bool
+                    ty: DfType::Bool,
                 })
             );
-
             assert_eq!(
                 query.key_comparisons,
-                vec![KeyComparison::Range((
-                    Bound::Excluded(vec1![DfValue::from(1), DfValue::from("a")]),
-                    Bound::Unbounded
-                ))]
+                vec![KeyComparison::from_range(
+                    &(vec1![DfValue::from("a"), DfValue::from(1)]
+                        ..=vec1![DfValue::from("a"), DfValue::from(5)])
+                )]
             );
         }
 
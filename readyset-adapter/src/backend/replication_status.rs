@@ -0,0 +1,350 @@
+//! The progress of replication, shared by all the connections of an adapter.
+//!
+//! Reads of caches created with `CREATE CACHE ... WITH MAX_STALENESS` need to know how far the
+//! tables they read have been replicated, and where in its replication log the upstream database
+//! was at various points in the recent past. Reads waiting for the writes made on their connection
+//! to be replicated (for read-your-writes consistency) need to know when the tables they read have
+//! caught up to those writes. Rather than having every such read ask the controller for the
+//! replication offsets, and every connection sample the upstream database's position, a single
+//! [`ReplicationStatusRefresher`] task per adapter does both periodically, and stores the results
+//! in a [`ReplicationStatus`] that reads consult (and wait on) instead.
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use nom_sql::{SelectStatement, SqlIdentifier};
+use parking_lot::Mutex;
+use readyset::replication::{ReplicationOffset, ReplicationOffsets};
+use readyset::{ReadySetHandle, ViewCreateRequest};
+use tokio::select;
+use tokio::sync::Notify;
+use tokio::time::MissedTickBehavior;
+use tracing::{info, instrument, warn};
+
+use super::staleness::{applied_offset, min_applied_offset, StalenessTracker};
+use crate::{UpstreamConfig, UpstreamDatabase};
+
+/// How often a [`ReplicationStatusRefresher`] checks whether the replication status needs to be
+/// refreshed
+const REFRESH_INTERVAL: Duration = Duration::from_millis(10);
+
+/// How long to wait before sampling the upstream database's position again after failing to
+const SAMPLE_RETRY_INTERVAL: Duration = Duration::from_secs(1);
+
+/// How far replication has progressed, as last loaded by a [`ReplicationStatusRefresher`].
+#[derive(Debug, Default)]
+pub struct ReplicationStatus {
+    /// The replication offsets most recently loaded from the controller
+    offsets: Mutex<Option<ReplicationOffsets>>,
+    /// Set when a read has used the replication offsets since they were last loaded, so that
+    /// they're only loaded while something needs them
+    offsets_requested: AtomicBool,
+    /// Notified every time the replication offsets are loaded
+    offsets_loaded: Notify,
+    /// Samples of the upstream database's position in its replication log
+    staleness: Mutex<Staleness>,
+}
+
+#[derive(Debug, Default)]
+struct Staleness {
+    tracker: StalenessTracker,
+    /// The smallest and largest staleness bounds checked so far, if any have been. The smallest
+    /// determines how often we sample the upstream database's position, and the largest how long
+    /// we keep those samples for.
+    bounds: Option<(Duration, Duration)>,
+}
+
+impl ReplicationStatus {
+    /// Returns an upper bound on how far reads of `statement` served by ReadySet lag behind the
+    /// upstream database, in order to check a staleness bound of `max_staleness`, or [`None`] if
+    /// we don't know (for example because the replication status hasn't been loaded yet).
+    pub(crate) fn staleness(
+        &self,
+        statement: &SelectStatement,
+        schema_search_path: &[SqlIdentifier],
+        max_staleness: Duration,
+    ) -> Option<Duration> {
+        self.offsets_requested.store(true, Ordering::Relaxed);
+
+        let mut staleness = self.staleness.lock();
+        staleness.bounds = Some(match staleness.bounds {
+            Some((min, max)) => (min.min(max_staleness), max.max(max_staleness)),
+            None => (max_staleness, max_staleness),
+        });
+        let offsets = self.offsets.lock();
+        staleness.tracker.staleness(
+            offsets.as_ref()?,
+            statement,
+            schema_search_path,
+            Instant::now(),
+        )
+    }
+
+    /// Waits for up to `timeout` for all the tables read by the query in `view_request` to have
+    /// been replicated up to `target`, or for all the tables to have been if we don't know which
+    /// ones the query reads. Returns whether they have been.
+    pub(crate) async fn wait_for_offset(
+        &self,
+        view_request: Option<&ViewCreateRequest>,
+        target: &ReplicationOffset,
+        timeout: Duration,
+    ) -> bool {
+        let deadline = tokio::time::Instant::now() + timeout;
+        loop {
+            // Start listening before checking the offsets, so that we can't miss a load that
+            // happens in between
+            let loaded = self.offsets_loaded.notified();
+            if self.replicated(view_request, target) {
+                return true;
+            }
+
+            self.offsets_requested.store(true, Ordering::Relaxed);
+            if tokio::time::timeout_at(deadline, loaded).await.is_err() {
+                return false;
+            }
+        }
+    }
+
+    /// Returns whether, as of when the replication offsets were last loaded, all the tables read
+    /// by the query in `view_request` (or all the tables, if it's [`None`]) had been replicated up
+    /// to `target`
+    pub(crate) fn replicated(
+        &self,
+        view_request: Option<&ViewCreateRequest>,
+        target: &ReplicationOffset,
+    ) -> bool {
+        let offsets = self.offsets.lock();
+        let offsets = match offsets.as_ref() {
+            Some(offsets) => offsets,
+            None => return false,
+        };
+        match view_request {
+            Some(view_request) => applied_offset(
+                offsets,
+                &view_request.statement,
+                &view_request.schema_search_path,
+            ),
+            None => min_applied_offset(offsets),
+        }
+        .map_or(false, |offset| offset >= target)
+    }
+}
+
+/// Periodically refreshes a [`ReplicationStatus`] shared by all the connections of an adapter,
+/// loading the replication offsets from the controller while reads need them, and sampling the
+/// upstream database's position in its replication log while caches with a staleness bound are
+/// being read.
+pub struct ReplicationStatusRefresher<DB> {
+    status: Arc<ReplicationStatus>,
+    controller: ReadySetHandle,
+    upstream_config: UpstreamConfig,
+    /// The connection used to sample the upstream database's position, which is only made once
+    /// it's first needed
+    upstream: Option<DB>,
+    /// When to next try sampling the upstream database's position, if it failed last time
+    retry_sample_at: Option<Instant>,
+    /// Receiver to return the shutdown signal on
+    shutdown_recv: tokio::sync::broadcast::Receiver<()>,
+}
+
+impl<DB> ReplicationStatusRefresher<DB>
+where
+    DB: UpstreamDatabase,
+{
+    pub fn new(
+        status: Arc<ReplicationStatus>,
+        controller: ReadySetHandle,
+        upstream_config: UpstreamConfig,
+        shutdown_recv: tokio::sync::broadcast::Receiver<()>,
+    ) -> Self {
+        ReplicationStatusRefresher {
+            status,
+            controller,
+            upstream_config,
+            upstream: None,
+            retry_sample_at: None,
+            shutdown_recv,
+        }
+    }
+
+    #[instrument(level = "info", name = "replication_status_refresher", skip(self))]
+    pub async fn run(&mut self) {
+        let mut interval = tokio::time::interval(REFRESH_INTERVAL);
+        interval.set_missed_tick_behavior(MissedTickBehavior::Delay);
+        loop {
+            select! {
+                _ = interval.tick() => self.refresh().await,
+                _ = self.shutdown_recv.recv() => {
+                    info!("Replication status refresher shutting down after shut down signal");
+                    break;
+                }
+            }
+        }
+    }
+
+    async fn refresh(&mut self) {
+        // Sample the upstream database first, so that the offsets we load afterwards are at least
+        // as recent as the sample
+        self.sample_upstream().await;
+
+        if self.status.offsets_requested.swap(false, Ordering::Relaxed) {
+            match self.controller.replication_offsets().await {
+                Ok(offsets) => {
+                    *self.status.offsets.lock() = Some(offsets);
+                    self.status.offsets_loaded.notify_waiters();
+                }
+                Err(error) => warn!(%error, "Could not load replication offsets"),
+            }
+        }
+    }
+
+    /// Records a sample of the upstream database's position in its replication log, if one is
+    /// needed to check the staleness bounds that reads have checked so far
+    async fn sample_upstream(&mut self) {
+        let now = Instant::now();
+        if self
+            .retry_sample_at
+            .map_or(false, |retry_at| now < retry_at)
+        {
+            return;
+        }
+        let max_staleness = {
+            let staleness = self.status.staleness.lock();
+            match staleness.bounds {
+                Some((min, max)) if staleness.tracker.needs_sample(min, now) => max,
+                _ => return,
+            }
+        };
+
+        let upstream = match &mut self.upstream {
+            Some(upstream) => upstream,
+            None => match DB::connect(self.upstream_config.clone(), None).await {
+                Ok(upstream) => self.upstream.insert(upstream),
+                Err(error) => {
+                    warn!(%error, "Could not connect to upstream database");
+                    self.retry_sample_at = Some(now + SAMPLE_RETRY_INTERVAL);
+                    return;
+                }
+            },
+        };
+
+        // The time has to be taken *before* looking up the position; see
+        // `StalenessTracker::record_sample`
+        let sampled_at = Instant::now();
+        match upstream.replication_offset().await {
+            Ok(Some(offset)) => {
+                self.retry_sample_at = None;
+                self.status.staleness.lock().tracker.record_sample(
+                    sampled_at,
+                    offset,
+                    max_staleness,
+                );
+            }
+            Ok(None) => {}
+            Err(error) => {
+                warn!(%error, "Could not look up replication offset of upstream database");
+                self.upstream = None;
+                self.retry_sample_at = Some(now + SAMPLE_RETRY_INTERVAL);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use nom_sql::{parse_select_statement, Dialect, Relation};
+    use readyset::replication::ReplicationOffset;
+
+    use super::*;
+
+    fn offset(offset: u128) -> ReplicationOffset {
+        ReplicationOffset {
+            offset,
+            replication_log_name: "binlog".into(),
+        }
+    }
+
+    #[test]
+    fn staleness_once_loaded() {
+        let status = ReplicationStatus::default();
+        let stmt = parse_select_statement(Dialect::MySQL, "SELECT * FROM t").unwrap();
+        let search_path = ["public".into()];
+        let max_staleness = Duration::from_secs(10);
+
+        // Nothing's been loaded yet, but checking the bound asks for it to be
+        assert_eq!(status.staleness(&stmt, &search_path, max_staleness), None);
+        assert!(status.offsets_requested.load(Ordering::Relaxed));
+        assert_eq!(
+            status.staleness.lock().bounds,
+            Some((max_staleness, max_staleness))
+        );
+
+        status
+            .staleness
+            .lock()
+            .tracker
+            .record_sample(Instant::now(), offset(2), max_staleness);
+        *status.offsets.lock() = Some(ReplicationOffsets {
+            schema: Some(offset(0)),
+            tables: [(
+                Relation {
+                    schema: Some("public".into()),
+                    name: "t".into(),
+                },
+                Some(offset(2)),
+            )]
+            .into(),
+        });
+        let staleness = status.staleness(&stmt, &search_path, Duration::from_secs(1));
+        assert!(staleness.unwrap() <= max_staleness);
+        assert_eq!(
+            status.staleness.lock().bounds,
+            Some((Duration::from_secs(1), max_staleness))
+        );
+    }
+
+    #[tokio::test]
+    async fn wait_for_offset_until_loaded() {
+        let status = Arc::new(ReplicationStatus::default());
+        let target = offset(2);
+        let load = |schema: u128| {
+            *status.offsets.lock() = Some(ReplicationOffsets {
+                schema: Some(offset(schema)),
+                tables: [(
+                    Relation {
+                        schema: Some("public".into()),
+                        name: "t".into(),
+                    },
+                    Some(offset(0)),
+                )]
+                .into(),
+            });
+            status.offsets_loaded.notify_waiters();
+        };
+
+        // Nothing's been loaded yet
+        assert!(
+            !status
+                .wait_for_offset(None, &target, Duration::from_millis(10))
+                .await
+        );
+        assert!(status.offsets_requested.load(Ordering::Relaxed));
+
+        load(1);
+        assert!(!status.replicated(None, &target));
+
+        let waiting = tokio::spawn({
+            let status = Arc::clone(&status);
+            async move {
+                status
+                    .wait_for_offset(None, &offset(2), Duration::from_secs(10))
+                    .await
+            }
+        });
+        tokio::time::sleep(Duration::from_millis(10)).await;
+        load(2);
+        assert!(waiting.await.unwrap());
+        assert!(status.replicated(None, &target));
+    }
+}
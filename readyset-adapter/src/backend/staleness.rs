@@ -0,0 +1,382 @@
+//! Tracking of how far the results of reads served by ReadySet may lag behind the upstream
+//! database, for caches created with `CREATE CACHE ... WITH MAX_STALENESS`.
+//!
+//! Replication offsets only tell us how far into the upstream's replication log each table has
+//! been replicated, not how long ago that position was written. To turn offsets into durations, we
+//! periodically sample the upstream database's current position in its replication log, and
+//! record when we did so. Once every table read by a query has been replicated up to the position
+//! sampled at time `t`, reads of that query observe every write made to the upstream database
+//! before `t`, so they are at most `now - t` stale.
+//!
+//! The offset of a table only advances when it's written to (or when the replicator records its
+//! position in the log), but the schema's offset is advanced by the replicator's periodic log
+//! position updates, and the replicator applies events in order. Every snapshotted table has
+//! therefore been replicated up to at least the schema's offset, which keeps tables that aren't
+//! written to from holding the staleness of the queries reading them back.
+
+use std::cmp::Ordering;
+use std::collections::VecDeque;
+use std::time::{Duration, Instant};
+
+use nom_sql::analysis::visit::{self, Visitor};
+use nom_sql::{Relation, SelectStatement, SqlIdentifier};
+use readyset::replication::{ReplicationOffset, ReplicationOffsets};
+
+/// Samples of the upstream database's position in its replication log, used to bound the
+/// staleness of reads served by ReadySet.
+#[derive(Debug, Default)]
+pub(crate) struct StalenessTracker {
+    /// Samples of the upstream database's position in its replication log, along with the time
+    /// they were taken at, oldest first. Both the times and the positions are increasing.
+    samples: VecDeque<(Instant, ReplicationOffset)>,
+    /// How long to keep samples for, which is the largest staleness bound we've been asked to
+    /// check so far
+    retention: Duration,
+}
+
+impl StalenessTracker {
+    /// Returns whether a new sample of the upstream database's position should be recorded before
+    /// checking a staleness bound of `max_staleness`.
+    ///
+    /// We sample at a rate proportional to the bound, so that the most recent sample is always
+    /// recent enough to meet it while keeping the number of samples we take (and keep) small.
+    pub(crate) fn needs_sample(&self, max_staleness: Duration, now: Instant) -> bool {
+        self.samples.back().map_or(true, |(sampled_at, _)| {
+            now.saturating_duration_since(*sampled_at) >= max_staleness / 4
+        })
+    }
+
+    /// Records that the upstream database was at `offset` in its replication log as of
+    /// `sampled_at`, in order to check staleness bounds of `max_staleness`.
+    ///
+    /// `sampled_at` must be taken *before* the position is looked up, so that every write made to
+    /// the upstream database before it is at or before `offset`.
+    pub(crate) fn record_sample(
+        &mut self,
+        sampled_at: Instant,
+        offset: ReplicationOffset,
+        max_staleness: Duration,
+    ) {
+        self.retention = self.retention.max(max_staleness);
+
+        // Any table that has been replicated up to an older sample's position has also been
+        // replicated up to the new one if it isn't further along, so the new sample, being more
+        // recent, supersedes those. Samples from a different replication log aren't comparable at
+        // all anymore.
+        while let Some((_, last)) = self.samples.back() {
+            match last.partial_cmp(&offset) {
+                Some(Ordering::Less) => break,
+                _ => {
+                    self.samples.pop_back();
+                }
+            }
+        }
+        self.samples.push_back((sampled_at, offset));
+
+        while let Some((oldest, _)) = self.samples.front() {
+            if sampled_at.saturating_duration_since(*oldest) <= self.retention {
+                break;
+            }
+            self.samples.pop_front();
+        }
+    }
+
+    /// Returns an upper bound on how far reads of `statement` served by ReadySet lag behind the
+    /// upstream database as of `now`, given the current replication `offsets`, or [`None`] if we
+    /// don't know.
+    pub(crate) fn staleness(
+        &self,
+        offsets: &ReplicationOffsets,
+        statement: &SelectStatement,
+        schema_search_path: &[SqlIdentifier],
+        now: Instant,
+    ) -> Option<Duration> {
+        let applied = applied_offset(offsets, statement, schema_search_path)?;
+        self.samples
+            .iter()
+            .rev()
+            .find(|(_, offset)| offset <= applied)
+            .map(|(sampled_at, _)| now.saturating_duration_since(*sampled_at))
+    }
+}
+
+/// Collects every relation referenced anywhere in a query. Since these include aliases and the
+/// names of views, not all of them are replicated tables.
+#[derive(Default)]
+struct ReferredRelations<'ast> {
+    relations: Vec<&'ast Relation>,
+}
+
+impl<'ast> Visitor<'ast> for ReferredRelations<'ast> {
+    type Error = !;
+
+    fn visit_table(&mut self, table: &'ast Relation) -> Result<(), Self::Error> {
+        self.relations.push(table);
+        visit::walk_relation(self, table)
+    }
+}
+
+/// Returns the replication offset of the replicated table that `table` refers to, resolving
+/// unqualified table names using `schema_search_path`. Returns [`None`] if `table` doesn't refer
+/// to a replicated table, and `Some(None)` if it does but the table hasn't been snapshotted yet.
+fn table_offset<'a>(
+    offsets: &'a ReplicationOffsets,
+    table: &Relation,
+    schema_search_path: &[SqlIdentifier],
+) -> Option<&'a Option<ReplicationOffset>> {
+    if table.schema.is_some() {
+        return offsets.tables.get(table);
+    }
+
+    schema_search_path
+        .iter()
+        .find_map(|schema| {
+            offsets.tables.get(&Relation {
+                schema: Some(schema.clone()),
+                name: table.name.clone(),
+            })
+        })
+        .or_else(|| offsets.tables.get(table))
+}
+
+/// Returns the position in the replication log up to which a snapshotted table at `offset` has
+/// been replicated, which is at least the schema's offset
+fn effective_offset<'a>(
+    offsets: &'a ReplicationOffsets,
+    offset: &'a ReplicationOffset,
+) -> &'a ReplicationOffset {
+    match &offsets.schema {
+        Some(schema) if schema.partial_cmp(offset) == Some(Ordering::Greater) => schema,
+        _ => offset,
+    }
+}
+
+/// Returns the position in the replication log up to which all the snapshotted tables have been
+/// replicated
+pub(crate) fn min_applied_offset(offsets: &ReplicationOffsets) -> Option<&ReplicationOffset> {
+    let min = offsets.min_present_offset().ok().flatten()?;
+    Some(effective_offset(offsets, min))
+}
+
+/// Returns the position in the replication log up to which all the tables read by `statement`
+/// have been replicated, or [`None`] if any of them haven't been snapshotted yet.
+///
+/// If none of the relations referenced by `statement` can be resolved to a replicated table, we
+/// conservatively use the position up to which *all* tables have been replicated.
+pub(crate) fn applied_offset<'a>(
+    offsets: &'a ReplicationOffsets,
+    statement: &SelectStatement,
+    schema_search_path: &[SqlIdentifier],
+) -> Option<&'a ReplicationOffset> {
+    let mut referred = ReferredRelations::default();
+    let Ok(()) = referred.visit_select_statement(statement);
+
+    let mut tables = referred
+        .relations
+        .into_iter()
+        .filter_map(|table| table_offset(offsets, table, schema_search_path))
+        .peekable();
+    if tables.peek().is_none() {
+        return min_applied_offset(offsets);
+    }
+
+    let mut res: Option<&ReplicationOffset> = None;
+    for offset in tables {
+        let offset = effective_offset(offsets, offset.as_ref()?);
+        res = match res {
+            Some(cur) => match cur.partial_cmp(offset)? {
+                Ordering::Greater => Some(offset),
+                _ => Some(cur),
+            },
+            None => Some(offset),
+        };
+    }
+    res
+}
+
+#[cfg(test)]
+mod tests {
+    use nom_sql::{parse_select_statement, Dialect};
+
+    use super::*;
+
+    fn offset(offset: u128) -> ReplicationOffset {
+        ReplicationOffset {
+            offset,
+            replication_log_name: "binlog".into(),
+        }
+    }
+
+    fn offsets(tables: Vec<(&str, Option<u128>)>) -> ReplicationOffsets {
+        ReplicationOffsets {
+            schema: Some(offset(0)),
+            tables: tables
+                .into_iter()
+                .map(|(name, off)| {
+                    (
+                        Relation {
+                            schema: Some("public".into()),
+                            name: name.into(),
+                        },
+                        off.map(offset),
+                    )
+                })
+                .collect(),
+        }
+    }
+
+    fn statement(query: &str) -> SelectStatement {
+        parse_select_statement(Dialect::PostgreSQL, query).unwrap()
+    }
+
+    #[test]
+    fn applied_offset_of_referenced_tables() {
+        let offsets = offsets(vec![("t1", Some(5)), ("t2", Some(3)), ("t3", Some(1))]);
+        let search_path = ["public".into()];
+
+        assert_eq!(
+            applied_offset(&offsets, &statement("SELECT * FROM t1"), &search_path),
+            Some(&offset(5))
+        );
+        assert_eq!(
+            applied_offset(
+                &offsets,
+                &statement("SELECT * FROM t1 JOIN public.t2 ON t1.x = t2.x"),
+                &search_path
+            ),
+            Some(&offset(3))
+        );
+        assert_eq!(
+            applied_offset(
+                &offsets,
+                &statement("SELECT * FROM t1 WHERE x IN (SELECT x FROM t3)"),
+                &search_path
+            ),
+            Some(&offset(1))
+        );
+    }
+
+    #[test]
+    fn applied_offset_unresolved_tables() {
+        let offsets = offsets(vec![("t1", Some(5)), ("t2", Some(3))]);
+
+        // Not in the schema search path, so we fall back to all the tables
+        assert_eq!(
+            applied_offset(&offsets, &statement("SELECT * FROM t1"), &[]),
+            Some(&offset(0))
+        );
+        assert_eq!(
+            applied_offset(
+                &offsets,
+                &statement("SELECT * FROM some_view"),
+                &["public".into()]
+            ),
+            Some(&offset(0))
+        );
+    }
+
+    #[test]
+    fn applied_offset_quiet_tables() {
+        let mut offsets = offsets(vec![("t1", Some(5)), ("t2", Some(3)), ("t3", None)]);
+        offsets.schema = Some(offset(4));
+        let search_path = ["public".into()];
+
+        // t2 hasn't been written to since offset 3, but it's been replicated up to the schema's
+        // offset
+        assert_eq!(
+            applied_offset(
+                &offsets,
+                &statement("SELECT * FROM t1 JOIN t2 ON t1.x = t2.x"),
+                &search_path
+            ),
+            Some(&offset(4))
+        );
+        assert_eq!(
+            applied_offset(&offsets, &statement("SELECT * FROM t1"), &search_path),
+            Some(&offset(5))
+        );
+        // But tables which haven't been snapshotted yet still haven't been replicated at all
+        assert_eq!(
+            applied_offset(&offsets, &statement("SELECT * FROM t3"), &search_path),
+            None
+        );
+    }
+
+    #[test]
+    fn applied_offset_not_snapshotted() {
+        let offsets = offsets(vec![("t1", Some(5)), ("t2", None)]);
+        assert_eq!(
+            applied_offset(
+                &offsets,
+                &statement("SELECT * FROM t1, t2"),
+                &["public".into()]
+            ),
+            None
+        );
+    }
+
+    #[test]
+    fn staleness_from_samples() {
+        let start = Instant::now();
+        let max_staleness = Duration::from_secs(10);
+        let mut tracker = StalenessTracker::default();
+        assert!(tracker.needs_sample(max_staleness, start));
+
+        tracker.record_sample(start, offset(2), max_staleness);
+        assert!(!tracker.needs_sample(max_staleness, start + Duration::from_secs(1)));
+        assert!(tracker.needs_sample(max_staleness, start + Duration::from_secs(3)));
+        tracker.record_sample(start + Duration::from_secs(3), offset(4), max_staleness);
+
+        let stmt = statement("SELECT * FROM t");
+        let search_path = ["public".into()];
+        let now = start + Duration::from_secs(5);
+
+        // Caught up with the most recent sample
+        let caught_up = offsets(vec![("t", Some(4))]);
+        assert_eq!(
+            tracker.staleness(&caught_up, &stmt, &search_path, now),
+            Some(Duration::from_secs(2))
+        );
+
+        // Only caught up with the first sample
+        let behind = offsets(vec![("t", Some(3))]);
+        assert_eq!(
+            tracker.staleness(&behind, &stmt, &search_path, now),
+            Some(Duration::from_secs(5))
+        );
+
+        // Not even caught up with that
+        let far_behind = offsets(vec![("t", Some(1))]);
+        assert_eq!(
+            tracker.staleness(&far_behind, &stmt, &search_path, now),
+            None
+        );
+    }
+
+    #[test]
+    fn record_sample_prunes() {
+        let start = Instant::now();
+        let max_staleness = Duration::from_secs(10);
+        let mut tracker = StalenessTracker::default();
+
+        tracker.record_sample(start, offset(2), max_staleness);
+        // No writes since the last sample, so it's superseded
+        tracker.record_sample(start + Duration::from_secs(3), offset(2), max_staleness);
+        assert_eq!(tracker.samples.len(), 1);
+        assert_eq!(tracker.samples[0].0, start + Duration::from_secs(3));
+
+        tracker.record_sample(start + Duration::from_secs(6), offset(3), max_staleness);
+        assert_eq!(tracker.samples.len(), 2);
+
+        // Older than the largest bound we've checked
+        tracker.record_sample(start + Duration::from_secs(15), offset(4), max_staleness);
+        assert_eq!(
+            tracker
+                .samples
+                .iter()
+                .map(|(_, o)| o.offset)
+                .collect::<Vec<_>>(),
+            vec![3, 4]
+        );
+    }
+}
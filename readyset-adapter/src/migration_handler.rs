@@ -236,7 +236,7 @@ where
 
                 self.start_time.remove(view_request);
                 self.query_status_cache
-                    .update_query_migration_state(view_request, MigrationState::Unsupported);
+                    .mark_query_unsupported(view_request, e.unsupported_cause().map(str::to_owned));
             }
             // Errors that were not caused by unsupported may be transient, do nothing
             // so we may retry the migration on this query.
@@ -291,7 +291,7 @@ where
             Err(e) if e.caused_by_unsupported() => {
                 self.start_time.remove(view_request);
                 self.query_status_cache
-                    .update_query_migration_state(view_request, MigrationState::Unsupported);
+                    .mark_query_unsupported(view_request, e.unsupported_cause().map(str::to_owned));
             }
             _ => {} // Leave it as pending.
         }
@@ -108,6 +108,7 @@ mod tests {
                 migration_state: MigrationState::Pending,
                 execution_info: None,
                 always: false,
+                unsupported_reason: None,
             },
         };
         proxied_queries_reporter.report_query(&mut init_q).await;
@@ -129,6 +130,7 @@ mod tests {
                 migration_state: MigrationState::Successful,
                 execution_info: None,
                 always: false,
+                unsupported_reason: None,
             },
         };
         proxied_queries_reporter.report_query(&mut updated_q).await;
@@ -244,6 +244,33 @@ impl QueryStatusCache {
                         migration_state: m,
                         execution_info: None,
                         always: false,
+                        unsupported_reason: None,
+                    },
+                );
+            }
+            _ => {}
+        }
+    }
+
+    /// Updates a queries migration state to `MigrationState::Unsupported`, recording `reason` as
+    /// the reason ReadySet doesn't support it if given. Has no effect on queries that are already
+    /// unsupported.
+    pub fn mark_query_unsupported<Q>(&self, q: &Q, reason: Option<String>)
+    where
+        Q: Clone + Hash + Eq,
+        Query: From<Q> + Borrow<Q>,
+    {
+        match self.statuses.get_mut(q) {
+            Some(mut s) if s.migration_state != MigrationState::Unsupported => {
+                s.migration_state = MigrationState::Unsupported;
+                s.unsupported_reason = reason;
+            }
+            None => {
+                self.insert_with_status(
+                    q.clone(),
+                    QueryStatus {
+                        unsupported_reason: reason,
+                        ..QueryStatus::with_migration_state(MigrationState::Unsupported)
                     },
                 );
             }
@@ -280,6 +307,7 @@ impl QueryStatusCache {
             Some(mut s) if s.migration_state != MigrationState::Unsupported => {
                 s.migration_state = status.migration_state;
                 s.execution_info = status.execution_info;
+                s.unsupported_reason = status.unsupported_reason;
             }
             Some(mut s) => {
                 s.execution_info = status.execution_info;
@@ -524,6 +552,29 @@ mod tests {
         assert_eq!(cache.deny_list().len(), 1);
     }
 
+    #[test]
+    fn query_unsupported_reason() {
+        let cache = QueryStatusCache::new();
+        let query = ViewCreateRequest::new(select_statement("SELECT * FROM t1").unwrap(), vec![]);
+
+        cache.query_migration_state(&query);
+        cache.mark_query_unsupported(&query, Some("excluded column".to_owned()));
+        let status = cache.query_status(&query);
+        assert_eq!(status.migration_state, MigrationState::Unsupported);
+        assert_eq!(
+            status.unsupported_reason.as_deref(),
+            Some("excluded column")
+        );
+        assert_eq!(cache.deny_list().len(), 1);
+
+        // Unsupported queries keep the reason they were first found to be unsupported for
+        cache.mark_query_unsupported(&query, Some("other".to_owned()));
+        assert_eq!(
+            cache.query_status(&query).unsupported_reason.as_deref(),
+            Some("excluded column")
+        );
+    }
+
     #[test]
     fn query_is_inferred_denied_explicit() {
         let cache = QueryStatusCache::with_style(MigrationStyle::Explicit);
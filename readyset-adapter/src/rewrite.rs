@@ -26,12 +26,23 @@ pub struct ProcessedQueryParams {
     pagination_parameters: AdapterPaginationParams,
 }
 
+/// The number of rows kept for each key of a query with a parametrized `LIMIT`.
+///
+/// The server can't size a TopK node from a `LIMIT ?`, so such queries are migrated with a `LIMIT`
+/// of this many rows instead, and the `LIMIT` and `OFFSET` passed at execution time are applied to
+/// the results of each lookup. Lookups whose `LIMIT` plus `OFFSET` exceeds this are sent to the
+/// upstream database.
+pub const MAX_PARAMETRIZED_LIMIT: usize = 1000;
+
 #[derive(Debug, Clone)]
 struct AdapterPaginationParams {
     /// The values of `LIMIT` and `OFFSET` in the original query
     limit: Option<Literal>,
     offset: Option<Literal>,
     force_paginate_in_adapter: bool,
+    /// If the query was migrated with [`MAX_PARAMETRIZED_LIMIT`] in place of its parametrized
+    /// `LIMIT`, the number of rows kept for each key
+    max_rows: Option<usize>,
 }
 
 /// This method checks if readyset-server is configured to handle LIMIT/OFFSET queries at the
@@ -43,7 +54,9 @@ fn use_fallback_pagination(
     offset: &Option<Literal>,
 ) -> bool {
     if server_supports_pagination &&
-        // Can't handle parameterized LIMIT even if support is enabled
+        // Parameterized LIMITs can't be used to size TopK or Paginate nodes (and are rejected by
+        // the server), so they're always applied to the results of the lookup. See
+        // [`MAX_PARAMETRIZED_LIMIT`].
         !matches!(limit, Some(Literal::Placeholder(_))) &&
         // Can't handle bare OFFSET
         !(limit.is_none() && offset.is_some())
@@ -63,6 +76,8 @@ fn use_fallback_pagination(
 ///   therefore cannot guarantee that the rewritten query is free of user PII.
 /// - Collapses 'WHERE <expr> IN ?, ... ?' to 'WHERE <expr> = ?'
 /// - Removes `OFFSET ?` if there isn't a `LIMIT`
+/// - Replaces a parametrized `LIMIT` (and its `OFFSET`) with a `LIMIT` of
+///   [`MAX_PARAMETRIZED_LIMIT`], if the server supports pagination
 pub fn process_query(
     query: &mut SelectStatement,
     server_supports_pagination: bool,
@@ -73,7 +88,13 @@ pub fn process_query(
     let offset = query.offset.take();
     let force_paginate_in_adapter =
         use_fallback_pagination(server_supports_pagination, &limit, &offset);
-    if !force_paginate_in_adapter {
+    let max_rows = (server_supports_pagination && matches!(limit, Some(Literal::Placeholder(_))))
+        .then_some(MAX_PARAMETRIZED_LIMIT);
+    if let Some(max_rows) = max_rows {
+        // Keep the first `max_rows` rows for each key, and apply the actual limit and offset to
+        // the results of the lookup
+        query.limit = Some(Literal::UnsignedInteger(max_rows as _));
+    } else if !force_paginate_in_adapter {
         // If adapter pagination shouldn't be used reinstate the limit and offset clauses
         query.limit.clone_from(&limit);
         query.offset.clone_from(&offset);
@@ -90,6 +111,7 @@ pub fn process_query(
             limit,
             offset,
             force_paginate_in_adapter,
+            max_rows,
         },
     })
 }
@@ -97,6 +119,9 @@ pub fn process_query(
 impl ProcessedQueryParams {
     /// If the query has values for OFFSET or LIMIT, get their values, returning a tuple of `limit,
     /// offset`
+    ///
+    /// Returns [`ReadySetError::LimitExceedsCachedRows`] if the query has a parametrized LIMIT, and
+    /// the given LIMIT and OFFSET ask for more than [`MAX_PARAMETRIZED_LIMIT`] rows.
     pub(crate) fn limit_offset_params(
         &self,
         params: &[DfValue],
@@ -143,6 +168,7 @@ impl ProcessedQueryParams {
             limit,
             offset,
             force_paginate_in_adapter,
+            max_rows,
         } = &self.pagination_parameters;
 
         // TODO(vlad): actually limit and offset can get in reverse order in MySQL if
@@ -151,6 +177,13 @@ impl ProcessedQueryParams {
         let offset = offset.as_ref().map(&mut get_param).transpose()?;
         let limit = limit.as_ref().map(&mut get_param).transpose()?;
 
+        if let (Some(max), Some(limit)) = (max_rows, limit) {
+            let rows = limit.saturating_add(offset.unwrap_or(0));
+            if rows > *max {
+                return Err(ReadySetError::LimitExceedsCachedRows { rows, max: *max });
+            }
+        }
+
         if *force_paginate_in_adapter || limit == Some(0) {
             Ok((limit, offset))
         } else {
@@ -177,6 +210,7 @@ impl ProcessedQueryParams {
             limit,
             offset,
             force_paginate_in_adapter,
+            ..
         } = &self.pagination_parameters;
 
         if *force_paginate_in_adapter {
@@ -1501,5 +1535,45 @@ mod tests {
                 (Some(4), Some(2))
             );
         }
+
+        #[test]
+        fn parametrized_limit_with_server_pagination() {
+            let mut query =
+                parse_select_statement("SELECT * FROM t WHERE x = ? ORDER BY y LIMIT ? OFFSET ?");
+            let proc = process_query(&mut query, true).unwrap();
+            assert_eq!(
+                query,
+                parse_select_statement(&format!(
+                    "SELECT * FROM t WHERE x = $1 ORDER BY y LIMIT {MAX_PARAMETRIZED_LIMIT}"
+                )),
+                "{}",
+                query
+            );
+
+            let params = [1.into(), 10.into(), 20.into()];
+            assert_eq!(
+                proc.make_keys(&params)
+                    .unwrap()
+                    .into_iter()
+                    .map(|c| c.to_vec())
+                    .collect::<Vec<_>>(),
+                vec![vec![DfValue::from(1)]]
+            );
+            assert_eq!(
+                proc.limit_offset_params(&params).unwrap(),
+                (Some(10), Some(20))
+            );
+
+            let err = proc
+                .limit_offset_params(&[1.into(), MAX_PARAMETRIZED_LIMIT.into(), 1.into()])
+                .unwrap_err();
+            assert!(matches!(
+                err,
+                ReadySetError::LimitExceedsCachedRows {
+                    max: MAX_PARAMETRIZED_LIMIT,
+                    ..
+                }
+            ));
+        }
     }
 }
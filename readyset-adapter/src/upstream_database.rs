@@ -4,6 +4,7 @@ use std::fmt::Debug;
 use async_trait::async_trait;
 pub use database_utils::UpstreamConfig;
 use nom_sql::SqlIdentifier;
+use readyset::replication::ReplicationOffset;
 use readyset::ColumnSchema;
 use readyset_client_metrics::QueryDestination;
 use readyset_data::DfValue;
@@ -150,6 +151,16 @@ pub trait UpstreamDatabase: Sized + Send {
     where
         S: AsRef<str> + Send + Sync + 'a;
 
+    /// Return the current position in the upstream database's replication log, which is at or
+    /// past the position of every write that has been committed on this connection.
+    ///
+    /// This is used to provide read-your-writes consistency for reads against ReadySet after a
+    /// write, by waiting until the replicator has applied the replication log up to this
+    /// position. Upstream databases which don't support this return `Ok(None)`.
+    async fn replication_offset(&mut self) -> Result<Option<ReplicationOffset>, Self::Error> {
+        Ok(None)
+    }
+
     /// Handle starting a transaction with the upstream database.
     async fn start_tx<'a>(&'a mut self) -> Result<Self::QueryResult<'a>, Self::Error>;
 
@@ -2,16 +2,17 @@
 
 mod query_logger;
 
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::io;
 use std::marker::Send;
 use std::net::{IpAddr, SocketAddr};
+use std::path::{Path, PathBuf};
 use std::str::FromStr;
 use std::sync::atomic::AtomicUsize;
 use std::sync::{Arc, Mutex, RwLock};
 use std::time::{Duration, SystemTime, UNIX_EPOCH};
 
-use anyhow::{anyhow, bail, ensure};
+use anyhow::{anyhow, bail, ensure, Context};
 use async_trait::async_trait;
 use clap::{ArgGroup, Parser};
 use failpoint_macros::set_failpoint;
@@ -29,7 +30,7 @@ use readyset::failpoints;
 use readyset::metrics::recorded;
 use readyset::{ReadySetError, ReadySetHandle, ViewCreateRequest};
 use readyset_adapter::backend::noria_connector::{NoriaConnector, ReadBehavior};
-use readyset_adapter::backend::MigrationMode;
+use readyset_adapter::backend::{MigrationMode, ReplicationStatus, ReplicationStatusRefresher};
 use readyset_adapter::fallback_cache::{
     DiskModeledCache, EvictionModeledCache, FallbackCache, SimpleFallbackCache,
 };
@@ -48,6 +49,7 @@ use stream_cancel::Valve;
 use tokio::net;
 use tokio::net::UdpSocket;
 use tokio::time::timeout;
+pub use tokio_native_tls::TlsAcceptor;
 use tokio_stream::wrappers::TcpListenerStream;
 use tracing::{debug, debug_span, error, info, span, warn, Level};
 use tracing_futures::Instrument;
@@ -70,10 +72,15 @@ pub trait ConnectionHandler {
     type UpstreamDatabase: UpstreamDatabase;
     type Handler: QueryHandler;
 
+    /// Serve a newly-established connection using the given backend.
+    ///
+    /// If `tls_acceptor` is set, clients may request that the connection be upgraded to TLS using
+    /// it.
     async fn process_connection(
         &mut self,
         stream: net::TcpStream,
         backend: Backend<Self::UpstreamDatabase, Self::Handler>,
+        tls_acceptor: Option<Arc<TlsAcceptor>>,
     );
 
     /// Return an immediate error to a newly-established connection, then immediately disconnect
@@ -235,6 +242,9 @@ pub struct Options {
 
     /// Password to authenticate database connections with. Ignored if
     /// --allow-unauthenticated-connections is passed
+    ///
+    /// For PostgreSQL, this may also be an MD5 or SCRAM-SHA-256 verifier for the password, in the
+    /// format stored in `pg_authid.rolpassword`
     #[clap(long, env = "ALLOWED_PASSWORD", short = 'p')]
     password: Option<RedactedString>,
 
@@ -327,6 +337,37 @@ pub struct Options {
     )]
     fallback_recovery_seconds: u64,
 
+    /// Make reads against ReadySet on a connection which has written to the upstream database
+    /// wait for up to this many milliseconds for those writes to be replicated, and proxy them to
+    /// the upstream database if they haven't been by then. Currently only supported for
+    /// PostgreSQL.
+    #[clap(long, env = "READ_YOUR_WRITES_TIMEOUT_MS")]
+    read_your_writes_timeout_ms: Option<u64>,
+
+    /// Path to a PEM-encoded certificate to present to clients. If set along with `--tls-key`,
+    /// clients may upgrade their connections to TLS.
+    #[clap(long, env = "TLS_CERT", requires = "tls-key")]
+    tls_cert: Option<PathBuf>,
+
+    /// Path to the PEM-encoded PKCS #8 private key for the certificate passed to `--tls-cert`
+    #[clap(long, env = "TLS_KEY", requires = "tls-cert")]
+    tls_key: Option<PathBuf>,
+
+    /// Comma-separated list of users who may only connect to the adapter over TLS. Requires
+    /// `--tls-cert` and `--tls-key`. Currently only supported for MySQL.
+    #[clap(
+        long,
+        env = "TLS_REQUIRED_USERS",
+        use_value_delimiter = true,
+        requires = "tls-cert"
+    )]
+    tls_required_users: Vec<String>,
+
+    /// Reject connections from all clients that don't upgrade them to TLS. Requires `--tls-cert`
+    /// and `--tls-key`.
+    #[clap(long, env = "REQUIRE_TLS", requires = "tls-cert")]
+    require_tls: bool,
+
     /// Whether to use non-blocking or blocking reads against the cache.
     #[clap(long, env = "NON_BLOCKING_READS")]
     non_blocking_reads: bool,
@@ -414,6 +455,18 @@ pub struct FallbackCacheEvictionOptions {
     eviction_rate: f64,
 }
 
+/// Build an acceptor for upgrading client connections to TLS from the certificate and private key
+/// at the given paths
+fn load_tls_acceptor(cert: &Path, key: &Path) -> anyhow::Result<TlsAcceptor> {
+    let cert = std::fs::read(cert)
+        .with_context(|| format!("Could not read TLS certificate from {}", cert.display()))?;
+    let key = std::fs::read(key)
+        .with_context(|| format!("Could not read TLS private key from {}", key.display()))?;
+    let identity = native_tls::Identity::from_pkcs8(&cert, &key)
+        .context("Could not load TLS certificate and private key")?;
+    Ok(native_tls::TlsAcceptor::new(identity)?.into())
+}
+
 impl<H> NoriaAdapter<H>
 where
     H: ConnectionHandler + Clone + Send + Sync + 'static,
@@ -437,6 +490,18 @@ where
         ));
         info!(version = %VERSION_STR_ONELINE);
 
+        let tls_acceptor = match (&options.tls_cert, &options.tls_key) {
+            (Some(cert), Some(key)) => Some(Arc::new(load_tls_acceptor(cert, key)?)),
+            _ => None,
+        };
+        if !options.tls_required_users.is_empty()
+            && matches!(self.database_type, DatabaseType::Psql)
+        {
+            bail!("--tls-required-users is not yet supported for PostgreSQL");
+        }
+        let tls_required_users: HashSet<String> =
+            options.tls_required_users.iter().cloned().collect();
+
         let telemetry_sender = rt.block_on(async {
             TelemetryInitializer::init(
                 options.disable_telemetry,
@@ -790,6 +855,26 @@ where
             rt.handle().spawn(abort_on_panic(fut));
         }
 
+        // The progress of replication is loaded once for all connections, rather than by each
+        // connection that needs it
+        let replication_status = Arc::new(ReplicationStatus::default());
+        if options
+            .server_worker_options
+            .replicator_config
+            .upstream_db_url
+            .is_some()
+        {
+            rs_connect.in_scope(|| info!("Spawning replication status refresher task"));
+            let mut refresher = ReplicationStatusRefresher::<H::UpstreamDatabase>::new(
+                Arc::clone(&replication_status),
+                rh.clone(),
+                options.server_worker_options.replicator_config.clone(),
+                shutdown_sender.subscribe(),
+            );
+            rt.handle()
+                .spawn(abort_on_panic(async move { refresher.run().await }));
+        }
+
         if matches!(migration_style, MigrationStyle::Explicit) {
             rs_connect.in_scope(|| info!("Spawning explicit migrations task"));
             let rh = rh.clone();
@@ -897,9 +982,12 @@ where
             let rh = rh.clone();
             let (auto_increments, query_cache) = (auto_increments.clone(), query_cache.clone());
             let mut connection_handler = self.connection_handler.clone();
+            let tls_acceptor = tls_acceptor.clone();
             let backend_builder = BackendBuilder::new()
                 .slowlog(options.log_slow)
                 .users(users.clone())
+                .tls_required_users(tls_required_users.clone())
+                .require_tls(options.require_tls)
                 .require_authentication(!options.allow_unauthenticated_connections)
                 .dialect(self.parse_dialect)
                 .query_log(qlog_sender.clone(), options.query_log_ad_hoc)
@@ -912,7 +1000,13 @@ where
                 .migration_mode(migration_mode)
                 .query_max_failure_seconds(options.query_max_failure_seconds)
                 .telemetry_sender(telemetry_sender.clone())
-                .fallback_recovery_seconds(options.fallback_recovery_seconds);
+                .fallback_recovery_seconds(options.fallback_recovery_seconds)
+                .read_your_writes_timeout(
+                    options
+                        .read_your_writes_timeout_ms
+                        .map(Duration::from_millis),
+                )
+                .replication_status(Arc::clone(&replication_status));
             let telemetry_sender = telemetry_sender.clone();
 
             // Initialize the reader layer for the adapter.
@@ -988,7 +1082,9 @@ where
                                     upstream,
                                     query_status_cache,
                                 );
-                                connection_handler.process_connection(s, backend).await;
+                                connection_handler
+                                    .process_connection(s, backend, tls_acceptor)
+                                    .await;
                             }
                             Err(error) => {
                                 error!(
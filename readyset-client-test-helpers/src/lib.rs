@@ -11,11 +11,14 @@ use nom_sql::Relation;
 use readyset::consensus::{Authority, LocalAuthorityStore};
 use readyset::ViewCreateRequest;
 use readyset_adapter::backend::noria_connector::{NoriaConnector, ReadBehavior};
-use readyset_adapter::backend::{BackendBuilder, MigrationMode};
+use readyset_adapter::backend::{
+    BackendBuilder, MigrationMode, ReplicationStatus, ReplicationStatusRefresher,
+};
 use readyset_adapter::query_status_cache::QueryStatusCache;
 use readyset_adapter::{Backend, QueryHandler, UpstreamConfig, UpstreamDatabase};
 use readyset_server::{Builder, Handle, LocalAuthority, ReadySetHandle};
 use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::broadcast;
 
 #[cfg(feature = "mysql")]
 pub mod mysql_helpers;
@@ -64,6 +67,7 @@ pub struct TestBuilder {
     migration_mode: MigrationMode,
     recreate_database: bool,
     query_status_cache: Option<&'static QueryStatusCache>,
+    replication_tables: Option<String>,
 }
 
 impl Default for TestBuilder {
@@ -84,6 +88,7 @@ impl TestBuilder {
             migration_mode: MigrationMode::InRequestPath,
             recreate_database: true,
             query_status_cache: None,
+            replication_tables: None,
         }
     }
 
@@ -123,6 +128,11 @@ impl TestBuilder {
         self
     }
 
+    pub fn replication_tables(mut self, replication_tables: String) -> Self {
+        self.replication_tables = Some(replication_tables);
+        self
+    }
+
     pub async fn build<A>(self) -> (A::ConnectionOpts, Handle)
     where
         A: Adapter + 'static,
@@ -160,17 +170,35 @@ impl TestBuilder {
         if let Some(f) = &fallback_url {
             builder.set_replication_url(f.clone());
         }
+        if let Some(replication_tables) = &self.replication_tables {
+            builder.set_replication_tables(replication_tables.clone());
+        }
         let mut handle = builder.start(authority.clone()).await.unwrap();
         if self.wait_for_backend {
             handle.backend_ready().await;
         }
 
+        // Like the adapter, share the progress of replication across all the connections
+        let replication_status = Arc::new(ReplicationStatus::default());
+        let (shutdown_tx, shutdown_rx) = broadcast::channel(1);
+        if let Some(f) = &fallback_url {
+            let mut refresher = ReplicationStatusRefresher::<A::Upstream>::new(
+                Arc::clone(&replication_status),
+                ReadySetHandle::new(authority.clone()).await,
+                UpstreamConfig::from_url(f),
+                shutdown_rx,
+            );
+            tokio::spawn(async move { refresher.run().await });
+        }
+
         let auto_increments: Arc<RwLock<HashMap<Relation, AtomicUsize>>> = Arc::default();
         let query_cache: Arc<RwLock<HashMap<ViewCreateRequest, Relation>>> = Arc::default();
         let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
         let addr = listener.local_addr().unwrap();
 
         tokio::spawn(async move {
+            // The replication status refresher stops once this is dropped
+            let _shutdown_tx = shutdown_tx;
             loop {
                 let (s, _) = listener.accept().await.unwrap();
                 let query_cache = query_cache.clone();
@@ -207,6 +235,7 @@ impl TestBuilder {
                 let backend = backend_builder
                     .dialect(A::DIALECT)
                     .migration_mode(self.migration_mode)
+                    .replication_status(Arc::clone(&replication_status))
                     .build(noria, upstream, query_status_cache);
 
                 tokio::spawn(A::run_backend(backend, s));
@@ -2,10 +2,12 @@ use std::env;
 use std::fmt::Display;
 
 use async_trait::async_trait;
+use mysql_async::consts::{CapabilityFlags, Command, StatusFlags};
 use mysql_async::prelude::Queryable;
 use mysql_srv::MySqlIntermediary;
 use readyset_adapter::backend::QueryInfo;
 use readyset_mysql::{Backend, MySqlQueryHandler, MySqlUpstream};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
 use tokio::net::TcpStream;
 
 use crate::Adapter;
@@ -19,6 +21,162 @@ pub async fn last_query_info(conn: &mut impl Queryable) -> QueryInfo {
         .unwrap()
 }
 
+/// A connection to an adapter which speaks just enough of the MySQL protocol to issue the commands
+/// that mysql_async doesn't support, such as `COM_FIELD_LIST` and `COM_STMT_FETCH`.
+pub struct RawConnection {
+    stream: TcpStream,
+    seq: u8,
+}
+
+impl RawConnection {
+    /// Connect to the adapter at `opts`, which must not require authentication
+    pub async fn connect(opts: &mysql_async::Opts) -> Self {
+        let mut conn = RawConnection {
+            stream: TcpStream::connect((opts.ip_or_hostname(), opts.tcp_port()))
+                .await
+                .unwrap(),
+            seq: 0,
+        };
+        let handshake = conn.read_packet().await;
+        assert_eq!(handshake[0], 10, "Expected initial handshake packet");
+
+        let capabilities = CapabilityFlags::CLIENT_PROTOCOL_41
+            | CapabilityFlags::CLIENT_SECURE_CONNECTION
+            | CapabilityFlags::CLIENT_PLUGIN_AUTH;
+        let mut packet = capabilities.bits().to_le_bytes().to_vec();
+        packet.extend_from_slice(&16777216u32.to_le_bytes()); // max packet size
+        packet.push(mysql_async::consts::UTF8MB4_GENERAL_CI as u8);
+        packet.extend_from_slice(&[0; 23]);
+        packet.extend_from_slice(b"root\0");
+        packet.push(0); // empty auth response
+        packet.extend_from_slice(b"mysql_native_password\0");
+        conn.write_packet(&packet).await;
+        let ok = conn.read_packet().await;
+        assert_eq!(ok[0], 0x00, "Expected OK packet, got {:?}", ok);
+        conn
+    }
+
+    async fn read_packet(&mut self) -> Vec<u8> {
+        let mut header = [0u8; 4];
+        self.stream.read_exact(&mut header).await.unwrap();
+        let len = u32::from_le_bytes([header[0], header[1], header[2], 0]) as usize;
+        self.seq = header[3].wrapping_add(1);
+        let mut packet = vec![0u8; len];
+        self.stream.read_exact(&mut packet).await.unwrap();
+        packet
+    }
+
+    async fn write_packet(&mut self, payload: &[u8]) {
+        let mut packet = (payload.len() as u32).to_le_bytes()[..3].to_vec();
+        packet.push(self.seq);
+        packet.extend_from_slice(payload);
+        self.stream.write_all(&packet).await.unwrap();
+        self.seq = self.seq.wrapping_add(1);
+    }
+
+    async fn command(&mut self, command: Command, payload: &[u8]) {
+        self.seq = 0;
+        let mut packet = vec![command as u8];
+        packet.extend_from_slice(payload);
+        self.write_packet(&packet).await;
+    }
+
+    /// Read packets up to and including the next EOF packet, returning the packets before it and
+    /// the status flags of the EOF packet
+    async fn read_until_eof(&mut self) -> Result<(Vec<Vec<u8>>, StatusFlags), u16> {
+        let mut packets = vec![];
+        loop {
+            let packet = self.read_packet().await;
+            match packet[0] {
+                0xfe if packet.len() < 9 => {
+                    let status =
+                        StatusFlags::from_bits_truncate(u16::from_le_bytes([packet[3], packet[4]]));
+                    return Ok((packets, status));
+                }
+                0xff => return Err(u16::from_le_bytes([packet[1], packet[2]])),
+                _ => packets.push(packet),
+            }
+        }
+    }
+
+    /// List the names of the columns of `table` with `COM_FIELD_LIST`, or return the code of the
+    /// error sent by the adapter
+    pub async fn list_fields(&mut self, table: &str) -> Result<Vec<String>, u16> {
+        let mut payload = table.as_bytes().to_vec();
+        payload.push(0);
+        self.command(Command::COM_FIELD_LIST, &payload).await;
+        let (columns, _) = self.read_until_eof().await?;
+        Ok(columns
+            .iter()
+            .map(|column| {
+                // Skip the catalog, schema, table and original table names, which are all
+                // length-encoded strings
+                let mut pos = 0;
+                for _ in 0..4 {
+                    pos += 1 + column[pos] as usize;
+                }
+                let len = column[pos] as usize;
+                String::from_utf8(column[pos + 1..pos + 1 + len].to_vec()).unwrap()
+            })
+            .collect())
+    }
+
+    /// Prepare `query`, returning the ID of the statement
+    pub async fn prepare(&mut self, query: &str) -> u32 {
+        self.command(Command::COM_STMT_PREPARE, query.as_bytes())
+            .await;
+        let packet = self.read_packet().await;
+        assert_eq!(
+            packet[0], 0x00,
+            "Expected prepare OK packet, got {:?}",
+            packet
+        );
+        let stmt = u32::from_le_bytes(packet[1..5].try_into().unwrap());
+        let num_columns = u16::from_le_bytes([packet[5], packet[6]]);
+        let num_params = u16::from_le_bytes([packet[7], packet[8]]);
+        if num_params > 0 {
+            self.read_until_eof().await.unwrap();
+        }
+        if num_columns > 0 {
+            self.read_until_eof().await.unwrap();
+        }
+        stmt
+    }
+
+    /// Execute the prepared statement `stmt`, which must not take any parameters, asking for a
+    /// read-only cursor over its results. Returns whether the adapter opened a cursor; if it
+    /// didn't, the rows of the resultset are read and discarded.
+    pub async fn execute_with_cursor(&mut self, stmt: u32) -> bool {
+        let mut payload = stmt.to_le_bytes().to_vec();
+        payload.push(0x01); // CURSOR_TYPE_READ_ONLY
+        payload.extend_from_slice(&1u32.to_le_bytes()); // iteration count
+        self.command(Command::COM_STMT_EXECUTE, &payload).await;
+        let column_count = self.read_packet().await;
+        assert_ne!(column_count[0], 0xff, "Error executing statement");
+        let (_, status) = self.read_until_eof().await.unwrap();
+        if status.contains(StatusFlags::SERVER_STATUS_CURSOR_EXISTS) {
+            true
+        } else {
+            self.read_until_eof().await.unwrap();
+            false
+        }
+    }
+
+    /// Fetch up to `num_rows` rows from the cursor opened for `stmt`, returning the row packets
+    /// and whether the adapter indicated that the last row was sent, or the code of the error
+    /// sent by the adapter
+    pub async fn fetch(&mut self, stmt: u32, num_rows: u32) -> Result<(Vec<Vec<u8>>, bool), u16> {
+        let mut payload = stmt.to_le_bytes().to_vec();
+        payload.extend_from_slice(&num_rows.to_le_bytes());
+        self.command(Command::COM_STMT_FETCH, &payload).await;
+        let (rows, status) = self.read_until_eof().await?;
+        Ok((
+            rows,
+            status.contains(StatusFlags::SERVER_STATUS_LAST_ROW_SENT),
+        ))
+    }
+}
+
 pub async fn recreate_database<N>(dbname: N)
 where
     N: Display,
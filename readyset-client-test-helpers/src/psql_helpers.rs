@@ -1,15 +1,20 @@
 use std::env;
 
 use async_trait::async_trait;
+use once_cell::sync::Lazy;
 use readyset_adapter::backend::{QueryDestination, QueryInfo};
 use readyset_adapter::Backend;
-use readyset_psql::{PostgreSqlQueryHandler, PostgreSqlUpstream};
+use readyset_psql::{CancelRegistry, PostgreSqlQueryHandler, PostgreSqlUpstream};
 use tokio::net::TcpStream;
 use tokio_postgres::{Client, NoTls, SimpleQueryMessage};
 use tracing::error;
 
 use crate::{sleep, Adapter};
 
+/// Shared by all connections to test adapters, so that cancel requests, which clients send on a
+/// new connection, can find the connection they're meant for
+static CANCEL_REGISTRY: Lazy<CancelRegistry> = Lazy::new(Default::default);
+
 pub fn upstream_config() -> tokio_postgres::Config {
     let mut config = tokio_postgres::Config::new();
     config
@@ -70,7 +75,8 @@ impl Adapter for PostgreSQLAdapter {
     }
 
     async fn run_backend(backend: Backend<Self::Upstream, Self::Handler>, s: TcpStream) {
-        psql_srv::run_backend(readyset_psql::Backend(backend), s).await
+        let backend = readyset_psql::Backend::new(backend, CANCEL_REGISTRY.clone());
+        psql_srv::run_backend(backend, s, None, false).await
     }
 }
 
@@ -1622,16 +1622,32 @@ impl ToSql for DfValue {
                     })
                     .and_then(|m| m.to_sql(ty, out))
             }
-            (Self::Text(_) | Self::TinyText(_), &Type::INET) => <&str>::try_from(self)
-                .unwrap()
-                .parse::<IpAddr>()
-                .map_err(|e| {
+            (Self::Text(_) | Self::TinyText(_), &Type::INET) => {
+                // The network mask is omitted if it covers the whole address
+                let s = <&str>::try_from(self).unwrap();
+                let (addr, netmask) = match s.split_once('/') {
+                    Some((addr, netmask)) => (addr, Some(netmask)),
+                    None => (s, None),
+                };
+                let addr = addr.parse::<IpAddr>().map_err(|e| {
                     Box::<dyn Error + Send + Sync>::from(format!(
                         "Could not convert Text into an IP Address: {}",
                         e
                     ))
-                })
-                .and_then(|ip| ip.to_sql(ty, out)),
+                })?;
+                let netmask = match netmask {
+                    Some(netmask) => netmask.parse::<u8>().map_err(|e| {
+                        Box::<dyn Error + Send + Sync>::from(format!(
+                            "Could not convert Text into a network mask: {}",
+                            e
+                        ))
+                    })?,
+                    None if addr.is_ipv4() => 32,
+                    None => 128,
+                };
+                postgres_protocol::types::inet_to_sql(addr, netmask, out);
+                Ok(IsNull::No)
+            }
             (Self::Text(_) | Self::TinyText(_), &Type::UUID) => {
                 Uuid::parse_str(<&str>::try_from(self).unwrap())
                     .map_err(|e| {
@@ -1732,7 +1748,15 @@ impl<'a> FromSql<'a> for DfValue {
                 Type::MACADDR => Ok(DfValue::from(
                     MacAddress::from_sql(ty, raw)?.to_string(MacAddressFormat::HexString),
                 )),
-                Type::INET => Ok(DfValue::from(IpAddr::from_sql(ty, raw)?.to_string())),
+                Type::INET => {
+                    let inet = postgres_protocol::types::inet_from_sql(raw)?;
+                    let max_netmask = if inet.addr().is_ipv4() { 32 } else { 128 };
+                    if inet.netmask() == max_netmask {
+                        Ok(DfValue::from(inet.addr().to_string()))
+                    } else {
+                        Ok(DfValue::from(format!("{}/{}", inet.addr(), inet.netmask())))
+                    }
+                }
                 Type::UUID => Ok(DfValue::from(Uuid::from_sql(ty, raw)?.to_string())),
                 Type::JSON | Type::JSONB => Ok(DfValue::from(
                     serde_json::Value::from_sql(ty, raw)?.to_string(),
@@ -0,0 +1,170 @@
+//! A log of the changes made to the contents of a reader, which clients can subscribe to in order
+//! to be told about changes to the results of a query rather than having to poll for them.
+
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use tokio::sync::futures::Notified;
+use tokio::sync::Notify;
+
+use crate::prelude::*;
+
+/// The maximum number of batches of records retained in a [`DeltaLog`]. Subscribers which fall
+/// further behind than this have to start over.
+const MAX_RETAINED_BATCHES: usize = 1024;
+
+/// How long a [`DeltaLog`] keeps recording changes for after it was last read by a subscriber.
+///
+/// Subscribers poll for changes rather than holding on to any state in the reader, so this is how
+/// we tell that nobody is subscribed to the reader anymore, and that we can stop paying for
+/// cloning the records written to it.
+const IDLE_TIMEOUT: Duration = Duration::from_secs(60);
+
+#[derive(Default)]
+struct Inner {
+    /// The sequence number of the first batch in `batches`
+    first_seq: u64,
+    batches: VecDeque<Arc<[Record]>>,
+    /// The last time the log was read, or `None` if it never has been
+    last_read: Option<Instant>,
+}
+
+impl Inner {
+    fn next_seq(&self) -> u64 {
+        self.first_seq + self.batches.len() as u64
+    }
+
+    fn is_active(&self) -> bool {
+        self.last_read
+            .map_or(false, |last_read| last_read.elapsed() < IDLE_TIMEOUT)
+    }
+}
+
+/// A log of the batches of records written to a reader, in the order in which they were made
+/// visible to readers, with each batch identified by a consecutive sequence number.
+///
+/// Only changes due to writes to the underlying tables are recorded, not the records used to fill
+/// holes in partial state. Records are only recorded while the log is being read by subscribers.
+#[derive(Clone, Default)]
+pub(crate) struct DeltaLog {
+    inner: Arc<Mutex<Inner>>,
+    /// Notified whenever a batch is added to the log
+    notify: Arc<Notify>,
+}
+
+impl DeltaLog {
+    #[allow(clippy::unwrap_used)] // Only fails if the lock is poisoned
+    fn lock(&self) -> std::sync::MutexGuard<'_, Inner> {
+        self.inner.lock().unwrap()
+    }
+
+    /// Returns true if the log has been read recently enough that records written to the reader
+    /// should be added to it
+    pub(crate) fn is_active(&self) -> bool {
+        self.lock().is_active()
+    }
+
+    /// Add a batch of records to the end of the log, if it's active and the batch is non-empty
+    pub(crate) fn push(&self, records: Vec<Record>) {
+        if records.is_empty() {
+            return;
+        }
+        let mut inner = self.lock();
+        if !inner.is_active() {
+            return;
+        }
+        inner.batches.push_back(records.into());
+        while inner.batches.len() > MAX_RETAINED_BATCHES {
+            inner.batches.pop_front();
+            inner.first_seq += 1;
+        }
+        drop(inner);
+        self.notify.notify_waiters();
+    }
+
+    /// Returns the sequence number of the next batch to be added to the log, marking the log as
+    /// being read
+    pub(crate) fn start(&self) -> u64 {
+        let mut inner = self.lock();
+        inner.last_read = Some(Instant::now());
+        inner.next_seq()
+    }
+
+    /// Returns all the batches in the log starting at the one with the sequence number `seq`,
+    /// along with the sequence number of the next batch to be added to the log, and marks the log
+    /// as being read.
+    ///
+    /// Returns [`ReadySetError::SubscriptionLagged`] if some of those batches have already been
+    /// dropped from the log.
+    pub(crate) fn read_from(&self, seq: u64) -> ReadySetResult<(Vec<Arc<[Record]>>, u64)> {
+        let mut inner = self.lock();
+        if seq < inner.first_seq || seq > inner.next_seq() {
+            return Err(ReadySetError::SubscriptionLagged);
+        }
+        inner.last_read = Some(Instant::now());
+        let batches = inner
+            .batches
+            .iter()
+            .skip((seq - inner.first_seq) as usize)
+            .cloned()
+            .collect();
+        Ok((batches, inner.next_seq()))
+    }
+
+    /// Returns a future which resolves the next time a batch is added to the log after this was
+    /// called
+    pub(crate) fn notified(&self) -> Notified<'_> {
+        self.notify.notified()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn batch(n: i32) -> Vec<Record> {
+        vec![Record::Positive(vec![n.into()])]
+    }
+
+    #[test]
+    fn only_records_while_read() {
+        let log = DeltaLog::default();
+        log.push(batch(1));
+        assert!(!log.is_active());
+
+        let seq = log.start();
+        assert_eq!(seq, 0);
+        log.push(batch(2));
+        log.push(vec![]);
+        log.push(batch(3));
+
+        let (batches, next) = log.read_from(seq).unwrap();
+        assert_eq!(next, 2);
+        assert_eq!(
+            batches.iter().map(|b| b.to_vec()).collect::<Vec<_>>(),
+            vec![batch(2), batch(3)]
+        );
+
+        let (batches, next) = log.read_from(next).unwrap();
+        assert!(batches.is_empty());
+        assert_eq!(next, 2);
+    }
+
+    #[test]
+    fn lagged_subscriber() {
+        let log = DeltaLog::default();
+        let seq = log.start();
+        for i in 0..=(MAX_RETAINED_BATCHES as i32) {
+            log.push(batch(i));
+        }
+
+        assert_eq!(
+            log.read_from(seq).unwrap_err(),
+            ReadySetError::SubscriptionLagged
+        );
+        let (batches, next) = log.read_from(seq + 1).unwrap();
+        assert_eq!(batches.len(), MAX_RETAINED_BATCHES);
+        assert_eq!(next, MAX_RETAINED_BATCHES as u64 + 1);
+    }
+}
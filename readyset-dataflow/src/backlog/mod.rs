@@ -2,6 +2,7 @@ use std::borrow::Cow;
 use std::cmp::Ordering;
 use std::ops::Bound;
 use std::sync::Arc;
+use std::time::Duration;
 
 use ahash::RandomState;
 use common::SizeOf;
@@ -9,9 +10,10 @@ use dataflow_expression::{PostLookup, ReaderProcessing};
 use reader_map::EvictionStrategy;
 use readyset::consistency::Timestamp;
 use readyset::results::SharedResults;
-use readyset::KeyComparison;
+use readyset::{KeyComparison, SubscriptionCursor, ViewDelta, ViewDeltas};
 use vec1::Vec1;
 
+use self::deltas::DeltaLog;
 pub use self::multir::LookupError;
 use crate::prelude::*;
 
@@ -137,6 +139,7 @@ fn new_inner(
     };
 
     let (notifier, receiver) = tokio::sync::broadcast::channel(1);
+    let deltas = DeltaLog::default();
 
     let w = WriteHandle {
         partial: trigger.is_some(),
@@ -147,6 +150,8 @@ fn new_inner(
         mem_size: 0,
         notifier,
         eviction_epoch: 0,
+        deltas: deltas.clone(),
+        pending_deltas: Vec::new(),
     };
 
     let r = SingleReadHandle {
@@ -156,11 +161,13 @@ fn new_inner(
         post_lookup: post_processing,
         receiver,
         eviction_epoch: 0,
+        deltas,
     };
 
     (r, w)
 }
 
+mod deltas;
 mod multir;
 mod multiw;
 
@@ -183,6 +190,10 @@ pub(crate) struct WriteHandle {
     notifier: ReaderUpdatedSender,
     /// How many eviction rounds this handle had
     eviction_epoch: usize,
+    /// Log of the changes made to the reader, for subscribers
+    deltas: DeltaLog,
+    /// Changes to add to `deltas` once they're made visible to readers by the next `swap()`
+    pending_deltas: Vec<Record>,
 }
 
 type Key<'a> = Cow<'a, [DfValue]>;
@@ -283,6 +294,19 @@ impl WriteHandle {
 
     pub(crate) fn swap(&mut self) {
         self.handle.refresh();
+        if !self.pending_deltas.is_empty() {
+            self.deltas.push(std::mem::take(&mut self.pending_deltas));
+        }
+    }
+
+    /// Record a set of records, which are about to be added to the backlog due to writes (rather
+    /// than to fill holes in partial state), as changes for subscribers to the reader.
+    ///
+    /// These will be made visible to subscribers after the next call to `swap()`.
+    pub(crate) fn add_deltas(&mut self, rs: &[Record]) {
+        if !rs.is_empty() && self.deltas.is_active() {
+            self.pending_deltas.extend_from_slice(rs);
+        }
     }
 
     pub(crate) fn len(&self) -> usize {
@@ -438,6 +462,8 @@ pub struct SingleReadHandle {
     receiver: ReaderUpdatedNotifier,
     /// Caches the eviction epoch of the associated [`WriteHandle`]
     eviction_epoch: usize,
+    /// Log of the changes made by the associated [`WriteHandle`], for subscribers
+    deltas: DeltaLog,
 }
 
 impl Clone for SingleReadHandle {
@@ -449,6 +475,7 @@ impl Clone for SingleReadHandle {
             post_lookup: self.post_lookup.clone(),
             receiver: self.receiver.resubscribe(),
             eviction_epoch: self.eviction_epoch,
+            deltas: self.deltas.clone(),
         }
     }
 }
@@ -548,6 +575,74 @@ impl SingleReadHandle {
 
         self.eviction_epoch
     }
+
+    /// Start a subscription to the changes made to this reader, returning the cursor to read
+    /// changes from
+    pub fn subscribe(&mut self) -> SubscriptionCursor {
+        SubscriptionCursor {
+            seq: self.deltas.start(),
+            eviction_epoch: self.eviction_epoch(),
+        }
+    }
+
+    /// Returns the changes made to the rows for `keys` since `cursor`, waiting for up to `timeout`
+    /// for there to be any, along with the cursor to read subsequent changes from.
+    ///
+    /// Returns [`ReadySetError::SubscriptionLagged`] if some of those changes may have been missed,
+    /// either because they're no longer retained or because rows were evicted from the reader.
+    pub async fn wait_for_deltas(
+        &mut self,
+        keys: &[KeyComparison],
+        mut cursor: SubscriptionCursor,
+        timeout: Duration,
+    ) -> ReadySetResult<ViewDeltas> {
+        let deadline = tokio::time::Instant::now() + timeout;
+        let log = self.deltas.clone();
+        loop {
+            // Register for notifications before reading the log, so that we can't miss batches
+            // added in between
+            let notified = log.notified();
+
+            if self.eviction_epoch() != cursor.eviction_epoch {
+                return Err(ReadySetError::SubscriptionLagged);
+            }
+            let (batches, next_seq) = log.read_from(cursor.seq)?;
+            cursor.seq = next_seq;
+
+            let deltas = self.deltas_for_keys(keys, &batches);
+            if !deltas.is_empty() || tokio::time::timeout_at(deadline, notified).await.is_err() {
+                return Ok(ViewDeltas { deltas, cursor });
+            }
+        }
+    }
+
+    /// Convert the records in `batches` whose keys match any of `keys` into [`ViewDelta`]s
+    fn deltas_for_keys(&self, keys: &[KeyComparison], batches: &[Arc<[Record]>]) -> Vec<ViewDelta> {
+        let cols = self
+            .post_lookup
+            .returned_cols
+            .as_ref()
+            .map(|cols| cols.len());
+        batches
+            .iter()
+            .flat_map(|batch| batch.iter())
+            .filter(|record| {
+                let key = self.index.columns.iter().map(|col| &record[*col]);
+                keys.iter().any(|k| k.contains(key.clone()))
+            })
+            .map(|record| {
+                let mut row = record.rec().to_vec();
+                if let Some(cols) = cols {
+                    row.truncate(cols);
+                }
+                if record.is_positive() {
+                    ViewDelta::Insert(row)
+                } else {
+                    ViewDelta::Delete(row)
+                }
+            })
+            .collect()
+    }
 }
 
 #[cfg(test)]
@@ -590,6 +685,41 @@ mod tests {
         assert_eq!(r.get(&a[0..1]).unwrap()[0], a);
     }
 
+    #[tokio::test]
+    async fn subscribe_to_deltas() {
+        let (mut r, mut w) = new(2, Index::hash_map(vec![0]), ReaderProcessing::default());
+        w.swap();
+
+        let cursor = r.subscribe();
+        let rows = vec![
+            Record::Positive(vec![1.into(), "a".into()]),
+            Record::Positive(vec![2.into(), "b".into()]),
+            Record::Negative(vec![1.into(), "c".into()]),
+        ];
+        w.add_deltas(&rows);
+        w.add(rows);
+        w.swap();
+
+        let keys = vec![KeyComparison::Equal(vec1![1.into()])];
+        let res = r
+            .wait_for_deltas(&keys, cursor, Duration::from_secs(1))
+            .await
+            .unwrap();
+        assert_eq!(
+            res.deltas,
+            vec![
+                ViewDelta::Insert(vec![1.into(), "a".into()]),
+                ViewDelta::Delete(vec![1.into(), "c".into()])
+            ]
+        );
+
+        let res = r
+            .wait_for_deltas(&keys, res.cursor, Duration::from_millis(10))
+            .await
+            .unwrap();
+        assert!(res.deltas.is_empty());
+    }
+
     #[test]
     fn busybusybusy() {
         use std::thread;
@@ -203,6 +203,12 @@ impl Reader {
             });
         }
 
+        // only writes (as opposed to replays filling holes) change the results of queries, so
+        // those are the only ones subscribers are told about
+        if m.is_regular() {
+            m.map_data(|data| state.add_deltas(data));
+        }
+
         state.add(m.take_data());
 
         if swap {
@@ -1,6 +1,7 @@
 use std::cell::RefCell;
+use std::collections::btree_map::Entry;
 use std::collections::hash_map::DefaultHasher;
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 use std::hash::{Hash, Hasher};
 
 pub use nom_sql::{BinaryOperator, Literal, SqlType};
@@ -22,6 +23,23 @@ pub enum Aggregation {
     Avg,
     /// Concatenates using the given separator between values.
     GroupConcat { separator: String },
+    /// The minimum value of the `over` column for all records of each group. Maintains a multiset
+    /// of the values in each group, so that deleting the current minimum doesn't require
+    /// re-querying the parent.
+    Min,
+    /// The maximum value of the `over` column for all records of each group. Maintains a multiset
+    /// of the values in each group, so that deleting the current maximum doesn't require
+    /// re-querying the parent.
+    Max,
+    /// Count the number of distinct non-null values. Maintains a multiset of the values in each
+    /// group.
+    CountDistinct,
+    /// Sum the distinct values of the `over` column for all records of each group. Maintains a
+    /// multiset of the values in each group.
+    SumDistinct,
+    /// Average the distinct values of the `over` column. Maintains a multiset of the values in
+    /// each group, along with the count and sum of the distinct values.
+    AvgDistinct,
 }
 
 impl Aggregation {
@@ -38,11 +56,14 @@ impl Aggregation {
         over_col_ty: &DfType,
     ) -> ReadySetResult<GroupedOperator<Aggregator>> {
         let out_ty = match &self {
-            Aggregation::Count { .. } => DfType::BigInt,
+            Aggregation::Count { .. } | Aggregation::CountDistinct => DfType::BigInt,
             // The SUM() and AVG() functions return a DECIMAL value for exact-value arguments
             // (integer or DECIMAL), and a DOUBLE value for approximate-value arguments (FLOAT or
             // DOUBLE).
-            Aggregation::Sum | Aggregation::Avg => {
+            Aggregation::Sum
+            | Aggregation::Avg
+            | Aggregation::SumDistinct
+            | Aggregation::AvgDistinct => {
                 if over_col_ty.is_any_float() {
                     DfType::Double
                 } else {
@@ -50,6 +71,8 @@ impl Aggregation {
                 }
             }
             Aggregation::GroupConcat { .. } => DfType::Text(/* TODO */ Collation::default()),
+            // MIN() and MAX() return a value of the same type as their argument
+            Aggregation::Min | Aggregation::Max => over_col_ty.clone(),
         };
 
         Ok(GroupedOperator::new(
@@ -59,6 +82,7 @@ impl Aggregation {
                 over,
                 group: group_by.into(),
                 count_sum_map: RefCell::new(Default::default()),
+                group_values: RefCell::new(Default::default()),
                 over_else: None,
                 out_ty,
             },
@@ -87,6 +111,11 @@ pub struct Aggregator {
     // We skip serde since we don't want the state, just the configuration.
     #[serde(skip)]
     count_sum_map: RefCell<HashMap<GroupHash, AverageDataPair>>,
+    // only needed for MIN, MAX and the DISTINCT aggregations. Stores the number of occurrences of
+    // each non-null value in the group, so we know the next extremum when the current one is
+    // removed, and when a value enters or leaves the set of distinct values.
+    #[serde(skip)]
+    group_values: RefCell<HashMap<GroupHash, GroupValues>>,
     over_else: Option<Literal>,
     // Output type of this column
     out_ty: DfType,
@@ -105,6 +134,67 @@ pub struct NumericalDiff {
 
 type GroupHash = u64;
 
+/// Multiset of the non-null values in a group, for MIN, MAX and the DISTINCT aggregations. Maps
+/// each value to its number of occurrences.
+type GroupValues = BTreeMap<DfValue, usize>;
+
+/// Look up the values tracked for the group with the given hash, clearing them if the group has no
+/// `current` value (since in that case the group is either new or being rebuilt from scratch, and
+/// any values we have left over for it are stale).
+///
+/// Groups with no non-null values aren't tracked (see [`remove_empty_group`]), so a `NULL`
+/// `current` value, which only MIN and MAX produce for such groups, starts from an empty set.
+///
+/// Returns `None` if the group has a non-null `current` value but we aren't tracking any values for
+/// it (eg because the operator was restarted), in which case the whole group needs to be replayed
+/// from the parent.
+fn group_values_for<'a>(
+    group_values: &'a mut HashMap<GroupHash, GroupValues>,
+    group_hash: GroupHash,
+    current: Option<&DfValue>,
+) -> Option<&'a mut GroupValues> {
+    match current {
+        None => {
+            let values = group_values.entry(group_hash).or_default();
+            values.clear();
+            Some(values)
+        }
+        Some(DfValue::None) => Some(group_values.entry(group_hash).or_default()),
+        Some(_) => group_values.get_mut(&group_hash),
+    }
+}
+
+/// Stop tracking the values of the group with the given hash if it has no non-null values left, so
+/// that memory isn't held for every group ever seen
+fn remove_empty_group(group_values: &mut HashMap<GroupHash, GroupValues>, group_hash: GroupHash) {
+    if group_values
+        .get(&group_hash)
+        .map_or(false, |values| values.is_empty())
+    {
+        group_values.remove(&group_hash);
+    }
+}
+
+/// Add or remove (according to `positive`) a single occurrence of `value` in `values`, returning
+/// whether that changed the set of distinct values in the group.
+fn update_group_values(values: &mut GroupValues, value: DfValue, positive: bool) -> bool {
+    if positive {
+        let count = values.entry(value).or_default();
+        *count += 1;
+        *count == 1
+    } else if let Entry::Occupied(mut entry) = values.entry(value) {
+        *entry.get_mut() -= 1;
+        if *entry.get() == 0 {
+            entry.remove();
+            true
+        } else {
+            false
+        }
+    } else {
+        false
+    }
+}
+
 /// For storing (Count, Sum) in additional state for Average.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 struct AverageDataPair {
@@ -152,6 +242,81 @@ impl Aggregator {
             _ => internal!(),
         }
     }
+
+    /// Apply `diffs` to the multiset of values tracked for a MIN or MAX group, and return the new
+    /// extremum of the group.
+    ///
+    /// Returns `None` if the values for the group need to be replayed from the parent (see
+    /// [`group_values_for`]).
+    fn apply_extremum(
+        &self,
+        current: Option<&DfValue>,
+        diffs: &mut dyn Iterator<Item = NumericalDiff>,
+    ) -> ReadySetResult<Option<DfValue>> {
+        let mut diffs = diffs.peekable();
+        let group_hash = match diffs.peek() {
+            Some(diff) => diff.group_hash,
+            None => return Ok(current.cloned()),
+        };
+
+        let mut group_values = self.group_values.borrow_mut();
+        let values = match group_values_for(&mut group_values, group_hash, current) {
+            Some(values) => values,
+            None => return Ok(None),
+        };
+
+        for diff in diffs {
+            if !diff.value.is_none() {
+                update_group_values(values, diff.value, diff.positive);
+            }
+        }
+
+        let extremum = match self.op {
+            Aggregation::Min => values.keys().next(),
+            Aggregation::Max => values.keys().next_back(),
+            _ => internal!("apply_extremum called for {:?}", self.op),
+        }
+        // A group consisting entirely of NULLs has a NULL extremum
+        .cloned()
+        .unwrap_or(DfValue::None);
+
+        remove_empty_group(&mut group_values, group_hash);
+        Ok(Some(extremum))
+    }
+
+    /// Filter `diffs` for a DISTINCT aggregation down to the diffs that add a new distinct value to
+    /// the group or remove the last occurrence of one, updating the multiset of values tracked for
+    /// the group along the way.
+    ///
+    /// Returns `None` if the values for the group need to be replayed from the parent (see
+    /// [`group_values_for`]).
+    fn distinct_diffs(
+        &self,
+        current: Option<&DfValue>,
+        diffs: &mut dyn Iterator<Item = NumericalDiff>,
+    ) -> Option<Vec<NumericalDiff>> {
+        let mut diffs = diffs.peekable();
+        let group_hash = match diffs.peek() {
+            Some(diff) => diff.group_hash,
+            None => return Some(vec![]),
+        };
+
+        let mut group_values = self.group_values.borrow_mut();
+        let values = group_values_for(&mut group_values, group_hash, current)?;
+        if current.is_none() {
+            self.count_sum_map.borrow_mut().remove(&group_hash);
+        }
+
+        let diffs = diffs
+            .filter(|diff| {
+                !diff.value.is_none()
+                    && update_group_values(values, diff.value.clone(), diff.positive)
+            })
+            .collect();
+
+        remove_empty_group(&mut group_values, group_hash);
+        Some(diffs)
+    }
 }
 
 impl GroupedOperation for Aggregator {
@@ -188,6 +353,19 @@ impl GroupedOperation for Aggregator {
         current: Option<&DfValue>,
         diffs: &mut dyn Iterator<Item = Self::Diff>,
     ) -> ReadySetResult<Option<DfValue>> {
+        let mut distinct_diffs;
+        let diffs = match self.op {
+            Aggregation::Min | Aggregation::Max => return self.apply_extremum(current, diffs),
+            Aggregation::CountDistinct | Aggregation::SumDistinct | Aggregation::AvgDistinct => {
+                distinct_diffs = match self.distinct_diffs(current, diffs) {
+                    Some(diffs) => diffs.into_iter(),
+                    None => return Ok(None),
+                };
+                &mut distinct_diffs as &mut dyn Iterator<Item = Self::Diff>
+            }
+            _ => diffs,
+        };
+
         let apply_count = |curr: DfValue, diff: Self::Diff| -> ReadySetResult<DfValue> {
             if diff.positive {
                 &curr + &DfValue::Int(1)
@@ -215,21 +393,25 @@ impl GroupedOperation for Aggregator {
                 .apply_diff(diff)
         };
 
-        let apply_diff =
-            |curr: ReadySetResult<DfValue>, diff: Self::Diff| -> ReadySetResult<DfValue> {
-                if diff.value.is_none() {
-                    return curr;
-                }
+        let apply_diff = |curr: ReadySetResult<DfValue>,
+                          diff: Self::Diff|
+         -> ReadySetResult<DfValue> {
+            if diff.value.is_none() {
+                return curr;
+            }
 
-                match self.op {
-                    Aggregation::Count { .. } => apply_count(curr?, diff),
-                    Aggregation::Sum => apply_sum(curr?, diff),
-                    Aggregation::Avg => apply_avg(curr?, diff),
-                    Aggregation::GroupConcat { separator: _ } => internal!(
-                        "GroupConcats are separate from the other aggregations in the dataflow."
-                    ),
+            match self.op {
+                Aggregation::Count { .. } | Aggregation::CountDistinct => apply_count(curr?, diff),
+                Aggregation::Sum | Aggregation::SumDistinct => apply_sum(curr?, diff),
+                Aggregation::Avg | Aggregation::AvgDistinct => apply_avg(curr?, diff),
+                Aggregation::GroupConcat { separator: _ } => internal!(
+                    "GroupConcats are separate from the other aggregations in the dataflow."
+                ),
+                Aggregation::Min | Aggregation::Max => {
+                    internal!("MIN and MAX are applied by apply_extremum")
                 }
-            };
+            }
+        };
 
         diffs
             .fold(Ok(current.cloned().unwrap_or(self.new_data()?)), apply_diff)
@@ -245,6 +427,11 @@ impl GroupedOperation for Aggregator {
                 Aggregation::GroupConcat { separator: ref s } => {
                     format!("||({})", s)
                 }
+                Aggregation::Min => "MIN".to_owned(),
+                Aggregation::Max => "MAX".to_owned(),
+                Aggregation::CountDistinct => "+DISTINCT".to_owned(),
+                Aggregation::SumDistinct => "𝛴DISTINCT".to_owned(),
+                Aggregation::AvgDistinct => "AvgDISTINCT".to_owned(),
             };
         }
 
@@ -253,6 +440,11 @@ impl GroupedOperation for Aggregator {
             Aggregation::Sum => format!("𝛴({})", self.over),
             Aggregation::Avg => format!("Avg({})", self.over),
             Aggregation::GroupConcat { separator: ref s } => format!("||({}, {})", s, self.over),
+            Aggregation::Min => format!("min({})", self.over),
+            Aggregation::Max => format!("max({})", self.over),
+            Aggregation::CountDistinct => format!("|DISTINCT {}|", self.over),
+            Aggregation::SumDistinct => format!("𝛴(DISTINCT {})", self.over),
+            Aggregation::AvgDistinct => format!("Avg(DISTINCT {})", self.over),
         };
         let group_cols = self
             .group
@@ -273,16 +465,16 @@ impl GroupedOperation for Aggregator {
 
     fn empty_value(&self) -> Option<DfValue> {
         match self.op {
-            Aggregation::Count { .. } => Some(0.into()),
+            Aggregation::Count { .. } | Aggregation::CountDistinct => Some(0.into()),
             _ => None,
         }
     }
 
     fn emit_empty(&self) -> bool {
         match self.op {
-            Aggregation::Count { .. } | Aggregation::GroupConcat { .. } => {
-                self.group_by().is_empty()
-            }
+            Aggregation::Count { .. }
+            | Aggregation::CountDistinct
+            | Aggregation::GroupConcat { .. } => self.group_by().is_empty(),
             _ => false,
         }
     }
@@ -343,6 +535,21 @@ mod tests {
             .over(src, 1, &[2, 0], &DfType::Unknown)
             .unwrap();
         assert_eq!(a.description(true), "Avg(1) γ[2, 0]");
+
+        let mn = Aggregation::Min
+            .over(src, 1, &[2, 0], &DfType::Unknown)
+            .unwrap();
+        assert_eq!(mn.description(true), "min(1) γ[2, 0]");
+
+        let mx = Aggregation::Max
+            .over(src, 1, &[2, 0], &DfType::Unknown)
+            .unwrap();
+        assert_eq!(mx.description(true), "max(1) γ[2, 0]");
+
+        let cd = Aggregation::CountDistinct
+            .over(src, 1, &[2, 0], &DfType::Unknown)
+            .unwrap();
+        assert_eq!(cd.description(true), "|DISTINCT 1| γ[2, 0]");
     }
 
     /// Testing count emits correct records with single column group and single over column
@@ -1118,4 +1325,281 @@ mod tests {
             .into()
         );
     }
+
+    #[test]
+    fn max_removes_current_maximum() {
+        // The parent isn't materialized, so these tests would fail if removing the current
+        // extremum required re-querying it
+        let mut c = setup(Aggregation::Max, true);
+
+        c.narrow_one_row(vec![1.into(), 4.into()], true);
+        c.narrow_one_row(vec![1.into(), 7.into()], true);
+        c.narrow_one_row(vec![1.into(), 7.into()], true);
+
+        // Removing one of two copies of the maximum leaves the maximum as-is
+        let rs = c.narrow_one_row((vec![1.into(), 7.into()], false), true);
+        assert_eq!(
+            rs,
+            vec![
+                (vec![1.into(), 7.into(), 3.into()], false),
+                (vec![1.into(), 7.into(), 2.into()], true),
+            ]
+            .into()
+        );
+
+        // Removing the last copy of the maximum falls back to the next largest value
+        let rs = c.narrow_one_row((vec![1.into(), 7.into()], false), true);
+        assert_eq!(
+            rs,
+            vec![
+                (vec![1.into(), 7.into(), 2.into()], false),
+                (vec![1.into(), 4.into(), 1.into()], true),
+            ]
+            .into()
+        );
+
+        // Removing the last value removes the group
+        let rs = c.narrow_one_row((vec![1.into(), 4.into()], false), true);
+        assert_eq!(rs, vec![(vec![1.into(), 4.into(), 1.into()], false)].into());
+    }
+
+    #[test]
+    fn empty_groups_are_not_tracked() {
+        let agg = Aggregator {
+            op: Aggregation::Max,
+            over: 1,
+            group: vec![0],
+            count_sum_map: Default::default(),
+            group_values: Default::default(),
+            over_else: None,
+            out_ty: DfType::Double,
+        };
+        let diff = |value: DfValue, positive| agg.to_diff(&[1.into(), value], positive).unwrap();
+
+        let max = agg
+            .apply(
+                None,
+                &mut vec![diff(4.into(), true), diff(7.into(), true)].into_iter(),
+            )
+            .unwrap();
+        assert_eq!(max, Some(7.into()));
+        assert_eq!(agg.group_values.borrow().len(), 1);
+
+        let max = agg
+            .apply(
+                max.as_ref(),
+                &mut vec![diff(4.into(), false), diff(7.into(), false)].into_iter(),
+            )
+            .unwrap();
+        assert_eq!(max, Some(DfValue::None));
+        assert!(agg.group_values.borrow().is_empty());
+
+        // A group with a NULL maximum has no values to replay from the parent
+        let max = agg
+            .apply(
+                max.as_ref(),
+                &mut vec![diff(DfValue::None, true), diff(3.into(), true)].into_iter(),
+            )
+            .unwrap();
+        assert_eq!(max, Some(3.into()));
+    }
+
+    #[test]
+    fn min_removes_current_minimum() {
+        let mut c = setup(Aggregation::Min, true);
+
+        c.narrow_one_row(vec![1.into(), 4.into()], true);
+        c.narrow_one_row(vec![1.into(), 2.into()], true);
+        c.narrow_one_row(vec![2.into(), 1.into()], true);
+
+        let rs = c.narrow_one_row((vec![1.into(), 2.into()], false), true);
+        assert_eq!(
+            rs,
+            vec![
+                (vec![1.into(), 2.into(), 2.into()], false),
+                (vec![1.into(), 4.into(), 1.into()], true),
+            ]
+            .into()
+        );
+
+        // Replacing the minimum in one batch only emits the new minimum
+        let rs = c.narrow_one(
+            vec![
+                (vec![1.into(), 4.into()], false),
+                (vec![1.into(), 3.into()], true),
+            ],
+            true,
+        );
+        assert_eq!(
+            rs,
+            vec![
+                (vec![1.into(), 4.into(), 1.into()], false),
+                (vec![1.into(), 3.into(), 1.into()], true),
+            ]
+            .into()
+        );
+    }
+
+    #[test]
+    fn min_ignores_nulls() {
+        let mut c = setup(Aggregation::Min, true);
+
+        let rs = c.narrow_one_row(vec![1.into(), DfValue::None], true);
+        assert_eq!(rs, vec![vec![1.into(), DfValue::None, 1.into()]].into());
+
+        let rs = c.narrow_one_row(vec![1.into(), 5.into()], true);
+        assert_eq!(
+            rs,
+            vec![
+                (vec![1.into(), DfValue::None, 1.into()], false),
+                (vec![1.into(), 5.into(), 2.into()], true),
+            ]
+            .into()
+        );
+
+        let rs = c.narrow_one_row((vec![1.into(), 5.into()], false), true);
+        assert_eq!(
+            rs,
+            vec![
+                (vec![1.into(), 5.into(), 2.into()], false),
+                (vec![1.into(), DfValue::None, 1.into()], true),
+            ]
+            .into()
+        );
+    }
+
+    #[test]
+    fn count_distinct_forwards() {
+        let mut c = setup(Aggregation::CountDistinct, true);
+
+        let rs = c.narrow_one_row(vec![1.into(), 1.into()], true);
+        assert_eq!(rs, vec![vec![1.into(), 1.into(), 1.into()]].into());
+
+        // A duplicate value doesn't change the count, only the number of rows in the group
+        let rs = c.narrow_one_row(vec![1.into(), 1.into()], true);
+        assert_eq!(
+            rs,
+            vec![
+                (vec![1.into(), 1.into(), 1.into()], false),
+                (vec![1.into(), 1.into(), 2.into()], true),
+            ]
+            .into()
+        );
+
+        let rs = c.narrow_one_row(vec![1.into(), 2.into()], true);
+        assert_eq!(
+            rs,
+            vec![
+                (vec![1.into(), 1.into(), 2.into()], false),
+                (vec![1.into(), 2.into(), 3.into()], true),
+            ]
+            .into()
+        );
+
+        // NULLs aren't counted
+        let rs = c.narrow_one_row(vec![1.into(), DfValue::None], true);
+        assert_eq!(
+            rs,
+            vec![
+                (vec![1.into(), 2.into(), 3.into()], false),
+                (vec![1.into(), 2.into(), 4.into()], true),
+            ]
+            .into()
+        );
+
+        // Removing one of the two copies of a value keeps it in the count
+        let rs = c.narrow_one_row((vec![1.into(), 1.into()], false), true);
+        assert_eq!(
+            rs,
+            vec![
+                (vec![1.into(), 2.into(), 4.into()], false),
+                (vec![1.into(), 2.into(), 3.into()], true),
+            ]
+            .into()
+        );
+
+        // Removing the last copy of a value removes it from the count
+        let rs = c.narrow_one_row((vec![1.into(), 1.into()], false), true);
+        assert_eq!(
+            rs,
+            vec![
+                (vec![1.into(), 2.into(), 3.into()], false),
+                (vec![1.into(), 1.into(), 2.into()], true),
+            ]
+            .into()
+        );
+    }
+
+    #[test]
+    fn sum_distinct_forwards() {
+        let mut c = setup(Aggregation::SumDistinct, true);
+
+        c.narrow_one_row(vec![1.into(), 2.into()], true);
+        let rs = c.narrow_one(
+            vec![
+                (vec![1.into(), 2.into()], true),
+                (vec![1.into(), 3.into()], true),
+            ],
+            true,
+        );
+        assert_eq!(
+            rs,
+            vec![
+                (
+                    vec![1.into(), DfValue::try_from(2.0f64).unwrap(), 1.into()],
+                    false
+                ),
+                (
+                    vec![1.into(), DfValue::try_from(5.0f64).unwrap(), 3.into()],
+                    true
+                ),
+            ]
+            .into()
+        );
+
+        let rs = c.narrow_one_row((vec![1.into(), 3.into()], false), true);
+        assert_eq!(
+            rs,
+            vec![
+                (
+                    vec![1.into(), DfValue::try_from(5.0f64).unwrap(), 3.into()],
+                    false
+                ),
+                (
+                    vec![1.into(), DfValue::try_from(2.0f64).unwrap(), 2.into()],
+                    true
+                ),
+            ]
+            .into()
+        );
+    }
+
+    #[test]
+    fn avg_distinct_forwards() {
+        let mut c = setup(Aggregation::AvgDistinct, true);
+
+        c.narrow_one(
+            vec![
+                (vec![1.into(), 1.into()], true),
+                (vec![1.into(), 1.into()], true),
+                (vec![1.into(), 1.into()], true),
+            ],
+            true,
+        );
+        let rs = c.narrow_one_row(vec![1.into(), 4.into()], true);
+        assert_eq!(
+            rs,
+            vec![
+                (
+                    vec![1.into(), DfValue::try_from(1.0f64).unwrap(), 3.into()],
+                    false
+                ),
+                (
+                    vec![1.into(), DfValue::try_from(2.5f64).unwrap(), 4.into()],
+                    true
+                ),
+            ]
+            .into()
+        );
+    }
 }
@@ -24,6 +24,30 @@ pub enum JoinType {
     Left,
     /// Inner join between two views
     Inner,
+    /// Full outer join between two views
+    Full,
+}
+
+impl JoinType {
+    /// Returns true if rows from the left parent with no match in the right parent should be
+    /// emitted, padded with nulls
+    fn preserves_left(&self) -> bool {
+        matches!(self, JoinType::Left | JoinType::Full)
+    }
+
+    /// Returns true if rows from the right parent with no match in the left parent should be
+    /// emitted, padded with nulls
+    fn preserves_right(&self) -> bool {
+        matches!(self, JoinType::Full)
+    }
+
+    fn symbol(&self) -> &'static str {
+        match self {
+            JoinType::Left => "⋉",
+            JoinType::Inner => "⋈",
+            JoinType::Full => "⟗",
+        }
+    }
 }
 
 /// Where to source a join column
@@ -194,6 +218,30 @@ impl Join {
             .collect()
     }
 
+    /// Generate a row for a record from the right parent with no match in the left parent, for
+    /// full outer joins. Note that this sets *all* columns sourced from the left to null,
+    /// including join key columns.
+    fn generate_null_left(&self, right: &[DfValue]) -> Vec<DfValue> {
+        self.emit
+            .iter()
+            .map(|&(side, col)| {
+                if side == Side::Right {
+                    right[col].clone()
+                } else {
+                    DfValue::None
+                }
+            })
+            .collect()
+    }
+
+    /// Generate a null-padded row for a record with no match in the other parent
+    fn generate_unmatched(&self, side: Side, row: &[DfValue]) -> Vec<DfValue> {
+        match side {
+            Side::Left => self.generate_null(row),
+            Side::Right => self.generate_null_left(row),
+        }
+    }
+
     fn resolve_col(&self, col: usize) -> (Option<usize>, Option<usize>) {
         let (side, pcol) = self.emit[col];
 
@@ -235,7 +283,18 @@ impl Ingredient for Join {
     }
 
     fn must_replay_among(&self) -> Option<HashSet<NodeIndex>> {
-        Some(Some(self.left.as_global()).into_iter().collect())
+        if self.kind == JoinType::Full {
+            // Rows from the right with no match in the left can only be produced by replaying
+            // the right parent - full replays from the right only emit those rows, so that
+            // matching rows aren't duplicated
+            Some(
+                [self.left.as_global(), self.right.as_global()]
+                    .into_iter()
+                    .collect(),
+            )
+        } else {
+            Some(Some(self.left.as_global()).into_iter().collect())
+        }
     }
 
     fn on_connected(&mut self, _g: &Graph) {}
@@ -260,9 +319,29 @@ impl Ingredient for Join {
         let mut lookups = Vec::new();
 
         let from_left = from == *self.left;
+        let (from_side, other_side) = if from_left {
+            (Side::Left, Side::Right)
+        } else {
+            (Side::Right, Side::Left)
+        };
 
         let other = if from_left { *self.right } else { *self.left };
 
+        // Whether unmatched rows from the side this update came from are emitted padded with
+        // nulls, and whether the same is true for unmatched rows on the other side
+        let (from_preserved, other_preserved) = if from_left {
+            (self.kind.preserves_left(), self.kind.preserves_right())
+        } else {
+            (self.kind.preserves_right(), self.kind.preserves_left())
+        };
+
+        // During a full replay through both parents of a full outer join, the replay through the
+        // left parent produces all rows with a match on both sides, so the replay through the
+        // right parent must only produce the rows with no match in the left.
+        let full_replay_unmatched_only = self.kind == JoinType::Full
+            && !from_left
+            && matches!(replay, ReplayContext::Full { .. });
+
         let (from_key, other_key): (Vec<usize>, Vec<usize>) = if from_left {
             self.on.iter().copied().unzip()
         } else {
@@ -384,12 +463,15 @@ impl Ingredient for Join {
             // The difference between a left join and an inner join, is that for the former we must
             // emit rows with nulls even if we later get no match in the other side.
 
-            let mut new_right_count = None;
+            let mut new_from_count = None;
 
-            if self.kind == JoinType::Left && !from_left {
+            // If rows on the other side are null-padded when they have no match on our side, we
+            // have to know whether our side went from having no rows to having some rows for this
+            // key (or vice versa), so we can retract (or emit) those null-padded rows
+            if other_preserved && !matches!(replay, ReplayContext::Full { .. }) {
                 let rc = self.lookup(
-                    *self.right,
-                    &self.on_right(),
+                    from,
+                    &from_key,
                     &PointKey::from(join_key.iter().cloned()),
                     nodes,
                     state,
@@ -400,8 +482,8 @@ impl Ingredient for Join {
                     IngredientLookupResult::Records(rc) => {
                         if replay_key_cols.is_some() && !nulls {
                             lookups.push(Lookup {
-                                on: *self.right,
-                                cols: self.on_right(),
+                                on: from,
+                                cols: from_key.clone(),
                                 key: join_key
                                     .clone()
                                     .try_into()
@@ -410,7 +492,7 @@ impl Ingredient for Join {
                         }
 
                         let rc = rc.count();
-                        new_right_count = Some(rc);
+                        new_from_count = Some(rc);
                     }
                     IngredientLookupResult::Miss => {
                         // we got something from right, but that row's key is not in right??
@@ -475,11 +557,12 @@ impl Ingredient for Join {
                 rc_diff += if positive { 1 } else { -1 };
 
                 if other_rows.is_empty() {
-                    if self.kind == JoinType::Left && from_left {
-                        // left join, got a thing from left, no rows in right == NULL
-                        ret.push((self.generate_null(&row), positive).into());
+                    if from_preserved {
+                        // outer join, got a thing from a preserved side, no rows in the other
+                        // side == NULL
+                        ret.push((self.generate_unmatched(from_side, &row), positive).into());
                     }
-                } else {
+                } else if !full_replay_unmatched_only {
                     for other in other_rows.iter() {
                         if from == *self.left {
                             ret.push((self.generate_row(&row, other), positive).into());
@@ -490,17 +573,18 @@ impl Ingredient for Join {
                 }
             }
 
-            // For a left join with updates from the right side, we also have to emit/delete NULL
-            // rows if row count changed to/from zero
-            if let Some(new_rc) = new_right_count {
+            // For a left join with updates from the right side (or a full join with updates from
+            // either side), we also have to emit/delete NULL rows for the other side if row count
+            // changed to/from zero
+            if let Some(new_rc) = new_from_count {
                 let old_rc = new_rc as isize - rc_diff;
                 if new_rc == 0 && old_rc != 0 {
                     for other in other_rows.iter() {
-                        ret.push((self.generate_null(other), true).into());
+                        ret.push((self.generate_unmatched(other_side, other), true).into());
                     }
                 } else if new_rc != 0 && old_rc == 0 {
                     for other in other_rows.iter() {
-                        ret.push((self.generate_null(other), false).into());
+                        ret.push((self.generate_unmatched(other_side, other), false).into());
                     }
                 }
             }
@@ -526,10 +610,7 @@ impl Ingredient for Join {
 
     fn description(&self, detailed: bool) -> String {
         if !detailed {
-            return String::from(match self.kind {
-                JoinType::Left => "⋉",
-                JoinType::Inner => "⋈",
-            });
+            return String::from(self.kind.symbol());
         }
 
         let emit = self
@@ -545,10 +626,7 @@ impl Ingredient for Join {
             .collect::<Vec<_>>()
             .join(", ");
 
-        let op = match self.kind {
-            JoinType::Left => "⋉",
-            JoinType::Inner => "⋈",
-        };
+        let op = self.kind.symbol();
 
         format!(
             "[{}] {}:({}) {} {}:({})",
@@ -719,6 +797,10 @@ impl Ingredient for Join {
                     .try_into()
                     .unwrap(),
             )
+        } else if self.kind == JoinType::Full {
+            // Generated columns are replayed by joining the rows from both parents for the key,
+            // which can't produce the null-padded rows of a full join
+            ColumnSource::RequiresFullReplay(vec1![self.left.as_global(), self.right.as_global()])
         } else {
             let right_cols = right_cols
                 .into_iter()
@@ -962,6 +1044,183 @@ mod tests {
         assert_eq!(res, vec![(l.as_global(), Some(0))]);
     }
 
+    mod full_join {
+        use super::*;
+
+        fn setup() -> (ops::test::MockGraph, IndexPair, IndexPair) {
+            let mut g = ops::test::MockGraph::new();
+            let l = g.add_base("left", &["l0", "l1"]);
+            let r = g.add_base("right", &["r0", "r1"]);
+
+            use self::JoinSource::*;
+            let j = Join::new(
+                l.as_global(),
+                r.as_global(),
+                JoinType::Full,
+                vec![B(0, 0), L(1), R(0), R(1)],
+            );
+
+            g.set_op("join", &["j0", "j1", "j2", "j3"], j, false);
+            (g, l, r)
+        }
+
+        #[test]
+        fn it_describes() {
+            let (j, l, r) = setup();
+            assert_eq!(
+                j.node().description(true),
+                format!("[{}:0, {}:1, {}:0, {}:1] {}:(0) ⟗ {}:(0)", l, l, r, r, l, r)
+            );
+        }
+
+        #[test]
+        fn replays_through_both_parents() {
+            let (j, l, r) = setup();
+            assert_eq!(
+                j.node().must_replay_among(),
+                Some([l.as_global(), r.as_global()].into_iter().collect())
+            );
+        }
+
+        #[test]
+        fn unmatched_rows_from_both_sides() {
+            let (mut j, l, r) = setup();
+            let l_1a = vec![1.into(), "a".try_into().unwrap()];
+            let r_2x = vec![2.into(), "x".try_into().unwrap()];
+
+            // forward from left with no rows in right; should produce [row + None]
+            j.seed(l, l_1a.clone());
+            let rs = j.one_row(l, l_1a, false);
+            assert_eq!(
+                rs,
+                vec![(
+                    vec![
+                        1.into(),
+                        "a".try_into().unwrap(),
+                        DfValue::None,
+                        DfValue::None
+                    ],
+                    true
+                )]
+                .into()
+            );
+
+            // forward from right with no rows in left; should produce [None + row]
+            j.seed(r, r_2x.clone());
+            let rs = j.one_row(r, r_2x, false);
+            assert_eq!(
+                rs,
+                vec![(
+                    vec![
+                        DfValue::None,
+                        DfValue::None,
+                        2.into(),
+                        "x".try_into().unwrap()
+                    ],
+                    true
+                )]
+                .into()
+            );
+        }
+
+        #[test]
+        fn retracts_nulls_on_match() {
+            let (mut j, l, r) = setup();
+            let l_1a = vec![1.into(), "a".try_into().unwrap()];
+            let l_2b = vec![2.into(), "b".try_into().unwrap()];
+            let r_1y = vec![1.into(), "y".try_into().unwrap()];
+            let r_2x = vec![2.into(), "x".try_into().unwrap()];
+
+            j.seed(l, l_1a.clone());
+            j.one_row(l, l_1a, false);
+            j.seed(r, r_2x.clone());
+            j.one_row(r, r_2x, false);
+
+            // a match from the right should revoke the null-padded row from the left
+            j.seed(r, r_1y.clone());
+            let rs = j.one_row(r, r_1y, false);
+            assert_eq!(
+                rs,
+                vec![
+                    (
+                        vec![
+                            1.into(),
+                            "a".try_into().unwrap(),
+                            1.into(),
+                            "y".try_into().unwrap()
+                        ],
+                        true
+                    ),
+                    (
+                        vec![
+                            1.into(),
+                            "a".try_into().unwrap(),
+                            DfValue::None,
+                            DfValue::None
+                        ],
+                        false
+                    ),
+                ]
+                .into()
+            );
+
+            // and a match from the left should revoke the null-padded row from the right
+            j.seed(l, l_2b.clone());
+            let rs = j.one_row(l, l_2b, false);
+            assert_eq!(
+                rs,
+                vec![
+                    (
+                        vec![
+                            2.into(),
+                            "b".try_into().unwrap(),
+                            2.into(),
+                            "x".try_into().unwrap()
+                        ],
+                        true
+                    ),
+                    (
+                        vec![
+                            DfValue::None,
+                            DfValue::None,
+                            2.into(),
+                            "x".try_into().unwrap()
+                        ],
+                        false
+                    ),
+                ]
+                .into()
+            );
+        }
+
+        #[test]
+        fn nulls_from_right() {
+            let (mut j, l, r) = setup();
+
+            let l_nulla = vec![DfValue::None, "a".try_into().unwrap()];
+            j.seed(l, l_nulla.clone());
+            j.one_row(l, l_nulla, false);
+
+            // null join keys never match, so the right row is emitted on its own
+            let r_nully = vec![DfValue::None, "y".try_into().unwrap()];
+            j.seed(r, r_nully.clone());
+            let rs = j.one_row(r, r_nully, false);
+            assert_eq!(
+                rs,
+                vec![(
+                    vec![
+                        DfValue::None,
+                        DfValue::None,
+                        DfValue::None,
+                        "y".try_into().unwrap()
+                    ],
+                    true
+                )]
+                .into()
+            );
+        }
+    }
+
     mod handle_upquery {
         use std::ops::Bound;
 
@@ -18,6 +18,7 @@ pub mod project;
 pub mod topk;
 pub mod union;
 pub(crate) mod utils;
+pub mod window;
 
 use crate::ops::grouped::concat::GroupConcat;
 use crate::processing::{
@@ -56,6 +57,7 @@ pub enum NodeOperator {
     Identity(identity::Identity),
     Filter(filter::Filter),
     TopK(topk::TopK),
+    Window(window::Window),
 }
 
 impl ToString for NodeOperator {
@@ -72,6 +74,7 @@ impl ToString for NodeOperator {
             NodeOperator::Identity(_) => "Identity",
             NodeOperator::Filter(_) => "Filter",
             NodeOperator::TopK(_) => "TopK",
+            NodeOperator::Window(_) => "Window",
         }
         .to_string()
     }
@@ -91,6 +94,7 @@ macro_rules! impl_ingredient_fn_mut {
             NodeOperator::Identity(ref mut i) => i.$fn($($arg),*),
             NodeOperator::Filter(ref mut i) => i.$fn($($arg),*),
             NodeOperator::TopK(ref mut i) => i.$fn($($arg),*),
+            NodeOperator::Window(ref mut i) => i.$fn($($arg),*),
         }
     }
 }
@@ -109,6 +113,7 @@ macro_rules! impl_ingredient_fn_ref {
             NodeOperator::Identity(ref i) => i.$fn($($arg),*),
             NodeOperator::Filter(ref i) => i.$fn($($arg),*),
             NodeOperator::TopK(ref i) => i.$fn($($arg),*),
+            NodeOperator::Window(ref i) => i.$fn($($arg),*),
         }
     }
 }
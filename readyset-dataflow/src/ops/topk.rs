@@ -145,7 +145,8 @@ impl TopK {
                 .checked_sub(current.len())
                 .and_then(NonZeroUsize::new)
             {
-                // there used to be k things in the group, now there are fewer than k.
+                // there used to be k things in the group, now there are fewer than k - refill the
+                // group with the best `diff` records in our parent that aren't already in it.
                 match self.lookup(
                     *self.src,
                     &self.group_by,
@@ -161,6 +162,16 @@ impl TopK {
                     }
                     IngredientLookupResult::Records(rs) => {
                         let mut rs = rs.collect::<Result<Vec<_>, _>>()?;
+                        // Our parent has already seen this batch, so every record still in the
+                        // group is also in `rs`. We can't just skip the first `current.len()`
+                        // records after sorting, since records that compare equal can be sorted in
+                        // any order, so instead remove exactly one copy of each record in the
+                        // group.
+                        for CurrentRecord { row, .. } in current.iter() {
+                            if let Some(pos) = rs.iter().position(|r| r.as_ref() == row.as_ref()) {
+                                rs.swap_remove(pos);
+                            }
+                        }
                         rs.sort_unstable_by(|a, b| {
                             self.order.cmp(a.as_ref(), b.as_ref()).reverse()
                         });
@@ -171,7 +182,6 @@ impl TopK {
                                     order: &self.order,
                                     is_new: true,
                                 })
-                                .skip(current.len())
                                 .take(diff.get()),
                         );
                         lookup = Some(Lookup {
@@ -617,6 +627,45 @@ mod tests {
         );
     }
 
+    #[test]
+    fn it_refills_from_parent_with_equal_order_values() {
+        let (mut g, s) = setup(false);
+
+        let r12: Vec<DfValue> = vec![1.into(), "z".try_into().unwrap(), 12.into()];
+        let r10a: Vec<DfValue> = vec![2.into(), "z".try_into().unwrap(), 10.into()];
+        let r10b: Vec<DfValue> = vec![3.into(), "z".try_into().unwrap(), 10.into()];
+        let r10c: Vec<DfValue> = vec![4.into(), "z".try_into().unwrap(), 10.into()];
+
+        // fill the parent (but not with 12 since we'll delete it)
+        g.seed(s, r10a.clone());
+        g.seed(s, r10b.clone());
+        g.seed(s, r10c.clone());
+
+        // fill topk - r10c compares equal to the minimum, so it won't displace either of the
+        // records already in the group
+        g.narrow_one_row(r12.clone(), true);
+        g.narrow_one_row(r10a, true);
+        g.narrow_one_row(r10b, true);
+        let a = g.narrow_one_row(r10c.clone(), true);
+        assert!(a.is_empty());
+
+        // [1, z, 12]
+        // [2, z, 10]
+        // [3, z, 10]
+
+        // removing 12 must bring in the only record that isn't already in the group, rather than
+        // duplicating one that compares equal to it
+        let delta = g.narrow_one_row((r12.clone(), false), true);
+        assert_eq!(delta.len(), 2);
+        assert!(delta.iter().any(|r| r == &(r12.clone(), false).into()));
+        assert!(
+            delta.iter().any(|r| r == &(r10c.clone(), true).into()),
+            "a = {:?} does not contain ({:?}, true)",
+            &delta,
+            r10c
+        );
+    }
+
     #[test]
     fn it_forwards_reversed() {
         use std::convert::TryFrom;
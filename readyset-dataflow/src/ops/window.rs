@@ -0,0 +1,786 @@
+use std::cmp::Ordering;
+use std::collections::HashMap;
+use std::convert::TryInto;
+use std::fmt;
+
+use dataflow_state::PointKey;
+use itertools::Itertools;
+use launchpad::Indices;
+use maplit::hashmap;
+use nom_sql::OrderType;
+use readyset::internal;
+use readyset_data::DfType;
+use readyset_errors::{internal_err, invariant, ReadySetResult};
+use serde::{Deserialize, Serialize};
+
+use crate::ops::utils::Order;
+use crate::prelude::*;
+use crate::processing::{ColumnSource, LookupIndex};
+
+/// Supported window functions.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum WindowFunction {
+    /// The number of the row within its partition, starting at 1
+    RowNumber,
+    /// The rank of the row within its partition, with gaps: rows that are peers according to the
+    /// window's ordering get the same rank, and the next row gets the rank it would have had if
+    /// the peers had distinct ranks
+    Rank,
+    /// The rank of the row within its partition, without gaps
+    DenseRank,
+    /// The value of the `over` column in the row `offset` rows before the current row within the
+    /// partition, or `default` if there is no such row
+    Lag { offset: usize, default: DfValue },
+    /// The value of the `over` column in the row `offset` rows after the current row within the
+    /// partition, or `default` if there is no such row
+    Lead { offset: usize, default: DfValue },
+    /// The sum of the `over` column for all rows in the partition up to and including the current
+    /// row and its peers (or all rows in the partition, if the window has no ordering)
+    Sum,
+    /// The number of non-null values of the `over` column (or of rows, if there is no `over`
+    /// column) in the partition up to and including the current row and its peers (or all rows in
+    /// the partition, if the window has no ordering)
+    Count,
+}
+
+impl fmt::Display for WindowFunction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            WindowFunction::RowNumber => write!(f, "ROW_NUMBER"),
+            WindowFunction::Rank => write!(f, "RANK"),
+            WindowFunction::DenseRank => write!(f, "DENSE_RANK"),
+            WindowFunction::Lag { offset, .. } => write!(f, "LAG[{}]", offset),
+            WindowFunction::Lead { offset, .. } => write!(f, "LEAD[{}]", offset),
+            WindowFunction::Sum => write!(f, "SUM"),
+            WindowFunction::Count => write!(f, "COUNT"),
+        }
+    }
+}
+
+impl WindowFunction {
+    /// Returns true if this window function takes an `over` column
+    pub fn requires_over_column(&self) -> bool {
+        matches!(
+            self,
+            WindowFunction::Lag { .. } | WindowFunction::Lead { .. } | WindowFunction::Sum
+        )
+    }
+}
+
+/// Window implements an operator which computes a [`WindowFunction`] over the rows of each
+/// partition of its input, and emits every input row with the value of the window function for
+/// that row appended as an extra column.
+///
+/// Like the grouped operators, `Window` keeps its own output materialized, indexed by the
+/// partition columns. Whenever a batch of records arrives for a partition, the operator looks up
+/// the current contents of that partition in its own state, applies the batch, and recomputes the
+/// window function for the rows from the first position in the partition's ordering that the batch
+/// changed onwards, emitting the rows whose window value changed.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Window {
+    src: IndexPair,
+
+    /// The index of this node
+    our_index: Option<IndexPair>,
+
+    function: WindowFunction,
+    over: Option<usize>,
+
+    /// The list of column indices that we're partitioning by.
+    partition_by: Vec<usize>,
+    order: Order,
+    order_cols: Vec<(usize, OrderType)>,
+
+    /// The type of the column this operator appends to its input
+    out_ty: DfType,
+}
+
+impl Window {
+    /// Construct a new Window operator.
+    ///
+    /// # Arguments
+    ///
+    /// * `src` - this operator's ancestor
+    /// * `function` - the window function to compute
+    /// * `over` - the column the window function takes as its argument, if any
+    /// * `partition_by` - the columns that this operator is keyed on
+    /// * `order` - the list of columns (and directions) to order each partition by
+    /// * `over_col_ty` - the type of the `over` column, if any
+    pub fn new(
+        src: NodeIndex,
+        function: WindowFunction,
+        over: Option<usize>,
+        partition_by: Vec<usize>,
+        order: Vec<(usize, OrderType)>,
+        over_col_ty: Option<&DfType>,
+    ) -> ReadySetResult<Self> {
+        invariant!(
+            over.is_some() || !function.requires_over_column(),
+            "{:?} requires a column to compute over",
+            function
+        );
+
+        let out_ty = match &function {
+            WindowFunction::RowNumber
+            | WindowFunction::Rank
+            | WindowFunction::DenseRank
+            | WindowFunction::Count => DfType::BigInt,
+            WindowFunction::Lag { .. } | WindowFunction::Lead { .. } => {
+                over_col_ty.cloned().unwrap_or_default()
+            }
+            // Like the SUM() aggregate, this returns a DOUBLE value for approximate-value
+            // arguments, and a DECIMAL value for everything else
+            WindowFunction::Sum => match over_col_ty {
+                Some(ty) if ty.is_any_float() => DfType::Double,
+                _ => DfType::DEFAULT_NUMERIC,
+            },
+        };
+
+        Ok(Window {
+            src: src.into(),
+            our_index: None,
+            function,
+            over,
+            partition_by,
+            order: order.clone().into(),
+            order_cols: order,
+            out_ty,
+        })
+    }
+
+    /// Returns the type of the column this operator appends to its input
+    pub fn output_col_type(&self) -> DfType {
+        self.out_ty.clone()
+    }
+
+    /// The value a running SUM starts from, so that its values have the type of our output column
+    fn sum_zero(&self) -> DfValue {
+        match self.out_ty {
+            DfType::Double => DfValue::Double(0.0),
+            _ => DfValue::Numeric(Default::default()),
+        }
+    }
+
+    /// Project the columns we are partitioning by out of the given record
+    fn project_partition<'rec, R>(&self, rec: &'rec R) -> ReadySetResult<Vec<&'rec DfValue>>
+    where
+        R: Indices<'static, usize, Output = DfValue> + ?Sized,
+    {
+        rec.indices(self.partition_by.clone())
+            .map_err(|_| ReadySetError::InvalidRecordLength)
+    }
+
+    /// Compare two rows by the window's ordering, breaking ties by comparing the whole row so that
+    /// the order of rows within a partition (and hence eg their row numbers) is deterministic.
+    fn cmp_rows(&self, a: &[DfValue], b: &[DfValue]) -> Ordering {
+        self.order.cmp(a, b).then_with(|| a.cmp(b))
+    }
+
+    fn over_value<'a>(&self, row: &'a [DfValue]) -> ReadySetResult<&'a DfValue> {
+        let over = self
+            .over
+            .ok_or_else(|| internal_err!("{:?} requires an over column", self.function))?;
+        row.get(over).ok_or(ReadySetError::InvalidRecordLength)
+    }
+
+    /// Returns true if the two rows are peers, ie equal according to the window's ordering
+    fn is_peer(&self, a: &[DfValue], b: &[DfValue]) -> bool {
+        self.order.cmp(a, b) == Ordering::Equal
+    }
+
+    /// Compute the value of the window function for each of the rows of a single partition from
+    /// `start` onwards. `rows` must make up the entire contents of the partition sorted with
+    /// [`Self::cmp_rows`], and `start` must be the first row of a run of peers if the window
+    /// function depends on peers.
+    ///
+    /// `prev` is the value of the window function for the row before `start`, if any, which
+    /// running functions such as [`WindowFunction::Sum`] continue from.
+    fn compute(
+        &self,
+        rows: &[Vec<DfValue>],
+        start: usize,
+        prev: Option<&DfValue>,
+    ) -> ReadySetResult<Vec<DfValue>> {
+        // Index of the first row in each run of peers (rows which are equal according to the
+        // window's ordering) along with the index one past the last row in that run, from `start`
+        // onwards
+        let peer_groups = {
+            let mut groups = Vec::new();
+            let mut group_start = start;
+            for i in start + 1..=rows.len() {
+                if i == rows.len() || !self.is_peer(&rows[group_start], &rows[i]) {
+                    groups.push((group_start, i));
+                    group_start = i;
+                }
+            }
+            groups
+        };
+
+        let mut values = Vec::with_capacity(rows.len() - start);
+        match &self.function {
+            WindowFunction::RowNumber => {
+                values.extend((start + 1..=rows.len()).map(|n| DfValue::from(n as i64)));
+            }
+            WindowFunction::Rank => {
+                for &(group_start, end) in &peer_groups {
+                    values
+                        .extend((group_start..end).map(|_| DfValue::from(group_start as i64 + 1)));
+                }
+            }
+            WindowFunction::DenseRank => {
+                let mut rank = prev.cloned().unwrap_or_else(|| DfValue::from(0i64));
+                for &(group_start, end) in &peer_groups {
+                    rank = (&rank + &DfValue::from(1i64))?;
+                    values.extend((group_start..end).map(|_| rank.clone()));
+                }
+            }
+            WindowFunction::Lag { offset, default } => {
+                for i in start..rows.len() {
+                    values.push(match i.checked_sub(*offset) {
+                        Some(j) => self.over_value(&rows[j])?.clone(),
+                        None => default.clone(),
+                    });
+                }
+            }
+            WindowFunction::Lead { offset, default } => {
+                for i in start..rows.len() {
+                    values.push(match rows.get(i + offset) {
+                        Some(row) => self.over_value(row)?.clone(),
+                        None => default.clone(),
+                    });
+                }
+            }
+            WindowFunction::Sum | WindowFunction::Count => {
+                // Without an ordering, the window frame for every row is the whole partition - so
+                // treat all the rows as peers
+                let peer_groups = if self.order_cols.is_empty() {
+                    vec![(start, rows.len())]
+                } else {
+                    peer_groups
+                };
+
+                let mut acc = match (&self.function, prev) {
+                    (_, Some(prev)) => prev.clone(),
+                    (WindowFunction::Sum, None) => DfValue::None,
+                    (_, None) => DfValue::from(0i64),
+                };
+                for (group_start, end) in peer_groups {
+                    for row in &rows[group_start..end] {
+                        match (&self.function, self.over) {
+                            (WindowFunction::Count, None) => acc = (&acc + &DfValue::from(1i64))?,
+                            (WindowFunction::Count, Some(_)) => {
+                                if !self.over_value(row)?.is_none() {
+                                    acc = (&acc + &DfValue::from(1i64))?
+                                }
+                            }
+                            _ => {
+                                let value = self.over_value(row)?;
+                                if !value.is_none() {
+                                    // The sum of no non-null values is NULL, but otherwise it
+                                    // starts from a zero of our output type
+                                    acc = if acc.is_none() {
+                                        (&self.sum_zero() + value)?
+                                    } else {
+                                        (&acc + value)?
+                                    };
+                                }
+                            }
+                        }
+                    }
+                    values.extend((group_start..end).map(|_| acc.clone()));
+                }
+            }
+        }
+
+        Ok(values)
+    }
+
+    /// Returns the position of the first row in a partition whose window value might differ
+    /// between `original` (our output rows for the partition) and `current` (the new input rows
+    /// for the partition), both sorted with [`Self::cmp_rows`], given that those are the same rows
+    /// up to `changed`.
+    fn recompute_from(
+        &self,
+        original: &[Vec<DfValue>],
+        current: &[Vec<DfValue>],
+        changed: usize,
+    ) -> usize {
+        match &self.function {
+            WindowFunction::RowNumber | WindowFunction::Lag { .. } => changed,
+            // The rows up to `offset` before the change lead into it
+            WindowFunction::Lead { offset, .. } => changed.saturating_sub(*offset),
+            // Without an ordering, every row's frame is the whole partition
+            WindowFunction::Sum | WindowFunction::Count if self.order_cols.is_empty() => 0,
+            // Otherwise, the values of any rows which were or are now peers of the changed row
+            // might have changed as well
+            WindowFunction::Rank
+            | WindowFunction::DenseRank
+            | WindowFunction::Sum
+            | WindowFunction::Count => {
+                let mut start = changed;
+                while start > 0
+                    && [original.get(changed), current.get(changed)]
+                        .into_iter()
+                        .flatten()
+                        .any(|row| self.is_peer(&current[start - 1], row))
+                {
+                    start -= 1;
+                }
+                start
+            }
+        }
+    }
+
+    /// Called inside of on_input after all the records for a partition have been applied to
+    /// `current`, to recompute the window function for the partition and emit the difference
+    /// between `original` (the contents of our state for the partition before processing) and the
+    /// new output rows into `out`.
+    fn post_partition(
+        &self,
+        out: &mut Vec<Record>,
+        mut original: Vec<Vec<DfValue>>,
+        mut current: Vec<Vec<DfValue>>,
+    ) -> ReadySetResult<()> {
+        original.sort_by(|a, b| self.cmp_rows(a, b));
+        current.sort_by(|a, b| self.cmp_rows(a, b));
+
+        // The rows before the first position where the input rows differ are the same in both,
+        // and (depending on the window function) so are their window values - so only the rows
+        // from there onwards need to be recomputed
+        let changed = original
+            .iter()
+            .zip(&current)
+            .take_while(|(orig, cur)| orig[..orig.len() - 1] == cur[..])
+            .count();
+        if changed == original.len() && changed == current.len() {
+            return Ok(());
+        }
+        let start = self.recompute_from(&original, &current, changed);
+        let prev = start
+            .checked_sub(1)
+            .map(|i| original[i].last().ok_or(ReadySetError::InvalidRecordLength))
+            .transpose()?;
+        let values = self.compute(&current, start, prev)?;
+
+        // Multiset difference between the original rows and the new ones, so we only emit rows
+        // whose window value actually changed
+        let mut diff: HashMap<Vec<DfValue>, isize> = HashMap::new();
+        for row in original.drain(start..) {
+            *diff.entry(row).or_default() -= 1;
+        }
+        for (mut row, value) in current.drain(start..).zip(values) {
+            row.push(value);
+            *diff.entry(row).or_default() += 1;
+        }
+
+        let (positives, negatives): (Vec<_>, Vec<_>) = diff
+            .into_iter()
+            .filter(|(_, n)| *n != 0)
+            .partition(|(_, n)| *n > 0);
+        for (row, n) in negatives {
+            out.extend((0..-n).map(|_| Record::Negative(row.clone())));
+        }
+        for (row, n) in positives {
+            out.extend((0..n).map(|_| Record::Positive(row.clone())));
+        }
+
+        Ok(())
+    }
+}
+
+impl Ingredient for Window {
+    fn take(&mut self) -> NodeOperator {
+        self.clone().into()
+    }
+
+    fn ancestors(&self) -> Vec<NodeIndex> {
+        vec![self.src.as_global()]
+    }
+
+    impl_replace_sibling!(src);
+
+    fn on_commit(&mut self, us: NodeIndex, remap: &HashMap<NodeIndex, IndexPair>) {
+        // who's our parent really?
+        self.src.remap(remap);
+
+        // who are we?
+        self.our_index = Some(remap[&us]);
+    }
+
+    fn on_input(
+        &mut self,
+        from: LocalNodeIndex,
+        rs: Records,
+        replay: &ReplayContext,
+        _nodes: &DomainNodes,
+        state: &StateMap,
+    ) -> ReadySetResult<ProcessingResult> {
+        debug_assert_eq!(from, *self.src);
+
+        if rs.is_empty() {
+            return Ok(ProcessingResult {
+                results: rs,
+                ..Default::default()
+            });
+        }
+
+        // Sort the batch by partition, so we only have to look up and recompute each partition
+        // once
+        let mut rs: Vec<_> = rs.into();
+        rs.sort_by(|a: &Record, b: &Record| {
+            self.project_partition(&***a)
+                .unwrap_or_default()
+                .cmp(&self.project_partition(&***b).unwrap_or_default())
+        });
+
+        let us = self
+            .our_index
+            .ok_or_else(|| internal_err!("on_input called on an uncommitted window operator"))?;
+        let db = state.get(*us).ok_or_else(|| {
+            internal_err!("window operators must have their own state materialized")
+        })?;
+
+        let mut out = Vec::new();
+        let mut misses = Vec::new();
+        let mut lookups = Vec::new();
+
+        for (partition_key, partition_rs) in &rs.into_iter().group_by(|r| {
+            self.project_partition(r.rec())
+                .ok()
+                .map(|key| key.into_iter().cloned().collect::<Vec<_>>())
+        }) {
+            let partition_key = partition_key.ok_or(ReadySetError::InvalidRecordLength)?;
+
+            let original = match db.lookup(
+                &self.partition_by,
+                &PointKey::from(partition_key.iter().cloned()),
+            ) {
+                LookupResult::Some(rows) => {
+                    if replay.is_partial() {
+                        lookups.push(Lookup {
+                            on: *us,
+                            cols: self.partition_by.clone(),
+                            key: partition_key
+                                .clone()
+                                .try_into()
+                                .map_err(|_| internal_err!("Empty partition"))?,
+                        });
+                    }
+                    rows.into_iter().map(|r| r.into_owned()).collect::<Vec<_>>()
+                }
+                LookupResult::Missing => {
+                    misses.extend(partition_rs.map(|r| {
+                        Miss::builder()
+                            .on(*us)
+                            .lookup_idx(self.partition_by.clone())
+                            .lookup_key(self.partition_by.clone())
+                            .replay(replay)
+                            .record(r.into_row())
+                            .build()
+                    }));
+                    continue;
+                }
+            };
+
+            // Strip our own output column off of the rows in our state to get back to the input
+            // rows for the partition
+            let mut current = original
+                .iter()
+                .map(|row| row[..row.len() - 1].to_vec())
+                .collect::<Vec<_>>();
+            for r in partition_rs {
+                match r {
+                    Record::Positive(r) => current.push(r),
+                    Record::Negative(r) => {
+                        if let Some(pos) = current.iter().position(|row| *row == r) {
+                            current.swap_remove(pos);
+                        }
+                    }
+                }
+            }
+
+            self.post_partition(&mut out, original, current)?;
+        }
+
+        Ok(ProcessingResult {
+            results: out.into(),
+            lookups,
+            misses,
+        })
+    }
+
+    fn suggest_indexes(&self, this: NodeIndex) -> HashMap<NodeIndex, LookupIndex> {
+        hashmap! {
+            this => LookupIndex::Strict(internal::Index::hash_map(self.partition_by.clone())),
+        }
+    }
+
+    fn column_source(&self, cols: &[usize]) -> ColumnSource {
+        // We emit all of our parent's columns, followed by the window function's value - but only
+        // the partition columns can be replayed from our parent, since replaying on any other
+        // column would give us just part of a partition
+        if cols.iter().all(|c| self.partition_by.contains(c)) {
+            ColumnSource::exact_copy(self.src.as_global(), cols.try_into().unwrap())
+        } else {
+            ColumnSource::RequiresFullReplay(vec1![self.src.as_global()])
+        }
+    }
+
+    fn description(&self, detailed: bool) -> String {
+        if !detailed {
+            return format!("{} OVER", self.function);
+        }
+
+        format!(
+            "{}({}) OVER γ[{}] o[{}]",
+            self.function,
+            self.over.iter().join(", "),
+            self.partition_by.iter().join(", "),
+            self.order
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use super::*;
+    use crate::ops;
+
+    fn setup(function: WindowFunction, over: Option<usize>) -> ops::test::MockGraph {
+        let mut g = ops::test::MockGraph::new();
+        let s = g.add_base("source", &["id", "partition", "score"]);
+
+        g.set_op(
+            "window",
+            &["id", "partition", "score", "value"],
+            Window::new(
+                s.as_global(),
+                function,
+                over,
+                vec![1],
+                vec![(2, OrderType::OrderDescending)],
+                Some(&DfType::BigInt),
+            )
+            .unwrap(),
+            true,
+        );
+        g
+    }
+
+    fn row(id: i64, partition: &str, score: i64) -> Vec<DfValue> {
+        vec![id.into(), partition.into(), score.into()]
+    }
+
+    fn output(id: i64, partition: &str, score: i64, value: DfValue) -> Vec<DfValue> {
+        vec![id.into(), partition.into(), score.into(), value]
+    }
+
+    #[test]
+    fn row_number_forwards() {
+        let mut g = setup(WindowFunction::RowNumber, None);
+
+        let rs = g.narrow_one_row(row(1, "a", 10), true);
+        assert_eq!(rs, vec![output(1, "a", 10, 1.into())].into());
+
+        // A row lower in the ordering only gets its own row number
+        let rs = g.narrow_one_row(row(2, "a", 5), true);
+        assert_eq!(rs, vec![output(2, "a", 5, 2.into())].into());
+
+        // A row at the top of the ordering renumbers the rest of the partition
+        let mut rs: Vec<_> = g.narrow_one_row(row(3, "a", 20), true).into();
+        rs.sort();
+        let mut expected: Vec<Record> = vec![
+            (output(1, "a", 10, 1.into()), false).into(),
+            (output(2, "a", 5, 2.into()), false).into(),
+            (output(3, "a", 20, 1.into()), true).into(),
+            (output(1, "a", 10, 2.into()), true).into(),
+            (output(2, "a", 5, 3.into()), true).into(),
+        ];
+        expected.sort();
+        assert_eq!(rs, expected);
+
+        // Other partitions are independent
+        let rs = g.narrow_one_row(row(4, "b", 1), true);
+        assert_eq!(rs, vec![output(4, "b", 1, 1.into())].into());
+
+        // Removing a row renumbers the rows after it
+        let mut rs: Vec<_> = g.narrow_one_row((row(1, "a", 10), false), true).into();
+        rs.sort();
+        let mut expected: Vec<Record> = vec![
+            (output(1, "a", 10, 2.into()), false).into(),
+            (output(2, "a", 5, 3.into()), false).into(),
+            (output(2, "a", 5, 2.into()), true).into(),
+        ];
+        expected.sort();
+        assert_eq!(rs, expected);
+    }
+
+    #[test]
+    fn rank_and_dense_rank() {
+        for (function, ranks) in [
+            (WindowFunction::Rank, [1, 1, 3]),
+            (WindowFunction::DenseRank, [1, 1, 2]),
+        ] {
+            let mut g = setup(function, None);
+            let mut rs: Vec<_> = g
+                .narrow_one(vec![row(1, "a", 10), row(2, "a", 10), row(3, "a", 5)], true)
+                .into();
+            rs.sort();
+            let mut expected: Vec<Record> = vec![
+                output(1, "a", 10, ranks[0].into()).into(),
+                output(2, "a", 10, ranks[1].into()).into(),
+                output(3, "a", 5, ranks[2].into()).into(),
+            ];
+            expected.sort();
+            assert_eq!(rs, expected);
+        }
+    }
+
+    #[test]
+    fn lag_and_lead() {
+        let mut g = setup(
+            WindowFunction::Lag {
+                offset: 1,
+                default: DfValue::None,
+            },
+            Some(2),
+        );
+        let mut rs: Vec<_> = g
+            .narrow_one(
+                vec![row(1, "a", 30), row(2, "a", 20), row(3, "a", 10)],
+                true,
+            )
+            .into();
+        rs.sort();
+        let mut expected: Vec<Record> = vec![
+            output(1, "a", 30, DfValue::None).into(),
+            output(2, "a", 20, 30.into()).into(),
+            output(3, "a", 10, 20.into()).into(),
+        ];
+        expected.sort();
+        assert_eq!(rs, expected);
+
+        let mut g = setup(
+            WindowFunction::Lead {
+                offset: 2,
+                default: 0.into(),
+            },
+            Some(2),
+        );
+        let mut rs: Vec<_> = g
+            .narrow_one(
+                vec![row(1, "a", 30), row(2, "a", 20), row(3, "a", 10)],
+                true,
+            )
+            .into();
+        rs.sort();
+        let mut expected: Vec<Record> = vec![
+            output(1, "a", 30, 10.into()).into(),
+            output(2, "a", 20, 0.into()).into(),
+            output(3, "a", 10, 0.into()).into(),
+        ];
+        expected.sort();
+        assert_eq!(rs, expected);
+    }
+
+    #[test]
+    fn running_count_includes_peers() {
+        let mut g = setup(WindowFunction::Count, None);
+        let mut rs: Vec<_> = g
+            .narrow_one(
+                vec![row(1, "a", 30), row(2, "a", 20), row(3, "a", 20)],
+                true,
+            )
+            .into();
+        rs.sort();
+        let mut expected: Vec<Record> = vec![
+            output(1, "a", 30, 1.into()).into(),
+            output(2, "a", 20, 3.into()).into(),
+            output(3, "a", 20, 3.into()).into(),
+        ];
+        expected.sort();
+        assert_eq!(rs, expected);
+    }
+
+    #[test]
+    fn running_sum() {
+        let numeric = |n: i64| DfValue::Numeric(Arc::new(n.into()));
+        let mut g = setup(WindowFunction::Sum, Some(2));
+        g.narrow_one(vec![row(1, "a", 30), row(2, "a", 20)], true);
+
+        let mut rs: Vec<_> = g.narrow_one_row(row(3, "a", 10), true).into();
+        rs.sort();
+        assert_eq!(rs, vec![output(3, "a", 10, numeric(60)).into()]);
+
+        let mut rs: Vec<_> = g.narrow_one_row((row(1, "a", 30), false), true).into();
+        rs.sort();
+        let mut expected: Vec<Record> = vec![
+            (output(1, "a", 30, numeric(30)), false).into(),
+            (output(2, "a", 20, numeric(50)), false).into(),
+            (output(3, "a", 10, numeric(60)), false).into(),
+            (output(2, "a", 20, numeric(20)), true).into(),
+            (output(3, "a", 10, numeric(30)), true).into(),
+        ];
+        expected.sort();
+        assert_eq!(rs, expected);
+
+        // A new peer of an existing row changes the sum for that row too
+        let mut rs: Vec<_> = g.narrow_one_row(row(4, "a", 20), true).into();
+        rs.sort();
+        let mut expected: Vec<Record> = vec![
+            (output(2, "a", 20, numeric(20)), false).into(),
+            (output(3, "a", 10, numeric(30)), false).into(),
+            (output(2, "a", 20, numeric(40)), true).into(),
+            (output(4, "a", 20, numeric(40)), true).into(),
+            (output(3, "a", 10, numeric(50)), true).into(),
+        ];
+        expected.sort();
+        assert_eq!(rs, expected);
+    }
+
+    #[test]
+    fn dense_rank_continues_from_unchanged_rows() {
+        let mut g = setup(WindowFunction::DenseRank, None);
+        g.narrow_one(
+            vec![row(1, "a", 30), row(2, "a", 30), row(3, "a", 10)],
+            true,
+        );
+
+        // Only the rows from the new row's position onwards are recomputed
+        let mut rs: Vec<_> = g.narrow_one_row(row(4, "a", 20), true).into();
+        rs.sort();
+        let mut expected: Vec<Record> = vec![
+            (output(3, "a", 10, 2.into()), false).into(),
+            (output(4, "a", 20, 2.into()), true).into(),
+            (output(3, "a", 10, 3.into()), true).into(),
+        ];
+        expected.sort();
+        assert_eq!(rs, expected);
+    }
+
+    #[test]
+    fn it_suggests_indices() {
+        let g = setup(WindowFunction::RowNumber, None);
+        let me = 1.into();
+        let idx = g.node().suggest_indexes(me);
+        assert_eq!(idx.len(), 1);
+        assert_eq!(
+            idx[&me],
+            LookupIndex::Strict(internal::Index::hash_map(vec![1]))
+        );
+    }
+
+    #[test]
+    fn it_resolves() {
+        let g = setup(WindowFunction::RowNumber, None);
+        assert_eq!(
+            g.node().resolve(1),
+            Some(vec![(g.narrow_base_id().as_global(), 1)])
+        );
+        // Only the partition columns can be traced back to the parent
+        assert_eq!(g.node().resolve(0), None);
+        assert_eq!(g.node().resolve(3), None);
+    }
+}
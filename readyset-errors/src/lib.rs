@@ -278,6 +278,25 @@ pub enum ReadySetError {
     #[error("the queries lookup key is not found at the reader")]
     ReaderMissingKey,
 
+    /// A lookup into a query with a parametrized `LIMIT` asked for more rows (counting the
+    /// `OFFSET`) than are kept for each key in the cache.
+    #[error("LIMIT and OFFSET of {rows} rows exceed the {max} rows cached per key")]
+    LimitExceedsCachedRows {
+        /// The `LIMIT` plus the `OFFSET` of the lookup
+        rows: usize,
+        /// The number of rows kept for each key
+        max: usize,
+    },
+
+    /// A subscription to a view missed some of the changes made to the view, either because it
+    /// fell too far behind or because the subscribed keys were evicted.
+    #[error("subscription to view missed some changes; the view must be read again")]
+    SubscriptionLagged,
+
+    /// The read was canceled at the request of the client before it completed.
+    #[error("canceling statement due to user request")]
+    QueryCanceled,
+
     /// A prepared statement is missing.
     #[error("Prepared statement with ID {statement_id} not found")]
     PreparedStatementMissing {
@@ -652,6 +671,18 @@ impl ReadySetError {
         self.any_cause(|e| e.is_unsupported())
     }
 
+    /// Returns the message of the [`Unsupported`] error that either *is* or *caused* this error, if
+    /// there is one
+    pub fn unsupported_cause(&self) -> Option<&str> {
+        match self {
+            Self::Unsupported(message) => Some(message),
+            _ => self
+                .source()
+                .and_then(|e| e.downcast_ref::<Box<ReadySetError>>())
+                .and_then(|e| e.unsupported_cause()),
+        }
+    }
+
     /// Returns `true` if self is ['ViewNotFound'].
     pub fn is_view_not_found(&self) -> bool {
         matches!(self, Self::ViewNotFound(..))
@@ -1084,5 +1115,10 @@ mod test {
             }),
         };
         assert!(err.caused_by_unsupported());
+        assert_eq!(err.unsupported_cause(), Some("Test"));
+        assert_eq!(
+            ReadySetError::ViewNotFound("v".to_owned()).unsupported_cause(),
+            None
+        );
     }
 }
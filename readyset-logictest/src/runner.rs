@@ -573,12 +573,17 @@ impl TestScript {
                 .unwrap(),
                 DatabaseType::PostgreSQL => {
                     psql_srv::run_backend(
-                        readyset_psql::Backend(make_backend!(
-                            PostgreSqlUpstream,
-                            PostgreSqlQueryHandler,
-                            Dialect::PostgreSQL,
-                        )),
+                        readyset_psql::Backend::new(
+                            make_backend!(
+                                PostgreSqlUpstream,
+                                PostgreSqlQueryHandler,
+                                Dialect::PostgreSQL,
+                            ),
+                            Default::default(),
+                        ),
                         s,
+                        None,
+                        false,
                     )
                     .await
                 }
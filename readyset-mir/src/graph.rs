@@ -158,6 +158,28 @@ impl MirGraph {
                 );
                 columns
             }
+            MirNodeInner::Window {
+                over,
+                partition_by,
+                order_by,
+                output_column,
+                ..
+            } => {
+                // Windows need all the columns they pass through, along with the columns they
+                // partition and order by and the "over" column - but not their own output column
+                let mut columns = self.columns(node);
+                columns.retain(|c| c != output_column);
+                for c in partition_by
+                    .iter()
+                    .chain(order_by.iter().map(|(c, _)| c))
+                    .chain(over)
+                {
+                    if !columns.contains(c) {
+                        columns.push(c.clone());
+                    }
+                }
+                columns
+            }
             MirNodeInner::Filter { conditions } => {
                 let mut columns = self.columns(node);
                 for c in conditions.referred_columns() {
@@ -213,6 +235,7 @@ impl MirGraph {
                 .collect(),
             MirNodeInner::Join { project, .. }
             | MirNodeInner::LeftJoin { project, .. }
+            | MirNodeInner::FullJoin { project, .. }
             | MirNodeInner::DependentJoin { project, .. } => project.clone(),
             MirNodeInner::JoinAggregates => {
                 let cols = self
@@ -252,6 +275,10 @@ impl MirGraph {
                 .into_iter()
                 .chain(iter::once(MirColumn::named(&*PAGE_NUMBER_COL)))
                 .collect(),
+            MirNodeInner::Window { output_column, .. } => parent_columns()
+                .into_iter()
+                .chain(iter::once(output_column.clone()))
+                .collect(),
             MirNodeInner::Distinct { group_by } => group_by
                 .iter()
                 .cloned()
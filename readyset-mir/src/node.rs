@@ -119,6 +119,7 @@ mod tests {
         use dataflow::ops::grouped::aggregate::Aggregation;
         use dataflow::ops::grouped::extremum::Extremum;
         use dataflow::ops::union::DuplicateMode;
+        use dataflow::ops::window::WindowFunction;
         use nom_sql::{BinaryOperator, ColumnSpecification, Expr, OrderType, SqlType};
         use readyset::ViewPlaceholder;
 
@@ -380,6 +381,24 @@ mod tests {
             )
         }
 
+        #[test]
+        fn window() {
+            has_columns_single_parent(
+                MirNodeInner::Window {
+                    function: WindowFunction::RowNumber,
+                    over: None,
+                    partition_by: vec![Column::new(Some("base"), "b")],
+                    order_by: vec![(Column::new(Some("base"), "a"), OrderType::OrderAscending)],
+                    output_column: Column::named("row_number"),
+                },
+                vec![
+                    Column::new(Some("base"), "a"),
+                    Column::new(Some("base"), "b"),
+                    Column::named("row_number"),
+                ],
+            )
+        }
+
         #[test]
         fn join_aggregates() {
             let mut graph = MirGraph::new();
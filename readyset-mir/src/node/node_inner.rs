@@ -4,6 +4,7 @@ use common::{DfValue, IndexType};
 use dataflow::ops::grouped::aggregate::Aggregation;
 use dataflow::ops::grouped::extremum::Extremum;
 use dataflow::ops::union;
+use dataflow::ops::window::WindowFunction;
 use dataflow::PostLookupAggregates;
 use itertools::Itertools;
 use nom_sql::{ColumnSpecification, Expr, OrderType, Relation, SqlIdentifier};
@@ -118,6 +119,20 @@ pub enum MirNodeInner {
         /// Columns (from both parents) to project in the output.
         project: Vec<Column>,
     },
+    /// Node which computes a *full outer* join on its two parents, emitting rows from either
+    /// parent with no match in the other padded with nulls
+    ///
+    /// Converted to [`Join`] with [`JoinType::Full`] when lowering to dataflow.
+    ///
+    /// [`Join`]: dataflow::ops::join::Join
+    /// [`JoinType::Full`]: dataflow::ops::join::JoinType::Full
+    FullJoin {
+        /// Columns to use as the join keys. Each tuple corresponds to a column in the left parent
+        /// and column in the right parent.
+        on: Vec<(Column, Column)>,
+        /// Columns (from both parents) to project in the output.
+        project: Vec<Column>,
+    },
     /// Join where nodes in the right-hand side depend on columns in the left-hand side
     /// (referencing tables in `dependent_tables`). These are created during compilation for
     /// correlated subqueries, and must be removed entirely by rewrite passes before lowering
@@ -205,6 +220,25 @@ pub enum MirNodeInner {
         /// LIMIT clause
         limit: usize,
     },
+    /// Node which computes a window function over the rows of each partition of its input, and
+    /// emits all of its input columns followed by the value of the window function for each row.
+    ///
+    /// Converted to [`Window`] when lowering to dataflow.
+    ///
+    /// [`Window`]: dataflow::ops::window::Window
+    Window {
+        /// Which window function we are computing
+        function: WindowFunction,
+        /// Column to compute the window function over, if the function takes one
+        over: Option<Column>,
+        /// Set of columns that partition the input rows
+        partition_by: Vec<Column>,
+        /// Set of columns used for ordering the rows within each partition
+        order_by: Vec<(Column, OrderType)>,
+        /// The column name to use for the result of the window function, which will always be the
+        /// last column
+        output_column: Column,
+    },
     /// Node which emits only distinct rows per some group.
     ///
     /// Converted to [`Aggregator`] with [`Aggregation::Count`] when lowering to dataflow.
@@ -279,6 +313,7 @@ impl MirNodeInner {
             }
             MirNodeInner::Join { project, .. }
             | MirNodeInner::LeftJoin { project, .. }
+            | MirNodeInner::FullJoin { project, .. }
             | MirNodeInner::DependentJoin { project, .. } => {
                 if !project.contains(&c) {
                     project.push(c);
@@ -333,6 +368,13 @@ impl MirNodeInner {
                     Aggregation::GroupConcat { separator: ref s } => {
                         format!("||([{}], \"{}\")", on.name.as_str(), s.as_str())
                     }
+                    Aggregation::Min => format!("min({})", on.name.as_str()),
+                    Aggregation::Max => format!("max({})", on.name.as_str()),
+                    Aggregation::CountDistinct => {
+                        format!("|DISTINCT {}|", on.name.as_str())
+                    }
+                    Aggregation::SumDistinct => format!("𝛴(DISTINCT {})", on.name.as_str()),
+                    Aggregation::AvgDistinct => format!("AVG(DISTINCT {})", on.name.as_str()),
                 };
                 let group_cols = group_by
                     .iter()
@@ -431,6 +473,26 @@ impl MirNodeInner {
                     jc
                 )
             }
+            MirNodeInner::FullJoin {
+                ref on,
+                ref project,
+                ..
+            } => {
+                let jc = on
+                    .iter()
+                    .map(|(l, r)| format!("{}:{}", l.name, r.name))
+                    .collect::<Vec<_>>()
+                    .join(", ");
+                format!(
+                    "⟗ [{} on {}]",
+                    project
+                        .iter()
+                        .map(|c| c.name.as_str())
+                        .collect::<Vec<_>>()
+                        .join(", "),
+                    jc
+                )
+            }
             MirNodeInner::DependentJoin {
                 ref on,
                 ref project,
@@ -495,6 +557,19 @@ impl MirNodeInner {
             } => {
                 format!("TopK [k: {}, {:?}]", limit, order)
             }
+            MirNodeInner::Window {
+                ref function,
+                ref over,
+                ref partition_by,
+                ref order_by,
+                ..
+            } => format!(
+                "Window [{}({}) OVER γ[{}] {:?}]",
+                function,
+                over.iter().map(|c| c.name.as_str()).join(", "),
+                partition_by.iter().map(|c| c.name.as_str()).join(", "),
+                order_by
+            ),
             MirNodeInner::Union {
                 ref emit,
                 ref duplicate_mode,
@@ -155,6 +155,11 @@ impl GraphViz for MirNodeInner {
                     AggregationKind::GroupConcat { separator: s } => {
                         format!("||({}, \"{}\")", on, s)
                     }
+                    AggregationKind::Min => format!("min({})", on),
+                    AggregationKind::Max => format!("max({})", on),
+                    AggregationKind::CountDistinct => format!("\\|DISTINCT {}\\|", on),
+                    AggregationKind::SumDistinct => format!("𝛴(DISTINCT {})", on),
+                    AggregationKind::AvgDistinct => format!("AVG(DISTINCT {})", on),
                 };
                 let group_cols = group_by.iter().join(", ");
                 write!(f, "{} | γ: {}", op_string, group_cols)
@@ -262,6 +267,10 @@ impl GraphViz for MirNodeInner {
                 let jc = on.iter().map(|(l, r)| format!("{}:{}", l, r)).join(", ");
                 write!(f, "⋉  | on: {}", jc)
             }
+            MirNodeInner::FullJoin { ref on, .. } => {
+                let jc = on.iter().map(|(l, r)| format!("{}:{}", l, r)).join(", ");
+                write!(f, "⟗  | on: {}", jc)
+            }
             MirNodeInner::DependentJoin { ref on, .. } => {
                 write!(
                     f,
@@ -332,6 +341,26 @@ impl GraphViz for MirNodeInner {
                     .unwrap_or_else(|| "".into());
                 write!(f, "TopK [k: {}; {}]", limit, order)
             }
+            MirNodeInner::Window {
+                ref function,
+                ref over,
+                ref partition_by,
+                ref order_by,
+                ..
+            } => {
+                let order = order_by
+                    .iter()
+                    .map(|(c, o)| format!("{}: {}", c.name.as_str(), o))
+                    .join(", ");
+                write!(
+                    f,
+                    "Window | {}({}) | γ: {} | {}",
+                    function,
+                    over.iter().map(|c| c.name.as_str()).join(", "),
+                    partition_by.iter().join(", "),
+                    order
+                )
+            }
             MirNodeInner::Union {
                 ref emit,
                 ref duplicate_mode,
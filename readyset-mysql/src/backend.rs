@@ -10,9 +10,10 @@ use launchpad::redacted::Sensitive;
 use mysql_async::consts::StatusFlags;
 use mysql_common::bigdecimal03::ToPrimitive;
 use mysql_srv::{
-    CachedSchema, Column, ColumnFlags, ColumnType, InitWriter, MsqlSrvError, MySqlShim,
-    QueryResultWriter, RowWriter, StatementMetaWriter,
+    CachedSchema, Column, ColumnFlags, ColumnType, CursorRows, InitWriter, ListFieldsWriter,
+    MsqlSrvError, MySqlShim, QueryResultWriter, RowWriter, StatementMetaWriter,
 };
+use readyset::results::ResultIterator;
 use readyset_adapter::backend::noria_connector::MetaVariable;
 use readyset_adapter::backend::{
     noria_connector, QueryResult, SinglePrepareResult, UpstreamPrepare,
@@ -126,6 +127,49 @@ async fn write_column<W: AsyncWrite + Unpin>(
     Ok(written?)
 }
 
+/// The results of a prepared statement executed against ReadySet with a read-only cursor, which
+/// are only encoded as the client fetches them.
+struct CursorResults {
+    rows: ResultIterator,
+    mysql_schema: Vec<Column>,
+    column_types: Vec<DfType>,
+    column_map: Vec<Option<usize>>,
+}
+
+#[async_trait]
+impl<W: AsyncWrite + Unpin + Send> CursorRows<W> for CursorResults {
+    async fn write_row(&mut self, rw: &mut RowWriter<'_, W>) -> io::Result<bool> {
+        let row = match self.rows.next() {
+            Some(row) => row,
+            None => return Ok(false),
+        };
+        for (c, ty, pos) in izip!(
+            self.mysql_schema.iter(),
+            self.column_types.iter(),
+            self.column_map.iter()
+        ) {
+            let res = match pos {
+                Some(coli) => write_column(rw, &row[*coli], c, ty).await,
+                None => Err(Error::from(internal_err!(
+                    "tried to emit column {:?} not in getter with schema {:?}",
+                    c.column,
+                    self.mysql_schema
+                ))),
+            };
+            if let Err(e) = res {
+                error!(err = %e, "encountered error while attempting to write column packet");
+                return Err(match e {
+                    Error::Io(e) => e,
+                    // Reported to the client, see `CursorRows::write_row`
+                    e => io::Error::new(io::ErrorKind::InvalidData, e.to_string()),
+                });
+            }
+        }
+        rw.end_row().await?;
+        Ok(true)
+    }
+}
+
 async fn write_query_results<W: AsyncWrite + Unpin>(
     r: Result<(u64, u64), Error>,
     results: QueryResultWriter<'_, W>,
@@ -620,6 +664,22 @@ where
                     }
                 };
 
+                if results.cursor_requested() {
+                    let cursor = CursorResults {
+                        rows,
+                        mysql_schema: mysql_schema.clone(),
+                        column_types: column_types.clone(),
+                        column_map: column_map.clone(),
+                    };
+                    return results
+                        .start_cursor(
+                            mysql_schema,
+                            Some(preencoded_schema.clone()),
+                            Box::new(cursor),
+                        )
+                        .await;
+                }
+
                 let mut rw = results
                     .start_with_cache(mysql_schema, preencoded_schema.clone())
                     .await?;
@@ -665,6 +725,45 @@ where
         }
     }
 
+    async fn on_list_fields(&mut self, table: &str, w: ListFieldsWriter<'_, W>) -> io::Result<()> {
+        let columns = match self.noria.table_columns(table).await {
+            Ok(columns) => columns,
+            Err(e) if e.caused_by_table_not_found() => {
+                // Tables which ReadySet doesn't replicate may still exist upstream
+                if let Some(upstream) = self.noria.upstream_mut() {
+                    return match upstream.table_columns(table).await {
+                        Ok(columns) => {
+                            w.reply(&columns.iter().map(Column::from).collect::<Vec<_>>())
+                                .await
+                        }
+                        Err(e) => w.error(e.error_kind(), e.to_string().as_bytes()).await,
+                    };
+                }
+                return w
+                    .error(
+                        mysql_srv::ErrorKind::ER_NO_SUCH_TABLE,
+                        format!("Table '{}' doesn't exist", table).as_bytes(),
+                    )
+                    .await;
+            }
+            Err(e) => {
+                let e = Error::from(e);
+                return w.error(e.error_kind(), e.to_string().as_bytes()).await;
+            }
+        };
+        match columns
+            .iter()
+            .map(convert_column)
+            .collect::<Result<Vec<_>, _>>()
+        {
+            Ok(columns) => w.reply(&columns).await,
+            Err(e) => {
+                let e = Error::from(e);
+                w.error(e.error_kind(), e.to_string().as_bytes()).await
+            }
+        }
+    }
+
     async fn on_close(&mut self, _: u32) {}
 
     async fn on_query(&mut self, query: &str, results: QueryResultWriter<'_, W>) -> io::Result<()> {
@@ -680,6 +779,10 @@ where
         self.does_require_authentication()
     }
 
+    fn require_tls(&self, username: &str) -> bool {
+        self.require_tls || self.tls_required_users.contains(username)
+    }
+
     fn version(&self) -> String {
         self.noria.version()
     }
@@ -8,12 +8,14 @@ mod schema;
 mod upstream;
 mod value;
 
+use std::sync::Arc;
+
 use async_trait::async_trait;
 pub use backend::Backend;
 pub use error::Error;
 use mysql_srv::MySqlIntermediary;
 pub use query_handler::MySqlQueryHandler;
-use readyset_client_adapter::ConnectionHandler;
+use readyset_client_adapter::{ConnectionHandler, TlsAcceptor};
 use tokio::net;
 use tracing::{error, instrument};
 pub use upstream::{MySqlUpstream, QueryResult};
@@ -31,8 +33,16 @@ impl ConnectionHandler for MySqlHandler {
         &mut self,
         stream: net::TcpStream,
         backend: readyset_adapter::Backend<MySqlUpstream, MySqlQueryHandler>,
+        tls_acceptor: Option<Arc<TlsAcceptor>>,
     ) {
-        if let Err(e) = MySqlIntermediary::run_on_tcp(Backend::new(backend), stream).await {
+        let backend = Backend::new(backend);
+        let res = match tls_acceptor {
+            Some(tls_acceptor) => {
+                MySqlIntermediary::run_on_tcp_with_tls(backend, stream, tls_acceptor).await
+            }
+            None => MySqlIntermediary::run_on_tcp(backend, stream).await,
+        };
+        if let Err(e) = res {
             error!(err = %e, "connection lost");
         }
     }
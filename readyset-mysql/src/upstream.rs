@@ -17,6 +17,7 @@ use mysql_async::{
 };
 use nom_sql::SqlIdentifier;
 use pin_project::pin_project;
+use readyset::replication::ReplicationOffset;
 use readyset::ColumnSchema;
 use readyset_adapter::fallback_cache::FallbackCache;
 #[cfg(feature = "fallback_cache")]
@@ -25,7 +26,7 @@ use readyset_adapter::upstream_database::{NoriaCompare, UpstreamDestination};
 use readyset_adapter::{UpstreamConfig, UpstreamDatabase, UpstreamPrepare};
 use readyset_client_metrics::QueryDestination;
 use readyset_data::DfValue;
-use readyset_errors::{internal_err, ReadySetError};
+use readyset_errors::{internal, internal_err, ReadySetError};
 use tracing::{error, info, info_span, Instrument};
 
 use crate::schema::{convert_column, is_subtype};
@@ -310,6 +311,21 @@ impl MySqlUpstream {
         let prepared_statements = HashMap::new();
         Ok((conn, prepared_statements, upstream_config))
     }
+
+    /// Returns the definitions of the columns of `table`, as the upstream database would send them
+    /// in response to `COM_FIELD_LIST`.
+    ///
+    /// mysql_async doesn't support `COM_FIELD_LIST`, so this prepares (without executing) a query
+    /// selecting all the columns of the table instead.
+    pub async fn table_columns(&mut self, table: &str) -> Result<Vec<Column>, Error> {
+        let statement = self
+            .conn
+            .prep(format!("SELECT * FROM `{}`", table.replace('`', "``")))
+            .await?;
+        let columns = statement.columns().to_vec();
+        self.conn.close(statement).await?;
+        Ok(columns)
+    }
 }
 
 #[async_trait]
@@ -561,6 +577,45 @@ impl UpstreamDatabase for MySqlUpstream {
         ))
     }
 
+    async fn replication_offset(&mut self) -> Result<Option<ReplicationOffset>, Error> {
+        // Identify the position the same way the replicator does: by the set of executed GTIDs if
+        // GTIDs are enabled, and by binlog file and position otherwise
+        let gtid_mode = match self
+            .conn
+            .query_first::<String, _>("SELECT @@GLOBAL.gtid_mode")
+            .await
+        {
+            Ok(mode) => mode.map_or(false, |mode| mode.eq_ignore_ascii_case("ON")),
+            // Servers without GTID support, such as MariaDB, don't have the variable at all
+            Err(mysql_async::Error::Server(_)) => false,
+            Err(error) => return Err(error.into()),
+        };
+
+        if gtid_mode {
+            let executed: String = self
+                .conn
+                .query_first("SELECT @@GLOBAL.gtid_executed")
+                .await?
+                .unwrap_or_default();
+            return Ok(Some(ReplicationOffset::from_gtid_set(&executed)?));
+        }
+
+        // Every transaction that has committed on this connection is before the end of the
+        // binlog, which is the position the replicator reports once it's applied them all
+        let status: Row = match self.conn.query_first("SHOW MASTER STATUS").await? {
+            Some(status) => status,
+            // Binary logging is disabled, so there's nothing to replicate from
+            None => return Ok(None),
+        };
+        let (file, position) = match (status.get::<String, _>(0), status.get::<u32, _>(1)) {
+            (Some(file), Some(position)) => (file, position),
+            _ => internal!("Unexpected response to SHOW MASTER STATUS"),
+        };
+        Ok(Some(ReplicationOffset::from_binlog_position(
+            &file, position,
+        )?))
+    }
+
     async fn start_tx<'a>(&'a mut self) -> Result<Self::QueryResult<'a>, Error> {
         self.conn.query_drop("START TRANSACTION").await?;
 
@@ -4,7 +4,7 @@ use readyset::query::QueryId;
 use readyset_adapter::backend::UnsupportedSetMode;
 use readyset_adapter::BackendBuilder;
 use readyset_client_metrics::QueryDestination;
-use readyset_client_test_helpers::mysql_helpers::{last_query_info, MySQLAdapter};
+use readyset_client_test_helpers::mysql_helpers::{last_query_info, MySQLAdapter, RawConnection};
 use readyset_client_test_helpers::{self, sleep, TestBuilder};
 use readyset_server::Handle;
 use serial_test::serial;
@@ -729,3 +729,36 @@ async fn switch_database_with_use() {
     conn.query_drop("SELECT b FROM t").await.unwrap();
     conn.query_drop("SELECT c FROM t2").await.unwrap();
 }
+
+#[tokio::test(flavor = "multi_thread")]
+#[serial]
+async fn list_fields_of_unreplicated_table() {
+    let (opts, _handle) = TestBuilder::new(BackendBuilder::new().require_authentication(false))
+        .fallback(true)
+        .replication_tables("noria.replicated".to_owned())
+        .build::<MySQLAdapter>()
+        .await;
+    let mut conn = mysql_async::Conn::new(opts.clone()).await.unwrap();
+    conn.query_drop("CREATE TABLE replicated (x int)")
+        .await
+        .unwrap();
+    conn.query_drop("CREATE TABLE unreplicated (y int, z text)")
+        .await
+        .unwrap();
+    sleep().await;
+
+    let mut raw = RawConnection::connect(&opts).await;
+    assert_eq!(
+        raw.list_fields("replicated").await.unwrap(),
+        vec!["x".to_owned()]
+    );
+    // COM_FIELD_LIST is proxied upstream for tables which ReadySet doesn't replicate
+    assert_eq!(
+        raw.list_fields("unreplicated").await.unwrap(),
+        vec!["y".to_owned(), "z".to_owned()]
+    );
+    assert_eq!(
+        raw.list_fields("nonexistent").await.unwrap_err(),
+        mysql_srv::ErrorKind::ER_NO_SUCH_TABLE as u16
+    );
+}
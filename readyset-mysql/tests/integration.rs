@@ -11,7 +11,7 @@ use readyset_adapter::proxied_queries_reporter::ProxiedQueriesReporter;
 use readyset_adapter::query_status_cache::{MigrationStyle, QueryStatusCache};
 use readyset_adapter::BackendBuilder;
 use readyset_client_metrics::QueryDestination;
-use readyset_client_test_helpers::mysql_helpers::{last_query_info, MySQLAdapter};
+use readyset_client_test_helpers::mysql_helpers::{last_query_info, MySQLAdapter, RawConnection};
 use readyset_client_test_helpers::{sleep, TestBuilder};
 use readyset_errors::ReadySetError;
 use readyset_server::Handle;
@@ -1814,3 +1814,52 @@ async fn test_proxied_queries_telemetry() {
     // with its initial value
     assert_eq!(telemetry.migration_status, Some("pending".to_string()));
 }
+
+#[tokio::test(flavor = "multi_thread")]
+async fn cursor_fetch_in_batches() {
+    let (opts, _handle) = setup().await;
+    let mut conn = mysql_async::Conn::new(opts.clone()).await.unwrap();
+    conn.query_drop("CREATE TABLE t (x int)").await.unwrap();
+    conn.query_drop("INSERT INTO t (x) VALUES (1), (2), (3)")
+        .await
+        .unwrap();
+    sleep().await;
+
+    let mut raw = RawConnection::connect(&opts).await;
+    let stmt = raw.prepare("SELECT x FROM t").await;
+    assert!(raw.execute_with_cursor(stmt).await);
+
+    let (rows, last_row_sent) = raw.fetch(stmt, 2).await.unwrap();
+    assert_eq!(rows.len(), 2);
+    assert!(!last_row_sent);
+
+    let (rows, last_row_sent) = raw.fetch(stmt, 2).await.unwrap();
+    assert_eq!(rows.len(), 1);
+    assert!(last_row_sent);
+
+    // The cursor is closed once all its rows have been fetched
+    assert_eq!(
+        raw.fetch(stmt, 2).await.unwrap_err(),
+        mysql_srv::ErrorKind::ER_STMT_HAS_NO_OPEN_CURSOR as u16
+    );
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn list_fields() {
+    let (opts, _handle) = setup().await;
+    let mut conn = mysql_async::Conn::new(opts.clone()).await.unwrap();
+    conn.query_drop("CREATE TABLE t (x int, y text)")
+        .await
+        .unwrap();
+    sleep().await;
+
+    let mut raw = RawConnection::connect(&opts).await;
+    assert_eq!(
+        raw.list_fields("t").await.unwrap(),
+        vec!["x".to_owned(), "y".to_owned()]
+    );
+    assert_eq!(
+        raw.list_fields("nonexistent").await.unwrap_err(),
+        mysql_srv::ErrorKind::ER_NO_SUCH_TABLE as u16
+    );
+}
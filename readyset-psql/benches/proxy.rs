@@ -157,7 +157,7 @@ where
                     .unwrap();
             tokio::spawn(conn);
             let backend = Backend { upstream: client };
-            psql_srv::run_backend(backend, sock).await
+            psql_srv::run_backend(backend, sock, None, false).await
         }
     }))
 }
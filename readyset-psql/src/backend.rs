@@ -1,14 +1,25 @@
+use std::collections::VecDeque;
 use std::convert::{TryFrom, TryInto};
 use std::ops::Deref;
 use std::sync::Arc;
 
 use async_trait::async_trait;
+use bytes::BytesMut;
 use eui48::MacAddressFormat;
+use futures::future;
+use postgres_types::{ToSql, Type};
 use psql_srv as ps;
+use readyset::ViewSubscription;
 use readyset_adapter::backend as cl;
-use readyset_data::DfValue;
+use readyset_data::{DfValue, PassThrough};
+use tracing::warn;
 
+use crate::cancel::{CancelHandle, CancelRegistry};
+use crate::copy::{parse_copy_to_stdout, CopyToStdout, UpstreamCopyData};
 use crate::error::Error;
+use crate::listen::{
+    delta_notifications, parse_listen, resync_notification, Channel, ListenStatement,
+};
 use crate::query_handler::PostgreSqlQueryHandler;
 use crate::response::{PrepareResponse, QueryResponse};
 use crate::resultset::Resultset;
@@ -20,27 +31,140 @@ use crate::PostgreSqlUpstream;
 /// requests provided to `psql_srv::Backend` trait function implementations are forwared to the
 /// wrapped `noria_client` `Backend`. All request parameters and response results are forwarded
 /// using type conversion.
-pub struct Backend(pub cl::Backend<PostgreSqlUpstream, PostgreSqlQueryHandler>);
+pub struct Backend {
+    inner: cl::Backend<PostgreSqlUpstream, PostgreSqlQueryHandler>,
+    /// Used to cancel queries running on other connections, when this connection is opened to
+    /// send a cancel request
+    cancel_registry: CancelRegistry,
+    /// This connection's registration in `cancel_registry`
+    cancel_handle: CancelHandle,
+    /// The channels this connection is listening on, with the subscriptions to changes to the
+    /// results of the cached queries they name
+    subscriptions: Vec<(String, ViewSubscription)>,
+    /// Notifications which have been received but not yet sent to the client
+    pending_notifications: VecDeque<ps::Notification>,
+}
 
 impl Deref for Backend {
     type Target = cl::Backend<PostgreSqlUpstream, PostgreSqlQueryHandler>;
 
     fn deref(&self) -> &Self::Target {
-        &self.0
+        &self.inner
     }
 }
 
 impl Backend {
+    /// Wrap `inner`, registering the connection in `cancel_registry` so that clients can cancel
+    /// queries running on it
+    pub fn new(
+        inner: cl::Backend<PostgreSqlUpstream, PostgreSqlQueryHandler>,
+        cancel_registry: CancelRegistry,
+    ) -> Self {
+        let cancel_handle = cancel_registry.register(inner.read_canceler());
+        Backend {
+            inner,
+            cancel_registry,
+            cancel_handle,
+            subscriptions: Vec::new(),
+            pending_notifications: VecDeque::new(),
+        }
+    }
+
+    /// Point cancel requests for this connection at the current upstream connection, which may
+    /// have been re-established since the last query
+    fn track_upstream(&self) {
+        self.cancel_handle
+            .set_upstream(self.inner.upstream().map(PostgreSqlUpstream::cancel_token));
+    }
+
     async fn query<'a>(&'a mut self, query: &'a str) -> Result<QueryResponse<'_>, Error> {
-        Ok(QueryResponse(self.0.query(query).await?))
+        self.track_upstream();
+        Ok(QueryResponse(self.inner.query(query).await?))
     }
 
     async fn prepare(&mut self, query: &str) -> Result<PrepareResponse<'_>, Error> {
-        Ok(PrepareResponse(self.0.prepare(query).await?))
+        self.track_upstream();
+        Ok(PrepareResponse(self.inner.prepare(query).await?))
     }
 
     async fn execute(&mut self, id: u32, params: &[DfValue]) -> Result<QueryResponse<'_>, Error> {
-        Ok(QueryResponse(self.0.execute(id, params).await?))
+        self.track_upstream();
+        Ok(QueryResponse(self.inner.execute(id, params).await?))
+    }
+
+    /// Execute a `LISTEN` or `UNLISTEN` statement, starting or ending subscriptions to changes to
+    /// the results of cached queries.
+    ///
+    /// Returns `false` if the statement should instead be proxied to the upstream database, which
+    /// is the case for channels that don't name a cached query when we have fallback enabled.
+    async fn listen(&mut self, statement: ListenStatement) -> Result<bool, ps::Error> {
+        let has_fallback = self.inner.has_fallback();
+        match statement {
+            ListenStatement::Listen(channel) => {
+                // As in PostgreSQL, listening on a channel more than once has no effect
+                if self.subscriptions.iter().any(|(c, _)| *c == channel) {
+                    return Ok(true);
+                }
+                let Channel { cache, params } = match Channel::parse(&channel) {
+                    Ok(parsed) => parsed,
+                    Err(_) if has_fallback => return Ok(false),
+                    Err(e) => return Err(e),
+                };
+                let subscription = match self.inner.subscribe(&cache, &params).await {
+                    Ok(subscription) => subscription,
+                    Err(e) if has_fallback && e.caused_by_view_not_found() => return Ok(false),
+                    Err(e) => return Err(Error::from(e).into()),
+                };
+                self.subscriptions.push((channel, subscription));
+                Ok(true)
+            }
+            ListenStatement::Unlisten(channel) => {
+                let num_subscriptions = self.subscriptions.len();
+                self.subscriptions.retain(|(c, _)| *c != channel);
+                Ok(self.subscriptions.len() < num_subscriptions || !has_fallback)
+            }
+            ListenStatement::UnlistenAll => {
+                // The client may also be listening on channels in the upstream database
+                self.subscriptions.clear();
+                Ok(!has_fallback)
+            }
+        }
+    }
+
+    /// Execute `copy`, parsed from the `COPY ... TO STDOUT` statement `query`. If the query being
+    /// copied is cached its results are read from ReadySet, otherwise the whole statement is
+    /// proxied to the upstream database.
+    async fn copy_out(
+        &mut self,
+        query: &str,
+        copy: CopyToStdout<'_>,
+    ) -> Result<ps::QueryResponse<Resultset>, ps::Error> {
+        if let Some(format) = copy.cacheable_format() {
+            // Queries which would be proxied aren't executed here, so that they only run upstream
+            // once, as part of the proxied `COPY` statement
+            if let Some(result) = self.inner.query_cached_select(copy.query).await? {
+                if let ps::QueryResponse::Select { schema, resultset } =
+                    QueryResponse(result).try_into()?
+                {
+                    return Ok(ps::QueryResponse::CopyOut {
+                        format,
+                        schema,
+                        resultset,
+                    });
+                }
+            }
+        }
+
+        self.track_upstream();
+        let upstream = self.inner.upstream().ok_or_else(|| {
+            ps::Error::Unsupported("COPY of a query which is not cached".to_string())
+        })?;
+        let (num_columns, data) = upstream.copy_out(copy.query, query).await?;
+        Ok(ps::QueryResponse::PassThroughCopyOut {
+            format: copy.transfer_format(),
+            num_columns,
+            data: Box::new(UpstreamCopyData::new(data, copy.row_counter())),
+        })
     }
 }
 
@@ -51,17 +175,25 @@ impl ps::Backend for Backend {
     type Resultset = Resultset;
 
     fn version(&self) -> String {
-        self.0.version()
+        self.inner.version()
     }
 
     async fn on_init(&mut self, _database: &str) -> Result<ps::CredentialsNeeded, ps::Error> {
         match self.does_require_authentication() {
-            true => Ok(ps::CredentialsNeeded::Cleartext),
+            true => Ok(ps::CredentialsNeeded::ScramSha256),
             false => Ok(ps::CredentialsNeeded::None),
         }
     }
 
     async fn on_query(&mut self, query: &str) -> Result<ps::QueryResponse<Resultset>, ps::Error> {
+        if let Some(statement) = parse_listen(query) {
+            if self.listen(statement).await? {
+                return Ok(ps::QueryResponse::Command);
+            }
+        }
+        if let Some(copy) = parse_copy_to_stdout(query) {
+            return self.copy_out(query, copy).await;
+        }
         self.query(query).await?.try_into()
     }
 
@@ -86,13 +218,75 @@ impl ps::Backend for Backend {
         Ok(())
     }
 
-    async fn on_auth(&mut self, credentials: ps::Credentials) -> Result<(), ps::Error> {
-        match credentials {
-            ps::Credentials::Cleartext { user, password } => {
-                if self.users.get(&user) == Some(&password) {
-                    return Ok(());
+    fn password_for_user(&self, user: &str) -> Option<String> {
+        self.users.get(user).cloned()
+    }
+
+    fn backend_key_data(&self) -> Option<ps::BackendKeyData> {
+        Some(self.cancel_handle.key())
+    }
+
+    async fn on_cancel(&mut self, key: ps::BackendKeyData) {
+        self.cancel_registry.cancel(key).await
+    }
+
+    async fn next_notification(&mut self) -> ps::Notification {
+        loop {
+            if let Some(notification) = self.pending_notifications.pop_front() {
+                return notification;
+            }
+
+            let Backend {
+                inner,
+                subscriptions,
+                pending_notifications,
+                ..
+            } = self;
+
+            // Polling a subscription or the upstream database is cancel-safe, and we only update
+            // any state once we have a batch of changes or a notification, so this is too
+            let next = {
+                let upstream = async {
+                    match inner.upstream_mut() {
+                        Some(upstream) => upstream.next_notification().await,
+                        None => future::pending().await,
+                    }
+                };
+                let subscription = async {
+                    if subscriptions.is_empty() {
+                        return future::pending().await;
+                    }
+                    let (res, idx, _) = future::select_all(
+                        subscriptions
+                            .iter_mut()
+                            .map(|(_, subscription)| Box::pin(subscription.next())),
+                    )
+                    .await;
+                    (res, idx)
+                };
+                futures::pin_mut!(upstream, subscription);
+                match future::select(upstream, subscription).await {
+                    future::Either::Left((notification, _)) => future::Either::Left(notification),
+                    future::Either::Right((next, _)) => future::Either::Right(next),
+                }
+            };
+
+            match next {
+                future::Either::Left(notification) => {
+                    pending_notifications.push_back(ps::Notification {
+                        channel: notification.channel().to_owned(),
+                        payload: notification.payload().to_owned(),
+                    });
+                }
+                future::Either::Right((Ok(deltas), idx)) => {
+                    let channel = &subscriptions[idx].0;
+                    pending_notifications.extend(delta_notifications(channel, deltas));
+                }
+                future::Either::Right((Err(error), idx)) => {
+                    let (channel, _) = subscriptions.remove(idx);
+                    warn!(%error, %channel, "Subscription to cached query ended");
+                    pending_notifications.push_back(resync_notification(channel));
                 }
-                return Err(ps::Error::AuthenticationFailure(user));
             }
         }
     }
@@ -102,6 +296,19 @@ impl ps::Backend for Backend {
 /// conversion to `DfValue`.
 struct ParamRef<'a>(&'a ps::Value);
 
+/// Converts a value of a type that `DfValue` has no representation for into a
+/// `DfValue::PassThrough`, so that it can still be passed on to the upstream database
+fn pass_through(ty: Type, value: &ps::Value) -> Result<DfValue, ps::Error> {
+    let mut data = BytesMut::new();
+    value
+        .to_sql(&ty, &mut data)
+        .map_err(|e| ps::Error::InternalError(e.to_string()))?;
+    Ok(DfValue::PassThrough(Arc::new(PassThrough {
+        ty,
+        data: data.to_vec().into_boxed_slice(),
+    })))
+}
+
 impl TryFrom<ParamRef<'_>> for DfValue {
     type Error = ps::Error;
 
@@ -126,13 +333,17 @@ impl TryFrom<ParamRef<'_>> for DfValue {
             ps::Value::TimestampTz(v) => Ok(DfValue::from(*v)),
             ps::Value::Date(v) => Ok((*v).into()),
             ps::Value::Time(v) => Ok((*v).into()),
+            ps::Value::Interval(_) => pass_through(Type::INTERVAL, v.0),
             ps::Value::ByteArray(b) => Ok(DfValue::ByteArray(Arc::new(b.clone()))),
             ps::Value::MacAddress(m) => Ok(DfValue::from(m.to_string(MacAddressFormat::HexString))),
             ps::Value::Inet(ip) => Ok(DfValue::from(ip.to_string())),
+            ps::Value::Cidr(_) => pass_through(Type::CIDR, v.0),
             ps::Value::Uuid(uuid) => Ok(DfValue::from(uuid.to_string())),
             ps::Value::Json(v) | ps::Value::Jsonb(v) => Ok(DfValue::from(v.to_string())),
             ps::Value::Bit(bits) | ps::Value::VarBit(bits) => Ok(DfValue::from(bits.clone())),
+            ps::Value::Money(_) => pass_through(Type::MONEY, v.0),
             ps::Value::Array(arr, _) => Ok(DfValue::from(arr.clone())),
+            ps::Value::Range(_, ty) => pass_through(ty.clone(), v.0),
             ps::Value::PassThrough(p) => Ok(DfValue::PassThrough(Arc::new(p.clone()))),
         }
     }
@@ -0,0 +1,178 @@
+//! Support for PostgreSQL [cancel requests][0], which clients send on a new connection to cancel
+//! the query running on another one.
+//!
+//! Every connection is registered in a [`CancelRegistry`] shared by all connections to the
+//! adapter, under the process ID and secret key sent to the client in `BackendKeyData` on startup.
+//! Canceling a connection aborts the read it's executing against ReadySet, if any, and forwards the
+//! cancel request to its upstream connection, if any, so that queries proxied there are canceled
+//! too.
+//!
+//! [0]: https://www.postgresql.org/docs/current/protocol-flow.html#PROTOCOL-FLOW-CANCELING-REQUESTS
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+use psql_srv::BackendKeyData;
+use readyset_adapter::backend::ReadCanceler;
+use tracing::{debug, warn};
+
+use crate::upstream::UpstreamCancelToken;
+
+/// The state of a single registered connection needed to cancel the query running on it
+struct Connection {
+    secret_key: i32,
+    read_canceler: ReadCanceler,
+    /// Updated before each query, since the upstream connection may be re-established
+    upstream: Mutex<Option<UpstreamCancelToken>>,
+}
+
+#[derive(Default)]
+struct Connections {
+    by_process_id: HashMap<i32, Arc<Connection>>,
+    next_process_id: i32,
+}
+
+/// The set of connections to the adapter whose queries can be canceled
+#[derive(Clone, Default)]
+pub struct CancelRegistry {
+    connections: Arc<Mutex<Connections>>,
+}
+
+impl CancelRegistry {
+    /// Register a new connection, which will remain registered until the returned handle is
+    /// dropped.
+    pub fn register(&self, read_canceler: ReadCanceler) -> CancelHandle {
+        let connection = Arc::new(Connection {
+            secret_key: rand::random(),
+            read_canceler,
+            upstream: Mutex::new(None),
+        });
+
+        let mut connections = self.connections.lock().unwrap();
+        let process_id = loop {
+            // Process IDs are positive in PostgreSQL, and some clients assume as much
+            let process_id = connections.next_process_id.max(1);
+            connections.next_process_id = process_id.wrapping_add(1);
+            if !connections.by_process_id.contains_key(&process_id) {
+                break process_id;
+            }
+        };
+        connections
+            .by_process_id
+            .insert(process_id, Arc::clone(&connection));
+
+        CancelHandle {
+            key: BackendKeyData {
+                process_id,
+                secret_key: connection.secret_key,
+            },
+            connection,
+            registry: self.clone(),
+        }
+    }
+
+    /// Cancel the query running on the connection identified by `key`, if any. Requests with a key
+    /// that doesn't match any connection are ignored.
+    pub async fn cancel(&self, key: BackendKeyData) {
+        let connection = match self
+            .connections
+            .lock()
+            .unwrap()
+            .by_process_id
+            .get(&key.process_id)
+        {
+            Some(connection) if connection.secret_key == key.secret_key => Arc::clone(connection),
+            _ => {
+                debug!(
+                    process_id = key.process_id,
+                    "Ignoring cancel request with unknown key"
+                );
+                return;
+            }
+        };
+
+        debug!(process_id = key.process_id, "Canceling query");
+        connection.read_canceler.cancel();
+        let upstream = connection.upstream.lock().unwrap().clone();
+        if let Some(upstream) = upstream {
+            if let Err(error) = upstream.cancel().await {
+                warn!(%error, "Could not forward cancel request to upstream database");
+            }
+        }
+    }
+}
+
+/// A connection's registration in a [`CancelRegistry`], which is removed when this is dropped
+pub struct CancelHandle {
+    key: BackendKeyData,
+    connection: Arc<Connection>,
+    registry: CancelRegistry,
+}
+
+impl CancelHandle {
+    /// The key identifying the connection, to be sent to the client
+    pub fn key(&self) -> BackendKeyData {
+        self.key
+    }
+
+    /// Set the connection to the upstream database to forward cancel requests to
+    pub fn set_upstream(&self, upstream: Option<UpstreamCancelToken>) {
+        *self.connection.upstream.lock().unwrap() = upstream;
+    }
+}
+
+impl Drop for CancelHandle {
+    fn drop(&mut self) {
+        self.registry
+            .connections
+            .lock()
+            .unwrap()
+            .by_process_id
+            .remove(&self.key.process_id);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn register_assigns_unique_keys() {
+        let registry = CancelRegistry::default();
+        let first = registry.register(ReadCanceler::default());
+        let second = registry.register(ReadCanceler::default());
+        assert_ne!(first.key().process_id, second.key().process_id);
+        assert!(first.key().process_id > 0);
+
+        let process_id = first.key().process_id;
+        drop(first);
+        assert!(!registry
+            .connections
+            .lock()
+            .unwrap()
+            .by_process_id
+            .contains_key(&process_id));
+    }
+
+    #[tokio::test]
+    async fn cancel_requires_secret_key() {
+        let registry = CancelRegistry::default();
+        let read_canceler = ReadCanceler::default();
+        let handle = registry.register(read_canceler.clone());
+        let key = handle.key();
+
+        let canceled = read_canceler.canceled();
+        tokio::pin!(canceled);
+
+        registry
+            .cancel(BackendKeyData {
+                process_id: key.process_id,
+                secret_key: key.secret_key.wrapping_add(1),
+            })
+            .await;
+        assert!(futures::poll!(canceled.as_mut()).is_pending());
+
+        registry.cancel(key).await;
+        assert!(futures::poll!(canceled.as_mut()).is_ready());
+    }
+}
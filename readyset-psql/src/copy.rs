@@ -0,0 +1,518 @@
+//! Support for `COPY (<query>) TO STDOUT` statements.
+//!
+//! These are used by clients (such as `psql`'s `\copy`) to bulk-export the results of a query. If
+//! the query is cached, the rows are read from ReadySet and encoded in the requested `COPY` format
+//! by `psql_srv`. Otherwise, the statement is proxied to the upstream database as-is, and its
+//! output forwarded to the client as it arrives.
+//!
+//! We only recognize the subset of the `COPY` grammar needed to copy a query's results to the
+//! client; anything else (such as `COPY` to or from a table or file) is handled like any other
+//! query.
+
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+use bytes::Bytes;
+use futures::{ready, Stream};
+use psql_srv::{PassThroughCopyData, TransferFormat};
+use tokio_postgres::CopyOutStream;
+
+/// The format of the data in a `COPY` statement
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum CopyFormat {
+    Text,
+    Csv,
+    Binary,
+}
+
+/// A parsed `COPY (<query>) TO STDOUT` statement
+#[derive(Debug, PartialEq, Eq)]
+pub(crate) struct CopyToStdout<'a> {
+    /// The query whose results are being copied
+    pub(crate) query: &'a str,
+    /// The format to copy the results in
+    pub(crate) format: CopyFormat,
+    /// Whether a header line with the names of the columns should be included, for CSV
+    pub(crate) header: bool,
+    /// Whether any other options (such as `DELIMITER` or `NULL`) were given, which we don't know
+    /// how to apply to rows read from ReadySet
+    pub(crate) other_options: bool,
+}
+
+impl<'a> CopyToStdout<'a> {
+    /// Returns the format to encode rows read from ReadySet in, or [`None`] if the statement
+    /// uses options which mean it has to be proxied to the upstream database instead
+    pub(crate) fn cacheable_format(&self) -> Option<TransferFormat> {
+        if self.header || self.other_options {
+            return None;
+        }
+        match self.format {
+            CopyFormat::Text => Some(TransferFormat::Text),
+            CopyFormat::Binary => Some(TransferFormat::Binary),
+            CopyFormat::Csv => None,
+        }
+    }
+
+    /// The overall format of the data sent to the client, as reported in `CopyOutResponse`
+    pub(crate) fn transfer_format(&self) -> TransferFormat {
+        match self.format {
+            CopyFormat::Text | CopyFormat::Csv => TransferFormat::Text,
+            CopyFormat::Binary => TransferFormat::Binary,
+        }
+    }
+
+    /// Returns a [`RowCounter`] for the output of this statement
+    pub(crate) fn row_counter(&self) -> RowCounter {
+        RowCounter {
+            format: self.format,
+            header: self.header,
+            rows: 0,
+            quoted: false,
+            binary: BinaryRows::default(),
+        }
+    }
+}
+
+/// Counts the rows in the output of a `COPY ... TO STDOUT` statement as sent by the upstream
+/// database, which doesn't otherwise tell us how many rows were copied, a chunk at a time as it's
+/// streamed to the client
+#[derive(Debug)]
+pub(crate) struct RowCounter {
+    format: CopyFormat,
+    header: bool,
+    rows: u64,
+    /// Whether we're within a quoted value, for CSV
+    quoted: bool,
+    binary: BinaryRows,
+}
+
+impl RowCounter {
+    /// Count the rows in the next chunk of output
+    pub(crate) fn update(&mut self, chunk: &[u8]) {
+        match self.format {
+            // Newlines within values are escaped in the text format, so every newline ends a row
+            CopyFormat::Text => self.rows += chunk.iter().filter(|b| **b == b'\n').count() as u64,
+            CopyFormat::Csv => {
+                for b in chunk {
+                    match b {
+                        b'"' => self.quoted = !self.quoted,
+                        b'\n' if !self.quoted => self.rows += 1,
+                        _ => {}
+                    }
+                }
+            }
+            CopyFormat::Binary => self.rows += self.binary.update(chunk),
+        }
+    }
+
+    /// Returns the number of rows in the output counted so far
+    pub(crate) fn num_rows(&self) -> u64 {
+        if self.format == CopyFormat::Csv && self.header {
+            self.rows.saturating_sub(1)
+        } else {
+            self.rows
+        }
+    }
+}
+
+/// The part of the output of a `COPY` in binary format that [`BinaryRows`] expects next
+#[derive(Debug, Default, Clone, Copy)]
+enum BinaryField {
+    /// The signature and flags field
+    #[default]
+    Header,
+    /// The length of the header extension area
+    ExtensionLength,
+    /// The number of fields in the next tuple, or -1 for the trailer
+    FieldCount,
+    /// The length of the next field of a tuple, followed by `fields_left - 1` more fields
+    FieldLength { fields_left: i16 },
+    /// Nothing, since we've reached the trailer (or the output is malformed)
+    Done,
+}
+
+/// Incrementally parses the tuples out of the output of a `COPY` in binary format
+#[derive(Debug, Default)]
+struct BinaryRows {
+    next: BinaryField,
+    /// The bytes of the next field received so far
+    partial: Vec<u8>,
+    /// The number of bytes of field data (or of the header extension) left to skip
+    skip: usize,
+}
+
+impl BinaryRows {
+    /// Parse the next chunk of output, returning the number of tuples started within it
+    fn update(&mut self, mut chunk: &[u8]) -> u64 {
+        const SIGNATURE_LEN: usize = 11;
+
+        let mut rows = 0;
+        loop {
+            let skipped = self.skip.min(chunk.len());
+            self.skip -= skipped;
+            chunk = &chunk[skipped..];
+
+            let len = match self.next {
+                BinaryField::Header => SIGNATURE_LEN + 4,
+                BinaryField::ExtensionLength | BinaryField::FieldLength { .. } => 4,
+                BinaryField::FieldCount => 2,
+                BinaryField::Done => return rows,
+            };
+            let needed = (len - self.partial.len()).min(chunk.len());
+            self.partial.extend_from_slice(&chunk[..needed]);
+            chunk = &chunk[needed..];
+            if self.partial.len() < len {
+                return rows;
+            }
+
+            let field = std::mem::take(&mut self.partial);
+            let read_i32 = || i32::from_be_bytes([field[0], field[1], field[2], field[3]]);
+            self.next = match self.next {
+                BinaryField::Header => BinaryField::ExtensionLength,
+                BinaryField::ExtensionLength => match usize::try_from(read_i32()) {
+                    Ok(extension_len) => {
+                        self.skip = extension_len;
+                        BinaryField::FieldCount
+                    }
+                    Err(_) => BinaryField::Done,
+                },
+                BinaryField::FieldCount => match i16::from_be_bytes([field[0], field[1]]) {
+                    fields_left if fields_left < 0 => BinaryField::Done,
+                    0 => {
+                        rows += 1;
+                        BinaryField::FieldCount
+                    }
+                    fields_left => {
+                        rows += 1;
+                        BinaryField::FieldLength { fields_left }
+                    }
+                },
+                BinaryField::FieldLength { fields_left } => {
+                    // NULLs are represented by a length of -1, with no data following
+                    self.skip = usize::try_from(read_i32()).unwrap_or(0);
+                    if fields_left > 1 {
+                        BinaryField::FieldLength {
+                            fields_left: fields_left - 1,
+                        }
+                    } else {
+                        BinaryField::FieldCount
+                    }
+                }
+                BinaryField::Done => BinaryField::Done,
+            };
+        }
+    }
+}
+
+/// The output of a `COPY ... TO STDOUT` statement proxied to the upstream database, which counts
+/// the rows in it as it's streamed to the client
+pub(crate) struct UpstreamCopyData {
+    data: Pin<Box<CopyOutStream>>,
+    rows: RowCounter,
+}
+
+impl UpstreamCopyData {
+    pub(crate) fn new(data: CopyOutStream, rows: RowCounter) -> Self {
+        Self {
+            data: Box::pin(data),
+            rows,
+        }
+    }
+}
+
+impl Stream for UpstreamCopyData {
+    type Item = Result<Bytes, psql_srv::Error>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let chunk = ready!(self.data.as_mut().poll_next(cx));
+        if let Some(Ok(chunk)) = &chunk {
+            self.rows.update(chunk);
+        }
+        Poll::Ready(chunk.map(|chunk| chunk.map_err(psql_srv::Error::from)))
+    }
+}
+
+impl PassThroughCopyData for UpstreamCopyData {
+    fn num_rows(&self) -> u64 {
+        self.rows.num_rows()
+    }
+}
+
+/// A token in the part of a `COPY` statement following the query
+#[derive(Debug, PartialEq, Eq)]
+enum Token<'a> {
+    Word(&'a str),
+    Quoted(&'a str),
+    Punct(char),
+}
+
+/// Split `s` into words, single-quoted strings, and punctuation, or return [`None`] if it contains
+/// an unterminated string
+fn tokenize(s: &str) -> Option<Vec<Token<'_>>> {
+    let mut tokens = vec![];
+    let mut chars = s.char_indices().peekable();
+    while let Some((i, c)) = chars.next() {
+        if c.is_whitespace() {
+            continue;
+        }
+        if c == '\'' {
+            let end = loop {
+                match chars.next() {
+                    // Quotes within strings are escaped by doubling them
+                    Some((_, '\'')) if matches!(chars.peek(), Some((_, '\''))) => {
+                        chars.next();
+                    }
+                    Some((j, '\'')) => break j,
+                    Some(_) => {}
+                    None => return None,
+                }
+            };
+            tokens.push(Token::Quoted(&s[i + 1..end]));
+        } else if c.is_alphanumeric() || c == '_' {
+            let mut end = s.len();
+            while let Some((j, c)) = chars.peek() {
+                if !(c.is_alphanumeric() || *c == '_') {
+                    end = *j;
+                    break;
+                }
+                chars.next();
+            }
+            tokens.push(Token::Word(&s[i..end]));
+        } else {
+            tokens.push(Token::Punct(c));
+        }
+    }
+    Some(tokens)
+}
+
+/// Returns the index in `s` of the parenthesis closing the one `s` starts with, skipping over
+/// any quoted strings and identifiers
+fn matching_paren(s: &str) -> Option<usize> {
+    let mut depth = 0;
+    let mut quote = None;
+    for (i, c) in s.char_indices() {
+        match (quote, c) {
+            (Some(q), c) if c == q => quote = None,
+            (Some(_), _) => {}
+            (None, '\'' | '"') => quote = Some(c),
+            (None, '(') => depth += 1,
+            (None, ')') => {
+                depth -= 1;
+                if depth == 0 {
+                    return Some(i);
+                }
+            }
+            (None, _) => {}
+        }
+    }
+    None
+}
+
+/// Returns whether the option value `token` turns a boolean option on
+fn is_true(token: Option<&Token>) -> bool {
+    match token {
+        None => true,
+        Some(Token::Word(w) | Token::Quoted(w)) => ["true", "on", "1"]
+            .iter()
+            .any(|t| w.eq_ignore_ascii_case(t)),
+        Some(Token::Punct(_)) => false,
+    }
+}
+
+/// Parse the options of a `COPY` statement in the parenthesized form, eg `(FORMAT csv, HEADER)`
+fn parse_options(tokens: &[Token], copy: &mut CopyToStdout) -> Option<()> {
+    for option in tokens.split(|t| *t == Token::Punct(',')) {
+        match option {
+            [Token::Word(name), value @ ..] if name.eq_ignore_ascii_case("format") => {
+                copy.format = match value {
+                    [Token::Word(f) | Token::Quoted(f)] if f.eq_ignore_ascii_case("text") => {
+                        CopyFormat::Text
+                    }
+                    [Token::Word(f) | Token::Quoted(f)] if f.eq_ignore_ascii_case("csv") => {
+                        CopyFormat::Csv
+                    }
+                    [Token::Word(f) | Token::Quoted(f)] if f.eq_ignore_ascii_case("binary") => {
+                        CopyFormat::Binary
+                    }
+                    _ => return None,
+                }
+            }
+            [Token::Word(name), value @ ..]
+                if name.eq_ignore_ascii_case("header") && value.len() <= 1 =>
+            {
+                copy.header = is_true(value.first());
+            }
+            [] => return None,
+            _ => copy.other_options = true,
+        }
+    }
+    Some(())
+}
+
+/// Parse the options of a `COPY` statement in the legacy unparenthesized form, eg `CSV HEADER`
+fn parse_legacy_options(tokens: &[Token], copy: &mut CopyToStdout) {
+    for token in tokens {
+        match token {
+            Token::Word(w) if w.eq_ignore_ascii_case("binary") => copy.format = CopyFormat::Binary,
+            Token::Word(w) if w.eq_ignore_ascii_case("csv") => copy.format = CopyFormat::Csv,
+            Token::Word(w) if w.eq_ignore_ascii_case("header") => copy.header = true,
+            _ => copy.other_options = true,
+        }
+    }
+}
+
+/// Parse `query` as a `COPY (<query>) TO STDOUT` statement, returning [`None`] if it's not one
+pub(crate) fn parse_copy_to_stdout(query: &str) -> Option<CopyToStdout<'_>> {
+    let query = query.trim_start();
+    let keyword = query.get(..4)?;
+    if !keyword.eq_ignore_ascii_case("copy") {
+        return None;
+    }
+    let rest = query[4..].trim_start();
+    if !rest.starts_with('(') {
+        return None;
+    }
+    let close = matching_paren(rest)?;
+    let inner = rest[1..close].trim();
+
+    let tail = rest[close + 1..].trim_end().trim_end_matches(';');
+    let tokens = tokenize(tail)?;
+    let options = match tokens.as_slice() {
+        [Token::Word(to), Token::Word(stdout), options @ ..]
+            if to.eq_ignore_ascii_case("to") && stdout.eq_ignore_ascii_case("stdout") =>
+        {
+            options
+        }
+        _ => return None,
+    };
+    let options = match options {
+        [Token::Word(with), options @ ..] if with.eq_ignore_ascii_case("with") => options,
+        options => options,
+    };
+
+    let mut copy = CopyToStdout {
+        query: inner,
+        format: CopyFormat::Text,
+        header: false,
+        other_options: false,
+    };
+    match options {
+        [Token::Punct('('), options @ .., Token::Punct(')')] => parse_options(options, &mut copy)?,
+        options => parse_legacy_options(options, &mut copy),
+    }
+    Some(copy)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_simple() {
+        assert_eq!(
+            parse_copy_to_stdout("COPY (SELECT * FROM t WHERE x = ')') TO STDOUT;"),
+            Some(CopyToStdout {
+                query: "SELECT * FROM t WHERE x = ')'",
+                format: CopyFormat::Text,
+                header: false,
+                other_options: false,
+            })
+        );
+        assert_eq!(
+            parse_copy_to_stdout("copy (select (1)) to stdout").map(|c| c.query),
+            Some("select (1)")
+        );
+    }
+
+    #[test]
+    fn parse_parenthesized_options() {
+        let copy = parse_copy_to_stdout("COPY (SELECT 1) TO STDOUT WITH (FORMAT binary)").unwrap();
+        assert_eq!(copy.format, CopyFormat::Binary);
+        assert_eq!(copy.cacheable_format(), Some(TransferFormat::Binary));
+
+        let copy =
+            parse_copy_to_stdout("COPY (SELECT 1) TO STDOUT (FORMAT 'csv', HEADER)").unwrap();
+        assert_eq!(copy.format, CopyFormat::Csv);
+        assert!(copy.header);
+        assert_eq!(copy.cacheable_format(), None);
+
+        let copy = parse_copy_to_stdout("COPY (SELECT 1) TO STDOUT (DELIMITER ',')").unwrap();
+        assert!(copy.other_options);
+        assert_eq!(copy.cacheable_format(), None);
+
+        assert_eq!(
+            parse_copy_to_stdout("COPY (SELECT 1) TO STDOUT (FORMAT xml)"),
+            None
+        );
+    }
+
+    #[test]
+    fn parse_unparenthesized_options() {
+        let copy = parse_copy_to_stdout("COPY (SELECT 1) TO STDOUT BINARY").unwrap();
+        assert_eq!(copy.format, CopyFormat::Binary);
+
+        let copy = parse_copy_to_stdout("COPY (SELECT 1) TO STDOUT WITH CSV HEADER").unwrap();
+        assert_eq!(copy.format, CopyFormat::Csv);
+        assert!(copy.header);
+    }
+
+    #[test]
+    fn parse_not_copy_to_stdout() {
+        assert_eq!(parse_copy_to_stdout("SELECT 1"), None);
+        assert_eq!(parse_copy_to_stdout("COPY t TO STDOUT"), None);
+        assert_eq!(parse_copy_to_stdout("COPY (SELECT 1) TO '/tmp/f'"), None);
+        assert_eq!(parse_copy_to_stdout("COPY (SELECT 1 TO STDOUT"), None);
+    }
+
+    #[test]
+    fn count_rows() {
+        fn count<B: AsRef<[u8]>>(copy: &str, chunks: &[B]) -> u64 {
+            let mut counter = parse_copy_to_stdout(copy).unwrap().row_counter();
+            for chunk in chunks {
+                counter.update(chunk.as_ref());
+            }
+            counter.num_rows()
+        }
+
+        assert_eq!(
+            count(
+                "COPY (SELECT 1) TO STDOUT",
+                &[&b"1\ta\\nb\n"[..], &b"2\tc\n"[..]]
+            ),
+            2
+        );
+
+        assert_eq!(
+            count(
+                "COPY (SELECT 1) TO STDOUT CSV HEADER",
+                &[&b"x,y\n1,\"a\nb\"\n2,c\n"[..]]
+            ),
+            2
+        );
+        // Quoted values can span chunks
+        assert_eq!(
+            count(
+                "COPY (SELECT 1) TO STDOUT CSV",
+                &[&b"1,\"a\n"[..], &b"b\"\n"[..]]
+            ),
+            1
+        );
+
+        let mut data = b"PGCOPY\n\xff\r\n\0".to_vec();
+        data.extend_from_slice(&[0; 8]);
+        // A row with an int4 and a NULL
+        data.extend_from_slice(&[0, 2, 0, 0, 0, 4, 0, 0, 0, 1, 0xff, 0xff, 0xff, 0xff]);
+        // A row with no fields
+        data.extend_from_slice(&[0, 0]);
+        data.extend_from_slice(&[0xff, 0xff]);
+        assert_eq!(count("COPY (SELECT 1) TO STDOUT BINARY", &[&data]), 2);
+        // Fields can be split across chunks at any point
+        assert_eq!(
+            count(
+                "COPY (SELECT 1) TO STDOUT BINARY",
+                &data.chunks(3).collect::<Vec<_>>()
+            ),
+            2
+        );
+    }
+}
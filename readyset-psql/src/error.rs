@@ -27,6 +27,7 @@ impl From<Error> for ps::Error {
                 ps::Error::MissingPreparedStatement(statement_id.to_string())
             }
             ReadySet(ReadySetError::Unsupported(s)) => ps::Error::Unsupported(s),
+            ReadySet(ReadySetError::QueryCanceled) => ps::Error::QueryCanceled,
             ReadySet(e) => ps::Error::Unknown(e.to_string()),
             PostgreSql(e) => e.into(),
         }
@@ -1,6 +1,9 @@
 #![feature(box_patterns, type_alias_impl_trait, generic_associated_types)]
 mod backend;
+mod cancel;
+mod copy;
 mod error;
+mod listen;
 mod query_handler;
 mod response;
 mod resultset;
@@ -10,6 +13,7 @@ mod upstream;
 mod value;
 
 pub use crate::backend::Backend;
+pub use crate::cancel::CancelRegistry;
 pub use crate::error::Error;
 pub use crate::query_handler::PostgreSqlQueryHandler;
 pub use crate::upstream::PostgreSqlUpstream;
@@ -0,0 +1,242 @@
+//! Support for `LISTEN` and `UNLISTEN` statements, which clients use to subscribe to changes to
+//! the results of cached queries rather than repeatedly executing them.
+//!
+//! The name of the channel being listened on is the name of a cached query (as shown by
+//! `SHOW CACHES`), followed by the values of its parameters in parentheses, for example
+//! `LISTEN "q_8bcd2f3c3ea1a9c4(42, 'abc')"`. Whenever a row is added to or removed from the
+//! results of the query for those parameters, a notification is sent on the channel with a JSON
+//! payload of the form `{"op":"insert","row":[...]}` or `{"op":"delete","row":[...]}`.
+//!
+//! If the subscription falls behind the changes being made to the query's results, a single
+//! `{"op":"resync"}` notification is sent and the subscription ends; the client should execute
+//! the query again, and `LISTEN` on the channel again to keep receiving changes.
+//!
+//! When ReadySet has an upstream database, `LISTEN` and `UNLISTEN` statements on channels that
+//! don't name a cached query are proxied to it as usual, and the notifications it sends on those
+//! channels are forwarded to the client.
+
+use nom_sql::{parse_select_statement, Dialect, Expr, FieldDefinitionExpr, Relation};
+use psql_srv as ps;
+use readyset::ViewDelta;
+use readyset_data::DfValue;
+use serde_json::json;
+
+/// A parsed `LISTEN` or `UNLISTEN` statement
+#[derive(Debug, PartialEq, Eq)]
+pub(crate) enum ListenStatement {
+    /// `LISTEN <channel>`
+    Listen(String),
+    /// `UNLISTEN <channel>`
+    Unlisten(String),
+    /// `UNLISTEN *`
+    UnlistenAll,
+}
+
+/// Parse `query` as a `LISTEN` or `UNLISTEN` statement, or return [`None`] if it isn't one
+pub(crate) fn parse_listen(query: &str) -> Option<ListenStatement> {
+    let query = query.trim().trim_end_matches(';').trim_end();
+    let (keyword, rest) = query.split_once(char::is_whitespace)?;
+    let rest = rest.trim_start();
+    if keyword.eq_ignore_ascii_case("listen") {
+        Some(ListenStatement::Listen(parse_identifier(rest)?))
+    } else if keyword.eq_ignore_ascii_case("unlisten") {
+        if rest == "*" {
+            Some(ListenStatement::UnlistenAll)
+        } else {
+            Some(ListenStatement::Unlisten(parse_identifier(rest)?))
+        }
+    } else {
+        None
+    }
+}
+
+/// Parse all of `s` as a single identifier, which is either double-quoted or folded to
+/// lowercase, as in PostgreSQL
+fn parse_identifier(s: &str) -> Option<String> {
+    if let Some(quoted) = s.strip_prefix('"') {
+        let quoted = quoted.strip_suffix('"')?;
+        // Quotes within identifiers are escaped by doubling them
+        if quoted.replace("\"\"", "").contains('"') {
+            return None;
+        }
+        Some(quoted.replace("\"\"", "\""))
+    } else if !s.is_empty() && s.chars().all(|c| c.is_alphanumeric() || c == '_') {
+        Some(s.to_lowercase())
+    } else {
+        None
+    }
+}
+
+/// The cached query and parameter values named by a channel
+#[derive(Debug, PartialEq)]
+pub(crate) struct Channel {
+    /// The name of the cached query
+    pub(crate) cache: Relation,
+    /// The values of the query's parameters
+    pub(crate) params: Vec<DfValue>,
+}
+
+impl Channel {
+    /// Parse the name of a channel, of the form `<cache name>(<parameter values>)`, where the
+    /// parameter values are a comma-separated list of SQL literals
+    pub(crate) fn parse(channel: &str) -> Result<Self, ps::Error> {
+        let invalid = || ps::Error::ParseError(format!("Invalid channel name `{}`", channel));
+        let (cache, params) = match channel.split_once('(') {
+            Some((cache, params)) => (cache, params.strip_suffix(')').ok_or_else(invalid)?),
+            None => (channel, ""),
+        };
+        let cache = cache.trim();
+        if cache.is_empty() {
+            return Err(invalid());
+        }
+
+        let params = if params.trim().is_empty() {
+            vec![]
+        } else {
+            parse_select_statement(Dialect::PostgreSQL, format!("SELECT {}", params))
+                .map_err(|_| invalid())?
+                .fields
+                .into_iter()
+                .map(|field| match field {
+                    FieldDefinitionExpr::Expr {
+                        expr: Expr::Literal(lit),
+                        alias: None,
+                    } => DfValue::try_from(lit).map_err(|_| invalid()),
+                    _ => Err(invalid()),
+                })
+                .collect::<Result<_, _>>()?
+        };
+
+        Ok(Channel {
+            cache: cache.into(),
+            params,
+        })
+    }
+}
+
+/// Convert a value in a row sent in a notification to JSON. Values with no direct JSON
+/// representation are sent as strings.
+fn json_value(value: &DfValue) -> serde_json::Value {
+    match value {
+        DfValue::None => serde_json::Value::Null,
+        DfValue::Int(n) => json!(n),
+        DfValue::UnsignedInt(n) => json!(n),
+        DfValue::Float(f) => json!(f),
+        DfValue::Double(f) => json!(f),
+        value => json!(value.to_string()),
+    }
+}
+
+/// Build the notifications to send on `channel` for a batch of changes to the rows of a
+/// subscribed query
+pub(crate) fn delta_notifications(
+    channel: &str,
+    deltas: Vec<ViewDelta>,
+) -> impl Iterator<Item = ps::Notification> + '_ {
+    deltas.into_iter().map(move |delta| {
+        let (op, row) = match delta {
+            ViewDelta::Insert(row) => ("insert", row),
+            ViewDelta::Delete(row) => ("delete", row),
+        };
+        ps::Notification {
+            channel: channel.to_owned(),
+            payload: json!({
+                "op": op,
+                "row": row.iter().map(json_value).collect::<Vec<_>>(),
+            })
+            .to_string(),
+        }
+    })
+}
+
+/// Build the notification sent on `channel` when its subscription has ended because it fell
+/// behind the changes to the rows of the subscribed query
+pub(crate) fn resync_notification(channel: String) -> ps::Notification {
+    ps::Notification {
+        channel,
+        payload: json!({ "op": "resync" }).to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_listen_statements() {
+        assert_eq!(
+            parse_listen("LISTEN q_1234;"),
+            Some(ListenStatement::Listen("q_1234".to_owned()))
+        );
+        assert_eq!(
+            parse_listen("listen Q_1234"),
+            Some(ListenStatement::Listen("q_1234".to_owned()))
+        );
+        assert_eq!(
+            parse_listen(r#"LISTEN "q_1234(1, 'a""b')""#),
+            Some(ListenStatement::Listen(r#"q_1234(1, 'a"b')"#.to_owned()))
+        );
+        assert_eq!(
+            parse_listen("UNLISTEN \"Q_1234()\""),
+            Some(ListenStatement::Unlisten("Q_1234()".to_owned()))
+        );
+        assert_eq!(
+            parse_listen("UNLISTEN *;"),
+            Some(ListenStatement::UnlistenAll)
+        );
+        assert_eq!(parse_listen("LISTEN q_1234(1)"), None);
+        assert_eq!(parse_listen("LISTEN"), None);
+        assert_eq!(parse_listen("SELECT * FROM listen"), None);
+    }
+
+    #[test]
+    fn parse_channel() {
+        assert_eq!(
+            Channel::parse("q_1234").unwrap(),
+            Channel {
+                cache: "q_1234".into(),
+                params: vec![],
+            }
+        );
+        assert_eq!(
+            Channel::parse("q_1234(1, -2, 'abc', NULL)").unwrap(),
+            Channel {
+                cache: "q_1234".into(),
+                params: vec![
+                    DfValue::from(1u64),
+                    DfValue::from(-2i64),
+                    DfValue::from("abc"),
+                    DfValue::None
+                ],
+            }
+        );
+        assert!(Channel::parse("q_1234(1").is_err());
+        assert!(Channel::parse("q_1234(a + 1)").is_err());
+        assert!(Channel::parse("(1)").is_err());
+    }
+
+    #[test]
+    fn notification_payloads() {
+        let notifications = delta_notifications(
+            "q_1234(1)",
+            vec![
+                ViewDelta::Insert(vec![1.into(), "a".into(), DfValue::None]),
+                ViewDelta::Delete(vec![2.into(), "b".into(), DfValue::None]),
+            ],
+        )
+        .collect::<Vec<_>>();
+        assert_eq!(
+            notifications,
+            vec![
+                ps::Notification {
+                    channel: "q_1234(1)".to_owned(),
+                    payload: r#"{"op":"insert","row":[1,"a",null]}"#.to_owned(),
+                },
+                ps::Notification {
+                    channel: "q_1234(1)".to_owned(),
+                    payload: r#"{"op":"delete","row":[2,"b",null]}"#.to_owned(),
+                },
+            ]
+        );
+    }
+}
@@ -1,11 +1,12 @@
 use std::net::{IpAddr, Ipv4Addr, SocketAddr};
+use std::sync::Arc;
 
 use async_trait::async_trait;
 use clap::Parser;
-use psql_srv::run_backend;
+use psql_srv::{run_backend, TlsAcceptor};
 use readyset_adapter::backend as cl;
 use readyset_client_adapter::{ConnectionHandler, DatabaseType, NoriaAdapter};
-use readyset_psql::{Backend, PostgreSqlQueryHandler, PostgreSqlUpstream};
+use readyset_psql::{Backend, CancelRegistry, PostgreSqlQueryHandler, PostgreSqlUpstream};
 use readyset_version::VERSION_STR_PRETTY;
 use tokio::net;
 use tracing::{error, instrument};
@@ -14,8 +15,12 @@ use tracing::{error, instrument};
 #[global_allocator]
 static ALLOC: tikv_jemallocator::Jemalloc = tikv_jemallocator::Jemalloc;
 
-#[derive(Clone, Copy)]
-struct PsqlHandler;
+#[derive(Clone, Default)]
+struct PsqlHandler {
+    /// Shared by all connections, so that clients can cancel queries running on one connection
+    /// from another
+    cancel_registry: CancelRegistry,
+}
 
 #[async_trait]
 impl ConnectionHandler for PsqlHandler {
@@ -27,9 +32,11 @@ impl ConnectionHandler for PsqlHandler {
         &mut self,
         stream: net::TcpStream,
         backend: cl::Backend<PostgreSqlUpstream, PostgreSqlQueryHandler>,
+        tls_acceptor: Option<Arc<TlsAcceptor>>,
     ) {
-        let backend = Backend(backend);
-        run_backend(backend, stream).await;
+        let require_tls = backend.require_tls;
+        let backend = Backend::new(backend, self.cancel_registry.clone());
+        run_backend(backend, stream, tls_acceptor, require_tls).await;
     }
 
     async fn immediate_error(self, stream: net::TcpStream, error_message: String) {
@@ -56,7 +63,7 @@ fn main() -> anyhow::Result<()> {
     let mut adapter = NoriaAdapter {
         description: "PostgreSQL adapter for ReadySet.",
         default_address: SocketAddr::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 3306),
-        connection_handler: PsqlHandler,
+        connection_handler: PsqlHandler::default(),
         database_type: DatabaseType::Psql,
         parse_dialect: nom_sql::Dialect::PostgreSQL,
         expr_dialect: readyset_data::Dialect::DEFAULT_POSTGRESQL,
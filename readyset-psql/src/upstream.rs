@@ -4,19 +4,22 @@ use std::os::unix::ffi::OsStrExt;
 use std::str::FromStr;
 
 use async_trait::async_trait;
-use futures::TryStreamExt;
+use futures::{future, TryStreamExt};
 use nom_sql::SqlIdentifier;
 use pgsql::config::Host;
 use pgsql::types::Type;
-use pgsql::{GenericResult, Row, SimpleQueryMessage};
+use pgsql::{AsyncMessage, CancelToken, GenericResult, Row, SimpleQueryMessage};
+use postgres_native_tls::MakeTlsConnector;
 use psql_srv::Column;
+use readyset::replication::ReplicationOffset;
 use readyset::ColumnSchema;
 use readyset_adapter::fallback_cache::FallbackCache;
 use readyset_adapter::upstream_database::{NoriaCompare, UpstreamDestination};
 use readyset_adapter::{UpstreamConfig, UpstreamDatabase, UpstreamPrepare};
 use readyset_data::DfValue;
-use readyset_errors::{unsupported, ReadySetError};
+use readyset_errors::{internal_err, unsupported, ReadySetError};
 use tokio::process::Command;
+use tokio::sync::mpsc;
 use tokio_postgres as pgsql;
 use tracing::{debug, info, info_span};
 use tracing_futures::Instrument;
@@ -30,6 +33,9 @@ pub struct PostgreSqlUpstream {
     client: pgsql::Client,
     /// A tokio task that handles the connection, required by `tokio_postgres` to operate
     _connection_handle: tokio::task::JoinHandle<Result<(), pgsql::Error>>,
+    /// Notifications sent by the upstream database on channels this connection is listening on,
+    /// which are forwarded by the connection task
+    notifications: mpsc::UnboundedReceiver<pgsql::Notification>,
     /// Map from prepared statement IDs to prepared statements
     prepared_statements: HashMap<u32, pgsql::Statement>,
     /// ID for the next prepared statement
@@ -38,6 +44,9 @@ pub struct PostgreSqlUpstream {
     user: Option<String>,
     /// Upstream db configuration
     upstream_config: UpstreamConfig,
+    /// The TLS connector used to connect to the upstream, which is also used to send it cancel
+    /// requests
+    tls: MakeTlsConnector,
 
     /// ReadySet-wrapped Postgresql version string, to return to clients
     version: String,
@@ -105,6 +114,65 @@ impl NoriaCompare for StatementMeta {
     }
 }
 
+/// A handle that can be used to cancel the query running on a [`PostgreSqlUpstream`] connection
+/// from another task
+#[derive(Clone)]
+pub struct UpstreamCancelToken {
+    token: CancelToken,
+    tls: MakeTlsConnector,
+}
+
+impl UpstreamCancelToken {
+    /// Ask the upstream database to cancel the query currently running on the connection, if any.
+    ///
+    /// As with any PostgreSQL cancel request, there's no guarantee that the query will actually be
+    /// canceled, and if it does, the error is returned to whoever is waiting on its results.
+    pub async fn cancel(&self) -> Result<(), Error> {
+        Ok(self.token.cancel_query(self.tls.clone()).await?)
+    }
+}
+
+impl PostgreSqlUpstream {
+    /// Returns a token that can be used to cancel the query running on this connection from
+    /// another task
+    pub fn cancel_token(&self) -> UpstreamCancelToken {
+        UpstreamCancelToken {
+            token: self.client.cancel_token(),
+            tls: self.tls.clone(),
+        }
+    }
+
+    /// Run `copy_statement`, a `COPY (<query>) TO STDOUT` statement, returning the number of
+    /// columns in the results of `query` along with a stream of the data copied out, which is
+    /// read from the upstream database as the stream is polled
+    pub async fn copy_out(
+        &self,
+        query: &str,
+        copy_statement: &str,
+    ) -> Result<(usize, pgsql::CopyOutStream), Error> {
+        // tokio-postgres doesn't give us the column count sent in the `CopyOutResponse`, so we
+        // describe `query` as well. Polling both futures together pipelines that with the `COPY`
+        // statement's own `Parse`, so it doesn't cost another round trip.
+        let (statement, data) = future::try_join(
+            self.client.prepare(query),
+            self.client.copy_out(copy_statement),
+        )
+        .await?;
+        Ok((statement.columns().len(), data))
+    }
+
+    /// Wait for the next notification sent by the upstream database on a channel this connection
+    /// is listening on. If the connection has closed this never returns.
+    ///
+    /// This is cancel-safe.
+    pub async fn next_notification(&mut self) -> pgsql::Notification {
+        match self.notifications.recv().await {
+            Some(notification) => notification,
+            None => future::pending().await,
+        }
+    }
+}
+
 #[async_trait]
 impl UpstreamDatabase for PostgreSqlUpstream {
     type StatementMeta = StatementMeta;
@@ -135,28 +203,51 @@ impl UpstreamDatabase for PostgreSqlUpstream {
             }
             builder.build().unwrap() // Never returns an error
         };
-        let tls = postgres_native_tls::MakeTlsConnector::new(connector);
+        let tls = MakeTlsConnector::new(connector);
         let span = info_span!(
             "Connecting to PostgreSQL upstream",
             host = ?pg_config.get_hosts(),
             port = ?pg_config.get_ports()
         );
         span.in_scope(|| info!("Establishing connection"));
-        let (client, connection) = pg_config.connect(tls).instrument(span.clone()).await?;
+        let (client, connection) = pg_config
+            .connect(tls.clone())
+            .instrument(span.clone())
+            .await?;
         let version = connection.parameter("server_version").ok_or_else(|| {
             ReadySetError::Internal("Upstream database failed to send server version".to_string())
         })?;
         let version = format!("{version} ReadySet");
-        let _connection_handle = tokio::spawn(connection);
+        let (notifications_tx, notifications) = mpsc::unbounded_channel();
+        let _connection_handle = tokio::spawn(async move {
+            // Polling the connection as a future would discard any notifications it receives, so
+            // drive it by polling for messages instead
+            let mut connection = connection;
+            while let Some(message) = future::poll_fn(|cx| connection.poll_message(cx)).await {
+                match message? {
+                    AsyncMessage::Notification(notification) => {
+                        // The receiver is dropped if the upstream is reset, which is fine
+                        let _ = notifications_tx.send(notification);
+                    }
+                    AsyncMessage::Notice(notice) => {
+                        info!("{}: {}", notice.severity(), notice.message())
+                    }
+                    _ => {}
+                }
+            }
+            Ok(())
+        });
         span.in_scope(|| info!("Established connection to upstream"));
 
         Ok(Self {
             client,
             _connection_handle,
+            notifications,
             prepared_statements: Default::default(),
             statement_id_counter: 0,
             user,
             upstream_config,
+            tls,
             version,
         })
     }
@@ -222,7 +313,25 @@ impl UpstreamDatabase for PostgreSqlUpstream {
     where
         S: AsRef<str> + Send + Sync + 'a,
     {
-        unsupported!("Read-Your-Write not yet implemented for PostgreSQL")
+        unsupported!(
+            "Timestamp-based Read-Your-Write is not supported for PostgreSQL; \
+             use replication offsets instead"
+        )
+    }
+
+    async fn replication_offset(&mut self) -> Result<Option<ReplicationOffset>, Error> {
+        // The insert position is past the end of the commit record of every transaction that has
+        // committed on this connection, which is the position the replicator reports once it's
+        // applied that transaction
+        let lsn: String = self
+            .client
+            .query_one("SELECT pg_current_wal_insert_lsn()::text", &[])
+            .await?
+            .get(0);
+        Ok(Some(ReplicationOffset {
+            offset: parse_lsn(&lsn)?,
+            replication_log_name: String::new(),
+        }))
     }
 
     async fn execute<'a>(
@@ -330,6 +439,16 @@ impl UpstreamDatabase for PostgreSqlUpstream {
     }
 }
 
+/// Parse a textual PostgreSQL log sequence number, which is printed as two hexadecimal numbers of
+/// up to 8 digits each separated by a slash (for example `16/B374D848`)
+fn parse_lsn(lsn: &str) -> Result<u128, ReadySetError> {
+    let invalid = || internal_err!("Invalid LSN returned by upstream: {lsn}");
+    let (hi, lo) = lsn.split_once('/').ok_or_else(invalid)?;
+    let hi = u32::from_str_radix(hi, 16).map_err(|_| invalid())?;
+    let lo = u32::from_str_radix(lo, 16).map_err(|_| invalid())?;
+    Ok((hi as u128) << 32 | lo as u128)
+}
+
 #[cfg(test)]
 mod tests {
     use nom_sql::{Column as NomColumn, ColumnSpecification, SqlType};
@@ -344,6 +463,14 @@ mod tests {
         }
     }
 
+    #[test]
+    fn parse_lsns() {
+        assert_eq!(parse_lsn("0/0").unwrap(), 0);
+        assert_eq!(parse_lsn("16/B374D848").unwrap(), 0x16_B374_D848);
+        parse_lsn("B374D848").unwrap_err();
+        parse_lsn("x/1").unwrap_err();
+    }
+
     #[test]
     fn compare_matching_schema() {
         let s: StatementMeta = StatementMeta {
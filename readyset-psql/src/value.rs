@@ -1,5 +1,4 @@
 use std::convert::{TryFrom, TryInto};
-use std::net::IpAddr;
 
 use eui48::MacAddress;
 use postgres_types::Kind;
@@ -91,7 +90,7 @@ impl TryFrom<Value> for ps::Value {
             (Type::INET, dt @ (DfValue::Text(_) | DfValue::TinyText(_))) => Ok(ps::Value::Inet(
                 <&str>::try_from(&dt)
                     .unwrap()
-                    .parse::<IpAddr>()
+                    .parse::<ps::IpNetwork>()
                     .map_err(|e| ps::Error::ParseError(e.to_string()))?,
             )),
             (Type::UUID, DfValue::Text(u)) => Ok(ps::Value::Uuid(
@@ -424,6 +424,233 @@ async fn toast() {
     assert!(last_statement_matches("upstream", "ok", &conn).await);
 }
 
+#[cfg(feature = "failure_injection")]
+#[tokio::test(flavor = "multi_thread")]
+#[serial]
+async fn canceled_read_is_not_proxied() {
+    use readyset_adapter::backend::QueryDestination;
+    use readyset_client_test_helpers::psql_helpers::last_query_info;
+    use tokio_postgres::error::SqlState;
+    use tokio_postgres::NoTls;
+
+    let (config, _handle) = setup().await;
+    let client = connect(config).await;
+
+    client
+        .simple_query("CREATE TABLE cats (id int, PRIMARY KEY(id))")
+        .await
+        .unwrap();
+    client
+        .simple_query("INSERT INTO cats (id) VALUES (1)")
+        .await
+        .unwrap();
+    sleep().await;
+    client
+        .simple_query("CREATE CACHE FROM SELECT id FROM cats WHERE id = $1")
+        .await
+        .unwrap();
+
+    // Hold up the adapter's first connection to the reader, so that the read is still in flight
+    // when it's canceled
+    fail::cfg(failpoints::READ_QUERY, "pause").unwrap();
+    let cancel_token = client.cancel_token();
+    let read = tokio::spawn(async move {
+        let res = client
+            .query("SELECT id FROM cats WHERE id = $1", &[&1i32])
+            .await;
+        (client, res)
+    });
+    sleep().await;
+    cancel_token.cancel_query(NoTls).await.unwrap();
+    let (client, res) = read.await.unwrap();
+    fail::cfg(failpoints::READ_QUERY, "off").unwrap();
+
+    // Had the read been retried against the upstream database, it would have succeeded
+    assert_eq!(
+        res.unwrap_err().code(),
+        Some(&SqlState::QUERY_CANCELED),
+        "Canceled read should not be proxied upstream"
+    );
+    assert_eq!(
+        last_query_info(&client).await.destination,
+        QueryDestination::Readyset
+    );
+}
+
+#[tokio::test(flavor = "multi_thread")]
+#[serial]
+async fn listen_on_uncached_channel_is_proxied() {
+    use std::time::Duration;
+
+    use tokio::sync::mpsc;
+    use tokio_postgres::{AsyncMessage, NoTls};
+
+    let (config, _handle) = setup().await;
+    let (client, mut connection) = config.connect(NoTls).await.unwrap();
+    // Polling the connection as a future would discard the notifications it receives
+    let (notifications_tx, mut notifications) = mpsc::unbounded_channel();
+    tokio::spawn(async move {
+        while let Some(message) = futures::future::poll_fn(|cx| connection.poll_message(cx)).await {
+            if let AsyncMessage::Notification(notification) = message.unwrap() {
+                notifications_tx.send(notification).unwrap();
+            }
+        }
+    });
+
+    // `my_channel` doesn't name a cached query, so this should be proxied rather than failing
+    client.simple_query("LISTEN my_channel").await.unwrap();
+    client
+        .simple_query("NOTIFY my_channel, 'hello'")
+        .await
+        .unwrap();
+
+    let notification = tokio::time::timeout(Duration::from_secs(5), notifications.recv())
+        .await
+        .expect("Timed out waiting for notification")
+        .unwrap();
+    assert_eq!(notification.channel(), "my_channel");
+    assert_eq!(notification.payload(), "hello");
+}
+
+#[tokio::test(flavor = "multi_thread")]
+#[serial]
+async fn read_your_writes() {
+    use std::time::Duration;
+
+    use readyset_adapter::backend::QueryDestination;
+    use readyset_client_test_helpers::psql_helpers::last_query_info;
+
+    let (config, _handle) = TestBuilder::new(
+        BackendBuilder::default()
+            .require_authentication(false)
+            .read_your_writes_timeout(Some(Duration::from_secs(10))),
+    )
+    .fallback(true)
+    .build::<PostgreSQLAdapter>()
+    .await;
+    let client = connect(config).await;
+
+    client
+        .simple_query("CREATE TABLE cats (id int, PRIMARY KEY(id))")
+        .await
+        .unwrap();
+    client
+        .simple_query("CREATE TABLE dogs (id int, PRIMARY KEY(id))")
+        .await
+        .unwrap();
+    client
+        .simple_query("INSERT INTO cats (id) VALUES (1)")
+        .await
+        .unwrap();
+    sleep().await;
+    client
+        .simple_query("CREATE CACHE FROM SELECT id FROM cats WHERE id = $1")
+        .await
+        .unwrap();
+    client
+        .query("SELECT id FROM cats WHERE id = $1", &[&1i32])
+        .await
+        .unwrap();
+
+    // A read right after a write to the table it reads observes the write, and is still served by
+    // ReadySet
+    client
+        .simple_query("INSERT INTO cats (id) VALUES (2)")
+        .await
+        .unwrap();
+    let rows = client
+        .query("SELECT id FROM cats WHERE id = $1", &[&2i32])
+        .await
+        .unwrap();
+    assert_eq!(rows.len(), 1);
+    assert_eq!(rows[0].get::<_, i32>(0), 2);
+    assert_eq!(
+        last_query_info(&client).await.destination,
+        QueryDestination::Readyset
+    );
+
+    // A write to a table the read doesn't touch doesn't hold it up until the next keepalive from
+    // the upstream database either
+    client
+        .simple_query("INSERT INTO dogs (id) VALUES (1)")
+        .await
+        .unwrap();
+    let rows = client
+        .query("SELECT id FROM cats WHERE id = $1", &[&1i32])
+        .await
+        .unwrap();
+    assert_eq!(rows.len(), 1);
+    assert_eq!(
+        last_query_info(&client).await.destination,
+        QueryDestination::Readyset
+    );
+}
+
+#[tokio::test(flavor = "multi_thread")]
+#[serial]
+async fn parametrized_limit() {
+    use readyset_adapter::backend::QueryDestination;
+    use readyset_adapter::rewrite::MAX_PARAMETRIZED_LIMIT;
+    use readyset_client_test_helpers::psql_helpers::last_query_info;
+
+    let (config, _handle) = setup().await;
+    let client = connect(config).await;
+
+    client
+        .simple_query("CREATE TABLE items (id int, tenant int, PRIMARY KEY(id))")
+        .await
+        .unwrap();
+    client
+        .simple_query(
+            "INSERT INTO items (id, tenant) VALUES (1, 1), (2, 1), (3, 1), (4, 1), (5, 1)",
+        )
+        .await
+        .unwrap();
+    sleep().await;
+
+    let query = "SELECT id FROM items WHERE tenant = $1 ORDER BY id LIMIT $2 OFFSET $3";
+    client
+        .simple_query(&format!("CREATE CACHE FROM {query}"))
+        .await
+        .unwrap();
+
+    let ids = |rows: Vec<tokio_postgres::Row>| -> Vec<i32> {
+        rows.into_iter().map(|r| r.get(0)).collect()
+    };
+
+    let rows = client.query(query, &[&1i32, &2i64, &1i64]).await.unwrap();
+    assert_eq!(ids(rows), vec![2, 3]);
+    assert_eq!(
+        last_query_info(&client).await.destination,
+        QueryDestination::Readyset
+    );
+
+    // Deleting a row in the page pulls the next row into the cached results for the key
+    client
+        .simple_query("DELETE FROM items WHERE id = 2")
+        .await
+        .unwrap();
+    sleep().await;
+
+    let rows = client.query(query, &[&1i32, &2i64, &1i64]).await.unwrap();
+    assert_eq!(ids(rows), vec![3, 4]);
+    assert_eq!(
+        last_query_info(&client).await.destination,
+        QueryDestination::Readyset
+    );
+
+    // Pages past the rows cached for each key are read from upstream
+    let rows = client
+        .query(query, &[&1i32, &(MAX_PARAMETRIZED_LIMIT as i64), &1i64])
+        .await
+        .unwrap();
+    assert_eq!(ids(rows), vec![3, 4, 5]);
+    assert_eq!(
+        last_query_info(&client).await.destination,
+        QueryDestination::ReadysetThenUpstream
+    );
+}
+
 #[allow(dead_code)]
 async fn last_statement_matches(dest: &str, status: &str, client: &Client) -> bool {
     match &client
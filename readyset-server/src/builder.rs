@@ -75,9 +75,10 @@ impl Builder {
         // See `noria/server/src/controller/sql/serde.rs` for details.
         builder.set_reuse(None);
 
-        builder.set_allow_topk(opts.enable_experimental_topk_support);
-        builder.set_allow_paginate(opts.enable_experimental_paginate_support);
-        builder.set_allow_mixed_comparisons(opts.enable_experimental_mixed_comparisons);
+        builder.set_allow_topk(!opts.disable_topk_support);
+        builder.set_allow_paginate(!opts.disable_paginate_support);
+        builder.set_allow_mixed_comparisons(!opts.disable_mixed_comparisons);
+        builder.set_multiset_extremum(!opts.disable_multiset_extremum);
 
         builder.set_replication_strategy(opts.domain_replication_options.into());
 
@@ -188,6 +189,11 @@ impl Builder {
         self.config.mir_config.allow_mixed_comparisons = allow_mixed_comparisons;
     }
 
+    /// Set the value of [`controller::sql::Config::multiset_extremum`]
+    pub fn set_multiset_extremum(&mut self, multiset_extremum: bool) {
+        self.config.mir_config.multiset_extremum = multiset_extremum;
+    }
+
     /// Set the value of [`DomainConfig::aggressively_update_state_sizes`][0]. See the documentation
     /// of that field for more information
     ///
@@ -201,6 +207,12 @@ impl Builder {
         self.config.replicator_config.upstream_db_url = Some(url.into());
     }
 
+    /// Sets the tables to replicate from the upstream database, in the format of
+    /// [`replicators::Config::replication_tables`]
+    pub fn set_replication_tables(&mut self, tables: String) {
+        self.config.replicator_config.replication_tables = Some(tables.into());
+    }
+
     /// Sets configuration for the replicator thread
     pub fn set_replicator_config(&mut self, config: UpstreamConfig) {
         self.config.replicator_config = config;
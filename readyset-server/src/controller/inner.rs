@@ -121,6 +121,7 @@ impl Leader {
 
                 match replicators::NoriaAdapter::start(
                     noria,
+                    Arc::clone(&authority),
                     config.clone(),
                     Some(ready_notification.clone()),
                     telemetry_sender.clone(),
@@ -15,6 +15,7 @@ use dataflow::ops::grouped::concat::GroupConcat;
 use dataflow::ops::join::{Join, JoinType};
 use dataflow::ops::latest::Latest;
 use dataflow::ops::project::Project;
+use dataflow::ops::window::WindowFunction;
 use dataflow::{node, ops, Expr as DfExpr, PostLookupAggregates, ReaderProcessing};
 use itertools::Itertools;
 use mir::graph::MirGraph;
@@ -278,6 +279,27 @@ pub(super) fn mir_node_to_flow_parts(
                         mig,
                     )?
                 }
+                MirNodeInner::FullJoin {
+                    ref on,
+                    ref project,
+                    ..
+                } => {
+                    invariant_eq!(ancestors.len(), 2);
+                    let left = ancestors[0];
+                    let right = ancestors[1];
+                    make_join_node(
+                        graph,
+                        name,
+                        left,
+                        right,
+                        &graph.columns(mir_node),
+                        on,
+                        project,
+                        JoinType::Full,
+                        custom_types,
+                        mig,
+                    )?
+                }
                 MirNodeInner::Project {
                     ref emit,
                     ref literals,
@@ -352,6 +374,27 @@ pub(super) fn mir_node_to_flow_parts(
                         mig,
                     )?
                 }
+                MirNodeInner::Window {
+                    ref function,
+                    ref over,
+                    ref partition_by,
+                    ref order_by,
+                    ..
+                } => {
+                    invariant_eq!(ancestors.len(), 1);
+                    let parent = ancestors[0];
+                    make_window_node(
+                        graph,
+                        name,
+                        parent,
+                        &graph.columns(mir_node),
+                        function.clone(),
+                        over.as_ref(),
+                        partition_by,
+                        order_by,
+                        mig,
+                    )?
+                }
                 MirNodeInner::AliasTable { .. } => {
                     invariant_eq!(ancestors.len(), 1);
                     // Ancestors should already have a flow node set.
@@ -1127,6 +1170,70 @@ fn make_paginate_or_topk_node(
     Ok(FlowNode::New(na))
 }
 
+#[allow(clippy::too_many_arguments)]
+fn make_window_node(
+    graph: &MirGraph,
+    name: Relation,
+    parent: NodeIndex,
+    columns: &[Column],
+    function: WindowFunction,
+    over: Option<&Column>,
+    partition_by: &[Column],
+    order_by: &[(Column, OrderType)],
+    mig: &mut Migration<'_>,
+) -> ReadySetResult<FlowNode> {
+    invariant!(
+        !partition_by.is_empty(),
+        "need bogokey for Window without partition columns"
+    );
+
+    let parent_na = graph[parent].flow_node_addr()?;
+    let mut parent_cols = mig.dataflow_state.ingredients[parent_na].columns().to_vec();
+
+    let over_col_indx = over
+        .map(|c| graph.column_id_for_column(parent, c))
+        .transpose()?;
+    let partition_by_indx = partition_by
+        .iter()
+        .map(|c| graph.column_id_for_column(parent, c))
+        .collect::<ReadySetResult<Vec<_>>>()?;
+    let order_by_indx = order_by
+        .iter()
+        .map(|(c, ot)| graph.column_id_for_column(parent, c).map(|id| (id, *ot)))
+        .collect::<ReadySetResult<Vec<_>>>()?;
+
+    let over_col_ty = over_col_indx
+        .map(|i| {
+            parent_cols
+                .get(i)
+                .map(|c| c.ty())
+                .ok_or_else(|| internal_err!("Invalid index"))
+        })
+        .transpose()?;
+    let window = ops::window::Window::new(
+        parent_na,
+        function,
+        over_col_indx,
+        partition_by_indx,
+        order_by_indx,
+        over_col_ty,
+    )?;
+    let output_col_ty = window.output_col_type();
+    let column_names = column_names(columns);
+    parent_cols.push(DfColumn::new(
+        column_names
+            .last()
+            .ok_or_else(|| internal_err!("Window has no projections"))?
+            .into(),
+        output_col_ty,
+        Some(name.clone()),
+    ));
+    set_names(&column_names, &mut parent_cols)?;
+
+    let na = mig.add_ingredient(name, parent_cols, window);
+    Ok(FlowNode::New(na))
+}
+
 fn make_reader_processing(
     graph: &MirGraph,
     parent: &NodeIndex,
@@ -726,6 +726,11 @@ impl AuthorityLeaderElectionState {
                                 let recipe = Recipe::with_config(
                                     crate::sql::Config {
                                         reuse_type: self.config.reuse,
+                                        excluded_columns: self
+                                            .config
+                                            .replicator_config
+                                            .replication_excluded_columns
+                                            .clone(),
                                         ..Default::default()
                                     },
                                     self.config.mir_config.clone(),
@@ -763,6 +768,19 @@ impl AuthorityLeaderElectionState {
                                 }
                                 state.dataflow_state.domain_config = self.config.domain_config.clone();
                                 state.dataflow_state.replication_strategy = self.config.replication_strategy;
+                                // Reject new queries that reference columns which are excluded
+                                // from replication now. The replicator drops the tables whose
+                                // exclusions changed, along with the queries cached on them, and
+                                // snapshots them again.
+                                let sql_config = crate::sql::Config {
+                                    excluded_columns: self
+                                        .config
+                                        .replicator_config
+                                        .replication_excluded_columns
+                                        .clone(),
+                                    ..state.dataflow_state.recipe.sql_config().clone()
+                                };
+                                state.dataflow_state.recipe.set_sql_config(sql_config);
                                 state.config = self.config.clone();
                                 Ok(state)
                             }
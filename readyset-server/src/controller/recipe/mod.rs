@@ -1,4 +1,5 @@
 use std::str;
+use std::time::Duration;
 use std::vec::Vec;
 
 use nom_sql::{
@@ -65,10 +66,12 @@ impl Recipe {
                 name,
                 statement,
                 always,
+                max_staleness,
             } => SqlQuery::CreateCache(CreateCacheStatement {
                 name: Some(name.clone()),
                 inner: CacheInner::Statement(Box::new(statement.clone())),
                 always: *always,
+                max_staleness: *max_staleness,
             }),
         });
         if expr.is_none() {
@@ -132,6 +135,15 @@ impl Recipe {
         self.registry.resolve_alias(alias)
     }
 
+    /// Returns the maximum staleness of reads of the cache with the given name (or alias), as
+    /// specified with `CREATE CACHE ... WITH MAX_STALENESS`
+    pub(in crate::controller) fn max_staleness(&self, name: &Relation) -> Option<Duration> {
+        match self.registry.get(name)? {
+            RecipeExpr::Cache { max_staleness, .. } => *max_staleness,
+            _ => None,
+        }
+    }
+
     /// Returns a set of all *original names* for all caches in the recipe (not including aliases)
     pub(in crate::controller) fn cache_names(&self) -> impl Iterator<Item = &Relation> + '_ {
         self.registry.cache_names()
@@ -273,6 +285,7 @@ impl Recipe {
                             name: name.clone(),
                             statement: statement.clone(),
                             always: ccqs.always,
+                            max_staleness: ccqs.max_staleness,
                         };
                         let aliased = self.registry.add_query(expression)?;
                         debug!(
@@ -286,7 +299,9 @@ impl Recipe {
                         )?;
                         if !aliased {
                             // The expression is already present, and we successfully added
-                            // a new alias for it.
+                            // a new alias for it. The most recently requested staleness bound
+                            // applies to reads of every alias.
+                            self.registry.set_max_staleness(name, ccqs.max_staleness);
                             continue;
                         }
                     }
@@ -296,6 +311,7 @@ impl Recipe {
                         name: name.clone(),
                         statement,
                         always: ccqs.always,
+                        max_staleness: ccqs.max_staleness,
                     })?;
                     self.registry
                         .insert_invalidating_tables(name.clone(), invalidating_tables)?;
@@ -1,6 +1,7 @@
 use std::borrow::Borrow;
 use std::collections::hash_map::Entry;
 use std::collections::{HashMap, HashSet};
+use std::time::Duration;
 
 use launchpad::hash::hash;
 use nom_sql::analysis::visit::{self, Visitor};
@@ -27,6 +28,10 @@ pub(super) enum RecipeExpr {
         name: Relation,
         statement: SelectStatement,
         always: bool,
+        /// The maximum staleness of reads of the cache, as specified with `CREATE CACHE ... WITH
+        /// MAX_STALENESS`
+        #[serde(default)]
+        max_staleness: Option<Duration>,
     },
 }
 
@@ -217,6 +222,21 @@ impl ExprRegistry {
         self.expressions.get(query_id)
     }
 
+    /// Sets the maximum staleness of reads of the cache with the given name (or alias), if there is
+    /// one.
+    pub(super) fn set_max_staleness(&mut self, alias: &Relation, max_staleness: Option<Duration>) {
+        let expression = self
+            .aliases
+            .get(alias)
+            .and_then(|query_id| self.expressions.get_mut(query_id));
+        if let Some(RecipeExpr::Cache {
+            max_staleness: cur, ..
+        }) = expression
+        {
+            *cur = max_staleness;
+        }
+    }
+
     /// Returns true if the given expression exists in `self`
     pub(super) fn contains<E>(&self, expression: &E) -> bool
     where
@@ -420,6 +440,7 @@ mod tests {
                 statement: parse_select_statement(Dialect::MySQL, "SELECT * FROM test_table;")
                     .unwrap(),
                 always: false,
+                max_staleness: None,
             };
 
             assert_eq!(cached_query.name(), &query_name);
@@ -451,6 +472,7 @@ mod tests {
                 statement: parse_select_statement(Dialect::MySQL, "SELECT * FROM test_table;")
                     .unwrap(),
                 always: false,
+                max_staleness: None,
             };
 
             let cached_query_table_refs = cached_query.table_references();
@@ -494,6 +516,7 @@ mod tests {
                     name: "test_query".into(),
                     statement: statement.clone(),
                     always: false,
+                    max_staleness: None,
                 })
                 .unwrap();
             registry
@@ -501,6 +524,7 @@ mod tests {
                     name: "test_query_alias".into(),
                     statement,
                     always: false,
+                    max_staleness: None,
                 })
                 .unwrap();
 
@@ -511,6 +535,7 @@ mod tests {
                     name: "test_query".into(),
                     statement: statement.clone(),
                     always: false,
+                    max_staleness: None,
                 })
                 .unwrap();
             registry
@@ -518,6 +543,7 @@ mod tests {
                     name: "test_query_alias".into(),
                     statement,
                     always: false,
+                    max_staleness: None,
                 })
                 .unwrap();
 
@@ -558,6 +584,7 @@ mod tests {
                 )
                 .unwrap(),
                 always: false,
+                max_staleness: None,
             };
 
             assert!(registry.add_query(expr.clone()).unwrap());
@@ -575,6 +602,7 @@ mod tests {
                 statement: parse_select_statement(Dialect::MySQL, "SELECT * FROM test_table;")
                     .unwrap(),
                 always: false,
+                max_staleness: None,
             };
             assert!(!registry.add_query(expr).unwrap());
 
@@ -585,11 +613,37 @@ mod tests {
                     name: "test_query".into(),
                     statement: parse_select_statement(Dialect::MySQL, "SELECT * FROM test_table;")
                         .unwrap(),
-                    always: false
+                    always: false,
+                    max_staleness: None,
                 }
             );
         }
 
+        #[test]
+        fn set_max_staleness_through_alias() {
+            let mut registry = setup();
+
+            let expr = RecipeExpr::Cache {
+                name: "test_query2".into(),
+                statement: parse_select_statement(Dialect::MySQL, "SELECT * FROM test_table;")
+                    .unwrap(),
+                always: false,
+                max_staleness: None,
+            };
+            assert!(!registry.add_query(expr).unwrap());
+            registry.set_max_staleness(&"test_query2".into(), Some(Duration::from_secs(5)));
+
+            for name in ["test_query", "test_query2"] {
+                assert!(matches!(
+                    registry.get(&name.into()).unwrap(),
+                    RecipeExpr::Cache {
+                        max_staleness: Some(d),
+                        ..
+                    } if *d == Duration::from_secs(5)
+                ));
+            }
+        }
+
         #[test]
         fn add_view() {
             let mut registry = setup();
@@ -728,7 +782,8 @@ mod tests {
                     name: "test_query".into(),
                     statement: parse_select_statement(Dialect::MySQL, "SELECT * FROM test_table")
                         .unwrap(),
-                    always: false
+                    always: false,
+                    max_staleness: None,
                 }
             );
             assert!(registry.get(&"test_query_alias".into()).is_none())
@@ -774,6 +829,7 @@ mod tests {
                     name: "test".into(),
                     statement: stmt.clone(),
                     always: false,
+                    max_staleness: None,
                 })
                 .unwrap();
             assert!(registry.contains(&stmt))
@@ -804,6 +860,7 @@ mod tests {
                     statement: parse_select_statement(Dialect::MySQL, "SELECT * FROM test_table")
                         .unwrap(),
                     always: false,
+                    max_staleness: None,
                 })
                 .unwrap();
 
@@ -890,7 +947,8 @@ mod tests {
                 .add_query(RecipeExpr::Cache {
                     name: "foo".into(),
                     statement: query.clone(),
-                    always: false
+                    always: false,
+                    max_staleness: None,
                 })
                 .unwrap());
 
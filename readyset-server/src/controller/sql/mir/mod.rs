@@ -7,6 +7,7 @@ use ::serde::{Deserialize, Serialize};
 use common::{DfValue, IndexType};
 use dataflow::ops::grouped::aggregate::Aggregation;
 use dataflow::ops::union;
+use dataflow::ops::window::WindowFunction;
 use launchpad::redacted::Sensitive;
 use lazy_static::lazy_static;
 use mir::graph::MirGraph;
@@ -45,6 +46,68 @@ lazy_static! {
     pub static ref PAGE_NUMBER_COL: SqlIdentifier = "__page_number".into();
 }
 
+/// Convert the given window function call into the kind of window function to compute and the
+/// column (if any) to compute it over
+fn window_function_kind(
+    function: &FunctionExpr,
+) -> ReadySetResult<(WindowFunction, Option<&nom_sql::Column>)> {
+    fn column_arg(arg: &Expr) -> ReadySetResult<&nom_sql::Column> {
+        match arg {
+            Expr::Column(c) => Ok(c),
+            _ => unsupported!("Window function arguments must currently be columns"),
+        }
+    }
+
+    Ok(match function {
+        FunctionExpr::Sum {
+            expr,
+            distinct: false,
+        } => (WindowFunction::Sum, Some(column_arg(expr)?)),
+        FunctionExpr::Count {
+            expr,
+            distinct: false,
+        } => (WindowFunction::Count, Some(column_arg(expr)?)),
+        FunctionExpr::CountStar => (WindowFunction::Count, None),
+        FunctionExpr::Call { name, arguments } => {
+            let name = name.to_ascii_lowercase();
+            match (name.as_str(), arguments.as_slice()) {
+                ("row_number", []) => (WindowFunction::RowNumber, None),
+                ("rank", []) => (WindowFunction::Rank, None),
+                ("dense_rank", []) => (WindowFunction::DenseRank, None),
+                ("lag" | "lead", [arg, rest @ ..]) if rest.len() <= 2 => {
+                    let offset = match rest.first() {
+                        None => 1,
+                        Some(Expr::Literal(Literal::UnsignedInteger(n))) => *n as usize,
+                        Some(Expr::Literal(Literal::Integer(n))) if *n >= 0 => *n as usize,
+                        Some(offset) => unsupported!(
+                            "The offset for {} must be a non-negative integer literal (got {})",
+                            name,
+                            offset
+                        ),
+                    };
+                    let default = match rest.get(1) {
+                        None => DfValue::None,
+                        Some(Expr::Literal(lit)) => DfValue::try_from(lit)?,
+                        Some(default) => unsupported!(
+                            "The default value for {} must be a literal (got {})",
+                            name,
+                            default
+                        ),
+                    };
+                    let function = if name == "lag" {
+                        WindowFunction::Lag { offset, default }
+                    } else {
+                        WindowFunction::Lead { offset, default }
+                    };
+                    (function, Some(column_arg(arg)?))
+                }
+                _ => unsupported!("Unsupported window function: {}", function),
+            }
+        }
+        _ => unsupported!("Unsupported window function: {}", function),
+    })
+}
+
 fn value_columns_needed_for_predicates(
     value_columns: &[OutputColumn],
     predicates: &[Expr],
@@ -117,16 +180,18 @@ pub enum JoinKind {
     Inner,
     /// Left joins - see [`MirNodeInner::LeftJoin`]
     Left,
+    /// Full outer joins - see [`MirNodeInner::FullJoin`]
+    Full,
     /// Dependent joins - see [`MirNodeInner::DependentJoin`]
     Dependent,
 }
 
 /// Configuration for how SQL is converted to MIR
-#[derive(Debug, PartialEq, Eq, Clone, Serialize, Deserialize, Default)]
+#[derive(Debug, PartialEq, Eq, Clone, Serialize, Deserialize)]
 pub(crate) struct Config {
     /// If set to `true`, a SQL `ORDER BY` with `LIMIT` will emit a [`TopK`][] node. If set to
     /// `false`, the SQL conversion process returns a [`ReadySetError::Unsupported`], causing the
-    /// adapter to send the query to fallback. Defaults to `false`.
+    /// adapter to send the query to fallback. Defaults to `true`.
     ///
     /// [`TopK`]: MirNodeInner::TopK
     pub(crate) allow_topk: bool,
@@ -134,14 +199,36 @@ pub(crate) struct Config {
     /// If set to 'true', a SQL 'ORDER BY' with 'LIMIT' and 'OFFSET' will emit a ['Paginate'][]
     /// node. If set to 'false', the SQL conversion process returns a
     /// ['ReadySetError::Unsupported'], causing the adapter to send the query to fallback. Defaults
-    /// to 'false'.
+    /// to 'true'.
     ///
     /// ['Paginate']: MirNodeInner::Paginate
     pub(crate) allow_paginate: bool,
 
-    /// Enable support for mixing equality and range comparisons in a query. Support for mixed
-    /// comparisons is currently unfinished, so these queries may return incorrect results.
+    /// If set to `true`, queries which mix equality and range comparisons against parameters
+    /// (such as `WHERE x = ? AND y > ?`) will be keyed on a compound range index, with the
+    /// equality columns as a prefix and the range columns as a suffix. If set to `false`, those
+    /// queries return a [`ReadySetError::Unsupported`]. Defaults to `true`.
     pub(crate) allow_mixed_comparisons: bool,
+
+    /// If set to `true`, SQL `MIN` and `MAX` aggregates will be converted to an [`Aggregation`][]
+    /// node which keeps a multiset of the values in each group, rather than an [`Extremum`][]
+    /// node. This trades memory for not having to re-query the parent when the current extremum
+    /// of a group is deleted. Defaults to `true`.
+    ///
+    /// [`Aggregation`]: MirNodeInner::Aggregation
+    /// [`Extremum`]: MirNodeInner::Extremum
+    pub(crate) multiset_extremum: bool,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            allow_topk: true,
+            allow_paginate: true,
+            allow_mixed_comparisons: true,
+            multiset_extremum: true,
+        }
+    }
 }
 
 #[derive(Clone, Debug, Default, Serialize, Deserialize)]
@@ -653,45 +740,49 @@ impl SqlToMirConverter {
             };
         }
 
-        let mut out_nodes = Vec::new();
+        let (min_kind, max_kind) = if self.config.multiset_extremum {
+            (
+                GroupedNodeType::Aggregation(Aggregation::Min),
+                GroupedNodeType::Aggregation(Aggregation::Max),
+            )
+        } else {
+            (
+                GroupedNodeType::Extremum(Extremum::Min),
+                GroupedNodeType::Extremum(Extremum::Max),
+            )
+        };
 
-        let mknode = |over: Column, t: GroupedNodeType, distinct: bool| {
-            if distinct {
-                let new_name = format!("{}_d{}", name, out_nodes.len()).into();
-                let mut dist_col = vec![over.clone()];
-                dist_col.extend(group_cols.clone());
-                let node = self.make_distinct_node(query_name, new_name, parent, dist_col.clone());
-                out_nodes.push(node);
-                out_nodes.push(self.make_grouped_node(
-                    query_name,
-                    name,
-                    func_col,
-                    (node, over),
-                    group_cols,
-                    t,
-                ));
+        let mknode = |over: Column, t: GroupedNodeType| {
+            vec![self.make_grouped_node(query_name, name, func_col, (parent, over), group_cols, t)]
+        };
+
+        let sum_kind = |distinct| {
+            GroupedNodeType::Aggregation(if distinct {
+                Aggregation::SumDistinct
             } else {
-                out_nodes.push(self.make_grouped_node(
-                    query_name,
-                    name,
-                    func_col,
-                    (parent, over),
-                    group_cols,
-                    t,
-                ));
-            }
-            out_nodes
+                Aggregation::Sum
+            })
+        };
+        let count_kind = |distinct| {
+            GroupedNodeType::Aggregation(if distinct {
+                Aggregation::CountDistinct
+            } else {
+                Aggregation::Count
+            })
+        };
+        let avg_kind = |distinct| {
+            GroupedNodeType::Aggregation(if distinct {
+                Aggregation::AvgDistinct
+            } else {
+                Aggregation::Avg
+            })
         };
 
         Ok(match function {
             Sum {
                 expr: box Expr::Column(col),
                 distinct,
-            } => mknode(
-                Column::from(col),
-                GroupedNodeType::Aggregation(Aggregation::Sum),
-                distinct,
-            ),
+            } => mknode(Column::from(col), sum_kind(distinct)),
             Sum { expr, distinct } => mknode(
                 // TODO(celine): replace with ParentRef
                 Column::named(
@@ -700,8 +791,7 @@ impl SqlToMirConverter {
                         .cloned()
                         .ok_or_else(|| mk_error!(&*expr))?,
                 ),
-                GroupedNodeType::Aggregation(Aggregation::Sum),
-                distinct,
+                sum_kind(distinct),
             ),
             CountStar => {
                 internal!("COUNT(*) should have been rewritten earlier!")
@@ -709,11 +799,7 @@ impl SqlToMirConverter {
             Count {
                 expr: box Expr::Column(col),
                 distinct,
-            } => mknode(
-                Column::from(col),
-                GroupedNodeType::Aggregation(Aggregation::Count),
-                distinct,
-            ),
+            } => mknode(Column::from(col), count_kind(distinct)),
             Count { ref expr, distinct } => mknode(
                 // TODO(celine): replace with ParentRef
                 Column::named(
@@ -722,17 +808,12 @@ impl SqlToMirConverter {
                         .cloned()
                         .ok_or_else(|| mk_error!(expr))?,
                 ),
-                GroupedNodeType::Aggregation(Aggregation::Count),
-                distinct,
+                count_kind(distinct),
             ),
             Avg {
                 expr: box Expr::Column(col),
                 distinct,
-            } => mknode(
-                Column::from(col),
-                GroupedNodeType::Aggregation(Aggregation::Avg),
-                distinct,
-            ),
+            } => mknode(Column::from(col), avg_kind(distinct)),
             Avg { ref expr, distinct } => mknode(
                 // TODO(celine): replace with ParentRef
                 Column::named(
@@ -741,16 +822,11 @@ impl SqlToMirConverter {
                         .cloned()
                         .ok_or_else(|| mk_error!(expr))?,
                 ),
-                GroupedNodeType::Aggregation(Aggregation::Avg),
-                distinct,
+                avg_kind(distinct),
             ),
             // TODO(atsakiris): Support Filters for Extremum/GroupConcat
             // CH: https://app.clubhouse.io/readysettech/story/198
-            Max(box Expr::Column(col)) => mknode(
-                Column::from(col),
-                GroupedNodeType::Extremum(Extremum::Max),
-                false,
-            ),
+            Max(box Expr::Column(col)) => mknode(Column::from(col), max_kind),
             Max(ref expr) => mknode(
                 // TODO(celine): replace with ParentRef
                 Column::named(
@@ -759,14 +835,9 @@ impl SqlToMirConverter {
                         .cloned()
                         .ok_or_else(|| mk_error!(expr))?,
                 ),
-                GroupedNodeType::Extremum(Extremum::Max),
-                false,
-            ),
-            Min(box Expr::Column(col)) => mknode(
-                Column::from(col),
-                GroupedNodeType::Extremum(Extremum::Min),
-                false,
+                max_kind,
             ),
+            Min(box Expr::Column(col)) => mknode(Column::from(col), min_kind),
             Min(ref expr) => mknode(
                 // TODO(celine): replace with ParentRef
                 Column::named(
@@ -775,8 +846,7 @@ impl SqlToMirConverter {
                         .cloned()
                         .ok_or_else(|| mk_error!(expr))?,
                 ),
-                GroupedNodeType::Extremum(Extremum::Min),
-                false,
+                min_kind,
             ),
             GroupConcat {
                 expr: box Expr::Column(col),
@@ -784,7 +854,6 @@ impl SqlToMirConverter {
             } => mknode(
                 Column::from(col),
                 GroupedNodeType::Aggregation(Aggregation::GroupConcat { separator }),
-                false,
             ),
             _ => {
                 internal!("not an aggregate: {:?}", Sensitive(&function));
@@ -894,6 +963,7 @@ impl SqlToMirConverter {
         let inner = match kind {
             JoinKind::Inner => MirNodeInner::Join { on, project },
             JoinKind::Left => MirNodeInner::LeftJoin { on, project },
+            JoinKind::Full => MirNodeInner::FullJoin { on, project },
             JoinKind::Dependent => MirNodeInner::DependentJoin { on, project },
         };
         trace!(?inner, "Added join node");
@@ -1551,6 +1621,64 @@ impl SqlToMirConverter {
                 prev_node = subquery_leaf;
             }
 
+            // 9b. Add window function nodes after HAVING, but before pagination
+            for call in &qg.window_functions {
+                let (function, over) = window_function_kind(&call.function)?;
+                let partition_by = if call.partition_by.is_empty() {
+                    // A window without a PARTITION BY is computed over all the rows of the query,
+                    // so we need a bogokey to partition by
+                    if !self
+                        .mir_graph
+                        .columns(prev_node)
+                        .contains(&Column::named("bogokey"))
+                    {
+                        let cols = self.mir_graph.columns(prev_node);
+                        let name = format!(
+                            "q_{:x}_n{}",
+                            qg.signature().hash,
+                            self.mir_graph.node_count()
+                        )
+                        .into();
+                        prev_node = self.make_project_node(
+                            query_name,
+                            name,
+                            prev_node,
+                            cols,
+                            vec![],
+                            vec![("bogokey".into(), DfValue::from(0i32))],
+                        );
+                    }
+                    vec![Column::named("bogokey")]
+                } else {
+                    call.partition_by.iter().map(Column::from).collect()
+                };
+
+                let name = format!(
+                    "q_{:x}_n{}",
+                    qg.signature().hash,
+                    self.mir_graph.node_count()
+                )
+                .into();
+                prev_node = self.add_query_node(
+                    query_name.clone(),
+                    MirNode::new(
+                        name,
+                        MirNodeInner::Window {
+                            function,
+                            over: over.map(Column::from),
+                            partition_by,
+                            order_by: call
+                                .order_by
+                                .iter()
+                                .map(|(c, ot)| (Column::from(c), *ot))
+                                .collect(),
+                            output_column: Column::named(&call.name),
+                        },
+                    ),
+                    &[prev_node],
+                );
+            }
+
             // 10. Get the final node
             let mut final_node = prev_node;
 
@@ -1568,23 +1696,25 @@ impl SqlToMirConverter {
                 let make_topk = offset.is_none();
                 let group_by = if qg.parameters().is_empty() {
                     // need to add another projection to introduce a bogokey to group by if there
-                    // are no query parameters
+                    // are no query parameters (unless a window function has already added one)
                     let cols: Vec<_> = self.mir_graph.columns(final_node);
-                    let name = format!(
-                        "q_{:x}_n{}",
-                        qg.signature().hash,
-                        self.mir_graph.node_count()
-                    )
-                    .into();
-                    let bogo_project = self.make_project_node(
-                        query_name,
-                        name,
-                        final_node,
-                        cols,
-                        vec![],
-                        vec![("bogokey".into(), DfValue::from(0i32))],
-                    );
-                    final_node = bogo_project;
+                    if !cols.contains(&Column::named("bogokey")) {
+                        let name = format!(
+                            "q_{:x}_n{}",
+                            qg.signature().hash,
+                            self.mir_graph.node_count()
+                        )
+                        .into();
+                        let bogo_project = self.make_project_node(
+                            query_name,
+                            name,
+                            final_node,
+                            cols,
+                            vec![],
+                            vec![("bogokey".into(), DfValue::from(0i32))],
+                        );
+                        final_node = bogo_project;
+                    }
                     // Indicates whether we need a bogokey at the leaf node. This is the case for
                     // topk nodes that group by a bogokey. However, this is not the case for
                     // paginate nodes as they will project a page number
@@ -4,14 +4,18 @@ use std::vec::Vec;
 
 use ::mir::visualize::GraphViz;
 use ::serde::{Deserialize, Serialize};
+use database_utils::ExcludedColumn;
+use nom_sql::analysis::visit::{self, Visitor};
 use nom_sql::{
-    CompoundSelectOperator, CompoundSelectStatement, CreateTableStatement, CreateViewStatement,
-    FieldDefinitionExpr, Relation, SelectSpecification, SelectStatement, SqlIdentifier, TableExpr,
+    Column, CompoundSelectOperator, CompoundSelectStatement, CreateTableStatement,
+    CreateViewStatement, FieldDefinitionExpr, Relation, SelectSpecification, SelectStatement,
+    SqlIdentifier, TableExpr,
 };
 use petgraph::graph::NodeIndex;
 use readyset::recipe::changelist::AlterTypeChange;
+use readyset_data::dialect::SqlEngine;
 use readyset_data::{DfType, Dialect, PgEnumMetadata};
-use readyset_errors::{invalid_err, ReadySetError, ReadySetResult};
+use readyset_errors::{invalid_err, unsupported, ReadySetError, ReadySetResult};
 use readyset_sql_passes::alias_removal::TableAliasRewrite;
 use readyset_sql_passes::{AliasRemoval, Rewrite, RewriteContext};
 use tracing::{debug, trace};
@@ -31,16 +35,49 @@ mod query_signature;
 #[non_exhaustive]
 pub(crate) struct Config {
     pub(crate) reuse_type: Option<ReuseConfigType>,
+    /// Columns of tables which are excluded from replication. Queries that reference any of these
+    /// columns can't be cached.
+    #[serde(default)]
+    pub(crate) excluded_columns: Vec<ExcludedColumn>,
 }
 
 impl Default for Config {
     fn default() -> Self {
         Self {
             reuse_type: Some(ReuseConfigType::Finkelstein),
+            excluded_columns: vec![],
         }
     }
 }
 
+/// Returns an unsupported error for the first column referenced by a query which is excluded from
+/// replication
+struct ExcludedColumnsVisitor<'a> {
+    excluded_columns: &'a [ExcludedColumn],
+    /// Whether column names are case-insensitive, as they are in MySQL
+    case_insensitive: bool,
+}
+
+impl<'ast, 'a> Visitor<'ast> for ExcludedColumnsVisitor<'a> {
+    type Error = ReadySetError;
+
+    fn visit_column(&mut self, column: &'ast Column) -> Result<(), Self::Error> {
+        if let Some(table) = &column.table {
+            if self.excluded_columns.iter().any(|excluded| {
+                excluded.matches_column(column.name.as_str(), self.case_insensitive)
+                    && excluded.matches_table(table.schema.as_deref(), table.name.as_str())
+            }) {
+                unsupported!(
+                    "Query references column {} of table {}, which is excluded from replication",
+                    column.name,
+                    table.name
+                );
+            }
+        }
+        visit::walk_column(self, column)
+    }
+}
+
 /// Long-lived struct that holds information about the SQL queries that have been incorporated into
 /// the dataflow graph `graph`.
 ///
@@ -455,6 +492,17 @@ impl SqlIncorporator {
 
         trace!(rewritten_query = %stmt);
 
+        // The values of excluded columns are never replicated, so queries that reference them
+        // would return incorrect results
+        if !self.config.excluded_columns.is_empty() {
+            ExcludedColumnsVisitor {
+                excluded_columns: &self.config.excluded_columns,
+                case_insensitive: mig.dialect.engine() == SqlEngine::MySQL,
+            }
+            .visit_select_statement(&stmt)
+            .map_err(on_err)?;
+        }
+
         let qg = to_query_graph(&stmt).map_err(on_err)?;
         let mir_leaf = self
             .mir_converter
@@ -638,6 +686,59 @@ mod tests {
         .await;
     }
 
+    #[tokio::test(flavor = "multi_thread")]
+    async fn it_rejects_queries_referencing_excluded_columns() {
+        let mut g = integration_utils::start_simple_unsharded(
+            "it_rejects_queries_referencing_excluded_columns",
+        )
+        .await;
+        g.migrate(|mig| {
+            let mut inc = SqlIncorporator::default();
+            // MySQL column names are case-insensitive
+            inc.config.excluded_columns = vec!["users.Password_Hash".parse().unwrap()];
+            inc.add_table(
+                inc.rewrite(
+                    parse_create_table(
+                        Dialect::MySQL,
+                        "CREATE TABLE users (id int, name varchar(40), password_hash text);",
+                    )
+                    .unwrap(),
+                    &[],
+                    DataDialect::DEFAULT_MYSQL,
+                    None,
+                )
+                .unwrap(),
+                mig,
+            )
+            .unwrap();
+
+            let mut add_query = |query: &str| {
+                inc.add_query(
+                    None,
+                    inc.rewrite(
+                        parse_select_statement(Dialect::MySQL, query).unwrap(),
+                        &[],
+                        DataDialect::DEFAULT_MYSQL,
+                        None,
+                    )
+                    .unwrap(),
+                    mig,
+                )
+            };
+
+            add_query("SELECT id, name FROM users WHERE id = ?").unwrap();
+            for query in [
+                "SELECT * FROM users WHERE id = ?",
+                "SELECT id FROM users WHERE password_hash = ?",
+                "SELECT u.id FROM users u WHERE u.password_hash IS NULL",
+            ] {
+                let err = add_query(query).unwrap_err();
+                assert!(err.caused_by_unsupported(), "{query}: {err}");
+            }
+        })
+        .await;
+    }
+
     #[tokio::test(flavor = "multi_thread")]
     async fn it_parses_parameter_column() {
         // set up graph
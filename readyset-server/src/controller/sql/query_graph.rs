@@ -197,6 +197,7 @@ pub struct QueryGraphNode {
 pub enum QueryGraphEdge {
     Join { on: Vec<JoinPredicate> },
     LeftJoin { on: Vec<JoinPredicate> },
+    FullJoin { on: Vec<JoinPredicate> },
 }
 
 #[derive(Clone, Debug, Hash, PartialEq, Eq, Serialize, Deserialize)]
@@ -206,6 +207,19 @@ pub struct Pagination {
     pub offset: Option<ViewPlaceholder>,
 }
 
+/// A window function projected by a query, along with the window it is computed over
+#[derive(Clone, Debug, Hash, PartialEq, Eq, Serialize, Deserialize)]
+pub struct WindowFunctionCall {
+    /// The function to compute
+    pub function: FunctionExpr,
+    /// The columns to partition the rows of the query by
+    pub partition_by: Vec<Column>,
+    /// The columns (and directions) to order the rows within each partition by
+    pub order_by: Vec<(Column, OrderType)>,
+    /// The name of the column for the result of the window function
+    pub name: SqlIdentifier,
+}
+
 /// Description of the lookup key for a view
 #[derive(Clone, Debug, PartialEq, Eq)]
 pub struct ViewKey {
@@ -247,6 +261,8 @@ pub struct QueryGraph {
     pub having_predicates: Vec<Expr>,
     /// The pagination (order, limit, offset) for the query, if any
     pub pagination: Option<Pagination>,
+    /// Window functions in the query, in the order they're projected
+    pub window_functions: Vec<WindowFunctionCall>,
 }
 
 impl QueryGraph {
@@ -321,28 +337,31 @@ impl QueryGraph {
                     (columns.last_mut(), last_op)
                 {
                     if *last_col == param.col {
+                        // A lower and an upper bound on the same column become a single
+                        // BETWEEN-style key column. If either bound is exclusive, the adapter
+                        // applies it as a filter after the (inclusive) range lookup.
                         match (last_op, param.op) {
                             (op1, op2) if op1 == op2 => {}
-                            (BinaryOperator::GreaterOrEqual, BinaryOperator::LessOrEqual) => {
-                                match (*placeholder, param.placeholder_idx) {
-                                    (ViewPlaceholder::OneToOne(lower_idx), Some(upper_idx)) => {
-                                        *placeholder =
-                                            ViewPlaceholder::Between(lower_idx, upper_idx);
-                                        continue;
-                                    }
-                                    _ => unsupported!("Conflicting binary operators in query"),
+                            (
+                                BinaryOperator::GreaterOrEqual | BinaryOperator::Greater,
+                                BinaryOperator::LessOrEqual | BinaryOperator::Less,
+                            ) => match (*placeholder, param.placeholder_idx) {
+                                (ViewPlaceholder::OneToOne(lower_idx), Some(upper_idx)) => {
+                                    *placeholder = ViewPlaceholder::Between(lower_idx, upper_idx);
+                                    continue;
                                 }
-                            }
-                            (BinaryOperator::LessOrEqual, BinaryOperator::GreaterOrEqual) => {
-                                match (param.placeholder_idx, *placeholder) {
-                                    (Some(lower_idx), ViewPlaceholder::OneToOne(upper_idx)) => {
-                                        *placeholder =
-                                            ViewPlaceholder::Between(lower_idx, upper_idx);
-                                        continue;
-                                    }
-                                    _ => unsupported!("Conflicting binary operators in query"),
+                                _ => unsupported!("Conflicting binary operators in query"),
+                            },
+                            (
+                                BinaryOperator::LessOrEqual | BinaryOperator::Less,
+                                BinaryOperator::GreaterOrEqual | BinaryOperator::Greater,
+                            ) => match (param.placeholder_idx, *placeholder) {
+                                (Some(lower_idx), ViewPlaceholder::OneToOne(upper_idx)) => {
+                                    *placeholder = ViewPlaceholder::Between(lower_idx, upper_idx);
+                                    continue;
                                 }
-                            }
+                                _ => unsupported!("Conflicting binary operators in query"),
+                            },
                             _ => unsupported!("Conflicting binary operators in query"),
                         }
                     }
@@ -401,6 +420,7 @@ impl Hash for QueryGraph {
         self.global_predicates.hash(state);
         self.having_predicates.hash(state);
         self.pagination.hash(state);
+        self.window_functions.hash(state);
     }
 }
 
@@ -514,15 +534,18 @@ fn classify_conditionals(
                         global.extend(new_global);
                     }
                     LogicalOp::Or => {
-                        if !new_join.is_empty() {
-                            unsupported!("can't handle OR expressions between JOIN predicates")
-                        }
                         if !new_params.is_empty() {
                             unsupported!(
                                 "can't handle OR expressions between query parameter predicates"
                             );
                         }
-                        if new_local.keys().len() == 1 && new_global.is_empty() {
+                        if !new_join.is_empty() {
+                            // An OR between join predicates can't be used as the key for a join, so
+                            // instead we keep the whole expression as a global predicate, which
+                            // filters the rows of the (cross-)joined tables after the join
+                            new_join.clear();
+                            global.push(ce.clone())
+                        } else if new_local.keys().len() == 1 && new_global.is_empty() {
                             // OR over a single table => local predicate
                             // just checked that new_local has one entry
                             #[allow(clippy::unwrap_used)]
@@ -634,7 +657,8 @@ fn classify_conditionals(
                     | Expr::Between { .. }
                     | Expr::Cast { .. }
                     | Expr::In { .. }
-                    | Expr::Variable(_) => {
+                    | Expr::Variable(_)
+                    | Expr::WindowFunction { .. } => {
                         unsupported!(
                             "Unsupported right-hand side of condition expression: {}",
                             rhs
@@ -697,15 +721,35 @@ fn classify_conditionals(
     Ok(())
 }
 
-/// Convert the given `Expr`, which should be a set of AND-ed together direct
-/// comparison predicates, into a list of predicate expressions
-fn collect_join_predicates(cond: Expr, out: &mut Vec<JoinPredicate>) -> ReadySetResult<()> {
+/// Extract the column referenced by an expression in the PARTITION BY or ORDER BY of a window
+fn window_column(expr: &Expr) -> ReadySetResult<Column> {
+    match expr {
+        Expr::Column(c) => Ok(c.clone()),
+        _ => unsupported!(
+            "Only column references are currently supported in window definitions (got {})",
+            expr
+        ),
+    }
+}
+
+/// Split the given join condition `Expr` at top-level ANDs, collecting the equality comparisons
+/// between columns into `out` (to be used as the keys for the join), and every other condition
+/// (such as non-equality comparisons or ORs) into `filters`, to be applied after the join.
+fn collect_join_predicates(
+    cond: Expr,
+    out: &mut Vec<JoinPredicate>,
+    filters: &mut Vec<Expr>,
+) -> ReadySetResult<()> {
     match cond {
         Expr::BinaryOp {
             op: BinaryOperator::Equal,
             lhs,
             rhs,
-        } => {
+        } if matches!(
+            (lhs.as_ref(), rhs.as_ref()),
+            (Expr::Column(_), Expr::Column(_))
+        ) =>
+        {
             out.push(JoinPredicate {
                 left: *lhs,
                 right: *rhs,
@@ -717,12 +761,13 @@ fn collect_join_predicates(cond: Expr, out: &mut Vec<JoinPredicate>) -> ReadySet
             op: BinaryOperator::And,
             rhs,
         } => {
-            collect_join_predicates(*lhs, out)?;
-            collect_join_predicates(*rhs, out)?;
+            collect_join_predicates(*lhs, out, filters)?;
+            collect_join_predicates(*rhs, out, filters)?;
             Ok(())
         }
-        _ => {
-            unsupported!("Only direct comparisons combined with AND supported for join conditions")
+        cond => {
+            filters.push(cond);
+            Ok(())
         }
     }
 }
@@ -783,6 +828,14 @@ fn extract_having_aggregates(
 
 /// Convert limit and offset fields to an optional constant numeric limit and optional placeholder
 /// for the offset
+///
+/// A parametrized LIMIT can't be used to size a [`TopK`][] or [`Paginate`][] node, so this returns
+/// an unsupported error for it. The adapter instead migrates such queries with a bounded constant
+/// LIMIT in place of the parametrized LIMIT and OFFSET, and applies the LIMIT and OFFSET passed at
+/// execution time to the results of each lookup.
+///
+/// [`TopK`]: ::mir::node::MirNodeInner::TopK
+/// [`Paginate`]: ::mir::node::MirNodeInner::Paginate
 pub(crate) fn extract_limit_offset(
     limit: &Option<Literal>,
     offset: &Option<Literal>,
@@ -900,11 +953,25 @@ pub fn to_query_graph(st: &SelectStatement) -> ReadySetResult<QueryGraph> {
         inner_join_rels.insert(rel);
     }
     for jc in &st.join {
+        if matches!(
+            jc.operator,
+            JoinOperator::RightJoin
+                | JoinOperator::RightOuterJoin
+                | JoinOperator::FullJoin
+                | JoinOperator::FullOuterJoin
+        ) {
+            // All the relations joined so far are on the null-padded side of a right or full join
+            inner_join_rels.clear();
+        }
+        let preserves_rhs = matches!(
+            jc.operator,
+            JoinOperator::RightJoin | JoinOperator::RightOuterJoin
+        );
         match &jc.right {
             JoinRightSide::Table(table_expr) => {
                 if !qg.relations.contains_key(&table_expr.table) {
                     let name = table_expr.table.clone();
-                    if jc.operator.is_inner_join() {
+                    if jc.operator.is_inner_join() || preserves_rhs {
                         inner_join_rels.insert(name.clone());
                     }
                     qg.relations
@@ -913,7 +980,7 @@ pub fn to_query_graph(st: &SelectStatement) -> ReadySetResult<QueryGraph> {
             }
             JoinRightSide::NestedSelect(subquery, alias) => {
                 let rel: Relation = alias.clone().into();
-                if jc.operator.is_inner_join() {
+                if jc.operator.is_inner_join() || preserves_rhs {
                     inner_join_rels.insert(rel.clone());
                 }
                 if let Entry::Vacant(e) = qg.relations.entry(rel.clone()) {
@@ -936,6 +1003,10 @@ pub fn to_query_graph(st: &SelectStatement) -> ReadySetResult<QueryGraph> {
         })
     };
 
+    // Conditions of inner joins that can't be used as join keys, which are applied as global
+    // predicates after the join
+    let mut join_filters = Vec::new();
+
     // 2a. Explicit joins
     // The table specified in the query is available for USING joins.
     // TODO(DAN): why is prev_table tables.last()?
@@ -954,33 +1025,82 @@ pub fn to_query_graph(st: &SelectStatement) -> ReadySetResult<QueryGraph> {
             JoinConstraint::On(cond) => {
                 use nom_sql::analysis::ReferredTables;
 
-                // find all distinct tables mentioned in the condition
-                // conditions for now.
-                let mut tables_mentioned: Vec<Relation> =
-                    cond.referred_tables().into_iter().collect();
-
                 let mut join_preds = vec![];
-                collect_join_predicates(cond.clone(), &mut join_preds)?;
-
-                if tables_mentioned.len() == 2 {
-                    // tables can appear in any order in the join predicate, but
-                    // we cannot just rely on that order, since it may lead us to
-                    // flip LEFT JOINs by accident (yes, this happened)
-                    #[allow(clippy::indexing_slicing)] // len() == 2
-                    if tables_mentioned[1] != rhs_relation {
-                        // tables are in the wrong order in join predicate, swap
-                        tables_mentioned.swap(0, 1);
-                        invariant_eq!(tables_mentioned[1], rhs_relation);
+                let mut filters = vec![];
+                collect_join_predicates(cond.clone(), &mut join_preds, &mut filters)?;
+
+                // Equality comparisons between two columns of the same table don't join anything,
+                // so treat them like any other filter
+                let (mut join_preds, same_table_preds): (Vec<_>, Vec<_>) =
+                    join_preds.into_iter().partition(|pred| {
+                        !matches!(
+                            (&pred.left, &pred.right),
+                            (Expr::Column(l), Expr::Column(r)) if l.table == r.table
+                        )
+                    });
+                filters.extend(same_table_preds.into_iter().map(|pred| Expr::BinaryOp {
+                    lhs: Box::new(pred.left),
+                    op: BinaryOperator::Equal,
+                    rhs: Box::new(pred.right),
+                }));
+
+                if !filters.is_empty() {
+                    if !jc.operator.is_inner_join() {
+                        // For outer joins, a condition that's not part of the join key decides
+                        // whether a row matches, not whether it's returned - so it can't be
+                        // applied as a filter after the join
+                        unsupported!(
+                            "Only equality comparisons between columns are supported in the \
+                             conditions of outer joins"
+                        )
+                    }
+                    for filter in filters {
+                        // Conditions on just one table that's never null-padded by an outer join
+                        // filter that table before the join, and the rest are applied after the
+                        // join
+                        let mut tables = filter.referred_tables().into_iter();
+                        match (tables.next(), tables.next()) {
+                            (Some(table), None)
+                                if inner_join_rels.contains(&table)
+                                    && qg.relations.contains_key(&table) =>
+                            {
+                                #[allow(clippy::unwrap_used)] // checked that the key exists
+                                qg.relations
+                                    .get_mut(&table)
+                                    .unwrap()
+                                    .predicates
+                                    .push(filter);
+                            }
+                            _ => join_filters.push(filter),
+                        }
+                    }
+                }
+
+                // The join is always between the right-hand side and a table mentioned in the
+                // join predicates - or, if the join predicates don't mention any other table (eg
+                // `t1 JOIN t2 ON t2.x > 5`), the table specified in the query
+                let mut other_tables = join_preds
+                    .iter()
+                    .flat_map(|pred| [&pred.left, &pred.right])
+                    .filter_map(|expr| match expr {
+                        Expr::Column(c) => c.table.clone(),
+                        _ => None,
+                    })
+                    .filter(|table| *table != rhs_relation)
+                    .collect::<HashSet<_>>()
+                    .into_iter();
+                left_table = match (other_tables.next(), other_tables.next()) {
+                    (Some(table), None) => table,
+                    (None, _) => {
+                        // prev_table must exist because we error on st.tables.is_empty()
+                        #[allow(clippy::unwrap_used)]
+                        prev_table.as_ref().unwrap().table.clone()
+                    }
+                    (Some(_), Some(_)) => {
+                        unsupported!("more than 2 tables mentioned in join condition!")
                     }
-                    left_table = tables_mentioned.remove(0);
-                    right_table = tables_mentioned.remove(0);
-                } else if tables_mentioned.len() == 1 {
-                    // just one table mentioned --> this is a self-join
-                    left_table = tables_mentioned.remove(0);
-                    right_table = left_table.clone();
-                } else {
-                    unsupported!("more than 2 tables mentioned in join condition!");
                 };
+                right_table = rhs_relation.clone();
 
                 for pred in join_preds.iter_mut() {
                     // the condition tree might specify tables in opposite order to
@@ -1029,20 +1149,41 @@ pub fn to_query_graph(st: &SelectStatement) -> ReadySetResult<QueryGraph> {
             }
         };
 
+        let (left_table, right_table, edge) = match jc.operator {
+            JoinOperator::LeftJoin | JoinOperator::LeftOuterJoin => (
+                left_table,
+                right_table,
+                QueryGraphEdge::LeftJoin { on: join_preds },
+            ),
+            JoinOperator::RightJoin | JoinOperator::RightOuterJoin => {
+                // `a RIGHT JOIN b` is `b LEFT JOIN a`, so swap the sides of the join
+                let on = join_preds
+                    .into_iter()
+                    .map(|JoinPredicate { left, right }| JoinPredicate {
+                        left: right,
+                        right: left,
+                    })
+                    .collect();
+                (right_table, left_table, QueryGraphEdge::LeftJoin { on })
+            }
+            JoinOperator::FullJoin | JoinOperator::FullOuterJoin => (
+                left_table,
+                right_table,
+                QueryGraphEdge::FullJoin { on: join_preds },
+            ),
+            JoinOperator::Join | JoinOperator::InnerJoin => (
+                left_table,
+                right_table,
+                QueryGraphEdge::Join { on: join_preds },
+            ),
+            _ => unsupported!("join operator not supported"),
+        };
+
         // add edge for join
-        // FIXME(eta): inefficient cloning!
         if let std::collections::hash_map::Entry::Vacant(e) =
-            qg.edges.entry((left_table.clone(), right_table.clone()))
+            qg.edges.entry((left_table, right_table))
         {
-            e.insert(match jc.operator {
-                JoinOperator::LeftJoin | JoinOperator::LeftOuterJoin => {
-                    QueryGraphEdge::LeftJoin { on: join_preds }
-                }
-                JoinOperator::Join | JoinOperator::InnerJoin => {
-                    QueryGraphEdge::Join { on: join_preds }
-                }
-                _ => unsupported!("join operator not supported"),
-            });
+            e.insert(edge);
         }
     }
 
@@ -1150,6 +1291,9 @@ pub fn to_query_graph(st: &SelectStatement) -> ReadySetResult<QueryGraph> {
         qg.global_predicates = global_predicates;
     }
 
+    // Add the conditions of inner joins that couldn't be used as join keys as global predicates
+    qg.global_predicates.extend(join_filters);
+
     // Add HAVING predicates and aggregates. Note that unlike below for selected columns, we don't
     // add any found aggregate functions in the HAVING clause to qg.columns, since we don't want to
     // necessarily return these in the query results.
@@ -1197,6 +1341,33 @@ pub fn to_query_graph(st: &SelectStatement) -> ReadySetResult<QueryGraph> {
                             },
                         })
                     }
+                    Expr::WindowFunction {
+                        function,
+                        partition_by,
+                        order_by,
+                    } => {
+                        let call = WindowFunctionCall {
+                            function: function.clone(),
+                            partition_by: partition_by
+                                .iter()
+                                .map(window_column)
+                                .collect::<ReadySetResult<_>>()?,
+                            order_by: order_by
+                                .iter()
+                                .map(|(expr, ot)| Ok((window_column(expr)?, *ot)))
+                                .collect::<ReadySetResult<_>>()?,
+                            name: name.clone(),
+                        };
+                        if !qg.window_functions.contains(&call) {
+                            qg.window_functions.push(call);
+                        }
+                        // Like aggregates, the values for window functions will have already been
+                        // projected by the time we project the result set columns
+                        qg.columns.push(OutputColumn::Data {
+                            alias: alias.clone().unwrap_or_else(|| name.clone()),
+                            column: Column { name, table: None },
+                        })
+                    }
                     _ => {
                         let mut expr = expr.clone();
                         let aggs = map_aggregates(&mut expr);
@@ -1231,6 +1402,28 @@ pub fn to_query_graph(st: &SelectStatement) -> ReadySetResult<QueryGraph> {
         );
     }
 
+    if !qg.window_functions.is_empty() {
+        if !qg.aggregates.is_empty() || !qg.group_by.is_empty() {
+            unsupported!("Window functions cannot yet be combined with aggregates or GROUP BY")
+        }
+
+        // Query parameters are looked up in the reader, *after* window functions have been
+        // computed over all the rows of the query - which is only equivalent to filtering the rows
+        // before computing the window function if the parameter's column partitions every window
+        for param in qg.parameters() {
+            if qg
+                .window_functions
+                .iter()
+                .any(|wf| !wf.partition_by.contains(&param.col))
+            {
+                unsupported!(
+                    "Parameter column {} must be part of the PARTITION BY of every window function",
+                    param.col
+                )
+            }
+        }
+    }
+
     if let Some(ref order) = st.order {
         // For each column in the `ORDER BY` clause, check if it needs to be projected
         order
@@ -1525,6 +1718,189 @@ mod tests {
         );
     }
 
+    #[test]
+    fn or_between_join_predicates() {
+        let qg = make_query_graph("SELECT t1.x FROM t1, t2 WHERE t1.a = t2.a OR t1.b = t2.b");
+        assert!(qg.edges.is_empty());
+        assert_eq!(
+            qg.global_predicates,
+            vec![Expr::BinaryOp {
+                lhs: Box::new(Expr::BinaryOp {
+                    lhs: Box::new(Expr::Column("t1.a".into())),
+                    op: BinaryOperator::Equal,
+                    rhs: Box::new(Expr::Column("t2.a".into())),
+                }),
+                op: BinaryOperator::Or,
+                rhs: Box::new(Expr::BinaryOp {
+                    lhs: Box::new(Expr::Column("t1.b".into())),
+                    op: BinaryOperator::Equal,
+                    rhs: Box::new(Expr::Column("t2.b".into())),
+                }),
+            }]
+        );
+    }
+
+    #[test]
+    fn non_equi_join_condition() {
+        let qg = make_query_graph(
+            "SELECT t1.x FROM t1 JOIN t2 ON t1.id = t2.id AND t1.ts >= t2.start_ts",
+        );
+        assert_eq!(
+            qg.edges[&("t1".into(), "t2".into())],
+            QueryGraphEdge::Join {
+                on: vec![JoinPredicate {
+                    left: Expr::Column("t1.id".into()),
+                    right: Expr::Column("t2.id".into()),
+                }]
+            }
+        );
+        assert_eq!(
+            qg.global_predicates,
+            vec![Expr::BinaryOp {
+                lhs: Box::new(Expr::Column("t1.ts".into())),
+                op: BinaryOperator::GreaterOrEqual,
+                rhs: Box::new(Expr::Column("t2.start_ts".into())),
+            }]
+        );
+    }
+
+    #[test]
+    fn single_table_join_condition() {
+        let qg = make_query_graph("SELECT t1.x FROM t1 JOIN t2 ON t1.x > 5");
+        assert_eq!(
+            qg.edges,
+            HashMap::from([(
+                ("t1".into(), "t2".into()),
+                QueryGraphEdge::Join { on: vec![] }
+            )])
+        );
+        assert_eq!(
+            qg.relations[&Relation::from("t1")].predicates,
+            vec![Expr::BinaryOp {
+                lhs: Box::new(Expr::Column("t1.x".into())),
+                op: BinaryOperator::Greater,
+                rhs: Box::new(Expr::Literal(Literal::UnsignedInteger(5))),
+            }]
+        );
+        assert!(qg.global_predicates.is_empty());
+    }
+
+    #[test]
+    fn non_equi_left_join_condition() {
+        let query = match parse_query(
+            Dialect::MySQL,
+            "SELECT t1.x FROM t1 LEFT JOIN t2 ON t1.id = t2.id AND t1.ts >= t2.start_ts",
+        )
+        .unwrap()
+        {
+            SqlQuery::Select(stmt) => stmt,
+            _ => unreachable!(),
+        };
+        to_query_graph(&query).unwrap_err();
+    }
+
+    #[test]
+    fn parametrized_limit() {
+        for sql in [
+            "SELECT t.x FROM t WHERE t.y = ? ORDER BY t.x LIMIT ?",
+            "SELECT t.x FROM t WHERE t.y = ? ORDER BY t.x LIMIT ? OFFSET ?",
+        ] {
+            let query = match parse_query(Dialect::MySQL, sql).unwrap() {
+                SqlQuery::Select(stmt) => stmt,
+                _ => unreachable!(),
+            };
+            assert!(to_query_graph(&query).unwrap_err().caused_by_unsupported());
+        }
+    }
+
+    #[test]
+    fn right_join_swaps_sides() {
+        let qg = make_query_graph("SELECT t1.x, t2.y FROM t1 RIGHT JOIN t2 ON t1.id = t2.t1_id");
+
+        assert_eq!(
+            qg.edges,
+            HashMap::from([(
+                ("t2".into(), "t1".into()),
+                QueryGraphEdge::LeftJoin {
+                    on: vec![JoinPredicate {
+                        left: Expr::Column("t2.t1_id".into()),
+                        right: Expr::Column("t1.id".into()),
+                    }]
+                }
+            )])
+        );
+        assert_eq!(
+            qg.join_order,
+            vec![JoinRef {
+                src: "t2".into(),
+                dst: "t1".into()
+            }]
+        );
+    }
+
+    #[test]
+    fn full_join() {
+        let qg =
+            make_query_graph("SELECT t1.x, t2.y FROM t1 FULL OUTER JOIN t2 ON t1.id = t2.t1_id");
+
+        assert_eq!(
+            qg.edges,
+            HashMap::from([(
+                ("t1".into(), "t2".into()),
+                QueryGraphEdge::FullJoin {
+                    on: vec![JoinPredicate {
+                        left: Expr::Column("t1.id".into()),
+                        right: Expr::Column("t2.t1_id".into()),
+                    }]
+                }
+            )])
+        );
+    }
+
+    #[test]
+    fn window_functions() {
+        let qg = make_query_graph(
+            "SELECT t.a, row_number() OVER (PARTITION BY t.b ORDER BY t.c DESC) AS rn FROM t",
+        );
+
+        assert_eq!(
+            qg.window_functions,
+            vec![WindowFunctionCall {
+                function: FunctionExpr::Call {
+                    name: "row_number".into(),
+                    arguments: vec![],
+                },
+                partition_by: vec![Column::from("t.b")],
+                order_by: vec![(Column::from("t.c"), OrderType::OrderDescending)],
+                name: "rn".into(),
+            }]
+        );
+        assert_eq!(
+            qg.columns.last(),
+            Some(&OutputColumn::Data {
+                alias: "rn".into(),
+                column: Column {
+                    name: "rn".into(),
+                    table: None
+                }
+            })
+        );
+    }
+
+    #[test]
+    fn window_function_parameter_outside_partition() {
+        let query = match parse_query(
+            Dialect::MySQL,
+            "SELECT rank() OVER (PARTITION BY t.b ORDER BY t.c) FROM t WHERE t.a = ?",
+        )
+        .unwrap()
+        {
+            SqlQuery::Select(stmt) => stmt,
+            _ => unreachable!(),
+        };
+        to_query_graph(&query).unwrap_err();
+    }
+
     mod view_key {
         use super::*;
 
@@ -1782,6 +2158,63 @@ mod tests {
             );
         }
 
+        #[test]
+        fn mixed_equal_and_multiple_ranges_by_default() {
+            let qg = make_query_graph("SELECT t.x FROM t WHERE t.z = $1 AND t.x > $2 AND t.y < $3");
+            let key = qg.view_key(&Default::default()).unwrap();
+
+            assert_eq!(key.index_type, IndexType::BTreeMap);
+            assert_eq!(
+                key.columns,
+                vec![
+                    (
+                        mir::Column::new(Some("t"), "z"),
+                        ViewPlaceholder::OneToOne(1)
+                    ),
+                    (
+                        mir::Column::new(Some("t"), "x"),
+                        ViewPlaceholder::OneToOne(2)
+                    ),
+                    (
+                        mir::Column::new(Some("t"), "y"),
+                        ViewPlaceholder::OneToOne(3)
+                    )
+                ]
+            );
+        }
+
+        #[test]
+        fn mixed_comparisons_disallowed() {
+            let qg = make_query_graph("SELECT t.x FROM t WHERE t.x > $1 AND t.y = $2");
+            qg.view_key(&mir::Config {
+                allow_mixed_comparisons: false,
+                ..Default::default()
+            })
+            .unwrap_err();
+        }
+
+        #[test]
+        fn exclusive_between() {
+            let qg =
+                make_query_graph("SELECT t.x FROM t WHERE t.y = $1 AND t.x > $2 AND t.x <= $3");
+            let key = qg.view_key(&Default::default()).unwrap();
+
+            assert_eq!(key.index_type, IndexType::BTreeMap);
+            assert_eq!(
+                key.columns,
+                vec![
+                    (
+                        mir::Column::new(Some("t"), "y"),
+                        ViewPlaceholder::OneToOne(1)
+                    ),
+                    (
+                        mir::Column::new(Some("t"), "x"),
+                        ViewPlaceholder::Between(2, 3)
+                    )
+                ]
+            );
+        }
+
         #[test]
         fn paginated() {
             let qg = make_query_graph(
@@ -418,6 +418,7 @@ impl DfState {
             replica_shard_addrs: Array2::from_rows(replicas),
             key_mapping,
             view_request_timeout: self.domain_config.view_request_timeout,
+            max_staleness: self.recipe.max_staleness(&view_req.name),
         }))
     }
 
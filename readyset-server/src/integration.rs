@@ -32,7 +32,7 @@ use readyset::consensus::{Authority, LocalAuthority, LocalAuthorityStore};
 use readyset::consistency::Timestamp;
 use readyset::internal::LocalNodeIndex;
 use readyset::recipe::changelist::ChangeList;
-use readyset::{KeyComparison, Modification, SchemaType, ViewPlaceholder, ViewQuery};
+use readyset::{KeyComparison, Modification, SchemaType, ViewDelta, ViewPlaceholder, ViewQuery};
 use readyset_data::{DfType, DfValue, Dialect};
 use readyset_errors::ReadySetError::{MigrationPlanFailed, RpcFailed, SelectQueryCreationFailed};
 use rust_decimal::prelude::ToPrimitive;
@@ -873,6 +873,52 @@ async fn it_works_with_sql_recipe() {
     assert_eq!(result[0][0], 2.into());
 }
 
+#[tokio::test(flavor = "multi_thread")]
+async fn subscribe_to_view() {
+    let mut g = start_simple("subscribe_to_view").await;
+    let sql = "
+        CREATE TABLE Car (id int, brand varchar(255), PRIMARY KEY(id));
+        CREATE CACHE CarsByBrand FROM SELECT id, brand FROM Car WHERE brand = ?;
+    ";
+    g.extend_recipe(ChangeList::from_str(sql, Dialect::DEFAULT_MYSQL).unwrap())
+        .await
+        .unwrap();
+
+    let mut mutator = g.table("Car").await.unwrap();
+    let mut getter = g.view("CarsByBrand").await.unwrap();
+    let car = |id: i32, brand: &str| vec![id.into(), DfValue::try_from(brand).unwrap()];
+
+    let keys = ["Volvo", "Saab"]
+        .map(|brand| KeyComparison::Equal(vec1![DfValue::try_from(brand).unwrap()]))
+        .to_vec();
+    // The keys have to be in the view before they can be subscribed to
+    getter.multi_lookup(keys.clone(), true).await.unwrap();
+    let mut subscription = getter.subscribe(keys).await.unwrap();
+
+    mutator.insert(car(1, "Volvo")).await.unwrap();
+    let deltas = tokio::time::timeout(Duration::from_secs(5), subscription.next())
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(deltas, vec![ViewDelta::Insert(car(1, "Volvo"))]);
+
+    // Changes to rows for keys that weren't subscribed to aren't sent
+    mutator.insert(car(2, "Volkswagen")).await.unwrap();
+    mutator.insert(car(3, "Saab")).await.unwrap();
+    let deltas = tokio::time::timeout(Duration::from_secs(5), subscription.next())
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(deltas, vec![ViewDelta::Insert(car(3, "Saab"))]);
+
+    mutator.delete(vec![1.into()]).await.unwrap();
+    let deltas = tokio::time::timeout(Duration::from_secs(5), subscription.next())
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(deltas, vec![ViewDelta::Delete(car(1, "Volvo"))]);
+}
+
 #[tokio::test(flavor = "multi_thread")]
 async fn it_works_with_vote() {
     let mut g = start_simple_unsharded("it_works_with_vote").await;
@@ -5287,6 +5333,61 @@ async fn post_join_filter() {
     );
 }
 
+#[tokio::test(flavor = "multi_thread")]
+async fn join_on_single_table_condition() {
+    let mut g = start_simple_unsharded("join_on_single_table_condition").await;
+
+    g.extend_recipe(
+        ChangeList::from_str(
+            "CREATE TABLE t1 (id int, x int);
+         CREATE TABLE t2 (id int, y int);
+         CREATE CACHE q FROM
+            SELECT t1.id AS id_1, t2.id AS id_2
+            FROM t1
+            JOIN t2 ON t1.x > 5",
+            Dialect::DEFAULT_MYSQL,
+        )
+        .unwrap(),
+    )
+    .await
+    .unwrap();
+
+    let mut t1 = g.table("t1").await.unwrap();
+    let mut t2 = g.table("t2").await.unwrap();
+    let mut q = g.view("q").await.unwrap();
+
+    t1.insert_many(vec![
+        vec![DfValue::from(1), DfValue::from(1)],
+        vec![DfValue::from(2), DfValue::from(10)],
+        vec![DfValue::from(3), DfValue::from(20)],
+    ])
+    .await
+    .unwrap();
+
+    t2.insert_many(vec![
+        vec![DfValue::from(4), DfValue::from(0)],
+        vec![DfValue::from(5), DfValue::from(0)],
+    ])
+    .await
+    .unwrap();
+
+    sleep().await;
+
+    let mut res: Vec<_> = q.lookup(&[0.into()], true).await.unwrap().into();
+    res.sort();
+
+    // Every row of t1 matching the condition is joined with every row of t2
+    assert_eq!(
+        res,
+        vec![
+            vec![2.into(), 4.into()],
+            vec![2.into(), 5.into()],
+            vec![3.into(), 4.into()],
+            vec![3.into(), 5.into()],
+        ]
+    );
+}
+
 #[tokio::test(flavor = "multi_thread")]
 /// Tests the case where two tables have the same column name and those columns are
 /// used in a post-join filter.
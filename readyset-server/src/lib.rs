@@ -600,18 +600,25 @@ pub struct WorkerOptions {
     #[clap(long, env = "VOLUME_ID")]
     pub volume_id: Option<VolumeId>,
 
-    /// Enable experimental support for TopK in dataflow
-    #[clap(long, env = "EXPERIMENTAL_TOPK_SUPPORT", hide = true)]
-    pub enable_experimental_topk_support: bool,
-
-    /// Enable experimental support for Paginate in dataflow
-    #[clap(long, env = "EXPERIMENTAL_PAGINATE_SUPPORT", hide = true)]
-    pub enable_experimental_paginate_support: bool,
-
-    /// Enable experimental support for mixing equality and inequality comparisons on query
-    /// parameters
-    #[clap(long, env = "EXPERIMENTAL_MIXED_COMPARISONS_SUPPORT", hide = true)]
-    pub enable_experimental_mixed_comparisons: bool,
+    /// Disable support for TopK in dataflow, causing queries with ORDER BY and LIMIT to be
+    /// paginated in the adapter instead
+    #[clap(long, env = "DISABLE_TOPK_SUPPORT", hide = true)]
+    pub disable_topk_support: bool,
+
+    /// Disable support for Paginate in dataflow, causing queries with ORDER BY, LIMIT and OFFSET
+    /// to be paginated in the adapter instead
+    #[clap(long, env = "DISABLE_PAGINATE_SUPPORT", hide = true)]
+    pub disable_paginate_support: bool,
+
+    /// Disable support for mixing equality and range comparisons on query parameters, causing
+    /// those queries to be proxied to the upstream database instead
+    #[clap(long, env = "DISABLE_MIXED_COMPARISONS_SUPPORT", hide = true)]
+    pub disable_mixed_comparisons: bool,
+
+    /// Disable keeping a multiset of the values in each group for MIN and MAX aggregates, causing
+    /// deletes of the current minimum or maximum of a group to re-query the upstream nodes
+    #[clap(long, env = "DISABLE_MULTISET_EXTREMUM", hide = true)]
+    pub disable_multiset_extremum: bool,
 
     /// Directory in which to store replicated table data. If not specified, defaults to the
     /// current working directory.
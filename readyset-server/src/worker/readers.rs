@@ -14,8 +14,8 @@ use dataflow::{
     Expr as DfExpr, LookupError, ReaderMap, ReaderUpdatedNotifier, Readers, SingleReadHandle,
 };
 use failpoint_macros::set_failpoint;
-use futures_util::future::TryFutureExt;
 use futures_util::stream::{StreamExt, TryStreamExt};
+use futures_util::SinkExt;
 use pin_project::pin_project;
 use readyset::consistency::Timestamp;
 #[cfg(feature = "failure_injection")]
@@ -23,14 +23,15 @@ use readyset::failpoints;
 use readyset::metrics::recorded;
 use readyset::results::ResultIterator;
 use readyset::{
-    KeyComparison, LookupResult, ReadQuery, ReadReply, ReadReplyStats, ReaderAddress, Tagged,
-    ViewQuery,
+    KeyComparison, LookupResult, ReadQuery, ReadReply, ReadReplyStats, ReaderAddress,
+    ReaderConnection, SubscriptionCursor, Tagged, ViewDeltas, ViewQuery,
 };
-use readyset_errors::internal_err;
+use readyset_errors::{internal_err, unsupported};
 use serde::ser::Serializer;
 use serde::Serialize;
 use stream_cancel::Valve;
 use streaming_iterator::StreamingIterator;
+use tokio::io::AsyncReadExt;
 use tokio::sync::mpsc::UnboundedReceiver;
 use tokio::sync::oneshot;
 use tokio_stream::wrappers::TcpListenerStream;
@@ -43,6 +44,10 @@ const RETRY_TIMEOUT: Duration = Duration::from_micros(100);
 
 const WAIT_BEFORE_WARNING: Duration = Duration::from_secs(7);
 
+/// How often an idle subscription checks whether rows have been evicted from its reader, which
+/// doesn't wake subscribers. Changes are sent to subscribers as soon as they're applied.
+const SUBSCRIPTION_CHECK_INTERVAL: Duration = Duration::from_secs(1);
+
 /// A batch of records either intended for local consumption only via the
 /// [`ServerReadReplyBatch::Unserialized`] variant, that avoids cloning entirely or for remote
 /// serialization using the [`ServerReadReplyBatch: :Serialized`] variant.
@@ -296,6 +301,112 @@ impl ReadRequestHandler {
     }
 }
 
+/// Serve a [`ReaderConnection::Subscription`] connection: read the [`ReadQuery::Subscribe`]
+/// request, then send the changes made to the subscribed rows as they're applied, until the client
+/// goes away, the worker shuts down, or the subscription fails.
+async fn serve_subscription(valve: Valve, stream: tokio::net::TcpStream, readers: Readers) {
+    let stream: AsyncBincodeStream<_, ReadQuery, ReadySetResult<ViewDeltas>, _> =
+        AsyncBincodeStream::from(stream).for_async();
+    let (mut tx, rx) = stream.split();
+    let mut rx = valve.wrap(rx);
+
+    let (target, key_comparisons) = match rx.next().await {
+        Some(Ok(ReadQuery::Subscribe {
+            target,
+            key_comparisons,
+        })) => (target, key_comparisons),
+        Some(Ok(_)) => {
+            let _ = tx
+                .send(Err(internal_err!("Expected a subscription request")))
+                .await;
+            return;
+        }
+        Some(Err(_)) | None => return,
+    };
+
+    let mut readers_cache = ReaderMap::default();
+    let mut reader = match get_reader_from_cache(&target, &mut readers_cache, &readers) {
+        Ok(reader) => {
+            // Make sure the clone starts out with an up-to-date eviction epoch
+            reader.eviction_epoch();
+            reader.clone()
+        }
+        Err(e) => {
+            let _ = tx.send(Err(e)).await;
+            return;
+        }
+    };
+
+    let mut cursor = None;
+    loop {
+        let deltas = tokio::select! {
+            deltas = subscription_deltas(&mut reader, &key_comparisons, cursor) => deltas,
+            // The client doesn't send anything after the subscription request, so this only
+            // completes once it's gone away or the worker is shutting down
+            _ = rx.next() => return,
+        };
+        match deltas {
+            // Only the reply that starts the subscription is sent without any changes
+            Ok(deltas) if cursor.is_some() && deltas.deltas.is_empty() => {
+                cursor = Some(deltas.cursor);
+            }
+            Ok(deltas) => {
+                cursor = Some(deltas.cursor);
+                if tx.send(Ok(deltas)).await.is_err() {
+                    return;
+                }
+            }
+            Err(e) => {
+                let _ = tx.send(Err(e)).await;
+                return;
+            }
+        }
+    }
+}
+
+/// Starts a subscription to the changes made to the rows for `key_comparisons` if `cursor` is
+/// `None`, otherwise waits for up to [`SUBSCRIPTION_CHECK_INTERVAL`] for changes made since
+/// `cursor`.
+async fn subscription_deltas(
+    reader: &mut SingleReadHandle,
+    key_comparisons: &[KeyComparison],
+    cursor: Option<SubscriptionCursor>,
+) -> ReadySetResult<ViewDeltas> {
+    if reader.post_lookup.aggregates.is_some() || reader.post_lookup.limit.is_some() {
+        unsupported!("Subscriptions to views with post-lookup aggregates or limits");
+    }
+
+    // Changes to rows for keys which aren't in the reader are dropped, so make sure that all the
+    // keys are present before starting the subscription, and that none of them have been evicted
+    // since
+    let cursor = match cursor {
+        Some(cursor) => cursor,
+        None => {
+            let mut misses = vec![];
+            for key in key_comparisons {
+                match reader.contains(key) {
+                    Ok(true) => {}
+                    Ok(false) => misses.push(key.clone()),
+                    Err(_) if reader.was_dropped() => return Err(ReadySetError::ViewDestroyed),
+                    Err(_) => return Err(ReadySetError::ViewNotYetAvailable),
+                }
+            }
+            if !misses.is_empty() {
+                reader.trigger(misses.into_iter());
+                return Err(ReadySetError::ReaderMissingKey);
+            }
+            return Ok(ViewDeltas {
+                deltas: vec![],
+                cursor: reader.subscribe(),
+            });
+        }
+    };
+
+    reader
+        .wait_for_deltas(key_comparisons, cursor, SUBSCRIPTION_CHECK_INTERVAL)
+        .await
+}
+
 impl Service<Tagged<ReadQuery>> for ReadRequestHandler {
     type Response = Tagged<ReadReply<ServerReadReplyBatch>>;
     type Error = ReadySetError;
@@ -310,6 +421,12 @@ impl Service<Tagged<ReadQuery>> for ReadRequestHandler {
     fn call(&mut self, m: Tagged<ReadQuery>) -> Self::Future {
         let tag = m.tag;
         let res = match m.v {
+            ReadQuery::Subscribe { .. } => CallResult::Immediate(Ok(Tagged {
+                tag,
+                v: ReadReply::Normal(Err(internal_err!(
+                    "Subscriptions must be made on a subscription connection"
+                ))),
+            })),
             ReadQuery::Normal { target, query } => {
                 let span = readyset_tracing::child_span!(INFO, "normal_read_query");
                 let _g = span.enter();
@@ -381,38 +498,56 @@ pub(crate) async fn listen(
             continue;
         }
 
-        let stream = stream.unwrap();
+        let mut stream = stream.unwrap();
         let readers = readers.clone();
+        let valve = valve.clone();
         stream.set_nodelay(true).expect("could not set TCP_NODELAY");
 
-        // future that ensures all blocking reads are handled in FIFO order
-        // and avoid hogging the executors with read retries
-        let (tx, rx) = tokio::sync::mpsc::unbounded_channel::<(BlockingRead, Ack)>();
-        tokio::spawn(retry_misses(rx));
-
-        let r = ReadRequestHandler::new(readers, tx, upquery_timeout);
-
-        let server = server::Server::new(AsyncBincodeStream::from(stream).for_async(), r);
-
-        tokio::spawn(server.map_err(|e| {
-            match e {
-                // server is shutting down -- no need to report this error
-                server::Error::Service(ReadySetError::ServerShuttingDown) => {}
-                server::Error::BrokenTransportRecv(ref e)
-                | server::Error::BrokenTransportSend(ref e) => {
-                    if let bincode::ErrorKind::Io(ref e) = **e {
-                        if e.kind() == std::io::ErrorKind::BrokenPipe
-                            || e.kind() == std::io::ErrorKind::ConnectionReset
-                        {
-                            // client went away
-                        }
-                    } else {
-                        error!(error = %e, "client transport error");
+        tokio::spawn(async move {
+            match stream.read_u8().await.map(ReaderConnection::from_u8) {
+                Ok(Some(ReaderConnection::Rpc)) => {
+                    serve_rpc(stream, readers, upquery_timeout).await
+                }
+                Ok(Some(ReaderConnection::Subscription)) => {
+                    serve_subscription(valve, stream, readers).await
+                }
+                Ok(None) => warn!("client sent an unknown reader connection type"),
+                // client went away
+                Err(_) => {}
+            }
+        });
+    }
+}
+
+/// Serve a [`ReaderConnection::Rpc`] connection
+async fn serve_rpc(stream: tokio::net::TcpStream, readers: Readers, upquery_timeout: Duration) {
+    // future that ensures all blocking reads are handled in FIFO order
+    // and avoid hogging the executors with read retries
+    let (tx, rx) = tokio::sync::mpsc::unbounded_channel::<(BlockingRead, Ack)>();
+    tokio::spawn(retry_misses(rx));
+
+    let r = ReadRequestHandler::new(readers, tx, upquery_timeout);
+
+    let server = server::Server::new(AsyncBincodeStream::from(stream).for_async(), r);
+
+    if let Err(e) = server.await {
+        match e {
+            // server is shutting down -- no need to report this error
+            server::Error::Service(ReadySetError::ServerShuttingDown) => {}
+            server::Error::BrokenTransportRecv(ref e)
+            | server::Error::BrokenTransportSend(ref e) => {
+                if let bincode::ErrorKind::Io(ref e) = **e {
+                    if e.kind() == std::io::ErrorKind::BrokenPipe
+                        || e.kind() == std::io::ErrorKind::ConnectionReset
+                    {
+                        // client went away
                     }
+                } else {
+                    error!(error = %e, "client transport error");
                 }
-                e => error!(error = %e, "reader service error"),
             }
-        }));
+            e => error!(error = %e, "reader service error"),
+        }
     }
 }
 
@@ -567,7 +702,7 @@ fn get_reader_from_cache<'a>(
 #[cfg(test)]
 mod readreply {
     use readyset::results::SharedResults;
-    use readyset::{LookupResult, ReadReply, ReadReplyStats, ReadySetError, Tagged};
+    use readyset::{LookupResult, ReadReply, ReadReplyStats, ReadySetError, Tagged, ViewDelta};
     use readyset_data::DfValue;
 
     use super::*;
@@ -639,6 +774,25 @@ mod readreply {
         }
     }
 
+    #[test]
+    fn rtt_deltas() {
+        let deltas = ViewDeltas {
+            deltas: vec![
+                ViewDelta::Insert(vec![1.into(), "a".into()]),
+                ViewDelta::Delete(vec![2.into(), "b".into()]),
+            ],
+            cursor: SubscriptionCursor {
+                seq: 3,
+                eviction_epoch: 4,
+            },
+        };
+        let got: ReadySetResult<ViewDeltas> = bincode::deserialize(
+            &bincode::serialize(&Ok::<_, ReadySetError>(deltas.clone())).unwrap(),
+        )
+        .unwrap();
+        assert_eq!(got.unwrap(), deltas);
+    }
+
     fn rows_vec<III, II, I>(data: III) -> SharedResults
     where
         III: IntoIterator<Item = II>,
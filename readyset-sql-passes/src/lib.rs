@@ -13,6 +13,7 @@ mod order_limit_removal;
 mod remove_numeric_field_references;
 mod resolve_schemas;
 mod rewrite_between;
+mod scalar_subqueries;
 mod star_expansion;
 mod strip_post_filters;
 mod util;
@@ -39,6 +40,7 @@ pub use crate::order_limit_removal::OrderLimitRemoval;
 pub use crate::remove_numeric_field_references::RemoveNumericFieldReferences;
 pub use crate::resolve_schemas::ResolveSchemas;
 pub use crate::rewrite_between::RewriteBetween;
+pub use crate::scalar_subqueries::RewriteScalarSubqueries;
 pub use crate::star_expansion::StarExpansion;
 pub use crate::strip_post_filters::StripPostFilters;
 pub use crate::util::{
@@ -128,6 +130,7 @@ impl Rewrite for SelectStatement {
             )
             .expand_stars(context.view_schemas)?
             .expand_implied_tables(context.view_schemas)?
+            .rewrite_scalar_subqueries()?
             .normalize_topk_with_aggregate()?
             .rewrite_count_star(context.view_schemas)?
             .detect_problematic_self_joins()?
@@ -0,0 +1,470 @@
+use std::collections::HashSet;
+use std::mem;
+
+use nom_sql::analysis::visit::{self, Visitor};
+use nom_sql::analysis::visit_mut::{self, VisitorMut};
+use nom_sql::analysis::{contains_aggregate, ReferredColumns};
+use nom_sql::{
+    BinaryOperator, Column, Expr, FieldDefinitionExpr, FieldReference, FunctionExpr, GroupByClause,
+    JoinClause, JoinConstraint, JoinOperator, JoinRightSide, Relation, SelectStatement,
+    SqlIdentifier, SqlQuery,
+};
+use readyset_errors::{internal_err, unsupported, ReadySetError, ReadySetResult};
+
+use crate::outermost_table_exprs;
+
+pub trait RewriteScalarSubqueries: Sized {
+    /// Decorrelate all scalar subqueries in the fields and the WHERE clause of the given query
+    /// into LEFT JOINs against grouped subqueries. For example, the following query:
+    ///
+    /// ```sql
+    /// SELECT p.id, (SELECT count(*) FROM comments c WHERE c.post_id = p.id) AS n FROM posts p
+    /// ```
+    ///
+    /// becomes:
+    ///
+    /// ```sql
+    /// SELECT p.id, coalesce(__sq0.__value, 0) AS n
+    /// FROM posts p
+    /// LEFT JOIN (
+    ///     SELECT c.post_id AS __key0, count(*) AS __value
+    ///     FROM comments c
+    ///     GROUP BY c.post_id
+    /// ) __sq0 ON p.id = __sq0.__key0
+    /// ```
+    ///
+    /// Only subqueries which are guaranteed to return at most one row per key (a single aggregate
+    /// with no GROUP BY) and which are correlated only via equality predicates in their WHERE
+    /// clause are supported - any other scalar subqueries will return an unsupported error.
+    ///
+    /// Subqueries in `EXISTS` and `IN` expressions are left untouched.
+    fn rewrite_scalar_subqueries(self) -> ReadySetResult<Self>;
+}
+
+/// Build the name of the `i`th key column projected out of a decorrelated subquery
+fn key_column_name(i: usize) -> SqlIdentifier {
+    format!("__key{}", i).into()
+}
+
+const VALUE_COLUMN_NAME: &str = "__value";
+
+fn relation_name(alias: &Option<SqlIdentifier>, table: &Relation) -> Relation {
+    alias
+        .clone()
+        .map(Relation::from)
+        .unwrap_or_else(|| table.clone())
+}
+
+/// Returns the set of names by which the outermost query in the given statement can refer to the
+/// relations it selects from
+fn local_relations(stmt: &SelectStatement) -> HashSet<Relation> {
+    outermost_table_exprs(stmt)
+        .map(|tbl| relation_name(&tbl.alias, &tbl.table))
+        .chain(stmt.join.iter().filter_map(|jc| match &jc.right {
+            JoinRightSide::NestedSelect(_, alias) => Some(alias.clone().into()),
+            _ => None,
+        }))
+        .collect()
+}
+
+fn split_conjunctions(expr: Expr, out: &mut Vec<Expr>) {
+    match expr {
+        Expr::BinaryOp {
+            lhs,
+            op: BinaryOperator::And,
+            rhs,
+        } => {
+            split_conjunctions(*lhs, out);
+            split_conjunctions(*rhs, out);
+        }
+        expr => out.push(expr),
+    }
+}
+
+fn and_all(exprs: impl IntoIterator<Item = Expr>) -> Option<Expr> {
+    exprs.into_iter().reduce(|lhs, rhs| Expr::BinaryOp {
+        lhs: Box::new(lhs),
+        op: BinaryOperator::And,
+        rhs: Box::new(rhs),
+    })
+}
+
+/// Visitor which finds any `COUNT` aggregate within an expression, not descending into subqueries
+#[derive(Default)]
+struct ContainsCountVisitor(bool);
+
+impl<'ast> Visitor<'ast> for ContainsCountVisitor {
+    type Error = !;
+
+    fn visit_function_expr(
+        &mut self,
+        function_expr: &'ast FunctionExpr,
+    ) -> Result<(), Self::Error> {
+        if matches!(
+            function_expr,
+            FunctionExpr::Count { .. } | FunctionExpr::CountStar
+        ) {
+            self.0 = true;
+        }
+        visit::walk_function_expr(self, function_expr)
+    }
+
+    fn visit_select_statement(&mut self, _: &'ast SelectStatement) -> Result<(), Self::Error> {
+        Ok(())
+    }
+}
+
+fn contains_count(expr: &Expr) -> bool {
+    let mut visitor = ContainsCountVisitor::default();
+    let Ok(()) = visitor.visit_expr(expr);
+    visitor.0
+}
+
+struct RewriteScalarSubqueriesVisitor<'a> {
+    /// The relations referenced by the outer query
+    outer_relations: &'a HashSet<Relation>,
+    /// Joins to add to the outer query, one per decorrelated subquery
+    joins: Vec<JoinClause>,
+    /// Counter used to generate unique names for decorrelated subqueries
+    next_subquery: usize,
+}
+
+impl<'a> RewriteScalarSubqueriesVisitor<'a> {
+    /// Decorrelate the given scalar subquery, adding a join to `self.joins` and returning the
+    /// expression to replace the subquery with
+    fn decorrelate(&mut self, mut subquery: SelectStatement) -> ReadySetResult<Expr> {
+        if !subquery.ctes.is_empty()
+            || subquery.distinct
+            || subquery.group_by.is_some()
+            || subquery.having.is_some()
+            || subquery.order.is_some()
+            || subquery.limit.is_some()
+            || subquery.offset.is_some()
+        {
+            unsupported!(
+                "Scalar subqueries with CTEs, DISTINCT, GROUP BY, HAVING, ORDER BY, LIMIT or \
+                 OFFSET are not supported"
+            );
+        }
+
+        let value = match mem::take(&mut subquery.fields).as_slice() {
+            [FieldDefinitionExpr::Expr { expr, .. }] if contains_aggregate(expr) => expr.clone(),
+            _ => unsupported!("Scalar subqueries must project exactly one aggregate expression"),
+        };
+
+        let inner_relations = local_relations(&subquery);
+        let is_inner = |col: &Column| {
+            col.table
+                .as_ref()
+                .map_or(true, |tbl| inner_relations.contains(tbl))
+        };
+
+        if !value.referred_columns().all(is_inner) {
+            unsupported!(
+                "Scalar subqueries may only reference outer columns in their WHERE clause"
+            );
+        }
+
+        let mut conjuncts = vec![];
+        if let Some(where_clause) = subquery.where_clause.take() {
+            split_conjunctions(where_clause, &mut conjuncts);
+        }
+
+        let mut keys = vec![];
+        let mut filters = vec![];
+        for conjunct in conjuncts {
+            match conjunct {
+                Expr::BinaryOp {
+                    lhs: box Expr::Column(lhs),
+                    op: BinaryOperator::Equal,
+                    rhs: box Expr::Column(rhs),
+                } if is_inner(&lhs) != is_inner(&rhs) => {
+                    let (inner, outer) = if is_inner(&lhs) {
+                        (lhs, rhs)
+                    } else {
+                        (rhs, lhs)
+                    };
+                    if !outer
+                        .table
+                        .as_ref()
+                        .map_or(false, |tbl| self.outer_relations.contains(tbl))
+                    {
+                        unsupported!(
+                            "Scalar subqueries may only reference columns from the immediately \
+                             enclosing query"
+                        );
+                    }
+                    keys.push((inner, outer));
+                }
+                conjunct => {
+                    if !conjunct.referred_columns().all(is_inner) {
+                        unsupported!(
+                            "Scalar subqueries may only be correlated via equality predicates"
+                        );
+                    }
+                    filters.push(conjunct);
+                }
+            }
+        }
+
+        if keys.is_empty() {
+            unsupported!("Uncorrelated scalar subqueries are not supported");
+        }
+
+        let is_count = matches!(
+            value,
+            Expr::Call(FunctionExpr::Count { .. } | FunctionExpr::CountStar)
+        );
+        if !is_count && contains_count(&value) {
+            unsupported!("COUNT nested within an expression in a scalar subquery");
+        }
+
+        let alias: SqlIdentifier = format!("__sq{}", self.next_subquery).into();
+        self.next_subquery += 1;
+
+        subquery.fields = keys
+            .iter()
+            .enumerate()
+            .map(|(i, (inner, _))| FieldDefinitionExpr::Expr {
+                expr: Expr::Column(inner.clone()),
+                alias: Some(key_column_name(i)),
+            })
+            .chain(std::iter::once(FieldDefinitionExpr::Expr {
+                expr: value,
+                alias: Some(VALUE_COLUMN_NAME.into()),
+            }))
+            .collect();
+        subquery.where_clause = and_all(filters);
+        subquery.group_by = Some(GroupByClause {
+            fields: keys
+                .iter()
+                .map(|(inner, _)| FieldReference::Expr(Expr::Column(inner.clone())))
+                .collect(),
+        });
+
+        let constraint =
+            and_all(
+                keys.into_iter()
+                    .enumerate()
+                    .map(|(i, (_, outer))| Expr::BinaryOp {
+                        lhs: Box::new(Expr::Column(outer)),
+                        op: BinaryOperator::Equal,
+                        rhs: Box::new(Expr::Column(Column {
+                            name: key_column_name(i),
+                            table: Some(alias.clone().into()),
+                        })),
+                    }),
+            )
+            .ok_or_else(|| internal_err!("No join keys for scalar subquery"))?;
+
+        self.joins.push(JoinClause {
+            operator: JoinOperator::LeftJoin,
+            right: JoinRightSide::NestedSelect(Box::new(subquery), alias.clone()),
+            constraint: JoinConstraint::On(constraint),
+        });
+
+        let value_column = Expr::Column(Column {
+            name: VALUE_COLUMN_NAME.into(),
+            table: Some(alias.into()),
+        });
+
+        // COUNT over zero rows is 0, but the LEFT JOIN will give us NULL for outer rows with no
+        // matching rows in the subquery
+        Ok(if is_count {
+            Expr::Call(FunctionExpr::Call {
+                name: "coalesce".into(),
+                arguments: vec![value_column, Expr::Literal(0.into())],
+            })
+        } else {
+            value_column
+        })
+    }
+}
+
+impl<'ast, 'a> VisitorMut<'ast> for RewriteScalarSubqueriesVisitor<'a> {
+    type Error = ReadySetError;
+
+    fn visit_expr(&mut self, expr: &'ast mut Expr) -> Result<(), Self::Error> {
+        if let Expr::NestedSelect(subquery) = expr {
+            let subquery = mem::take(subquery.as_mut());
+            *expr = self.decorrelate(subquery)?;
+            return Ok(());
+        }
+
+        visit_mut::walk_expr(self, expr)
+    }
+
+    fn visit_select_statement(
+        &mut self,
+        _select_statement: &'ast mut SelectStatement,
+    ) -> Result<(), Self::Error> {
+        // Don't descend into the subqueries of EXISTS or IN expressions
+        Ok(())
+    }
+}
+
+impl RewriteScalarSubqueries for SelectStatement {
+    fn rewrite_scalar_subqueries(mut self) -> ReadySetResult<Self> {
+        let outer_relations = local_relations(&self);
+        let mut visitor = RewriteScalarSubqueriesVisitor {
+            outer_relations: &outer_relations,
+            joins: vec![],
+            next_subquery: 0,
+        };
+
+        for field in &mut self.fields {
+            if let FieldDefinitionExpr::Expr { expr, alias } = field {
+                let original = expr.clone();
+                visitor.visit_expr(expr)?;
+                // Preserve the name of the field if we rewrote it
+                if alias.is_none() && *expr != original {
+                    *alias = Some(original.to_string().into());
+                }
+            }
+        }
+
+        if let Some(where_clause) = &mut self.where_clause {
+            visitor.visit_expr(where_clause)?;
+        }
+
+        self.join.extend(visitor.joins);
+        Ok(self)
+    }
+}
+
+impl RewriteScalarSubqueries for SqlQuery {
+    fn rewrite_scalar_subqueries(self) -> ReadySetResult<Self> {
+        match self {
+            SqlQuery::Select(stmt) => Ok(SqlQuery::Select(stmt.rewrite_scalar_subqueries()?)),
+            _ => Ok(self),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use nom_sql::{parse_query, Dialect};
+
+    use super::*;
+
+    fn rewrites_to(input: &str, expected: &str) {
+        let actual = parse_query(Dialect::MySQL, input)
+            .unwrap()
+            .rewrite_scalar_subqueries()
+            .unwrap();
+        let expected = parse_query(Dialect::MySQL, expected).unwrap();
+        assert_eq!(actual.to_string(), expected.to_string());
+    }
+
+    fn unsupported(input: &str) {
+        let res = parse_query(Dialect::MySQL, input)
+            .unwrap()
+            .rewrite_scalar_subqueries();
+        assert!(
+            matches!(res, Err(ReadySetError::Unsupported(_))),
+            "Expected unsupported, got {:?}",
+            res
+        );
+    }
+
+    #[test]
+    fn count_in_fields() {
+        rewrites_to(
+            "SELECT p.id, (SELECT count(*) FROM comments c WHERE c.post_id = p.id) AS n
+             FROM posts p",
+            "SELECT p.id, coalesce(__sq0.__value, 0) AS n
+             FROM posts p
+             LEFT JOIN (
+                 SELECT c.post_id AS __key0, count(*) AS __value
+                 FROM comments c
+                 GROUP BY c.post_id
+             ) __sq0 ON p.id = __sq0.__key0",
+        );
+    }
+
+    #[test]
+    fn max_in_where_with_filter() {
+        rewrites_to(
+            "SELECT p.id FROM posts p
+             WHERE (SELECT max(c.score) FROM comments c
+                    WHERE p.id = c.post_id AND c.visible = 1) > 10",
+            "SELECT p.id FROM posts p
+             LEFT JOIN (
+                 SELECT c.post_id AS __key0, max(c.score) AS __value
+                 FROM comments c
+                 WHERE c.visible = 1
+                 GROUP BY c.post_id
+             ) __sq0 ON p.id = __sq0.__key0
+             WHERE __sq0.__value > 10",
+        );
+    }
+
+    #[test]
+    fn multiple_subqueries_and_keys() {
+        rewrites_to(
+            "SELECT
+               (SELECT sum(v.n) FROM votes v WHERE v.post_id = p.id AND v.site_id = p.site_id) AS s,
+               (SELECT min(c.id) FROM comments c WHERE c.post_id = p.id) AS m
+             FROM posts p",
+            "SELECT __sq0.__value AS s, __sq1.__value AS m
+             FROM posts p
+             LEFT JOIN (
+                 SELECT v.post_id AS __key0, v.site_id AS __key1, sum(v.n) AS __value
+                 FROM votes v
+                 GROUP BY v.post_id, v.site_id
+             ) __sq0 ON p.id = __sq0.__key0 AND p.site_id = __sq0.__key1
+             LEFT JOIN (
+                 SELECT c.post_id AS __key0, min(c.id) AS __value
+                 FROM comments c
+                 GROUP BY c.post_id
+             ) __sq1 ON p.id = __sq1.__key0",
+        );
+    }
+
+    #[test]
+    fn preserves_field_name() {
+        let query = parse_query(
+            Dialect::MySQL,
+            "SELECT (SELECT count(*) FROM comments c WHERE c.post_id = p.id) FROM posts p",
+        )
+        .unwrap();
+        let original_name = match &query {
+            SqlQuery::Select(stmt) => match &stmt.fields[0] {
+                FieldDefinitionExpr::Expr { expr, .. } => expr.to_string(),
+                _ => panic!(),
+            },
+            _ => panic!(),
+        };
+
+        match query.rewrite_scalar_subqueries().unwrap() {
+            SqlQuery::Select(stmt) => match &stmt.fields[0] {
+                FieldDefinitionExpr::Expr { alias, .. } => {
+                    assert_eq!(alias.as_deref(), Some(original_name.as_str()))
+                }
+                _ => panic!(),
+            },
+            _ => panic!(),
+        }
+    }
+
+    #[test]
+    fn leaves_exists_alone() {
+        let input = "SELECT p.id FROM posts p
+                     WHERE EXISTS (SELECT * FROM comments c WHERE c.post_id = p.id)";
+        rewrites_to(input, input);
+    }
+
+    #[test]
+    fn unsupported_shapes() {
+        unsupported("SELECT (SELECT c.body FROM comments c WHERE c.post_id = p.id) FROM posts p");
+        unsupported("SELECT (SELECT count(*) FROM comments c) FROM posts p");
+        unsupported("SELECT (SELECT count(*) FROM comments c WHERE c.post_id > p.id) FROM posts p");
+        unsupported(
+            "SELECT (SELECT count(*) + 1 FROM comments c WHERE c.post_id = p.id) FROM posts p",
+        );
+        unsupported(
+            "SELECT (SELECT count(*) FROM comments c WHERE c.post_id = p.id GROUP BY c.author_id)
+             FROM posts p",
+        );
+    }
+}
@@ -120,6 +120,8 @@ pub fn map_aggregates(expr: &mut Expr) -> Vec<(FunctionExpr, SqlIdentifier)> {
             }
         }
         Expr::Array(exprs) => ret.extend(exprs.iter_mut().flat_map(map_aggregates)),
+        // Window functions are lowered separately from aggregates
+        Expr::WindowFunction { .. } => {}
     }
     ret
 }
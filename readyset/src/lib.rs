@@ -361,12 +361,12 @@ pub use crate::controller::{ControllerDescriptor, ReadySetHandle};
 pub use crate::table::{Modification, Operation, Table, TableOperation, TableRequest};
 #[doc(hidden)]
 pub use crate::table::{PacketData, PacketPayload, PacketTrace};
-pub use crate::view::View;
 #[doc(hidden)]
 pub use crate::view::{
-    KeyComparison, LookupResult, ReadQuery, ReadReply, ReadReplyBatch, ReadReplyStats, SchemaType,
-    ViewCreateRequest, ViewQuery,
+    KeyComparison, LookupResult, ReadQuery, ReadReply, ReadReplyBatch, ReadReplyStats,
+    ReaderConnection, SchemaType, ViewCreateRequest, ViewQuery,
 };
+pub use crate::view::{SubscriptionCursor, View, ViewDelta, ViewDeltas, ViewSubscription};
 
 #[doc(hidden)]
 pub mod builders {
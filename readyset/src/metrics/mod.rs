@@ -422,6 +422,11 @@ pub mod recorded {
     /// Counter: Number of replication actions performed successfully.
     pub const REPLICATOR_SUCCESS: &str = "replicator.update_success";
 
+    /// Counter: Number of upstream transactions that were too large to be buffered, and so were
+    /// written to ReadySet in several parts rather than all at once. Incremented by 1 each time a
+    /// transaction is split.
+    pub const REPLICATOR_TRANSACTION_SPLIT: &str = "replicator.transaction_split";
+
     /// Gauge: Indicates whether a server is the leader. Set to 1 when the
     /// server is leader, 0 for follower.
     pub const CONTROLLER_IS_LEADER: &str = "controller.is_leader";
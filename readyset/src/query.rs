@@ -171,16 +171,21 @@ pub struct QueryStatus {
     pub execution_info: Option<ExecutionInfo>,
     /// If we should always cache the query (never proxy to upstream)
     pub always: bool,
+    /// Why the query isn't supported by ReadySet, if it's [unsupported][] and the reason is known
+    ///
+    /// [unsupported]: MigrationState::Unsupported
+    pub unsupported_reason: Option<String>,
 }
 
 impl QueryStatus {
     /// Constructs a QueryStatus with the default migration state for the query, no migration state,
-    /// and always set to false
+    /// always set to false, no staleness bound and no unsupported reason
     pub fn default_for_query(query: &Query) -> Self {
         Self {
             migration_state: MigrationState::default_for_query(query),
             execution_info: None,
             always: false,
+            unsupported_reason: None,
         }
     }
 
@@ -190,6 +195,7 @@ impl QueryStatus {
             migration_state,
             execution_info: None,
             always: false,
+            unsupported_reason: None,
         }
     }
 
@@ -325,6 +325,7 @@ impl Change {
             name: Some(name.into()),
             inner: CacheInner::Statement(Box::new(statement)),
             always,
+            max_staleness: None,
         })
     }
 
@@ -10,6 +10,17 @@ use nom_sql::Relation;
 use readyset_errors::{ReadySetError, ReadySetResult};
 use serde::{Deserialize, Serialize};
 
+/// The prefix of the [`replication_log_name`](ReplicationOffset::replication_log_name) of offsets
+/// in a MySQL binlog that's read by GTID rather than by binlog file and position.
+///
+/// The rest of the name is the set of GTIDs of the transactions executed as of that offset. Since
+/// GTIDs are preserved when a replica is promoted to primary, all such offsets are within the same
+/// log, named [`GTID_LOG_NAME`], regardless of which server they were read from.
+pub const GTID_SET_PREFIX: &str = "gtid:";
+
+/// The name of the log that offsets named with a [`GTID_SET_PREFIX`] are within
+pub const GTID_LOG_NAME: &str = "gtid";
+
 /// A data type representing an offset in a replication log
 ///
 /// Replication offsets are represented by a single global [offset](ReplicationOffset::offset),
@@ -31,7 +42,10 @@ pub struct ReplicationOffset {
 
 impl fmt::Display for ReplicationOffset {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        if !self.replication_log_name.is_empty() {
+        if let Some(gtid_set) = self.gtid_set() {
+            // The low 64 bits are the number of events read in the transaction after `gtid_set`
+            write!(f, "gtid[{}]+{}", gtid_set, self.offset as u64)
+        } else if !self.replication_log_name.is_empty() {
             // Wish we could simply convert to BinlogPosition, but including it in the manifest
             // creates a cyclic dependency hell, so duplicate the code here.
             let suffix_len = (self.offset >> 123) as usize;
@@ -53,7 +67,7 @@ impl fmt::Display for ReplicationOffset {
 
 impl PartialOrd for ReplicationOffset {
     fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
-        if other.replication_log_name != self.replication_log_name {
+        if other.log_name() != self.log_name() {
             None
         } else {
             self.offset.partial_cmp(&other.offset)
@@ -62,6 +76,84 @@ impl PartialOrd for ReplicationOffset {
 }
 
 impl ReplicationOffset {
+    /// Returns the name of the replication log that this offset is within. Only offsets within
+    /// the same log can be compared.
+    ///
+    /// This is the [`replication_log_name`](ReplicationOffset::replication_log_name), except for
+    /// offsets in a MySQL binlog that's read by GTID, which are all within the same log.
+    pub fn log_name(&self) -> &str {
+        if self.gtid_set().is_some() {
+            GTID_LOG_NAME
+        } else {
+            &self.replication_log_name
+        }
+    }
+
+    /// If this is an offset in a MySQL binlog that's read by GTID, returns the set of GTIDs of
+    /// the transactions executed as of this offset, in the format used by MySQL
+    pub fn gtid_set(&self) -> Option<&str> {
+        self.replication_log_name.strip_prefix(GTID_SET_PREFIX)
+    }
+
+    /// Returns the offset of `position` in the MySQL binlog file named `binlog_file`, for a binlog
+    /// that's read by file and position.
+    ///
+    /// We use the binlog basefile name as the log name, and we use the binlog suffix len for the
+    /// top 5 bits, which can be as big as 31 digits in theory, but we only allow up to 17 decimal
+    /// digits, which is more than enough for the binlog spec. This is required to be able to
+    /// properly format the integer back to string, including any leading zeroes. The following 59
+    /// bits are used for the numerical value of the suffix, finally the last 64 bits of the offset
+    /// are the actual binlog offset.
+    pub fn from_binlog_position(binlog_file: &str, position: u32) -> ReadySetResult<Self> {
+        let invalid =
+            || ReadySetError::ReplicationFailed(format!("Invalid binlog name {binlog_file}"));
+        let (basename, suffix) = binlog_file.rsplit_once('.').ok_or_else(invalid)?;
+
+        let suffix_len = suffix.len() as u128;
+        if suffix_len > 17 {
+            // 17 digit decimal number is the most we can fit into 59 bits
+            return Err(invalid());
+        }
+        let suffix = suffix.parse::<u128>().map_err(|_| invalid())?;
+
+        Ok(ReplicationOffset {
+            offset: (suffix_len << 123) + (suffix << 64) + (position as u128),
+            replication_log_name: basename.to_string(),
+        })
+    }
+
+    /// Returns the offset right after all the transactions in the set of MySQL GTIDs `gtid_set`
+    /// (in the format used by MySQL, such as in `@@GLOBAL.gtid_executed`), for a binlog that's
+    /// read by GTID.
+    ///
+    /// `gtid_set` must not contain overlapping intervals, as is the case for the sets MySQL
+    /// returns.
+    pub fn from_gtid_set(gtid_set: &str) -> ReadySetResult<Self> {
+        let invalid = || ReadySetError::ReplicationFailed(format!("Invalid GTID set {gtid_set}"));
+        let mut transactions = 0u128;
+        for sid in gtid_set
+            .split(',')
+            .map(str::trim)
+            .filter(|sid| !sid.is_empty())
+        {
+            // The first part is the server's UUID, and the rest are intervals of transactions
+            for interval in sid.split(':').skip(1) {
+                let (start, end) = interval.split_once('-').unwrap_or((interval, interval));
+                let start = start.trim().parse::<u128>().map_err(|_| invalid())?;
+                let end = end.trim().parse::<u128>().map_err(|_| invalid())?;
+                if end < start {
+                    return Err(invalid());
+                }
+                transactions += end - start + 1;
+            }
+        }
+
+        Ok(ReplicationOffset {
+            offset: transactions << 64,
+            replication_log_name: format!("{GTID_SET_PREFIX}{}", gtid_set.replace('\n', "")),
+        })
+    }
+
     /// Try to mutate `other` to take the maximum of its offset and the offset of
     /// `self`. If `other` is `None`, will assign it to `Some(self.clone)`.
     ///
@@ -69,15 +161,16 @@ impl ReplicationOffset {
     /// [`ReadySetError::ReplicationOffsetLogDifferent`]
     pub fn try_max_into(&self, other: &mut Option<ReplicationOffset>) -> ReadySetResult<()> {
         if let Some(other) = other {
-            if self.replication_log_name != other.replication_log_name {
+            if self.log_name() != other.log_name() {
                 return Err(ReadySetError::ReplicationOffsetLogDifferent(
-                    self.replication_log_name.clone(),
-                    other.replication_log_name.clone(),
+                    self.log_name().to_owned(),
+                    other.log_name().to_owned(),
                 ));
             }
 
             if self.offset > other.offset {
-                other.offset = self.offset
+                // Offsets read by GTID also differ in their names, so take the whole offset
+                *other = self.clone()
             }
         } else {
             *other = Some(self.clone())
@@ -166,10 +259,10 @@ impl ReplicationOffsets {
     /// all replication offsets, from which streaming replication can successfully continue.
     /// Otherwise, returns `Ok(None)`.
     ///
-    /// If all replication offsets are present but any have mismatched [`replication_log_name`]s,
-    /// returns an error.
+    /// If all replication offsets are present but any have mismatched [`log_name`]s, returns an
+    /// error.
     ///
-    /// [`replication_log_name`]: ReplicationOffset::replication_log_name
+    /// [`log_name`]: ReplicationOffset::log_name
     pub fn max_offset(&self) -> ReadySetResult<Option<&ReplicationOffset>> {
         let mut res = match &self.schema {
             Some(schema_offset) => schema_offset,
@@ -180,10 +273,10 @@ impl ReplicationOffsets {
                 Some(offset) => offset,
                 None => return Ok(None),
             };
-            if res.replication_log_name != offset.replication_log_name {
+            if res.log_name() != offset.log_name() {
                 return Err(ReadySetError::ReplicationOffsetLogDifferent(
-                    res.replication_log_name.clone(),
-                    offset.replication_log_name.clone(),
+                    res.log_name().to_owned(),
+                    offset.log_name().to_owned(),
                 ));
             }
             if offset.offset > res.offset {
@@ -198,18 +291,18 @@ impl ReplicationOffsets {
     /// If no offset is present _at all_, returns [`None`] (but note that unlike [`max_offset`][]
     /// this function does *not* return [`None`] if an offset is absent).
     ///
-    /// If any offsets have a different [`replication_log_name`], returns an error.
+    /// If any offsets have a different [`log_name`], returns an error.
     ///
     /// [`max_offset`]: Self::max_offset
-    /// [`replication_log_name`]: ReplicationOffset::replication_log_name
+    /// [`log_name`]: ReplicationOffset::log_name
     pub fn min_present_offset(&self) -> ReadySetResult<Option<&ReplicationOffset>> {
         let mut res: Option<&ReplicationOffset> = None;
         for offset in self.schema.iter().chain(self.tables.values().flatten()) {
             match (res, offset) {
-                (Some(off1), off2) if off1.replication_log_name != off2.replication_log_name => {
+                (Some(off1), off2) if off1.log_name() != off2.log_name() => {
                     return Err(ReadySetError::ReplicationOffsetLogDifferent(
-                        off1.replication_log_name.clone(),
-                        off2.replication_log_name.clone(),
+                        off1.log_name().to_owned(),
+                        off2.log_name().to_owned(),
                     ));
                 }
                 (Some(off1), off2) => {
@@ -223,6 +316,38 @@ impl ReplicationOffsets {
         Ok(res)
     }
 
+    /// Returns the maximum offset *from those present* within the set of replication offsets.
+    ///
+    /// This is only an upper bound on how far replication has progressed: tables which haven't been
+    /// written to recently, or which were snapshotted at an earlier position, may not have had all
+    /// the events before this offset applied yet. Use [`min_present_offset`][] to find a position
+    /// up to which all events have been applied.
+    ///
+    /// If no offset is present _at all_, returns [`None`] (but note that unlike [`max_offset`][]
+    /// this function does *not* return [`None`] if an offset is absent).
+    ///
+    /// If any offsets have a different [`log_name`], returns an error.
+    ///
+    /// [`max_offset`]: Self::max_offset
+    /// [`min_present_offset`]: Self::min_present_offset
+    /// [`log_name`]: ReplicationOffset::log_name
+    pub fn max_present_offset(&self) -> ReadySetResult<Option<&ReplicationOffset>> {
+        let mut res: Option<&ReplicationOffset> = None;
+        for offset in self.schema.iter().chain(self.tables.values().flatten()) {
+            match res {
+                Some(cur) if cur.log_name() != offset.log_name() => {
+                    return Err(ReadySetError::ReplicationOffsetLogDifferent(
+                        cur.log_name().to_owned(),
+                        offset.log_name().to_owned(),
+                    ));
+                }
+                Some(cur) if cur.offset >= offset.offset => {}
+                _ => res = Some(offset),
+            }
+        }
+        Ok(res)
+    }
+
     /// Advance replication offset for the schema and all tables to the given offset.
     /// Replication offsets will not change if they are ahead of the provided offset.
     pub fn advance_offset(&mut self, offset: ReplicationOffset) -> ReadySetResult<()> {
@@ -345,4 +470,126 @@ mod tests {
             assert!(res.is_none());
         }
     }
+
+    mod max_present_offset {
+        use super::*;
+
+        #[test]
+        fn ignores_missing() {
+            let offsets = ReplicationOffsets {
+                schema: None,
+                tables: HashMap::from([
+                    (
+                        "t1".into(),
+                        Some(ReplicationOffset {
+                            offset: 3,
+                            replication_log_name: "test".to_owned(),
+                        }),
+                    ),
+                    (
+                        "t2".into(),
+                        Some(ReplicationOffset {
+                            offset: 2,
+                            replication_log_name: "test".to_owned(),
+                        }),
+                    ),
+                    ("t3".into(), None),
+                ]),
+            };
+            let res = offsets.max_present_offset().unwrap().unwrap();
+            assert_eq!(res.offset, 3);
+        }
+
+        #[test]
+        fn none_present() {
+            let offsets = ReplicationOffsets {
+                schema: None,
+                tables: HashMap::from([("t1".into(), None)]),
+            };
+            assert!(offsets.max_present_offset().unwrap().is_none());
+        }
+
+        #[test]
+        fn not_matching() {
+            let offsets = ReplicationOffsets {
+                schema: Some(ReplicationOffset {
+                    offset: 1,
+                    replication_log_name: "binlog".to_owned(),
+                }),
+                tables: HashMap::from([(
+                    "t1".into(),
+                    Some(ReplicationOffset {
+                        offset: 2,
+                        replication_log_name: "test".to_owned(),
+                    }),
+                )]),
+            };
+            offsets.max_present_offset().unwrap_err();
+        }
+    }
+
+    mod gtid {
+        use super::*;
+
+        fn gtid_offset(gtid_set: &str, offset: u128) -> ReplicationOffset {
+            ReplicationOffset {
+                offset,
+                replication_log_name: format!("{GTID_SET_PREFIX}{gtid_set}"),
+            }
+        }
+
+        #[test]
+        fn offsets_with_different_gtid_sets_are_comparable() {
+            let before = gtid_offset("3e11fa47-71ca-11e1-9e33-c80aa9429562:1-5", 5 << 64);
+            let after = gtid_offset(
+                "3e11fa47-71ca-11e1-9e33-c80aa9429562:1-5,\
+                 4f22fb58-82db-22f2-af44-d91bba53a673:1",
+                6 << 64,
+            );
+            assert_eq!(before.log_name(), GTID_LOG_NAME);
+            assert!(before < after);
+
+            let mut max = Some(before.clone());
+            after.try_max_into(&mut max).unwrap();
+            assert_eq!(max, Some(after.clone()));
+
+            let offsets = ReplicationOffsets {
+                schema: Some(after.clone()),
+                tables: HashMap::from([("t1".into(), Some(before.clone()))]),
+            };
+            assert_eq!(offsets.min_present_offset().unwrap(), Some(&before));
+            assert_eq!(offsets.max_offset().unwrap(), Some(&after));
+        }
+
+        #[test]
+        fn from_gtid_set() {
+            let offset = ReplicationOffset::from_gtid_set(
+                "3e11fa47-71ca-11e1-9e33-c80aa9429562:1-5:7,\n\
+                 4f22fb58-82db-22f2-af44-d91bba53a673:1-3",
+            )
+            .unwrap();
+            assert_eq!(offset.offset, 9 << 64);
+            assert_eq!(
+                offset.gtid_set(),
+                Some(
+                    "3e11fa47-71ca-11e1-9e33-c80aa9429562:1-5:7,\
+                     4f22fb58-82db-22f2-af44-d91bba53a673:1-3"
+                )
+            );
+            assert_eq!(ReplicationOffset::from_gtid_set("").unwrap().offset, 0);
+            ReplicationOffset::from_gtid_set("3e11fa47-71ca-11e1-9e33-c80aa9429562:5-1")
+                .unwrap_err();
+        }
+
+        #[test]
+        fn gtid_and_binlog_offsets_are_not_comparable() {
+            let gtid = gtid_offset("3e11fa47-71ca-11e1-9e33-c80aa9429562:1-5", 5 << 64);
+            let binlog = ReplicationOffset {
+                offset: 5 << 64,
+                replication_log_name: "binlog".to_owned(),
+            };
+            assert_eq!(gtid.partial_cmp(&binlog), None);
+            gtid.try_max_into(&mut Some(binlog)).unwrap_err();
+        }
+    }
 }
@@ -15,8 +15,8 @@ use async_bincode::{AsyncBincodeStream, AsyncDestination};
 use dataflow_expression::{Dialect, Expr as DfExpr};
 use futures_util::future::TryFutureExt;
 use futures_util::stream::futures_unordered::FuturesUnordered;
-use futures_util::stream::{StreamExt, TryStreamExt};
-use futures_util::{future, ready};
+use futures_util::stream::{BoxStream, SelectAll, StreamExt, TryStreamExt};
+use futures_util::{future, ready, SinkExt};
 use launchpad::intervals::{cmp_start_end, BoundPair};
 use launchpad::redacted::Sensitive;
 use nom_sql::{
@@ -29,12 +29,14 @@ use rand::prelude::IteratorRandom;
 use rand::thread_rng;
 use readyset_data::{DfType, DfValue};
 use readyset_errors::{
-    internal_err, rpc_err, unsupported, view_err, ReadySetError, ReadySetResult,
+    internal_err, rpc_err, rpc_err_no_downcast, unsupported, view_err, ReadySetError,
+    ReadySetResult,
 };
 use readyset_sql_passes::anonymize::{Anonymize, Anonymizer};
 use readyset_tracing::presampled::instrument_if_enabled;
 use readyset_tracing::propagation::Instrumented;
 use serde::{Deserialize, Serialize};
+use tokio::io::AsyncWriteExt;
 use tokio_tower::multiplex;
 use tower::balance::p2c::Balance;
 use tower::buffer::Buffer;
@@ -58,6 +60,39 @@ type Transport = AsyncBincodeStream<
     AsyncDestination,
 >;
 
+type SubscriptionTransport = AsyncBincodeStream<
+    tokio::net::TcpStream,
+    ReadySetResult<ViewDeltas>,
+    ReadQuery,
+    AsyncDestination,
+>;
+
+/// The first byte sent by the client on each connection to a worker's reader listener, saying
+/// what the connection is used for
+#[doc(hidden)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[repr(u8)]
+pub enum ReaderConnection {
+    /// Multiplexed request/response reads, of [`ReadQuery`]s answered with [`ReadReply`]s
+    Rpc = 0,
+    /// A single subscription. The client sends one [`ReadQuery::Subscribe`], and the reader then
+    /// sends a [`ViewDeltas`] with no changes once the subscription has started, followed by a
+    /// [`ViewDeltas`] for each batch of changes made to the subscribed rows. The reader closes the
+    /// connection after sending an error.
+    Subscription = 1,
+}
+
+impl ReaderConnection {
+    /// Parse the first byte sent on a connection to a reader listener
+    pub fn from_u8(b: u8) -> Option<Self> {
+        match b {
+            0 => Some(Self::Rpc),
+            1 => Some(Self::Subscription),
+            _ => None,
+        }
+    }
+}
+
 /// Index of a key column as it exists in the underlying state. During a migration this will be
 /// used throughout MIR. In steady state this will refer to the reader key columns.
 pub type KeyColumnIdx = usize;
@@ -234,8 +269,9 @@ impl Service<()> for Endpoint {
         let f = tokio::net::TcpStream::connect(self.addr);
         let timeout = self.timeout;
         async move {
-            let s = tokio::time::timeout(timeout, f).await??;
+            let mut s = tokio::time::timeout(timeout, f).await??;
             s.set_nodelay(true)?;
+            s.write_u8(ReaderConnection::Rpc as u8).await?;
             let s = AsyncBincodeStream::from(s).for_async();
             let t = multiplex::MultiplexTransport::new(s, Tagger::default());
             Ok(multiplex::Client::with_error_handler(
@@ -806,6 +842,43 @@ pub enum ReadQuery {
         /// Where to read from
         target: ReaderAddress,
     },
+    /// Subscribe to the changes made to the rows for a set of keys in a leaf view. This is only
+    /// valid as the request sent on a [`ReaderConnection::Subscription`] connection.
+    Subscribe {
+        /// Where to read from
+        target: ReaderAddress,
+        /// The keys to receive changes for
+        key_comparisons: Vec<KeyComparison>,
+    },
+}
+
+/// A change to the rows of a view, as delivered to subscribers to the view
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub enum ViewDelta {
+    /// A row was added to the view
+    Insert(Vec<DfValue>),
+    /// A row was removed from the view
+    Delete(Vec<DfValue>),
+}
+
+/// The position of a subscriber in the log of changes made to a single shard of a view
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SubscriptionCursor {
+    /// The sequence number of the next batch of changes to read
+    pub seq: u64,
+    /// The eviction epoch of the reader when the subscription was started. Any eviction from the
+    /// reader may drop changes that the subscriber is interested in, so once this changes the
+    /// subscriber has to start over.
+    pub eviction_epoch: usize,
+}
+
+/// A batch of changes made to a view, along with the cursor to read subsequent changes from
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct ViewDeltas {
+    /// The changes, in the order they were made
+    pub deltas: Vec<ViewDelta>,
+    /// Where to read the next batch of changes from
+    pub cursor: SubscriptionCursor,
 }
 
 /// The result of a lookup to a view.
@@ -898,6 +971,11 @@ pub struct ViewBuilder {
 
     /// The amount of time before a view request RPC is terminated.
     pub view_request_timeout: Duration,
+
+    /// The maximum staleness of reads of the view, as specified with `CREATE CACHE ... WITH
+    /// MAX_STALENESS`
+    #[serde(default)]
+    pub max_staleness: Option<Duration>,
 }
 
 impl ViewBuilder {
@@ -976,6 +1054,8 @@ impl ViewBuilder {
             schema,
             columns,
             key_mapping,
+            max_staleness: self.max_staleness,
+            request_timeout: self.view_request_timeout,
             shard_addrs: addrs,
             shards: Vec1::try_from_vec(conns)
                 .map_err(|_| internal_err!("cannot create view '{}' without shards", self.name))?,
@@ -996,6 +1076,8 @@ pub struct View {
     /// (view_placeholder, key_column_index) pairs according to their mapping. Contains exactly
     /// one entry for each key column at the reader.
     key_mapping: Vec<(ViewPlaceholder, KeyColumnIdx)>,
+    max_staleness: Option<Duration>,
+    request_timeout: Duration,
 
     shards: Vec1<ViewRpc>,
     shard_addrs: Vec<SocketAddr>,
@@ -1241,6 +1323,12 @@ impl View {
         self.schema.as_ref()
     }
 
+    /// Get the maximum staleness of reads of this view, if it was created with `CREATE CACHE ...
+    /// WITH MAX_STALENESS`
+    pub fn max_staleness(&self) -> Option<Duration> {
+        self.max_staleness
+    }
+
     /// Get the NodeIndex of the dataflow node that this
     /// view refers to.
     pub fn node(&self) -> &NodeIndex {
@@ -1350,6 +1438,103 @@ impl View {
         Ok(vec)
     }
 
+    /// Subscribe to the changes made to the rows of this view for the given keys.
+    ///
+    /// See [`ViewSubscription`] for how changes are delivered.
+    ///
+    /// The keys must already be present in the view, for example because they have previously
+    /// been looked up, otherwise this returns [`ReadySetError::ReaderMissingKey`] (after
+    /// triggering a replay for them). Changes made before the subscription was started are not
+    /// delivered.
+    #[instrument(level = "info", skip(self))]
+    pub async fn subscribe(
+        &mut self,
+        key_comparisons: Vec<KeyComparison>,
+    ) -> ReadySetResult<ViewSubscription> {
+        let mut shard_keys = vec![Vec::new(); self.shards.len()];
+        for comparison in key_comparisons {
+            for shard in comparison.shard_keys(self.shards.len()) {
+                #[allow(clippy::indexing_slicing)]
+                // We built `shard_keys` to be the correct length, so it's safe to access it by
+                // index in this case.
+                shard_keys[shard].push(comparison.clone());
+            }
+        }
+
+        let shards = future::try_join_all(
+            shard_keys
+                .into_iter()
+                .enumerate()
+                .filter(|(_, key_comparisons)| !key_comparisons.is_empty())
+                .map(|(shard, key_comparisons)| self.subscribe_shard(shard, key_comparisons)),
+        )
+        .await?;
+
+        Ok(ViewSubscription {
+            view: self.clone(),
+            shards: futures_util::stream::select_all(shards),
+        })
+    }
+
+    /// Open a subscription connection to the given shard of this view, and wait for its reader to
+    /// start the subscription. The returned stream ends after the first error, and returns an
+    /// error if the connection is closed.
+    async fn subscribe_shard(
+        &self,
+        shard: usize,
+        key_comparisons: Vec<KeyComparison>,
+    ) -> ReadySetResult<BoxStream<'static, ReadySetResult<ViewDeltas>>> {
+        let node = self.node;
+        let request = ReadQuery::Subscribe {
+            target: ReaderAddress {
+                node,
+                name: self.name.clone(),
+                shard,
+            },
+            key_comparisons,
+        };
+        let addr = self.shard_addrs.get(shard).copied();
+        let timeout = self.request_timeout;
+
+        let connect = async move {
+            let addr =
+                addr.ok_or_else(|| internal_err!("shard {shard} of view {node:?} out of bounds"))?;
+            let mut stream = tokio::time::timeout(timeout, tokio::net::TcpStream::connect(addr))
+                .await
+                .map_err(|_| internal_err!("timed out connecting to {addr}"))??;
+            stream.set_nodelay(true)?;
+            stream
+                .write_u8(ReaderConnection::Subscription as u8)
+                .await?;
+            let mut transport: SubscriptionTransport = AsyncBincodeStream::from(stream).for_async();
+            transport.send(request).await?;
+            // The reader replies once the subscription has started, or with why it couldn't be
+            match tokio::time::timeout(timeout, transport.next()).await {
+                Ok(Some(reply)) => reply??,
+                Ok(None) => return Err(internal_err!("subscription connection closed")),
+                Err(_) => return Err(internal_err!("timed out starting subscription")),
+            };
+            Ok(transport)
+        };
+        let transport = connect
+            .await
+            .map_err(|e| view_err(node, rpc_err_no_downcast("View::subscribe", e)))?;
+
+        Ok(transport
+            .map(|reply| reply?)
+            .chain(futures_util::stream::once(future::ready(Err(
+                internal_err!("subscription connection closed"),
+            ))))
+            .scan(false, |failed, reply| {
+                // Stop after the first error
+                let done = *failed;
+                *failed = reply.is_err();
+                future::ready((!done).then_some(reply))
+            })
+            .map_err(move |e| view_err(node, e))
+            .boxed())
+    }
+
     // TODO(andrew): consolidate RYW and normal reads into cohesive API once API design is settled.
     // RYW functionality currently added as duplicate methods so as not to disrupt current
     // reader usage until RYW is fully adopted
@@ -1423,6 +1608,73 @@ impl View {
     }
 }
 
+/// A subscription to the changes made to the rows of a [`View`] for a set of keys, created with
+/// [`View::subscribe`].
+///
+/// Each shard of the view that holds any of the subscribed keys streams changes over its own
+/// connection to the shard's reader, which sends each batch of changes to the subscribed rows as
+/// soon as it's applied. Use [`ViewSubscription::next`] to wait for the next batch, or
+/// [`ViewSubscription::into_stream`] to consume the changes as a stream.
+pub struct ViewSubscription {
+    view: View,
+    shards: SelectAll<BoxStream<'static, ReadySetResult<ViewDeltas>>>,
+}
+
+impl fmt::Debug for ViewSubscription {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ViewSubscription")
+            .field("view", &self.view)
+            .field("shards", &self.shards.len())
+            .finish()
+    }
+}
+
+impl ViewSubscription {
+    /// Get the view that this subscription is to
+    pub fn view(&self) -> &View {
+        &self.view
+    }
+
+    /// Wait for the next batch of changes made to the subscribed rows.
+    ///
+    /// If the subscription falls too far behind, or changes for the subscribed keys have been
+    /// dropped due to eviction, this returns [`ReadySetError::SubscriptionLagged`], in which case
+    /// the keys should be read again before starting a new subscription. No more changes are
+    /// returned after an error.
+    ///
+    /// This is cancel-safe: if the returned future is dropped before it completes, no changes are
+    /// lost, and will be returned by the next call instead.
+    pub async fn next(&mut self) -> ReadySetResult<Vec<ViewDelta>> {
+        loop {
+            match self.shards.next().await {
+                Some(Ok(ViewDeltas { deltas, .. })) if deltas.is_empty() => {}
+                Some(Ok(ViewDeltas { deltas, .. })) => return Ok(deltas),
+                Some(Err(e)) => {
+                    // Don't return changes from the other shards, which may have happened after
+                    // changes that this shard missed
+                    self.shards = SelectAll::new();
+                    return Err(e);
+                }
+                // Not subscribed to any keys, or the subscription has failed, so there will never
+                // be any more changes
+                None => return future::pending().await,
+            }
+        }
+    }
+
+    /// Convert this subscription into a stream of batches of changes made to the subscribed rows,
+    /// which ends after the first error
+    pub fn into_stream(self) -> impl futures_util::Stream<Item = ReadySetResult<Vec<ViewDelta>>> {
+        futures_util::stream::unfold(Some(self), |subscription| async move {
+            let mut subscription = subscription?;
+            match subscription.next().await {
+                Ok(deltas) => Some((Ok(deltas), Some(subscription))),
+                Err(e) => Some((Err(e), None)),
+            }
+        })
+    }
+}
+
 #[derive(Debug, Default)]
 #[doc(hidden)]
 #[repr(transparent)]
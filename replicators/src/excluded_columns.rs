@@ -0,0 +1,362 @@
+//! Column-level replication exclusions, which keep the values of sensitive columns of a table, for
+//! example `users.password_hash`, out of ReadySet.
+//!
+//! Excluded columns remain part of the schema of the table's base node, but their values never
+//! leave the upstream database: while snapshotting, `NULL` is selected in their place in the
+//! queries that read the table, and while replicating their values are replaced with `NULL` in the
+//! table's replication events. Queries that reference an excluded column are rejected by the
+//! server, so that they're proxied to the upstream database instead.
+//!
+//! Column names are compared case-insensitively for MySQL, and exactly for PostgreSQL. Snapshotting
+//! or replicating a table fails if any of its excluded columns doesn't exist, rather than
+//! replicating the values of a column whose exclusion was misspelled.
+//!
+//! The excluded columns are recorded in the authority when the replicator starts. If they've
+//! changed since it last started, the tables whose exclusions changed are dropped, along with the
+//! queries cached on them, and snapshotted again.
+
+use std::collections::HashSet;
+use std::convert::Infallible;
+
+use database_utils::ExcludedColumn;
+use itertools::Itertools;
+use nom_sql::{ColumnConstraint, CreateTableStatement, Relation, TableKey};
+use readyset::consensus::{Authority, AuthorityControl};
+use readyset::recipe::changelist::{Change, ChangeList};
+use readyset::{Modification, ReadySetHandle, TableOperation};
+use readyset_data::{DfValue, Dialect};
+use readyset_errors::{internal_err, ReadySetError, ReadySetResult};
+use tracing::info;
+
+use crate::snapshot::CheckpointStore;
+
+/// The path in the authority that the columns which were excluded from replication when the
+/// replicator last started are stored at
+const EXCLUDED_COLUMNS_PATH: &str = "/replication_excluded_columns";
+
+/// Drop the tables whose excluded columns have changed since the replicator last started, along
+/// with the queries cached on them, and mark a snapshot as being in progress so that the tables are
+/// snapshotted again. Otherwise, the tables would keep the values of columns that are newly
+/// excluded, and `NULL` in place of the values of columns that are no longer excluded.
+pub(crate) async fn drop_tables_with_changed_exclusions(
+    noria: &mut ReadySetHandle,
+    authority: &Authority,
+    checkpoints: &CheckpointStore,
+    excluded: &[ExcludedColumn],
+    dialect: Dialect,
+) -> ReadySetResult<()> {
+    let previous = authority
+        .try_read::<Vec<ExcludedColumn>>(EXCLUDED_COLUMNS_PATH)
+        .await
+        .map_err(|e| internal_err!("Error reading excluded columns: {e}"))?
+        .unwrap_or_default();
+    let previous = previous.iter().collect::<HashSet<_>>();
+    let current = excluded.iter().collect::<HashSet<_>>();
+    if previous == current {
+        return Ok(());
+    }
+
+    let changed = previous.symmetric_difference(&current).collect::<Vec<_>>();
+    let tables = noria
+        .replication_offsets()
+        .await?
+        .tables
+        .into_keys()
+        .filter(|table| {
+            changed.iter().any(|excluded| {
+                excluded.matches_table(table.schema.as_deref(), table.name.as_str())
+            })
+        })
+        .collect::<Vec<_>>();
+    if !tables.is_empty() {
+        info!(
+            tables = %tables.iter().join(", "),
+            "Excluded columns changed, dropping tables to snapshot them again"
+        );
+        // Start the snapshot first, so that the tables are still snapshotted again if we're
+        // interrupted after dropping them
+        checkpoints.start().await?;
+        noria
+            .extend_recipe_no_leader_ready(ChangeList::from_changes(
+                tables
+                    .into_iter()
+                    .map(|name| Change::Drop {
+                        name,
+                        if_exists: true,
+                    })
+                    .collect::<Vec<_>>(),
+                dialect,
+            ))
+            .await?;
+    }
+
+    let res = authority
+        .read_modify_write(EXCLUDED_COLUMNS_PATH, |_: Option<Vec<ExcludedColumn>>| {
+            Ok::<_, Infallible>(excluded.to_vec())
+        })
+        .await
+        .map_err(|e| internal_err!("Error writing excluded columns: {e}"))?;
+    match res {
+        Ok(_) => Ok(()),
+        Err(never) => match never {},
+    }
+}
+
+/// Returns an error if any of the `excluded` columns of `table` is one of its `key` columns, since
+/// rows couldn't be identified by their key without their values
+pub(crate) fn check_key_columns<'a>(
+    table: &Relation,
+    excluded: &[&str],
+    key: impl IntoIterator<Item = &'a str>,
+) -> ReadySetResult<()> {
+    match key.into_iter().find(|column| excluded.contains(column)) {
+        Some(column) => Err(ReadySetError::ReplicationFailed(format!(
+            "Column {column} of table {table} is part of its primary key, and can't be excluded \
+             from replication"
+        ))),
+        None => Ok(()),
+    }
+}
+
+/// Find the names of the `excluded` columns of `table` among its `columns`. Column names are
+/// compared case-insensitively if `case_insensitive` is true, as they are in MySQL.
+///
+/// Returns an error if any of the `excluded` columns isn't in the table, since a misspelled
+/// exclusion would otherwise replicate the values of the column it was meant to exclude
+pub(crate) fn resolve_columns<'a, S: AsRef<str>>(
+    table: &Relation,
+    excluded: &[&str],
+    columns: &'a [S],
+    case_insensitive: bool,
+) -> ReadySetResult<Vec<&'a str>> {
+    excluded
+        .iter()
+        .map(|excluded| {
+            columns
+                .iter()
+                .map(AsRef::as_ref)
+                .find(|column| {
+                    if case_insensitive {
+                        column.to_lowercase() == excluded.to_lowercase()
+                    } else {
+                        column == excluded
+                    }
+                })
+                .ok_or_else(|| {
+                    ReadySetError::ReplicationFailed(format!(
+                        "Column {excluded} of table {table} is excluded from replication, but \
+                         the table has no such column"
+                    ))
+                })
+        })
+        .collect()
+}
+
+/// Build the list of expressions to select in order to read the `columns` of a table with the
+/// `excluded` columns replaced by `NULL`, quoting column names with `quote`.
+///
+/// `null` is called with the position of each excluded column to get the expression to select
+/// in its place.
+pub(crate) fn select_list<S: AsRef<str>>(
+    columns: &[S],
+    excluded: &[&str],
+    quote: char,
+    null: impl Fn(usize) -> String,
+) -> String {
+    columns
+        .iter()
+        .enumerate()
+        .map(|(i, column)| {
+            let column = column.as_ref();
+            if excluded.contains(&column) {
+                null(i)
+            } else {
+                let escaped = column.replace(quote, &format!("{quote}{quote}"));
+                format!("{quote}{escaped}{quote}")
+            }
+        })
+        .join(", ")
+}
+
+/// The positions of the excluded columns of a particular table
+#[derive(Debug, Clone)]
+pub(crate) struct ExcludedColumnIndices(Vec<usize>);
+
+impl ExcludedColumnIndices {
+    /// Find the positions of the `excluded` columns in the table with the given `schema`, comparing
+    /// column names case-insensitively if `case_insensitive` is true. Returns an error if any of
+    /// the `excluded` columns isn't in the table.
+    pub(crate) fn new(
+        schema: &CreateTableStatement,
+        excluded: &[&str],
+        case_insensitive: bool,
+    ) -> ReadySetResult<Self> {
+        let columns = schema
+            .fields
+            .iter()
+            .map(|field| field.column.name.as_str())
+            .collect::<Vec<_>>();
+        let excluded = resolve_columns(&schema.table, excluded, &columns, case_insensitive)?;
+
+        let key = schema
+            .fields
+            .iter()
+            .filter(|field| field.constraints.contains(&ColumnConstraint::PrimaryKey))
+            .map(|field| &field.column)
+            .chain(
+                schema
+                    .keys
+                    .iter()
+                    .flatten()
+                    .filter_map(|key| match key {
+                        TableKey::PrimaryKey { columns, .. } => Some(columns),
+                        _ => None,
+                    })
+                    .flatten(),
+            )
+            .map(|column| column.name.as_str());
+        check_key_columns(&schema.table, &excluded, key)?;
+
+        Ok(Self(
+            columns
+                .iter()
+                .positions(|column| excluded.contains(column))
+                .collect(),
+        ))
+    }
+
+    /// Replace the values of the excluded columns in the rows and updates of `actions` with `NULL`
+    pub(crate) fn clear_actions(&self, actions: &mut [TableOperation]) {
+        if self.0.is_empty() {
+            return;
+        }
+
+        let clear_row = |row: &mut Vec<DfValue>| {
+            for i in &self.0 {
+                if let Some(value) = row.get_mut(*i) {
+                    *value = DfValue::None;
+                }
+            }
+        };
+        let clear_update = |update: &mut Vec<Modification>| {
+            for i in &self.0 {
+                if let Some(modification @ Modification::Set(_)) = update.get_mut(*i) {
+                    *modification = Modification::Set(DfValue::None);
+                }
+            }
+        };
+
+        for action in actions {
+            match action {
+                TableOperation::Insert(row) | TableOperation::DeleteRow { row } => clear_row(row),
+                TableOperation::InsertOrUpdate { row, update } => {
+                    clear_row(row);
+                    clear_update(update);
+                }
+                TableOperation::Update { update, .. } => clear_update(update),
+                _ => {}
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use nom_sql::{parse_create_table, Dialect};
+
+    use super::*;
+
+    #[test]
+    fn clear_actions() {
+        let schema = parse_create_table(
+            Dialect::MySQL,
+            "CREATE TABLE users (id INT PRIMARY KEY, name TEXT, password_hash TEXT)",
+        )
+        .unwrap();
+        let excluded = ExcludedColumnIndices::new(&schema, &["password_hash"], false).unwrap();
+
+        let mut actions = vec![
+            TableOperation::Insert(vec![1.into(), "a".into(), "x".into()]),
+            TableOperation::DeleteRow {
+                row: vec![2.into(), "b".into(), "y".into()],
+            },
+            TableOperation::Update {
+                key: vec![3.into()],
+                update: vec![Modification::None, "c".into(), "z".into()],
+            },
+            TableOperation::DeleteByKey {
+                key: vec![4.into()],
+            },
+        ];
+        excluded.clear_actions(&mut actions);
+        assert_eq!(
+            actions,
+            vec![
+                TableOperation::Insert(vec![1.into(), "a".into(), DfValue::None]),
+                TableOperation::DeleteRow {
+                    row: vec![2.into(), "b".into(), DfValue::None],
+                },
+                TableOperation::Update {
+                    key: vec![3.into()],
+                    update: vec![Modification::None, "c".into(), DfValue::None.into()],
+                },
+                TableOperation::DeleteByKey {
+                    key: vec![4.into()],
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn key_columns_cant_be_excluded() {
+        let schema = parse_create_table(
+            Dialect::MySQL,
+            "CREATE TABLE users (email TEXT, name TEXT, PRIMARY KEY (email))",
+        )
+        .unwrap();
+        ExcludedColumnIndices::new(&schema, &["email"], false).unwrap_err();
+        ExcludedColumnIndices::new(&schema, &["name"], false).unwrap();
+    }
+
+    #[test]
+    fn unknown_columns_cant_be_excluded() {
+        let schema = parse_create_table(
+            Dialect::MySQL,
+            "CREATE TABLE users (id INT PRIMARY KEY, name TEXT, ssn TEXT)",
+        )
+        .unwrap();
+        ExcludedColumnIndices::new(&schema, &["social_security_number"], true).unwrap_err();
+        ExcludedColumnIndices::new(&schema, &["SSN"], false).unwrap_err();
+        ExcludedColumnIndices::new(&schema, &["ID"], true).unwrap_err();
+
+        let mut actions = vec![TableOperation::Insert(vec![
+            1.into(),
+            "a".into(),
+            "x".into(),
+        ])];
+        ExcludedColumnIndices::new(&schema, &["SSN"], true)
+            .unwrap()
+            .clear_actions(&mut actions);
+        assert_eq!(
+            actions,
+            vec![TableOperation::Insert(vec![
+                1.into(),
+                "a".into(),
+                DfValue::None
+            ])]
+        );
+    }
+
+    #[test]
+    fn build_select_list() {
+        assert_eq!(
+            select_list(
+                &["id", "password_hash", "na\"me"],
+                &["password_hash"],
+                '"',
+                |_| "NULL".to_owned()
+            ),
+            "\"id\", NULL, \"na\"\"me\""
+        );
+    }
+}
@@ -1,14 +1,18 @@
 #![feature(
     never_type,
+    exhaustive_patterns,
     hash_raw_entry,
     drain_filter,
     string_remove_matches,
     iter_intersperse
 )]
 pub mod db_util;
+pub(crate) mod excluded_columns;
 pub(crate) mod mysql_connector;
 pub(crate) mod noria_adapter;
 pub(crate) mod postgres_connector;
+pub(crate) mod row_filter;
+pub(crate) mod snapshot;
 pub(crate) mod table_filter;
 
 use std::time::Duration;
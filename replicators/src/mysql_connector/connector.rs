@@ -1,4 +1,5 @@
 use std::convert::{TryFrom, TryInto};
+use std::time::{Duration, Instant};
 
 use async_trait::async_trait;
 use binlog::consts::{BinlogChecksumAlg, EventType};
@@ -18,11 +19,21 @@ use readyset::{ReadySetError, ReadySetResult};
 use readyset_data::{DfValue, Dialect};
 use tracing::warn;
 
+use super::gtid::GtidPosition;
 use super::BinlogPosition;
 use crate::noria_adapter::{Connector, ReplicationAction};
 
 const CHECKSUM_QUERY: &str = "SET @master_binlog_checksum='CRC32'";
 const DEFAULT_SERVER_ID: u32 = u32::MAX - 55;
+/// The maximum number of row changes buffered for a single transaction, past which the rest of the
+/// transaction is streamed rather than held in memory until it commits
+const MAX_TRANSACTION_BUFFER_ROWS: usize = 100_000;
+/// How often the position in the binlog is reported even if none of the replicated tables have
+/// changed, so that readers can tell that tables which aren't written to are caught up
+const LOG_POSITION_INTERVAL: Duration = Duration::from_secs(1);
+/// Ask the server to send heartbeat events whenever it has nothing else to send for this long (in
+/// nanoseconds), so that we report our position regularly even when the binlog is quiet
+const HEARTBEAT_QUERY: &str = "SET @master_heartbeat_period = 1000000000";
 
 /// A connector that connects to a MySQL server and starts reading binlogs from a given position.
 ///
@@ -37,7 +48,28 @@ const DEFAULT_SERVER_ID: u32 = u32::MAX - 55;
 /// * `REPLICATION SLAVE` - to be able to connect and read the binlog
 /// * `REPLICATION CLIENT` - to use SHOW MASTER STATUS, SHOW SLAVE STATUS, and SHOW BINARY LOGS;
 ///
-/// The connector must also be assigned a unique `server_id` value
+/// The connector must also be assigned a unique `server_id` value.
+///
+/// If the position the connector starts at is identified by a set of GTIDs rather than a binlog
+/// file and position, the binlog is requested by GTID, and the positions of all subsequent events
+/// are identified by GTIDs as well. Such positions remain valid after a replica is promoted to
+/// primary, so replication can continue against the new primary.
+///
+/// Row events between the `BEGIN` of a transaction and its commit are buffered, and returned as a
+/// single [`ReplicationAction::TransactionAction`] once the transaction commits, so that all the
+/// changes made by the transaction are written together. Transactions which change more than
+/// [`MAX_TRANSACTION_BUFFER_ROWS`] rows are only buffered up to that point, after which the
+/// buffered changes are returned and the rest of the transaction is streamed as it is read; each
+/// time that happens a warning is logged and [`recorded::REPLICATOR_TRANSACTION_SPLIT`] is
+/// incremented.
+///
+/// Writing a transaction's changes together doesn't make them visible atomically, since the
+/// changes to each table still propagate through the dataflow independently.
+///
+/// Between transactions, the position in the binlog is reported with a
+/// [`ReplicationAction::LogPosition`] at least every [`LOG_POSITION_INTERVAL`] (as long as the
+/// server sends us events or heartbeats), so that the offsets of tables which aren't written to
+/// keep up with the binlog.
 pub(crate) struct MySqlBinlogConnector {
     /// This is the underlying (regular) MySQL connection
     connection: mysql::Conn,
@@ -48,9 +80,19 @@ pub(crate) struct MySqlBinlogConnector {
     server_id: Option<u32>,
     /// If we just want to continue reading the binlog from a previous point
     next_position: BinlogPosition,
+    /// The position in the binlog in terms of GTIDs, if the binlog is being read by GTID
+    gtid_position: Option<GtidPosition>,
     /// The GTID of the current transaction. Table modification events will have
     /// the current GTID attached if enabled in mysql.
     current_gtid: Option<u64>,
+    /// The changes made to each table by the transaction being read, if a `BEGIN` of a
+    /// transaction has been read and its commit hasn't been yet
+    transaction: Option<Vec<(Relation, Vec<readyset::TableOperation>)>>,
+    /// When the position in the binlog was last reported with a [`ReplicationAction::LogPosition`]
+    position_reported: Instant,
+    /// Set if the position in the binlog is due to be reported once the action that was returned
+    /// instead has been handled
+    report_position: bool,
 }
 
 impl PartialOrd for BinlogPosition {
@@ -84,36 +126,9 @@ impl PartialOrd for BinlogPosition {
 impl TryFrom<&BinlogPosition> for ReplicationOffset {
     type Error = ReadySetError;
 
-    /// `ReplicationOffset` is a filename and a u128 offset
-    /// We use the binlog basefile name as the filename, and we use the binlog suffix len for
-    /// the top 5 bits, which can be as big as 31 digits in theory, but we only allow up to 17
-    /// decimal digits, which is more than enough for the binlog spec. This is required to be
-    /// able to properly format the integer back to string, including any leading zeroes.
-    /// The following 59 bits are used for the numerical value of the suffix, finally the last
-    /// 64 bits of the offset are the actual binlog offset.
+    /// See [`ReplicationOffset::from_binlog_position`]
     fn try_from(value: &BinlogPosition) -> Result<Self, Self::Error> {
-        let (basename, suffix) = value.binlog_file.rsplit_once('.').ok_or_else(|| {
-            ReadySetError::ReplicationFailed(format!("Invalid binlog name {}", value.binlog_file))
-        })?;
-
-        let suffix_len = suffix.len() as u128;
-
-        if suffix_len > 17 {
-            // 17 digit decimal number is the most we can fit into 59 bits
-            return Err(ReadySetError::ReplicationFailed(format!(
-                "Invalid binlog suffix {}",
-                value.binlog_file
-            )));
-        }
-
-        let suffix = suffix.parse::<u128>().map_err(|_| {
-            ReadySetError::ReplicationFailed(format!("Invalid binlog suffix {}", value.binlog_file))
-        })?;
-
-        Ok(ReplicationOffset {
-            offset: (suffix_len << 123) + (suffix << 64) + (value.position as u128),
-            replication_log_name: basename.to_string(),
-        })
+        ReplicationOffset::from_binlog_position(&value.binlog_file, value.position)
     }
 }
 
@@ -160,6 +175,7 @@ impl MySqlBinlogConnector {
     /// but others use CRC32 🤷‍♂️
     async fn register_as_replica(&mut self) -> mysql::Result<()> {
         self.connection.query_drop(CHECKSUM_QUERY).await?;
+        self.connection.query_drop(HEARTBEAT_QUERY).await?;
 
         let cmd = mysql_common::packets::ComRegisterSlave::new(self.server_id());
         self.connection.write_command(&cmd).await?;
@@ -170,15 +186,116 @@ impl MySqlBinlogConnector {
 
     /// After we have registered as a replica, we can request the binlog
     async fn request_binlog(&mut self) -> mysql::Result<()> {
-        let cmd = mysql_common::packets::ComBinlogDump::new(self.server_id())
-            .with_pos(self.next_position.position)
-            .with_filename(self.next_position.binlog_file.as_bytes());
+        match &self.gtid_position {
+            Some(gtid_position) => {
+                // Request all the transactions that aren't in the executed set, from whichever
+                // binlog file the server finds the first of them in
+                let cmd = mysql_common::packets::ComBinlogDumpGtid::new(self.server_id())
+                    .with_flags(mysql_common::packets::BinlogDumpFlags::BINLOG_THROUGH_GTID)
+                    .with_sids(gtid_position.executed().to_sids());
+                self.connection.write_command(&cmd).await?;
+            }
+            None => {
+                let cmd = mysql_common::packets::ComBinlogDump::new(self.server_id())
+                    .with_pos(self.next_position.position)
+                    .with_filename(self.next_position.binlog_file.as_bytes());
+                self.connection.write_command(&cmd).await?;
+            }
+        }
 
-        self.connection.write_command(&cmd).await?;
         self.connection.read_packet().await?;
         Ok(())
     }
 
+    /// The offset of the current position in the binlog
+    fn current_offset(&self) -> ReadySetResult<ReplicationOffset> {
+        match &self.gtid_position {
+            Some(gtid_position) => gtid_position.try_into(),
+            None => (&self.next_position).try_into(),
+        }
+    }
+
+    /// Record that an event resulting in a replication action was read, which only advances the
+    /// position when reading by GTID (otherwise, every event has its own position already)
+    fn add_event(&mut self) {
+        if let Some(gtid_position) = &mut self.gtid_position {
+            gtid_position.add_event();
+        }
+    }
+
+    /// Returns the action for the changes made to `table` by a row event, or buffers the changes
+    /// and returns `None` if the event is part of a transaction that hasn't committed yet
+    fn table_action(
+        &mut self,
+        table: Relation,
+        actions: Vec<readyset::TableOperation>,
+    ) -> Option<ReplicationAction> {
+        let tables = match &mut self.transaction {
+            Some(tables) => tables,
+            None => {
+                self.add_event();
+                return Some(ReplicationAction::TableAction {
+                    table,
+                    actions,
+                    txid: self.current_gtid,
+                });
+            }
+        };
+
+        match tables.iter_mut().find(|(t, _)| *t == table) {
+            Some((_, buffered)) => buffered.extend(actions),
+            None => tables.push((table, actions)),
+        }
+        let buffered_rows = tables
+            .iter()
+            .map(|(_, actions)| actions.len())
+            .sum::<usize>();
+        if buffered_rows <= MAX_TRANSACTION_BUFFER_ROWS {
+            return None;
+        }
+
+        // Rather than holding the whole of a very large transaction (such as a bulk load) in
+        // memory, write what we have so far and stream the rest of it. Reads may then see the
+        // transaction partially applied for much longer than usual, so make that visible.
+        warn!(
+            rows = buffered_rows,
+            txid = ?self.current_gtid,
+            "Transaction is too large to buffer, writing its changes as they are read. Its \
+             changes won't be applied to ReadySet all at once."
+        );
+        counter!(recorded::REPLICATOR_TRANSACTION_SPLIT, 1u64);
+        let tables = std::mem::take(tables);
+        self.transaction = None;
+        self.add_event();
+        Some(ReplicationAction::TransactionAction {
+            tables,
+            txid: self.current_gtid,
+        })
+    }
+
+    /// Record that the current transaction has committed, returning the action for all the
+    /// changes it made, if it made any
+    fn commit_transaction(&mut self) -> Option<ReplicationAction> {
+        if let Some(gtid_position) = &mut self.gtid_position {
+            gtid_position.finish_transaction();
+        }
+        let tables = self.transaction.take()?;
+        (!tables.is_empty()).then(|| ReplicationAction::TransactionAction {
+            tables,
+            txid: self.current_gtid,
+        })
+    }
+
+    /// Returns whether the position in the binlog is due to be reported, which it is once every
+    /// [`LOG_POSITION_INTERVAL`] while we're between transactions
+    fn position_report_due(&mut self) -> bool {
+        if self.transaction.is_some() || self.position_reported.elapsed() < LOG_POSITION_INTERVAL {
+            return false;
+        }
+        self.position_reported = Instant::now();
+        true
+    }
+
     /// Compute the checksum of the event and compare to the supplied checksum
     fn validate_event_checksum(event: &binlog::events::Event) -> bool {
         if let Ok(Some(BinlogChecksumAlg::BINLOG_CHECKSUM_ALG_CRC32)) =
@@ -194,18 +311,33 @@ impl MySqlBinlogConnector {
         true
     }
 
-    /// Connect to a given MySQL database and subscribe to the binlog
+    /// Connect to a given MySQL database and subscribe to the binlog, starting at `position`
     pub(crate) async fn connect<O: Into<mysql::Opts>>(
         mysql_opts: O,
-        next_position: BinlogPosition,
+        position: &ReplicationOffset,
         server_id: Option<u32>,
     ) -> ReadySetResult<Self> {
+        let (next_position, gtid_position) = if position.gtid_set().is_some() {
+            // The server starts with a rotate event telling us which binlog file it's reading
+            let next_position = BinlogPosition {
+                binlog_file: String::new(),
+                position: 0,
+            };
+            (next_position, Some(GtidPosition::try_from(position)?))
+        } else {
+            (position.into(), None)
+        };
+
         let mut connector = MySqlBinlogConnector {
             connection: mysql::Conn::new(mysql_opts).await?,
             reader: binlog::EventStreamReader::new(binlog::consts::BinlogVersion::Version4),
             server_id,
             next_position,
+            gtid_position,
             current_gtid: None,
+            transaction: None,
+            position_reported: Instant::now(),
+            report_position: false,
         };
 
         connector.register_as_replica().await?;
@@ -234,9 +366,14 @@ impl MySqlBinlogConnector {
     pub(crate) async fn next_action_inner(
         &mut self,
         until: Option<&ReplicationOffset>,
-    ) -> mysql::Result<(ReplicationAction, &BinlogPosition)> {
+    ) -> mysql::Result<ReplicationAction> {
         use mysql_common::binlog::events;
 
+        if self.report_position {
+            self.report_position = false;
+            return Ok(ReplicationAction::LogPosition);
+        }
+
         loop {
             let binlog_event = self.next_event().await?;
 
@@ -261,13 +398,29 @@ impl MySqlBinlogConnector {
                         position: u32::try_from(ev.position()).unwrap(),
                     };
 
-                    return Ok((ReplicationAction::LogPosition, &self.next_position));
+                    return Ok(ReplicationAction::LogPosition);
                 }
 
                 EventType::QUERY_EVENT => {
                     // Written when an updating statement is done.
                     let ev: events::QueryEvent = binlog_event.read_event()?;
 
+                    match ev.query().trim() {
+                        // Row events are buffered until the transaction commits
+                        "BEGIN" => {
+                            self.transaction = Some(Vec::new());
+                            continue;
+                        }
+                        // Transactions on tables of storage engines that don't support XA commit
+                        // with a `COMMIT` query instead of an XID event. A `ROLLBACK` is only
+                        // written if such tables were modified, and those changes still persist.
+                        "COMMIT" | "ROLLBACK" => match self.commit_transaction() {
+                            Some(action) => return Ok(action),
+                            None => continue,
+                        },
+                        _ => {}
+                    }
+
                     let schema = match ev
                         .status_vars()
                         .get_status_var(binlog::consts::StatusVarKey::UpdatedDbNames)
@@ -282,8 +435,6 @@ impl MySqlBinlogConnector {
                             names.first().unwrap().as_str().to_string()
                         }
                         // If the query does not affect the schema, just keep going
-                        // TODO: Transactions begin with the `BEGIN` queries, but we do not
-                        // currently support those
                         _ => continue,
                     };
 
@@ -296,10 +447,8 @@ impl MySqlBinlogConnector {
                         }
                     };
 
-                    return Ok((
-                        ReplicationAction::DdlChange { schema, changes },
-                        &self.next_position,
-                    ));
+                    self.add_event();
+                    return Ok(ReplicationAction::DdlChange { schema, changes });
                 }
 
                 EventType::TABLE_MAP_EVENT => {
@@ -338,17 +487,13 @@ impl MySqlBinlogConnector {
                         ));
                     }
 
-                    return Ok((
-                        ReplicationAction::TableAction {
-                            table: Relation {
-                                schema: Some(tme.database_name().into()),
-                                name: tme.table_name().into(),
-                            },
-                            actions: inserted_rows,
-                            txid: self.current_gtid,
-                        },
-                        &self.next_position,
-                    ));
+                    let table = Relation {
+                        schema: Some(tme.database_name().into()),
+                        name: tme.table_name().into(),
+                    };
+                    if let Some(action) = self.table_action(table, inserted_rows) {
+                        return Ok(action);
+                    }
                 }
 
                 EventType::UPDATE_ROWS_EVENT => {
@@ -388,17 +533,13 @@ impl MySqlBinlogConnector {
                         ));
                     }
 
-                    return Ok((
-                        ReplicationAction::TableAction {
-                            table: Relation {
-                                schema: Some(tme.database_name().into()),
-                                name: tme.table_name().into(),
-                            },
-                            actions: updated_rows,
-                            txid: self.current_gtid,
-                        },
-                        &self.next_position,
-                    ));
+                    let table = Relation {
+                        schema: Some(tme.database_name().into()),
+                        name: tme.table_name().into(),
+                    };
+                    if let Some(action) = self.table_action(table, updated_rows) {
+                        return Ok(action);
+                    }
                 }
 
                 EventType::DELETE_ROWS_EVENT => {
@@ -423,17 +564,13 @@ impl MySqlBinlogConnector {
                         });
                     }
 
-                    return Ok((
-                        ReplicationAction::TableAction {
-                            table: Relation {
-                                schema: Some(tme.database_name().into()),
-                                name: tme.table_name().into(),
-                            },
-                            actions: deleted_rows,
-                            txid: self.current_gtid,
-                        },
-                        &self.next_position,
-                    ));
+                    let table = Relation {
+                        schema: Some(tme.database_name().into()),
+                        name: tme.table_name().into(),
+                    };
+                    if let Some(action) = self.table_action(table, deleted_rows) {
+                        return Ok(action);
+                    }
                 }
 
                 EventType::WRITE_ROWS_EVENT_V1 => unimplemented!(), /* The V1 event numbers are */
@@ -456,24 +593,42 @@ impl MySqlBinlogConnector {
                     // See also https://dev.mysql.com/doc/refman/8.0/en/replication-mode-change-online-concepts.html
                     let ev: events::GtidEvent = binlog_event.read_event()?;
                     self.current_gtid = Some(ev.gno());
+                    if let Some(gtid_position) = &mut self.gtid_position {
+                        gtid_position.start_transaction(ev.sid(), ev.gno());
+                    }
+                }
+
+                EventType::XID_EVENT => {
+                    // Generated for a commit of a transaction that modifies one or more tables of
+                    // an XA-capable storage engine, such as InnoDB
+                    // Tables the transaction didn't modify are caught up to its commit as well
+                    let action = self.commit_transaction();
+                    let report_position = self.position_report_due();
+                    match action {
+                        Some(action) => {
+                            self.report_position = report_position;
+                            return Ok(action);
+                        }
+                        None if report_position => return Ok(ReplicationAction::LogPosition),
+                        None => {}
+                    }
+                }
+
+                EventType::HEARTBEAT_EVENT => {
+                    // Sent by the server when there are no new events, so every table is caught up
+                    if self.position_report_due() {
+                        return Ok(ReplicationAction::LogPosition);
+                    }
                 }
 
                 /*
 
                 EventType::ANONYMOUS_GTID_EVENT => {}
 
-                EventType::XID_EVENT => {
-                    // Generated for a commit of a transaction that modifies one or more tables of an XA-capable
-                    // storage engine. Normal transactions are implemented by sending a QUERY_EVENT containing a
-                    // BEGIN statement and a QUERY_EVENT containing a COMMIT statement
-                    // (or a ROLLBACK statement if the transaction is rolled back).
-                }
-
                 EventType::START_EVENT_V3 // Old version of FORMAT_DESCRIPTION_EVENT
                 | EventType::FORMAT_DESCRIPTION_EVENT // A descriptor event that is written to the beginning of each binary log file. This event is used as of MySQL 5.0; it supersedes START_EVENT_V3.
                 | EventType::STOP_EVENT // Written when mysqld stops
-                | EventType::INCIDENT_EVENT // The event is used to inform the slave that something out of the ordinary happened on the master that might cause the database to be in an inconsistent state.
-                | EventType::HEARTBEAT_EVENT => {} // The event is originated by master's dump thread and sent straight to slave without being logged. Slave itself does not store it in relay log but rather uses a data for immediate checks and throws away the event.
+                | EventType::INCIDENT_EVENT => {} // The event is used to inform the slave that something out of the ordinary happened on the master that might cause the database to be in an inconsistent state.
 
                 EventType::UNKNOWN_EVENT | EventType::SLAVE_EVENT => {} // Ignored events
 
@@ -508,11 +663,12 @@ impl MySqlBinlogConnector {
             }
 
             // We didn't get an actionable event, but we still need to check that we haven't reached
-            // the until limit
-            if let Some(limit) = until {
-                let limit = BinlogPosition::try_from(limit).expect("Valid binlog limit");
-                if self.next_position >= limit {
-                    return Ok((ReplicationAction::LogPosition, &self.next_position));
+            // the until limit. Positions in the middle of a transaction are never returned, since
+            // none of the transaction's changes have been applied yet.
+            if let (Some(limit), None) = (until, &self.transaction) {
+                let position = self.current_offset().map_err(|e| e.to_string())?;
+                if position >= *limit {
+                    return Ok(ReplicationAction::LogPosition);
                 }
             }
         }
@@ -620,7 +776,7 @@ impl Connector for MySqlBinlogConnector {
         _: &ReplicationOffset,
         until: Option<&ReplicationOffset>,
     ) -> ReadySetResult<(ReplicationAction, ReplicationOffset)> {
-        let (action, pos) = self.next_action_inner(until).await?;
-        Ok((action, pos.try_into()?))
+        let action = self.next_action_inner(until).await?;
+        Ok((action, self.current_offset()?))
     }
 }
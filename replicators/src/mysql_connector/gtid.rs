@@ -0,0 +1,317 @@
+//! Positions in a MySQL binlog that's read by GTID rather than by binlog file and position.
+//!
+//! Every transaction written to the binlog of a server with GTIDs enabled is assigned a GTID,
+//! which consists of the UUID of the server the transaction originated on (the SID) and a
+//! transaction number (the GNO). Unlike binlog file names and positions, GTIDs are preserved when
+//! transactions are replicated, so the set of GTIDs executed as of some point in the binlog
+//! identifies the same point in the binlog of any server in the replica topology, including a
+//! replica that's been promoted to primary.
+//!
+//! A [`GtidPosition`] is stored as a [`ReplicationOffset`] with the executed GTID set in its
+//! [`replication_log_name`](ReplicationOffset::replication_log_name) (prefixed with
+//! [`GTID_SET_PREFIX`]), and an offset whose high 64 bits are the number of transactions in that
+//! set and whose low 64 bits are the number of events read so far in the following transaction.
+//! Since transactions are written to the binlog one at a time, the number of executed
+//! transactions increases with every transaction, which makes offsets read from different servers
+//! comparable.
+
+use std::collections::BTreeMap;
+use std::convert::TryFrom;
+use std::fmt::{self, Display};
+use std::ops::Range;
+use std::str::FromStr;
+
+use itertools::Itertools;
+use mysql_common::packets::{GnoInterval, Sid};
+use readyset::replication::{ReplicationOffset, GTID_SET_PREFIX};
+use readyset::{ReadySetError, ReadySetResult};
+
+/// The UUID of a MySQL server, which identifies the source of a transaction in its GTID
+type ServerUuid = [u8; 16];
+
+/// A set of MySQL GTIDs, such as the GTIDs of all the transactions executed by a server
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub(crate) struct GtidSet {
+    /// The transaction numbers in the set for each server UUID, as sorted, non-overlapping,
+    /// non-adjacent ranges
+    sids: BTreeMap<ServerUuid, Vec<Range<u64>>>,
+}
+
+impl GtidSet {
+    /// Add the GTID with the server UUID `sid` and transaction number `gno` to the set
+    pub(crate) fn insert(&mut self, sid: ServerUuid, gno: u64) {
+        let intervals = self.sids.entry(sid).or_default();
+        // The first interval which contains `gno`, or ends right before it, or is after it
+        let i = intervals.partition_point(|interval| interval.end < gno);
+        match intervals.get(i) {
+            Some(interval) if interval.contains(&gno) => {}
+            Some(interval) if interval.end == gno => {
+                intervals[i].end = gno + 1;
+                if intervals
+                    .get(i + 1)
+                    .map_or(false, |next| next.start == gno + 1)
+                {
+                    let next = intervals.remove(i + 1);
+                    intervals[i].end = next.end;
+                }
+            }
+            Some(interval) if interval.start == gno + 1 => intervals[i].start = gno,
+            _ => intervals.insert(i, gno..(gno + 1)),
+        }
+    }
+
+    /// Returns the number of GTIDs in the set
+    pub(crate) fn count(&self) -> u64 {
+        self.sids
+            .values()
+            .flatten()
+            .map(|interval| interval.end - interval.start)
+            .sum()
+    }
+
+    /// Returns the set in the form used to request the binlog starting after it
+    pub(crate) fn to_sids(&self) -> Vec<Sid<'static>> {
+        self.sids
+            .iter()
+            .map(|(uuid, intervals)| {
+                intervals.iter().fold(Sid::new(*uuid), |sid, interval| {
+                    sid.with_interval(GnoInterval::new(interval.start, interval.end))
+                })
+            })
+            .collect()
+    }
+}
+
+fn parse_error(s: &str) -> ReadySetError {
+    ReadySetError::ReplicationFailed(format!("Invalid GTID set {s}"))
+}
+
+/// Parses GTID sets in the format used by MySQL, for example
+/// `3e11fa47-71ca-11e1-9e33-c80aa9429562:1-5:11,4f22fb58-82db-22f2-af44-d91bba53a673:1`
+impl FromStr for GtidSet {
+    type Err = ReadySetError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut set = GtidSet::default();
+        for sid in s.split(',').map(str::trim).filter(|sid| !sid.is_empty()) {
+            let mut parts = sid.split(':');
+            let uuid = parts.next().ok_or_else(|| parse_error(s))?.replace('-', "");
+            let uuid = hex::decode(uuid)
+                .ok()
+                .and_then(|uuid| ServerUuid::try_from(uuid).ok())
+                .ok_or_else(|| parse_error(s))?;
+
+            let mut intervals = parts
+                .map(|interval| {
+                    let (start, end) = interval.split_once('-').unwrap_or((interval, interval));
+                    match (start.parse::<u64>(), end.parse::<u64>()) {
+                        (Ok(start), Ok(end)) if start <= end => Ok(start..(end + 1)),
+                        _ => Err(parse_error(s)),
+                    }
+                })
+                .collect::<ReadySetResult<Vec<_>>>()?;
+            intervals.sort_by_key(|interval| interval.start);
+
+            let merged = set.sids.entry(uuid).or_default();
+            for interval in intervals {
+                match merged.last_mut() {
+                    Some(last) if interval.start <= last.end => {
+                        last.end = last.end.max(interval.end)
+                    }
+                    _ => merged.push(interval),
+                }
+            }
+        }
+        Ok(set)
+    }
+}
+
+impl Display for GtidSet {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let sids = self.sids.iter().map(|(uuid, intervals)| {
+            let uuid = hex::encode(uuid);
+            let intervals = intervals.iter().map(|interval| {
+                if interval.end - interval.start == 1 {
+                    interval.start.to_string()
+                } else {
+                    format!("{}-{}", interval.start, interval.end - 1)
+                }
+            });
+            format!(
+                "{}-{}-{}-{}-{}:{}",
+                &uuid[0..8],
+                &uuid[8..12],
+                &uuid[12..16],
+                &uuid[16..20],
+                &uuid[20..32],
+                intervals.format(":")
+            )
+        });
+        write!(f, "{}", sids.format(","))
+    }
+}
+
+/// A position in a MySQL binlog that's read by GTID
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct GtidPosition {
+    /// The GTIDs of the transactions that have been completely read
+    executed: GtidSet,
+    /// The GTID of the transaction being read, if any
+    current: Option<(ServerUuid, u64)>,
+    /// The number of events that resulted in replication actions read so far in the
+    /// transaction following `executed`
+    events: u64,
+}
+
+impl GtidPosition {
+    /// The position right after all the transactions in `executed`
+    pub(crate) fn new(executed: GtidSet) -> Self {
+        GtidPosition {
+            executed,
+            current: None,
+            events: 0,
+        }
+    }
+
+    /// The GTIDs of the transactions that have been completely read
+    pub(crate) fn executed(&self) -> &GtidSet {
+        &self.executed
+    }
+
+    /// Record that a new transaction with the GTID `sid:gno` has started, which also means the
+    /// previous transaction has been completely read
+    pub(crate) fn start_transaction(&mut self, sid: ServerUuid, gno: u64) {
+        self.finish_transaction();
+        self.current = Some((sid, gno));
+    }
+
+    /// Record that the current transaction, if any, has been completely read
+    pub(crate) fn finish_transaction(&mut self) {
+        if let Some((sid, gno)) = self.current.take() {
+            self.executed.insert(sid, gno);
+        }
+        self.events = 0;
+    }
+
+    /// Record that an event resulting in a replication action was read in the current transaction
+    pub(crate) fn add_event(&mut self) {
+        self.events += 1;
+    }
+}
+
+impl TryFrom<&GtidPosition> for ReplicationOffset {
+    type Error = ReadySetError;
+
+    fn try_from(value: &GtidPosition) -> Result<Self, Self::Error> {
+        Ok(ReplicationOffset {
+            offset: ((value.executed.count() as u128) << 64) + value.events as u128,
+            replication_log_name: format!("{GTID_SET_PREFIX}{}", value.executed),
+        })
+    }
+}
+
+impl TryFrom<&ReplicationOffset> for GtidPosition {
+    type Error = ReadySetError;
+
+    /// If the offset is in the middle of a transaction, the position is at the start of that
+    /// transaction, but keeps track of the number of events in it that were already read.
+    /// Reading the binlog from the position reads those events again, but they'll have the same
+    /// offsets as before, so they can be skipped.
+    fn try_from(value: &ReplicationOffset) -> Result<Self, Self::Error> {
+        let gtid_set = value.gtid_set().ok_or_else(|| {
+            ReadySetError::ReplicationFailed(format!("{value} is not a GTID position"))
+        })?;
+        Ok(GtidPosition {
+            executed: gtid_set.parse()?,
+            current: None,
+            events: value.offset as u64,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const UUID1: &str = "3e11fa47-71ca-11e1-9e33-c80aa9429562";
+    const UUID2: &str = "4f22fb58-82db-22f2-af44-d91bba53a673";
+
+    fn uuid(s: &str) -> ServerUuid {
+        ServerUuid::try_from(hex::decode(s.replace('-', "")).unwrap()).unwrap()
+    }
+
+    #[test]
+    fn parse_and_display() {
+        let set: GtidSet = format!("{UUID2}:1-5:11,\n{UUID1}:3:1-2:7-9")
+            .parse()
+            .unwrap();
+        assert_eq!(set.count(), 13);
+        assert_eq!(set.to_string(), format!("{UUID1}:1-3:7-9,{UUID2}:1-5:11"));
+        assert_eq!(set.to_string().parse::<GtidSet>().unwrap(), set);
+
+        assert_eq!("".parse::<GtidSet>().unwrap(), GtidSet::default());
+        "not-a-uuid:1-5".parse::<GtidSet>().unwrap_err();
+        format!("{UUID1}:5-1").parse::<GtidSet>().unwrap_err();
+    }
+
+    #[test]
+    fn insert() {
+        let mut set: GtidSet = format!("{UUID1}:1-3:7").parse().unwrap();
+        set.insert(uuid(UUID1), 2);
+        set.insert(uuid(UUID1), 5);
+        assert_eq!(set.to_string(), format!("{UUID1}:1-3:5:7"));
+        set.insert(uuid(UUID1), 4);
+        set.insert(uuid(UUID1), 6);
+        assert_eq!(set.to_string(), format!("{UUID1}:1-7"));
+        set.insert(uuid(UUID2), 1);
+        assert_eq!(set.to_string(), format!("{UUID1}:1-7,{UUID2}:1"));
+        assert_eq!(set.count(), 8);
+    }
+
+    #[test]
+    fn offsets_increase_across_failover() {
+        // Read a couple of transactions from the original primary
+        let mut position = GtidPosition::new(format!("{UUID1}:1-5").parse().unwrap());
+        let start = ReplicationOffset::try_from(&position).unwrap();
+        position.start_transaction(uuid(UUID1), 6);
+        position.add_event();
+        let in_transaction = ReplicationOffset::try_from(&position).unwrap();
+        assert!(in_transaction > start);
+
+        // After failing over to a replica, resume reading from the middle of that transaction
+        let mut resumed = GtidPosition::try_from(&in_transaction).unwrap();
+        assert_eq!(resumed.executed().to_string(), format!("{UUID1}:1-5"));
+        resumed.start_transaction(uuid(UUID1), 6);
+        resumed.add_event();
+        assert_eq!(
+            ReplicationOffset::try_from(&resumed).unwrap(),
+            in_transaction
+        );
+
+        // Transactions originating on the new primary come after all the previous ones
+        resumed.start_transaction(uuid(UUID2), 1);
+        let after_failover = ReplicationOffset::try_from(&resumed).unwrap();
+        assert!(after_failover > in_transaction);
+        assert_eq!(
+            after_failover.gtid_set(),
+            Some(format!("{UUID1}:1-6").as_str())
+        );
+    }
+
+    #[test]
+    fn finished_transaction() {
+        let mut position = GtidPosition::new(format!("{UUID1}:1-5").parse().unwrap());
+        position.start_transaction(uuid(UUID1), 6);
+        let in_transaction = ReplicationOffset::try_from(&position).unwrap();
+
+        // Once a transaction commits its GTID is executed, without waiting for the next one
+        position.finish_transaction();
+        let committed = ReplicationOffset::try_from(&position).unwrap();
+        assert!(committed > in_transaction);
+        assert_eq!(committed.gtid_set(), Some(format!("{UUID1}:1-6").as_str()));
+
+        // Starting the next transaction doesn't change the executed set any further
+        position.start_transaction(uuid(UUID1), 7);
+        assert_eq!(ReplicationOffset::try_from(&position).unwrap(), committed);
+    }
+}
@@ -1,4 +1,5 @@
 mod connector;
+mod gtid;
 mod snapshot;
 
 pub(crate) use connector::MySqlBinlogConnector;
@@ -2,7 +2,6 @@ use std::convert::{TryFrom, TryInto};
 use std::error::Error;
 use std::fmt::{self, Display};
 use std::future;
-use std::time::Instant;
 
 use futures::future::TryFutureExt;
 use futures::stream::FuturesUnordered;
@@ -16,20 +15,23 @@ use nom_sql::Relation;
 use readyset::metrics::recorded;
 use readyset::recipe::changelist::ChangeList;
 use readyset::replication::{ReplicationOffset, ReplicationOffsets};
-use readyset::ReadySetResult;
-use readyset_data::Dialect;
+use readyset::{ReadySetResult, Table};
+use readyset_data::{DfValue, Dialect};
 use tokio::task::JoinHandle;
 use tracing::{debug, error, info, info_span, warn};
 use tracing_futures::Instrument;
 
+use super::gtid::{GtidPosition, GtidSet};
 use super::BinlogPosition;
 use crate::db_util::DatabaseSchemas;
+use crate::excluded_columns::{check_key_columns, resolve_columns, select_list};
+use crate::snapshot::{
+    CheckpointStore, SnapshotCheckpoint, SnapshotOptions, SnapshotProgress, TableCheckpoint,
+};
 use crate::table_filter::TableFilter;
 
 const BATCH_SIZE: usize = 1000; // How many queries to buffer before pushing to ReadySet
 
-const MAX_SNAPSHOT_BATCH: usize = 8; // How many tables to snapshot at the same time
-
 /// A list of databases MySQL uses internally, they should not be replicated
 pub const MYSQL_INTERNAL_DBS: &[&str] =
     &["mysql", "information_schema", "performance_schema", "sys"];
@@ -51,6 +53,12 @@ pub(crate) struct MySqlReplicator {
     pub(crate) pool: mysql::Pool,
     /// Filters out the desired tables to snapshot and replicate
     pub(crate) table_filter: TableFilter,
+    /// Records the progress of the snapshot, so that it can be resumed if interrupted
+    pub(crate) checkpoints: CheckpointStore,
+    pub(crate) options: SnapshotOptions,
+    /// Whether to identify positions in the binlog by the set of executed GTIDs, rather than by
+    /// binlog file and position
+    pub(crate) gtid_mode: bool,
 }
 
 /// Get the list of tables defined in the database
@@ -237,10 +245,10 @@ impl MySqlReplicator {
         // will advance while we are taking the snapshot. This is fine, we will catch up later.
         // We prefer to take the binlog position *after* the recipe is loaded in order to make sure
         // no ddl changes took place between the binlog position and the schema that we loaded
-        let binlog_position = self.get_binlog_position().await?;
+        let binlog_position = self.get_replication_offset().await?;
 
         noria
-            .set_schema_replication_offset(Some(&binlog_position.try_into()?))
+            .set_schema_replication_offset(Some(&binlog_position))
             .await?;
 
         let table_list = all_tables
@@ -261,7 +269,7 @@ impl MySqlReplicator {
     /// Call `SELECT * FROM table` and convert all rows into a ReadySet row
     /// it may seem inefficient but apparently that is the correct way to
     /// replicate a table, and `mysqldump` and `debezium` do just that
-    pub(crate) async fn dump_table(&self, table: &Relation) -> mysql::Result<TableDumper> {
+    pub(crate) async fn dump_table(&self, table: &Relation) -> ReadySetResult<TableDumper> {
         let mut tx = self
             .pool
             .start_transaction(tx_opts())
@@ -273,11 +281,51 @@ impl MySqlReplicator {
             .await
             .map_err(log_err);
 
-        let query_count = format!("select count(*) from {table}");
-        let query = format!("select * from {table}");
+        // Only copy the rows satisfying the table's row filter, if it has one
+        let row_filter = table
+            .schema
+            .as_deref()
+            .and_then(|schema| self.table_filter.row_filter(schema, table.name.as_str()))
+            .map(|row_filter| row_filter.predicate.clone());
+        let where_clause = row_filter
+            .as_ref()
+            .map(|predicate| format!(" where ({predicate})"))
+            .unwrap_or_default();
+
+        let key = primary_key(&mut tx, table).await?;
+
+        // Select NULL in place of the columns that are excluded from replication, if there are any
+        let excluded_columns = table
+            .schema
+            .as_deref()
+            .map(|schema| {
+                self.table_filter
+                    .excluded_columns(schema, table.name.as_str())
+            })
+            .unwrap_or_default();
+        let columns = if excluded_columns.is_empty() {
+            "*".to_owned()
+        } else {
+            let column_names = column_names(&mut tx, table).await?;
+            // MySQL column names are case-insensitive
+            let excluded_columns = resolve_columns(table, &excluded_columns, &column_names, true)?;
+            check_key_columns(
+                table,
+                &excluded_columns,
+                key.iter().flatten().map(|(col, _)| col.as_str()),
+            )?;
+            select_list(&column_names, &excluded_columns, '`', |_| "NULL".to_owned())
+        };
+
+        let query_count = format!("select count(*) from {table}{where_clause}");
+        let query = format!("select {columns} from {table}{where_clause}");
         Ok(TableDumper {
+            table: table.clone(),
             query_count,
             query,
+            columns,
+            key,
+            row_filter,
             tx,
         })
     }
@@ -302,6 +350,21 @@ impl MySqlReplicator {
         })
     }
 
+    /// Get the current position in the binlog as a replication offset, identified either by the
+    /// set of executed GTIDs or by binary log file name and position depending on `gtid_mode`
+    async fn get_replication_offset(&self) -> ReadySetResult<ReplicationOffset> {
+        if self.gtid_mode {
+            let mut conn = self.pool.get_conn().await?;
+            let executed: String = conn
+                .query_first("SELECT @@GLOBAL.gtid_executed")
+                .await?
+                .unwrap_or_default();
+            ReplicationOffset::try_from(&GtidPosition::new(executed.parse::<GtidSet>()?))
+        } else {
+            self.get_binlog_position().await?.try_into()
+        }
+    }
+
     /// Issue a `LOCK TABLES tbl_name READ` for the table name provided
     async fn lock_table(&self, table: &Relation) -> mysql::Result<mysql::Conn> {
         let mut conn = self.pool.get_conn().await?;
@@ -311,14 +374,17 @@ impl MySqlReplicator {
     }
 
     /// Replicate a single table from the provided TableDumper and into ReadySet by
-    /// converting every MySQL row into ReadySet row and calling `insert_many` in batches
+    /// converting every MySQL row into ReadySet row and calling `insert_many` in batches.
+    ///
+    /// Tables with a primary key are copied in chunks, starting after the last key recorded in
+    /// `checkpoint`, and the progress of the copy is recorded in `checkpoints` after each chunk.
     async fn replicate_table(
         mut dumper: TableDumper,
-        mut table_mutator: readyset::Table,
-        snapshot_report_interval_secs: u16,
+        mut table_mutator: Table,
+        mut checkpoint: TableCheckpoint,
+        checkpoints: CheckpointStore,
+        options: SnapshotOptions,
     ) -> ReadySetResult<()> {
-        let mut cnt = 0;
-
         // Query for number of rows first
         let nrows: usize = dumper
             .tx
@@ -327,69 +393,144 @@ impl MySqlReplicator {
             .map_err(log_err)?
             .unwrap_or(0);
 
-        let mut row_stream = dumper.stream().await.map_err(log_err)?;
-        let mut rows = Vec::with_capacity(BATCH_SIZE);
-
-        info!(rows = %nrows, "Replication started");
+        if checkpoint.last_key.is_some() {
+            info!(rows = %nrows, "Resuming replication");
+            // Some of the rows after the checkpoint may have been written already, so leave
+            // snapshot mode in order for inserts of keys that already exist to be ignored
+            table_mutator.set_snapshot_mode(false).await?;
+        } else {
+            info!(rows = %nrows, "Replication started");
+            table_mutator.set_snapshot_mode(true).await?;
+        }
 
-        table_mutator.set_snapshot_mode(true).await?;
-        let progress_percentage_metric: metrics::Gauge = register_gauge!(
-            recorded::REPLICATOR_SNAPSHOT_PERCENT,
-            "name" => table_mutator.table_name().to_string(),
+        let mut progress = SnapshotProgress::new(
+            nrows as u64,
+            options.report_interval_secs,
+            register_gauge!(
+                recorded::REPLICATOR_SNAPSHOT_PERCENT,
+                "name" => table_mutator.table_name().to_string(),
+            ),
         );
 
-        let start_time = Instant::now();
-        let mut last_report_time = start_time;
-        let snapshot_report_interval_secs = snapshot_report_interval_secs as u64;
+        let result = match dumper.key.take() {
+            Some(key) => {
+                Self::replicate_chunks(
+                    &mut dumper,
+                    &key,
+                    &mut table_mutator,
+                    &mut checkpoint,
+                    &checkpoints,
+                    options.chunk_rows,
+                    &mut progress,
+                )
+                .await
+            }
+            None => {
+                Self::replicate_all(&mut dumper, nrows, &mut table_mutator, &mut progress).await
+            }
+        };
+        if let Err(err) = result {
+            progress.failed();
+            return Err(log_err(err));
+        }
+
+        info!(rows_replicated = %progress.rows(), "Replication finished");
+        progress.finished();
+
+        Ok(())
+    }
+
+    /// Copy all of a table's rows in a single pass
+    async fn replicate_all(
+        dumper: &mut TableDumper,
+        nrows: usize,
+        table_mutator: &mut Table,
+        progress: &mut SnapshotProgress,
+    ) -> ReadySetResult<()> {
+        let mut row_stream = dumper.stream().await?;
+        let mut rows = Vec::with_capacity(BATCH_SIZE);
 
         loop {
             let row = match row_stream.next().await {
                 Ok(Some(row)) => row,
                 Ok(None) => break,
-                Err(err) if cnt == nrows => {
+                Err(err) if progress.rows() == nrows as u64 => {
                     info!(error = %err, "Error encountered during snapshot, but all rows replicated succesfully");
                     break;
                 }
-                Err(err) => {
-                    progress_percentage_metric.set(0.0);
-                    return Err(err).map_err(log_err);
-                }
+                Err(err) => return Err(err),
             };
 
             rows.push(row);
-            cnt += 1;
+            progress.add_row();
 
             if rows.len() == BATCH_SIZE {
                 // We aggregate rows into batches and then send them all to noria
                 let send_rows = std::mem::replace(&mut rows, Vec::with_capacity(BATCH_SIZE));
-                table_mutator.insert_many(send_rows).await.map_err(|err| {
-                    progress_percentage_metric.set(0.0);
-                    log_err(err)
-                })?;
-            }
-
-            if snapshot_report_interval_secs != 0
-                && last_report_time.elapsed().as_secs() > snapshot_report_interval_secs
-            {
-                last_report_time = Instant::now();
-                let estimate =
-                    crate::estimate_remaining_time(start_time.elapsed(), cnt as f64, nrows as f64);
-                let progress_percent = (cnt as f64 / nrows as f64) * 100.;
-                let progress = format!("{:.2}%", progress_percent);
-                info!(rows_replicated = %cnt, %progress, %estimate, "Snapshotting progress");
-                progress_percentage_metric.set(progress_percent);
+                table_mutator.insert_many(send_rows).await?;
             }
         }
 
         if !rows.is_empty() {
-            table_mutator.insert_many(rows).await.map_err(|err| {
-                progress_percentage_metric.set(0.0);
-                log_err(err)
-            })?;
+            table_mutator.insert_many(rows).await?;
         }
 
-        info!(rows_replicated = %cnt, "Replication finished");
-        progress_percentage_metric.set(100.0);
+        Ok(())
+    }
+
+    /// Copy a table's rows in chunks of `chunk_rows` rows ordered by its primary key `key`,
+    /// starting after the last key recorded in `checkpoint`. Once each chunk has been durably
+    /// written to ReadySet, the key of its last row is recorded in `checkpoints`.
+    async fn replicate_chunks(
+        dumper: &mut TableDumper,
+        key: &[(String, usize)],
+        table_mutator: &mut Table,
+        checkpoint: &mut TableCheckpoint,
+        checkpoints: &CheckpointStore,
+        chunk_rows: u64,
+        progress: &mut SnapshotProgress,
+    ) -> ReadySetResult<()> {
+        let table = dumper.table.clone();
+        loop {
+            let mut row_stream = dumper
+                .stream_chunk(key, checkpoint.last_key.as_deref(), chunk_rows)
+                .await?;
+            let mut rows = Vec::with_capacity(BATCH_SIZE);
+            let mut chunk_len = 0;
+            let mut last_key = None;
+
+            while let Some(row) = row_stream.next().await? {
+                last_key = Some(key.iter().map(|(_, idx)| row[*idx].clone()).collect());
+                rows.push(row);
+                chunk_len += 1;
+                progress.add_row();
+
+                if rows.len() == BATCH_SIZE {
+                    let send_rows = std::mem::replace(&mut rows, Vec::with_capacity(BATCH_SIZE));
+                    table_mutator.insert_many(send_rows).await?;
+                }
+            }
+
+            if !rows.is_empty() {
+                table_mutator.insert_many(rows).await?;
+            }
+
+            if last_key.is_none() {
+                break;
+            }
+
+            // Setting the replication offset flushes the rows written so far to disk, which has
+            // to happen before we record them as copied
+            table_mutator
+                .set_replication_offset(checkpoint.offset.clone())
+                .await?;
+            checkpoint.last_key = last_key;
+            checkpoints.set_table(&table, checkpoint.clone()).await?;
+
+            if chunk_len < chunk_rows {
+                break;
+            }
+        }
 
         Ok(())
     }
@@ -408,15 +549,16 @@ impl MySqlReplicator {
         mut self,
         noria: &mut readyset::ReadySetHandle,
         db_schemas: &mut DatabaseSchemas,
-        snapshot_report_interval_secs: u16,
     ) -> ReadySetResult<()> {
         let result = self
-            .replicate_to_noria_with_table_locks(noria, db_schemas, snapshot_report_interval_secs)
+            .replicate_to_noria_with_table_locks(noria, db_schemas)
             .await;
 
         // Wait for all connections to finish, not strictly necessary
         self.pool.disconnect().await?;
-        result
+        result?;
+
+        self.checkpoints.finish().await
     }
 
     /// This is a fallback method to obtaining a database lock, that obtains table level locks
@@ -428,7 +570,6 @@ impl MySqlReplicator {
         &mut self,
         noria: &mut readyset::ReadySetHandle,
         db_schemas: &mut DatabaseSchemas,
-        snapshot_report_interval_secs: u16,
     ) -> ReadySetResult<()> {
         // NOTE: There are two ways to prevent DDL changes in MySQL:
         // `FLUSH TABLES WITH READ LOCK` or `LOCK INSTANCE FOR BACKUP`. Both are not
@@ -455,6 +596,11 @@ impl MySqlReplicator {
             }
         };
 
+        // Mark the snapshot as being in progress before loading the recipe, which assigns the
+        // schema a replication offset, so that an interrupted snapshot is resumed rather than
+        // being taken for a complete one
+        let checkpoint = self.checkpoints.start().await?;
+
         let (_meta_lock, table_list) = self
             .load_recipe_with_meta_lock(noria, db_schemas)
             .await
@@ -463,13 +609,8 @@ impl MySqlReplicator {
         // Replication offsets could change following a schema update, so get a new list
         let replication_offsets = noria.replication_offsets().await?;
 
-        self.dump_tables(
-            noria,
-            table_list,
-            &replication_offsets,
-            snapshot_report_interval_secs,
-        )
-        .await
+        self.dump_tables(noria, table_list, &replication_offsets, &checkpoint)
+            .await
     }
 
     /// Spawns a new tokio task that replicates a given table to noria, returning
@@ -478,14 +619,13 @@ impl MySqlReplicator {
         &mut self,
         noria: &mut readyset::ReadySetHandle,
         table: Relation,
-        snapshot_report_interval_secs: u16,
     ) -> ReadySetResult<JoinHandle<(Relation, ReplicationOffset, ReadySetResult<()>)>> {
         let span = info_span!("replicating table", %table);
         span.in_scope(|| info!("Acquiring read lock"));
         let mut read_lock = self.lock_table(&table).await?;
         // We acquire the position for each table individually, since it changes from
         // one lock to the other
-        let repl_offset = ReplicationOffset::try_from(self.get_binlog_position().await?)?;
+        let repl_offset = self.get_replication_offset().await?;
         span.in_scope(|| info!("Replicating table"));
 
         let dumper = self.dump_table(&table).instrument(span.clone()).await?;
@@ -497,11 +637,28 @@ impl MySqlReplicator {
 
         let table_mutator = noria.table(table.clone()).instrument(span.clone()).await?;
 
+        // If a previous snapshot copied some of the table already, resume copying it where that
+        // snapshot left off, otherwise start from scratch at this binlog position
+        let checkpoint = match self
+            .checkpoints
+            .load()
+            .await?
+            .and_then(|checkpoint| checkpoint.table(&table).cloned())
+        {
+            Some(checkpoint) if checkpoint.last_key.is_some() && dumper.key.is_some() => checkpoint,
+            _ => TableCheckpoint::new(repl_offset),
+        };
+        self.checkpoints
+            .set_table(&table, checkpoint.clone())
+            .await?;
+
+        let checkpoints = self.checkpoints.clone();
+        let options = self.options;
         Ok(tokio::spawn(async move {
             (
                 table,
-                repl_offset,
-                Self::replicate_table(dumper, table_mutator, snapshot_report_interval_secs)
+                checkpoint.offset.clone(),
+                Self::replicate_table(dumper, table_mutator, checkpoint, checkpoints, options)
                     .instrument(span)
                     .await,
             )
@@ -514,7 +671,7 @@ impl MySqlReplicator {
         noria: &mut readyset::ReadySetHandle,
         mut table_list: Vec<Relation>,
         replication_offsets: &ReplicationOffsets,
-        snapshot_report_interval_secs: u16,
+        checkpoint: &SnapshotCheckpoint,
     ) -> ReadySetResult<()> {
         let mut replication_tasks = FuturesUnordered::new();
         let mut compacting_tasks = FuturesUnordered::new();
@@ -523,16 +680,13 @@ impl MySqlReplicator {
         // We pop front because we add the tables before the views, and the views depend on the
         // tables. TODO: do we need to fully finish tables before views?
         while let Some(table) = table_list.pop() {
-            if replication_offsets.has_table(&table) {
+            if is_snapshotted(&table, replication_offsets, checkpoint) {
                 info!(%table, "Replication offset already exists for table, skipping snapshot");
             } else {
-                replication_tasks.push(
-                    self.dumper_task_for_table(noria, table, snapshot_report_interval_secs)
-                        .await?,
-                );
+                replication_tasks.push(self.dumper_task_for_table(noria, table).await?);
             }
 
-            if replication_tasks.len() == MAX_SNAPSHOT_BATCH {
+            if replication_tasks.len() == self.options.parallelism {
                 break;
             }
         }
@@ -542,6 +696,7 @@ impl MySqlReplicator {
             match task_result.unwrap() {
                 (table, repl_offset, Ok(())) => {
                     let mut noria_table = noria.table(table.clone()).await?;
+                    let checkpoints = self.checkpoints.clone();
                     compacting_tasks.push(tokio::spawn(async move {
                         let span = info_span!("Compacting table", %table);
                         span.in_scope(|| info!("Setting replication offset"));
@@ -558,29 +713,24 @@ impl MySqlReplicator {
                             .instrument(span.clone())
                             .await?;
 
+                        checkpoints.finish_table(&table).await?;
                         span.in_scope(|| info!("Compacting finished"));
                         ReadySetResult::Ok(())
                     }));
                 }
                 (table, _, Err(err)) => {
                     error!(%table, error = %err, "Replication failed, retrying");
-                    replication_tasks.push(
-                        self.dumper_task_for_table(noria, table, snapshot_report_interval_secs)
-                            .await?,
-                    );
+                    replication_tasks.push(self.dumper_task_for_table(noria, table).await?);
                 }
             }
 
             // If still have tables to snapshot add them to the task list
-            while replication_tasks.len() < MAX_SNAPSHOT_BATCH && !table_list.is_empty() {
+            while replication_tasks.len() < self.options.parallelism && !table_list.is_empty() {
                 let table = table_list.pop().expect("Not empty");
-                if replication_offsets.has_table(&table) {
+                if is_snapshotted(&table, replication_offsets, checkpoint) {
                     info!(%table, "Replication offset already exists for table, skipping snapshot");
                 } else {
-                    replication_tasks.push(
-                        self.dumper_task_for_table(noria, table, snapshot_report_interval_secs)
-                            .await?,
-                    );
+                    replication_tasks.push(self.dumper_task_for_table(noria, table).await?);
                 }
             }
         }
@@ -598,8 +748,15 @@ impl MySqlReplicator {
 // This is required because mysql::QueryResult borrows from conn and then
 // we have some hard to solve borrowing issues
 pub(crate) struct TableDumper {
+    table: Relation,
     query_count: String,
     query: String,
+    /// The list of columns to select from the table
+    columns: String,
+    /// The names and positions of the columns in the table's primary key, if it has one
+    key: Option<Vec<(String, usize)>>,
+    /// The predicate of the table's row filter, if it has one
+    row_filter: Option<String>,
     tx: mysql::Transaction<'static>,
 }
 
@@ -609,6 +766,104 @@ impl TableDumper {
             query: self.tx.exec_iter(&self.query, ()).await?,
         })
     }
+
+    /// Get a stream of at most `chunk_rows` rows of the table, in the order of the primary key
+    /// `key`, starting after the row with the key `after` if given
+    pub(crate) async fn stream_chunk(
+        &mut self,
+        key: &[(String, usize)],
+        after: Option<&[DfValue]>,
+        chunk_rows: u64,
+    ) -> ReadySetResult<TableStream<'_>> {
+        let key_columns = key.iter().map(|(col, _)| format!("`{col}`")).join(", ");
+        let row_filter = self
+            .row_filter
+            .as_ref()
+            .map(|predicate| format!("({predicate})"));
+        let (query, params) = match after {
+            Some(after) => (
+                format!(
+                    "select {} from {} where ({key_columns}) > ({}){} order by {key_columns} \
+                     limit {chunk_rows}",
+                    self.columns,
+                    self.table,
+                    after.iter().map(|_| "?").join(", "),
+                    row_filter
+                        .map(|predicate| format!(" and {predicate}"))
+                        .unwrap_or_default()
+                ),
+                mysql::Params::Positional(
+                    after
+                        .iter()
+                        .map(|v| mysql_common::value::Value::try_from(v).map(value_from_value))
+                        .collect::<ReadySetResult<_>>()?,
+                ),
+            ),
+            None => (
+                format!(
+                    "select {} from {}{} order by {key_columns} limit {chunk_rows}",
+                    self.columns,
+                    self.table,
+                    row_filter
+                        .map(|predicate| format!(" where {predicate}"))
+                        .unwrap_or_default()
+                ),
+                mysql::Params::Empty,
+            ),
+        };
+        Ok(TableStream {
+            query: self.tx.exec_iter(query, params).await?,
+        })
+    }
+}
+
+/// Returns true if `table` was already completely copied by a previous snapshot
+fn is_snapshotted(
+    table: &Relation,
+    replication_offsets: &ReplicationOffsets,
+    checkpoint: &SnapshotCheckpoint,
+) -> bool {
+    // Tables which were being copied when a snapshot was interrupted may have a replication
+    // offset, as it's set after each chunk is copied
+    replication_offsets.has_table(table) && checkpoint.table(table).is_none()
+}
+
+/// Get the names and positions of the columns in the primary key of `table`, if it has one
+async fn primary_key<Q: Queryable>(
+    q: &mut Q,
+    table: &Relation,
+) -> mysql::Result<Option<Vec<(String, usize)>>> {
+    let query = "SELECT k.COLUMN_NAME, c.ORDINAL_POSITION \
+                 FROM information_schema.KEY_COLUMN_USAGE k \
+                 JOIN information_schema.COLUMNS c \
+                 ON c.TABLE_SCHEMA = k.TABLE_SCHEMA AND c.TABLE_NAME = k.TABLE_NAME \
+                 AND c.COLUMN_NAME = k.COLUMN_NAME \
+                 WHERE k.CONSTRAINT_NAME = 'PRIMARY' AND k.TABLE_SCHEMA = ? AND k.TABLE_NAME = ? \
+                 ORDER BY k.ORDINAL_POSITION";
+    let schema = table
+        .schema
+        .as_ref()
+        .map(|s| s.to_string())
+        .unwrap_or_default();
+    let key: Vec<(String, u64)> = q.exec(query, (schema, table.name.to_string())).await?;
+    Ok((!key.is_empty()).then(|| {
+        key.into_iter()
+            .map(|(col, pos)| (col, pos as usize - 1))
+            .collect()
+    }))
+}
+
+/// Get the names of the columns of `table`, in order
+async fn column_names<Q: Queryable>(q: &mut Q, table: &Relation) -> mysql::Result<Vec<String>> {
+    let query = "SELECT COLUMN_NAME FROM information_schema.COLUMNS \
+                 WHERE TABLE_SCHEMA = ? AND TABLE_NAME = ? \
+                 ORDER BY ORDINAL_POSITION";
+    let schema = table
+        .schema
+        .as_ref()
+        .map(|s| s.to_string())
+        .unwrap_or_default();
+    q.exec(query, (schema, table.name.to_string())).await
 }
 
 // Just another helper struct to make it streamable
@@ -654,6 +909,24 @@ fn value_to_value(val: &mysql::Value) -> mysql_common::value::Value {
     }
 }
 
+/// The inverse of [`value_to_value`]
+fn value_from_value(val: mysql_common::value::Value) -> mysql::Value {
+    match val {
+        mysql_common::value::Value::NULL => mysql::Value::NULL,
+        mysql_common::value::Value::Bytes(b) => mysql::Value::Bytes(b),
+        mysql_common::value::Value::Int(i) => mysql::Value::Int(i),
+        mysql_common::value::Value::UInt(u) => mysql::Value::UInt(u),
+        mysql_common::value::Value::Float(f) => mysql::Value::Float(f),
+        mysql_common::value::Value::Double(d) => mysql::Value::Double(d),
+        mysql_common::value::Value::Date(y, m, d, hh, mm, ss, us) => {
+            mysql::Value::Date(y, m, d, hh, mm, ss, us)
+        }
+        mysql_common::value::Value::Time(is_neg, d, hh, mm, ss, us) => {
+            mysql::Value::Time(is_neg, d, hh, mm, ss, us)
+        }
+    }
+}
+
 impl TableKind {
     pub fn kind(&self) -> &str {
         match self {
@@ -1,17 +1,17 @@
 use std::collections::{hash_map, HashMap, HashSet};
-use std::convert::TryInto;
 use std::sync::Arc;
 use std::time::{Duration, Instant};
 
 use async_trait::async_trait;
 use database_utils::{DatabaseURL, UpstreamConfig};
 use failpoint_macros::set_failpoint;
+use futures::future::try_join_all;
 use futures::FutureExt;
 use launchpad::select;
 use metrics::{counter, histogram};
 use mysql::prelude::Queryable;
 use mysql::{OptsBuilder, SslOpts};
-use nom_sql::Relation;
+use nom_sql::{Relation, SqlIdentifier};
 use readyset::consensus::Authority;
 use readyset::consistency::Timestamp;
 #[cfg(feature = "failure_injection")]
@@ -20,18 +20,22 @@ use readyset::metrics::recorded::{self, SnapshotStatusTag};
 use readyset::recipe::changelist::{Change, ChangeList};
 use readyset::replication::{ReplicationOffset, ReplicationOffsets};
 use readyset::{ReadySetError, ReadySetHandle, ReadySetResult, Table, TableOperation};
-use readyset_data::Dialect;
-use readyset_errors::{internal_err, invalid_err};
+use readyset_data::dialect::SqlEngine;
+use readyset_data::{DfValue, Dialect};
+use readyset_errors::{internal, internal_err, invalid_err};
 use readyset_telemetry_reporter::{TelemetryBuilder, TelemetryEvent, TelemetrySender};
 use tokio::sync::Notify;
 use tracing::{debug, error, info, info_span, trace, warn, Instrument};
 use {mysql_async as mysql, tokio_postgres as pgsql};
 
 use crate::db_util::{CreateSchema, DatabaseSchemas};
+use crate::excluded_columns::{drop_tables_with_changed_exclusions, ExcludedColumnIndices};
 use crate::mysql_connector::{MySqlBinlogConnector, MySqlReplicator};
 use crate::postgres_connector::{
     PostgresReplicator, PostgresWalConnector, PUBLICATION_NAME, REPLICATION_SLOT,
 };
+use crate::row_filter::{FilteredAction, RowPredicate};
+use crate::snapshot::{CheckpointStore, SnapshotOptions};
 use crate::table_filter::TableFilter;
 
 const WAIT_BEFORE_RESNAPSHOT: Duration = Duration::from_secs(3);
@@ -49,6 +53,16 @@ pub(crate) enum ReplicationAction {
         /// increasing across transactions.
         txid: Option<u64>,
     },
+    /// The changes made to any number of tables by a single upstream transaction, which are
+    /// sent to all the tables in one batch once the transaction has been completely read (or once
+    /// too many changes have been buffered, in which case the rest of the transaction's changes
+    /// follow as [`ReplicationAction::TableAction`]s)
+    TransactionAction {
+        /// The operations to perform on each table modified by the transaction
+        tables: Vec<(Relation, Vec<TableOperation>)>,
+        /// The transaction id of the transaction, as for [`ReplicationAction::TableAction`]
+        txid: Option<u64>,
+    },
     DdlChange {
         schema: String,
         changes: Vec<Change>,
@@ -74,6 +88,19 @@ pub(crate) trait Connector {
         last_pos: &ReplicationOffset,
         until: Option<&ReplicationOffset>,
     ) -> ReadySetResult<(ReplicationAction, ReplicationOffset)>;
+
+    /// Fetch the current contents of the row of `table` whose `key_columns` have the values in
+    /// `key`, selecting `columns`, or `None` if there is no such row. This is only needed by
+    /// connectors which return updates of only some of the columns of a row.
+    async fn fetch_row(
+        &mut self,
+        table: &Relation,
+        _columns: &[SqlIdentifier],
+        _key_columns: &[SqlIdentifier],
+        _key: &[DfValue],
+    ) -> ReadySetResult<Option<Vec<DfValue>>> {
+        internal!("Fetching rows of table {table} from the upstream database isn't supported")
+    }
 }
 
 /// An adapter that converts database events into ReadySet API calls
@@ -88,6 +115,11 @@ pub struct NoriaAdapter {
     mutator_map: HashMap<Relation, Option<Table>>,
     /// A HashSet of tables we've already warned about not existing
     warned_missing_tables: HashSet<Relation>,
+    /// A map of the row filters of tables, prepared to be evaluated against the rows of the
+    /// table's replication events
+    row_predicates: HashMap<Relation, Option<RowPredicate>>,
+    /// A map of the positions of the columns of tables that are excluded from replication
+    excluded_columns: HashMap<Relation, ExcludedColumnIndices>,
     /// The set of replication offsets for the schema and the tables, obtained from the controller
     /// at startup and maintained during replication.
     ///
@@ -107,12 +139,14 @@ impl NoriaAdapter {
         telemetry_sender: TelemetrySender,
         config: UpstreamConfig,
     ) -> ReadySetResult<!> {
-        let noria = readyset::ReadySetHandle::new(authority).await;
-        NoriaAdapter::start(noria, config, None, telemetry_sender).await
+        let authority = Arc::new(authority);
+        let noria = readyset::ReadySetHandle::new(Arc::clone(&authority)).await;
+        NoriaAdapter::start(noria, authority, config, None, telemetry_sender).await
     }
 
     pub async fn start(
         noria: ReadySetHandle,
+        authority: Arc<Authority>,
         mut config: UpstreamConfig,
         mut notify: Option<Arc<Notify>>,
         telemetry_sender: TelemetrySender,
@@ -124,6 +158,20 @@ impl NoriaAdapter {
             .ok_or_else(|| internal_err!("Replication URL not supplied"))?
             .parse()
             .map_err(|e| invalid_err!("Invalid URL supplied to --upstream-db-url: {e}"))?;
+        let checkpoints = CheckpointStore::new(Arc::clone(&authority));
+
+        let dialect = match url {
+            DatabaseURL::MySQL(_) => Dialect::DEFAULT_MYSQL,
+            DatabaseURL::PostgreSQL(_) => Dialect::DEFAULT_POSTGRESQL,
+        };
+        drop_tables_with_changed_exclusions(
+            &mut noria.clone(),
+            &authority,
+            &checkpoints,
+            &config.replication_excluded_columns,
+            dialect,
+        )
+        .await?;
 
         while let Err(err) = match url.clone() {
             DatabaseURL::MySQL(options) => {
@@ -132,6 +180,7 @@ impl NoriaAdapter {
                 NoriaAdapter::start_inner_mysql(
                     options,
                     noria,
+                    checkpoints.clone(),
                     config,
                     &mut notify,
                     resnapshot,
@@ -145,6 +194,7 @@ impl NoriaAdapter {
                 NoriaAdapter::start_inner_postgres(
                     options,
                     noria,
+                    checkpoints.clone(),
                     config,
                     &mut notify,
                     resnapshot,
@@ -177,13 +227,12 @@ impl NoriaAdapter {
     async fn start_inner_mysql(
         mut mysql_options: mysql::Opts,
         mut noria: ReadySetHandle,
+        checkpoints: CheckpointStore,
         mut config: UpstreamConfig,
         ready_notify: &mut Option<Arc<Notify>>,
         resnapshot: bool,
         telemetry_sender: &TelemetrySender,
     ) -> ReadySetResult<!> {
-        use crate::mysql_connector::BinlogPosition;
-
         if let Some(cert_path) = config.ssl_root_cert.clone() {
             let ssl_opts = SslOpts::default().with_root_cert_path(Some(cert_path));
             mysql_options = OptsBuilder::from_opts(mysql_options)
@@ -198,11 +247,24 @@ impl NoriaAdapter {
             nom_sql::Dialect::MySQL,
             config.replication_tables.take(),
             mysql_options.db_name(),
-        )?;
+        )?
+        .with_row_filters(
+            nom_sql::Dialect::MySQL,
+            config.replication_row_filters.take(),
+            mysql_options.db_name(),
+        )?
+        .with_excluded_columns(std::mem::take(&mut config.replication_excluded_columns));
 
         let mut db_schemas = DatabaseSchemas::new();
 
-        let pos = match (replication_offsets.max_offset()?, resnapshot) {
+        // A snapshot that was interrupted has to be resumed even if all the tables it copied
+        // so far have replication offsets
+        let snapshot_in_progress = checkpoints.load().await?.is_some();
+
+        let pos = match (
+            replication_offsets.max_offset()?,
+            resnapshot || snapshot_in_progress,
+        ) {
             (None, _) | (_, true) => {
                 let span = info_span!("taking database snapshot");
                 let replicator_options = mysql_options.clone();
@@ -218,9 +280,44 @@ impl NoriaAdapter {
                     .flatten()
                     .unwrap_or_else(|| "unknown".to_owned());
 
+                // Identify positions in the binlog by GTID if the server has GTIDs enabled, so that
+                // replication can continue after a replica is promoted to primary. All the offsets
+                // have to be comparable though, so stick to binlog file and position if that's
+                // what the offsets of the tables that are already snapshotted use.
+                let gtid_mode = match replication_offsets.max_present_offset()? {
+                    Some(offset) => offset.gtid_set().is_some(),
+                    None => match pool
+                        .get_conn()
+                        .await?
+                        .query_first::<String, _>("SELECT @@GLOBAL.gtid_mode")
+                        .await
+                    {
+                        Ok(mode) => mode.map_or(false, |mode| mode.eq_ignore_ascii_case("ON")),
+                        Err(error) => {
+                            // Servers without GTID support, such as MariaDB, don't have the
+                            // variable at all
+                            warn!(
+                                %error,
+                                "Could not determine whether GTIDs are enabled, identifying \
+                                 binlog positions by file and position. Replication won't be \
+                                 able to follow a failover until the next full snapshot."
+                            );
+                            false
+                        }
+                    },
+                };
+                if gtid_mode {
+                    info!("Identifying binlog positions by GTID");
+                } else {
+                    info!("Identifying binlog positions by file and position");
+                }
+
                 let replicator = MySqlReplicator {
                     pool,
                     table_filter: table_filter.clone(),
+                    checkpoints,
+                    options: SnapshotOptions::from(&config),
+                    gtid_mode,
                 };
 
                 let snapshot_start = Instant::now();
@@ -232,11 +329,7 @@ impl NoriaAdapter {
 
                 span.in_scope(|| info!("Starting snapshot"));
                 let snapshot_result = replicator
-                    .snapshot_to_noria(
-                        &mut noria,
-                        &mut db_schemas,
-                        config.snapshot_report_interval_secs,
-                    )
+                    .snapshot_to_noria(&mut noria, &mut db_schemas)
                     .instrument(span.clone())
                     .await;
 
@@ -266,11 +359,10 @@ impl NoriaAdapter {
                 // can do this "catching up" by just starting replication at
                 // the old offset. Note that at the very least we will
                 // always have the schema offset for the minimum.
-                let pos: BinlogPosition = replication_offsets
+                let pos = replication_offsets
                     .min_present_offset()?
                     .expect("Minimal offset must be present after snapshot")
-                    .clone()
-                    .into();
+                    .clone();
 
                 span.in_scope(|| info!("Snapshot finished"));
                 histogram!(
@@ -290,7 +382,7 @@ impl NoriaAdapter {
 
                 pos
             }
-            (Some(pos), _) => pos.clone().into(),
+            (Some(pos), _) => pos.clone(),
         };
 
         // TODO: it is possible that the binlog position from noria is no longer
@@ -299,7 +391,7 @@ impl NoriaAdapter {
         let connector = Box::new(
             MySqlBinlogConnector::connect(
                 mysql_options.clone(),
-                pos.clone(),
+                &pos,
                 config.replication_server_id,
             )
             .await?,
@@ -311,12 +403,15 @@ impl NoriaAdapter {
             replication_offsets,
             mutator_map: HashMap::new(),
             warned_missing_tables: HashSet::new(),
+            row_predicates: HashMap::new(),
+            excluded_columns: HashMap::new(),
             table_filter,
             supports_resnapshot: true,
             dialect: Dialect::DEFAULT_MYSQL,
         };
+        adapter.load_all_table_filters().await?;
 
-        let mut current_pos: ReplicationOffset = pos.try_into()?;
+        let mut current_pos = pos;
 
         // At this point it is possible that we just finished replication, but
         // our schema and our tables are taken at different position in the binlog.
@@ -350,6 +445,7 @@ impl NoriaAdapter {
     async fn start_inner_postgres(
         pgsql_opts: pgsql::Config,
         mut noria: ReadySetHandle,
+        checkpoints: CheckpointStore,
         mut config: UpstreamConfig,
         ready_notify: &mut Option<Arc<Notify>>,
         resnapshot: bool,
@@ -362,14 +458,28 @@ impl NoriaAdapter {
         // Attempt to retrieve the latest replication offset from ReadySet-server, if none is
         // present begin the snapshot process
         let replication_offsets = noria.replication_offsets().await?;
-        let pos = replication_offsets.max_offset()?.map(Into::into);
-        let snapshot_report_interval_secs = config.snapshot_report_interval_secs;
+        let snapshot_in_progress = checkpoints.load().await?.is_some();
+        let pos = if snapshot_in_progress {
+            // A snapshot was interrupted, so some tables are missing replication offsets, but we
+            // still have to keep the replication slot that the tables which do have them have to
+            // catch up from. Those tables can't have offsets older than the schema's.
+            replication_offsets.min_present_offset()?.map(Into::into)
+        } else {
+            replication_offsets.max_offset()?.map(Into::into)
+        };
+        let snapshot_options = SnapshotOptions::from(&config);
 
         let table_filter = TableFilter::try_new(
             nom_sql::Dialect::PostgreSQL,
             config.replication_tables.take(),
             None,
-        )?;
+        )?
+        .with_row_filters(
+            nom_sql::Dialect::PostgreSQL,
+            config.replication_row_filters.take(),
+            None,
+        )?
+        .with_excluded_columns(std::mem::take(&mut config.replication_excluded_columns));
 
         let connector = {
             let mut builder = native_tls::TlsConnector::builder();
@@ -398,7 +508,7 @@ impl NoriaAdapter {
 
         let replication_slot = if let Some(slot) = &connector.replication_slot {
             Some(slot.clone())
-        } else if resnapshot || pos.is_none() {
+        } else if resnapshot || pos.is_none() || snapshot_in_progress {
             // This is not an initial connection but we need to resnapshot the latest schema,
             // therefore we create a new replication slot, just so we can get a consistent snapshot
             // with a WAL position attached. This is more robust than locking and allows us to reuse
@@ -423,7 +533,7 @@ impl NoriaAdapter {
             let snapshot_start = Instant::now();
             // If snapshot name exists, it means we need to make a snapshot to noria
 
-            let (mut client, connection) = pgsql_opts.connect(tls_connector).await?;
+            let (mut client, connection) = pgsql_opts.connect(tls_connector.clone()).await?;
 
             let connection_handle = tokio::spawn(connection);
             let db_version = client
@@ -432,11 +542,18 @@ impl NoriaAdapter {
                 .and_then(|row| row.try_get::<_, String>(0))
                 .unwrap_or_else(|_| "unknown".to_owned());
 
-            let mut replicator =
-                PostgresReplicator::new(&mut client, &mut noria, table_filter.clone()).await?;
+            let mut replicator = PostgresReplicator::new(
+                &mut client,
+                &mut noria,
+                table_filter.clone(),
+                pgsql_opts.clone(),
+                tls_connector,
+                checkpoints,
+            )
+            .await?;
 
             select! {
-                snapshot_result = replicator.snapshot_to_noria(&replication_slot, &mut create_schema, snapshot_report_interval_secs).fuse() =>  {
+                snapshot_result = replicator.snapshot_to_noria(&replication_slot, &mut create_schema, snapshot_options).fuse() =>  {
                     let status = if snapshot_result.is_err() {
                         SnapshotStatusTag::Failed.value()
                     } else {
@@ -496,10 +613,13 @@ impl NoriaAdapter {
             replication_offsets,
             mutator_map: HashMap::new(),
             warned_missing_tables: HashSet::new(),
+            row_predicates: HashMap::new(),
+            excluded_columns: HashMap::new(),
             table_filter,
             supports_resnapshot: true,
             dialect: Dialect::DEFAULT_POSTGRESQL,
         };
+        adapter.load_all_table_filters().await?;
 
         if min_pos != max_pos {
             info!(start = %min_pos, end = %max_pos, "Catching up");
@@ -607,6 +727,8 @@ impl NoriaAdapter {
         txid: Option<u64>,
         pos: ReplicationOffset,
     ) -> ReadySetResult<()> {
+        actions = self.filter_rows(&table, actions).await?;
+
         // Send the rows as are
         let table_mutator = if let Some(table) = self.mutator_for_table(&table).await? {
             table
@@ -645,6 +767,230 @@ impl NoriaAdapter {
         Ok(())
     }
 
+    /// Send the table actions of a single upstream transaction to all the noria tables it
+    /// modified, and update the binlog position for those tables.
+    ///
+    /// The writes to all the tables are batched, and sent at the same time once the whole
+    /// transaction has been read, rather than one table at a time as the transaction is read.
+    /// This only narrows the window in which a transaction is partially applied: each table's
+    /// writes still propagate through the dataflow independently, so reads of a cached query over
+    /// several of the tables may briefly see some of the transaction's writes but not others.
+    ///
+    /// The transaction's timestamp is only propagated and the tables' positions only advanced
+    /// once every table has accepted its writes; if any of the writes fail, the whole transaction
+    /// is applied again when replication resumes.
+    async fn handle_transaction_actions(
+        &mut self,
+        tables: Vec<(Relation, Vec<TableOperation>)>,
+        txid: Option<u64>,
+        pos: ReplicationOffset,
+    ) -> ReadySetResult<()> {
+        let mut writes = Vec::with_capacity(tables.len());
+        for (table, mut actions) in tables {
+            actions = self.filter_rows(&table, actions).await?;
+            let mut table_mutator = match self.mutator_for_table(&table).await? {
+                Some(table_mutator) => table_mutator.clone(),
+                None => {
+                    // As in `handle_table_actions`, it's ok to discard the actions of tables
+                    // that don't exist
+                    if self.warned_missing_tables.insert(table.clone()) {
+                        warn!(
+                            table_name = %table,
+                            num_actions = actions.len(),
+                            "Could not find table, discarding actions"
+                        );
+                    }
+                    continue;
+                }
+            };
+            actions.push(TableOperation::SetReplicationOffset(pos.clone()));
+            writes.push(async move {
+                table_mutator.perform_all(actions).await?;
+                ReadySetResult::Ok((table, table_mutator))
+            });
+        }
+        let mut written = try_join_all(writes).await?;
+
+        // Every table modified by the transaction is assigned the transaction's id, so that reads
+        // after the transaction wait for all of its writes.
+        // TODO: Making the transaction visible atomically needs readers to hold off publishing
+        // writes until the transaction's timestamp has arrived from every table it modified.
+        if let Some(tx) = txid {
+            try_join_all(written.iter_mut().map(|(_, table_mutator)| {
+                let mut timestamp = Timestamp::default();
+                timestamp.map.insert(table_mutator.node, tx);
+                table_mutator.update_timestamp(timestamp)
+            }))
+            .await?;
+        }
+
+        for (table, _) in written {
+            self.replication_offsets
+                .tables
+                .insert(table, Some(pos.clone()));
+        }
+
+        Ok(())
+    }
+
+    /// Lower the row filters of all the tables that have one against the tables' schemas, and look
+    /// up the excluded columns of all the tables that have any, so that a filter which can't be
+    /// evaluated or an excluded column that doesn't exist (for example because its name is
+    /// misspelled) fails replication at startup rather than once a change is made to its table
+    async fn load_all_table_filters(&mut self) -> ReadySetResult<()> {
+        for table in self.table_filter.row_filtered_tables() {
+            self.load_table_filters(&table).await?;
+            if let Some(None) = self.row_predicates.get(&table) {
+                warn!(%table, "Replication row filter is for a table that doesn't exist");
+            }
+        }
+
+        let excluded_tables = self
+            .replication_offsets
+            .tables
+            .keys()
+            .filter(|table| {
+                table.schema.as_deref().map_or(false, |schema| {
+                    !self
+                        .table_filter
+                        .excluded_columns(schema, table.name.as_str())
+                        .is_empty()
+                })
+            })
+            .cloned()
+            .collect::<Vec<_>>();
+        for table in excluded_tables {
+            self.load_table_filters(&table).await?;
+        }
+
+        Ok(())
+    }
+
+    /// Lower the row filter of `table` and look up the indices of its excluded columns, if they
+    /// haven't been already
+    async fn load_table_filters(&mut self, table: &Relation) -> ReadySetResult<()> {
+        if !self.row_predicates.contains_key(table) {
+            let (row_filter, excluded_columns) = match table.schema.as_deref() {
+                Some(schema) => (
+                    self.table_filter
+                        .row_filter(schema, table.name.as_str())
+                        .cloned(),
+                    self.table_filter
+                        .excluded_columns(schema, table.name.as_str())
+                        .into_iter()
+                        .map(str::to_owned)
+                        .collect::<Vec<_>>(),
+                ),
+                None => (None, vec![]),
+            };
+            let dialect = self.dialect;
+            let (row_predicate, excluded_columns) =
+                if row_filter.is_none() && excluded_columns.is_empty() {
+                    (None, None)
+                } else {
+                    match self.mutator_for_table(table).await? {
+                        Some(table_mutator) => {
+                            let schema = table_mutator
+                                .schema()
+                                .ok_or_else(|| internal_err!("Table {table} has no schema"))?;
+                            let excluded_columns = excluded_columns
+                                .iter()
+                                .map(String::as_str)
+                                .collect::<Vec<_>>();
+                            (
+                                row_filter
+                                    .map(|row_filter| row_filter.lower(dialect, schema))
+                                    .transpose()
+                                    .map_err(|e| {
+                                        ReadySetError::ReplicationFailed(format!(
+                                            "Invalid replication row filter for table {table}: {e}"
+                                        ))
+                                    })?,
+                                Some(ExcludedColumnIndices::new(
+                                    schema,
+                                    &excluded_columns,
+                                    dialect.engine() == SqlEngine::MySQL,
+                                )?),
+                            )
+                        }
+                        None => (None, None),
+                    }
+                };
+            self.row_predicates.insert(table.clone(), row_predicate);
+            if let Some(excluded_columns) = excluded_columns {
+                self.excluded_columns
+                    .insert(table.clone(), excluded_columns);
+            }
+        }
+        Ok(())
+    }
+
+    /// Remove the changes to rows that don't satisfy the row filter of `table` from `actions`, if
+    /// the table has a row filter, and clear the values of the table's columns that are excluded
+    /// from replication
+    async fn filter_rows(
+        &mut self,
+        table: &Relation,
+        actions: Vec<TableOperation>,
+    ) -> ReadySetResult<Vec<TableOperation>> {
+        self.load_table_filters(table).await?;
+
+        let mut actions = match self.row_predicates.get(table) {
+            Some(Some(row_predicate)) => {
+                let mut filtered = Vec::with_capacity(actions.len());
+                for action in row_predicate.filter_actions(actions)? {
+                    match action {
+                        FilteredAction::Perform(action) => filtered.push(action),
+                        FilteredAction::FetchRow { key } => {
+                            let row = self
+                                .connector
+                                .fetch_row(
+                                    table,
+                                    &row_predicate.table_columns,
+                                    &row_predicate.key_columns,
+                                    &key,
+                                )
+                                .await?;
+                            filtered.extend(row_predicate.filter_fetched_row(key, row)?);
+                        }
+                    }
+                }
+                filtered
+            }
+            _ => actions,
+        };
+        if let Some(excluded_columns) = self.excluded_columns.get(table) {
+            excluded_columns.clear_actions(&mut actions);
+        }
+        Ok(actions)
+    }
+
+    /// Returns true if actions for `table` at `pos` should be skipped, either because the table
+    /// is already past `pos` or because we're not interested in the table
+    fn skip_table_action(
+        &self,
+        table: &Relation,
+        pos: &ReplicationOffset,
+        catchup: bool,
+    ) -> ReadySetResult<bool> {
+        match self.replication_offsets.tables.get(table) {
+            Some(Some(cur)) if pos <= cur => {
+                if !catchup {
+                    warn!(%table, %pos, %cur, "Skipping table action for earlier entry");
+                }
+                return Ok(true);
+            }
+            _ => {}
+        }
+
+        Ok(!self.table_filter.should_be_processed(
+            table.schema.as_deref().ok_or_else(|| {
+                internal_err!("All tables should have a schema in the replicator")
+            })?,
+            &table.name,
+        ))
+    }
+
     /// Handle a single BinlogAction by calling the proper ReadySet RPC. If `catchup` is set,
     /// we will not log warnings for skipping entries, as we may iterate over many entries tables
     /// have already seen when catching each table up to the current binlog offset.
@@ -669,25 +1015,12 @@ impl NoriaAdapter {
                 }
             }
             ReplicationAction::TableAction { table, .. } => {
-                match self.replication_offsets.tables.get(table) {
-                    Some(Some(cur)) if pos <= *cur => {
-                        if !catchup {
-                            warn!(%table, %pos, %cur, "Skipping table action for earlier entry");
-                        }
-                        return Ok(());
-                    }
-                    _ => {}
-                }
-
-                if !self.table_filter.should_be_processed(
-                    table.schema.as_deref().ok_or_else(|| {
-                        internal_err!("All tables should have a schema in the replicator")
-                    })?,
-                    &table.name,
-                ) {
+                if self.skip_table_action(table, &pos, catchup)? {
                     return Ok(());
                 }
             }
+            // Tables in a transaction are skipped individually below
+            ReplicationAction::TransactionAction { .. } => {}
         }
 
         match action {
@@ -699,6 +1032,15 @@ impl NoriaAdapter {
                 actions,
                 txid,
             } => self.handle_table_actions(table, actions, txid, pos).await,
+            ReplicationAction::TransactionAction { tables, txid } => {
+                let mut applied = Vec::with_capacity(tables.len());
+                for (table, actions) in tables {
+                    if !self.skip_table_action(&table, &pos, catchup)? {
+                        applied.push((table, actions));
+                    }
+                }
+                self.handle_transaction_actions(applied, txid, pos).await
+            }
             ReplicationAction::LogPosition => self.handle_log_position(pos).await,
         }
     }
@@ -740,7 +1082,9 @@ impl NoriaAdapter {
     /// When schema changes there is a risk the cached mutators will no longer be in sync
     /// and we need to drop them all
     fn clear_mutator_cache(&mut self) {
-        self.mutator_map.clear()
+        self.mutator_map.clear();
+        self.row_predicates.clear();
+        self.excluded_columns.clear();
     }
 
     /// Get a mutator for a noria table from the cache if available, or fetch a new one
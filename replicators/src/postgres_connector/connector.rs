@@ -2,11 +2,12 @@ use async_trait::async_trait;
 use database_utils::UpstreamConfig;
 use futures::FutureExt;
 use launchpad::select;
-use nom_sql::Relation;
+use nom_sql::{Relation, SqlIdentifier};
 use postgres_native_tls::MakeTlsConnector;
 use readyset::replication::ReplicationOffset;
 use readyset::{ReadySetError, ReadySetResult, TableOperation};
-use readyset_errors::invariant;
+use readyset_data::DfValue;
+use readyset_errors::{internal, internal_err, invariant};
 use tokio_postgres as pgsql;
 use tracing::{debug, error, info, trace, warn};
 
@@ -40,8 +41,19 @@ pub struct PostgresWalConnector {
     peek: Option<(WalEvent, i64)>,
     /// If we just want to continue reading the log from a previous point
     next_position: Option<PostgresPosition>,
+    /// Whether we're currently reading the changes of a transaction, between its `BEGIN` and
+    /// `COMMIT`
+    in_transaction: bool,
+    /// The position of the commit of a transaction whose last actions we've just returned, which
+    /// we report with a [`ReplicationAction::LogPosition`] next
+    commit_position: Option<PostgresPosition>,
     /// The replication slot if was created for this connector
     pub(crate) replication_slot: Option<CreatedSlot>,
+    /// The configuration to make regular (non-replication) connections to the database with
+    pg_config: pgsql::Config,
+    tls_connector: MakeTlsConnector,
+    /// A regular connection used to fetch rows, which is only made once it's first needed
+    fetch_client: Option<pgsql::Client>,
 }
 
 /// The decoded response to `IDENTIFY_SYSTEM`
@@ -91,9 +103,11 @@ impl PostgresWalConnector {
         if !config.disable_setup_ddl_replication {
             setup_ddl_replication(pg_config.clone(), tls_connector.clone()).await?;
         }
-        pg_config.dbname(dbname.as_ref()).set_replication_database();
+        pg_config.dbname(dbname.as_ref());
+        let mut replication_config = pg_config.clone();
+        replication_config.set_replication_database();
 
-        let (client, connection) = pg_config.connect(tls_connector).await?;
+        let (client, connection) = replication_config.connect(tls_connector.clone()).await?;
         let connection_handle = tokio::spawn(connection);
 
         let mut connector = PostgresWalConnector {
@@ -101,8 +115,13 @@ impl PostgresWalConnector {
             connection_handle,
             reader: None,
             peek: None,
+            in_transaction: false,
+            commit_position: None,
             next_position,
             replication_slot: None,
+            pg_config,
+            tls_connector,
+            fetch_client: None,
         };
 
         if next_position.is_none() {
@@ -449,6 +468,10 @@ impl Connector for PostgresWalConnector {
                 "We should either have no current table, or the current table should have a schema"
             );
 
+            if let Some(pos) = self.commit_position.take() {
+                return Ok((ReplicationAction::LogPosition, pos.into()));
+            }
+
             // Don't accumulate too many actions between calls
             if actions.len() > MAX_QUEUED_ACTIONS {
                 return Ok((
@@ -489,6 +512,26 @@ impl Connector for PostgresWalConnector {
             // Check if next event is for another table, in which case we have to flush the events
             // accumulated for this table and store the next event in `peek`.
             match &mut event {
+                WalEvent::Keepalive { wants_response } => {
+                    if *wants_response {
+                        self.send_standy_status_update(last_pos.into())?;
+                    }
+
+                    // Outside of a transaction, every transaction that committed before the
+                    // keepalive's position has already been sent to us, and since we flush on
+                    // commit, all of their actions have been returned. That means we can advance
+                    // our position up to the keepalive, even if the WAL in between contained no
+                    // changes that we replicate - which is what lets reads waiting on a position
+                    // in the WAL (for read-your-writes consistency) make progress.
+                    let pos = PostgresPosition::from(lsn);
+                    if !self.in_transaction
+                        && actions.is_empty()
+                        && pos > PostgresPosition::from(last_pos)
+                    {
+                        return Ok((ReplicationAction::LogPosition, pos.into()));
+                    }
+                    continue;
+                }
                 WalEvent::Truncate { tables } => {
                     let (matching, mut other_tables) =
                         tables.drain(..).partition::<Vec<_>, _>(|(schema, table)| {
@@ -600,13 +643,21 @@ impl Connector for PostgresWalConnector {
                         ));
                     }
                 }
-                WalEvent::WantsKeepaliveResponse => {
-                    self.send_standy_status_update(last_pos.into())?;
+                WalEvent::Keepalive { .. } => {
+                    internal!("Keepalives are handled before tracking the LSN")
                 }
+                WalEvent::Begin => self.in_transaction = true,
                 WalEvent::Commit => {
+                    self.in_transaction = false;
+                    // Every table has now been replicated up to the commit, including the ones the
+                    // transaction didn't modify, so we report its position. Otherwise, those tables
+                    // would only catch up on the next keepalive, and reads waiting on the
+                    // transaction (for read-your-writes consistency) would have to wait for it.
+                    let pos = PostgresPosition::from(lsn);
                     if !actions.is_empty() {
                         // On commit we flush, because there is no knowing when the next commit is
                         // coming
+                        self.commit_position = Some(pos);
                         return Ok((
                             ReplicationAction::TableAction {
                                 table: cur_table,
@@ -616,6 +667,9 @@ impl Connector for PostgresWalConnector {
                             cur_lsn.into(),
                         ));
                     }
+                    if pos > PostgresPosition::from(last_pos) {
+                        return Ok((ReplicationAction::LogPosition, pos.into()));
+                    }
                 }
                 WalEvent::Insert { tuple, .. } => actions.push(TableOperation::Insert(tuple)),
                 WalEvent::DeleteRow { tuple, .. } => {
@@ -639,4 +693,67 @@ impl Connector for PostgresWalConnector {
             }
         }
     }
+
+    async fn fetch_row(
+        &mut self,
+        table: &Relation,
+        columns: &[SqlIdentifier],
+        key_columns: &[SqlIdentifier],
+        key: &[DfValue],
+    ) -> ReadySetResult<Option<Vec<DfValue>>> {
+        if key_columns.len() != key.len() {
+            internal!("Key of row of table {table} doesn't match its primary key");
+        }
+        let schema = table
+            .schema
+            .as_ref()
+            .ok_or_else(|| internal_err!("All tables should have a schema in the replicator"))?;
+
+        let client = match &mut self.fetch_client {
+            Some(client) => client,
+            None => {
+                let (client, connection) =
+                    self.pg_config.connect(self.tls_connector.clone()).await?;
+                // The connection finishes once the client is dropped
+                tokio::spawn(connection);
+                self.fetch_client.insert(client)
+            }
+        };
+
+        let query = format!(
+            "SELECT {} FROM \"{}\".\"{}\" WHERE {}",
+            columns
+                .iter()
+                .map(|col| format!("\"{col}\""))
+                .collect::<Vec<_>>()
+                .join(", "),
+            schema,
+            table.name,
+            key_columns
+                .iter()
+                .enumerate()
+                .map(|(i, col)| format!("\"{col}\" = ${}", i + 1))
+                .collect::<Vec<_>>()
+                .join(" AND ")
+        );
+        let params = key
+            .iter()
+            .map(|value| value as &(dyn pgsql::types::ToSql + Sync))
+            .collect::<Vec<_>>();
+        let row = match client.query_opt(query.as_str(), &params).await {
+            Ok(row) => row,
+            Err(error) => {
+                // Reconnect next time, in case the connection was lost
+                self.fetch_client = None;
+                return Err(error.into());
+            }
+        };
+        row.map(|row| {
+            (0..columns.len())
+                .map(|i| row.try_get::<_, DfValue>(i))
+                .collect::<Result<Vec<_>, _>>()
+        })
+        .transpose()
+        .map_err(Into::into)
+    }
 }
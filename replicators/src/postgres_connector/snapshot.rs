@@ -2,10 +2,10 @@ use std::convert::{TryFrom, TryInto};
 use std::error::Error;
 use std::fmt::{self, Display};
 use std::future;
-use std::time::Instant;
 
 use futures::stream::FuturesUnordered;
 use futures::{pin_mut, StreamExt, TryFutureExt};
+use itertools::Itertools;
 use metrics::register_gauge;
 use nom_sql::{
     parse_key_specification_string, parse_sql_type, Column, ColumnConstraint, ColumnSpecification,
@@ -14,15 +14,21 @@ use nom_sql::{
 use postgres_types::{accepts, FromSql, Kind, Type};
 use readyset::metrics::recorded;
 use readyset::recipe::changelist::{Change, ChangeList};
-use readyset::{ReadySetError, ReadySetResult};
+use readyset::replication::ReplicationOffset;
+use readyset::{ReadySetError, ReadySetResult, Table};
 use readyset_data::{DfType, DfValue, Dialect as DataDialect, PgEnumMetadata};
 use readyset_errors::{internal, internal_err, unsupported};
+use tokio::sync::Mutex;
 use tokio_postgres as pgsql;
 use tracing::{debug, error, info, info_span, trace, warn, Instrument};
 
 use super::connector::CreatedSlot;
 use super::PostgresPosition;
 use crate::db_util::CreateSchema;
+use crate::excluded_columns::{check_key_columns, resolve_columns, select_list};
+use crate::snapshot::{
+    CheckpointStore, SnapshotCheckpoint, SnapshotOptions, SnapshotProgress, TableCheckpoint,
+};
 use crate::table_filter::TableFilter;
 
 const BATCH_SIZE: usize = 1024; // How many queries to buffer before pushing to ReadySet
@@ -33,6 +39,11 @@ pub struct PostgresReplicator<'a> {
     pub(crate) noria: &'a mut readyset::ReadySetHandle,
     /// Filters out tables we are not interested in
     pub(crate) table_filter: TableFilter,
+    /// Used to open the additional connections that tables are copied over
+    pub(crate) pgsql_opts: pgsql::Config,
+    pub(crate) tls_connector: postgres_native_tls::MakeTlsConnector,
+    /// Records the progress of the snapshot, so that it can be resumed if interrupted
+    pub(crate) checkpoints: CheckpointStore,
 }
 
 #[derive(Debug)]
@@ -403,6 +414,16 @@ impl TableDescription {
             .ok_or_else(|| internal_err!("All tables must have a schema in the replicator"))
     }
 
+    /// Returns the columns in the table's primary key, if it has one
+    fn primary_key(&self) -> Option<&[Column]> {
+        self.constraints
+            .iter()
+            .find_map(|constraint| match &constraint.definition {
+                TableKey::PrimaryKey { columns, .. } => Some(columns.as_slice()),
+                _ => None,
+            })
+    }
+
     fn try_into_change(self) -> ReadySetResult<Change> {
         Ok(Change::CreateTable(CreateTableStatement {
             table: self.name.clone(),
@@ -436,21 +457,50 @@ impl TableDescription {
         }))
     }
 
-    /// Copy a table's contents from PostgreSQL to ReadySet
-    async fn dump<'a>(
+    /// Copy a table's contents from PostgreSQL to ReadySet.
+    ///
+    /// Tables with a primary key are copied in chunks, starting after the last key recorded in
+    /// `checkpoint`, and the progress of the copy is recorded in `checkpoints` after each chunk.
+    /// If the table has a row filter, only the rows satisfying its predicate `row_filter` are
+    /// copied, and `NULL` is copied in place of the values of the `excluded_columns`.
+    #[allow(clippy::too_many_arguments)]
+    async fn dump(
         &self,
-        transaction: &'a pgsql::Transaction<'a>,
-        mut noria_table: readyset::Table,
-        snapshot_report_interval_secs: u16,
+        transaction: &pgsql::Transaction<'_>,
+        noria_table: &mut Table,
+        checkpoint: TableCheckpoint,
+        checkpoints: &CheckpointStore,
+        row_filter: Option<&str>,
+        excluded_columns: &[&str],
+        options: SnapshotOptions,
     ) -> ReadySetResult<()> {
-        let mut cnt = 0;
+        let column_names = self
+            .columns
+            .iter()
+            .map(|c| c.name.as_str())
+            .collect::<Vec<_>>();
+        let excluded_columns = resolve_columns(&self.name, excluded_columns, &column_names, false)?;
+        check_key_columns(
+            &self.name,
+            &excluded_columns,
+            self.primary_key()
+                .into_iter()
+                .flatten()
+                .map(|k| k.name.as_str()),
+        )?;
+        let columns = select_list(&column_names, &excluded_columns, '"', |i| {
+            format!("NULL::{}", self.columns[i].sql_type)
+        });
 
         let nrows = transaction
             .query_one(
                 format!(
-                    "SELECT count(*) AS nrows FROM \"{}\".\"{}\"",
+                    "SELECT count(*) AS nrows FROM \"{}\".\"{}\"{}",
                     self.schema()?,
                     &self.name.name,
+                    row_filter
+                        .map(|predicate| format!(" WHERE ({predicate})"))
+                        .unwrap_or_default()
                 )
                 .as_str(),
                 &[],
@@ -458,12 +508,134 @@ impl TableDescription {
             .await?
             .try_get::<_, i64>("nrows")?;
 
-        // The most efficient way to copy an entire table is COPY BINARY
-        let query = format!(
-            "COPY \"{}\".\"{}\" TO stdout BINARY",
-            self.schema()?,
-            self.name.name
+        if checkpoint.last_key.is_some() {
+            info!(rows = %nrows, "Resuming snapshotting");
+        } else {
+            info!(rows = %nrows, "Snapshotting started");
+        }
+        let mut progress = SnapshotProgress::new(
+            nrows as u64,
+            options.report_interval_secs,
+            register_gauge!(
+                recorded::REPLICATOR_SNAPSHOT_PERCENT,
+                "schema" => self.schema()?.to_string(),
+                "name" => self.name.name.to_string()
+            ),
         );
+
+        let result = match self.primary_key() {
+            Some(key) => {
+                let chunks = ChunkCheckpointer::new(
+                    self.key_indices(key)?,
+                    checkpoint,
+                    checkpoints,
+                    options.chunk_rows,
+                );
+                // COPY is much faster than repeatedly selecting chunks of rows, but can't start
+                // after a given key, so we only use it if we aren't resuming a previous snapshot
+                if chunks.checkpoint.last_key.is_some() {
+                    self.dump_chunks(
+                        transaction,
+                        noria_table,
+                        chunks,
+                        &columns,
+                        row_filter,
+                        &mut progress,
+                    )
+                    .await
+                } else {
+                    self.dump_all(
+                        transaction,
+                        noria_table,
+                        Some(columns.as_str()),
+                        row_filter,
+                        Some(chunks),
+                        &mut progress,
+                    )
+                    .await
+                }
+            }
+            None => {
+                self.dump_all(
+                    transaction,
+                    noria_table,
+                    (!excluded_columns.is_empty()).then(|| columns.as_str()),
+                    row_filter,
+                    None,
+                    &mut progress,
+                )
+                .await
+            }
+        };
+        if result.is_err() {
+            progress.failed();
+        }
+        result?;
+
+        info!(rows_replicated = %progress.rows(), "Snapshotting finished");
+        progress.finished();
+
+        Ok(())
+    }
+
+    /// Returns the indices of the columns in the primary key `key`
+    fn key_indices(&self, key: &[Column]) -> ReadySetResult<Vec<usize>> {
+        key.iter()
+            .map(|k| {
+                self.columns
+                    .iter()
+                    .position(|c| c.name == k.name.as_str())
+                    .ok_or_else(|| {
+                        internal_err!("Primary key column {} not found in {}", k.name, self.name)
+                    })
+            })
+            .collect()
+    }
+
+    /// Returns the names of the columns at `key_indices`, quoted and separated by commas
+    fn key_columns(&self, key_indices: &[usize]) -> String {
+        key_indices
+            .iter()
+            .map(|i| format!("\"{}\"", self.columns[*i].name))
+            .join(", ")
+    }
+
+    /// Copy all of a table's rows in a single pass, selecting only `columns` if given.
+    ///
+    /// If `chunks` is given the rows are copied in order of the table's primary key, and the
+    /// progress of the copy is checkpointed after every chunk of rows.
+    async fn dump_all(
+        &self,
+        transaction: &pgsql::Transaction<'_>,
+        noria_table: &mut Table,
+        columns: Option<&str>,
+        row_filter: Option<&str>,
+        mut chunks: Option<ChunkCheckpointer<'_>>,
+        progress: &mut SnapshotProgress,
+    ) -> ReadySetResult<()> {
+        // The most efficient way to copy an entire table is COPY BINARY
+        let order_by = chunks
+            .as_ref()
+            .map(|chunks| self.key_columns(&chunks.key_indices));
+        let query = match (columns, row_filter, order_by) {
+            (None, None, None) => format!(
+                "COPY \"{}\".\"{}\" TO stdout BINARY",
+                self.schema()?,
+                self.name.name
+            ),
+            (columns, row_filter, order_by) => format!(
+                "COPY (SELECT {} FROM \"{}\".\"{}\"{}{}) TO stdout BINARY",
+                columns.unwrap_or("*"),
+                self.schema()?,
+                self.name.name,
+                row_filter
+                    .map(|predicate| format!(" WHERE ({predicate})"))
+                    .unwrap_or_default(),
+                order_by
+                    .map(|key_columns| format!(" ORDER BY {key_columns}"))
+                    .unwrap_or_default()
+            ),
+        };
         let rows = transaction.copy_out(query.as_str()).await?;
 
         let type_map: Vec<_> = self.columns.iter().map(|c| c.pg_type.clone()).collect();
@@ -473,88 +645,207 @@ impl TableDescription {
 
         let mut noria_rows = Vec::with_capacity(BATCH_SIZE);
 
-        info!(rows = %nrows, "Snapshotting started");
-        let progress_percentage_metric: metrics::Gauge = register_gauge!(
-            recorded::REPLICATOR_SNAPSHOT_PERCENT,
-            "schema" => self.schema()?.to_string(),
-            "name" => self.name.name.to_string()
-        );
-        let start_time = Instant::now();
-        let mut last_report_time = start_time;
-        let snapshot_report_interval_secs = snapshot_report_interval_secs as u64;
-
         while let Some(Ok(row)) = binary_rows.next().await {
             let noria_row = (0..type_map.len())
                 .map(|i| row.try_get::<DfValue>(i))
-                .collect::<Result<Vec<_>, _>>()
-                .map_err(|err| {
-                    progress_percentage_metric.set(0.0);
-                    err
-                })?;
+                .collect::<Result<Vec<_>, _>>()?;
 
+            if let Some(chunks) = &mut chunks {
+                chunks.add_row(&noria_row);
+            }
             noria_rows.push(noria_row);
-            cnt += 1;
+            progress.add_row();
 
             // Accumulate as many inserts as possible before calling into noria, as
             // those calls can be quite expensive
-            if noria_rows.len() >= BATCH_SIZE {
+            let full_chunk = chunks.as_mut().filter(|chunks| chunks.is_full());
+            if noria_rows.len() >= BATCH_SIZE || full_chunk.is_some() {
                 noria_table
                     .insert_many(std::mem::replace(
                         &mut noria_rows,
                         Vec::with_capacity(BATCH_SIZE),
                     ))
-                    .await
-                    .map_err(|err| {
-                        progress_percentage_metric.set(0.0);
-                        err
-                    })?;
+                    .await?;
             }
-
-            if snapshot_report_interval_secs != 0
-                && last_report_time.elapsed().as_secs() > snapshot_report_interval_secs
-            {
-                last_report_time = Instant::now();
-                let estimate =
-                    crate::estimate_remaining_time(start_time.elapsed(), cnt as f64, nrows as f64);
-                let progress_percent = (cnt as f64 / nrows as f64) * 100.;
-                let progress = format!("{:.2}%", progress_percent);
-                info!(rows_replicated = %cnt, %progress, %estimate, "Snapshotting progress");
-                progress_percentage_metric.set(progress_percent);
+            if let Some(chunks) = full_chunk {
+                chunks.finish_chunk(noria_table, &self.name).await?;
             }
         }
 
         if !noria_rows.is_empty() {
-            noria_table.insert_many(noria_rows).await.map_err(|err| {
-                progress_percentage_metric.set(0.0);
-                err
-            })?;
+            noria_table.insert_many(noria_rows).await?;
+        }
+        if let Some(chunks) = &mut chunks {
+            chunks.finish_chunk(noria_table, &self.name).await?;
         }
 
-        info!(rows_replicated = %cnt, "Snapshotting finished");
-        progress_percentage_metric.set(100.0);
+        Ok(())
+    }
+
+    /// Copy a table's rows in chunks ordered by its primary key, selecting `columns`, starting
+    /// after the last key recorded in the checkpoint of `chunks`. Once each chunk has been
+    /// durably written to ReadySet, the key of its last row is checkpointed.
+    async fn dump_chunks(
+        &self,
+        transaction: &pgsql::Transaction<'_>,
+        noria_table: &mut Table,
+        mut chunks: ChunkCheckpointer<'_>,
+        columns: &str,
+        row_filter: Option<&str>,
+        progress: &mut SnapshotProgress,
+    ) -> ReadySetResult<()> {
+        let table = format!("\"{}\".\"{}\"", self.schema()?, self.name.name);
+        let key_columns = self.key_columns(&chunks.key_indices);
+        let chunk_rows = chunks.chunk_rows;
+        let row_filter = row_filter.map(|predicate| format!("({predicate})"));
+
+        loop {
+            let query = match &chunks.checkpoint.last_key {
+                Some(last_key) => format!(
+                    "SELECT {columns} FROM {table} WHERE ({key_columns}) > ({}){} \
+                     ORDER BY {key_columns} LIMIT {chunk_rows}",
+                    (1..=last_key.len()).map(|i| format!("${i}")).join(", "),
+                    row_filter
+                        .as_ref()
+                        .map(|predicate| format!(" AND {predicate}"))
+                        .unwrap_or_default()
+                ),
+                None => format!(
+                    "SELECT {columns} FROM {table}{} ORDER BY {key_columns} LIMIT {chunk_rows}",
+                    row_filter
+                        .as_ref()
+                        .map(|predicate| format!(" WHERE {predicate}"))
+                        .unwrap_or_default()
+                ),
+            };
+            let params = chunks.checkpoint.last_key.clone().unwrap_or_default();
+            let rows = transaction.query_raw(query.as_str(), &params).await?;
+            pin_mut!(rows);
+
+            let mut noria_rows = Vec::with_capacity(BATCH_SIZE);
+
+            while let Some(row) = rows.next().await {
+                let row = row?;
+                let noria_row = (0..self.columns.len())
+                    .map(|i| row.try_get::<_, DfValue>(i))
+                    .collect::<Result<Vec<_>, _>>()?;
+
+                chunks.add_row(&noria_row);
+                noria_rows.push(noria_row);
+                progress.add_row();
+
+                if noria_rows.len() >= BATCH_SIZE {
+                    noria_table
+                        .insert_many(std::mem::replace(
+                            &mut noria_rows,
+                            Vec::with_capacity(BATCH_SIZE),
+                        ))
+                        .await?;
+                }
+            }
+
+            if !noria_rows.is_empty() {
+                noria_table.insert_many(noria_rows).await?;
+            }
+
+            let chunk_full = chunks.is_full();
+            chunks.finish_chunk(noria_table, &self.name).await?;
+            if !chunk_full {
+                break;
+            }
+        }
 
         Ok(())
     }
 }
 
+/// Keeps track of the rows copied from a table with a primary key in chunks, so that the progress
+/// of the copy can be checkpointed once all the rows in a chunk have been written to ReadySet
+struct ChunkCheckpointer<'a> {
+    /// The indices of the columns in the table's primary key
+    key_indices: Vec<usize>,
+    /// The progress of copying the table as of the last completed chunk
+    checkpoint: TableCheckpoint,
+    checkpoints: &'a CheckpointStore,
+    /// The number of rows in each chunk
+    chunk_rows: u64,
+    /// The number of rows in the current chunk so far
+    chunk_len: u64,
+    /// The primary key of the last row in the current chunk
+    last_key: Option<Vec<DfValue>>,
+}
+
+impl<'a> ChunkCheckpointer<'a> {
+    fn new(
+        key_indices: Vec<usize>,
+        checkpoint: TableCheckpoint,
+        checkpoints: &'a CheckpointStore,
+        chunk_rows: u64,
+    ) -> Self {
+        ChunkCheckpointer {
+            key_indices,
+            checkpoint,
+            checkpoints,
+            chunk_rows,
+            chunk_len: 0,
+            last_key: None,
+        }
+    }
+
+    /// Record that `row` has been copied as part of the current chunk
+    fn add_row(&mut self, row: &[DfValue]) {
+        self.last_key = Some(self.key_indices.iter().map(|i| row[*i].clone()).collect());
+        self.chunk_len += 1;
+    }
+
+    /// Whether the current chunk has all the rows it should
+    fn is_full(&self) -> bool {
+        self.chunk_len >= self.chunk_rows
+    }
+
+    /// Checkpoint the rows in the current chunk, all of which must have been written to
+    /// `noria_table` already, and start the next chunk
+    async fn finish_chunk(
+        &mut self,
+        noria_table: &mut Table,
+        name: &Relation,
+    ) -> ReadySetResult<()> {
+        let last_key = match self.last_key.take() {
+            Some(last_key) => last_key,
+            None => return Ok(()),
+        };
+        self.chunk_len = 0;
+
+        // Setting the replication offset flushes the rows written so far to disk, which has
+        // to happen before we record them as copied
+        noria_table
+            .set_replication_offset(self.checkpoint.offset.clone())
+            .await?;
+        self.checkpoint.last_key = Some(last_key);
+        self.checkpoints
+            .set_table(name, self.checkpoint.clone())
+            .await
+    }
+}
+
 impl<'a> PostgresReplicator<'a> {
     pub(crate) async fn new(
         client: &'a mut pgsql::Client,
         noria: &'a mut readyset::ReadySetHandle,
         table_filter: TableFilter,
+        pgsql_opts: pgsql::Config,
+        tls_connector: postgres_native_tls::MakeTlsConnector,
+        checkpoints: CheckpointStore,
     ) -> ReadySetResult<PostgresReplicator<'a>> {
-        let transaction = client
-            .build_transaction()
-            .deferrable(true)
-            .isolation_level(pgsql::IsolationLevel::RepeatableRead)
-            .read_only(true)
-            .start()
-            .await?;
+        let transaction = snapshot_transaction(client).await?;
 
         Ok(PostgresReplicator {
             transaction,
             noria,
             table_filter,
+            pgsql_opts,
+            tls_connector,
+            checkpoints,
         })
     }
 
@@ -564,10 +855,15 @@ impl<'a> PostgresReplicator<'a> {
         &mut self,
         replication_slot: &CreatedSlot,
         create_schema: &mut CreateSchema,
-        snapshot_report_interval_secs: u16,
+        options: SnapshotOptions,
     ) -> ReadySetResult<()> {
         let wal_position = PostgresPosition::from(replication_slot.consistent_point).into();
-        self.set_snapshot(&replication_slot.snapshot_name).await?;
+        set_snapshot(&self.transaction, &replication_slot.snapshot_name).await?;
+
+        // Mark the snapshot as being in progress before assigning the schema a replication
+        // offset, so that an interrupted snapshot is resumed rather than being taken for a
+        // complete one
+        let checkpoint = self.checkpoints.start().await?;
 
         let mut table_list = self.get_table_list(TableKind::RegularTable).await?;
         let view_list = self.get_table_list(TableKind::View).await?;
@@ -696,58 +992,131 @@ impl<'a> PostgresReplicator<'a> {
 
         let replication_offsets = self.noria.replication_offsets().await?;
 
-        tables.drain_filter(|t| replication_offsets.has_table(&t.name)).for_each(|t|
-            info!(table = %t.name, "Replication offset already exists for table, skipping snapshot")
-        );
+        // Tables which were being copied when a snapshot was interrupted may have a replication
+        // offset, as it's set after each chunk is copied
+        tables
+            .drain_filter(|t| {
+                replication_offsets.has_table(&t.name) && checkpoint.table(&t.name).is_none()
+            })
+            .for_each(|t| {
+                info!(table = %t.name, "Replication offset already exists for table, skipping snapshot")
+            });
+
+        // Finally copy the tables into noria, over several connections at once which all read from
+        // the same snapshot
+        let workers = options.parallelism.min(tables.len());
+        let tables = Mutex::new(tables);
+        let mut dumpers = (0..workers)
+            .map(|_| {
+                self.dump_worker(
+                    self.noria.clone(),
+                    &tables,
+                    replication_slot,
+                    &wal_position,
+                    &checkpoint,
+                    options,
+                )
+            })
+            .collect::<FuturesUnordered<_>>();
+        while let Some(res) = dumpers.next().await {
+            res?;
+        }
+        drop(dumpers);
+
+        self.checkpoints.finish().await
+    }
+
+    /// Copy tables from `tables` into noria until there are none left, reading them over a new
+    /// connection that uses the snapshot exported by the replication slot
+    async fn dump_worker(
+        &self,
+        mut noria: readyset::ReadySetHandle,
+        tables: &Mutex<Vec<TableDescription>>,
+        replication_slot: &CreatedSlot,
+        wal_position: &ReplicationOffset,
+        checkpoint: &SnapshotCheckpoint,
+        options: SnapshotOptions,
+    ) -> ReadySetResult<()> {
+        let (mut client, connection) = self.pgsql_opts.connect(self.tls_connector.clone()).await?;
+        // The connection finishes once the client is dropped
+        tokio::spawn(connection);
+        let transaction = snapshot_transaction(&mut client).await?;
+        set_snapshot(&transaction, &replication_slot.snapshot_name).await?;
+
+        loop {
+            let table = match tables.lock().await.pop() {
+                Some(table) => table,
+                None => break,
+            };
 
-        // Finally copy each table into noria
-        for table in &tables {
-            // TODO: parallelize with a connection pool if performance here matters
             let span = info_span!("Replicating table", table = %table.name);
             span.in_scope(|| info!("Replicating table"));
-            let mut noria_table = self
-                .noria
+            let mut noria_table = noria
                 .table(table.name.clone())
                 .instrument(span.clone())
                 .await?;
 
-            noria_table.set_snapshot_mode(true).await?;
+            // If a previous snapshot copied some of the table already, resume copying it where
+            // that snapshot left off, otherwise start from scratch at the position of this one
+            let table_checkpoint = match checkpoint.table(&table.name) {
+                Some(table_checkpoint)
+                    if table_checkpoint.last_key.is_some() && table.primary_key().is_some() =>
+                {
+                    // Some of the rows after the checkpoint may have been written already, so
+                    // leave snapshot mode in order for inserts of keys that already exist to be
+                    // ignored
+                    noria_table.set_snapshot_mode(false).await?;
+                    table_checkpoint.clone()
+                }
+                _ => {
+                    noria_table.set_snapshot_mode(true).await?;
+                    TableCheckpoint::new(wal_position.clone())
+                }
+            };
+            self.checkpoints
+                .set_table(&table.name, table_checkpoint.clone())
+                .await?;
+            let offset = table_checkpoint.offset.clone();
+            let row_filter = table.schema().ok().and_then(|schema| {
+                self.table_filter
+                    .row_filter(schema.as_str(), table.name.name.as_str())
+            });
+            let excluded_columns = table
+                .schema()
+                .map(|schema| {
+                    self.table_filter
+                        .excluded_columns(schema.as_str(), table.name.name.as_str())
+                })
+                .unwrap_or_default();
 
             table
                 .dump(
-                    &self.transaction,
-                    noria_table,
-                    snapshot_report_interval_secs,
+                    &transaction,
+                    &mut noria_table,
+                    table_checkpoint,
+                    &self.checkpoints,
+                    row_filter.map(|row_filter| row_filter.predicate.as_str()),
+                    &excluded_columns,
+                    options,
                 )
                 .instrument(span.clone())
                 .await?;
-        }
 
-        let mut compacting = FuturesUnordered::new();
-        for table in tables {
-            let mut noria_table = self.noria.table(table.name.clone()).await?;
-            let wal_position = wal_position.clone();
-            compacting.push(async move {
-                let span = info_span!("Compacting table", table = %table.name);
-                span.in_scope(|| info!("Setting replication offset"));
-                if let Err(error) = noria_table
-                    .set_replication_offset(wal_position)
-                    .instrument(span.clone())
-                    .await
-                {
-                    span.in_scope(|| error!(%error, "Error setting replication offset"));
-                    return Err(error);
-                };
-                span.in_scope(|| info!("Set replication offset"));
-
-                span.in_scope(|| info!("Compacting table"));
-                noria_table.set_snapshot_mode(false).await?;
-                span.in_scope(|| info!("Compacting finished"));
-                ReadySetResult::Ok(())
-            })
-        }
-        while let Some(f) = compacting.next().await {
-            f?;
+            span.in_scope(|| info!("Setting replication offset"));
+            if let Err(error) = noria_table
+                .set_replication_offset(offset)
+                .instrument(span.clone())
+                .await
+            {
+                span.in_scope(|| error!(%error, "Error setting replication offset"));
+                return Err(error);
+            };
+            span.in_scope(|| info!("Set replication offset"));
+
+            span.in_scope(|| info!("Compacting table"));
+            noria_table.set_snapshot_mode(false).await?;
+            self.checkpoints.finish_table(&table.name).await?;
+            span.in_scope(|| info!("Compacting finished"));
         }
 
         Ok(())
@@ -801,13 +1170,6 @@ impl<'a> PostgresReplicator<'a> {
         res.into_iter().map(TryInto::try_into).collect()
     }
 
-    /// Assign the specific snapshot to the underlying transaction
-    async fn set_snapshot(&mut self, name: &str) -> Result<(), pgsql::Error> {
-        let query = format!("SET TRANSACTION SNAPSHOT '{}'", name);
-        self.transaction.query(query.as_str(), &[]).await?;
-        Ok(())
-    }
-
     /// Get a list of ReadySet cache tables and compare against the list of upstream tables and
     /// views, dropping any cache tables that are missing upstream.
     ///
@@ -869,6 +1231,29 @@ impl<'a> PostgresReplicator<'a> {
     }
 }
 
+/// Start the kind of transaction that tables are copied in
+async fn snapshot_transaction(
+    client: &mut pgsql::Client,
+) -> Result<pgsql::Transaction<'_>, pgsql::Error> {
+    client
+        .build_transaction()
+        .deferrable(true)
+        .isolation_level(pgsql::IsolationLevel::RepeatableRead)
+        .read_only(true)
+        .start()
+        .await
+}
+
+/// Assign the specific snapshot to the transaction
+async fn set_snapshot(
+    transaction: &pgsql::Transaction<'_>,
+    name: &str,
+) -> Result<(), pgsql::Error> {
+    let query = format!("SET TRANSACTION SNAPSHOT '{}'", name);
+    transaction.query(query.as_str(), &[]).await?;
+    Ok(())
+}
+
 #[cfg(test)]
 mod tests {
     use nom_sql::{parse_query, Column, Dialect, SqlQuery, TableKey};
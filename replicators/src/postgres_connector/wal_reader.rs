@@ -38,7 +38,11 @@ pub struct WalReader {
 
 #[derive(Debug)]
 pub(crate) enum WalEvent {
-    WantsKeepaliveResponse,
+    Keepalive {
+        /// Whether the server wants us to reply to this keepalive as soon as possible
+        wants_response: bool,
+    },
+    Begin,
     Commit,
     Insert {
         schema: String,
@@ -106,8 +110,13 @@ impl WalReader {
             };
 
             let (end, record) = match data {
-                WalData::Keepalive { end, reply, .. } if reply == 1 => {
-                    return Ok((WalEvent::WantsKeepaliveResponse, end))
+                WalData::Keepalive { end, reply, .. } => {
+                    return Ok((
+                        WalEvent::Keepalive {
+                            wants_response: reply == 1,
+                        },
+                        end,
+                    ))
                 }
                 WalData::XLogData { end, data, .. } => (end, data),
                 msg => {
@@ -307,7 +316,7 @@ impl WalReader {
                         }
                     }
                 }
-                WalRecord::Begin { .. } => {}
+                WalRecord::Begin { .. } => return Ok((WalEvent::Begin, end)),
                 WalRecord::Message {
                     prefix,
                     payload,
@@ -0,0 +1,650 @@
+//! Row-level replication filters, which restrict the rows of a table that are replicated to the
+//! ones satisfying a predicate, for example `orders WHERE region = 'eu'`.
+//!
+//! While snapshotting, the predicate is added to the queries that read the table from the upstream
+//! database, and while replicating it's evaluated against the rows in each replication event for
+//! the table, so that rows that don't satisfy it never reach the table's base node. A change which
+//! makes a row stop satisfying the predicate deletes the row, and one which makes a row start
+//! satisfying it inserts the row.
+//!
+//! Some updates only contain the columns that changed (for example, PostgreSQL omits the values of
+//! unchanged TOASTed columns). If none of the columns the predicate references changed, the update
+//! is applied as usual. If the changed columns make the row stop satisfying the predicate the row
+//! is deleted. Otherwise the row may have only just started satisfying the predicate, in which case
+//! it was never replicated, and the update can't insert it since its other values aren't known.
+//! Instead, the whole row is fetched from the upstream database and replaces the replicated row,
+//! if it satisfies the predicate. The fetched row may already reflect changes made after the
+//! update, but those changes are replicated afterwards too, so the row ends up in the same state.
+//!
+//! Since the predicate is evaluated by the upstream database while snapshotting but by ReadySet
+//! while replicating, it has to mean the same thing to both:
+//!
+//! - Filters are lowered against their table's schema when replication starts, which rejects
+//!   predicates that reference unknown columns or use functions ReadySet can't evaluate.
+//! - Predicates with an explicit `COLLATE` can't be parsed, and are rejected.
+//! - Both follow SQL's three-valued logic, so rows for which the predicate is `NULL` (for example
+//!   `region = 'eu'` where `region` is `NULL`) are never replicated.
+//! - ReadySet compares text case-sensitively, whereas MySQL's default collations are
+//!   case-insensitive, so MySQL predicates which compare text columns whose collation isn't binary
+//!   are rejected.
+
+use dataflow_expression::{Expr as DataflowExpr, LowerContext};
+use nom_locate::LocatedSpan;
+use nom_sql::analysis::visit::{self, Visitor};
+use nom_sql::analysis::ReferredColumns;
+use nom_sql::{
+    parse_select_statement, replicator_table_list, BinaryOperator, Column, ColumnConstraint,
+    ColumnSpecification, CreateTableOption, CreateTableStatement, Dialect, Expr, InValue, Relation,
+    SqlIdentifier, SqlType, TableKey,
+};
+use readyset::{Modification, TableOperation};
+use readyset_data::dialect::SqlEngine;
+use readyset_data::{DfType, DfValue};
+use readyset_errors::{invalid_err, ReadySetError, ReadySetResult};
+
+/// A predicate that the rows of a table have to satisfy in order to be replicated
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct RowFilter {
+    /// The predicate as it was written, in the SQL dialect of the upstream database, which is
+    /// added to the queries used to snapshot the table
+    pub(crate) predicate: String,
+    /// The parsed predicate
+    expr: Expr,
+}
+
+fn parse_error() -> ReadySetError {
+    ReadySetError::ReplicationFailed("Unable to parse replication row filters".to_string())
+}
+
+/// Split a row filter into the table and the predicate on either side of its `WHERE` keyword
+fn split_where(filter: &str) -> Option<(&str, &str)> {
+    let (i, _) = filter
+        .to_ascii_lowercase()
+        .match_indices("where")
+        .find(|(i, _)| {
+            filter[..*i].ends_with(char::is_whitespace)
+                && filter[(i + 5)..].starts_with(char::is_whitespace)
+        })?;
+    Some((filter[..i].trim(), filter[(i + 5)..].trim()))
+}
+
+/// Parse a semicolon-separated list of row filters, each of the form `<table> WHERE <predicate>`,
+/// as given to the `--replication-row-filters` option. Tables without a schema are assumed to be
+/// in `default_schema`.
+pub(crate) fn parse_row_filters(
+    dialect: Dialect,
+    filters: &str,
+    default_schema: Option<&SqlIdentifier>,
+) -> ReadySetResult<Vec<(Relation, RowFilter)>> {
+    filters
+        .split(';')
+        .map(str::trim)
+        .filter(|filter| !filter.is_empty())
+        .map(|filter| {
+            let (table, predicate) = split_where(filter).ok_or_else(parse_error)?;
+
+            let mut tables =
+                match replicator_table_list(dialect)(LocatedSpan::new(table.as_bytes())) {
+                    Ok((rem, tables)) if rem.is_empty() => tables,
+                    _ => return Err(parse_error()),
+                };
+            let table = match (tables.pop(), tables.is_empty()) {
+                (Some(table), true) if table.name != "*" => table,
+                _ => return Err(parse_error()),
+            };
+            let schema = table
+                .schema
+                .or_else(|| default_schema.cloned())
+                .ok_or_else(|| {
+                    ReadySetError::ReplicationFailed(format!(
+                        "No database and no default database for table {}",
+                        table.name
+                    ))
+                })?;
+
+            let expr =
+                parse_select_statement(dialect, format!("SELECT * FROM t WHERE {predicate}"))
+                    .ok()
+                    .and_then(|select| select.where_clause)
+                    .ok_or_else(parse_error)?;
+
+            Ok((
+                Relation {
+                    schema: Some(schema),
+                    name: table.name,
+                },
+                RowFilter {
+                    predicate: predicate.to_owned(),
+                    expr,
+                },
+            ))
+        })
+        .collect()
+}
+
+/// Resolves the columns referenced by a row filter to the columns of the table it filters
+#[derive(Clone)]
+struct TableLowerContext<'a> {
+    schema: &'a CreateTableStatement,
+    dialect: readyset_data::Dialect,
+}
+
+impl<'a> LowerContext for TableLowerContext<'a> {
+    fn resolve_column(&self, col: Column) -> ReadySetResult<(usize, DfType)> {
+        let (index, field) = self
+            .schema
+            .fields
+            .iter()
+            .enumerate()
+            .find(|(_, field)| field.column.name == col.name)
+            .ok_or_else(|| {
+                invalid_err!(
+                    "Column {} in replication row filter not found in table {}",
+                    col.name,
+                    self.schema.table
+                )
+            })?;
+        Ok((
+            index,
+            DfType::from_sql_type(&field.sql_type, self.dialect, |_| None)?,
+        ))
+    }
+
+    fn resolve_type(&self, _ty: Relation) -> Option<DfType> {
+        None
+    }
+}
+
+/// Collects the columns whose values are compared by an expression, either with each other or with
+/// other values
+#[derive(Default)]
+struct ComparedColumns<'ast> {
+    columns: Vec<&'ast Column>,
+}
+
+impl<'ast> Visitor<'ast> for ComparedColumns<'ast> {
+    type Error = !;
+
+    fn visit_expr(&mut self, expr: &'ast Expr) -> Result<(), Self::Error> {
+        use BinaryOperator::*;
+
+        let compared: Vec<&Expr> = match expr {
+            Expr::BinaryOp {
+                lhs,
+                op:
+                    Like | NotLike | ILike | NotILike | Equal | NotEqual | Greater | GreaterOrEqual
+                    | Less | LessOrEqual,
+                rhs,
+            } => vec![lhs.as_ref(), rhs.as_ref()],
+            Expr::Between {
+                operand, min, max, ..
+            } => vec![operand.as_ref(), min.as_ref(), max.as_ref()],
+            Expr::In {
+                lhs,
+                rhs: InValue::List(exprs),
+                ..
+            } => std::iter::once(lhs.as_ref()).chain(exprs).collect(),
+            _ => vec![],
+        };
+        for expr in compared {
+            self.columns.extend(expr.referred_columns());
+        }
+        visit::walk_expr(self, expr)
+    }
+}
+
+/// Returns whether MySQL compares the values of `field` the same way ReadySet does, which isn't
+/// the case for text columns unless their collation is binary. Columns without a collation of
+/// their own use their table's, and tables without one use the server's default, which is
+/// case-insensitive.
+fn mysql_compares_exactly(schema: &CreateTableStatement, field: &ColumnSpecification) -> bool {
+    if !matches!(
+        field.sql_type,
+        SqlType::Char(_)
+            | SqlType::VarChar(_)
+            | SqlType::TinyText
+            | SqlType::MediumText
+            | SqlType::LongText
+            | SqlType::Text
+            | SqlType::Enum(_)
+    ) {
+        return true;
+    }
+
+    let is_binary = |collation: &str| {
+        let collation = collation.to_ascii_lowercase();
+        collation == "binary" || collation.ends_with("_bin")
+    };
+    field
+        .constraints
+        .iter()
+        .find_map(|constraint| match constraint {
+            ColumnConstraint::Collation(collation) => Some(is_binary(collation)),
+            ColumnConstraint::CharacterSet(charset) => Some(charset.eq_ignore_ascii_case("binary")),
+            _ => None,
+        })
+        .or_else(|| {
+            schema.options.iter().find_map(|option| match option {
+                CreateTableOption::Collate(collation) => Some(is_binary(&collation.to_string())),
+                _ => None,
+            })
+        })
+        .unwrap_or(false)
+}
+
+impl RowFilter {
+    /// Prepare the filter to be evaluated against the rows of the table with the given `schema`
+    pub(crate) fn lower(
+        &self,
+        dialect: readyset_data::Dialect,
+        schema: &CreateTableStatement,
+    ) -> ReadySetResult<RowPredicate> {
+        if dialect.engine() == SqlEngine::MySQL {
+            let mut compared = ComparedColumns::default();
+            let Ok(()) = compared.visit_expr(&self.expr);
+            for column in compared.columns {
+                let field = schema
+                    .fields
+                    .iter()
+                    .find(|field| field.column.name == column.name);
+                if let Some(field) = field {
+                    if !mysql_compares_exactly(schema, field) {
+                        return Err(invalid_err!(
+                            "Replication row filter compares column {}, whose collation isn't \
+                             binary, which ReadySet can't evaluate the same way as MySQL",
+                            column.name
+                        ));
+                    }
+                }
+            }
+        }
+
+        let context = TableLowerContext { schema, dialect };
+        let columns = self
+            .expr
+            .referred_columns()
+            .map(|col| Ok(context.resolve_column(col.clone())?.0))
+            .collect::<ReadySetResult<Vec<_>>>()?;
+        let table_columns = schema
+            .fields
+            .iter()
+            .map(|field| field.column.name.clone())
+            .collect();
+        let key_columns = schema
+            .keys
+            .iter()
+            .flatten()
+            .find_map(|key| match key {
+                TableKey::PrimaryKey { columns, .. } => {
+                    Some(columns.iter().map(|col| col.name.clone()).collect())
+                }
+                _ => None,
+            })
+            .or_else(|| {
+                schema
+                    .fields
+                    .iter()
+                    .find(|field| field.constraints.contains(&ColumnConstraint::PrimaryKey))
+                    .map(|field| vec![field.column.name.clone()])
+            })
+            .unwrap_or_default();
+        Ok(RowPredicate {
+            expr: DataflowExpr::lower(self.expr.clone(), dialect, context)?,
+            columns,
+            table_columns,
+            key_columns,
+        })
+    }
+}
+
+/// A replication action for a table with a row filter, as filtered by
+/// [`RowPredicate::filter_actions`]
+#[derive(Debug, PartialEq)]
+pub(crate) enum FilteredAction {
+    /// An action to perform on the table
+    Perform(TableOperation),
+    /// A partial update of the row with the given key, after which the row may satisfy the
+    /// predicate. The row has to be fetched from the upstream database, and passed to
+    /// [`RowPredicate::filter_fetched_row`].
+    FetchRow { key: Vec<DfValue> },
+}
+
+/// A [`RowFilter`] which has been prepared to be evaluated against the rows of a particular table
+#[derive(Debug, Clone)]
+pub(crate) struct RowPredicate {
+    expr: DataflowExpr,
+    /// The indices of the columns referenced by the predicate
+    columns: Vec<usize>,
+    /// The names of all the columns of the table, in order
+    pub(crate) table_columns: Vec<SqlIdentifier>,
+    /// The names of the columns of the table's primary key, which identify the rows of partial
+    /// updates
+    pub(crate) key_columns: Vec<SqlIdentifier>,
+}
+
+impl RowPredicate {
+    /// Returns true if `row` satisfies the predicate
+    fn matches(&self, row: &[DfValue]) -> ReadySetResult<bool> {
+        Ok(self.expr.eval(row)?.is_truthy())
+    }
+
+    /// Filter an update which only sets some of the columns of the row with the given key. See the
+    /// module documentation for how these are handled.
+    fn filter_partial_update(
+        &self,
+        key: Vec<DfValue>,
+        update: Vec<Modification>,
+    ) -> ReadySetResult<FilteredAction> {
+        let columns_are = |pred: fn(&Modification) -> bool| {
+            self.columns
+                .iter()
+                .all(|i| update.get(*i).map_or(false, pred))
+        };
+        if columns_are(|m| matches!(m, Modification::None)) {
+            // Whether the row satisfies the predicate can't have changed
+            return Ok(FilteredAction::Perform(TableOperation::Update {
+                key,
+                update,
+            }));
+        }
+
+        if columns_are(|m| matches!(m, Modification::Set(_))) {
+            // The predicate only reads the columns it references, so the others can be anything
+            let row = update
+                .iter()
+                .map(|modification| match modification {
+                    Modification::Set(value) => value.clone(),
+                    _ => DfValue::None,
+                })
+                .collect::<Vec<_>>();
+            if !self.matches(&row)? {
+                return Ok(FilteredAction::Perform(TableOperation::DeleteByKey { key }));
+            }
+        }
+
+        // Either the row satisfies the predicate, but may not have before, or we can't tell
+        // whether it does
+        Ok(FilteredAction::FetchRow { key })
+    }
+
+    /// Returns the actions which replace the row with the given `key` with `row`, as fetched from
+    /// the upstream database for a [`FilteredAction::FetchRow`], if it satisfies the predicate
+    pub(crate) fn filter_fetched_row(
+        &self,
+        key: Vec<DfValue>,
+        row: Option<Vec<DfValue>>,
+    ) -> ReadySetResult<Vec<TableOperation>> {
+        let mut actions = vec![TableOperation::DeleteByKey { key }];
+        if let Some(row) = row {
+            if self.matches(&row)? {
+                actions.push(TableOperation::Insert(row));
+            }
+        }
+        Ok(actions)
+    }
+
+    /// Remove the changes to rows which don't satisfy the predicate from `actions`.
+    ///
+    /// Deletes of rows which don't satisfy the predicate are removed as well, since those rows were
+    /// never replicated. Updates of the row with a given key can't be checked against the
+    /// previous contents of the row, so they're replaced with a delete of the key, followed by an
+    /// insert of the new row if it satisfies the predicate. Updates which only set some of the
+    /// row's columns are handled as described in the module documentation.
+    pub(crate) fn filter_actions(
+        &self,
+        actions: Vec<TableOperation>,
+    ) -> ReadySetResult<Vec<FilteredAction>> {
+        let mut filtered = Vec::with_capacity(actions.len());
+        for action in actions {
+            match action {
+                TableOperation::Insert(row) => {
+                    if self.matches(&row)? {
+                        filtered.push(FilteredAction::Perform(TableOperation::Insert(row)));
+                    }
+                }
+                TableOperation::DeleteRow { row } => {
+                    if self.matches(&row)? {
+                        filtered.push(FilteredAction::Perform(TableOperation::DeleteRow { row }));
+                    }
+                }
+                TableOperation::Update { key, update } => {
+                    let row = update
+                        .iter()
+                        .map(|modification| match modification {
+                            Modification::Set(value) => Some(value.clone()),
+                            _ => None,
+                        })
+                        .collect::<Option<Vec<_>>>();
+                    match row {
+                        Some(row) => filtered.extend(
+                            self.filter_fetched_row(key, Some(row))?
+                                .into_iter()
+                                .map(FilteredAction::Perform),
+                        ),
+                        None => filtered.push(self.filter_partial_update(key, update)?),
+                    }
+                }
+                // Other operations either don't contain a row, or aren't produced by replication
+                action => filtered.push(FilteredAction::Perform(action)),
+            }
+        }
+        Ok(filtered)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use nom_sql::parse_create_table;
+
+    use super::*;
+
+    #[test]
+    fn parse_filters() {
+        let filters = parse_row_filters(
+            Dialect::MySQL,
+            "orders WHERE region = 'eu'; shop.items\nwhere tenant_id IN (1, 2);",
+            Some(&"noria".into()),
+        )
+        .unwrap();
+        assert_eq!(filters.len(), 2);
+        assert_eq!(
+            filters[0].0,
+            Relation {
+                schema: Some("noria".into()),
+                name: "orders".into()
+            }
+        );
+        assert_eq!(filters[0].1.predicate, "region = 'eu'");
+        assert_eq!(
+            filters[1].0,
+            Relation {
+                schema: Some("shop".into()),
+                name: "items".into()
+            }
+        );
+        assert_eq!(filters[1].1.predicate, "tenant_id IN (1, 2)");
+
+        parse_row_filters(Dialect::MySQL, "orders", Some(&"noria".into())).unwrap_err();
+        parse_row_filters(Dialect::MySQL, "* WHERE x = 1", Some(&"noria".into())).unwrap_err();
+        parse_row_filters(
+            Dialect::MySQL,
+            "orders WHERE region =",
+            Some(&"noria".into()),
+        )
+        .unwrap_err();
+        parse_row_filters(Dialect::MySQL, "orders WHERE region = 'eu'", None).unwrap_err();
+    }
+
+    #[test]
+    fn filter_actions() {
+        let schema = parse_create_table(
+            Dialect::MySQL,
+            "CREATE TABLE orders (id INT PRIMARY KEY, region TEXT COLLATE utf8mb4_bin)",
+        )
+        .unwrap();
+        let (_, filter) = parse_row_filters(
+            Dialect::MySQL,
+            "orders WHERE region = 'eu'",
+            Some(&"noria".into()),
+        )
+        .unwrap()
+        .pop()
+        .unwrap();
+        let predicate = filter
+            .lower(readyset_data::Dialect::DEFAULT_MYSQL, &schema)
+            .unwrap();
+
+        let eu = |id: i32| vec![DfValue::from(id), DfValue::from("eu")];
+        let us = |id: i32| vec![DfValue::from(id), DfValue::from("us")];
+        let set = |row: Vec<DfValue>| row.into_iter().map(Modification::Set).collect();
+
+        let filtered = predicate
+            .filter_actions(vec![
+                TableOperation::Insert(eu(1)),
+                TableOperation::Insert(us(2)),
+                TableOperation::DeleteRow { row: us(3) },
+                TableOperation::DeleteRow { row: eu(4) },
+                TableOperation::Update {
+                    key: vec![5.into()],
+                    update: set(us(5)),
+                },
+                TableOperation::Update {
+                    key: vec![6.into()],
+                    update: set(eu(6)),
+                },
+                TableOperation::Truncate,
+            ])
+            .unwrap();
+        assert_eq!(
+            filtered,
+            vec![
+                TableOperation::Insert(eu(1)),
+                TableOperation::DeleteRow { row: eu(4) },
+                TableOperation::DeleteByKey {
+                    key: vec![5.into()]
+                },
+                TableOperation::DeleteByKey {
+                    key: vec![6.into()]
+                },
+                TableOperation::Insert(eu(6)),
+                TableOperation::Truncate,
+            ]
+            .into_iter()
+            .map(FilteredAction::Perform)
+            .collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn filter_partial_updates() {
+        let schema = parse_create_table(
+            Dialect::PostgreSQL,
+            "CREATE TABLE orders (id INT PRIMARY KEY, region TEXT, notes TEXT)",
+        )
+        .unwrap();
+        let (_, filter) = parse_row_filters(
+            Dialect::PostgreSQL,
+            "orders WHERE region = 'eu'",
+            Some(&"public".into()),
+        )
+        .unwrap()
+        .pop()
+        .unwrap();
+        let predicate = filter
+            .lower(readyset_data::Dialect::DEFAULT_POSTGRESQL, &schema)
+            .unwrap();
+
+        let update = |id: i32, region: Modification| TableOperation::Update {
+            key: vec![id.into()],
+            update: vec![Modification::Set(id.into()), region, Modification::None],
+        };
+
+        let filtered = predicate
+            .filter_actions(vec![
+                // The region didn't change
+                update(1, Modification::None),
+                // The row satisfies the predicate, but may not have before
+                update(2, Modification::Set("eu".into())),
+                // The row doesn't satisfy the predicate (anymore)
+                update(3, Modification::Set("us".into())),
+            ])
+            .unwrap();
+        assert_eq!(
+            filtered,
+            vec![
+                FilteredAction::Perform(update(1, Modification::None)),
+                FilteredAction::FetchRow {
+                    key: vec![2.into()]
+                },
+                FilteredAction::Perform(TableOperation::DeleteByKey {
+                    key: vec![3.into()]
+                }),
+            ]
+        );
+        assert_eq!(predicate.key_columns, vec![SqlIdentifier::from("id")]);
+
+        let row = |id: i32, region: &str| vec![id.into(), region.into(), "notes".into()];
+        assert_eq!(
+            predicate
+                .filter_fetched_row(vec![2.into()], Some(row(2, "eu")))
+                .unwrap(),
+            vec![
+                TableOperation::DeleteByKey {
+                    key: vec![2.into()]
+                },
+                TableOperation::Insert(row(2, "eu")),
+            ]
+        );
+        // The row changed again, or was deleted, since the update
+        for fetched in [Some(row(2, "us")), None] {
+            assert_eq!(
+                predicate
+                    .filter_fetched_row(vec![2.into()], fetched)
+                    .unwrap(),
+                vec![TableOperation::DeleteByKey {
+                    key: vec![2.into()]
+                }]
+            );
+        }
+    }
+
+    #[test]
+    fn mysql_text_comparisons_need_binary_collation() {
+        let lower = |table: &str, predicate: &str| {
+            let schema = parse_create_table(Dialect::MySQL, table).unwrap();
+            let (_, filter) = parse_row_filters(
+                Dialect::MySQL,
+                &format!("orders WHERE {predicate}"),
+                Some(&"noria".into()),
+            )
+            .unwrap()
+            .pop()
+            .unwrap();
+            filter.lower(readyset_data::Dialect::DEFAULT_MYSQL, &schema)
+        };
+
+        let default_collation = "CREATE TABLE orders (id INT PRIMARY KEY, region VARCHAR(8))";
+        lower(default_collation, "region = 'eu'").unwrap_err();
+        lower(default_collation, "id = 1 OR region IN ('eu', 'us')").unwrap_err();
+        lower(default_collation, "region LIKE 'eu%'").unwrap_err();
+        // Text columns which aren't compared, and other columns, are fine
+        lower(default_collation, "region IS NOT NULL AND id > 10").unwrap();
+
+        lower(
+            "CREATE TABLE orders (id INT PRIMARY KEY, region VARCHAR(8) COLLATE utf8mb4_bin)",
+            "region = 'eu'",
+        )
+        .unwrap();
+        lower(
+            "CREATE TABLE orders (id INT PRIMARY KEY, region VARCHAR(8)) COLLATE=utf8mb4_bin",
+            "region = 'eu'",
+        )
+        .unwrap();
+        lower(
+            "CREATE TABLE orders (id INT PRIMARY KEY, \
+             region VARCHAR(8) COLLATE utf8mb4_general_ci) COLLATE=utf8mb4_bin",
+            "region = 'eu'",
+        )
+        .unwrap_err();
+    }
+}
@@ -0,0 +1,312 @@
+//! Functionality shared between the PostgreSQL and MySQL snapshotting code: options, progress
+//! reporting, and checkpointing of the progress of a snapshot so that it can be resumed.
+//!
+//! Tables with a primary key are copied in chunks of rows, ordered by primary key. Once the rows in
+//! a chunk have been durably written to ReadySet, the primary key of the last row in the chunk is
+//! recorded in a [`SnapshotCheckpoint`], which is stored in the authority. If the snapshot is
+//! interrupted, the next snapshot copies the rest of the table starting after that key, reading
+//! from its own (later) point in the replication log, while the table keeps the replication offset
+//! of the snapshot that the first chunks were copied at. When there is no checkpoint to resume
+//! from, PostgreSQL tables are still read with a single `COPY ... ORDER BY <primary key>`, and
+//! checkpointed every chunk's worth of rows as they arrive, so that the first (and usually only)
+//! snapshot doesn't pay for a query per chunk.
+//!
+//! Replication then catches the table up from that older offset, which re-applies some changes that
+//! are already reflected in the rows copied after resuming. This is harmless, since for tables with
+//! a primary key ReadySet ignores inserts of rows with keys that already exist, and updates and
+//! deletes of rows that don't, as long as the table isn't in snapshot mode. Tables without a
+//! primary key are always copied in one pass, and copied again from scratch if they were
+//! interrupted.
+
+use std::convert::Infallible;
+use std::sync::Arc;
+use std::time::Instant;
+
+use database_utils::UpstreamConfig;
+use nom_sql::Relation;
+use readyset::consensus::{Authority, AuthorityControl};
+use readyset::replication::ReplicationOffset;
+use readyset_data::DfValue;
+use readyset_errors::{internal_err, ReadySetResult};
+use serde::{Deserialize, Serialize};
+use tracing::info;
+
+/// The path in the authority that the checkpoint of the snapshot in progress is stored at
+const CHECKPOINT_PATH: &str = "/snapshot_checkpoint";
+
+/// Options for how to snapshot the upstream database, taken from the [`UpstreamConfig`]
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct SnapshotOptions {
+    /// The time between reports of the progress of copying each table, in seconds, or 0 to
+    /// disable reporting
+    pub(crate) report_interval_secs: u16,
+    /// The maximum number of tables to copy at the same time
+    pub(crate) parallelism: usize,
+    /// The number of rows to copy at a time from tables with a primary key
+    pub(crate) chunk_rows: u64,
+}
+
+impl From<&UpstreamConfig> for SnapshotOptions {
+    fn from(config: &UpstreamConfig) -> Self {
+        SnapshotOptions {
+            report_interval_secs: config.snapshot_report_interval_secs,
+            parallelism: config.snapshot_parallelism.max(1),
+            chunk_rows: config.snapshot_chunk_rows.max(1),
+        }
+    }
+}
+
+/// The progress of copying a single table
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub(crate) struct TableCheckpoint {
+    /// The replication offset that the rows of the table copied so far were read at
+    pub(crate) offset: ReplicationOffset,
+    /// The primary key of the last row copied, or `None` if no chunks of the table have been
+    /// completely copied yet
+    pub(crate) last_key: Option<Vec<DfValue>>,
+}
+
+impl TableCheckpoint {
+    /// Create a checkpoint for a table which is about to be copied at `offset`
+    pub(crate) fn new(offset: ReplicationOffset) -> Self {
+        TableCheckpoint {
+            offset,
+            last_key: None,
+        }
+    }
+}
+
+/// The progress of a snapshot which is in progress.
+///
+/// The presence of a checkpoint in the authority indicates that a snapshot was started, but has
+/// not yet finished.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub(crate) struct SnapshotCheckpoint {
+    /// The tables which have been partially copied
+    tables: Vec<(Relation, TableCheckpoint)>,
+}
+
+impl SnapshotCheckpoint {
+    /// Returns the progress of copying `table`, if it has been partially copied
+    pub(crate) fn table(&self, table: &Relation) -> Option<&TableCheckpoint> {
+        self.tables
+            .iter()
+            .find_map(|(t, checkpoint)| (t == table).then_some(checkpoint))
+    }
+
+    fn set_table(&mut self, table: &Relation, checkpoint: TableCheckpoint) {
+        match self.tables.iter_mut().find(|(t, _)| t == table) {
+            Some((_, existing)) => *existing = checkpoint,
+            None => self.tables.push((table.clone(), checkpoint)),
+        }
+    }
+
+    fn remove_table(&mut self, table: &Relation) {
+        self.tables.retain(|(t, _)| t != table);
+    }
+}
+
+/// Reads and writes the [`SnapshotCheckpoint`] for the snapshot in progress, which is stored in
+/// the authority so that it survives the replicator (or the whole server) restarting
+#[derive(Clone)]
+pub(crate) struct CheckpointStore {
+    authority: Arc<Authority>,
+}
+
+impl CheckpointStore {
+    pub(crate) fn new(authority: Arc<Authority>) -> Self {
+        CheckpointStore { authority }
+    }
+
+    /// Returns the checkpoint for the snapshot in progress, or `None` if there isn't one
+    pub(crate) async fn load(&self) -> ReadySetResult<Option<SnapshotCheckpoint>> {
+        self.authority
+            .try_read::<Option<SnapshotCheckpoint>>(CHECKPOINT_PATH)
+            .await
+            .map(Option::flatten)
+            .map_err(|e| internal_err!("Error reading snapshot checkpoint: {e}"))
+    }
+
+    /// Apply `f` to the checkpoint for the snapshot in progress (or a new, empty one if there
+    /// isn't one), and store the result
+    async fn update<F>(&self, mut f: F) -> ReadySetResult<SnapshotCheckpoint>
+    where
+        F: FnMut(&mut SnapshotCheckpoint) + Send,
+    {
+        let res = self
+            .authority
+            .read_modify_write(CHECKPOINT_PATH, |checkpoint: Option<Option<_>>| {
+                let mut checkpoint = checkpoint.flatten().unwrap_or_default();
+                f(&mut checkpoint);
+                Ok::<_, Infallible>(Some(checkpoint))
+            })
+            .await
+            .map_err(|e| internal_err!("Error writing snapshot checkpoint: {e}"))?;
+        match res {
+            Ok(checkpoint) => Ok(checkpoint.unwrap_or_default()),
+            Err(never) => match never {},
+        }
+    }
+
+    /// Mark a snapshot as being in progress, returning the checkpoint of the existing snapshot in
+    /// progress if there is one
+    pub(crate) async fn start(&self) -> ReadySetResult<SnapshotCheckpoint> {
+        self.update(|_| {}).await
+    }
+
+    /// Record the progress of copying `table`
+    pub(crate) async fn set_table(
+        &self,
+        table: &Relation,
+        checkpoint: TableCheckpoint,
+    ) -> ReadySetResult<()> {
+        self.update(|snapshot| snapshot.set_table(table, checkpoint.clone()))
+            .await?;
+        Ok(())
+    }
+
+    /// Record that `table` has been completely copied
+    pub(crate) async fn finish_table(&self, table: &Relation) -> ReadySetResult<()> {
+        self.update(|snapshot| snapshot.remove_table(table)).await?;
+        Ok(())
+    }
+
+    /// Record that the snapshot in progress has finished
+    pub(crate) async fn finish(&self) -> ReadySetResult<()> {
+        self.authority
+            .read_modify_write(CHECKPOINT_PATH, |_: Option<Option<SnapshotCheckpoint>>| {
+                Ok::<_, Infallible>(None)
+            })
+            .await
+            .map_err(|e| internal_err!("Error clearing snapshot checkpoint: {e}"))?;
+        Ok(())
+    }
+}
+
+/// Keeps track of, and periodically reports, the progress of copying a single table
+pub(crate) struct SnapshotProgress {
+    /// The (approximate) number of rows in the table
+    total_rows: u64,
+    /// The number of rows copied so far
+    rows: u64,
+    report_interval_secs: u64,
+    start_time: Instant,
+    last_report_time: Instant,
+    /// The metric to report the percentage of the table copied to
+    metric: metrics::Gauge,
+}
+
+impl SnapshotProgress {
+    pub(crate) fn new(total_rows: u64, report_interval_secs: u16, metric: metrics::Gauge) -> Self {
+        let start_time = Instant::now();
+        SnapshotProgress {
+            total_rows,
+            rows: 0,
+            report_interval_secs: report_interval_secs as u64,
+            start_time,
+            last_report_time: start_time,
+            metric,
+        }
+    }
+
+    /// The number of rows copied so far
+    pub(crate) fn rows(&self) -> u64 {
+        self.rows
+    }
+
+    /// Record that another row has been copied, reporting progress if it has been long enough
+    /// since the last report
+    pub(crate) fn add_row(&mut self) {
+        self.rows += 1;
+
+        if self.report_interval_secs != 0
+            && self.last_report_time.elapsed().as_secs() > self.report_interval_secs
+        {
+            self.last_report_time = Instant::now();
+            let estimate = crate::estimate_remaining_time(
+                self.start_time.elapsed(),
+                self.rows as f64,
+                self.total_rows as f64,
+            );
+            let progress_percent = (self.rows as f64 / self.total_rows as f64) * 100.;
+            let progress = format!("{:.2}%", progress_percent);
+            info!(rows_replicated = %self.rows, %progress, %estimate, "Snapshotting progress");
+            self.metric.set(progress_percent);
+        }
+    }
+
+    /// Record that copying the table failed
+    pub(crate) fn failed(&self) {
+        self.metric.set(0.0);
+    }
+
+    /// Record that the table has been completely copied
+    pub(crate) fn finished(&self) {
+        self.metric.set(100.0);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use readyset::consensus::{LocalAuthority, LocalAuthorityStore};
+
+    use super::*;
+
+    fn offset(offset: u128) -> ReplicationOffset {
+        ReplicationOffset {
+            offset,
+            replication_log_name: String::new(),
+        }
+    }
+
+    #[tokio::test]
+    async fn checkpoint_round_trip() {
+        let authority = Arc::new(Authority::from(LocalAuthority::new_with_store(Arc::new(
+            LocalAuthorityStore::new(),
+        ))));
+        let checkpoints = CheckpointStore::new(authority);
+        let t1 = Relation::from("t1");
+        let t2 = Relation::from("t2");
+
+        assert_eq!(checkpoints.load().await.unwrap(), None);
+        assert_eq!(
+            checkpoints.start().await.unwrap(),
+            SnapshotCheckpoint::default()
+        );
+        assert_eq!(
+            checkpoints.load().await.unwrap(),
+            Some(SnapshotCheckpoint::default())
+        );
+
+        checkpoints
+            .set_table(&t1, TableCheckpoint::new(offset(1)))
+            .await
+            .unwrap();
+        checkpoints
+            .set_table(&t2, TableCheckpoint::new(offset(2)))
+            .await
+            .unwrap();
+        checkpoints
+            .set_table(
+                &t1,
+                TableCheckpoint {
+                    offset: offset(1),
+                    last_key: Some(vec![DfValue::from(10)]),
+                },
+            )
+            .await
+            .unwrap();
+        checkpoints.finish_table(&t2).await.unwrap();
+
+        // Starting a snapshot again resumes the one in progress
+        let checkpoint = checkpoints.start().await.unwrap();
+        assert_eq!(
+            checkpoint.table(&t1).unwrap().last_key,
+            Some(vec![DfValue::from(10)])
+        );
+        assert_eq!(checkpoint.table(&t2), None);
+
+        checkpoints.finish().await.unwrap();
+        assert_eq!(checkpoints.load().await.unwrap(), None);
+    }
+}
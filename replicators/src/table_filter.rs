@@ -1,11 +1,14 @@
 use std::borrow::Borrow;
 use std::collections::{BTreeMap, BTreeSet};
 
+use database_utils::ExcludedColumn;
 use launchpad::redacted::RedactedString;
 use nom_locate::LocatedSpan;
-use nom_sql::{replicator_table_list, Dialect, SqlIdentifier};
+use nom_sql::{replicator_table_list, Dialect, Relation, SqlIdentifier};
 use readyset::{ReadySetError, ReadySetResult};
 
+use crate::row_filter::{parse_row_filters, RowFilter};
+
 /// A [`TableFilter`] keeps lists of all the tables readyset-server is interested in, as well as a
 /// list of tables that we explicitly want to filter out of replication.
 /// Tables may be filtered from replication in 2 ways:
@@ -22,6 +25,10 @@ use readyset::{ReadySetError, ReadySetResult};
 /// When a replication event happens, the event is filtered based on its
 /// schema/table before being sent to readyset-server.
 ///
+/// Tables can additionally have a [`RowFilter`], given with the --replication-row-filters option,
+/// which limits the rows of the table that are replicated, and columns that are excluded from
+/// replication, given with the --replication-excluded-columns option.
+///
 /// `BTreeMap`s are used here, because the assumption is that the number of schemas in a
 /// database is usually small, and their names are short, so a small `BTreeMap` with inlined
 /// `SqlIdentifiers` would perform better than a `HashMap` where a hash is performed on every
@@ -36,6 +43,12 @@ pub(crate) struct TableFilter {
     /// Any other valid tables will be replicated, where a valid table is either one of the tables
     /// in `explicitly_replicated`, or all tables if that is empty.
     replication_denied: BTreeMap<SqlIdentifier, ReplicateTableSpec>,
+    /// A mapping between schema to the row filters of the tables in that schema which have one.
+    /// This is only populated by the --replication-row-filters option
+    row_filters: BTreeMap<SqlIdentifier, BTreeMap<SqlIdentifier, RowFilter>>,
+    /// The columns to exclude from replication.
+    /// This is only populated by the --replication-excluded-columns option
+    excluded_columns: Vec<ExcludedColumn>,
 }
 
 #[derive(Debug, Clone)]
@@ -107,6 +120,8 @@ impl TableFilter {
                         return Ok(TableFilter {
                             explicitly_replicated: schemas,
                             replication_denied: BTreeMap::new(),
+                            row_filters: BTreeMap::new(),
+                            excluded_columns: Vec::new(),
                         });
                     }
                     None => {
@@ -158,14 +173,54 @@ impl TableFilter {
         Ok(TableFilter {
             explicitly_replicated: schemas,
             replication_denied: BTreeMap::new(),
+            row_filters: BTreeMap::new(),
+            excluded_columns: Vec::new(),
         })
     }
 
+    /// Add the row filters in `row_filter_list`, as given to the --replication-row-filters option,
+    /// to the filter
+    pub(crate) fn with_row_filters(
+        mut self,
+        dialect: Dialect,
+        row_filter_list: Option<RedactedString>,
+        default_schema: Option<&str>,
+    ) -> ReadySetResult<TableFilter> {
+        let row_filter_list = match row_filter_list {
+            Some(row_filter_list) => row_filter_list,
+            None => return Ok(self),
+        };
+
+        let default_schema = default_schema.map(SqlIdentifier::from);
+        for (table, row_filter) in
+            parse_row_filters(dialect, row_filter_list.as_str(), default_schema.as_ref())?
+        {
+            // All tables returned by `parse_row_filters` have a schema
+            if let Some(schema) = table.schema {
+                self.row_filters
+                    .entry(schema)
+                    .or_default()
+                    .insert(table.name, row_filter);
+            }
+        }
+
+        Ok(self)
+    }
+
+    /// Add the columns in `excluded_columns`, as given to the --replication-excluded-columns
+    /// option, to the filter
+    pub(crate) fn with_excluded_columns(mut self, excluded_columns: Vec<ExcludedColumn>) -> Self {
+        self.excluded_columns = excluded_columns;
+        self
+    }
+
     /// Create a new filter that will pass all tables
     fn for_all_tables() -> Self {
         Self {
             explicitly_replicated: BTreeMap::new(),
             replication_denied: BTreeMap::new(),
+            row_filters: BTreeMap::new(),
+            excluded_columns: Vec::new(),
         }
     }
 
@@ -193,6 +248,38 @@ impl TableFilter {
             || self.is_explicitly_replicated(schema, table)
     }
 
+    /// Returns the filter on the rows of the given table to replicate, if it has one
+    pub(crate) fn row_filter<Q1, Q2>(&self, schema: &Q1, table: &Q2) -> Option<&RowFilter>
+    where
+        Q1: Ord + ?Sized,
+        Q2: Ord + ?Sized,
+        SqlIdentifier: Borrow<Q1> + Borrow<Q2>,
+    {
+        self.row_filters.get(schema)?.get(table)
+    }
+
+    /// Returns the tables that have row filters
+    pub(crate) fn row_filtered_tables(&self) -> Vec<Relation> {
+        self.row_filters
+            .iter()
+            .flat_map(|(schema, tables)| {
+                tables.keys().map(move |table| Relation {
+                    schema: Some(schema.clone()),
+                    name: table.clone(),
+                })
+            })
+            .collect()
+    }
+
+    /// Returns the names of the columns of the given table that are excluded from replication
+    pub(crate) fn excluded_columns(&self, schema: &str, table: &str) -> Vec<&str> {
+        self.excluded_columns
+            .iter()
+            .filter(|excluded| excluded.matches_table(Some(schema), table))
+            .map(|excluded| excluded.column.as_str())
+            .collect()
+    }
+
     pub(crate) fn is_explicitly_replicated<Q1, Q2>(&self, schema: &Q1, table: &Q2) -> bool
     where
         Q1: Ord + ?Sized,
@@ -327,6 +414,48 @@ mod tests {
         assert!(!filter.should_be_processed("readyset", "t4"));
     }
 
+    #[test]
+    fn row_filters() {
+        let filter = TableFilter::try_new(
+            nom_sql::Dialect::MySQL,
+            Some("t1,readyset.t2".to_string().into()),
+            Some("noria"),
+        )
+        .unwrap()
+        .with_row_filters(
+            nom_sql::Dialect::MySQL,
+            Some("t1 WHERE x = 1; readyset.t2 WHERE y > 2".to_string().into()),
+            Some("noria"),
+        )
+        .unwrap();
+        assert_eq!(filter.row_filter("noria", "t1").unwrap().predicate, "x = 1");
+        assert_eq!(
+            filter.row_filter("readyset", "t2").unwrap().predicate,
+            "y > 2"
+        );
+        assert!(filter.row_filter("noria", "t2").is_none());
+        // Row filters don't affect which tables are replicated
+        assert!(filter.should_be_processed("readyset", "t2"));
+        assert!(!filter.should_be_processed("noria", "t3"));
+    }
+
+    #[test]
+    fn excluded_columns() {
+        let filter = TableFilter::for_all_tables().with_excluded_columns(vec![
+            "users.password_hash".parse().unwrap(),
+            "noria.users.ssn".parse().unwrap(),
+        ]);
+        assert_eq!(
+            filter.excluded_columns("noria", "users"),
+            vec!["password_hash", "ssn"]
+        );
+        assert_eq!(
+            filter.excluded_columns("readyset", "users"),
+            vec!["password_hash"]
+        );
+        assert!(filter.excluded_columns("noria", "posts").is_empty());
+    }
+
     #[test]
     fn all_allowed_then_one_denied() {
         let mut filter = TableFilter::for_all_tables();